@@ -0,0 +1,670 @@
+//! Range proofs of [Bulletproofs] over G1, with aggregation.
+//!
+//! A `RangeProof` shows that each of `m` Pedersen commitments
+//! `V_j = v_j B + gamma_j B'` hides a value `v_j` of `n` bits. Its size is
+//! logarithmic in `n m`, and there is no trusted setup: the generators are
+//! derived from a hash like those of `ipa`. This suits a few range checks
+//! that do not justify a circuit and a parameter ceremony, but verifying
+//! takes time linear in `n m`.
+//!
+//! [Bulletproofs]: https://eprint.iacr.org/2017/1066
+
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective};
+use rand_core::RngCore;
+
+use crate::bls::Engine;
+use crate::ipa::{
+    fold, fold_points, generators, inner_product, msm, powers, read_point, round_challenge,
+};
+use crate::transcript::{Blake2bTranscript, Transcript};
+use crate::SynthesisError;
+
+/// The generators of the commitments and of the proofs.
+pub struct Generators<E: Engine> {
+    /// The generators of the bits and of the inner product argument.
+    pub g: Vec<E::G1Affine>,
+    pub h: Vec<E::G1Affine>,
+    /// `B`, the generator of the committed values.
+    pub value: E::G1Affine,
+    /// `B'`, the generator of the blindings.
+    pub blinding: E::G1Affine,
+    /// The generator of the inner product.
+    pub u: E::G1Affine,
+}
+
+impl<E: Engine> Clone for Generators<E> {
+    fn clone(&self) -> Self {
+        Generators {
+            g: self.g.clone(),
+            h: self.h.clone(),
+            value: self.value,
+            blinding: self.blinding,
+            u: self.u,
+        }
+    }
+}
+
+impl<E: Engine> Generators<E> {
+    /// Derives the generators for proofs of up to `capacity` bits in total,
+    /// the number of bits of each value times the number of values.
+    pub fn new(capacity: usize) -> Self {
+        let transcript = Blake2bTranscript::new(b"crusty3_zk bulletproofs");
+        let bases = generators::<E>(&transcript, b"bases", 3);
+
+        Generators {
+            g: generators::<E>(&transcript, b"g", capacity),
+            h: generators::<E>(&transcript, b"h", capacity),
+            value: bases[0],
+            blinding: bases[1],
+            u: bases[2],
+        }
+    }
+
+    /// The commitment `v B + gamma B'` to `value` with the blinding `gamma`.
+    pub fn commit(&self, value: u64, blinding: &E::Fr) -> E::G1Affine {
+        self.pedersen(&scalar::<E>(value), blinding)
+    }
+
+    // `value B + blinding B'` for a scalar `value`.
+    fn pedersen(&self, value: &E::Fr, blinding: &E::Fr) -> E::G1Affine {
+        let mut commitment = self.value.mul(value.into_repr());
+        commitment.add_assign(&self.blinding.mul(blinding.into_repr()));
+        commitment.into_affine()
+    }
+}
+
+/// An inner product argument for `<a, b>` under the generators `G` and
+/// `H'`.
+pub struct InnerProductProof<E: Engine> {
+    pub l: Vec<E::G1Affine>,
+    pub r: Vec<E::G1Affine>,
+    pub a: E::Fr,
+    pub b: E::Fr,
+}
+
+impl<E: Engine> Clone for InnerProductProof<E> {
+    fn clone(&self) -> Self {
+        InnerProductProof {
+            l: self.l.clone(),
+            r: self.r.clone(),
+            a: self.a,
+            b: self.b,
+        }
+    }
+}
+
+/// A proof that committed values fit in a number of bits.
+pub struct RangeProof<E: Engine> {
+    /// Commitments to the bits and to their blinding vectors.
+    pub a: E::G1Affine,
+    pub s: E::G1Affine,
+    /// Commitments to the coefficients of `t(X)`.
+    pub t1: E::G1Affine,
+    pub t2: E::G1Affine,
+    pub tau_x: E::Fr,
+    pub mu: E::Fr,
+    pub t_hat: E::Fr,
+    pub inner_product: InnerProductProof<E>,
+}
+
+impl<E: Engine> Clone for RangeProof<E> {
+    fn clone(&self) -> Self {
+        RangeProof {
+            a: self.a,
+            s: self.s,
+            t1: self.t1,
+            t2: self.t2,
+            tau_x: self.tau_x,
+            mu: self.mu,
+            t_hat: self.t_hat,
+            inner_product: self.inner_product.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for RangeProof<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.a == other.a
+            && self.s == other.s
+            && self.t1 == other.t1
+            && self.t2 == other.t2
+            && self.tau_x == other.tau_x
+            && self.mu == other.mu
+            && self.t_hat == other.t_hat
+            && self.inner_product.l == other.inner_product.l
+            && self.inner_product.r == other.inner_product.r
+            && self.inner_product.a == other.inner_product.a
+            && self.inner_product.b == other.inner_product.b
+    }
+}
+
+impl<E: Engine> RangeProof<E> {
+    /// Proves that each of `values` fits in `bits` bits, and returns the
+    /// proof with the commitments to the values under `blindings`.
+    ///
+    /// `bits` has to be a power of two up to 64, and the number of values a
+    /// power of two. Fails with `Unsatisfiable` if a value is out of range.
+    pub fn create<T: Transcript, R: RngCore>(
+        gens: &Generators<E>,
+        transcript: &mut T,
+        values: &[u64],
+        blindings: &[E::Fr],
+        bits: usize,
+        rng: &mut R,
+    ) -> Result<(Self, Vec<E::G1Affine>), SynthesisError> {
+        if values.len() != blindings.len() {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "{} values and {} blindings",
+                values.len(),
+                blindings.len()
+            )));
+        }
+        let size = check_size(gens, bits, values.len())?;
+        if bits < 64 && values.iter().any(|v| v >> bits != 0) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let commitments = values
+            .iter()
+            .zip(blindings)
+            .map(|(v, gamma)| gens.commit(*v, gamma))
+            .collect::<Vec<_>>();
+        append_statement::<E, _>(transcript, &commitments, bits);
+
+        // The bits `a_L` of the values and `a_R = a_L - 1`.
+        let a_l = values
+            .iter()
+            .flat_map(|v| (0..bits).map(move |k| (v >> k) & 1 == 1))
+            .map(|bit| if bit { E::Fr::one() } else { E::Fr::zero() })
+            .collect::<Vec<_>>();
+        let a_r = a_l
+            .iter()
+            .map(|bit| {
+                let mut bit = *bit;
+                bit.sub_assign(&E::Fr::one());
+                bit
+            })
+            .collect::<Vec<_>>();
+        let s_l = (0..size).map(|_| E::Fr::random(rng)).collect::<Vec<_>>();
+        let s_r = (0..size).map(|_| E::Fr::random(rng)).collect::<Vec<_>>();
+        let alpha = E::Fr::random(rng);
+        let rho = E::Fr::random(rng);
+
+        let a = vector_commitment(gens, &a_l, &a_r, &alpha)?;
+        let s = vector_commitment(gens, &s_l, &s_r, &rho)?;
+        transcript.append_point(b"A", &a);
+        transcript.append_point(b"S", &s);
+        let y = transcript.challenge_scalar::<E::Fr>(b"y");
+        let z = transcript.challenge_scalar::<E::Fr>(b"z");
+
+        // `l(X) = a_L - z + s_L X` and
+        // `r(X) = y^i (a_R + z + s_R X) + z^(2 + j) 2^k` for bit `k` of value
+        // `j`.
+        let y_powers = powers::<E>(&y, size);
+        let d = offsets::<E>(&z, bits, values.len());
+        let mut l0 = a_l;
+        let mut r0 = a_r;
+        let mut r1 = s_r;
+        for (((l, r), r_s), (y_i, d_i)) in l0
+            .iter_mut()
+            .zip(&mut r0)
+            .zip(&mut r1)
+            .zip(y_powers.iter().zip(&d))
+        {
+            l.sub_assign(&z);
+            r.add_assign(&z);
+            r.mul_assign(y_i);
+            r.add_assign(d_i);
+            r_s.mul_assign(y_i);
+        }
+        let l1 = s_l;
+
+        // `t(X) = <l(X), r(X)> = t_0 + t_1 X + t_2 X^2`
+        let mut t1 = inner_product::<E>(&l0, &r1);
+        t1.add_assign(&inner_product::<E>(&l1, &r0));
+        let t2 = inner_product::<E>(&l1, &r1);
+        let tau1 = E::Fr::random(rng);
+        let tau2 = E::Fr::random(rng);
+        let t1 = gens.pedersen(&t1, &tau1);
+        let t2 = gens.pedersen(&t2, &tau2);
+        transcript.append_point(b"T1", &t1);
+        transcript.append_point(b"T2", &t2);
+        let x = transcript.challenge_scalar::<E::Fr>(b"x");
+
+        let l = combine::<E>(&l0, &l1, &x);
+        let r = combine::<E>(&r0, &r1, &x);
+        let t_hat = inner_product::<E>(&l, &r);
+        let mut x_squared = x;
+        x_squared.square();
+        let mut tau_x = tau2;
+        tau_x.mul_assign(&x_squared);
+        let mut term = tau1;
+        term.mul_assign(&x);
+        tau_x.add_assign(&term);
+        for (gamma, z_j) in blindings
+            .iter()
+            .zip(&powers::<E>(&z, values.len() + 2)[2..])
+        {
+            let mut term = *gamma;
+            term.mul_assign(z_j);
+            tau_x.add_assign(&term);
+        }
+        let mut mu = rho;
+        mu.mul_assign(&x);
+        mu.add_assign(&alpha);
+
+        let u = bind::<E, _>(gens, transcript, &tau_x, &mu, &t_hat);
+        let y_inv = y.inverse().ok_or(SynthesisError::DivisionByZero)?;
+        let h = gens.h[..size]
+            .iter()
+            .zip(powers::<E>(&y_inv, size))
+            .map(|(h, y)| h.mul(y.into_repr()).into_affine())
+            .collect::<Vec<_>>();
+        let inner_product = prove_inner_product(transcript, &gens.g[..size], &h, &u, l, r)?;
+
+        let proof = RangeProof {
+            a,
+            s,
+            t1,
+            t2,
+            tau_x,
+            mu,
+            t_hat,
+            inner_product,
+        };
+        Ok((proof, commitments))
+    }
+
+    /// Verifies that each of the values committed in `commitments` fits in
+    /// `bits` bits, replaying the transcript of `create`.
+    pub fn verify<T: Transcript>(
+        &self,
+        gens: &Generators<E>,
+        transcript: &mut T,
+        commitments: &[E::G1Affine],
+        bits: usize,
+    ) -> Result<bool, SynthesisError> {
+        let size = check_size(gens, bits, commitments.len())?;
+        let rounds = size.trailing_zeros() as usize;
+        let ipp = &self.inner_product;
+        if ipp.l.len() != rounds || ipp.r.len() != rounds {
+            return Ok(false);
+        }
+
+        append_statement::<E, _>(transcript, commitments, bits);
+        transcript.append_point(b"A", &self.a);
+        transcript.append_point(b"S", &self.s);
+        let y = transcript.challenge_scalar::<E::Fr>(b"y");
+        let z = transcript.challenge_scalar::<E::Fr>(b"z");
+        transcript.append_point(b"T1", &self.t1);
+        transcript.append_point(b"T2", &self.t2);
+        let x = transcript.challenge_scalar::<E::Fr>(b"x");
+        let u = bind::<E, _>(gens, transcript, &self.tau_x, &self.mu, &self.t_hat);
+        let mut x_squared = x;
+        x_squared.square();
+
+        // `t_hat B + tau_x B' = sum z^(2 + j) V_j + delta B + x T_1 + x^2 T_2`
+        // with `delta = (z - z^2) sum y^i - sum z^(3 + j) (2^n - 1)`.
+        let y_powers = powers::<E>(&y, size);
+        let z_powers = powers::<E>(&z, commitments.len() + 3);
+        let mut delta = z;
+        delta.sub_assign(&z_powers[2]);
+        delta.mul_assign(&sum::<E>(&y_powers));
+        let mut range = sum::<E>(&powers::<E>(&two::<E>(), bits));
+        range.mul_assign(&sum::<E>(&z_powers[3..]));
+        delta.sub_assign(&range);
+
+        let mut lhs = gens.pedersen(&self.t_hat, &self.tau_x).into_projective();
+        let mut rhs = msm::<E>(commitments, &z_powers[2..commitments.len() + 2])?;
+        rhs.add_assign(&gens.value.mul(delta.into_repr()));
+        rhs.add_assign(&self.t1.mul(x.into_repr()));
+        rhs.add_assign(&self.t2.mul(x_squared.into_repr()));
+        lhs.sub_assign(&rhs);
+        if !lhs.is_zero() {
+            return Ok(false);
+        }
+
+        // The inner product argument for `l = l(x)` and `r = r(x)`:
+        // `A + x S - z <1, G> + <z y^i + d_i, H'> - mu B' + t_hat U'` has to
+        // be `<l, G> + <r, H'> + <l, r> U'` with `H'_i = y^-i H_i`. The
+        // rounds fold it into `a G_f + b H_f + a b U'`, where `G_f = <s, G>`
+        // and `H_f = <s^-1, H'>`.
+        let mut p = self.s.mul(x.into_repr());
+        p.add_assign_mixed(&self.a);
+        p.sub_assign(&gens.blinding.mul(self.mu.into_repr()));
+        p.add_assign(&u.mul(self.t_hat.into_repr()));
+        let mut s = vec![E::Fr::one()];
+        let mut s_inv = vec![E::Fr::one()];
+        for (l_j, r_j) in ipp.l.iter().zip(&ipp.r) {
+            let (x_j, x_j_inv) = round_challenge::<E, _>(transcript, l_j, r_j)?;
+            let mut x_j_squared = x_j;
+            x_j_squared.square();
+            let mut x_j_inv_squared = x_j_inv;
+            x_j_inv_squared.square();
+            p.add_assign(&l_j.mul(x_j_squared.into_repr()));
+            p.add_assign(&r_j.mul(x_j_inv_squared.into_repr()));
+
+            s = expand::<E>(&s, &x_j_inv, &x_j);
+            s_inv = expand::<E>(&s_inv, &x_j, &x_j_inv);
+        }
+
+        // Moving the terms in `G` and `H` to the right-hand side gives the
+        // coefficients `a s_i + z` and `y^-i (b s_i^-1 - d_i) - z`.
+        let y_inv = y.inverse().ok_or(SynthesisError::DivisionByZero)?;
+        let d = offsets::<E>(&z, bits, commitments.len());
+        let mut g_coeffs = s;
+        let mut h_coeffs = s_inv;
+        for (((g, h), d_i), y_inv_i) in g_coeffs
+            .iter_mut()
+            .zip(&mut h_coeffs)
+            .zip(&d)
+            .zip(powers::<E>(&y_inv, size))
+        {
+            g.mul_assign(&ipp.a);
+            g.add_assign(&z);
+            h.mul_assign(&ipp.b);
+            h.sub_assign(d_i);
+            h.mul_assign(&y_inv_i);
+            h.sub_assign(&z);
+        }
+        let mut ab = ipp.a;
+        ab.mul_assign(&ipp.b);
+        let mut expected = msm::<E>(&gens.g[..size], &g_coeffs)?;
+        expected.add_assign(&msm::<E>(&gens.h[..size], &h_coeffs)?);
+        expected.add_assign(&u.mul(ab.into_repr()));
+
+        Ok(p == expected)
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for point in &[self.a, self.s, self.t1, self.t2] {
+            writer.write_all(point.into_compressed().as_ref())?;
+        }
+        for value in &[self.tau_x, self.mu, self.t_hat] {
+            value.into_repr().write_be(&mut writer)?;
+        }
+
+        let ipp = &self.inner_product;
+        writer.write_u32::<BigEndian>(ipp.l.len() as u32)?;
+        for (l, r) in ipp.l.iter().zip(&ipp.r) {
+            writer.write_all(l.into_compressed().as_ref())?;
+            writer.write_all(r.into_compressed().as_ref())?;
+        }
+        ipp.a.into_repr().write_be(&mut writer)?;
+        ipp.b.into_repr().write_be(&mut writer)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut points = [E::G1Affine::zero(); 4];
+        for point in points.iter_mut() {
+            *point = read_point::<E, _>(&mut reader)?;
+        }
+        let mut values = [E::Fr::zero(); 3];
+        for value in values.iter_mut() {
+            *value = read_scalar::<E, _>(&mut reader)?;
+        }
+
+        let rounds = reader.read_u32::<BigEndian>()?;
+        if rounds > 64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "too many rounds",
+            ));
+        }
+        let mut l = vec![];
+        let mut r = vec![];
+        for _ in 0..rounds {
+            l.push(read_point::<E, _>(&mut reader)?);
+            r.push(read_point::<E, _>(&mut reader)?);
+        }
+        let a = read_scalar::<E, _>(&mut reader)?;
+        let b = read_scalar::<E, _>(&mut reader)?;
+
+        Ok(RangeProof {
+            a: points[0],
+            s: points[1],
+            t1: points[2],
+            t2: points[3],
+            tau_x: values[0],
+            mu: values[1],
+            t_hat: values[2],
+            inner_product: InnerProductProof { l, r, a, b },
+        })
+    }
+}
+
+// Checks the shape of a proof and returns its total number of bits.
+fn check_size<E: Engine>(
+    gens: &Generators<E>,
+    bits: usize,
+    count: usize,
+) -> Result<usize, SynthesisError> {
+    if !bits.is_power_of_two() || bits > 64 || !count.is_power_of_two() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} values of {} bits, both have to be powers of two",
+            count, bits
+        )));
+    }
+    let size = bits * count;
+    if size > gens.g.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} bits do not fit {} generators",
+            size,
+            gens.g.len()
+        )));
+    }
+
+    Ok(size)
+}
+
+fn append_statement<E: Engine, T: Transcript>(
+    transcript: &mut T,
+    commitments: &[E::G1Affine],
+    bits: usize,
+) {
+    transcript.append_message(b"protocol", b"crusty3_zk range proof");
+    transcript.append_message(b"bits", &(bits as u64).to_le_bytes());
+    for commitment in commitments {
+        transcript.append_point(b"V", commitment);
+    }
+}
+
+// Appends the final scalars and derives the generator `U'` of the inner
+// product from them.
+fn bind<E: Engine, T: Transcript>(
+    gens: &Generators<E>,
+    transcript: &mut T,
+    tau_x: &E::Fr,
+    mu: &E::Fr,
+    t_hat: &E::Fr,
+) -> E::G1Affine {
+    transcript.append_scalar(b"tau_x", tau_x);
+    transcript.append_scalar(b"mu", mu);
+    transcript.append_scalar(b"t_hat", t_hat);
+    let w = transcript.challenge_scalar::<E::Fr>(b"w");
+
+    gens.u.mul(w.into_repr()).into_affine()
+}
+
+// `<a, G> + <b, H> + blinding B'`
+fn vector_commitment<E: Engine>(
+    gens: &Generators<E>,
+    a: &[E::Fr],
+    b: &[E::Fr],
+    blinding: &E::Fr,
+) -> Result<E::G1Affine, SynthesisError> {
+    let mut commitment = msm::<E>(&gens.g[..a.len()], a)?;
+    commitment.add_assign(&msm::<E>(&gens.h[..b.len()], b)?);
+    commitment.add_assign(&gens.blinding.mul(blinding.into_repr()));
+    Ok(commitment.into_affine())
+}
+
+// `d_i = z^(2 + j) 2^k` for bit `k` of value `j`.
+fn offsets<E: Engine>(z: &E::Fr, bits: usize, count: usize) -> Vec<E::Fr> {
+    let two_powers = powers::<E>(&two::<E>(), bits);
+    let z_powers = powers::<E>(z, count + 2);
+    z_powers[2..]
+        .iter()
+        .flat_map(|z_j| {
+            two_powers.iter().map(move |two_k| {
+                let mut d = *two_k;
+                d.mul_assign(z_j);
+                d
+            })
+        })
+        .collect()
+}
+
+// `a + x b`
+fn combine<E: Engine>(a: &[E::Fr], b: &[E::Fr], x: &E::Fr) -> Vec<E::Fr> {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| {
+            let mut term = *b;
+            term.mul_assign(x);
+            term.add_assign(a);
+            term
+        })
+        .collect()
+}
+
+// Each element of `v` times `lo` followed by each times `hi`, in place of
+// every element, that is the coefficients of the next round.
+fn expand<E: Engine>(v: &[E::Fr], lo: &E::Fr, hi: &E::Fr) -> Vec<E::Fr> {
+    v.iter()
+        .flat_map(|v| {
+            let mut lo_v = *v;
+            lo_v.mul_assign(lo);
+            let mut hi_v = *v;
+            hi_v.mul_assign(hi);
+            vec![lo_v, hi_v]
+        })
+        .collect()
+}
+
+fn sum<E: Engine>(v: &[E::Fr]) -> E::Fr {
+    let mut acc = E::Fr::zero();
+    for v in v {
+        acc.add_assign(v);
+    }
+    acc
+}
+
+fn two<E: Engine>() -> E::Fr {
+    let mut two = E::Fr::one();
+    two.double();
+    two
+}
+
+fn scalar<E: Engine>(value: u64) -> E::Fr {
+    E::Fr::from_repr(value.into()).expect("a u64 is smaller than the modulus")
+}
+
+fn read_scalar<E: Engine, R: Read>(reader: &mut R) -> io::Result<E::Fr> {
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    repr.read_be(reader)?;
+    E::Fr::from_repr(repr).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// Proves `P = <a, G> + <b, H> + <a, b> U` in `log n` rounds, each halving
+// the vectors.
+fn prove_inner_product<E: Engine, T: Transcript>(
+    transcript: &mut T,
+    g: &[E::G1Affine],
+    h: &[E::G1Affine],
+    u: &E::G1Affine,
+    mut a: Vec<E::Fr>,
+    mut b: Vec<E::Fr>,
+) -> Result<InnerProductProof<E>, SynthesisError> {
+    let mut g = g.to_vec();
+    let mut h = h.to_vec();
+    let mut l = vec![];
+    let mut r = vec![];
+    while a.len() > 1 {
+        let half = a.len() / 2;
+        let (a_lo, a_hi) = a.split_at(half);
+        let (b_lo, b_hi) = b.split_at(half);
+        let (g_lo, g_hi) = g.split_at(half);
+        let (h_lo, h_hi) = h.split_at(half);
+
+        let mut l_j = msm::<E>(g_hi, a_lo)?;
+        l_j.add_assign(&msm::<E>(h_lo, b_hi)?);
+        l_j.add_assign(&u.mul(inner_product::<E>(a_lo, b_hi).into_repr()));
+        let mut r_j = msm::<E>(g_lo, a_hi)?;
+        r_j.add_assign(&msm::<E>(h_hi, b_lo)?);
+        r_j.add_assign(&u.mul(inner_product::<E>(a_hi, b_lo).into_repr()));
+        let l_j = l_j.into_affine();
+        let r_j = r_j.into_affine();
+
+        let (x, x_inv) = round_challenge::<E, _>(transcript, &l_j, &r_j)?;
+        a = fold::<E>(a_lo, a_hi, &x, &x_inv);
+        b = fold::<E>(b_lo, b_hi, &x_inv, &x);
+        g = fold_points::<E>(g_lo, g_hi, &x_inv, &x);
+        h = fold_points::<E>(h_lo, h_hi, &x, &x_inv);
+        l.push(l_j);
+        r.push(r_j);
+    }
+
+    Ok(InnerProductProof {
+        l,
+        r,
+        a: a[0],
+        b: b[0],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr, G1Affine};
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_range_proof() {
+        let mut rng = test_rng();
+        let gens = Generators::<Bls12>::new(64);
+
+        let mut prove = |values: &[u64], bits: usize| {
+            let blindings = values
+                .iter()
+                .map(|_| Fr::random(&mut rng))
+                .collect::<Vec<_>>();
+            let mut transcript = Blake2bTranscript::new(b"test");
+            RangeProof::create(&gens, &mut transcript, values, &blindings, bits, &mut rng)
+        };
+        let verify = |proof: &RangeProof<Bls12>, commitments: &[G1Affine], bits: usize| {
+            let mut transcript = Blake2bTranscript::new(b"test");
+            proof
+                .verify(&gens, &mut transcript, commitments, bits)
+                .unwrap()
+        };
+
+        let (proof, commitments) = prove(&[u64::max_value()], 64).unwrap();
+        assert!(verify(&proof, &commitments, 64));
+
+        let values = [0, 1, 40_000, 65_535];
+        let (proof, commitments) = prove(&values, 16).unwrap();
+        assert!(verify(&proof, &commitments, 16));
+        let mut swapped = commitments.clone();
+        swapped.swap(0, 1);
+        assert!(!verify(&proof, &swapped, 16));
+
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+        let read = RangeProof::<Bls12>::read(&bytes[..]).unwrap();
+        assert!(read == proof);
+        assert!(verify(&read, &commitments, 16));
+
+        match prove(&[256], 8).err() {
+            Some(SynthesisError::Unsatisfiable) => {}
+            _ => panic!("expected an out of range value"),
+        }
+        assert!(prove(&[1, 2, 3], 8).is_err());
+        assert!(prove(&[1, 2], 64).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Canonical serialized fixtures and round-trip checkers.
+//!
+//! The fixtures are hex encoded so that implementations in other languages can
+//! check their encoders and decoders against the exact same bytes. All of them
+//! are built from the BLS12-381 generators and small field elements, so they
+//! are stable across releases; they are not valid proofs of any statement.
+//!
+//! The byteblob layouts follow the =nil; marshalling format: field elements
+//! are little-endian 64-bit limbs in little-endian limb order, lengths are
+//! little-endian `u32`s and curve points use the compressed encoding.
+
+use std::error;
+use std::io;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, EncodedPoint};
+
+use crate::bls::{Bls12, Fq12, Fr, G1Affine, G2Affine};
+use crate::groth16::{
+    fr_process, g1_affine_process, g2_affine_process, groth16_primary_input_from_byteblob,
+    groth16_processed_vk_from_byteblob, groth16_proof_from_byteblob, Proof,
+};
+
+macro_rules! g1_generator {
+    () => {
+        "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+    };
+}
+
+macro_rules! g2_generator {
+    () => {
+        concat!(
+            "93e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e",
+            "024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8"
+        )
+    };
+}
+
+macro_rules! fr_five {
+    () => {
+        "0500000000000000000000000000000000000000000000000000000000000000"
+    };
+}
+
+/// The scalar `0`.
+pub const FR_ZERO: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// The scalar `1`.
+pub const FR_ONE: &str = "0100000000000000000000000000000000000000000000000000000000000000";
+/// The scalar `5`.
+pub const FR_FIVE: &str = fr_five!();
+/// The largest scalar, `r - 1`.
+pub const FR_MAX: &str = "00000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73";
+
+/// The generator of G1, compressed.
+pub const G1_GENERATOR_COMPRESSED: &str = g1_generator!();
+/// The generator of G2, compressed.
+pub const G2_GENERATOR_COMPRESSED: &str = g2_generator!();
+
+/// A proof with `A = C = g1` and `B = g2`, as written by `Proof::write` and
+/// read by `groth16_proof_from_byteblob`.
+pub const PROOF: &str = concat!(g1_generator!(), g2_generator!(), g1_generator!());
+
+/// A primary input of the single scalar `5`, prefixed with its length.
+pub const PRIMARY_INPUT_BYTEBLOB: &str = concat!("01000000", fr_five!());
+
+/// A verifying key byteblob with `alpha_g1_beta_g2 = 1`, `gamma_g2 = delta_g2 = g2`
+/// and `ic = [g1, g1]`.
+pub fn vk_byteblob() -> Vec<u8> {
+    // alpha_g1_beta_g2: Fq12 one, twelve 48 byte Fq coefficients.
+    let mut blob = vec![0u8; 12 * 48];
+    blob[0] = 1;
+
+    blob.extend(decode_hex(G2_GENERATOR_COMPRESSED).unwrap());
+    blob.extend(decode_hex(G2_GENERATOR_COMPRESSED).unwrap());
+
+    // ic: the first element, then the remaining ones as a sparse vector: the
+    // number of elements, their indices, the elements and the domain size.
+    blob.extend(decode_hex(G1_GENERATOR_COMPRESSED).unwrap());
+    blob.extend(&[1, 0, 0, 0]);
+    blob.extend(&[0, 0, 0, 0]);
+    blob.extend(decode_hex(G1_GENERATOR_COMPRESSED).unwrap());
+    blob.extend(&[1, 0, 0, 0]);
+
+    blob
+}
+
+/// The input of `verify_groth16_proof_from_byteblob`: [`PROOF`],
+/// [`PRIMARY_INPUT_BYTEBLOB`] and [`vk_byteblob`].
+pub fn verification_byteblob() -> Vec<u8> {
+    let mut blob = decode_hex(PROOF).unwrap();
+    blob.extend(decode_hex(PRIMARY_INPUT_BYTEBLOB).unwrap());
+    blob.extend(vk_byteblob());
+
+    blob
+}
+
+/// Checks that a hex encoded scalar decodes and encodes back to the same bytes.
+pub fn check_fr(hex: &str) -> Result<Fr, Box<dyn error::Error>> {
+    let bytes = decode_hex(hex)?;
+    ensure(bytes.len() == 32, "scalar length")?;
+    let fr = fr_process::<Bls12>(&bytes)?;
+
+    let mut encoded = Vec::with_capacity(bytes.len());
+    for limb in fr.into_repr().as_ref() {
+        encoded.write_u64::<LittleEndian>(*limb)?;
+    }
+    ensure(encoded == bytes, "scalar")?;
+
+    Ok(fr)
+}
+
+/// Checks that a hex encoded compressed G1 point decodes and encodes back to the same bytes.
+pub fn check_g1(hex: &str) -> Result<G1Affine, Box<dyn error::Error>> {
+    let bytes = decode_hex(hex)?;
+    ensure(
+        bytes.len() == <G1Affine as CurveAffine>::Compressed::size(),
+        "G1 point length",
+    )?;
+    let point = g1_affine_process::<Bls12>(&bytes)?;
+    ensure(point.into_compressed().as_ref() == &bytes[..], "G1 point")?;
+
+    Ok(point)
+}
+
+/// Checks that a hex encoded compressed G2 point decodes and encodes back to the same bytes.
+pub fn check_g2(hex: &str) -> Result<G2Affine, Box<dyn error::Error>> {
+    let bytes = decode_hex(hex)?;
+    ensure(
+        bytes.len() == <G2Affine as CurveAffine>::Compressed::size(),
+        "G2 point length",
+    )?;
+    let point = g2_affine_process::<Bls12>(&bytes)?;
+    ensure(point.into_compressed().as_ref() == &bytes[..], "G2 point")?;
+
+    Ok(point)
+}
+
+/// Checks that a hex encoded proof decodes and encodes back to the same bytes,
+/// both through `Proof::read` and the byteblob codec.
+pub fn check_proof(hex: &str) -> Result<Proof<Bls12>, Box<dyn error::Error>> {
+    let bytes = decode_hex(hex)?;
+    ensure(bytes.len() == Proof::<Bls12>::size(), "proof length")?;
+    let proof = Proof::<Bls12>::read(&bytes[..])?;
+    ensure(
+        proof == groth16_proof_from_byteblob::<Bls12>(&bytes)?,
+        "proof",
+    )?;
+
+    let mut encoded = Vec::with_capacity(bytes.len());
+    proof.write(&mut encoded)?;
+    ensure(encoded == bytes, "proof")?;
+
+    Ok(proof)
+}
+
+/// Checks all the fixtures of this module against the codecs of this crate.
+pub fn check_fixtures() -> Result<(), Box<dyn error::Error>> {
+    ensure(check_fr(FR_ZERO)? == Fr::zero(), "FR_ZERO")?;
+    ensure(check_fr(FR_ONE)? == Fr::one(), "FR_ONE")?;
+    ensure(check_fr(FR_FIVE)? == Fr::from_str("5").unwrap(), "FR_FIVE")?;
+    let mut max = Fr::zero();
+    max.sub_assign(&Fr::one());
+    ensure(check_fr(FR_MAX)? == max, "FR_MAX")?;
+
+    ensure(
+        check_g1(G1_GENERATOR_COMPRESSED)? == G1Affine::one(),
+        "G1_GENERATOR_COMPRESSED",
+    )?;
+    ensure(
+        check_g2(G2_GENERATOR_COMPRESSED)? == G2Affine::one(),
+        "G2_GENERATOR_COMPRESSED",
+    )?;
+
+    let proof = check_proof(PROOF)?;
+    ensure(
+        proof.a == G1Affine::one() && proof.b == G2Affine::one() && proof.c == G1Affine::one(),
+        "PROOF",
+    )?;
+
+    let primary_input =
+        groth16_primary_input_from_byteblob::<Bls12>(&decode_hex(PRIMARY_INPUT_BYTEBLOB)?)?;
+    ensure(
+        primary_input == vec![Fr::from_str("5").unwrap()],
+        "PRIMARY_INPUT_BYTEBLOB",
+    )?;
+
+    let vk = groth16_processed_vk_from_byteblob(&vk_byteblob())?;
+    ensure(
+        vk.alpha_g1_beta_g2 == Fq12::one()
+            && vk.gamma_g2 == G2Affine::one()
+            && vk.delta_g2 == G2Affine::one()
+            && vk.ic == vec![G1Affine::one(); 2],
+        "vk_byteblob",
+    )?;
+
+    Ok(())
+}
+
+fn ensure(condition: bool, what: &str) -> Result<(), Box<dyn error::Error>> {
+    if condition {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} mismatch", what)).into())
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, Box<dyn error::Error>> {
+    if !hex.is_ascii() || hex.len() % 2 != 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid hex string").into());
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.into()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_fixtures() {
+        check_fixtures().unwrap();
+    }
+
+    #[test]
+    fn test_verification_byteblob_layout() {
+        use crate::groth16::verify_groth16_proof_from_byteblob;
+
+        let blob = verification_byteblob();
+        let g1 = decode_hex(G1_GENERATOR_COMPRESSED).unwrap();
+        let g2 = decode_hex(G2_GENERATOR_COMPRESSED).unwrap();
+        assert_eq!(blob.len(), 1104);
+
+        // The proof: A, B and C.
+        assert_eq!(blob[0..48], g1[..]);
+        assert_eq!(blob[48..144], g2[..]);
+        assert_eq!(blob[144..192], g1[..]);
+
+        // The primary input: its length and the scalar 5.
+        assert_eq!(blob[192..196], [1, 0, 0, 0]);
+        assert_eq!(blob[196], 5);
+        assert!(blob[197..228].iter().all(|&b| b == 0));
+
+        // alpha_g1_beta_g2 = 1: the lowest limb of the first coefficient.
+        assert_eq!(blob[228], 1);
+        assert!(blob[229..804].iter().all(|&b| b == 0));
+
+        // gamma_g2 and delta_g2.
+        assert_eq!(blob[804..900], g2[..]);
+        assert_eq!(blob[900..996], g2[..]);
+
+        // ic: the first element, then a sparse vector of one element at index
+        // 0 and its domain size 1.
+        assert_eq!(blob[996..1044], g1[..]);
+        assert_eq!(blob[1044..1048], [1, 0, 0, 0]);
+        assert_eq!(blob[1048..1052], [0, 0, 0, 0]);
+        assert_eq!(blob[1052..1100], g1[..]);
+        assert_eq!(blob[1100..1104], [1, 0, 0, 0]);
+
+        // Decodes fine, but the fixture is not a valid proof.
+        assert!(!verify_groth16_proof_from_byteblob::<Bls12>(&blob).unwrap());
+    }
+
+    #[test]
+    fn test_check_rejects_mismatch() {
+        assert!(check_fr("01").is_err());
+        assert!(check_g1(&G1_GENERATOR_COMPRESSED[..94]).is_err());
+        assert!(check_proof(&PROOF[2..]).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+//! Combinators for assembling a circuit from independent sub-circuits.
+//!
+//! `CircuitExt` is implemented for every `Circuit`. `named` synthesizes a
+//! circuit in its own namespace and `then` synthesizes two circuits one
+//! after the other. `then_sharing` additionally lets the second circuit
+//! reuse public inputs of the first instead of allocating its own, and
+//! `then_with` builds the second circuit from the values of the public
+//! inputs of the first.
+//!
+//! Public inputs are numbered in the order a circuit allocates them, without
+//! the constant one. A composed circuit allocates the inputs of its parts in
+//! order, except for the shared ones.
+
+use std::collections::HashMap;
+
+use ff::ScalarEngine;
+
+use crate::{Circuit, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+/// Combinators on circuits.
+pub trait CircuitExt<E: ScalarEngine>: Circuit<E> + Sized {
+    /// Synthesizes `self` in the namespace `name`.
+    fn named<N: Into<String>>(self, name: N) -> Named<Self> {
+        Named {
+            name: name.into(),
+            circuit: self,
+        }
+    }
+
+    /// Synthesizes `self` and then `next`.
+    fn then<C: Circuit<E>>(self, next: C) -> Then<Self, C> {
+        Then {
+            first: self,
+            second: next,
+        }
+    }
+
+    /// Synthesizes `self` and then `next`, where each pair `(i, j)` of
+    /// `shared` makes input `j` of `next` the same variable as input `i` of
+    /// `self`. When proving, the values the two circuits give such an input
+    /// must be equal.
+    fn then_sharing<C: Circuit<E>>(self, next: C, shared: &[(usize, usize)]) -> Sharing<Self, C> {
+        Sharing {
+            first: self,
+            second: next,
+            shared: shared.to_vec(),
+        }
+    }
+
+    /// Synthesizes `self` and then the circuit `build` returns for the
+    /// values of the public inputs of `self`. These are `None` when not all
+    /// of them are known, as during parameter generation.
+    fn then_with<C, F>(self, build: F) -> ThenWith<Self, F>
+    where
+        C: Circuit<E>,
+        F: FnOnce(Option<Vec<E::Fr>>) -> C,
+    {
+        ThenWith { first: self, build }
+    }
+}
+
+impl<E: ScalarEngine, C: Circuit<E>> CircuitExt<E> for C {}
+
+/// A circuit synthesized in a namespace, see `CircuitExt::named`.
+#[derive(Clone, Debug)]
+pub struct Named<C> {
+    name: String,
+    circuit: C,
+}
+
+impl<E: ScalarEngine, C: Circuit<E>> Circuit<E> for Named<C> {
+    fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let Named { name, circuit } = self;
+        circuit.synthesize(&mut cs.namespace(|| name))
+    }
+}
+
+/// Two circuits synthesized in order, see `CircuitExt::then`.
+#[derive(Clone, Debug)]
+pub struct Then<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<E: ScalarEngine, A: Circuit<E>, B: Circuit<E>> Circuit<E> for Then<A, B> {
+    fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        self.first.synthesize(cs)?;
+        self.second.synthesize(cs)
+    }
+}
+
+/// Two circuits with shared public inputs, see `CircuitExt::then_sharing`.
+#[derive(Clone, Debug)]
+pub struct Sharing<A, B> {
+    first: A,
+    second: B,
+    shared: Vec<(usize, usize)>,
+}
+
+impl<E: ScalarEngine, A: Circuit<E>, B: Circuit<E>> Circuit<E> for Sharing<A, B> {
+    fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let mut first = InputCS::new(cs, HashMap::new());
+        self.first.synthesize(&mut first)?;
+        let inputs = first.inputs;
+
+        let mut shared = HashMap::new();
+        for &(i, j) in &self.shared {
+            let input = inputs.get(i).ok_or_else(|| {
+                SynthesisError::IncompatibleLengthVector(format!(
+                    "shared input {} of a circuit with {} inputs",
+                    i,
+                    inputs.len()
+                ))
+            })?;
+            shared.insert(j, *input);
+        }
+
+        let mut second = InputCS::new(cs, shared);
+        self.second.synthesize(&mut second)?;
+        if let Some(&(_, j)) = self.shared.iter().find(|(_, j)| *j >= second.allocated) {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "shared input {} of a circuit with {} inputs",
+                j, second.allocated
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// A circuit followed by one built from its public inputs, see
+/// `CircuitExt::then_with`.
+#[derive(Clone, Debug)]
+pub struct ThenWith<A, F> {
+    first: A,
+    build: F,
+}
+
+impl<E, A, B, F> Circuit<E> for ThenWith<A, F>
+where
+    E: ScalarEngine,
+    A: Circuit<E>,
+    B: Circuit<E>,
+    F: FnOnce(Option<Vec<E::Fr>>) -> B,
+{
+    fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let mut first = InputCS::new(cs, HashMap::new());
+        self.first.synthesize(&mut first)?;
+        let values = first
+            .inputs
+            .iter()
+            .map(|(_, value)| *value)
+            .collect::<Option<Vec<_>>>();
+
+        (self.build)(values).synthesize(cs)
+    }
+}
+
+/// A public input and its value, if known.
+type Input<E> = (Variable, Option<<E as ScalarEngine>::Fr>);
+
+// Passes everything through to `cs` and records the public inputs allocated
+// there. The inputs with an index in `shared` are not allocated but mapped to
+// the given ones.
+struct InputCS<'a, E: ScalarEngine, CS: ConstraintSystem<E>> {
+    cs: &'a mut CS,
+    shared: HashMap<usize, Input<E>>,
+    /// Number of inputs the circuit asked for, shared ones included.
+    allocated: usize,
+    inputs: Vec<Input<E>>,
+}
+
+impl<'a, E: ScalarEngine, CS: ConstraintSystem<E>> InputCS<'a, E, CS> {
+    fn new(cs: &'a mut CS, shared: HashMap<usize, Input<E>>) -> Self {
+        InputCS {
+            cs,
+            shared,
+            allocated: 0,
+            inputs: vec![],
+        }
+    }
+}
+
+impl<'a, E: ScalarEngine, CS: ConstraintSystem<E>> ConstraintSystem<E> for InputCS<'a, E, CS> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.alloc(annotation, f)
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let index = self.allocated;
+        self.allocated += 1;
+        let value = f();
+
+        if let Some(&(variable, shared)) = self.shared.get(&index) {
+            if let (Ok(value), Some(shared)) = (&value, shared) {
+                if *value != shared {
+                    return Err(SynthesisError::Unsatisfiable);
+                }
+            }
+            return Ok(variable);
+        }
+
+        let known = value.as_ref().ok().copied();
+        let variable = self.cs.alloc_input(annotation, || value)?;
+        self.inputs.push((variable, known));
+
+        Ok(variable)
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+    {
+        self.cs.enforce(annotation, a, b, c)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.get_root().push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self) {
+        self.cs.get_root().pop_namespace()
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::{Field, PrimeField};
+
+    fn fr(n: &str) -> Fr {
+        Fr::from_str(n).unwrap()
+    }
+
+    // Exposes x * y, and x itself if `public`.
+    #[derive(Clone)]
+    struct Product {
+        x: Option<Fr>,
+        y: Option<Fr>,
+        public: bool,
+    }
+
+    impl Circuit<Bls12> for Product {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let x_value = self.x;
+            let x = if self.public {
+                cs.alloc_input(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?
+            } else {
+                cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            let y = cs.alloc(|| "y", || self.y.ok_or(SynthesisError::AssignmentMissing))?;
+            let xy = cs.alloc_input(
+                || "xy",
+                || {
+                    let mut xy = self.x.ok_or(SynthesisError::AssignmentMissing)?;
+                    xy.mul_assign(&self.y.ok_or(SynthesisError::AssignmentMissing)?);
+                    Ok(xy)
+                },
+            )?;
+            cs.enforce(|| "x * y = xy", |lc| lc + x, |lc| lc + y, |lc| lc + xy);
+
+            Ok(())
+        }
+    }
+
+    fn product(x: Option<&str>, y: Option<&str>, public: bool) -> Product {
+        Product {
+            x: x.map(fr),
+            y: y.map(fr),
+            public,
+        }
+    }
+
+    #[test]
+    fn test_then() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        product(Some("2"), Some("3"), false)
+            .named("a")
+            .then(product(Some("4"), Some("5"), false).named("b"))
+            .synthesize(&mut cs)
+            .unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_inputs(), 3);
+        assert_eq!(cs.num_constraints(), 2);
+        assert_eq!(cs.get_input(1, "a/xy"), fr("6"));
+        assert_eq!(cs.get_input(2, "b/xy"), fr("20"));
+    }
+
+    #[test]
+    fn test_then_sharing() {
+        let circuit = |x: &str| {
+            product(Some("2"), Some("3"), false)
+                .named("a")
+                .then_sharing(product(Some(x), Some("5"), true).named("b"), &[(0, 0)])
+        };
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        circuit("6").synthesize(&mut cs).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_inputs(), 3);
+        assert_eq!(cs.get_input(1, "a/xy"), fr("6"));
+        assert_eq!(cs.get_input(2, "b/xy"), fr("30"));
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        assert!(matches!(
+            circuit("7").synthesize(&mut cs),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+
+        for shared in &[(1, 0), (0, 2)] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let result = product(Some("2"), Some("3"), false)
+                .named("a")
+                .then_sharing(product(Some("6"), Some("5"), true).named("b"), &[*shared])
+                .synthesize(&mut cs);
+            assert!(matches!(
+                result,
+                Err(SynthesisError::IncompatibleLengthVector(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_then_with() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        product(Some("2"), Some("3"), true)
+            .named("a")
+            .then_with(|inputs: Option<Vec<Fr>>| {
+                let inputs = inputs.unwrap();
+                assert_eq!(inputs, vec![fr("2"), fr("6")]);
+                Product {
+                    x: Some(inputs[1]),
+                    y: Some(fr("5")),
+                    public: false,
+                }
+                .named("b")
+            })
+            .synthesize(&mut cs)
+            .unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_inputs(), 4);
+        assert_eq!(cs.get_input(3, "b/xy"), fr("30"));
+    }
+
+    #[test]
+    fn test_prove_composed() {
+        let mut rng = test_rng();
+
+        let circuit = |x: Option<&str>, y: Option<&str>, z: Option<&str>| {
+            product(x, y, false)
+                .named("a")
+                .then_sharing(product(y, z, true).named("b"), &[(0, 0)])
+        };
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(circuit(None, None, None), &mut rng).unwrap();
+        let proof =
+            create_random_proof(circuit(Some("2"), Some("3"), Some("5")), &params, &mut rng);
+
+        // The second product is 3 * 5, but b/x is a/xy = 6.
+        assert!(matches!(proof, Err(SynthesisError::Unsatisfiable)));
+
+        let circuit = |x: Option<&str>, z: Option<&str>| {
+            product(x, Some("3"), false)
+                .named("a")
+                .then_sharing(product(Some("6"), z, true).named("b"), &[(0, 0)])
+        };
+        let proof = create_random_proof(circuit(Some("2"), Some("5")), &params, &mut rng).unwrap();
+
+        let pvk = prepare_verifying_key(&params.vk);
+        assert!(verify_proof(&pvk, &proof, &[fr("6"), fr("30")]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[fr("6"), fr("31")]).unwrap());
+    }
+}
@@ -48,40 +48,23 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
         self.coeffs
     }
 
-    pub fn from_coeffs(mut coeffs: Vec<G>) -> Result<EvaluationDomain<E, G>, SynthesisError> {
-        // Compute the size of our evaluation domain
-        let mut m = 1;
-        let mut exp = 0;
-        while m < coeffs.len() {
-            m *= 2;
-            exp += 1;
-
-            // The pairing-friendly curve may not be able to support
-            // large enough (radix2) evaluation domains.
-            if exp >= E::Fr::S {
-                return Err(SynthesisError::PolynomialDegreeTooLarge);
-            }
-        }
-        // Compute omega, the 2^exp primitive root of unity
-        let mut omega = E::Fr::root_of_unity();
-        for _ in exp..E::Fr::S {
-            omega.square();
-        }
+    pub fn from_coeffs(coeffs: Vec<G>) -> Result<EvaluationDomain<E, G>, SynthesisError> {
+        let constants = DomainConstants::new(domain_exp::<E>(coeffs.len())?);
+        Ok(Self::with_constants(coeffs, constants))
+    }
 
+    fn with_constants(mut coeffs: Vec<G>, constants: DomainConstants<E>) -> Self {
         // Extend the coeffs vector with zeroes if necessary
-        coeffs.resize(m, G::group_zero());
+        coeffs.resize(1 << constants.exp, G::group_zero());
 
-        Ok(EvaluationDomain {
+        EvaluationDomain {
             coeffs,
-            exp,
-            omega,
-            omegainv: omega.inverse().unwrap(),
-            geninv: E::Fr::multiplicative_generator().inverse().unwrap(),
-            minv: E::Fr::from_str(&format!("{}", m))
-                .unwrap()
-                .inverse()
-                .unwrap(),
-        })
+            exp: constants.exp,
+            omega: constants.omega,
+            omegainv: constants.omegainv,
+            geninv: constants.geninv,
+            minv: constants.minv,
+        }
     }
 
     pub fn fft(
@@ -89,7 +72,14 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
         worker: &Worker,
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
     ) -> gpu::GPUResult<()> {
-        best_fft(kern, &mut self.coeffs, worker, &self.omega, self.exp)?;
+        best_fft(
+            kern,
+            &mut self.coeffs,
+            worker,
+            &self.omega,
+            self.exp,
+            &mut vec![],
+        )?;
         Ok(())
     }
 
@@ -98,8 +88,29 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
         worker: &Worker,
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
     ) -> gpu::GPUResult<()> {
-        best_fft(kern, &mut self.coeffs, worker, &self.omegainv, self.exp)?;
+        self.ifft_in(worker, kern, &mut vec![])
+    }
+
+    fn ifft_in(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        tmp: &mut Vec<G>,
+    ) -> gpu::GPUResult<()> {
+        best_fft(
+            kern,
+            &mut self.coeffs,
+            worker,
+            &self.omegainv,
+            self.exp,
+            tmp,
+        )?;
+        self.divide_by_m(worker);
+
+        Ok(())
+    }
 
+    fn divide_by_m(&mut self, worker: &Worker) {
         worker.scope(self.coeffs.len(), |scope, chunk| {
             let minv = self.minv;
 
@@ -111,6 +122,72 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
                 });
             }
         });
+    }
+
+    /// Like `ifft` for several domains of the same size at once, which lets
+    /// the GPU code spread them over the devices.
+    pub fn ifft_many(
+        domains: &mut [&mut Self],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        Self::ifft_many_in(domains, worker, kern, &mut vec![])
+    }
+
+    fn ifft_many_in(
+        domains: &mut [&mut Self],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        tmp: &mut Vec<G>,
+    ) -> gpu::GPUResult<()> {
+        if domains.is_empty() {
+            return Ok(());
+        }
+        let (omegainv, exp) = (domains[0].omegainv, domains[0].exp);
+        assert!(domains.iter().all(|d| d.exp == exp));
+
+        let mut coeffs = domains
+            .iter_mut()
+            .map(|d| &mut d.coeffs[..])
+            .collect::<Vec<_>>();
+        best_fft_many(kern, &mut coeffs, worker, &omegainv, exp, tmp)?;
+        for domain in domains.iter_mut() {
+            domain.divide_by_m(worker);
+        }
+
+        Ok(())
+    }
+
+    /// Like `coset_fft` for several domains of the same size at once, see
+    /// `ifft_many`.
+    pub fn coset_fft_many(
+        domains: &mut [&mut Self],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        Self::coset_fft_many_in(domains, worker, kern, &mut vec![])
+    }
+
+    fn coset_fft_many_in(
+        domains: &mut [&mut Self],
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        tmp: &mut Vec<G>,
+    ) -> gpu::GPUResult<()> {
+        if domains.is_empty() {
+            return Ok(());
+        }
+        let (omega, exp) = (domains[0].omega, domains[0].exp);
+        assert!(domains.iter().all(|d| d.exp == exp));
+
+        for domain in domains.iter_mut() {
+            domain.distribute_powers(worker, E::Fr::multiplicative_generator());
+        }
+        let mut coeffs = domains
+            .iter_mut()
+            .map(|d| &mut d.coeffs[..])
+            .collect::<Vec<_>>();
+        best_fft_many(kern, &mut coeffs, worker, &omega, exp, tmp)?;
 
         Ok(())
     }
@@ -143,9 +220,18 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
         &mut self,
         worker: &Worker,
         kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    ) -> gpu::GPUResult<()> {
+        self.icoset_fft_in(worker, kern, &mut vec![])
+    }
+
+    fn icoset_fft_in(
+        &mut self,
+        worker: &Worker,
+        kern: &mut Option<gpu::LockedFFTKernel<E>>,
+        tmp: &mut Vec<G>,
     ) -> gpu::GPUResult<()> {
         let geninv = self.geninv;
-        self.ifft(worker, kern)?;
+        self.ifft_in(worker, kern, tmp)?;
         self.distribute_powers(worker, geninv);
         Ok(())
     }
@@ -218,6 +304,182 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
     }
 }
 
+/// Computes the coefficients of the quotient polynomial `h(x)` of a QAP,
+/// where `a(x) * b(x) - c(x) = h(x) * z(x)`.
+///
+/// `a`, `b` and `c` hold the evaluations of the QAP polynomials over the
+/// domain, one per constraint, as produced during synthesis. They are
+/// interpolated, evaluated over a coset of the domain (where `z(x)` has no
+/// roots), combined pointwise and divided by `z(x)` before being interpolated
+/// back. For a domain of size `m` the quotient has degree at most `m - 2`, so
+/// the returned vector holds `m - 1` coefficients.
+pub fn compute_quotient_polynomial<E: Engine>(
+    worker: &Worker,
+    kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    a: Vec<Scalar<E>>,
+    b: Vec<Scalar<E>>,
+    c: Vec<Scalar<E>>,
+) -> Result<Vec<Scalar<E>>, SynthesisError> {
+    compute_quotient_polynomial_in(worker, kern, a, b, c, &mut FftScratch::new())
+}
+
+/// Like [`compute_quotient_polynomial`], with the buffers and domain
+/// constants of `scratch`, which the quotients of a batch share.
+pub fn compute_quotient_polynomial_in<E: Engine>(
+    worker: &Worker,
+    kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    a: Vec<Scalar<E>>,
+    b: Vec<Scalar<E>>,
+    c: Vec<Scalar<E>>,
+    scratch: &mut FftScratch<E>,
+) -> Result<Vec<Scalar<E>>, SynthesisError> {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), c.len());
+
+    let mut a = scratch.domain(a)?;
+    let mut b = scratch.domain(b)?;
+    let mut c = scratch.domain(c)?;
+
+    // The three polynomials are independent, so with several GPUs each of
+    // them can be transformed on a different device.
+    {
+        let mut domains = [&mut a, &mut b, &mut c];
+        EvaluationDomain::ifft_many_in(&mut domains, worker, kern, &mut scratch.tmp)?;
+        EvaluationDomain::coset_fft_many_in(&mut domains, worker, kern, &mut scratch.tmp)?;
+    }
+
+    a.mul_assign(worker, &b);
+    scratch.recycle(b.into_coeffs());
+    a.sub_assign(worker, &c);
+    scratch.recycle(c.into_coeffs());
+    a.divide_by_z_on_coset(worker);
+    a.icoset_fft_in(worker, kern, &mut scratch.tmp)?;
+
+    let mut a = a.into_coeffs();
+    let a_len = a.len() - 1;
+    a.truncate(a_len);
+
+    Ok(a)
+}
+
+/// The size `2^exp` of the smallest domain holding `len` evaluations.
+fn domain_exp<E: ScalarEngine>(len: usize) -> Result<u32, SynthesisError> {
+    let mut m = 1;
+    let mut exp = 0;
+    while m < len {
+        m *= 2;
+        exp += 1;
+
+        // The pairing-friendly curve may not be able to support
+        // large enough (radix2) evaluation domains.
+        if exp >= E::Fr::S {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+    }
+
+    Ok(exp)
+}
+
+struct DomainConstants<E: ScalarEngine> {
+    exp: u32,
+    omega: E::Fr,
+    omegainv: E::Fr,
+    geninv: E::Fr,
+    minv: E::Fr,
+}
+
+impl<E: ScalarEngine> Copy for DomainConstants<E> {}
+
+impl<E: ScalarEngine> Clone for DomainConstants<E> {
+    fn clone(&self) -> DomainConstants<E> {
+        *self
+    }
+}
+
+impl<E: ScalarEngine> DomainConstants<E> {
+    fn new(exp: u32) -> Self {
+        // Compute omega, the 2^exp primitive root of unity
+        let mut omega = E::Fr::root_of_unity();
+        for _ in exp..E::Fr::S {
+            omega.square();
+        }
+
+        DomainConstants {
+            exp,
+            omega,
+            omegainv: omega.inverse().unwrap(),
+            geninv: E::Fr::multiplicative_generator().inverse().unwrap(),
+            minv: E::Fr::from_str(&format!("{}", 1u64 << exp))
+                .unwrap()
+                .inverse()
+                .unwrap(),
+        }
+    }
+}
+
+/// Buffers and domain constants the quotient polynomials of a batch of
+/// proofs share, see [`compute_quotient_polynomial_in`].
+///
+/// The scratch space of the CPU FFT is allocated once, and the domains freed
+/// by a quotient hold the evaluations of the next one when its witness has
+/// to grow to the size of the domain, e.g. after it was read from disk. The
+/// buffers are kept until the scratch is dropped.
+pub struct FftScratch<E: ScalarEngine> {
+    constants: Option<DomainConstants<E>>,
+    tmp: Vec<Scalar<E>>,
+    spare: Vec<Vec<Scalar<E>>>,
+}
+
+impl<E: ScalarEngine> Default for FftScratch<E> {
+    fn default() -> Self {
+        FftScratch {
+            constants: None,
+            tmp: vec![],
+            spare: vec![],
+        }
+    }
+}
+
+impl<E: Engine> FftScratch<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn domain(
+        &mut self,
+        mut coeffs: Vec<Scalar<E>>,
+    ) -> Result<EvaluationDomain<E, Scalar<E>>, SynthesisError> {
+        let exp = domain_exp::<E>(coeffs.len())?;
+        let constants = match self.constants {
+            Some(constants) if constants.exp == exp => constants,
+            _ => {
+                let constants = DomainConstants::new(exp);
+                self.constants = Some(constants);
+                constants
+            }
+        };
+
+        let m = 1 << exp;
+        if coeffs.capacity() < m {
+            if let Some(i) = self.spare.iter().position(|b| b.capacity() >= m) {
+                let mut buffer = self.spare.swap_remove(i);
+                buffer.clear();
+                buffer.extend_from_slice(&coeffs);
+                coeffs = buffer;
+            }
+        }
+
+        Ok(EvaluationDomain::with_constants(coeffs, constants))
+    }
+
+    // The two domains a quotient frees are enough for the next one.
+    fn recycle(&mut self, buffer: Vec<Scalar<E>>) {
+        if self.spare.len() < 2 {
+            self.spare.push(buffer);
+        }
+    }
+}
+
 pub trait Group<E: ScalarEngine>: Sized + Copy + Clone + Send + Sync {
     fn group_zero() -> Self;
     fn group_mul_assign(&mut self, by: &E::Fr);
@@ -293,6 +555,7 @@ fn best_fft<E: Engine, T: Group<E>>(
     worker: &Worker,
     omega: &E::Fr,
     log_n: u32,
+    tmp: &mut Vec<T>,
 ) -> gpu::GPUResult<()> {
     if let Some(ref mut kern) = kern {
         if kern
@@ -303,14 +566,48 @@ fn best_fft<E: Engine, T: Group<E>>(
         }
     }
 
+    cpu_fft(a, worker, omega, log_n, tmp);
+
+    Ok(())
+}
+
+fn best_fft_many<E: Engine, T: Group<E>>(
+    kern: &mut Option<gpu::LockedFFTKernel<E>>,
+    inputs: &mut [&mut [T]],
+    worker: &Worker,
+    omega: &E::Fr,
+    log_n: u32,
+    tmp: &mut Vec<T>,
+) -> gpu::GPUResult<()> {
+    if let Some(ref mut kern) = kern {
+        if kern
+            .with(|k: &mut gpu::FFTKernel<E>| gpu_fft_many(k, inputs, omega, log_n))
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+
+    for a in inputs.iter_mut() {
+        cpu_fft(a, worker, omega, log_n, tmp);
+    }
+
+    Ok(())
+}
+
+fn cpu_fft<E: Engine, T: Group<E>>(
+    a: &mut [T],
+    worker: &Worker,
+    omega: &E::Fr,
+    log_n: u32,
+    tmp: &mut Vec<T>,
+) {
     let log_cpus = worker.log_num_cpus();
     if log_n <= log_cpus {
         serial_fft(a, omega, log_n);
     } else {
-        parallel_fft(a, worker, omega, log_n, log_cpus);
+        parallel_fft_in(a, worker, omega, log_n, log_cpus, tmp);
     }
-
-    Ok(())
 }
 
 pub fn gpu_fft<E: Engine, T: Group<E>>(
@@ -331,6 +628,18 @@ pub fn gpu_fft<E: Engine, T: Group<E>>(
     Ok(())
 }
 
+pub fn gpu_fft_many<E: Engine, T: Group<E>>(
+    kern: &mut gpu::FFTKernel<E>,
+    inputs: &mut [&mut [T]],
+    omega: &E::Fr,
+    log_n: u32,
+) -> gpu::GPUResult<()> {
+    // See `gpu_fft` for why this transmute is sound.
+    let inputs = unsafe { std::mem::transmute::<&mut [&mut [T]], &mut [&mut [E::Fr]]>(inputs) };
+    kern.radix_fft_many(inputs, omega, log_n)?;
+    Ok(())
+}
+
 pub fn serial_fft<E: ScalarEngine, T: Group<E>>(a: &mut [T], omega: &E::Fr, log_n: u32) {
     fn bitreverse(mut n: u32, l: u32) -> u32 {
         let mut r = 0;
@@ -375,24 +684,38 @@ pub fn serial_fft<E: ScalarEngine, T: Group<E>>(a: &mut [T], omega: &E::Fr, log_
     }
 }
 
+#[cfg(test)]
 fn parallel_fft<E: ScalarEngine, T: Group<E>>(
     a: &mut [T],
     worker: &Worker,
     omega: &E::Fr,
     log_n: u32,
     log_cpus: u32,
+) {
+    parallel_fft_in(a, worker, omega, log_n, log_cpus, &mut vec![])
+}
+
+/// `parallel_fft` with the sub-FFTs in `tmp`, which is resized as needed.
+fn parallel_fft_in<E: ScalarEngine, T: Group<E>>(
+    a: &mut [T],
+    worker: &Worker,
+    omega: &E::Fr,
+    log_n: u32,
+    log_cpus: u32,
+    tmp: &mut Vec<T>,
 ) {
     assert!(log_n >= log_cpus);
 
     let num_cpus = 1 << log_cpus;
     let log_new_n = log_n - log_cpus;
-    let mut tmp = vec![vec![T::group_zero(); 1 << log_new_n]; num_cpus];
+    tmp.clear();
+    tmp.resize(1 << log_n, T::group_zero());
     let new_omega = omega.pow(&[num_cpus as u64]);
 
     worker.scope(0, |scope, _| {
         let a = &*a;
 
-        for (j, tmp) in tmp.iter_mut().enumerate() {
+        for (j, tmp) in tmp.chunks_mut(1 << log_new_n).enumerate() {
             scope.spawn(move |_scope| {
                 // Shuffle into a sub-FFT
                 let omega_j = omega.pow(&[j as u64]);
@@ -418,14 +741,14 @@ fn parallel_fft<E: ScalarEngine, T: Group<E>>(
 
     // TODO: does this hurt or help?
     worker.scope(a.len(), |scope, chunk| {
-        let tmp = &tmp;
+        let tmp = &*tmp;
 
         for (idx, a) in a.chunks_mut(chunk).enumerate() {
             scope.spawn(move |_scope| {
                 let mut idx = idx * chunk;
                 let mask = (1 << log_cpus) - 1;
                 for a in a {
-                    *a = tmp[idx & mask][idx >> log_cpus];
+                    *a = tmp[((idx & mask) << log_new_n) + (idx >> log_cpus)];
                     idx += 1;
                 }
             });
@@ -524,6 +847,68 @@ fn fft_composition() {
     test_comp::<Bls12, _>(rng);
 }
 
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn quotient_polynomial() {
+    use crate::bls::{Bls12, Engine};
+    use rand_core::RngCore;
+
+    fn evaluate<E: ScalarEngine>(coeffs: &[Scalar<E>], point: &E::Fr) -> E::Fr {
+        let mut acc = E::Fr::zero();
+        for coeff in coeffs.iter().rev() {
+            acc.mul_assign(point);
+            acc.add_assign(&coeff.0);
+        }
+        acc
+    }
+
+    fn test_quotient<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
+        let worker = Worker::new();
+
+        for &n in &[1, 2, 7, 16, 33] {
+            let a: Vec<_> = (0..n).map(|_| Scalar::<E>(E::Fr::random(rng))).collect();
+            let b: Vec<_> = (0..n).map(|_| Scalar::<E>(E::Fr::random(rng))).collect();
+            let c: Vec<_> = a
+                .iter()
+                .zip(b.iter())
+                .map(|(a, b)| {
+                    let mut c = *a;
+                    c.group_mul_assign(&b.0);
+                    c
+                })
+                .collect();
+
+            // Interpolate the evaluations so the polynomials can be checked at a random point.
+            let interpolate = |evals: &[Scalar<E>]| {
+                let mut domain = EvaluationDomain::from_coeffs(evals.to_vec()).unwrap();
+                domain.ifft(&worker, &mut None).unwrap();
+                domain
+            };
+            let a_poly = interpolate(&a);
+            let b_poly = interpolate(&b);
+            let c_poly = interpolate(&c);
+
+            let h = compute_quotient_polynomial(&worker, &mut None, a, b, c).unwrap();
+            assert_eq!(h.len(), a_poly.as_ref().len() - 1);
+
+            let tau = E::Fr::random(rng);
+
+            let mut lhs = evaluate(a_poly.as_ref(), &tau);
+            lhs.mul_assign(&evaluate(b_poly.as_ref(), &tau));
+            lhs.sub_assign(&evaluate(c_poly.as_ref(), &tau));
+
+            let mut rhs = evaluate(&h, &tau);
+            rhs.mul_assign(&a_poly.z(&tau));
+
+            assert_eq!(lhs, rhs);
+        }
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_quotient::<Bls12, _>(rng);
+}
+
 #[cfg(any(feature = "pairing", feature = "blst"))]
 #[test]
 fn parallel_fft_consistency() {
@@ -559,11 +944,103 @@ fn parallel_fft_consistency() {
     test_consistency::<Bls12, _>(rng);
 }
 
-pub fn create_fft_kernel<E>(_log_d: usize, priority: bool) -> Option<gpu::FFTKernel<E>>
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn fft_many_consistency() {
+    use crate::bls::{Bls12, Engine};
+    use rand_core::RngCore;
+
+    fn test_consistency<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
+        let worker = Worker::new();
+
+        for log_d in 0..8 {
+            let d = 1 << log_d;
+
+            let v = (0..3)
+                .map(|_| {
+                    (0..d)
+                        .map(|_| Scalar::<E>(E::Fr::random(rng)))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut many = v
+                .iter()
+                .map(|v| EvaluationDomain::from_coeffs(v.clone()).unwrap())
+                .collect::<Vec<_>>();
+            {
+                let mut domains = many.iter_mut().collect::<Vec<_>>();
+                EvaluationDomain::ifft_many(&mut domains, &worker, &mut None).unwrap();
+                EvaluationDomain::coset_fft_many(&mut domains, &worker, &mut None).unwrap();
+            }
+
+            for (v, many) in v.into_iter().zip(many.iter()) {
+                let mut one = EvaluationDomain::from_coeffs(v).unwrap();
+                one.ifft(&worker, &mut None).unwrap();
+                one.coset_fft(&worker, &mut None).unwrap();
+                assert!(one.coeffs == many.coeffs);
+            }
+        }
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_consistency::<Bls12, _>(rng);
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn quotient_scratch_consistency() {
+    use crate::bls::{Bls12, Engine};
+    use rand_core::RngCore;
+
+    fn test_consistency<E: ScalarEngine + Engine, R: RngCore>(rng: &mut R) {
+        let worker = Worker::new();
+        let mut scratch = FftScratch::new();
+
+        // Sizes change within the batch, and the witnesses have no spare
+        // capacity, so they are moved into recycled buffers.
+        for &n in &[100, 100, 37, 1000, 100] {
+            let mut random = || {
+                let mut v = (0..n)
+                    .map(|_| Scalar::<E>(E::Fr::random(rng)))
+                    .collect::<Vec<_>>();
+                v.shrink_to_fit();
+                v
+            };
+            let (a, b, c) = (random(), random(), random());
+
+            let expected =
+                compute_quotient_polynomial(&worker, &mut None, a.clone(), b.clone(), c.clone())
+                    .unwrap();
+            let h =
+                compute_quotient_polynomial_in(&worker, &mut None, a, b, c, &mut scratch).unwrap();
+            assert!(h == expected);
+        }
+        assert_eq!(scratch.spare.len(), 2);
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_consistency::<Bls12, _>(rng);
+}
+
+pub fn create_fft_kernel<E>(log_d: usize, priority: bool) -> Option<gpu::FFTKernel<E>>
+where
+    E: Engine,
+{
+    create_fft_kernel_with_options(log_d, priority, &gpu::GpuOptions::default())
+}
+
+pub fn create_fft_kernel_with_options<E>(
+    _log_d: usize,
+    priority: bool,
+    options: &gpu::GpuOptions,
+) -> Option<gpu::FFTKernel<E>>
 where
     E: Engine,
 {
-    match gpu::FFTKernel::create(priority) {
+    match gpu::FFTKernel::create_with_options(priority, options) {
         Ok(k) => {
             info!("GPU FFT kernel instantiated!");
             Some(k)
@@ -579,7 +1056,9 @@ where
 #[cfg(test)]
 mod tests {
     use crate::bls::{Bls12, Fr};
-    use crate::domain::{gpu_fft, parallel_fft, serial_fft, EvaluationDomain, Scalar};
+    use crate::domain::{
+        gpu_fft, gpu_fft_many, parallel_fft, serial_fft, EvaluationDomain, Scalar,
+    };
     use crate::gpu;
     use crate::multicore::Worker;
     use ff::Field;
@@ -629,4 +1108,41 @@ mod tests {
             println!("============================");
         }
     }
+
+    #[test]
+    pub fn gpu_fft_many_consistency() {
+        let _ = env_logger::try_init();
+
+        let rng = &mut rand::thread_rng();
+
+        let mut kern = gpu::FFTKernel::create(false).expect("Cannot initialize kernel!");
+
+        for log_d in 1..=16 {
+            let d = 1 << log_d;
+
+            let mut v1 = (0..3)
+                .map(|_| {
+                    let elems = (0..d)
+                        .map(|_| Scalar::<Bls12>(Fr::random(rng)))
+                        .collect::<Vec<_>>();
+                    EvaluationDomain::from_coeffs(elems).unwrap()
+                })
+                .collect::<Vec<_>>();
+            let mut v2 = v1
+                .iter()
+                .map(|v| EvaluationDomain::from_coeffs(v.coeffs.clone()).unwrap())
+                .collect::<Vec<_>>();
+            let omega = v1[0].omega;
+
+            let mut inputs = v1.iter_mut().map(|v| &mut v.coeffs[..]).collect::<Vec<_>>();
+            gpu_fft_many(&mut kern, &mut inputs, &omega, log_d).expect("GPU FFT failed!");
+            for v in v2.iter_mut() {
+                gpu_fft(&mut kern, &mut v.coeffs, &omega, log_d).expect("GPU FFT failed!");
+            }
+
+            for (v1, v2) in v1.iter().zip(v2.iter()) {
+                assert!(v1.coeffs == v2.coeffs);
+            }
+        }
+    }
 }
@@ -0,0 +1,246 @@
+//! EdDSA signatures over the Jubjub curve of the `jubjub` module, verified
+//! in circuits by `gadgets::eddsa`.
+//!
+//! The message `m` is an element of the scalar field of BLS12-381. A
+//! signature under the private key `a`, with public key `A = [a] B`, is
+//! `(R, S)` where `R = [r] B` for a deterministic nonce `r` and
+//! `S = r + c a` modulo the order of `B`. The challenge `c` is the Poseidon
+//! hash of the coordinates of `R` and `A` and of `m`, taken as a 255-bit
+//! integer so that circuits need not reduce it. A signature is valid when
+//! `[S] B = R + [c] A`.
+
+use blake2b_simd::Params as Blake2bParams;
+use ff::{PrimeField, PrimeFieldRepr};
+use rand_core::RngCore;
+
+use crate::bls::{Bls12, Fr};
+use crate::gadgets::nonnative::Nat;
+use crate::jubjub::{generator, Point, SUBGROUP_ORDER};
+use crate::poseidon::{poseidon, PoseidonConstants};
+
+/// The base point and challenge hash of a signature scheme.
+pub struct EddsaParams {
+    base: Point,
+    hash: PoseidonConstants<Bls12>,
+}
+
+impl EddsaParams {
+    /// Uses the first generator under the 8 byte `personalization` as the
+    /// base point.
+    pub fn new(personalization: &[u8]) -> Self {
+        EddsaParams {
+            base: generator(personalization, 0),
+            hash: PoseidonConstants::new(5),
+        }
+    }
+
+    pub fn base(&self) -> &Point {
+        &self.base
+    }
+
+    pub fn hash_constants(&self) -> &PoseidonConstants<Bls12> {
+        &self.hash
+    }
+
+    /// The challenge of a signature with nonce commitment `r` under the
+    /// public key `a`.
+    pub fn challenge(&self, r: &Point, a: &Point, message: &Fr) -> Fr {
+        let (rx, ry) = r.get_xy();
+        let (ax, ay) = a.get_xy();
+        poseidon(&self.hash, &[rx, ry, ax, ay, *message])
+    }
+}
+
+fn subgroup_order() -> Nat {
+    nat_from_bytes_le(&SUBGROUP_ORDER)
+}
+
+fn nat_from_bytes_le(bytes: &[u8]) -> Nat {
+    let mut bytes = bytes.to_vec();
+    bytes.reverse();
+    Nat::from_bytes_be(&bytes)
+}
+
+fn nat_to_bytes_le(nat: &Nat) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&nat.to_bytes_be(32));
+    bytes.reverse();
+    bytes
+}
+
+fn fr_to_nat(fr: &Fr) -> Nat {
+    let mut bytes = [0u8; 32];
+    fr.into_repr()
+        .write_le(&mut bytes[..])
+        .expect("32 bytes hold the repr");
+    nat_from_bytes_le(&bytes)
+}
+
+/// A private key, a nonzero scalar less than the order of the base.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateKey(Nat);
+
+impl PrivateKey {
+    pub fn random<R: RngCore>(rng: &mut R) -> Self {
+        loop {
+            let mut bytes = [0u8; 64];
+            rng.fill_bytes(&mut bytes);
+            let (_, scalar) = Nat::from_bytes_be(&bytes).div_rem(&subgroup_order());
+            if !scalar.is_zero() {
+                return PrivateKey(scalar);
+            }
+        }
+    }
+
+    /// Reads a little-endian scalar, which must be nonzero and less than the
+    /// order of the base.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        let scalar = nat_from_bytes_le(bytes);
+        if scalar.is_zero() || scalar >= subgroup_order() {
+            None
+        } else {
+            Some(PrivateKey(scalar))
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        nat_to_bytes_le(&self.0)
+    }
+
+    pub fn public_key(&self, params: &EddsaParams) -> PublicKey {
+        PublicKey(params.base.mul(&self.to_bytes()))
+    }
+
+    /// Signs `message`, with a nonce derived from the key and the message.
+    pub fn sign(&self, params: &EddsaParams, message: &Fr) -> Signature {
+        let order = subgroup_order();
+
+        let mut message_bytes = [0u8; 32];
+        message
+            .into_repr()
+            .write_le(&mut message_bytes[..])
+            .expect("32 bytes hold the repr");
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(b"Jubjub_EdDSA_Nnc")
+            .to_state()
+            .update(&self.to_bytes())
+            .update(&message_bytes)
+            .finalize();
+        let (_, nonce) = Nat::from_bytes_be(hash.as_bytes()).div_rem(&order);
+
+        let r = params.base.mul(&nat_to_bytes_le(&nonce));
+        let public_key = self.public_key(params);
+        let c = fr_to_nat(&params.challenge(&r, &public_key.0, message));
+
+        let (_, s) = (&nonce + &(&c * &self.0)).div_rem(&order);
+
+        Signature {
+            r: r.to_bytes(),
+            s: nat_to_bytes_le(&s),
+        }
+    }
+}
+
+/// A public key `A = [a] B`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(pub Point);
+
+impl PublicKey {
+    /// Whether `signature` is a valid signature of `message` under this key,
+    /// which must not have small order.
+    pub fn verify(&self, params: &EddsaParams, message: &Fr, signature: &Signature) -> bool {
+        if self.0.mul_by_cofactor().is_identity() {
+            return false;
+        }
+
+        let r = match Point::from_bytes(&signature.r) {
+            Some(r) => r,
+            None => return false,
+        };
+        if nat_from_bytes_le(&signature.s) >= subgroup_order() {
+            return false;
+        }
+
+        let c = params.challenge(&r, &self.0, message);
+        let mut c_bytes = [0u8; 32];
+        c.into_repr()
+            .write_le(&mut c_bytes[..])
+            .expect("32 bytes hold the repr");
+
+        params.base.mul(&signature.s) == r.add(&self.0.mul(&c_bytes))
+    }
+}
+
+/// A signature: the encoding of the nonce commitment `R` and the
+/// little-endian scalar `S`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+impl Signature {
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..].copy_from_slice(&self.s);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; 64]) -> Self {
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..]);
+        Signature { r, s }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::Field;
+
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = test_rng();
+        let params = EddsaParams::new(b"Test_EdD");
+
+        for _ in 0..5 {
+            let sk = PrivateKey::random(&mut rng);
+            let pk = sk.public_key(&params);
+            let message = Fr::random(&mut rng);
+
+            let signature = sk.sign(&params, &message);
+            assert!(pk.verify(&params, &message, &signature));
+            assert_eq!(sk.sign(&params, &message), signature);
+            assert_eq!(Signature::from_bytes(&signature.to_bytes()), signature);
+            assert_eq!(PrivateKey::from_bytes(&sk.to_bytes()), Some(sk.clone()));
+
+            // Another message, key or signature does not verify.
+            assert!(!pk.verify(&params, &Fr::random(&mut rng), &signature));
+            let other = PrivateKey::random(&mut rng).public_key(&params);
+            assert!(!other.verify(&params, &message, &signature));
+            let mut tampered = signature;
+            tampered.s[0] ^= 1;
+            assert!(!pk.verify(&params, &message, &tampered));
+
+            // S + r_J gives the same point, but is not canonical.
+            let mut malleated = signature;
+            malleated.s = nat_to_bytes_le(&(&nat_from_bytes_le(&signature.s) + &subgroup_order()));
+            assert!(!pk.verify(&params, &message, &malleated));
+        }
+    }
+
+    #[test]
+    fn test_private_key_range() {
+        assert!(PrivateKey::from_bytes(&[0; 32]).is_none());
+        assert!(PrivateKey::from_bytes(&SUBGROUP_ORDER).is_none());
+
+        let mut bytes = SUBGROUP_ORDER;
+        bytes[0] -= 1;
+        assert!(PrivateKey::from_bytes(&bytes).is_some());
+    }
+}
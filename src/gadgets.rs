@@ -1,15 +1,27 @@
 //! Self-contained sub-circuit implementations for various primitives.
 
-pub mod test;
-
 pub mod blake2s;
 pub mod boolean;
+pub mod ecdsa;
+pub mod eddsa;
+pub mod jubjub;
+pub mod keccak256;
 pub mod lookup;
+pub mod merkle;
+pub mod mimc;
 pub mod multieq;
 pub mod multipack;
+pub mod mux;
+pub mod nonnative;
 pub mod num;
-pub mod sha256;
+pub mod pedersen_hash;
+pub mod permutation;
+pub mod poseidon;
+pub mod range;
+#[macro_use]
+mod uint;
 pub mod uint32;
+pub mod uint64;
 
 use crate::SynthesisError;
 
@@ -0,0 +1,446 @@
+//! Circuit verifying the secp256k1 ECDSA signatures of the native
+//! `crate::secp256k1` module, with the arithmetic of `gadgets::nonnative`.
+//!
+//! Points are affine and added with the incomplete formulas, which are
+//! enforced to never see two points of the same abscissa. The double scalar
+//! multiplication `u1 G + u2 Q` is a single double-and-add over the bits of
+//! both scalars. It starts from a fixed offset point so that the accumulator
+//! is never the point at infinity, and subtracts the multiple of the offset
+//! at the end. An honest prover hits an exceptional case with negligible
+//! probability.
+//!
+//! With four 64-bit limbs, a multiplication costs 1092 constraints and an
+//! addition or subtraction 671. A point doubling costs 9736 constraints and
+//! an addition 9069, so a verification costs about 19000 constraints per bit
+//! of the scalars, or 4.8 million.
+
+use ff::ScalarEngine;
+
+use super::boolean::Boolean;
+use super::nonnative::{AllocatedNonNative, Nat, NonNativeParams};
+use crate::secp256k1::{self, Point};
+use crate::{ConstraintSystem, SynthesisError};
+
+/// A point of secp256k1 other than the point at infinity, allocated in the
+/// constraint system.
+pub struct AllocatedPoint<E: ScalarEngine> {
+    x: AllocatedNonNative<E>,
+    y: AllocatedNonNative<E>,
+}
+
+impl<E: ScalarEngine> Clone for AllocatedPoint<E> {
+    fn clone(&self) -> Self {
+        AllocatedPoint {
+            x: self.x.clone(),
+            y: self.y.clone(),
+        }
+    }
+}
+
+/// Allocates the inverse of `a` and enforces that it is one, which also
+/// enforces that `a` is not zero.
+fn alloc_inverse<E, CS>(
+    mut cs: CS,
+    a: &AllocatedNonNative<E>,
+) -> Result<AllocatedNonNative<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    let params = a.params();
+    let inverse = a
+        .get_value()
+        .map(|a| {
+            a.inv_mod(params.modulus())
+                .ok_or(SynthesisError::Unsatisfiable)
+        })
+        .transpose()?;
+    let inverse = AllocatedNonNative::alloc(cs.namespace(|| "inverse"), params, inverse)?;
+
+    let product = a.mul(cs.namespace(|| "product"), &inverse)?;
+    let one =
+        AllocatedNonNative::alloc_constant(cs.namespace(|| "one"), params, &Nat::from_u64(1))?;
+    product.enforce_equal(cs.namespace(|| "product is one"), &one)?;
+
+    Ok(inverse)
+}
+
+impl<E: ScalarEngine> AllocatedPoint<E> {
+    /// Allocates a point and enforces that it is on the curve.
+    pub fn alloc<CS>(mut cs: CS, value: Option<&Point>) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let params = NonNativeParams::secp256k1_base::<E>();
+        let x =
+            AllocatedNonNative::alloc(cs.namespace(|| "x"), &params, value.map(|p| p.x().clone()))?;
+        let y =
+            AllocatedNonNative::alloc(cs.namespace(|| "y"), &params, value.map(|p| p.y().clone()))?;
+
+        // y^2 = x^3 + 7
+        let y2 = y.mul(cs.namespace(|| "y^2"), &y)?;
+        let x2 = x.mul(cs.namespace(|| "x^2"), &x)?;
+        let x3 = x2.mul(cs.namespace(|| "x^3"), &x)?;
+        let seven = AllocatedNonNative::alloc_constant(
+            cs.namespace(|| "seven"),
+            &params,
+            &Nat::from_u64(7),
+        )?;
+        let rhs = x3.add(cs.namespace(|| "x^3 + 7"), &seven)?;
+        y2.enforce_equal(cs.namespace(|| "on curve check"), &rhs)?;
+
+        Ok(AllocatedPoint { x, y })
+    }
+
+    /// Allocates the constant `value`.
+    pub fn alloc_constant<CS>(mut cs: CS, value: &Point) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let params = NonNativeParams::secp256k1_base::<E>();
+
+        Ok(AllocatedPoint {
+            x: AllocatedNonNative::alloc_constant(cs.namespace(|| "x"), &params, value.x())?,
+            y: AllocatedNonNative::alloc_constant(cs.namespace(|| "y"), &params, value.y())?,
+        })
+    }
+
+    pub fn get_x(&self) -> &AllocatedNonNative<E> {
+        &self.x
+    }
+
+    pub fn get_y(&self) -> &AllocatedNonNative<E> {
+        &self.y
+    }
+
+    pub fn get_value(&self) -> Option<Point> {
+        let p = secp256k1::base_modulus();
+        match (self.x.get_value(), self.y.get_value()) {
+            (Some(x), Some(y)) => Point::from_xy(x.div_rem(&p).1, y.div_rem(&p).1),
+            _ => None,
+        }
+    }
+
+    /// Adds a point of another abscissa, which is enforced.
+    pub fn add<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        // lambda = (y2 - y1) / (x2 - x1)
+        let dx = other.x.sub(cs.namespace(|| "x2 - x1"), &self.x)?;
+        let dy = other.y.sub(cs.namespace(|| "y2 - y1"), &self.y)?;
+        let inverse = alloc_inverse(cs.namespace(|| "1 / (x2 - x1)"), &dx)?;
+        let lambda = dy.mul(cs.namespace(|| "lambda"), &inverse)?;
+
+        self.with_slope(cs, &lambda, &other.x)
+    }
+
+    pub fn double<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        // lambda = 3 x^2 / 2 y. The curve has no point of order two, so y is
+        // not zero and lambda is unique.
+        let x2 = self.x.mul(cs.namespace(|| "x^2"), &self.x)?;
+        let two_x2 = x2.add(cs.namespace(|| "2 x^2"), &x2)?;
+        let three_x2 = two_x2.add(cs.namespace(|| "3 x^2"), &x2)?;
+        let two_y = self.y.add(cs.namespace(|| "2 y"), &self.y)?;
+
+        let params = self.x.params();
+        let lambda = match (three_x2.get_value(), two_y.get_value()) {
+            (Some(num), Some(den)) => {
+                let inverse = den
+                    .inv_mod(params.modulus())
+                    .ok_or(SynthesisError::Unsatisfiable)?;
+                Some((num * &inverse).div_rem(params.modulus()).1)
+            }
+            _ => None,
+        };
+        let lambda = AllocatedNonNative::alloc(cs.namespace(|| "lambda"), params, lambda)?;
+        let product = lambda.mul(cs.namespace(|| "lambda 2 y"), &two_y)?;
+        product.enforce_equal(cs.namespace(|| "lambda check"), &three_x2)?;
+
+        self.with_slope(cs, &lambda, &self.x)
+    }
+
+    /// The sum of this point and the point of abscissa `other_x` on the line
+    /// through it of slope `lambda`.
+    fn with_slope<CS>(
+        &self,
+        mut cs: CS,
+        lambda: &AllocatedNonNative<E>,
+        other_x: &AllocatedNonNative<E>,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        // x3 = lambda^2 - x1 - x2
+        let lambda2 = lambda.mul(cs.namespace(|| "lambda^2"), lambda)?;
+        let tmp = lambda2.sub(cs.namespace(|| "lambda^2 - x1"), &self.x)?;
+        let x = tmp.sub(cs.namespace(|| "x3"), other_x)?;
+
+        // y3 = lambda (x1 - x3) - y1
+        let dx = self.x.sub(cs.namespace(|| "x1 - x3"), &x)?;
+        let tmp = lambda.mul(cs.namespace(|| "lambda (x1 - x3)"), &dx)?;
+        let y = tmp.sub(cs.namespace(|| "y3"), &self.y)?;
+
+        Ok(AllocatedPoint { x, y })
+    }
+
+    /// Returns `if_true` if `condition` is set and `if_false` otherwise.
+    pub fn select<CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        if_true: &Self,
+        if_false: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        Ok(AllocatedPoint {
+            x: AllocatedNonNative::select(
+                cs.namespace(|| "x"),
+                condition,
+                &if_true.x,
+                &if_false.x,
+            )?,
+            y: AllocatedNonNative::select(
+                cs.namespace(|| "y"),
+                condition,
+                &if_true.y,
+                &if_false.y,
+            )?,
+        })
+    }
+}
+
+/// The offset point of the scalar multiplication: the point of abscissa 1
+/// with an even ordinate.
+fn offset_point() -> Point {
+    Point::from_xy(
+        Nat::from_u64(1),
+        Nat::from_bytes_be(&[
+            0x42, 0x18, 0xf2, 0x0a, 0xe6, 0xc6, 0x46, 0xb3, 0x63, 0xdb, 0x68, 0x60, 0x58, 0x22,
+            0xfb, 0x14, 0x26, 0x4c, 0xa8, 0xd2, 0x58, 0x7f, 0xdd, 0x6f, 0xbc, 0x75, 0x0d, 0x58,
+            0x7e, 0x76, 0xa7, 0xee,
+        ]),
+    )
+    .expect("the offset point is on the curve")
+}
+
+/// Computes `[a] G + [b] q` for little-endian bits `a` and `b` of the same
+/// length, with the offset point.
+pub fn double_base_multiplication<E, CS>(
+    mut cs: CS,
+    a: &[Boolean],
+    q: &AllocatedPoint<E>,
+    b: &[Boolean],
+) -> Result<AllocatedPoint<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(a.len(), b.len());
+
+    let offset = offset_point();
+    let g = AllocatedPoint::alloc_constant(cs.namespace(|| "g"), &Point::generator())?;
+    let gq = g.add(cs.namespace(|| "g + q"), q)?;
+
+    let mut acc = AllocatedPoint::alloc_constant(cs.namespace(|| "offset"), &offset)?;
+    for (i, (a, b)) in a.iter().zip(b).enumerate().rev() {
+        let mut cs = cs.namespace(|| format!("bit {}", i));
+
+        acc = acc.double(cs.namespace(|| "double"))?;
+
+        // G, Q or G + Q, and G when neither bit is set.
+        let single = AllocatedPoint::select(cs.namespace(|| "g or q"), b, q, &g)?;
+        let both = Boolean::and(cs.namespace(|| "both"), a, b)?;
+        let addend = AllocatedPoint::select(cs.namespace(|| "addend"), &both, &gq, &single)?;
+
+        let sum = acc.add(cs.namespace(|| "add"), &addend)?;
+        let any = Boolean::or(cs.namespace(|| "any"), a, b)?;
+        acc = AllocatedPoint::select(cs.namespace(|| "result"), &any, &sum, &acc)?;
+    }
+
+    // Subtract 2^len times the offset.
+    let shifted_offset = offset
+        .mul(&(&Nat::from_u64(1) << a.len()))
+        .expect("the offset has prime order")
+        .negate();
+    let shifted_offset =
+        AllocatedPoint::alloc_constant(cs.namespace(|| "shifted offset"), &shifted_offset)?;
+
+    acc.add(cs.namespace(|| "remove offset"), &shifted_offset)
+}
+
+/// Enforces that `(r, s)` is a valid signature of the message hash `z`
+/// under `public_key`, as `secp256k1::verify` checks natively. `z`, `r` and
+/// `s` must be numbers modulo the order of the curve, and `r` and `s` must
+/// be less than it, which is enforced here.
+pub fn verify<E, CS>(
+    mut cs: CS,
+    public_key: &AllocatedPoint<E>,
+    z: &AllocatedNonNative<E>,
+    r: &AllocatedNonNative<E>,
+    s: &AllocatedNonNative<E>,
+) -> Result<(), SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(z.params().modulus(), &secp256k1::scalar_modulus());
+
+    // Otherwise r + n would verify too.
+    r.enforce_canonical(cs.namespace(|| "canonical r"))?;
+    s.enforce_canonical(cs.namespace(|| "canonical s"))?;
+
+    // r and s are not zero, and u1 = z / s, u2 = r / s.
+    alloc_inverse(cs.namespace(|| "r is not zero"), r)?;
+    let w = alloc_inverse(cs.namespace(|| "1 / s"), s)?;
+    let u1 = z.mul(cs.namespace(|| "u1"), &w)?;
+    let u2 = r.mul(cs.namespace(|| "u2"), &w)?;
+
+    let u1_bits = u1.to_bits_le(cs.namespace(|| "u1 bits"))?;
+    let u2_bits = u2.to_bits_le(cs.namespace(|| "u2 bits"))?;
+    let point = double_base_multiplication(
+        cs.namespace(|| "u1 g + u2 q"),
+        &u1_bits,
+        public_key,
+        &u2_bits,
+    )?;
+
+    // The canonical abscissa is congruent to r modulo the order.
+    point.x.enforce_canonical(cs.namespace(|| "canonical x"))?;
+    point
+        .x
+        .with_params(z.params())
+        .enforce_equal(cs.namespace(|| "x is r"), r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use ff::Field;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_point_arithmetic() {
+        let g = Point::generator();
+        let g2 = g.double().unwrap();
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let a = AllocatedPoint::alloc(cs.namespace(|| "a"), Some(&g)).unwrap();
+        let b = AllocatedPoint::alloc(cs.namespace(|| "b"), Some(&g2)).unwrap();
+
+        let start = cs.num_constraints();
+        let double = a.double(cs.namespace(|| "2a")).unwrap();
+        assert_eq!(cs.num_constraints() - start, 9736);
+
+        let start = cs.num_constraints();
+        let sum = a.add(cs.namespace(|| "a + b"), &b).unwrap();
+        assert_eq!(cs.num_constraints() - start, 9069);
+
+        assert!(cs.is_satisfied());
+        assert_eq!(double.get_value(), Some(g2.clone()));
+        assert_eq!(sum.get_value(), g2.add(&g));
+
+        // Adding a point to itself is not satisfiable.
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let a = AllocatedPoint::alloc(cs.namespace(|| "a"), Some(&g)).unwrap();
+        assert!(a.add(cs.namespace(|| "a + a"), &a).is_err());
+
+        // Neither is a point off the curve.
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        AllocatedPoint::alloc(cs.namespace(|| "a"), Some(&g)).unwrap();
+        cs.set("a/y/limb 0/value/num", Fr::zero());
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_double_base_multiplication() {
+        let g = Point::generator();
+        let q = g.mul(&Nat::from_u64(12345)).unwrap();
+
+        for &(a, b) in &[(0b1011u8, 0b0110u8), (0b1111, 0b0001), (0b0001, 0b1000)] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let q_num = AllocatedPoint::alloc(cs.namespace(|| "q"), Some(&q)).unwrap();
+            let bits = |x: u8| {
+                (0..4)
+                    .map(|i| Boolean::constant((x >> i) & 1 == 1))
+                    .collect::<Vec<_>>()
+            };
+
+            let result = double_base_multiplication(
+                cs.namespace(|| "multiplication"),
+                &bits(a),
+                &q_num,
+                &bits(b),
+            )
+            .unwrap();
+
+            assert!(cs.is_satisfied());
+            let expected = g
+                .mul(&Nat::from_u64(u64::from(a)))
+                .unwrap()
+                .add(&q.mul(&Nat::from_u64(u64::from(b))).unwrap());
+            assert_eq!(result.get_value(), expected);
+        }
+    }
+
+    // Synthesizes several million constraints.
+    #[test]
+    #[ignore]
+    fn test_verify() {
+        let q = Point::from_xy(
+            Nat::from_bytes_be(&hex!(
+                "2c8c31fc9f990c6b55e3865a184a4ce50e09481f2eaeb3e60ec1cea13a6ae645"
+            )),
+            Nat::from_bytes_be(&hex!(
+                "64b95e4fdb6948c0386e189b006a29f686769b011704275e4459822dc3328085"
+            )),
+        )
+        .unwrap();
+        let z = Nat::from_bytes_be(&hex!(
+            "af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf"
+        ));
+        let r = Nat::from_bytes_be(&hex!(
+            "432310e32cb80eb6503a26ce83cc165c783b870845fb8aad6d970889fcd7a6c8"
+        ));
+        let s = Nat::from_bytes_be(&hex!(
+            "530128b6b81c548874a6305d93ed071ca6e05074d85863d4056ce89b02bfab69"
+        ));
+
+        let params = NonNativeParams::secp256k1_scalar::<Bls12>();
+        for (message, valid) in vec![(z.clone(), true), (&z + &Nat::from_u64(1), false)] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let public_key = AllocatedPoint::alloc(cs.namespace(|| "q"), Some(&q)).unwrap();
+            let z =
+                AllocatedNonNative::alloc(cs.namespace(|| "z"), &params, Some(message)).unwrap();
+            let r =
+                AllocatedNonNative::alloc(cs.namespace(|| "r"), &params, Some(r.clone())).unwrap();
+            let s =
+                AllocatedNonNative::alloc(cs.namespace(|| "s"), &params, Some(s.clone())).unwrap();
+
+            verify(cs.namespace(|| "verify"), &public_key, &z, &r, &s).unwrap();
+            assert_eq!(cs.is_satisfied(), valid);
+        }
+
+        // r and s must be less than the order.
+        let n = secp256k1::scalar_modulus();
+        for (r, s) in vec![(&n + &Nat::from_u64(1), s.clone()), (r, n)] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let public_key = AllocatedPoint::alloc(cs.namespace(|| "q"), Some(&q)).unwrap();
+            let z =
+                AllocatedNonNative::alloc(cs.namespace(|| "z"), &params, Some(z.clone())).unwrap();
+            let r = AllocatedNonNative::alloc(cs.namespace(|| "r"), &params, Some(r)).unwrap();
+            let s = AllocatedNonNative::alloc(cs.namespace(|| "s"), &params, Some(s)).unwrap();
+
+            match verify(cs.namespace(|| "verify"), &public_key, &z, &r, &s).err() {
+                Some(SynthesisError::Unsatisfiable) => {}
+                _ => panic!("expected a non-canonical signature to be rejected"),
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Circuit verifying the signatures of the native `crate::eddsa` module.
+
+use ff::{Field, PrimeField, PrimeFieldRepr};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::jubjub::{fixed_base_multiplication, EdwardsPoint};
+use super::num::AllocatedNum;
+use super::poseidon::poseidon;
+use super::range::decompose;
+use crate::bls::{Bls12, Fr, FrRepr};
+use crate::eddsa::{EddsaParams, Signature};
+use crate::jubjub::{bytes_to_bits_le, SUBGROUP_ORDER};
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+/// Number of bits of `S`, which is less than the order of the base.
+pub const S_BITS: usize = 252;
+
+/// Allocates the 256 bits of the encoding of `R` and the [`S_BITS`] bits of
+/// `S`, little-endian.
+pub fn alloc_signature<CS>(
+    mut cs: CS,
+    signature: Option<&Signature>,
+) -> Result<(Vec<Boolean>, Vec<Boolean>), SynthesisError>
+where
+    CS: ConstraintSystem<Bls12>,
+{
+    let mut alloc_bits = |name: &str, bytes: Option<&[u8; 32]>, n: usize| {
+        let values = match bytes {
+            Some(bytes) => bytes_to_bits_le(bytes).take(n).map(Some).collect(),
+            None => vec![None; n],
+        };
+
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, b)| {
+                Ok(Boolean::from(AllocatedBit::alloc(
+                    cs.namespace(|| format!("{} bit {}", name, i)),
+                    b,
+                )?))
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()
+    };
+
+    let r = alloc_bits("r", signature.map(|s| &s.r), 256)?;
+    let s = alloc_bits("s", signature.map(|s| &s.s), S_BITS)?;
+
+    Ok((r, s))
+}
+
+/// Enforces that the [`S_BITS`] little-endian `s_bits` encode a scalar less
+/// than the order of the base, with `S_BITS + 1` constraints.
+pub fn assert_canonical_s<CS>(cs: CS, s_bits: &[Boolean]) -> Result<(), SynthesisError>
+where
+    CS: ConstraintSystem<Bls12>,
+{
+    assert_eq!(s_bits.len(), S_BITS);
+
+    let mut repr = FrRepr::default();
+    repr.read_le(&SUBGROUP_ORDER[..])
+        .expect("32 bytes fill the repr");
+    let mut max = Fr::from_repr(repr).expect("the order of the base is less than the modulus");
+    max.sub_assign(&Fr::one());
+
+    // r_J - 1 - S fits in S_BITS bits when S < r_J, and wraps around the
+    // modulus to a much larger number otherwise.
+    let mut lc = LinearCombination::zero() + (max, CS::one());
+    let mut value = Some(max);
+    let mut coeff = Fr::one();
+    for bit in s_bits {
+        lc = lc - &bit.lc(CS::one(), coeff);
+        value = match (value, bit.get_value()) {
+            (Some(mut value), Some(b)) => {
+                if b {
+                    value.sub_assign(&coeff);
+                }
+                Some(value)
+            }
+            _ => None,
+        };
+        coeff.double();
+    }
+
+    decompose(cs, lc, value, S_BITS)?;
+
+    Ok(())
+}
+
+/// Enforces that `r_bits` and `s_bits` encode a signature of `message` under
+/// `public_key`, as `PublicKey::verify` checks natively: the encoding of `R`
+/// and `S` are canonical, and the public key does not have small order.
+pub fn verify<CS>(
+    mut cs: CS,
+    params: &EddsaParams,
+    public_key: &EdwardsPoint,
+    message: &AllocatedNum<Bls12>,
+    r_bits: &[Boolean],
+    s_bits: &[Boolean],
+) -> Result<(), SynthesisError>
+where
+    CS: ConstraintSystem<Bls12>,
+{
+    public_key.assert_not_small_order(cs.namespace(|| "public key order"))?;
+
+    let r = EdwardsPoint::decompress(cs.namespace(|| "decompress r"), r_bits)?;
+    assert_canonical_s(cs.namespace(|| "canonical s"), s_bits)?;
+
+    let c = poseidon(
+        cs.namespace(|| "challenge"),
+        params.hash_constants(),
+        &[
+            r.get_x().clone(),
+            r.get_y().clone(),
+            public_key.get_x().clone(),
+            public_key.get_y().clone(),
+            message.clone(),
+        ],
+    )?;
+    let c_bits = c.to_bits_le_strict(cs.namespace(|| "challenge bits"))?;
+
+    // [S] B = R + [c] A
+    let lhs = fixed_base_multiplication(cs.namespace(|| "[s] b"), params.base(), s_bits)?;
+    let ca = public_key.mul(cs.namespace(|| "[c] a"), &c_bits)?;
+    let rhs = r.add(cs.namespace(|| "r + [c] a"), &ca)?;
+
+    cs.enforce(
+        || "x equality",
+        |lc| lc + lhs.get_x().get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + rhs.get_x().get_variable(),
+    );
+    cs.enforce(
+        || "y equality",
+        |lc| lc + lhs.get_y().get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + rhs.get_y().get_variable(),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eddsa::PrivateKey;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_verify() {
+        let mut rng = test_rng();
+        let params = EddsaParams::new(b"Test_EdD");
+
+        let sk = PrivateKey::random(&mut rng);
+        let pk = sk.public_key(&params);
+        let message = Fr::random(&mut rng);
+        let signature = sk.sign(&params, &message);
+
+        for &(signed, valid) in &[(message, true), (Fr::random(&mut rng), false)] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+
+            let public_key = EdwardsPoint::witness(cs.namespace(|| "pk"), Some(pk.0)).unwrap();
+            let message = AllocatedNum::alloc(cs.namespace(|| "message"), || Ok(signed)).unwrap();
+            let (r_bits, s_bits) =
+                alloc_signature(cs.namespace(|| "signature"), Some(&signature)).unwrap();
+
+            verify(
+                cs.namespace(|| "verify"),
+                &params,
+                &public_key,
+                &message,
+                &r_bits,
+                &s_bits,
+            )
+            .unwrap();
+
+            assert_eq!(cs.is_satisfied(), valid);
+        }
+    }
+
+    #[test]
+    fn test_canonical_s() {
+        for &(sub, valid) in &[(1u8, true), (0, false)] {
+            // r_J - 1 and r_J, whose low byte is not zero.
+            let mut bytes = SUBGROUP_ORDER;
+            bytes[0] -= sub;
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let s_bits = bytes_to_bits_le(&bytes)
+                .take(S_BITS)
+                .enumerate()
+                .map(|(i, b)| {
+                    Boolean::from(
+                        AllocatedBit::alloc(cs.namespace(|| format!("s bit {}", i)), Some(b))
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_canonical_s(cs.namespace(|| "canonical s"), &s_bits).unwrap();
+
+            assert_eq!(cs.is_satisfied(), valid);
+            assert_eq!(cs.num_constraints(), 2 * S_BITS + 1);
+        }
+    }
+}
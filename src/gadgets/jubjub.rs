@@ -0,0 +1,542 @@
+//! Gadgets for the Jubjub curve of the native `crate::jubjub` module, whose
+//! coordinates are elements of the scalar field of BLS12-381.
+
+use ff::Field;
+
+use super::boolean::Boolean;
+use super::lookup::lookup3_xy;
+use super::num::AllocatedNum;
+use super::Assignment;
+use crate::bls::{Bls12, Fr};
+use crate::jubjub::{edwards_d, Point};
+use crate::{ConstraintSystem, SynthesisError};
+
+/// A point of the curve in affine Edwards coordinates, allocated in the
+/// constraint system.
+#[derive(Clone)]
+pub struct EdwardsPoint {
+    x: AllocatedNum<Bls12>,
+    y: AllocatedNum<Bls12>,
+}
+
+impl EdwardsPoint {
+    pub fn get_x(&self) -> &AllocatedNum<Bls12> {
+        &self.x
+    }
+
+    pub fn get_y(&self) -> &AllocatedNum<Bls12> {
+        &self.y
+    }
+
+    pub fn get_value(&self) -> Option<Point> {
+        match (self.x.get_value(), self.y.get_value()) {
+            (Some(x), Some(y)) => Some(Point::from_xy_unchecked(x, y)),
+            _ => None,
+        }
+    }
+
+    /// Allocates a point and constrains it to be on the curve, with four
+    /// constraints. The point is not checked to be in the prime order
+    /// subgroup.
+    pub fn witness<CS>(mut cs: CS, p: Option<Point>) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        let xy = p.map(|p| p.get_xy());
+        let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(xy.get()?.0))?;
+        let y = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(xy.get()?.1))?;
+
+        Self::interpret(cs, &x, &y)
+    }
+
+    /// Allocates the identity `(0, 1)`, with two constraints fixing its
+    /// coordinates.
+    pub fn identity<CS>(mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(Fr::zero()))?;
+        let y = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(Fr::one()))?;
+
+        let one = CS::one();
+        cs.enforce(
+            || "x = 0",
+            |lc| lc + x.get_variable(),
+            |lc| lc + one,
+            |lc| lc,
+        );
+        cs.enforce(
+            || "y = 1",
+            |lc| lc + y.get_variable(),
+            |lc| lc + one,
+            |lc| lc + one,
+        );
+
+        Ok(EdwardsPoint { x, y })
+    }
+
+    /// Interprets `(x, y)` as a point, constraining it to be on the curve.
+    pub fn interpret<CS>(
+        mut cs: CS,
+        x: &AllocatedNum<Bls12>,
+        y: &AllocatedNum<Bls12>,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        // -x^2 + y^2 = 1 + d x^2 y^2
+        let x2 = x.mul(cs.namespace(|| "x^2"), x)?;
+        let y2 = y.mul(cs.namespace(|| "y^2"), y)?;
+        let x2y2 = x2.mul(cs.namespace(|| "x^2 y^2"), &y2)?;
+
+        let one = CS::one();
+        cs.enforce(
+            || "on curve check",
+            |lc| lc + y2.get_variable() - x2.get_variable(),
+            |lc| lc + one,
+            |lc| lc + one + (edwards_d(), x2y2.get_variable()),
+        );
+
+        Ok(EdwardsPoint {
+            x: x.clone(),
+            y: y.clone(),
+        })
+    }
+
+    /// Makes the coordinates public inputs of the circuit.
+    pub fn inputize<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        self.x.inputize(cs.namespace(|| "x"))?;
+        self.y.inputize(cs.namespace(|| "y"))?;
+
+        Ok(())
+    }
+
+    /// Adds two points with seven constraints. The addition law is complete,
+    /// so it also doubles and handles the identity.
+    pub fn add<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        // x3 = (x1 y2 + y1 x2) / (1 + d x1 x2 y1 y2)
+        // y3 = (y1 y2 + x1 x2) / (1 - d x1 x2 y1 y2)
+        let x1y2 = self.x.mul(cs.namespace(|| "x1 y2"), &other.y)?;
+        let y1x2 = self.y.mul(cs.namespace(|| "y1 x2"), &other.x)?;
+        let y1y2 = self.y.mul(cs.namespace(|| "y1 y2"), &other.y)?;
+        let x1x2 = self.x.mul(cs.namespace(|| "x1 x2"), &other.x)?;
+        let t = x1x2.mul(cs.namespace(|| "x1 x2 y1 y2"), &y1y2)?;
+
+        let sum = match (self.get_value(), other.get_value()) {
+            (Some(a), Some(b)) => Some(a.add(&b).get_xy()),
+            _ => None,
+        };
+
+        let x = AllocatedNum::alloc(cs.namespace(|| "x3"), || Ok(sum.get()?.0))?;
+        let y = AllocatedNum::alloc(cs.namespace(|| "y3"), || Ok(sum.get()?.1))?;
+
+        let one = CS::one();
+        let d = edwards_d();
+        let mut neg_d = d;
+        neg_d.negate();
+
+        cs.enforce(
+            || "x3 computation",
+            |lc| lc + x.get_variable(),
+            |lc| lc + one + (d, t.get_variable()),
+            |lc| lc + x1y2.get_variable() + y1x2.get_variable(),
+        );
+        cs.enforce(
+            || "y3 computation",
+            |lc| lc + y.get_variable(),
+            |lc| lc + one + (neg_d, t.get_variable()),
+            |lc| lc + y1y2.get_variable() + x1x2.get_variable(),
+        );
+
+        Ok(EdwardsPoint { x, y })
+    }
+
+    pub fn double<CS>(&self, cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        self.add(cs, self)
+    }
+
+    /// Returns this point if `condition` is true and the identity otherwise,
+    /// with two constraints.
+    pub fn conditionally_select<CS>(
+        &self,
+        mut cs: CS,
+        condition: &Boolean,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        let value = match (self.get_value(), condition.get_value()) {
+            (Some(p), Some(true)) => Some(p.get_xy()),
+            (Some(_), Some(false)) => Some(Point::identity().get_xy()),
+            _ => None,
+        };
+
+        let x = AllocatedNum::alloc(cs.namespace(|| "x'"), || Ok(value.get()?.0))?;
+        let y = AllocatedNum::alloc(cs.namespace(|| "y'"), || Ok(value.get()?.1))?;
+
+        let one = CS::one();
+
+        // x' = x * condition
+        cs.enforce(
+            || "x' computation",
+            |lc| lc + self.x.get_variable(),
+            |_| condition.lc(one, Fr::one()),
+            |lc| lc + x.get_variable(),
+        );
+
+        // y' - 1 = (y - 1) * condition
+        cs.enforce(
+            || "y' computation",
+            |lc| lc + self.y.get_variable() - one,
+            |_| condition.lc(one, Fr::one()),
+            |lc| lc + y.get_variable() - one,
+        );
+
+        Ok(EdwardsPoint { x, y })
+    }
+
+    /// Decompresses the 256 little-endian bits of an encoding of
+    /// `Point::to_bytes`: the low 255 bits are `y`, less than the modulus so
+    /// that every point has a single encoding, and the top one is the parity
+    /// of `x`. Fails with `Unsatisfiable` if the bits do not encode a point.
+    pub fn decompress<CS>(mut cs: CS, bits: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        assert_eq!(bits.len(), 256);
+
+        let one = CS::one();
+
+        let mut y_value = Some(Fr::zero());
+        let mut coeff = Fr::one();
+        for bit in &bits[..255] {
+            y_value = match (y_value, bit.get_value()) {
+                (Some(mut y), Some(b)) => {
+                    if b {
+                        y.add_assign(&coeff);
+                    }
+                    Some(y)
+                }
+                _ => None,
+            };
+            coeff.double();
+        }
+
+        // The canonical bits of y are the given ones, which also packs them
+        // into y.
+        let y = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(*y_value.get()?))?;
+        let y_bits = y.to_bits_le_strict(cs.namespace(|| "y bits"))?;
+        for (i, (a, b)) in y_bits.iter().zip(&bits[..255]).enumerate() {
+            Boolean::enforce_equal(cs.namespace(|| format!("y bit {}", i)), a, b)?;
+        }
+
+        let x_value = match (y_value, bits[255].get_value()) {
+            (Some(y), Some(sign)) => Some(
+                Point::from_y_and_sign(y, sign)
+                    .ok_or(SynthesisError::Unsatisfiable)?
+                    .get_xy()
+                    .0,
+            ),
+            _ => None,
+        };
+        let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(*x_value.get()?))?;
+
+        // x^2 (1 + d y^2) = y^2 - 1
+        let x2 = x.mul(cs.namespace(|| "x^2"), &x)?;
+        let y2 = y.mul(cs.namespace(|| "y^2"), &y)?;
+        cs.enforce(
+            || "on curve check",
+            |lc| lc + x2.get_variable(),
+            |lc| lc + one + (edwards_d(), y2.get_variable()),
+            |lc| lc + y2.get_variable() - one,
+        );
+
+        // The parity of x is the sign bit.
+        let x_bits = x.to_bits_le_strict(cs.namespace(|| "x bits"))?;
+        Boolean::enforce_equal(cs.namespace(|| "sign"), &x_bits[0], &bits[255])?;
+
+        Ok(EdwardsPoint { x, y })
+    }
+
+    /// Enforces that the point does not have small order, with 22
+    /// constraints.
+    pub fn assert_not_small_order<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        let tmp = self.double(cs.namespace(|| "first doubling"))?;
+        let tmp = tmp.double(cs.namespace(|| "second doubling"))?;
+        let tmp = tmp.double(cs.namespace(|| "third doubling"))?;
+
+        // (0, -1) is a small order point, but won't ever appear here
+        // because cofactor is 2^3, and we performed three doublings.
+        // (0, 1) is the neutral element, so checking if x is nonzero
+        // is sufficient to prevent small order points here.
+        tmp.x.assert_nonzero(cs.namespace(|| "check x != 0"))
+    }
+
+    /// Multiplies by a scalar given as little-endian bits, with a double and
+    /// add per bit.
+    pub fn mul<CS>(&self, mut cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        let mut curbase = self.clone();
+        let mut result: Option<Self> = None;
+
+        for (i, bit) in by.iter().enumerate() {
+            if i > 0 {
+                curbase = curbase.double(cs.namespace(|| format!("doubling {}", i)))?;
+            }
+
+            let selected =
+                curbase.conditionally_select(cs.namespace(|| format!("selection {}", i)), bit)?;
+
+            result = Some(match result {
+                Some(result) => {
+                    result.add(cs.namespace(|| format!("addition {}", i)), &selected)?
+                }
+                None => selected,
+            });
+        }
+
+        match result {
+            Some(result) => Ok(result),
+            None => Self::identity(cs.namespace(|| "identity")),
+        }
+    }
+}
+
+/// Multiplies the constant `base` by a scalar given as little-endian bits,
+/// with a table lookup and an addition per window of three bits.
+pub fn fixed_base_multiplication<CS>(
+    mut cs: CS,
+    base: &Point,
+    by: &[Boolean],
+) -> Result<EdwardsPoint, SynthesisError>
+where
+    CS: ConstraintSystem<Bls12>,
+{
+    let mut window_base = *base;
+    let mut result: Option<EdwardsPoint> = None;
+
+    for (i, window) in by.chunks(3).enumerate() {
+        let mut table = Vec::with_capacity(8);
+        let mut multiple = Point::identity();
+        for _ in 0..8 {
+            table.push(multiple.get_xy());
+            multiple = multiple.add(&window_base);
+        }
+
+        let mut bits = window.to_vec();
+        bits.resize(3, Boolean::constant(false));
+
+        let (x, y) = lookup3_xy(
+            cs.namespace(|| format!("window table {}", i)),
+            &bits,
+            &table,
+        )?;
+        let p = EdwardsPoint { x, y };
+
+        result = Some(match result {
+            Some(result) => result.add(cs.namespace(|| format!("addition {}", i)), &p)?,
+            None => p,
+        });
+
+        // 8 * window_base
+        window_base = window_base.double().double().double();
+    }
+
+    match result {
+        Some(result) => Ok(result),
+        None => EdwardsPoint::identity(cs.namespace(|| "identity")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::jubjub::generator;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::{PrimeField, PrimeFieldRepr};
+    use rand_core::RngCore;
+
+    const PERSONALIZATION: &[u8] = b"Test_Jub";
+
+    fn alloc_bits<CS: ConstraintSystem<Bls12>>(mut cs: CS, bits: &[bool]) -> Vec<Boolean> {
+        bits.iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                AllocatedBit::alloc(cs.namespace(|| format!("bit {}", i)), Some(b))
+                    .unwrap()
+                    .into()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decompress() {
+        for i in 0..4 {
+            let g = generator(PERSONALIZATION, i);
+            for p in &[g, g.negate()] {
+                let bytes = p.to_bytes();
+                let bits = crate::jubjub::bytes_to_bits_le(&bytes).collect::<Vec<_>>();
+
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+                let bits = alloc_bits(cs.namespace(|| "bits"), &bits);
+                let point = EdwardsPoint::decompress(cs.namespace(|| "decompress"), &bits).unwrap();
+
+                assert!(cs.is_satisfied());
+                assert_eq!(point.get_value(), Some(*p));
+
+                // The other square root has the other parity.
+                let mut x = p.get_xy().0;
+                x.negate();
+                cs.set("decompress/x/num", x);
+                assert!(!cs.is_satisfied());
+            }
+        }
+
+        // Not every y is on the curve.
+        let bits = (0..256).map(|i| i == 1).collect::<Vec<_>>();
+        assert!(Point::from_y_and_sign(Fr::from_str("2").unwrap(), false).is_none());
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let bits = alloc_bits(cs.namespace(|| "bits"), &bits);
+        assert!(EdwardsPoint::decompress(cs.namespace(|| "decompress"), &bits).is_err());
+
+        // y = 1 + r encodes the identity too, but is not canonical.
+        let mut y = Fr::char();
+        y.add_nocarry(&1.into());
+        let bits = (0..256)
+            .map(|i| i < 255 && (y.as_ref()[i / 64] >> (i % 64)) & 1 == 1)
+            .collect::<Vec<_>>();
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let bits = alloc_bits(cs.namespace(|| "bits"), &bits);
+        let point = EdwardsPoint::decompress(cs.namespace(|| "decompress"), &bits).unwrap();
+        assert_eq!(point.get_value(), Some(Point::identity()));
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_identity() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let p = EdwardsPoint::identity(cs.namespace(|| "identity")).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(p.get_value(), Some(Point::identity()));
+
+        // The identity is fixed, unlike a witnessed point.
+        let g = generator(PERSONALIZATION, 0).get_xy();
+        cs.set("identity/x/num", g.0);
+        cs.set("identity/y/num", g.1);
+        assert!(!cs.is_satisfied());
+
+        // It is the result of multiplying by no bits at all.
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let p = fixed_base_multiplication(cs.namespace(|| "mul"), &Point::identity(), &[]).unwrap();
+        assert_eq!(p.get_value(), Some(Point::identity()));
+        assert_eq!(cs.num_constraints(), 2);
+    }
+
+    #[test]
+    fn test_assert_not_small_order() {
+        let g = generator(PERSONALIZATION, 0);
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let p = EdwardsPoint::witness(cs.namespace(|| "p"), Some(g)).unwrap();
+        p.assert_not_small_order(cs.namespace(|| "check")).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 4 + 22);
+
+        // (0, -1) has order 2.
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        let small = Point::from_xy(Fr::zero(), minus_one).unwrap();
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let p = EdwardsPoint::witness(cs.namespace(|| "p"), Some(small)).unwrap();
+        assert!(p.assert_not_small_order(cs.namespace(|| "check")).is_err());
+    }
+
+    #[test]
+    fn test_witness_and_add() {
+        let g = generator(PERSONALIZATION, 0);
+        let h = generator(PERSONALIZATION, 1);
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let a = EdwardsPoint::witness(cs.namespace(|| "a"), Some(g)).unwrap();
+        let b = EdwardsPoint::witness(cs.namespace(|| "b"), Some(h)).unwrap();
+        assert_eq!(cs.num_constraints(), 8);
+
+        let sum = a.add(cs.namespace(|| "a + b"), &b).unwrap();
+        let double = a.double(cs.namespace(|| "2a")).unwrap();
+        assert_eq!(cs.num_constraints(), 8 + 2 * 7);
+
+        assert!(cs.is_satisfied());
+        assert_eq!(sum.get_value(), Some(g.add(&h)));
+        assert_eq!(double.get_value(), Some(g.double()));
+
+        cs.set("a + b/x3/num", h.get_xy().0);
+        assert!(!cs.is_satisfied());
+
+        // A point off the curve is rejected.
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let (x, y) = g.get_xy();
+        let x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(x)).unwrap();
+        let y = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(y)).unwrap();
+        EdwardsPoint::interpret(&mut cs, &y, &x).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_multiplication() {
+        let mut rng = test_rng();
+        let g = generator(PERSONALIZATION, 0);
+
+        for &len in &[0, 1, 7, 32, 252] {
+            let scalar = (0..len)
+                .map(|_| rng.next_u32() % 2 == 1)
+                .collect::<Vec<_>>();
+            let expected = g.mul_bits(scalar.iter().cloned());
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let bits = alloc_bits(cs.namespace(|| "scalar"), &scalar);
+            let base = EdwardsPoint::witness(cs.namespace(|| "base"), Some(g)).unwrap();
+
+            let variable = base.mul(cs.namespace(|| "variable"), &bits).unwrap();
+            let fixed = fixed_base_multiplication(cs.namespace(|| "fixed"), &g, &bits).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(variable.get_value(), Some(expected));
+            assert_eq!(fixed.get_value(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_conditionally_select() {
+        let g = generator(PERSONALIZATION, 0);
+
+        for &condition in &[false, true] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let p = EdwardsPoint::witness(cs.namespace(|| "p"), Some(g)).unwrap();
+            let bit = AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition)).unwrap();
+
+            let selected = p
+                .conditionally_select(cs.namespace(|| "select"), &bit.into())
+                .unwrap();
+
+            assert!(cs.is_satisfied());
+            let expected = if condition { g } else { Point::identity() };
+            assert_eq!(selected.get_value(), Some(expected));
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! The [Keccak-256] hash function as used by Ethereum. It pads with the
+//! original Keccak rule, so its digests differ from those of SHA3-256.
+//!
+//! [Keccak-256]: https://keccak.team/keccak_specs_summary.html
+
+use ff::ScalarEngine;
+
+use super::{boolean::Boolean, uint64::UInt64};
+use crate::{ConstraintSystem, SynthesisError};
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// Rotation offsets of the lanes, indexed by `x` then `y`.
+const ROTATIONS: [[usize; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+/// Number of input bits absorbed per permutation.
+pub const RATE: usize = 1088;
+
+/// Computes `a ^ (!b & c)` bitwise.
+fn chi<E, CS>(mut cs: CS, a: &UInt64, b: &UInt64, c: &UInt64) -> Result<UInt64, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    let bits = a
+        .clone()
+        .into_bits()
+        .into_iter()
+        .zip(b.clone().into_bits())
+        .zip(c.clone().into_bits())
+        .enumerate()
+        .map(|(i, ((a, b), c))| {
+            let mut cs = cs.namespace(|| format!("bit {}", i));
+            let tmp = Boolean::and(cs.namespace(|| "and"), &b.not(), &c)?;
+            Boolean::xor(cs.namespace(|| "xor"), &a, &tmp)
+        })
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    Ok(UInt64::from_bits(&bits))
+}
+
+/// The Keccak-f[1600] permutation of the 25 lanes of `state`, the lane at
+/// `(x, y)` being `state[x + 5 y]`.
+///
+/// On allocated lanes a round costs 6400 constraints, 100 per bit of a lane:
+/// 20 for the column parities, 5 for their combinations, 25 to add them to
+/// the state and 50 for the nonlinear step. A permutation costs 153600
+/// constraints, and fewer when some bits are constant.
+pub fn keccak_f1600<E, CS>(mut cs: CS, state: &[UInt64]) -> Result<Vec<UInt64>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(state.len(), 25);
+
+    let mut a = state.to_vec();
+    for (round, &rc) in ROUND_CONSTANTS.iter().enumerate() {
+        let mut cs = cs.namespace(|| format!("round {}", round));
+
+        // theta
+        let mut c = Vec::with_capacity(5);
+        for x in 0..5 {
+            let mut cs = cs.namespace(|| format!("column {}", x));
+            let mut parity = a[x].clone();
+            for y in 1..5 {
+                parity = parity.xor(cs.namespace(|| format!("row {}", y)), &a[x + 5 * y])?;
+            }
+            c.push(parity);
+        }
+        for x in 0..5 {
+            let d =
+                c[(x + 4) % 5].xor(cs.namespace(|| format!("d {}", x)), &c[(x + 1) % 5].rotl(1))?;
+            for y in 0..5 {
+                a[x + 5 * y] =
+                    a[x + 5 * y].xor(cs.namespace(|| format!("theta {} {}", x, y)), &d)?;
+            }
+        }
+
+        // rho and pi
+        let mut b = vec![UInt64::constant(0); 25];
+        for (x, rotations) in ROTATIONS.iter().enumerate() {
+            for (y, &rotation) in rotations.iter().enumerate() {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotl(rotation);
+            }
+        }
+
+        // chi
+        for x in 0..5 {
+            for y in 0..5 {
+                a[x + 5 * y] = chi(
+                    cs.namespace(|| format!("chi {} {}", x, y)),
+                    &b[x + 5 * y],
+                    &b[(x + 1) % 5 + 5 * y],
+                    &b[(x + 2) % 5 + 5 * y],
+                )?;
+            }
+        }
+
+        // iota
+        a[0] = a[0].xor(cs.namespace(|| "iota"), &UInt64::constant(rc))?;
+    }
+
+    Ok(a)
+}
+
+/// Computes the 32 byte Keccak-256 digest of `input`.
+///
+/// The input must be a whole number of bytes. Both the input and the 256
+/// output bits are bytes in order, each byte least significant bit first.
+/// Each [`RATE`] bits of padded input cost a permutation.
+pub fn keccak256<E, CS>(mut cs: CS, input: &[Boolean]) -> Result<Vec<Boolean>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert!(input.len() % 8 == 0);
+
+    // pad10*1: a one, zeros, and a one ending the block.
+    let mut padded = input.to_vec();
+    padded.push(Boolean::constant(true));
+    while padded.len() % RATE != RATE - 1 {
+        padded.push(Boolean::constant(false));
+    }
+    padded.push(Boolean::constant(true));
+
+    let mut state = vec![UInt64::constant(0); 25];
+    for (i, block) in padded.chunks(RATE).enumerate() {
+        let mut cs = cs.namespace(|| format!("block {}", i));
+
+        for (j, lane) in block.chunks(64).enumerate() {
+            state[j] = state[j].xor(
+                cs.namespace(|| format!("absorb {}", j)),
+                &UInt64::from_bits(lane),
+            )?;
+        }
+        state = keccak_f1600(cs.namespace(|| "permutation"), &state)?;
+    }
+
+    Ok(state
+        .into_iter()
+        .take(4)
+        .flat_map(|lane| lane.into_bits())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::Bls12;
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use rand_core::RngCore;
+
+    fn alloc_bytes<CS: ConstraintSystem<Bls12>>(mut cs: CS, data: &[u8]) -> Vec<Boolean> {
+        data.iter()
+            .flat_map(|&byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .enumerate()
+            .map(|(i, b)| {
+                AllocatedBit::alloc(cs.namespace(|| format!("input bit {}", i)), Some(b))
+                    .unwrap()
+                    .into()
+            })
+            .collect()
+    }
+
+    fn to_bytes(bits: &[Boolean]) -> Vec<u8> {
+        bits.chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .map(|(i, b)| (b.get_value().unwrap() as u8) << i)
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn keccak_f1600_native(a: &mut [u64; 25]) {
+        for rc in ROUND_CONSTANTS.iter() {
+            let c = (0..5)
+                .map(|x| a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20])
+                .collect::<Vec<_>>();
+            for x in 0..5 {
+                let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+                for y in 0..5 {
+                    a[x + 5 * y] ^= d;
+                }
+            }
+
+            let mut b = [0u64; 25];
+            for (x, rotations) in ROTATIONS.iter().enumerate() {
+                for (y, &rotation) in rotations.iter().enumerate() {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotate_left(rotation as u32);
+                }
+            }
+
+            for x in 0..5 {
+                for y in 0..5 {
+                    a[x + 5 * y] =
+                        b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+                }
+            }
+            a[0] ^= rc;
+        }
+    }
+
+    #[test]
+    fn test_keccak_f1600() {
+        let mut rng = test_rng();
+
+        let mut expected = [0u64; 25];
+        for lane in expected.iter_mut() {
+            *lane = rng.next_u64();
+        }
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let state = expected
+            .iter()
+            .enumerate()
+            .map(|(i, &lane)| UInt64::alloc(cs.namespace(|| format!("lane {}", i)), Some(lane)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let allocated = cs.num_constraints();
+
+        let result = keccak_f1600(cs.namespace(|| "permutation"), &state).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints() - allocated, 153600);
+
+        keccak_f1600_native(&mut expected);
+        for (lane, expected) in result.iter().zip(expected.iter()) {
+            assert_eq!(lane.get_value(), Some(*expected));
+        }
+    }
+
+    #[test]
+    fn test_keccak256() {
+        let vectors: &[(&[u8], [u8; 32])] = &[
+            (
+                &b""[..],
+                hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"),
+            ),
+            (
+                &b"abc"[..],
+                hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+            ),
+        ];
+        for (data, expected) in vectors {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let input = alloc_bytes(cs.namespace(|| "input"), data);
+            let hash = keccak256(cs.namespace(|| "keccak256"), &input).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(to_bytes(&hash), expected.to_vec());
+        }
+
+        // Inputs around the block size, where the padding spills into a
+        // second block.
+        let data = (0..200).map(|i| i as u8).collect::<Vec<_>>();
+        let vectors = [
+            (
+                135,
+                hex!("cbdfd9dee5faad3818d6b06f95a219fd290b0e1706f6a82e5a595b9ce9faca62"),
+            ),
+            (
+                136,
+                hex!("7ce759f1ab7f9ce437719970c26b0a66ff11fe3e38e17df89cf5d29c7d7f807e"),
+            ),
+            (
+                200,
+                hex!("bfb0aa97863e797943cf7c33bb7e880bb4543f3d2703c0923c6901c2af57b890"),
+            ),
+        ];
+        for (len, expected) in vectors.iter() {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let input = alloc_bytes(cs.namespace(|| "input"), &data[..*len]);
+            let hash = keccak256(cs.namespace(|| "keccak256"), &input).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(to_bytes(&hash), expected.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_keccak256_constant_constraints() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let input = (0..256)
+            .map(|i| Boolean::constant(i % 3 == 0))
+            .collect::<Vec<_>>();
+        keccak256(cs.namespace(|| "keccak256"), &input).unwrap();
+        assert_eq!(cs.num_constraints(), 0);
+    }
+}
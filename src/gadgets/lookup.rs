@@ -0,0 +1,165 @@
+//! Window table lookups for fixed-base scalar multiplication.
+
+use ff::{Field, ScalarEngine};
+
+use super::boolean::Boolean;
+use super::num::AllocatedNum;
+use super::Assignment;
+use crate::{ConstraintSystem, SynthesisError};
+
+// Synthesize the constants for each base pattern.
+fn synth<'a, E: ScalarEngine, I>(window_size: usize, constants: I, assignment: &mut [E::Fr])
+where
+    I: IntoIterator<Item = &'a E::Fr>,
+{
+    assert_eq!(assignment.len(), 1 << window_size);
+
+    for (i, constant) in constants.into_iter().enumerate() {
+        let mut cur = assignment[i];
+        cur.negate();
+        cur.add_assign(constant);
+        assignment[i] = cur;
+        for (j, eval) in assignment.iter_mut().enumerate().skip(i + 1) {
+            if j & i == i {
+                eval.add_assign(&cur);
+            }
+        }
+    }
+}
+
+/// Performs a 3-bit window table lookup. `bits` is in
+/// little-endian order.
+pub fn lookup3_xy<E: ScalarEngine, CS>(
+    mut cs: CS,
+    bits: &[Boolean],
+    coords: &[(E::Fr, E::Fr)],
+) -> Result<(AllocatedNum<E>, AllocatedNum<E>), SynthesisError>
+where
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(bits.len(), 3);
+    assert_eq!(coords.len(), 8);
+
+    // Calculate the index into `coords`
+    let i = match (
+        bits[0].get_value(),
+        bits[1].get_value(),
+        bits[2].get_value(),
+    ) {
+        (Some(a_value), Some(b_value), Some(c_value)) => {
+            let mut tmp = 0;
+            if a_value {
+                tmp += 1;
+            }
+            if b_value {
+                tmp += 2;
+            }
+            if c_value {
+                tmp += 4;
+            }
+            Some(tmp)
+        }
+        _ => None,
+    };
+
+    // Allocate the x-coordinate resulting from the lookup
+    let res_x = AllocatedNum::alloc(cs.namespace(|| "x"), || Ok(coords[*i.get()?].0))?;
+
+    // Allocate the y-coordinate resulting from the lookup
+    let res_y = AllocatedNum::alloc(cs.namespace(|| "y"), || Ok(coords[*i.get()?].1))?;
+
+    // Compute the coefficients for the lookup constraints
+    let mut x_coeffs = [E::Fr::zero(); 8];
+    let mut y_coeffs = [E::Fr::zero(); 8];
+    synth::<E, _>(3, coords.iter().map(|c| &c.0), &mut x_coeffs);
+    synth::<E, _>(3, coords.iter().map(|c| &c.1), &mut y_coeffs);
+
+    let precomp = Boolean::and(cs.namespace(|| "precomp"), &bits[1], &bits[2])?;
+
+    let one = CS::one();
+
+    cs.enforce(
+        || "x-coordinate lookup",
+        |lc| {
+            lc + (x_coeffs[0b001], one)
+                + &bits[1].lc::<E>(one, x_coeffs[0b011])
+                + &bits[2].lc::<E>(one, x_coeffs[0b101])
+                + &precomp.lc::<E>(one, x_coeffs[0b111])
+        },
+        |lc| lc + &bits[0].lc::<E>(one, E::Fr::one()),
+        |lc| {
+            lc + res_x.get_variable()
+                - (x_coeffs[0b000], one)
+                - &bits[1].lc::<E>(one, x_coeffs[0b010])
+                - &bits[2].lc::<E>(one, x_coeffs[0b100])
+                - &precomp.lc::<E>(one, x_coeffs[0b110])
+        },
+    );
+
+    cs.enforce(
+        || "y-coordinate lookup",
+        |lc| {
+            lc + (y_coeffs[0b001], one)
+                + &bits[1].lc::<E>(one, y_coeffs[0b011])
+                + &bits[2].lc::<E>(one, y_coeffs[0b101])
+                + &precomp.lc::<E>(one, y_coeffs[0b111])
+        },
+        |lc| lc + &bits[0].lc::<E>(one, E::Fr::one()),
+        |lc| {
+            lc + res_y.get_variable()
+                - (y_coeffs[0b000], one)
+                - &bits[1].lc::<E>(one, y_coeffs[0b010])
+                - &bits[2].lc::<E>(one, y_coeffs[0b100])
+                - &precomp.lc::<E>(one, y_coeffs[0b110])
+        },
+    );
+
+    Ok((res_x, res_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_lookup3_xy() {
+        let mut rng = test_rng();
+
+        for index in 0..8usize {
+            for &constant_bits in &[false, true] {
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+
+                let bits = (0..3)
+                    .map(|i| {
+                        let b = (index >> i) & 1 == 1;
+                        if constant_bits {
+                            Boolean::constant(b)
+                        } else {
+                            AllocatedBit::alloc(cs.namespace(|| format!("bit {}", i)), Some(b))
+                                .unwrap()
+                                .into()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                let points = (0..8)
+                    .map(|_| (Fr::random(&mut rng), Fr::random(&mut rng)))
+                    .collect::<Vec<_>>();
+
+                let res = lookup3_xy(&mut cs, &bits, &points).unwrap();
+
+                assert!(cs.is_satisfied());
+                assert_eq!(res.0.get_value().unwrap(), points[index].0);
+                assert_eq!(res.1.get_value().unwrap(), points[index].1);
+
+                // Any other entry does not satisfy the lookup.
+                cs.set("x/num", points[(index + 1) % 8].0);
+                assert!(!cs.is_satisfied());
+            }
+        }
+    }
+}
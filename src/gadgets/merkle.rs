@@ -0,0 +1,242 @@
+//! Circuits verifying the authentication paths of the native
+//! `crate::merkle` trees.
+//!
+//! A path is a list of siblings from the leaf up, each with a bit telling
+//! whether the node on that level is a right child. The node and its sibling
+//! are swapped accordingly before hashing, at a cost of two constraints per
+//! level, so the ordering matches `MerklePath::root`.
+
+use ff::ScalarEngine;
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::num::AllocatedNum;
+use super::pedersen_hash::pedersen_hash;
+use super::poseidon::poseidon;
+use super::Assignment;
+use crate::bls::Bls12;
+use crate::merkle::{Hasher, MerklePath, PedersenHasher, PoseidonHasher};
+use crate::{ConstraintSystem, SynthesisError};
+
+/// A [`Hasher`] that can also be computed in a circuit.
+pub trait HashGadget<E: ScalarEngine>: Hasher<E> {
+    fn hash_circuit<CS>(
+        &self,
+        cs: CS,
+        left: &AllocatedNum<E>,
+        right: &AllocatedNum<E>,
+    ) -> Result<AllocatedNum<E>, SynthesisError>
+    where
+        CS: ConstraintSystem<E>;
+}
+
+impl<E: ScalarEngine> HashGadget<E> for PoseidonHasher<E> {
+    fn hash_circuit<CS>(
+        &self,
+        cs: CS,
+        left: &AllocatedNum<E>,
+        right: &AllocatedNum<E>,
+    ) -> Result<AllocatedNum<E>, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        poseidon(cs, self.constants(), &[left.clone(), right.clone()])
+    }
+}
+
+impl HashGadget<Bls12> for PedersenHasher {
+    fn hash_circuit<CS>(
+        &self,
+        mut cs: CS,
+        left: &AllocatedNum<Bls12>,
+        right: &AllocatedNum<Bls12>,
+    ) -> Result<AllocatedNum<Bls12>, SynthesisError>
+    where
+        CS: ConstraintSystem<Bls12>,
+    {
+        let mut bits = left.to_bits_le_strict(cs.namespace(|| "left bits"))?;
+        bits.extend(right.to_bits_le_strict(cs.namespace(|| "right bits"))?);
+
+        let hash = pedersen_hash(cs.namespace(|| "hash"), self.personalization(), &bits)?;
+
+        Ok(hash.get_x().clone())
+    }
+}
+
+/// Allocates the siblings and directions of a path of `depth` levels, whose
+/// values are those of `path` when it is known.
+pub fn alloc_path<E, CS>(
+    mut cs: CS,
+    depth: usize,
+    path: Option<&MerklePath<E>>,
+) -> Result<Vec<(AllocatedNum<E>, Boolean)>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    if let Some(path) = path {
+        assert_eq!(path.depth(), depth, "path of the wrong depth");
+    }
+    let directions = path.map(|path| path.directions());
+
+    (0..depth)
+        .map(|i| {
+            let mut cs = cs.namespace(|| format!("level {}", i));
+
+            let sibling =
+                AllocatedNum::alloc(cs.namespace(|| "sibling"), || Ok(path.get()?.siblings()[i]))?;
+            let is_right = AllocatedBit::alloc(
+                cs.namespace(|| "is right"),
+                directions.as_ref().map(|d| d[i]),
+            )?;
+
+            Ok((sibling, Boolean::from(is_right)))
+        })
+        .collect()
+}
+
+/// Computes the root of the tree holding `leaf` at `path`, as
+/// `MerklePath::root` does natively.
+pub fn compute_root<E, H, CS>(
+    mut cs: CS,
+    hasher: &H,
+    leaf: &AllocatedNum<E>,
+    path: &[(AllocatedNum<E>, Boolean)],
+) -> Result<AllocatedNum<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    H: HashGadget<E>,
+    CS: ConstraintSystem<E>,
+{
+    let mut node = leaf.clone();
+
+    for (i, (sibling, is_right)) in path.iter().enumerate() {
+        let mut cs = cs.namespace(|| format!("level {}", i));
+
+        // Put the node on the right when it is a right child.
+        let (left, right) = AllocatedNum::conditionally_reverse(
+            cs.namespace(|| "order"),
+            &node,
+            sibling,
+            is_right,
+        )?;
+
+        node = hasher.hash_circuit(cs.namespace(|| "hash"), &left, &right)?;
+    }
+
+    Ok(node)
+}
+
+/// Enforces that `leaf` is in the tree of root `root` at `path`.
+pub fn enforce_membership<E, H, CS>(
+    mut cs: CS,
+    hasher: &H,
+    leaf: &AllocatedNum<E>,
+    path: &[(AllocatedNum<E>, Boolean)],
+    root: &AllocatedNum<E>,
+) -> Result<(), SynthesisError>
+where
+    E: ScalarEngine,
+    H: HashGadget<E>,
+    CS: ConstraintSystem<E>,
+{
+    let computed = compute_root(cs.namespace(|| "compute root"), hasher, leaf, path)?;
+
+    cs.enforce(
+        || "root matches",
+        |lc| lc + computed.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + root.get_variable(),
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::Fr;
+    use crate::merkle::MerkleTree;
+    use crate::util_cs::metric_cs::MetricCS;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::{Field, PrimeField};
+
+    fn check_tree<H: HashGadget<Bls12>>(tree: &MerkleTree<Bls12, H>) {
+        for index in 0..tree.leaves().len() as u64 {
+            let path = tree.path(index);
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let leaf =
+                AllocatedNum::alloc(
+                    cs.namespace(|| "leaf"),
+                    || Ok(tree.leaves()[index as usize]),
+                )
+                .unwrap();
+            let root = AllocatedNum::alloc(cs.namespace(|| "root"), || Ok(tree.root())).unwrap();
+            let path = alloc_path(cs.namespace(|| "path"), tree.depth(), Some(&path)).unwrap();
+
+            let computed =
+                compute_root(cs.namespace(|| "membership"), tree.hasher(), &leaf, &path).unwrap();
+            assert_eq!(computed.get_value(), Some(tree.root()));
+
+            enforce_membership(
+                cs.namespace(|| "enforce"),
+                tree.hasher(),
+                &leaf,
+                &path,
+                &root,
+            )
+            .unwrap();
+            assert!(cs.is_satisfied());
+
+            // Flipping a direction moves the leaf to another position.
+            let flipped = if index & 1 == 1 {
+                Fr::zero()
+            } else {
+                Fr::one()
+            };
+            cs.set("path/level 0/is right/boolean", flipped);
+            assert!(!cs.is_satisfied());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_membership() {
+        let mut rng = test_rng();
+
+        let leaves = (0..6).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        check_tree(&MerkleTree::new(PoseidonHasher::new(), 3, &leaves));
+    }
+
+    #[test]
+    fn test_pedersen_membership() {
+        let leaves = (1..=3)
+            .map(|i| Fr::from_str(&i.to_string()).unwrap())
+            .collect::<Vec<_>>();
+        check_tree(&MerkleTree::new(
+            PedersenHasher::new(*b"Test_MPH"),
+            2,
+            &leaves,
+        ));
+    }
+
+    #[test]
+    fn test_membership_without_witness() {
+        let mut cs = MetricCS::<Bls12>::new();
+        let hasher = PoseidonHasher::new();
+        let leaf = AllocatedNum::alloc(cs.namespace(|| "leaf"), || {
+            Err(SynthesisError::AssignmentMissing)
+        })
+        .unwrap();
+        let root = AllocatedNum::alloc(cs.namespace(|| "root"), || {
+            Err(SynthesisError::AssignmentMissing)
+        })
+        .unwrap();
+        let path = alloc_path::<Bls12, _>(cs.namespace(|| "path"), 4, None).unwrap();
+
+        enforce_membership(cs.namespace(|| "membership"), &hasher, &leaf, &path, &root).unwrap();
+
+        // Per level: the direction bit, the reversal and the hash.
+        assert_eq!(cs.num_constraints(), 4 * (1 + 2 + 244) + 1);
+    }
+}
@@ -0,0 +1,239 @@
+//! Circuits for the MiMC cipher and hash and the GMiMC permutation,
+//! computing the same results as the native `crate::mimc` module.
+//!
+//! As in `gadgets::poseidon`, values are kept as linear combinations between
+//! S-boxes and each S-box takes three constraints. Encrypting costs
+//! `3 * rounds + 1` constraints, hashing `n` elements `3 * rounds * n + 1`
+//! and a GMiMC hash `3 * rounds + 1`.
+
+use ff::{Field, ScalarEngine};
+
+use super::num::AllocatedNum;
+use super::poseidon::Elt;
+use crate::mimc::{GmimcConstants, MimcConstants};
+use crate::{ConstraintSystem, SynthesisError};
+
+fn encrypt_elt<E, CS>(
+    mut cs: CS,
+    constants: &MimcConstants<E>,
+    key: &Elt<E>,
+    message: Elt<E>,
+) -> Result<Elt<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    let mut x = message;
+    for (i, c) in constants.round_constants().iter().enumerate() {
+        x = x
+            .add(key)
+            .add_constant(c, CS::one())
+            .sbox(cs.namespace(|| format!("round {}", i)))?;
+    }
+
+    Ok(x.add(key))
+}
+
+/// Encrypts `message` under `key`.
+pub fn encrypt<E, CS>(
+    mut cs: CS,
+    constants: &MimcConstants<E>,
+    key: &AllocatedNum<E>,
+    message: &AllocatedNum<E>,
+) -> Result<AllocatedNum<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    let ciphertext = encrypt_elt(
+        cs.namespace(|| "encryption"),
+        constants,
+        &Elt::num(key),
+        Elt::num(message),
+    )?;
+
+    ciphertext.into_num(cs.namespace(|| "ciphertext"))
+}
+
+/// Hashes `inputs` with the Miyaguchi-Preneel construction.
+pub fn mimc_hash<E, CS>(
+    mut cs: CS,
+    constants: &MimcConstants<E>,
+    inputs: &[AllocatedNum<E>],
+) -> Result<AllocatedNum<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    let mut h = Elt::constant(E::Fr::zero(), CS::one());
+    for (i, input) in inputs.iter().enumerate() {
+        let m = Elt::num(input);
+        h = encrypt_elt(
+            cs.namespace(|| format!("input {}", i)),
+            constants,
+            &h,
+            m.clone(),
+        )?
+        .add(&h)
+        .add(&m);
+    }
+
+    h.into_num(cs.namespace(|| "digest"))
+}
+
+/// Applies the GMiMC permutation to `state`, allocating the result with a
+/// constraint per element.
+pub fn gmimc_permute<E, CS>(
+    mut cs: CS,
+    constants: &GmimcConstants<E>,
+    state: &[AllocatedNum<E>],
+) -> Result<Vec<AllocatedNum<E>>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(state.len(), constants.width(), "wrong state width");
+
+    let state = permute_elts(
+        cs.namespace(|| "permutation"),
+        constants,
+        state.iter().map(Elt::num).collect(),
+    )?;
+
+    state
+        .into_iter()
+        .enumerate()
+        .map(|(i, elt)| elt.into_num(cs.namespace(|| format!("output {}", i))))
+        .collect()
+}
+
+fn permute_elts<E, CS>(
+    mut cs: CS,
+    constants: &GmimcConstants<E>,
+    mut state: Vec<Elt<E>>,
+) -> Result<Vec<Elt<E>>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    for (i, c) in constants.round_constants().iter().enumerate() {
+        let s = state[0]
+            .clone()
+            .add_constant(c, CS::one())
+            .sbox(cs.namespace(|| format!("round {}", i)))?;
+
+        for elt in state[1..].iter_mut() {
+            *elt = elt.clone().add(&s);
+        }
+        state.rotate_left(1);
+    }
+
+    Ok(state)
+}
+
+/// Hashes `inputs`, of which there must be `constants.arity()`.
+pub fn gmimc<E, CS>(
+    mut cs: CS,
+    constants: &GmimcConstants<E>,
+    inputs: &[AllocatedNum<E>],
+) -> Result<AllocatedNum<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(inputs.len(), constants.arity(), "wrong number of inputs");
+
+    let state = Some(Elt::constant(E::Fr::zero(), CS::one()))
+        .into_iter()
+        .chain(inputs.iter().map(Elt::num))
+        .collect();
+    let mut state = permute_elts(cs.namespace(|| "permutation"), constants, state)?;
+
+    state.swap_remove(1).into_num(cs.namespace(|| "digest"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::mimc;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+
+    fn alloc_nums<CS: ConstraintSystem<Bls12>>(
+        mut cs: CS,
+        values: &[Fr],
+    ) -> Vec<AllocatedNum<Bls12>> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                AllocatedNum::alloc(cs.namespace(|| format!("input {}", i)), || Ok(*v)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_mimc_matches_native() {
+        let mut rng = test_rng();
+        let constants = MimcConstants::<Bls12>::new();
+        let values = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let nums = alloc_nums(cs.namespace(|| "inputs"), &values);
+        let ciphertext =
+            encrypt(cs.namespace(|| "encrypt"), &constants, &nums[0], &nums[1]).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 3 * 110 + 1);
+        assert_eq!(
+            ciphertext.get_value().unwrap(),
+            mimc::encrypt(&constants, values[0], values[1])
+        );
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let nums = alloc_nums(cs.namespace(|| "inputs"), &values);
+        let digest = mimc_hash(cs.namespace(|| "hash"), &constants, &nums).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 3 * 110 * 3 + 1);
+        assert_eq!(
+            digest.get_value().unwrap(),
+            mimc::mimc_hash(&constants, &values)
+        );
+
+        // A wrong digest does not satisfy the circuit.
+        cs.set("hash/digest/value/num", Fr::one());
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_gmimc_matches_native() {
+        let mut rng = test_rng();
+
+        for &width in &[2, 3, 5] {
+            let constants = GmimcConstants::<Bls12>::new(width, 50);
+            let values = (0..width).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let nums = alloc_nums(cs.namespace(|| "inputs"), &values);
+            let permuted = gmimc_permute(cs.namespace(|| "permute"), &constants, &nums).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 3 * 50 + width);
+
+            let mut expected = values.clone();
+            mimc::gmimc_permute(&constants, &mut expected);
+            for (num, expected) in permuted.iter().zip(expected) {
+                assert_eq!(num.get_value().unwrap(), expected);
+            }
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let nums = alloc_nums(cs.namespace(|| "inputs"), &values[1..]);
+            let digest = gmimc(cs.namespace(|| "hash"), &constants, &nums).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 3 * 50 + 1);
+            assert_eq!(
+                digest.get_value().unwrap(),
+                mimc::gmimc(&constants, &values[1..])
+            );
+        }
+    }
+}
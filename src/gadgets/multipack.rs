@@ -0,0 +1,138 @@
+//! Helpers for packing vectors of bits into scalar field elements, so that
+//! many bits cost few public inputs.
+//!
+//! Bits are packed little-endian, `E::Fr::CAPACITY` to an element. The
+//! verifier computes the same elements from the bits with
+//! [`compute_multipacking`].
+
+use ff::{Field, PrimeField, ScalarEngine};
+
+use super::boolean::Boolean;
+use super::num::Num;
+use super::Assignment;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// Exposes `bits` as public inputs, one per `E::Fr::CAPACITY` bits, with a
+/// constraint per input.
+pub fn pack_bits_into_inputs<E, CS>(mut cs: CS, bits: &[Boolean]) -> Result<(), SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    for (i, bits) in bits.chunks(E::Fr::CAPACITY as usize).enumerate() {
+        let mut num = Num::<E>::zero();
+        let mut coeff = E::Fr::one();
+        for bit in bits {
+            num = num.add_bool_with_coeff(CS::one(), bit, coeff);
+
+            coeff.double();
+        }
+
+        let input = cs.alloc_input(|| format!("input {}", i), || Ok(*num.get_value().get()?))?;
+
+        // num * 1 = input
+        cs.enforce(
+            || format!("packing constraint {}", i),
+            |_| num.lc(E::Fr::one()),
+            |lc| lc + CS::one(),
+            |lc| lc + input,
+        );
+    }
+
+    Ok(())
+}
+
+/// The bits of `bytes`, each byte most significant bit first.
+pub fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|&v| (0..8).rev().map(move |i| (v >> i) & 1 == 1))
+        .collect()
+}
+
+/// The bits of `bytes`, each byte least significant bit first.
+pub fn bytes_to_bits_le(bytes: &[u8]) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|&v| (0..8).map(move |i| (v >> i) & 1 == 1))
+        .collect()
+}
+
+/// The public inputs that [`pack_bits_into_inputs`] allocates for `bits`.
+pub fn compute_multipacking<E: ScalarEngine>(bits: &[bool]) -> Vec<E::Fr> {
+    bits.chunks(E::Fr::CAPACITY as usize)
+        .map(|bits| {
+            let mut cur = E::Fr::zero();
+            let mut coeff = E::Fr::one();
+            for &bit in bits {
+                if bit {
+                    cur.add_assign(&coeff);
+                }
+                coeff.double();
+            }
+
+            cur
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use rand_core::RngCore;
+
+    #[test]
+    fn test_multipacking() {
+        let mut rng = test_rng();
+
+        for num_bits in 0..1500 {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+
+            let bits: Vec<bool> = (0..num_bits).map(|_| rng.next_u32() % 2 != 0).collect();
+
+            let circuit_bits = bits
+                .iter()
+                .enumerate()
+                .map(|(i, &b)| {
+                    Boolean::from(
+                        AllocatedBit::alloc(cs.namespace(|| format!("bit {}", i)), Some(b))
+                            .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let expected_inputs = compute_multipacking::<Bls12>(&bits);
+
+            pack_bits_into_inputs(cs.namespace(|| "pack"), &circuit_bits).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert!(cs.verify(&expected_inputs));
+            assert_eq!(
+                cs.num_inputs(),
+                1 + (num_bits + Fr::CAPACITY as usize - 1) / Fr::CAPACITY as usize
+            );
+        }
+    }
+
+    #[test]
+    fn test_bytes_to_bits() {
+        assert_eq!(
+            bytes_to_bits(&[0b1000_0001, 0b0000_0010]),
+            vec![
+                true, false, false, false, false, false, false, true, false, false, false, false,
+                false, false, true, false
+            ]
+        );
+        assert_eq!(
+            bytes_to_bits_le(&[0b1000_0001, 0b0000_0010]),
+            vec![
+                true, false, false, false, false, false, false, true, false, true, false, false,
+                false, false, false, false
+            ]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Multiplexers selecting one of `2^k` allocated values by a `k`-bit index.
+//!
+//! The values are reduced pairwise, one index bit per level, so a
+//! multiplexer over `n` values costs `n - 1` constraints, fewer where index
+//! bits are constant. For tables of constants, the window lookups of
+//! `gadgets::lookup` are cheaper.
+
+use ff::ScalarEngine;
+
+use super::boolean::Boolean;
+use super::num::AllocatedNum;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// Reduces `values` pairwise with `select`, one level per bit of `index`.
+fn reduce<T, CS, F>(
+    mut cs: CS,
+    index: &[Boolean],
+    values: &[T],
+    mut select: F,
+) -> Result<T, SynthesisError>
+where
+    T: Clone,
+    F: FnMut(&mut CS, usize, usize, &Boolean, &T, &T) -> Result<T, SynthesisError>,
+{
+    assert_eq!(
+        values.len(),
+        1 << index.len(),
+        "there must be a value for each index"
+    );
+
+    let mut level = values.to_vec();
+    for (i, bit) in index.iter().enumerate() {
+        level = level
+            .chunks(2)
+            .enumerate()
+            .map(|(j, pair)| select(&mut cs, i, j, bit, &pair[1], &pair[0]))
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+    }
+
+    Ok(level.pop().expect("one value is left"))
+}
+
+/// Returns `values[index]`, for a little-endian `index` with one bit per
+/// halving of `values`.
+pub fn mux<E, CS>(
+    cs: CS,
+    index: &[Boolean],
+    values: &[AllocatedNum<E>],
+) -> Result<AllocatedNum<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    reduce(cs, index, values, |cs, i, j, bit, a, b| {
+        AllocatedNum::conditionally_select(
+            cs.namespace(|| format!("level {} select {}", i, j)),
+            bit,
+            a,
+            b,
+        )
+    })
+}
+
+/// Returns `values[index]` for booleans, as [`mux`] does for numbers.
+pub fn mux_bool<E, CS>(
+    cs: CS,
+    index: &[Boolean],
+    values: &[Boolean],
+) -> Result<Boolean, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    reduce(cs, index, values, |cs, i, j, bit, a, b| {
+        Boolean::conditionally_select(
+            cs.namespace(|| format!("level {} select {}", i, j)),
+            bit,
+            a,
+            b,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::Field;
+    use rand_core::RngCore;
+
+    #[test]
+    fn test_mux() {
+        let mut rng = test_rng();
+
+        for index in 0..8usize {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+
+            let values = (0..8)
+                .map(|i| {
+                    AllocatedNum::alloc(cs.namespace(|| format!("value {}", i)), || {
+                        Ok(Fr::random(&mut rng))
+                    })
+                    .unwrap()
+                })
+                .collect::<Vec<_>>();
+            let bits = (0..3)
+                .map(|i| {
+                    Boolean::from(
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("index {}", i)),
+                            Some((index >> i) & 1 == 1),
+                        )
+                        .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let constraints = cs.num_constraints();
+
+            let result = mux(cs.namespace(|| "mux"), &bits, &values).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints() - constraints, 7);
+            assert_eq!(result.get_value(), values[index].get_value());
+
+            // A constant index only selects.
+            let constant = (0..3)
+                .map(|i| Boolean::constant((index >> i) & 1 == 1))
+                .collect::<Vec<_>>();
+            let constraints = cs.num_constraints();
+            let result = mux(cs.namespace(|| "constant mux"), &constant, &values).unwrap();
+            assert_eq!(cs.num_constraints(), constraints);
+            assert_eq!(result.get_variable(), values[index].get_variable());
+        }
+    }
+
+    #[test]
+    fn test_mux_bool() {
+        let mut rng = test_rng();
+
+        for index in 0..4usize {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+
+            let values = (0..4)
+                .map(|i| {
+                    Boolean::from(
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("value {}", i)),
+                            Some(rng.next_u32() % 2 == 0),
+                        )
+                        .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let bits = (0..2)
+                .map(|i| {
+                    Boolean::from(
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("index {}", i)),
+                            Some((index >> i) & 1 == 1),
+                        )
+                        .unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let constraints = cs.num_constraints();
+
+            let result = mux_bool(cs.namespace(|| "mux"), &bits, &values).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints() - constraints, 3);
+            assert_eq!(result.get_value(), values[index].get_value());
+        }
+    }
+}
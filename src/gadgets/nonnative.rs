@@ -0,0 +1,1083 @@
+//! Arithmetic modulo a foreign modulus, such as the base field of secp256k1,
+//! emulated over the scalar field of the circuit.
+//!
+//! A number is split into `n_limbs` limbs of `limb_width` bits, each
+//! range-checked. An operation witnesses the quotient `q` and remainder `r`
+//! of its integer result by the modulus `p`, and enforces `result = q p + r`
+//! on the polynomials in `2^limb_width` whose coefficients are the limbs,
+//! propagating the carries between coefficients with one constraint each.
+//!
+//! Results are only known to fit in the limbs, not to be less than `p`, so
+//! two numbers are compared with [`AllocatedNonNative::enforce_equal`],
+//! which checks that they are congruent.
+
+use std::cmp::Ordering;
+use std::ops::{Add, Mul, Shl, Shr, Sub};
+
+use ff::{Field, PrimeField, PrimeFieldRepr, ScalarEngine};
+
+use super::boolean::Boolean;
+use super::num::AllocatedNum;
+use super::range::assert_bits;
+use crate::secp256k1;
+use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+/// A natural number of arbitrary size, used for the values of the emulated
+/// numbers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Nat {
+    // Little-endian, without trailing zeros.
+    words: Vec<u64>,
+}
+
+impl Nat {
+    pub fn zero() -> Self {
+        Nat { words: vec![] }
+    }
+
+    pub fn from_u64(value: u64) -> Self {
+        Self::from_words(vec![value])
+    }
+
+    fn from_words(mut words: Vec<u64>) -> Self {
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+
+        Nat { words }
+    }
+
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        let words = bytes
+            .rchunks(8)
+            .map(|chunk| chunk.iter().fold(0u64, |acc, b| acc << 8 | u64::from(*b)))
+            .collect();
+
+        Self::from_words(words)
+    }
+
+    /// The big-endian bytes of this number, left-padded with zeros to `len`.
+    ///
+    /// Panics if the number does not fit in `len` bytes.
+    pub fn to_bytes_be(&self, len: usize) -> Vec<u8> {
+        assert!(self.bits() <= len * 8, "number does not fit in the bytes");
+
+        (0..len)
+            .rev()
+            .map(|i| {
+                let word = self.words.get(i / 8).cloned().unwrap_or(0);
+                (word >> (8 * (i % 8))) as u8
+            })
+            .collect()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The number of bits, not counting leading zeros.
+    pub fn bits(&self) -> usize {
+        match self.words.last() {
+            Some(last) => 64 * self.words.len() - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn bit(&self, i: usize) -> bool {
+        self.words
+            .get(i / 64)
+            .map_or(false, |word| (word >> (i % 64)) & 1 == 1)
+    }
+
+    /// The number modulo `2^bits`.
+    pub fn low_bits(&self, bits: usize) -> Self {
+        let mut words = self
+            .words
+            .iter()
+            .take((bits + 63) / 64)
+            .cloned()
+            .collect::<Vec<_>>();
+        if bits % 64 != 0 {
+            if let Some(last) = words.get_mut(bits / 64) {
+                *last &= (1 << (bits % 64)) - 1;
+            }
+        }
+
+        Self::from_words(words)
+    }
+
+    /// The quotient and remainder of the division by `divisor`.
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        assert!(!divisor.is_zero(), "division by zero");
+
+        let mut quotient = vec![0u64; self.words.len()];
+        let mut remainder = Self::zero();
+        for i in (0..self.bits()).rev() {
+            remainder = &remainder << 1;
+            if self.bit(i) {
+                remainder = &remainder + &Self::from_u64(1);
+            }
+            if remainder >= *divisor {
+                remainder = &remainder - divisor;
+                quotient[i / 64] |= 1 << (i % 64);
+            }
+        }
+
+        (Self::from_words(quotient), remainder)
+    }
+
+    /// The inverse modulo the odd `modulus`, if there is one.
+    pub fn inv_mod(&self, modulus: &Self) -> Option<Self> {
+        assert!(modulus.bit(0), "the modulus must be odd");
+
+        // Binary extended Euclidean algorithm, keeping u = x1 self and
+        // v = x2 self modulo the modulus.
+        let one = Self::from_u64(1);
+        let halve = |x: &Self| {
+            if x.bit(0) {
+                &(x + modulus) >> 1
+            } else {
+                x >> 1
+            }
+        };
+        let sub_mod = |a: &Self, b: &Self| {
+            if a >= b {
+                a - b
+            } else {
+                &(a + modulus) - b
+            }
+        };
+
+        let mut u = self.div_rem(modulus).1;
+        let mut v = modulus.clone();
+        let mut x1 = one.clone();
+        let mut x2 = Self::zero();
+        while u != one && v != one {
+            if u.is_zero() || v.is_zero() {
+                return None;
+            }
+            while !u.bit(0) {
+                u = &u >> 1;
+                x1 = halve(&x1);
+            }
+            while !v.bit(0) {
+                v = &v >> 1;
+                x2 = halve(&x2);
+            }
+            if u >= v {
+                u = &u - &v;
+                x1 = sub_mod(&x1, &x2);
+            } else {
+                v = &v - &u;
+                x2 = sub_mod(&x2, &x1);
+            }
+        }
+
+        Some(if u == one { x1 } else { x2 })
+    }
+
+    /// Converts to a field element.
+    ///
+    /// Panics if the number is not less than the modulus of the field.
+    pub fn to_field<F: PrimeField>(&self) -> F {
+        let mut repr = F::Repr::default();
+        assert!(
+            self.words.len() <= repr.as_ref().len(),
+            "number does not fit in the field"
+        );
+        repr.as_mut()[..self.words.len()].copy_from_slice(&self.words);
+
+        F::from_repr(repr).expect("number does not fit in the field")
+    }
+}
+
+impl Ord for Nat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.words
+            .len()
+            .cmp(&other.words.len())
+            .then_with(|| self.words.iter().rev().cmp(other.words.iter().rev()))
+    }
+}
+
+impl PartialOrd for Nat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Add<&'a Nat> for &'a Nat {
+    type Output = Nat;
+
+    fn add(self, other: &Nat) -> Nat {
+        let len = self.words.len().max(other.words.len());
+        let mut words = Vec::with_capacity(len + 1);
+        let mut carry = 0u128;
+        for i in 0..len {
+            let sum = u128::from(self.words.get(i).cloned().unwrap_or(0))
+                + u128::from(other.words.get(i).cloned().unwrap_or(0))
+                + carry;
+            words.push(sum as u64);
+            carry = sum >> 64;
+        }
+        words.push(carry as u64);
+
+        Nat::from_words(words)
+    }
+}
+
+impl<'a> Sub<&'a Nat> for &'a Nat {
+    type Output = Nat;
+
+    /// Panics if `other` is greater than `self`.
+    fn sub(self, other: &Nat) -> Nat {
+        assert!(*self >= *other, "subtraction underflow");
+
+        let mut words = Vec::with_capacity(self.words.len());
+        let mut borrow = false;
+        for (i, word) in self.words.iter().enumerate() {
+            let (tmp, b1) = word.overflowing_sub(other.words.get(i).cloned().unwrap_or(0));
+            let (tmp, b2) = tmp.overflowing_sub(borrow as u64);
+            words.push(tmp);
+            borrow = b1 || b2;
+        }
+
+        Nat::from_words(words)
+    }
+}
+
+impl<'a> Mul<&'a Nat> for &'a Nat {
+    type Output = Nat;
+
+    fn mul(self, other: &Nat) -> Nat {
+        let mut words = vec![0u64; self.words.len() + other.words.len()];
+        for (i, a) in self.words.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, b) in other.words.iter().enumerate() {
+                let tmp = u128::from(*a) * u128::from(*b) + u128::from(words[i + j]) + carry;
+                words[i + j] = tmp as u64;
+                carry = tmp >> 64;
+            }
+            words[i + other.words.len()] = carry as u64;
+        }
+
+        Nat::from_words(words)
+    }
+}
+
+impl<'a> Shl<usize> for &'a Nat {
+    type Output = Nat;
+
+    fn shl(self, bits: usize) -> Nat {
+        let mut words = vec![0u64; bits / 64];
+        let shift = bits % 64;
+        let mut carry = 0;
+        for word in &self.words {
+            if shift == 0 {
+                words.push(*word);
+            } else {
+                words.push(word << shift | carry);
+                carry = word >> (64 - shift);
+            }
+        }
+        words.push(carry);
+
+        Nat::from_words(words)
+    }
+}
+
+impl<'a> Shr<usize> for &'a Nat {
+    type Output = Nat;
+
+    fn shr(self, bits: usize) -> Nat {
+        let shift = bits % 64;
+        let words = self
+            .words
+            .iter()
+            .enumerate()
+            .skip(bits / 64)
+            .map(|(i, word)| {
+                let next = self.words.get(i + 1).cloned().unwrap_or(0);
+                if shift == 0 {
+                    *word
+                } else {
+                    word >> shift | next << (64 - shift)
+                }
+            })
+            .collect();
+
+        Nat::from_words(words)
+    }
+}
+
+/// The modulus of the emulated field and the shape of its numbers.
+#[derive(Clone, Debug)]
+pub struct NonNativeParams {
+    modulus: Nat,
+    limb_width: usize,
+    n_limbs: usize,
+}
+
+impl NonNativeParams {
+    /// Panics unless the modulus fits in `n_limbs` limbs of `limb_width` bits
+    /// and uses the top one, or if products of limbs with their carries do
+    /// not fit in the scalar field of `E`.
+    pub fn new<E: ScalarEngine>(modulus: Nat, limb_width: usize, n_limbs: usize) -> Self {
+        assert!(n_limbs >= 1, "at least one limb");
+        assert!(
+            modulus.bits() <= limb_width * n_limbs && modulus.bits() > limb_width * (n_limbs - 1),
+            "the modulus must fill the top limb"
+        );
+        assert!(modulus > Nat::from_u64(1), "the modulus must be at least 2");
+
+        let params = NonNativeParams {
+            modulus,
+            limb_width,
+            n_limbs,
+        };
+        assert!(
+            params.carry_width() + limb_width + 1 < E::Fr::CAPACITY as usize,
+            "limbs too wide for the scalar field"
+        );
+
+        params
+    }
+
+    /// The base field of secp256k1, in four 64-bit limbs.
+    pub fn secp256k1_base<E: ScalarEngine>() -> Self {
+        Self::new::<E>(secp256k1::base_modulus(), 64, 4)
+    }
+
+    /// The scalar field of secp256k1, in four 64-bit limbs.
+    pub fn secp256k1_scalar<E: ScalarEngine>() -> Self {
+        Self::new::<E>(secp256k1::scalar_modulus(), 64, 4)
+    }
+
+    pub fn modulus(&self) -> &Nat {
+        &self.modulus
+    }
+
+    pub fn limb_width(&self) -> usize {
+        self.limb_width
+    }
+
+    pub fn n_limbs(&self) -> usize {
+        self.n_limbs
+    }
+
+    /// Width of the offset carries. A coefficient of a product is less than
+    /// `(n_limbs + 1) 2^(2 limb_width)`, so a carry is less than
+    /// `(n_limbs + 2) 2^limb_width` in absolute value.
+    fn carry_width(&self) -> usize {
+        let log = (0usize..).find(|i| 1 << i >= self.n_limbs + 2).unwrap();
+        self.limb_width + log + 1
+    }
+
+    /// The limbs of `value`, which must fit in `n` of them.
+    fn limbs_of(&self, value: &Nat, n: usize) -> Vec<Nat> {
+        assert!(
+            value.bits() <= self.limb_width * n,
+            "number does not fit in the limbs"
+        );
+
+        (0..n)
+            .map(|i| (value >> (i * self.limb_width)).low_bits(self.limb_width))
+            .collect()
+    }
+
+    /// The smallest multiple of the modulus not less than
+    /// `2^(limb_width n_limbs)`, so adding it to a number makes it greater
+    /// than any other.
+    fn modulus_multiple(&self) -> Nat {
+        let bound = &Nat::from_u64(1) << (self.limb_width * self.n_limbs);
+        let (q, r) = bound.div_rem(&self.modulus);
+        if r.is_zero() {
+            bound
+        } else {
+            &(&q + &Nat::from_u64(1)) * &self.modulus
+        }
+    }
+}
+
+/// A coefficient of a polynomial in `2^limb_width`, with its value as an
+/// integer.
+struct Coeff<E: ScalarEngine> {
+    lc: LinearCombination<E>,
+    value: Option<Nat>,
+}
+
+fn constant_poly<E: ScalarEngine>(limbs: &[Nat], one: Variable) -> Vec<Coeff<E>> {
+    limbs
+        .iter()
+        .map(|limb| Coeff {
+            lc: LinearCombination::zero() + (limb.to_field(), one),
+            value: Some(limb.clone()),
+        })
+        .collect()
+}
+
+fn add_poly<E: ScalarEngine>(a: Vec<Coeff<E>>, b: Vec<Coeff<E>>) -> Vec<Coeff<E>> {
+    let (mut long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    for (x, y) in long.iter_mut().zip(short) {
+        x.lc = x.lc.clone() + &y.lc;
+        x.value = match (&x.value, y.value) {
+            (Some(x), Some(y)) => Some(x + &y),
+            _ => None,
+        };
+    }
+
+    long
+}
+
+/// The product of the polynomials of allocated limbs `a` and constant limbs
+/// `b`, which costs no constraints.
+fn mul_poly_constant<E: ScalarEngine>(
+    a: &[AllocatedNum<E>],
+    a_values: Option<&[Nat]>,
+    b: &[Nat],
+) -> Vec<Coeff<E>> {
+    (0..a.len() + b.len() - 1)
+        .map(|k| {
+            let terms = (0..a.len())
+                .filter(|i| k >= *i && k - i < b.len())
+                .collect::<Vec<_>>();
+
+            let lc = terms.iter().fold(LinearCombination::zero(), |lc, &i| {
+                lc + (b[k - i].to_field(), a[i].get_variable())
+            });
+            let value = a_values.map(|a_values| {
+                terms
+                    .iter()
+                    .fold(Nat::zero(), |acc, &i| &acc + &(&a_values[i] * &b[k - i]))
+            });
+
+            Coeff { lc, value }
+        })
+        .collect()
+}
+
+/// A number modulo `params.modulus()`, as range-checked limbs.
+pub struct AllocatedNonNative<E: ScalarEngine> {
+    limbs: Vec<AllocatedNum<E>>,
+    value: Option<Nat>,
+    params: NonNativeParams,
+}
+
+impl<E: ScalarEngine> Clone for AllocatedNonNative<E> {
+    fn clone(&self) -> Self {
+        AllocatedNonNative {
+            limbs: self.limbs.clone(),
+            value: self.value.clone(),
+            params: self.params.clone(),
+        }
+    }
+}
+
+impl<E: ScalarEngine> AllocatedNonNative<E> {
+    /// Allocates a number and range-checks its limbs.
+    ///
+    /// Panics if the value does not fit in the limbs.
+    pub fn alloc<CS>(
+        cs: CS,
+        params: &NonNativeParams,
+        value: Option<Nat>,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let limbs = alloc_limbs(cs, params, value.as_ref(), params.n_limbs)?;
+
+        Ok(AllocatedNonNative {
+            limbs,
+            value,
+            params: params.clone(),
+        })
+    }
+
+    pub fn get_value(&self) -> Option<&Nat> {
+        self.value.as_ref()
+    }
+
+    pub fn limbs(&self) -> &[AllocatedNum<E>] {
+        &self.limbs
+    }
+
+    pub fn params(&self) -> &NonNativeParams {
+        &self.params
+    }
+
+    fn limb_values(&self) -> Option<Vec<Nat>> {
+        self.value
+            .as_ref()
+            .map(|value| self.params.limbs_of(value, self.params.n_limbs))
+    }
+
+    fn poly(&self) -> Vec<Coeff<E>> {
+        let limb_values = self.limb_values();
+        self.limbs
+            .iter()
+            .enumerate()
+            .map(|(i, limb)| Coeff {
+                lc: LinearCombination::zero() + limb.get_variable(),
+                value: limb_values.as_ref().map(|values| values[i].clone()),
+            })
+            .collect()
+    }
+
+    /// `self + other` modulo the modulus.
+    pub fn add<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let value = match (&self.value, &other.value) {
+            (Some(a), Some(b)) => Some(a + b),
+            _ => None,
+        };
+
+        let lhs = add_poly(self.poly(), other.poly());
+        self.params
+            .reduce(cs.namespace(|| "reduce"), lhs, vec![], value, 2)
+    }
+
+    /// `self - other` modulo the modulus.
+    pub fn sub<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        // Adding a multiple of the modulus greater than `other` keeps the
+        // integers positive.
+        let multiple = self.params.modulus_multiple();
+        let value = match (&self.value, &other.value) {
+            (Some(a), Some(b)) => Some(&(a + &multiple) - b),
+            _ => None,
+        };
+
+        let multiple = constant_poly(
+            &self.params.limbs_of(&multiple, self.params.n_limbs + 1),
+            CS::one(),
+        );
+        let lhs = add_poly(self.poly(), multiple);
+        self.params
+            .reduce(cs.namespace(|| "reduce"), lhs, other.poly(), value, 2)
+    }
+
+    /// `self * other` modulo the modulus.
+    pub fn mul<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let value = match (&self.value, &other.value) {
+            (Some(a), Some(b)) => Some(a * b),
+            _ => None,
+        };
+
+        // Each product of limbs costs a constraint.
+        let mut lhs = Vec::with_capacity(2 * self.limbs.len() - 1);
+        let limb_values = match (self.limb_values(), other.limb_values()) {
+            (Some(a), Some(b)) => Some((a, b)),
+            _ => None,
+        };
+        for k in 0..2 * self.limbs.len() - 1 {
+            let mut cs = cs.namespace(|| format!("coefficient {}", k));
+
+            let mut coeff = Coeff {
+                lc: LinearCombination::zero(),
+                value: limb_values.as_ref().map(|_| Nat::zero()),
+            };
+            for (i, limb) in self.limbs.iter().enumerate() {
+                if k < i || k - i >= other.limbs.len() {
+                    continue;
+                }
+
+                let product = limb.mul(
+                    cs.namespace(|| format!("product {} {}", i, k - i)),
+                    &other.limbs[k - i],
+                )?;
+                coeff.lc = coeff.lc + product.get_variable();
+                if let (Some(value), Some((a, b))) = (coeff.value.as_mut(), limb_values.as_ref()) {
+                    *value = &*value + &(&a[i] * &b[k - i]);
+                }
+            }
+            lhs.push(coeff);
+        }
+
+        self.params.reduce(
+            cs.namespace(|| "reduce"),
+            lhs,
+            vec![],
+            value,
+            self.limbs.len() + 1,
+        )
+    }
+
+    /// Allocates the constant `value`, with a constraint per limb.
+    pub fn alloc_constant<CS>(
+        mut cs: CS,
+        params: &NonNativeParams,
+        value: &Nat,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let limbs = params
+            .limbs_of(value, params.n_limbs)
+            .iter()
+            .enumerate()
+            .map(|(i, limb_value)| {
+                let limb_value = limb_value.to_field();
+                let limb =
+                    AllocatedNum::alloc(cs.namespace(|| format!("limb {}", i)), || Ok(limb_value))?;
+                cs.enforce(
+                    || format!("limb {} is constant", i),
+                    |lc| lc + limb.get_variable(),
+                    |lc| lc + CS::one(),
+                    |lc| lc + (limb_value, CS::one()),
+                );
+
+                Ok(limb)
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        Ok(AllocatedNonNative {
+            limbs,
+            value: Some(value.clone()),
+            params: params.clone(),
+        })
+    }
+
+    /// Returns `if_true` if `condition` is set and `if_false` otherwise, with
+    /// a constraint per limb.
+    pub fn select<CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        if_true: &Self,
+        if_false: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let value = match condition.get_value() {
+            Some(true) => if_true.value.clone(),
+            Some(false) => if_false.value.clone(),
+            None => None,
+        };
+        let params = &if_true.params;
+        let limb_values = value
+            .as_ref()
+            .map(|value| params.limbs_of(value, params.n_limbs));
+
+        let limbs = if_true
+            .limbs
+            .iter()
+            .zip(&if_false.limbs)
+            .enumerate()
+            .map(|(i, (t, f))| {
+                let limb = AllocatedNum::alloc(cs.namespace(|| format!("limb {}", i)), || {
+                    limb_values
+                        .as_ref()
+                        .map(|values| values[i].to_field())
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+
+                // (t - f) * condition = limb - f
+                cs.enforce(
+                    || format!("selection {}", i),
+                    |lc| lc + t.get_variable() - f.get_variable(),
+                    |_| condition.lc(CS::one(), E::Fr::one()),
+                    |lc| lc + limb.get_variable() - f.get_variable(),
+                );
+
+                Ok(limb)
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        Ok(AllocatedNonNative {
+            limbs,
+            value,
+            params: params.clone(),
+        })
+    }
+
+    /// The `limb_width * n_limbs` little-endian bits of the number.
+    pub fn to_bits_le<CS>(&self, mut cs: CS) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let mut bits = Vec::with_capacity(self.params.limb_width * self.params.n_limbs);
+        for (i, limb) in self.limbs.iter().enumerate() {
+            bits.extend(assert_bits(
+                cs.namespace(|| format!("limb {}", i)),
+                limb,
+                self.params.limb_width,
+            )?);
+        }
+
+        Ok(bits)
+    }
+
+    /// Reinterprets the limbs modulo the modulus of `params`, which must
+    /// have the same limbs.
+    pub fn with_params(&self, params: &NonNativeParams) -> Self {
+        assert_eq!(params.limb_width, self.params.limb_width);
+        assert_eq!(params.n_limbs, self.params.n_limbs);
+
+        AllocatedNonNative {
+            limbs: self.limbs.clone(),
+            value: self.value.clone(),
+            params: params.clone(),
+        }
+    }
+
+    /// Enforces that the number is less than the modulus. Fails with
+    /// `Unsatisfiable` if it is not.
+    pub fn enforce_canonical<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let params = &self.params;
+        let max = &params.modulus - &Nat::from_u64(1);
+
+        // self + difference = modulus - 1, for a non-negative difference
+        let difference = self
+            .value
+            .as_ref()
+            .map(|value| {
+                if *value <= max {
+                    Ok(&max - value)
+                } else {
+                    Err(SynthesisError::Unsatisfiable)
+                }
+            })
+            .transpose()?;
+        let difference =
+            AllocatedNonNative::alloc(cs.namespace(|| "difference"), params, difference)?;
+
+        let lhs = add_poly(self.poly(), difference.poly());
+        let rhs = constant_poly(&params.limbs_of(&max, params.n_limbs), CS::one());
+
+        params.enforce_poly_equal(cs.namespace(|| "sum"), lhs, rhs)
+    }
+
+    /// Enforces that `self` and `other` are congruent modulo the modulus.
+    pub fn enforce_equal<CS>(&self, mut cs: CS, other: &Self) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let params = &self.params;
+        let multiple = params.modulus_multiple();
+
+        // self + multiple = other + q p
+        let quotient = match (&self.value, &other.value) {
+            (Some(a), Some(b)) => Some((&(a + &multiple) - b).div_rem(&params.modulus).0),
+            _ => None,
+        };
+        let q = alloc_limbs(cs.namespace(|| "quotient"), params, quotient.as_ref(), 2)?;
+
+        let lhs = add_poly(
+            self.poly(),
+            constant_poly(&params.limbs_of(&multiple, params.n_limbs + 1), CS::one()),
+        );
+        let rhs = add_poly(
+            other.poly(),
+            mul_poly_constant(
+                &q,
+                quotient.as_ref().map(|q| params.limbs_of(q, 2)).as_deref(),
+                &params.limbs_of(&params.modulus, params.n_limbs),
+            ),
+        );
+
+        params.enforce_poly_equal(cs.namespace(|| "congruence"), lhs, rhs)
+    }
+}
+
+/// Allocates `n` limbs of `value`, range-checked.
+fn alloc_limbs<E, CS>(
+    mut cs: CS,
+    params: &NonNativeParams,
+    value: Option<&Nat>,
+    n: usize,
+) -> Result<Vec<AllocatedNum<E>>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    let limb_values = value.map(|value| params.limbs_of(value, n));
+
+    (0..n)
+        .map(|i| {
+            let mut cs = cs.namespace(|| format!("limb {}", i));
+            let limb = AllocatedNum::alloc(cs.namespace(|| "value"), || {
+                limb_values
+                    .as_ref()
+                    .map(|values| values[i].to_field())
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            assert_bits(cs.namespace(|| "range"), &limb, params.limb_width)?;
+
+            Ok(limb)
+        })
+        .collect()
+}
+
+impl NonNativeParams {
+    /// Witnesses the quotient `q` and remainder `r` of `value`, the value of
+    /// `lhs - rhs`, and enforces `lhs = rhs + q p + r`.
+    fn reduce<E, CS>(
+        &self,
+        mut cs: CS,
+        lhs: Vec<Coeff<E>>,
+        rhs: Vec<Coeff<E>>,
+        value: Option<Nat>,
+        quotient_limbs: usize,
+    ) -> Result<AllocatedNonNative<E>, SynthesisError>
+    where
+        E: ScalarEngine,
+        CS: ConstraintSystem<E>,
+    {
+        let qr = value.map(|value| value.div_rem(&self.modulus));
+        let (quotient, remainder) = match qr {
+            Some((q, r)) => (Some(q), Some(r)),
+            None => (None, None),
+        };
+
+        let q = alloc_limbs(
+            cs.namespace(|| "quotient"),
+            self,
+            quotient.as_ref(),
+            quotient_limbs,
+        )?;
+        let r = AllocatedNonNative::alloc(cs.namespace(|| "remainder"), self, remainder)?;
+
+        let qp = mul_poly_constant(
+            &q,
+            quotient
+                .as_ref()
+                .map(|q| self.limbs_of(q, quotient_limbs))
+                .as_deref(),
+            &self.limbs_of(&self.modulus, self.n_limbs),
+        );
+        let rhs = add_poly(add_poly(rhs, qp), r.poly());
+
+        self.enforce_poly_equal(cs.namespace(|| "reduction"), lhs, rhs)?;
+
+        Ok(r)
+    }
+
+    /// Enforces that two polynomials evaluate to the same integer at
+    /// `2^limb_width`, carrying from each coefficient to the next.
+    fn enforce_poly_equal<E, CS>(
+        &self,
+        mut cs: CS,
+        lhs: Vec<Coeff<E>>,
+        rhs: Vec<Coeff<E>>,
+    ) -> Result<(), SynthesisError>
+    where
+        E: ScalarEngine,
+        CS: ConstraintSystem<E>,
+    {
+        let len = lhs.len().max(rhs.len());
+        let zero = || Coeff {
+            lc: LinearCombination::zero(),
+            value: Some(Nat::zero()),
+        };
+        let lhs = lhs.into_iter().chain(std::iter::repeat_with(zero));
+        let rhs = rhs.into_iter().chain(std::iter::repeat_with(zero));
+
+        // The carries are offset to be non-negative.
+        let offset = &Nat::from_u64(1) << (self.carry_width() - 1);
+        let offset_fr: E::Fr = offset.to_field();
+        let shift_fr: E::Fr = (&Nat::from_u64(1) << self.limb_width).to_field();
+
+        // Prefix sums of both sides, from which the carries follow.
+        let mut sums = Some((Nat::zero(), Nat::zero()));
+        let mut carry: Option<AllocatedNum<E>> = None;
+
+        for (i, (l, r)) in lhs.zip(rhs).take(len).enumerate() {
+            let mut cs = cs.namespace(|| format!("coefficient {}", i));
+
+            sums = match (sums, &l.value, &r.value) {
+                (Some((sum_l, sum_r)), Some(l), Some(r)) => Some((
+                    &sum_l + &(l << (i * self.limb_width)),
+                    &sum_r + &(r << (i * self.limb_width)),
+                )),
+                _ => None,
+            };
+
+            // l + carry_in = r + carry_out 2^limb_width
+            let mut lc = l.lc - &r.lc;
+            if let Some(carry) = &carry {
+                lc = lc + carry.get_variable() - (offset_fr, CS::one());
+            }
+
+            if i + 1 < len {
+                let shift = (i + 1) * self.limb_width;
+                let value = sums.as_ref().map(|(sum_l, sum_r)| {
+                    if sum_l >= sum_r {
+                        &offset + &(&(sum_l - sum_r) >> shift)
+                    } else {
+                        &offset - &(&(sum_r - sum_l) >> shift)
+                    }
+                });
+
+                let carry_out = AllocatedNum::alloc(cs.namespace(|| "carry"), || {
+                    value
+                        .as_ref()
+                        .map(|value| value.to_field())
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                assert_bits(
+                    cs.namespace(|| "carry range"),
+                    &carry_out,
+                    self.carry_width(),
+                )?;
+
+                let mut offset_shifted = offset_fr;
+                offset_shifted.mul_assign(&shift_fr);
+                lc = lc - (shift_fr, carry_out.get_variable()) + (offset_shifted, CS::one());
+                carry = Some(carry_out);
+            }
+
+            cs.enforce(|| "carry", |_| lc, |lc| lc + CS::one(), |lc| lc);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use rand_core::RngCore;
+
+    fn random_nat<R: RngCore>(rng: &mut R, modulus: &Nat) -> Nat {
+        let mut bytes = [0u8; 40];
+        rng.fill_bytes(&mut bytes);
+        Nat::from_bytes_be(&bytes).div_rem(modulus).1
+    }
+
+    #[test]
+    fn test_nat() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let a = rng.next_u64();
+            let b = rng.next_u64() >> (rng.next_u32() % 64);
+            let (na, nb) = (Nat::from_u64(a), Nat::from_u64(b));
+
+            let product = u128::from(a) * u128::from(b);
+            assert_eq!(&na * &nb, Nat::from_bytes_be(&product.to_be_bytes()));
+            assert_eq!(
+                &na + &nb,
+                Nat::from_bytes_be(&(u128::from(a) + u128::from(b)).to_be_bytes())
+            );
+            assert_eq!(&(&na * &nb) >> 64, Nat::from_u64((product >> 64) as u64));
+            assert_eq!(&(&na << 67) >> 67, na);
+            assert_eq!((&na << 67).low_bits(67), Nat::zero());
+            assert_eq!(na.cmp(&nb), a.cmp(&b));
+
+            if b != 0 {
+                assert_eq!(
+                    na.div_rem(&nb),
+                    (Nat::from_u64(a / b), Nat::from_u64(a % b))
+                );
+                let (q, r) = (&na * &na).div_rem(&nb);
+                assert_eq!(&(&q * &nb) + &r, &na * &na);
+                assert!(r < nb);
+            }
+            if a >= b {
+                assert_eq!(&na - &nb, Nat::from_u64(a - b));
+            }
+        }
+
+        let p = crate::secp256k1::base_modulus();
+        for _ in 0..10 {
+            let a = random_nat(&mut rng, &p);
+            let inverse = a.inv_mod(&p).unwrap();
+            assert_eq!((&a * &inverse).div_rem(&p).1, Nat::from_u64(1));
+        }
+        assert!(Nat::zero().inv_mod(&p).is_none());
+        assert!(Nat::from_u64(6).inv_mod(&Nat::from_u64(9)).is_none());
+
+        let bytes = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+        assert_eq!(Nat::from_bytes_be(&bytes).to_bytes_be(12)[3..], bytes);
+        assert_eq!(Nat::from_bytes_be(&bytes).bits(), 65);
+    }
+
+    #[test]
+    fn test_nonnative_ops() {
+        let mut rng = test_rng();
+
+        for params in &[
+            NonNativeParams::secp256k1_base::<Bls12>(),
+            NonNativeParams::secp256k1_scalar::<Bls12>(),
+        ] {
+            let p = params.modulus();
+            for _ in 0..5 {
+                let a = random_nat(&mut rng, p);
+                let b = random_nat(&mut rng, p);
+
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+                let a_num =
+                    AllocatedNonNative::alloc(cs.namespace(|| "a"), params, Some(a.clone()))
+                        .unwrap();
+                let b_num =
+                    AllocatedNonNative::alloc(cs.namespace(|| "b"), params, Some(b.clone()))
+                        .unwrap();
+
+                let sum = a_num.add(cs.namespace(|| "add"), &b_num).unwrap();
+                let difference = a_num.sub(cs.namespace(|| "sub"), &b_num).unwrap();
+                let product = a_num.mul(cs.namespace(|| "mul"), &b_num).unwrap();
+                // Operations chain on unreduced results.
+                let square = product.mul(cs.namespace(|| "square"), &product).unwrap();
+
+                assert!(cs.is_satisfied());
+                assert_eq!(sum.get_value(), Some(&(&a + &b).div_rem(p).1));
+                assert_eq!(difference.get_value(), Some(&(&(&a + p) - &b).div_rem(p).1));
+                let ab = (&a * &b).div_rem(p).1;
+                assert_eq!(product.get_value(), Some(&ab));
+                assert_eq!(square.get_value(), Some(&(&ab * &ab).div_rem(p).1));
+
+                // A wrong remainder does not satisfy the reduction.
+                let limb = cs.get("mul/reduce/remainder/limb 0/value/num");
+                let mut tmp = limb;
+                tmp.add_assign(&Fr::one());
+                cs.set("mul/reduce/remainder/limb 0/value/num", tmp);
+                assert!(!cs.is_satisfied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_enforce_equal() {
+        let params = NonNativeParams::secp256k1_base::<Bls12>();
+        let five = Nat::from_u64(5);
+
+        for (other, congruent) in vec![
+            (five.clone(), true),
+            (&five + params.modulus(), true),
+            (Nat::from_u64(6), false),
+        ] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let a = AllocatedNonNative::alloc(cs.namespace(|| "a"), &params, Some(five.clone()))
+                .unwrap();
+            let b = AllocatedNonNative::alloc(cs.namespace(|| "b"), &params, Some(other)).unwrap();
+
+            a.enforce_equal(cs.namespace(|| "equal"), &b).unwrap();
+            assert_eq!(cs.is_satisfied(), congruent);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "number does not fit in the limbs")]
+    fn test_alloc_out_of_range() {
+        let params = NonNativeParams::secp256k1_base::<Bls12>();
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let value = &Nat::from_u64(1) << 256;
+        let _ = AllocatedNonNative::alloc(cs.namespace(|| "a"), &params, Some(value));
+    }
+}
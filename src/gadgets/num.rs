@@ -0,0 +1,790 @@
+//! Gadgets representing numbers in the scalar field of the underlying curve.
+
+use ff::{BitIterator, Field, PrimeField, PrimeFieldRepr, ScalarEngine};
+
+use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+use super::boolean::{field_into_allocated_bits_le, AllocatedBit, Boolean};
+use super::Assignment;
+
+/// A field element allocated in the constraint system, together with its
+/// value when it is known.
+pub struct AllocatedNum<E: ScalarEngine> {
+    value: Option<E::Fr>,
+    variable: Variable,
+}
+
+impl<E: ScalarEngine> Clone for AllocatedNum<E> {
+    fn clone(&self) -> Self {
+        AllocatedNum {
+            value: self.value,
+            variable: self.variable,
+        }
+    }
+}
+
+impl<E: ScalarEngine> AllocatedNum<E> {
+    pub fn alloc<CS, F>(mut cs: CS, value: F) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+    {
+        let mut new_value = None;
+        let var = cs.alloc(
+            || "num",
+            || {
+                let tmp = value()?;
+
+                new_value = Some(tmp);
+
+                Ok(tmp)
+            },
+        )?;
+
+        Ok(AllocatedNum {
+            value: new_value,
+            variable: var,
+        })
+    }
+
+    /// Exposes this number as a public input, constrained to be equal to it.
+    pub fn inputize<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let input = cs.alloc_input(|| "input variable", || Ok(*self.value.get()?))?;
+
+        cs.enforce(
+            || "enforce input is correct",
+            |lc| lc + input,
+            |lc| lc + CS::one(),
+            |lc| lc + self.variable,
+        );
+
+        Ok(())
+    }
+
+    pub fn mul<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let mut value = None;
+
+        let var = cs.alloc(
+            || "product num",
+            || {
+                let mut tmp = *self.value.get()?;
+                tmp.mul_assign(other.value.get()?);
+
+                value = Some(tmp);
+
+                Ok(tmp)
+            },
+        )?;
+
+        // Constrain: a * b = ab
+        cs.enforce(
+            || "multiplication constraint",
+            |lc| lc + self.variable,
+            |lc| lc + other.variable,
+            |lc| lc + var,
+        );
+
+        Ok(AllocatedNum {
+            value,
+            variable: var,
+        })
+    }
+
+    pub fn square<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let mut value = None;
+
+        let var = cs.alloc(
+            || "squared num",
+            || {
+                let mut tmp = *self.value.get()?;
+                tmp.square();
+
+                value = Some(tmp);
+
+                Ok(tmp)
+            },
+        )?;
+
+        // Constrain: a * a = aa
+        cs.enforce(
+            || "squaring constraint",
+            |lc| lc + self.variable,
+            |lc| lc + self.variable,
+            |lc| lc + var,
+        );
+
+        Ok(AllocatedNum {
+            value,
+            variable: var,
+        })
+    }
+
+    /// Computes the inverse of this number, with one constraint that also
+    /// enforces that it is not zero. Fails with `DivisionByZero` if it is.
+    pub fn inverse<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let mut value = None;
+
+        let var = cs.alloc(
+            || "inverse num",
+            || {
+                let tmp = self
+                    .value
+                    .get()?
+                    .inverse()
+                    .ok_or(SynthesisError::DivisionByZero)?;
+
+                value = Some(tmp);
+
+                Ok(tmp)
+            },
+        )?;
+
+        // Constrain: a * inv = 1
+        cs.enforce(
+            || "inversion constraint",
+            |lc| lc + self.variable,
+            |lc| lc + var,
+            |lc| lc + CS::one(),
+        );
+
+        Ok(AllocatedNum {
+            value,
+            variable: var,
+        })
+    }
+
+    /// Deconstructs this number into little-endian bits. Unlike
+    /// [`to_bits_le_strict`](Self::to_bits_le_strict), this does not enforce
+    /// that they represent a value less than the modulus, so a number has
+    /// two representations when it is small enough.
+    pub fn to_bits_le<CS>(&self, mut cs: CS) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let bits = field_into_allocated_bits_le(&mut cs, self.value)?;
+
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+
+        for bit in bits.iter() {
+            lc = lc + (coeff, bit.get_variable());
+            coeff.double();
+        }
+
+        lc = lc - self.variable;
+
+        cs.enforce(|| "unpacking constraint", |lc| lc, |lc| lc, |_| lc);
+
+        Ok(bits.into_iter().map(Boolean::from).collect())
+    }
+
+    /// Deconstructs this number into its canonical little-endian bits,
+    /// enforcing that they represent a value less than the modulus.
+    pub fn to_bits_le_strict<CS>(&self, mut cs: CS) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        fn kary_and<E, CS>(mut cs: CS, v: &[AllocatedBit]) -> Result<AllocatedBit, SynthesisError>
+        where
+            E: ScalarEngine,
+            CS: ConstraintSystem<E>,
+        {
+            assert!(!v.is_empty());
+
+            let mut cur = v[0].clone();
+            for (i, v) in v.iter().enumerate().skip(1) {
+                cur = AllocatedBit::and(cs.namespace(|| format!("and {}", i)), &cur, v)?;
+            }
+
+            Ok(cur)
+        }
+
+        // We want to ensure that the bit representation of a is
+        // less than or equal to r - 1.
+        let mut a = self.value.map(|e| BitIterator::new(e.into_repr()));
+        let mut b = E::Fr::char();
+        b.sub_noborrow(&1.into());
+
+        let mut result = vec![];
+
+        // Runs of ones in r
+        let mut last_run = None;
+        let mut current_run = vec![];
+
+        let mut found_one = false;
+        let mut i = 0;
+        for b in BitIterator::new(b) {
+            let a_bit = a.as_mut().map(|e| e.next().unwrap());
+
+            // Skip over unset bits at the beginning
+            found_one |= b;
+            if !found_one {
+                // a_bit should also be false
+                if let Some(a_bit) = a_bit {
+                    assert!(!a_bit);
+                }
+                continue;
+            }
+
+            if b {
+                // This is part of a run of ones. Let's just
+                // allocate the boolean with the expected value.
+                let a_bit = AllocatedBit::alloc(cs.namespace(|| format!("bit {}", i)), a_bit)?;
+                // ... and add it to the current run of ones.
+                current_run.push(a_bit.clone());
+                result.push(a_bit);
+            } else {
+                if !current_run.is_empty() {
+                    // This is the start of a run of zeros, but we need
+                    // to k-ary AND against `last_run` first.
+                    if let Some(last_run) = last_run.take() {
+                        current_run.push(last_run);
+                    }
+                    last_run = Some(kary_and(
+                        cs.namespace(|| format!("run ending at {}", i)),
+                        &current_run,
+                    )?);
+                    current_run.truncate(0);
+                }
+
+                // If `last_run` is true, `a` must be false, or it would
+                // not be in the field.
+                //
+                // If `last_run` is false, `a` can be true or false.
+                let a_bit = AllocatedBit::alloc_conditionally(
+                    cs.namespace(|| format!("bit {}", i)),
+                    a_bit,
+                    last_run.as_ref().expect("char always starts with a one"),
+                )?;
+                result.push(a_bit);
+            }
+
+            i += 1;
+        }
+
+        // char is prime, so we'll always end on
+        // a run of zeros.
+        assert_eq!(current_run.len(), 0);
+
+        // Now, we have `result` in big-endian order.
+        // However, now we have to unpack self!
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+
+        for bit in result.iter().rev() {
+            lc = lc + (coeff, bit.get_variable());
+            coeff.double();
+        }
+
+        lc = lc - self.variable;
+
+        cs.enforce(|| "unpacking constraint", |lc| lc, |lc| lc, |_| lc);
+
+        // Convert into booleans, and reverse for little-endian bit order
+        Ok(result.into_iter().map(Boolean::from).rev().collect())
+    }
+
+    /// Enforces that this number is not zero, with one constraint.
+    pub fn assert_nonzero<CS>(&self, mut cs: CS) -> Result<(), SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let inv = cs.alloc(
+            || "ephemeral inverse",
+            || {
+                let tmp = *self.value.get()?;
+
+                if tmp.is_zero() {
+                    Err(SynthesisError::DivisionByZero)
+                } else {
+                    Ok(tmp.inverse().unwrap())
+                }
+            },
+        )?;
+
+        // Constrain a * inv = 1, which is only valid
+        // iff a has a multiplicative inverse, untrue
+        // for zero.
+        cs.enforce(
+            || "nonzero assertion constraint",
+            |lc| lc + self.variable,
+            |lc| lc + inv,
+            |lc| lc + CS::one(),
+        );
+
+        Ok(())
+    }
+
+    /// Returns `a` if `condition` is true and `b` otherwise, with one
+    /// constraint, or none when `condition` is constant.
+    pub fn conditionally_select<CS>(
+        mut cs: CS,
+        condition: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        if let Boolean::Constant(c) = *condition {
+            return Ok(if c { a.clone() } else { b.clone() });
+        }
+
+        let c = Self::alloc(cs.namespace(|| "conditional select result"), || {
+            if *condition.get_value().get()? {
+                Ok(*a.value.get()?)
+            } else {
+                Ok(*b.value.get()?)
+            }
+        })?;
+
+        // (a - b) * condition = c - b
+        cs.enforce(
+            || "conditional select",
+            |lc| lc + a.variable - b.variable,
+            |_| condition.lc(CS::one(), E::Fr::one()),
+            |lc| lc + c.variable - b.variable,
+        );
+
+        Ok(c)
+    }
+
+    /// Takes two allocated numbers (a, b) and returns
+    /// (b, a) if the condition is true, and (a, b)
+    /// otherwise.
+    pub fn conditionally_reverse<CS>(
+        mut cs: CS,
+        a: &Self,
+        b: &Self,
+        condition: &Boolean,
+    ) -> Result<(Self, Self), SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let c = Self::alloc(cs.namespace(|| "conditional reversal result 1"), || {
+            if *condition.get_value().get()? {
+                Ok(*b.value.get()?)
+            } else {
+                Ok(*a.value.get()?)
+            }
+        })?;
+
+        // (b - a) * condition = c - a
+        cs.enforce(
+            || "first conditional reversal",
+            |lc| lc + b.variable - a.variable,
+            |_| condition.lc(CS::one(), E::Fr::one()),
+            |lc| lc + c.variable - a.variable,
+        );
+
+        let d = Self::alloc(cs.namespace(|| "conditional reversal result 2"), || {
+            if *condition.get_value().get()? {
+                Ok(*a.value.get()?)
+            } else {
+                Ok(*b.value.get()?)
+            }
+        })?;
+
+        // a + b = c + d
+        cs.enforce(
+            || "second conditional reversal",
+            |lc| lc + a.variable + b.variable,
+            |lc| lc + CS::one(),
+            |lc| lc + c.variable + d.variable,
+        );
+
+        Ok((c, d))
+    }
+
+    pub fn get_value(&self) -> Option<E::Fr> {
+        self.value
+    }
+
+    pub fn get_variable(&self) -> Variable {
+        self.variable
+    }
+}
+
+/// A linear combination of allocated variables, together with its value
+/// when it is known. Adding to it costs no constraints.
+pub struct Num<E: ScalarEngine> {
+    value: Option<E::Fr>,
+    lc: LinearCombination<E>,
+}
+
+impl<E: ScalarEngine> Clone for Num<E> {
+    fn clone(&self) -> Self {
+        Num {
+            value: self.value,
+            lc: self.lc.clone(),
+        }
+    }
+}
+
+impl<E: ScalarEngine> From<AllocatedNum<E>> for Num<E> {
+    fn from(num: AllocatedNum<E>) -> Num<E> {
+        Num {
+            value: num.value,
+            lc: LinearCombination::<E>::zero() + num.variable,
+        }
+    }
+}
+
+impl<E: ScalarEngine> Num<E> {
+    pub fn zero() -> Self {
+        Num {
+            value: Some(E::Fr::zero()),
+            lc: LinearCombination::zero(),
+        }
+    }
+
+    pub fn get_value(&self) -> Option<E::Fr> {
+        self.value
+    }
+
+    /// The linear combination scaled by `coeff`.
+    pub fn lc(&self, coeff: E::Fr) -> LinearCombination<E> {
+        LinearCombination::zero() + (coeff, &self.lc)
+    }
+
+    /// Adds `bit` times `coeff`.
+    pub fn add_bool_with_coeff(self, one: Variable, bit: &Boolean, coeff: E::Fr) -> Self {
+        let newval = match (self.value, bit.get_value()) {
+            (Some(mut curval), Some(bval)) => {
+                if bval {
+                    curval.add_assign(&coeff);
+                }
+
+                Some(curval)
+            }
+            _ => None,
+        };
+
+        Num {
+            value: newval,
+            lc: self.lc + &bit.lc(one, coeff),
+        }
+    }
+
+    /// Adds `other` times `coeff`.
+    pub fn add_with_coeff(self, other: &Self, coeff: E::Fr) -> Self {
+        let newval = match (self.value, other.value) {
+            (Some(mut curval), Some(mut val)) => {
+                val.mul_assign(&coeff);
+                curval.add_assign(&val);
+
+                Some(curval)
+            }
+            _ => None,
+        };
+
+        Num {
+            value: newval,
+            lc: self.lc + (coeff, &other.lc),
+        }
+    }
+
+    /// Allocates the value, with a constraint equating it to the linear
+    /// combination.
+    pub fn alloc<CS>(&self, mut cs: CS) -> Result<AllocatedNum<E>, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+    {
+        let num = AllocatedNum::alloc(&mut cs, || Ok(*self.value.get()?))?;
+
+        cs.enforce(
+            || "allocation constraint",
+            |_| self.lc.clone(),
+            |lc| lc + CS::one(),
+            |lc| lc + num.variable,
+        );
+
+        Ok(num)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_allocated_num() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        AllocatedNum::alloc(&mut cs, || Ok(Fr::one())).unwrap();
+
+        assert!(cs.get("num") == Fr::one());
+    }
+
+    #[test]
+    fn test_num_squaring() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        let n = AllocatedNum::alloc(&mut cs, || Ok(Fr::from_str("3").unwrap())).unwrap();
+        let n2 = n.square(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert!(cs.get("squared num") == Fr::from_str("9").unwrap());
+        assert!(n2.value.unwrap() == Fr::from_str("9").unwrap());
+        cs.set("squared num", Fr::from_str("10").unwrap());
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_num_multiplication() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        let n =
+            AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from_str("12").unwrap())).unwrap();
+        let n2 =
+            AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::from_str("10").unwrap())).unwrap();
+        let n3 = n.mul(&mut cs, &n2).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert!(cs.get("product num") == Fr::from_str("120").unwrap());
+        assert!(n3.value.unwrap() == Fr::from_str("120").unwrap());
+        cs.set("product num", Fr::from_str("121").unwrap());
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_num_inverse() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        let n = AllocatedNum::alloc(&mut cs, || Ok(Fr::from_str("3").unwrap())).unwrap();
+        let inv = n.inverse(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 1);
+        assert_eq!(inv.get_value(), Fr::from_str("3").unwrap().inverse());
+        cs.set("inverse num", Fr::one());
+        assert!(!cs.is_satisfied());
+
+        // Zero has no inverse.
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let n = AllocatedNum::alloc(&mut cs, || Ok(Fr::zero())).unwrap();
+        assert!(matches!(
+            n.inverse(&mut cs),
+            Err(SynthesisError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn test_num_to_bits_le() {
+        let mut rng = test_rng();
+
+        let r = Fr::random(&mut rng);
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        let n = AllocatedNum::alloc(&mut cs, || Ok(r)).unwrap();
+        let bits = n.to_bits_le(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(bits.len(), Fr::NUM_BITS as usize);
+        assert_eq!(cs.num_constraints(), Fr::NUM_BITS as usize + 1);
+
+        for (b, a) in BitIterator::new(r.into_repr())
+            .skip(1)
+            .zip(bits.iter().rev())
+        {
+            assert_eq!(Some(b), a.get_value());
+        }
+
+        cs.set("num", Fr::random(&mut rng));
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_num() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        let a =
+            AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from_str("5").unwrap())).unwrap();
+        let bit = Boolean::from(AllocatedBit::alloc(cs.namespace(|| "bit"), Some(true)).unwrap());
+
+        // 5 * 3 + 1 * 4 + true * 2
+        let num = Num::zero()
+            .add_with_coeff(&Num::from(a), Fr::from_str("3").unwrap())
+            .add_bool_with_coeff(
+                TestConstraintSystem::<Bls12>::one(),
+                &Boolean::constant(true),
+                Fr::from_str("4").unwrap(),
+            )
+            .add_bool_with_coeff(
+                TestConstraintSystem::<Bls12>::one(),
+                &bit,
+                Fr::from_str("2").unwrap(),
+            );
+        assert_eq!(num.get_value(), Some(Fr::from_str("21").unwrap()));
+
+        let constraints = cs.num_constraints();
+        let allocated = num.alloc(cs.namespace(|| "sum")).unwrap();
+        assert_eq!(cs.num_constraints(), constraints + 1);
+        assert!(cs.is_satisfied());
+        assert_eq!(allocated.get_value(), Some(Fr::from_str("21").unwrap()));
+
+        cs.set("sum/num", Fr::from_str("20").unwrap());
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_into_bits_strict() {
+        let mut negone = Fr::one();
+        negone.negate();
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+
+        let n = AllocatedNum::alloc(&mut cs, || Ok(negone)).unwrap();
+        n.to_bits_le_strict(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+
+        // make the bit representation the characteristic
+        cs.set("bit 254/boolean", Fr::one());
+
+        // this makes the conditional boolean constraint fail
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_into_bits() {
+        let mut rng = test_rng();
+
+        for _ in 0..20 {
+            let r = Fr::random(&mut rng);
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+
+            let n = AllocatedNum::alloc(&mut cs, || Ok(r)).unwrap();
+            let bits = n.to_bits_le_strict(&mut cs).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(bits.len(), 255);
+
+            for (b, a) in BitIterator::new(r.into_repr())
+                .skip(1)
+                .zip(bits.iter().rev())
+            {
+                if let Boolean::Is(ref a) = a {
+                    assert_eq!(b, a.get_value().unwrap());
+                } else {
+                    unreachable!()
+                }
+            }
+
+            cs.set("num", Fr::random(&mut rng));
+            assert!(!cs.is_satisfied());
+            cs.set("num", r);
+            assert!(cs.is_satisfied());
+
+            for i in 0..Fr::NUM_BITS {
+                let name = format!("bit {}/boolean", i);
+                let cur = cs.get(&name);
+                let mut tmp = Fr::one();
+                tmp.sub_assign(&cur);
+                cs.set(&name, tmp);
+                assert!(!cs.is_satisfied());
+                cs.set(&name, cur);
+                assert!(cs.is_satisfied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_conditionally_select() {
+        let mut rng = test_rng();
+
+        for &condition in &[false, true] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+
+            let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::random(&mut rng))).unwrap();
+            let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::random(&mut rng))).unwrap();
+            let allocated: Boolean =
+                AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition))
+                    .unwrap()
+                    .into();
+
+            for (i, condition) in [allocated.clone(), allocated.not()].iter().enumerate() {
+                let c = AllocatedNum::conditionally_select(
+                    cs.namespace(|| format!("select {}", i)),
+                    condition,
+                    &a,
+                    &b,
+                )
+                .unwrap();
+
+                if condition.get_value().unwrap() {
+                    assert_eq!(c.value, a.value);
+                } else {
+                    assert_eq!(c.value, b.value);
+                }
+            }
+
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 3);
+
+            cs.set(
+                "select 0/conditional select result/num",
+                Fr::random(&mut rng),
+            );
+            assert!(!cs.is_satisfied());
+
+            // A constant condition costs nothing.
+            let c = AllocatedNum::conditionally_select(
+                cs.namespace(|| "constant"),
+                &Boolean::constant(condition),
+                &a,
+                &b,
+            )
+            .unwrap();
+            assert_eq!(cs.num_constraints(), 3);
+            assert_eq!(c.value, if condition { a.value } else { b.value });
+        }
+    }
+
+    #[test]
+    fn test_conditionally_reverse() {
+        let mut rng = test_rng();
+
+        for &condition in &[false, true] {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+
+            let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::random(&mut rng))).unwrap();
+            let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::random(&mut rng))).unwrap();
+            let condition: Boolean =
+                AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition))
+                    .unwrap()
+                    .into();
+
+            let (c, d) = AllocatedNum::conditionally_reverse(&mut cs, &a, &b, &condition).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), 3);
+
+            if condition.get_value().unwrap() {
+                assert_eq!(c.value, b.value);
+                assert_eq!(d.value, a.value);
+            } else {
+                assert_eq!(c.value, a.value);
+                assert_eq!(d.value, b.value);
+            }
+
+            cs.set("conditional reversal result 1/num", Fr::random(&mut rng));
+            assert!(!cs.is_satisfied());
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Circuit for the Pedersen hash of the native `crate::jubjub` module.
+
+use super::boolean::Boolean;
+use super::jubjub::{fixed_base_multiplication, EdwardsPoint};
+use crate::bls::Bls12;
+use crate::jubjub::{generator, PEDERSEN_SEGMENT_BITS};
+use crate::{ConstraintSystem, SynthesisError};
+
+/// Computes the Pedersen hash of little-endian `bits` under an 8 byte
+/// `personalization`, as `crate::jubjub::pedersen_hash` does natively.
+pub fn pedersen_hash<CS>(
+    mut cs: CS,
+    personalization: &[u8],
+    bits: &[Boolean],
+) -> Result<EdwardsPoint, SynthesisError>
+where
+    CS: ConstraintSystem<Bls12>,
+{
+    let mut result: Option<EdwardsPoint> = None;
+
+    for (i, segment) in bits.chunks(PEDERSEN_SEGMENT_BITS).enumerate() {
+        let g = generator(personalization, i as u32);
+        let p = fixed_base_multiplication(cs.namespace(|| format!("segment {}", i)), &g, segment)?;
+
+        result = Some(match result {
+            Some(result) => result.add(cs.namespace(|| format!("addition {}", i)), &p)?,
+            None => p,
+        });
+    }
+
+    match result {
+        Some(result) => Ok(result),
+        None => EdwardsPoint::identity(cs.namespace(|| "identity")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::boolean::AllocatedBit;
+    use crate::jubjub::pedersen_hash as native_pedersen_hash;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use rand_core::RngCore;
+
+    const PERSONALIZATION: &[u8] = b"Test_PHG";
+
+    #[test]
+    fn test_pedersen_hash() {
+        let mut rng = test_rng();
+
+        for &len in &[0, 1, 6, 189, 190, 510] {
+            let input = (0..len)
+                .map(|_| rng.next_u32() % 2 == 1)
+                .collect::<Vec<_>>();
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let bits = input
+                .iter()
+                .enumerate()
+                .map(|(i, &b)| {
+                    AllocatedBit::alloc(cs.namespace(|| format!("input bit {}", i)), Some(b))
+                        .unwrap()
+                        .into()
+                })
+                .collect::<Vec<Boolean>>();
+
+            let hash = pedersen_hash(cs.namespace(|| "pedersen"), PERSONALIZATION, &bits).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(
+                hash.get_value(),
+                Some(native_pedersen_hash(PERSONALIZATION, &input))
+            );
+        }
+    }
+}
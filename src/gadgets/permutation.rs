@@ -0,0 +1,406 @@
+//! Gadgets enforcing that a list of allocated numbers is a permutation of
+//! another, with a [Beneš network].
+//!
+//! The prover routes the values through `log2 n` columns of switches on
+//! each side, each switch swapping a pair or not. Any setting of the
+//! switches permutes the values, and every permutation has a setting, so
+//! the switches are witnessed without a challenge. A switch costs three
+//! constraints and a network of `n` values, a power of two,
+//! `n log2 n - n / 2` switches. Other lengths are padded with zeros.
+//!
+//! [Beneš network]: https://en.wikipedia.org/wiki/Bene%C5%A1_network
+
+use ff::{Field, PrimeField, ScalarEngine};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::num::AllocatedNum;
+use crate::{ConstraintSystem, SynthesisError};
+
+/// Number of switches of a network of `n` values, a power of two.
+pub fn num_switches(n: usize) -> usize {
+    assert!(n.is_power_of_two());
+
+    match n {
+        1 => 0,
+        2 => 1,
+        n => n + 2 * num_switches(n / 2),
+    }
+}
+
+/// Appends to `settings` the switch settings routing input `i` to output
+/// `permutation[i]`, in the order `network` consumes them: the input
+/// column, the upper and lower halves, and the output column.
+fn route(permutation: &[usize], settings: &mut Vec<bool>) {
+    let n = permutation.len();
+    match n {
+        1 => return,
+        2 => {
+            settings.push(permutation[0] == 1);
+            return;
+        }
+        _ => {}
+    }
+
+    let mut inverse = vec![0; n];
+    for (i, &p) in permutation.iter().enumerate() {
+        inverse[p] = i;
+    }
+
+    // Whether each input goes through the lower half. The two inputs of a
+    // switch take different halves, and so do the two outputs of a switch,
+    // which the loop propagates until it closes.
+    let mut lower: Vec<Option<bool>> = vec![None; n];
+    for start in (0..n).step_by(2) {
+        let mut x = start;
+        while lower[x].is_none() {
+            lower[x] = Some(false);
+            lower[x ^ 1] = Some(true);
+            x = inverse[permutation[x ^ 1] ^ 1];
+        }
+    }
+    let lower = lower
+        .into_iter()
+        .map(|l| l.expect("every input is routed"))
+        .collect::<Vec<_>>();
+
+    let half = n / 2;
+    let mut upper_permutation = vec![0; half];
+    let mut lower_permutation = vec![0; half];
+    let mut output_settings = vec![false; half];
+    for (x, &p) in permutation.iter().enumerate() {
+        if lower[x] {
+            lower_permutation[x / 2] = p / 2;
+        } else {
+            upper_permutation[x / 2] = p / 2;
+        }
+        if p % 2 == 0 {
+            output_settings[p / 2] = lower[x];
+        }
+    }
+
+    settings.extend((0..half).map(|i| lower[2 * i]));
+    route(&upper_permutation, settings);
+    route(&lower_permutation, settings);
+    settings.extend(output_settings);
+}
+
+/// A switch swapping `a` and `b` on a setting allocated from `settings`.
+fn switch<E, CS, I>(
+    mut cs: CS,
+    a: &AllocatedNum<E>,
+    b: &AllocatedNum<E>,
+    settings: &mut I,
+) -> Result<(AllocatedNum<E>, AllocatedNum<E>), SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+    I: Iterator<Item = Option<bool>>,
+{
+    let setting = settings.next().expect("a setting per switch");
+    let bit = Boolean::from(AllocatedBit::alloc(cs.namespace(|| "setting"), setting)?);
+
+    AllocatedNum::conditionally_reverse(cs.namespace(|| "swap"), a, b, &bit)
+}
+
+/// Routes `values`, a power of two of them, through a network whose
+/// switches are allocated from `settings`.
+fn network<E, CS, I>(
+    mut cs: CS,
+    values: &[AllocatedNum<E>],
+    settings: &mut I,
+) -> Result<Vec<AllocatedNum<E>>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+    I: Iterator<Item = Option<bool>>,
+{
+    let n = values.len();
+    if n == 1 {
+        return Ok(values.to_vec());
+    }
+    if n == 2 {
+        let (a, b) = switch(cs.namespace(|| "switch"), &values[0], &values[1], settings)?;
+        return Ok(vec![a, b]);
+    }
+
+    let mut upper = Vec::with_capacity(n / 2);
+    let mut lower = Vec::with_capacity(n / 2);
+    for (i, pair) in values.chunks(2).enumerate() {
+        let (a, b) = switch(
+            cs.namespace(|| format!("input switch {}", i)),
+            &pair[0],
+            &pair[1],
+            settings,
+        )?;
+        upper.push(a);
+        lower.push(b);
+    }
+
+    let upper = network(cs.namespace(|| "upper"), &upper, settings)?;
+    let lower = network(cs.namespace(|| "lower"), &lower, settings)?;
+
+    let mut outputs = Vec::with_capacity(n);
+    for (j, (a, b)) in upper.iter().zip(&lower).enumerate() {
+        let (a, b) = switch(
+            cs.namespace(|| format!("output switch {}", j)),
+            a,
+            b,
+            settings,
+        )?;
+        outputs.push(a);
+        outputs.push(b);
+    }
+
+    Ok(outputs)
+}
+
+/// Returns `values` permuted so that `values[i]` ends up at position
+/// `permutation[i]`. The result is enforced to be some permutation of
+/// `values`, while `permutation` itself is a witness, `None` when unknown.
+///
+/// Panics if `permutation` is not a permutation of the indices.
+pub fn permute<E, CS>(
+    mut cs: CS,
+    values: &[AllocatedNum<E>],
+    permutation: Option<&[usize]>,
+) -> Result<Vec<AllocatedNum<E>>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    let n = values.len();
+    if n == 0 {
+        return Ok(vec![]);
+    }
+    let padded_len = n.next_power_of_two();
+
+    // Padding values are zero, and stay at the end.
+    let mut padded = values.to_vec();
+    if padded_len > n {
+        let zero = AllocatedNum::alloc(cs.namespace(|| "padding"), || Ok(E::Fr::zero()))?;
+        cs.enforce(
+            || "padding is zero",
+            |lc| lc + zero.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc,
+        );
+        padded.resize(padded_len, zero);
+    }
+
+    let settings = permutation.map(|permutation| {
+        assert_eq!(permutation.len(), n, "wrong permutation length");
+        let mut seen = vec![false; n];
+        for &p in permutation {
+            assert!(p < n && !seen[p], "not a permutation");
+            seen[p] = true;
+        }
+
+        let padded_permutation = permutation
+            .iter()
+            .cloned()
+            .chain(n..padded_len)
+            .collect::<Vec<_>>();
+        let mut settings = Vec::with_capacity(num_switches(padded_len));
+        route(&padded_permutation, &mut settings);
+        settings
+    });
+    let mut settings: Box<dyn Iterator<Item = Option<bool>>> = match settings {
+        Some(settings) => Box::new(settings.into_iter().map(Some)),
+        None => Box::new(std::iter::repeat(None)),
+    };
+
+    let mut outputs = network(cs.namespace(|| "network"), &padded, &mut settings)?;
+
+    // The network may move real values into the padding, so the padding
+    // outputs must be zero for the others to be a permutation of `values`.
+    for (i, output) in outputs[n..].iter().enumerate() {
+        cs.enforce(
+            || format!("padding output {} is zero", i),
+            |lc| lc + output.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc,
+        );
+    }
+    outputs.truncate(n);
+
+    Ok(outputs)
+}
+
+/// Enforces that `b` is a permutation of `a`, whose permutation the prover
+/// finds from the values. If it is not, the constraints are not satisfied.
+pub fn enforce_permutation<E, CS>(
+    mut cs: CS,
+    a: &[AllocatedNum<E>],
+    b: &[AllocatedNum<E>],
+) -> Result<(), SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(a.len(), b.len(), "lists of different lengths");
+
+    let a_values = a.iter().map(|v| v.get_value()).collect::<Option<Vec<_>>>();
+    let b_values = b.iter().map(|v| v.get_value()).collect::<Option<Vec<_>>>();
+    let permutation = match (a_values, b_values) {
+        (Some(a_values), Some(b_values)) => Some(
+            find_permutation::<E>(&a_values, &b_values).unwrap_or_else(|| (0..a.len()).collect()),
+        ),
+        _ => None,
+    };
+
+    let permuted = permute(cs.namespace(|| "permute"), a, permutation.as_deref())?;
+    for (i, (x, y)) in permuted.iter().zip(b).enumerate() {
+        cs.enforce(
+            || format!("equality {}", i),
+            |lc| lc + x.get_variable() - y.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc,
+        );
+    }
+
+    Ok(())
+}
+
+/// A permutation taking `a` to `b`, if there is one, pairing the values in
+/// sorted order.
+fn find_permutation<E: ScalarEngine>(a: &[E::Fr], b: &[E::Fr]) -> Option<Vec<usize>> {
+    let sorted = |values: &[E::Fr]| {
+        let mut indices = (0..values.len()).collect::<Vec<_>>();
+        indices.sort_by_key(|&i| values[i].into_repr());
+        indices
+    };
+
+    let mut permutation = vec![0; a.len()];
+    for (i, j) in sorted(a).into_iter().zip(sorted(b)) {
+        if a[i] != b[j] {
+            return None;
+        }
+        permutation[i] = j;
+    }
+
+    Some(permutation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::metric_cs::MetricCS;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use rand_core::RngCore;
+
+    fn alloc_nums<CS: ConstraintSystem<Bls12>>(
+        mut cs: CS,
+        values: &[Fr],
+    ) -> Vec<AllocatedNum<Bls12>> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                AllocatedNum::alloc(cs.namespace(|| format!("value {}", i)), || Ok(*v)).unwrap()
+            })
+            .collect()
+    }
+
+    fn random_permutation<R: RngCore>(rng: &mut R, n: usize) -> Vec<usize> {
+        let mut permutation = (0..n).collect::<Vec<_>>();
+        for i in (1..n).rev() {
+            permutation.swap(i, rng.next_u32() as usize % (i + 1));
+        }
+        permutation
+    }
+
+    #[test]
+    fn test_permute() {
+        let mut rng = test_rng();
+
+        for n in 1..=20 {
+            for _ in 0..5 {
+                let values = (0..n).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+                let permutation = random_permutation(&mut rng, n);
+
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+                let nums = alloc_nums(cs.namespace(|| "values"), &values);
+                let permuted =
+                    permute(cs.namespace(|| "permute"), &nums, Some(&permutation)).unwrap();
+
+                assert!(cs.is_satisfied());
+                for (i, &p) in permutation.iter().enumerate() {
+                    assert_eq!(permuted[p].get_value(), Some(values[i]));
+                }
+
+                let padded_len = n.next_power_of_two();
+                let padding = if padded_len > n {
+                    1 + padded_len - n
+                } else {
+                    0
+                };
+                assert_eq!(cs.num_constraints(), 3 * num_switches(padded_len) + padding);
+            }
+        }
+    }
+
+    #[test]
+    fn test_num_switches() {
+        assert_eq!(num_switches(1), 0);
+        assert_eq!(num_switches(2), 1);
+        assert_eq!(num_switches(4), 6);
+        assert_eq!(num_switches(8), 20);
+        assert_eq!(num_switches(1024), 1024 * 10 - 512);
+    }
+
+    #[test]
+    fn test_enforce_permutation() {
+        let mut rng = test_rng();
+
+        // Repeated values must be matched once each.
+        let mut values = (0..6).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        values[4] = values[1];
+        let permutation = random_permutation(&mut rng, 6);
+        let mut shuffled = values.clone();
+        for (i, &p) in permutation.iter().enumerate() {
+            shuffled[p] = values[i];
+        }
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let a = alloc_nums(cs.namespace(|| "a"), &values);
+        let b = alloc_nums(cs.namespace(|| "b"), &shuffled);
+        enforce_permutation(cs.namespace(|| "permutation"), &a, &b).unwrap();
+        assert!(cs.is_satisfied());
+
+        // Changing a value breaks it.
+        let mut other = shuffled.clone();
+        other[0] = Fr::random(&mut rng);
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let a = alloc_nums(cs.namespace(|| "a"), &values);
+        let b = alloc_nums(cs.namespace(|| "b"), &other);
+        enforce_permutation(cs.namespace(|| "permutation"), &a, &b).unwrap();
+        assert!(!cs.is_satisfied());
+
+        // So does duplicating one value over another.
+        let mut other = values.clone();
+        other[0] = other[2];
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let a = alloc_nums(cs.namespace(|| "a"), &values);
+        let b = alloc_nums(cs.namespace(|| "b"), &other);
+        enforce_permutation(cs.namespace(|| "permutation"), &a, &b).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_permutation_shape_without_values() {
+        let mut cs = MetricCS::<Bls12>::new();
+        let values = (0..8)
+            .map(|i| {
+                AllocatedNum::alloc(cs.namespace(|| format!("value {}", i)), || {
+                    Err(SynthesisError::AssignmentMissing)
+                })
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        enforce_permutation(cs.namespace(|| "permutation"), &values[..4], &values[4..]).unwrap();
+        assert_eq!(cs.num_constraints(), 3 * 6 + 4);
+    }
+}
@@ -0,0 +1,286 @@
+//! Circuit for the [Poseidon] hash function, computing the same digests as
+//! the native `crate::poseidon` module.
+//!
+//! The state is kept as linear combinations between S-boxes, so adding the
+//! round constants and multiplying by the MDS matrix cost no constraints.
+//! Each S-box takes three constraints, for a total of
+//! `3 * (full_rounds * width + partial_rounds) + 1` per hash.
+//!
+//! [Poseidon]: https://eprint.iacr.org/2019/458
+
+use ff::{Field, ScalarEngine};
+
+use super::num::AllocatedNum;
+use super::Assignment;
+use crate::poseidon::PoseidonConstants;
+use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+/// An element of the state: a linear combination and its value. Shared with
+/// `gadgets::mimc`.
+pub(super) struct Elt<E: ScalarEngine> {
+    pub(super) lc: LinearCombination<E>,
+    pub(super) value: Option<E::Fr>,
+}
+
+impl<E: ScalarEngine> Clone for Elt<E> {
+    fn clone(&self) -> Self {
+        Elt {
+            lc: self.lc.clone(),
+            value: self.value,
+        }
+    }
+}
+
+impl<E: ScalarEngine> Elt<E> {
+    pub(super) fn constant(value: E::Fr, one: Variable) -> Self {
+        Elt {
+            lc: LinearCombination::zero() + (value, one),
+            value: Some(value),
+        }
+    }
+
+    pub(super) fn num(num: &AllocatedNum<E>) -> Self {
+        Elt {
+            lc: LinearCombination::zero() + num.get_variable(),
+            value: num.get_value(),
+        }
+    }
+
+    pub(super) fn add(self, other: &Self) -> Self {
+        Elt {
+            lc: self.lc + &other.lc,
+            value: self.value.and_then(|mut v| {
+                other.value.map(|o| {
+                    v.add_assign(&o);
+                    v
+                })
+            }),
+        }
+    }
+
+    pub(super) fn add_constant(self, constant: &E::Fr, one: Variable) -> Self {
+        Elt {
+            lc: self.lc + (*constant, one),
+            value: self.value.map(|mut v| {
+                v.add_assign(constant);
+                v
+            }),
+        }
+    }
+
+    /// Allocates the value of the linear combination, with a constraint.
+    pub(super) fn into_num<CS: ConstraintSystem<E>>(
+        self,
+        mut cs: CS,
+    ) -> Result<AllocatedNum<E>, SynthesisError> {
+        let Elt { lc, value } = self;
+        let num = AllocatedNum::alloc(cs.namespace(|| "value"), || Ok(*value.get()?))?;
+        cs.enforce(
+            || "value computation",
+            |_| lc,
+            |lc| lc + CS::one(),
+            |lc| lc + num.get_variable(),
+        );
+
+        Ok(num)
+    }
+
+    /// Computes `self^5` with three constraints.
+    pub(super) fn sbox<CS: ConstraintSystem<E>>(self, mut cs: CS) -> Result<Self, SynthesisError> {
+        let x2 = square(cs.namespace(|| "x^2"), &self.lc, self.value)?;
+        let x4 = square(
+            cs.namespace(|| "x^4"),
+            &(LinearCombination::zero() + x2.get_variable()),
+            x2.get_value(),
+        )?;
+
+        let value = x4.get_value().and_then(|x4| {
+            self.value.map(|x| {
+                let mut x5 = x4;
+                x5.mul_assign(&x);
+                x5
+            })
+        });
+        let x5 = cs.alloc(|| "x^5", || Ok(*value.get()?))?;
+        cs.enforce(
+            || "x^5 = x^4 * x",
+            |lc| lc + x4.get_variable(),
+            |_| self.lc,
+            |lc| lc + x5,
+        );
+
+        Ok(Elt {
+            lc: LinearCombination::zero() + x5,
+            value,
+        })
+    }
+}
+
+fn square<E: ScalarEngine, CS: ConstraintSystem<E>>(
+    mut cs: CS,
+    lc: &LinearCombination<E>,
+    value: Option<E::Fr>,
+) -> Result<AllocatedNum<E>, SynthesisError> {
+    let square = AllocatedNum::alloc(&mut cs, || {
+        let mut tmp = *value.get()?;
+        tmp.square();
+        Ok(tmp)
+    })?;
+    cs.enforce(
+        || "squaring",
+        |_| lc.clone(),
+        |_| lc.clone(),
+        |lc| lc + square.get_variable(),
+    );
+
+    Ok(square)
+}
+
+/// Computes the Poseidon digest of `inputs`, of which there must be
+/// `constants.arity()`.
+pub fn poseidon<E, CS>(
+    mut cs: CS,
+    constants: &PoseidonConstants<E>,
+    inputs: &[AllocatedNum<E>],
+) -> Result<AllocatedNum<E>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert_eq!(inputs.len(), constants.arity(), "wrong number of inputs");
+
+    let width = constants.width();
+    let rounds = constants.full_rounds() + constants.partial_rounds();
+
+    let mut state = Some(Elt::constant(constants.domain_tag(), CS::one()))
+        .into_iter()
+        .chain(inputs.iter().map(Elt::num))
+        .collect::<Vec<_>>();
+
+    for (round, round_constants) in constants
+        .round_constants()
+        .chunks(width)
+        .enumerate()
+        .take(rounds)
+    {
+        let mut cs = cs.namespace(|| format!("round {}", round));
+
+        let full = constants.is_full_round(round);
+        state = state
+            .into_iter()
+            .zip(round_constants)
+            .enumerate()
+            .map(|(i, (elt, constant))| {
+                let elt = elt.add_constant(constant, CS::one());
+                if full || i == 0 {
+                    elt.sbox(cs.namespace(|| format!("sbox {}", i)))
+                } else {
+                    Ok(elt)
+                }
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        state = constants
+            .mds()
+            .iter()
+            .map(|row| {
+                row.iter().zip(state.iter()).fold(
+                    Elt {
+                        lc: LinearCombination::zero(),
+                        value: Some(E::Fr::zero()),
+                    },
+                    |acc, (m, elt)| {
+                        let value = acc.value.and_then(|mut acc| {
+                            elt.value.map(|v| {
+                                let mut tmp = *m;
+                                tmp.mul_assign(&v);
+                                acc.add_assign(&tmp);
+                                acc
+                            })
+                        });
+                        Elt {
+                            lc: acc.lc + (*m, &elt.lc),
+                            value,
+                        }
+                    },
+                )
+            })
+            .collect();
+    }
+
+    let Elt { lc, value } = state.swap_remove(1);
+    let digest = AllocatedNum::alloc(cs.namespace(|| "digest"), || Ok(*value.get()?))?;
+    cs.enforce(
+        || "digest computation",
+        |_| lc,
+        |lc| lc + CS::one(),
+        |lc| lc + digest.get_variable(),
+    );
+
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::poseidon::poseidon as native_poseidon;
+    use crate::util_cs::metric_cs::MetricCS;
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_poseidon_matches_native() {
+        let mut rng = test_rng();
+
+        for &arity in &[1, 2, 4, 8] {
+            let constants = PoseidonConstants::<Bls12>::new(arity);
+            let values = (0..arity).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let inputs = values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    AllocatedNum::alloc(cs.namespace(|| format!("input {}", i)), || Ok(*v)).unwrap()
+                })
+                .collect::<Vec<_>>();
+
+            let digest = poseidon(cs.namespace(|| "poseidon"), &constants, &inputs).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(
+                digest.get_value().unwrap(),
+                native_poseidon(&constants, &values)
+            );
+            assert_eq!(
+                cs.num_constraints(),
+                3 * (constants.full_rounds() * constants.width() + constants.partial_rounds()) + 1
+            );
+
+            // A wrong digest does not satisfy the circuit.
+            cs.set("poseidon/digest/num", Fr::one());
+            assert!(!cs.is_satisfied());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_shape_without_values() {
+        let constants = PoseidonConstants::<Bls12>::new(2);
+
+        let mut cs = MetricCS::<Bls12>::new();
+        let inputs = (0..2)
+            .map(|i| {
+                AllocatedNum::alloc(cs.namespace(|| format!("input {}", i)), || {
+                    Err(SynthesisError::AssignmentMissing)
+                })
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let digest = poseidon(cs.namespace(|| "poseidon"), &constants, &inputs).unwrap();
+
+        assert!(digest.get_value().is_none());
+        assert_eq!(cs.num_constraints(), 244);
+    }
+}
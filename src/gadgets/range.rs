@@ -0,0 +1,260 @@
+//! Range checks and comparisons of allocated numbers by bit decomposition.
+//!
+//! Checking that a number fits in `n` bits costs `n + 1` constraints: one per
+//! bit and one packing the bits back into the number. Comparing two numbers
+//! of `n` bits decomposes their difference shifted by `2^n` into `n + 1`
+//! bits, whose top bit is set exactly when the first is not less than the
+//! second, for `n + 2` constraints.
+//!
+//! The comparisons assume their operands fit in `n` bits, which the caller
+//! must enforce, for example with [`assert_bits`].
+
+use ff::{BitIterator, Field, PrimeField, ScalarEngine};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::num::AllocatedNum;
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+/// Decomposes the value of `lc` into `n` little-endian bits, enforcing that
+/// they pack into it.
+pub(crate) fn decompose<E, CS>(
+    mut cs: CS,
+    lc: LinearCombination<E>,
+    value: Option<E::Fr>,
+    n: usize,
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert!(
+        n <= E::Fr::CAPACITY as usize,
+        "cannot decompose into more bits than the field capacity"
+    );
+
+    let values = match value {
+        Some(value) => {
+            let mut bits = BitIterator::new(value.into_repr()).collect::<Vec<_>>();
+            bits.reverse();
+            bits.into_iter().take(n).map(Some).collect()
+        }
+        None => vec![None; n],
+    };
+
+    let bits = values
+        .into_iter()
+        .enumerate()
+        .map(|(i, b)| AllocatedBit::alloc(cs.namespace(|| format!("bit {}", i)), b))
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    let mut packed = LinearCombination::zero();
+    let mut coeff = E::Fr::one();
+    for bit in &bits {
+        packed = packed + (coeff, bit.get_variable());
+        coeff.double();
+    }
+
+    cs.enforce(
+        || "packing constraint",
+        |_| packed,
+        |lc| lc + CS::one(),
+        |_| lc,
+    );
+
+    Ok(bits.into_iter().map(Boolean::from).collect())
+}
+
+/// Enforces that `num` is less than `2^n`, returning its `n` little-endian
+/// bits.
+///
+/// Panics if `n` exceeds the capacity of the field.
+pub fn assert_bits<E, CS>(
+    cs: CS,
+    num: &AllocatedNum<E>,
+    n: usize,
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    decompose(
+        cs,
+        LinearCombination::zero() + num.get_variable(),
+        num.get_value(),
+        n,
+    )
+}
+
+/// Whether `a < b`, for `a` and `b` less than `2^n`.
+///
+/// Panics unless `n` is less than the capacity of the field.
+pub fn less_than<E, CS>(
+    cs: CS,
+    a: &AllocatedNum<E>,
+    b: &AllocatedNum<E>,
+    n: usize,
+) -> Result<Boolean, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    Ok(greater_equal(cs, a, b, n)?.not())
+}
+
+/// Whether `a >= b`, for `a` and `b` less than `2^n`.
+///
+/// Panics unless `n` is less than the capacity of the field.
+pub fn greater_equal<E, CS>(
+    mut cs: CS,
+    a: &AllocatedNum<E>,
+    b: &AllocatedNum<E>,
+    n: usize,
+) -> Result<Boolean, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    assert!(
+        n < E::Fr::CAPACITY as usize,
+        "cannot compare numbers of as many bits as the field capacity"
+    );
+
+    let mut shift = E::Fr::one();
+    for _ in 0..n {
+        shift.double();
+    }
+
+    // a - b + 2^n is in [1, 2^(n + 1)), and at least 2^n iff a >= b.
+    let value = match (a.get_value(), b.get_value()) {
+        (Some(a), Some(b)) => {
+            let mut tmp = a;
+            tmp.sub_assign(&b);
+            tmp.add_assign(&shift);
+            Some(tmp)
+        }
+        _ => None,
+    };
+
+    let lc = LinearCombination::zero() + a.get_variable() - b.get_variable() + (shift, CS::one());
+    let mut bits = decompose(cs.namespace(|| "difference"), lc, value, n + 1)?;
+
+    Ok(bits.pop().expect("n + 1 bits"))
+}
+
+/// Whether `a <= b`, for `a` and `b` less than `2^n`.
+pub fn less_equal<E, CS>(
+    cs: CS,
+    a: &AllocatedNum<E>,
+    b: &AllocatedNum<E>,
+    n: usize,
+) -> Result<Boolean, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    greater_equal(cs, b, a, n)
+}
+
+/// Whether `a > b`, for `a` and `b` less than `2^n`.
+pub fn greater_than<E, CS>(
+    cs: CS,
+    a: &AllocatedNum<E>,
+    b: &AllocatedNum<E>,
+    n: usize,
+) -> Result<Boolean, SynthesisError>
+where
+    E: ScalarEngine,
+    CS: ConstraintSystem<E>,
+{
+    less_than(cs, b, a, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr, FrRepr};
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use rand_core::RngCore;
+
+    fn alloc(cs: &mut TestConstraintSystem<Bls12>, name: &str, value: u64) -> AllocatedNum<Bls12> {
+        AllocatedNum::alloc(cs.namespace(|| name), || {
+            Ok(Fr::from_repr(FrRepr::from(value)).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_assert_bits() {
+        let mut rng = test_rng();
+
+        for &n in &[1, 8, 32, 63] {
+            let value = rng.next_u64() & ((1 << n) - 1);
+
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let num = alloc(&mut cs, "num", value);
+            let bits = assert_bits(cs.namespace(|| "range"), &num, n).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(cs.num_constraints(), n + 1);
+            for (i, bit) in bits.iter().enumerate() {
+                assert_eq!(bit.get_value(), Some((value >> i) & 1 == 1));
+            }
+
+            // A number of n + 1 bits is out of range.
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let num = alloc(&mut cs, "num", value | (1 << n));
+            assert_bits(cs.namespace(|| "range"), &num, n).unwrap();
+            assert!(!cs.is_satisfied());
+        }
+    }
+
+    #[test]
+    fn test_assert_bits_wraparound() {
+        // -1 has all of its low bits set, but does not fit in them.
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        let num = AllocatedNum::alloc(cs.namespace(|| "num"), || {
+            let mut tmp = Fr::one();
+            tmp.negate();
+            Ok(tmp)
+        })
+        .unwrap();
+        assert_bits(cs.namespace(|| "range"), &num, 64).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let mut rng = test_rng();
+
+        let mut cases = vec![(0, 0), (0, 1), (1, 0), (u64::max_value(), u64::max_value())];
+        for _ in 0..20 {
+            let a = rng.next_u64();
+            cases.push((a, rng.next_u64()));
+            cases.push((a, a));
+        }
+
+        for (a, b) in cases {
+            let mut cs = TestConstraintSystem::<Bls12>::new();
+            let a_num = alloc(&mut cs, "a", a);
+            let b_num = alloc(&mut cs, "b", b);
+
+            let lt = less_than(cs.namespace(|| "lt"), &a_num, &b_num, 64).unwrap();
+            assert_eq!(cs.num_constraints(), 64 + 2);
+            let ge = greater_equal(cs.namespace(|| "ge"), &a_num, &b_num, 64).unwrap();
+            let le = less_equal(cs.namespace(|| "le"), &a_num, &b_num, 64).unwrap();
+            let gt = greater_than(cs.namespace(|| "gt"), &a_num, &b_num, 64).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(lt.get_value(), Some(a < b));
+            assert_eq!(ge.get_value(), Some(a >= b));
+            assert_eq!(le.get_value(), Some(a <= b));
+            assert_eq!(gt.get_value(), Some(a > b));
+
+            // The result cannot be flipped.
+            let flipped = if a >= b { Fr::zero() } else { Fr::one() };
+            cs.set("lt/difference/bit 64/boolean", flipped);
+            assert!(!cs.is_satisfied());
+        }
+    }
+}
@@ -0,0 +1,660 @@
+//! The gadgets of the unsigned integers, generated for each width by
+//! `uint_impl!` so that `UInt32` and `UInt64` share one implementation.
+
+// `$name` holds `$bits` bits, the width of `$native`. `addmany` sums in
+// `$wide`, which has twice as many bits.
+macro_rules! uint_impl {
+    ($name:ident, $native:ident, $wide:ident, $bits:expr, $doc:literal) => {
+        use ff::{Field, PrimeField, ScalarEngine};
+
+        use crate::gadgets::boolean::{AllocatedBit, Boolean};
+        use crate::gadgets::multieq::MultiEq;
+        use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+        #[doc = $doc]
+        #[derive(Clone, Debug)]
+        pub struct $name {
+            // Least significant bit first
+            bits: Vec<Boolean>,
+            value: Option<$native>,
+        }
+
+        impl $name {
+            /// Construct a constant from its value.
+            pub fn constant(value: $native) -> Self {
+                let bits = (0..$bits)
+                    .map(|i| Boolean::constant(value >> i & 1 == 1))
+                    .collect();
+
+                $name {
+                    bits,
+                    value: Some(value),
+                }
+            }
+
+            /// Allocate the bits of `value` in the constraint system.
+            pub fn alloc<E, CS>(mut cs: CS, value: Option<$native>) -> Result<Self, SynthesisError>
+            where
+                E: ScalarEngine,
+                CS: ConstraintSystem<E>,
+            {
+                let values = match value {
+                    Some(val) => (0..$bits).map(|i| Some(val >> i & 1 == 1)).collect(),
+                    None => vec![None; $bits],
+                };
+
+                let bits = values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        Ok(Boolean::from(AllocatedBit::alloc(
+                            cs.namespace(|| format!("allocated bit {}", i)),
+                            v,
+                        )?))
+                    })
+                    .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+                Ok($name { bits, value })
+            }
+
+            pub fn get_value(&self) -> Option<$native> {
+                self.value
+            }
+
+            pub fn into_bits_be(self) -> Vec<Boolean> {
+                let mut ret = self.bits;
+                ret.reverse();
+                ret
+            }
+
+            pub fn from_bits_be(bits: &[Boolean]) -> Self {
+                assert_eq!(bits.len(), $bits);
+
+                let mut bits = bits.to_vec();
+                bits.reverse();
+
+                Self::from_bits(&bits)
+            }
+
+            /// Turns this integer into its little-endian byte order representation.
+            pub fn into_bits(self) -> Vec<Boolean> {
+                self.bits
+            }
+
+            /// Converts a little-endian byte order representation of bits into an
+            /// integer.
+            pub fn from_bits(bits: &[Boolean]) -> Self {
+                assert_eq!(bits.len(), $bits);
+
+                let new_bits = bits.to_vec();
+
+                let mut value: Option<$native> = Some(0);
+                for b in new_bits.iter().rev() {
+                    value = match (value, b.get_value()) {
+                        (Some(v), Some(b)) => Some(v << 1 | $native::from(b)),
+                        _ => None,
+                    };
+                }
+
+                $name {
+                    value,
+                    bits: new_bits,
+                }
+            }
+
+            pub fn rotr(&self, by: usize) -> Self {
+                let by = by % $bits;
+
+                let new_bits = self
+                    .bits
+                    .iter()
+                    .skip(by)
+                    .chain(self.bits.iter())
+                    .take($bits)
+                    .cloned()
+                    .collect();
+
+                $name {
+                    bits: new_bits,
+                    value: self.value.map(|v| v.rotate_right(by as u32)),
+                }
+            }
+
+            pub fn rotl(&self, by: usize) -> Self {
+                self.rotr($bits - by % $bits)
+            }
+
+            pub fn shr(&self, by: usize) -> Self {
+                let by = by % $bits;
+
+                let fill = Boolean::constant(false);
+
+                let new_bits = self
+                    .bits
+                    .iter() // The bits are least significant first
+                    .skip(by) // Skip the bits that will be lost during the shift
+                    .chain(Some(&fill).into_iter().cycle()) // Rest will be zeros
+                    .take($bits) // Only the width is needed!
+                    .cloned()
+                    .collect();
+
+                $name {
+                    bits: new_bits,
+                    value: self.value.map(|v| v >> by as u32),
+                }
+            }
+
+            pub fn shl(&self, by: usize) -> Self {
+                let by = by % $bits;
+
+                let fill = Boolean::constant(false);
+
+                let new_bits = Some(&fill)
+                    .into_iter()
+                    .cycle()
+                    .take(by) // The vacated low bits are zeros
+                    .chain(self.bits.iter())
+                    .take($bits)
+                    .cloned()
+                    .collect();
+
+                $name {
+                    bits: new_bits,
+                    value: self.value.map(|v| v << by as u32),
+                }
+            }
+
+            fn triop<E, CS, F, U>(
+                mut cs: CS,
+                a: &Self,
+                b: &Self,
+                c: &Self,
+                tri_fn: F,
+                circuit_fn: U,
+            ) -> Result<Self, SynthesisError>
+            where
+                E: ScalarEngine,
+                CS: ConstraintSystem<E>,
+                F: Fn($native, $native, $native) -> $native,
+                U: Fn(
+                    &mut CS,
+                    usize,
+                    &Boolean,
+                    &Boolean,
+                    &Boolean,
+                ) -> Result<Boolean, SynthesisError>,
+            {
+                let new_value = match (a.value, b.value, c.value) {
+                    (Some(a), Some(b), Some(c)) => Some(tri_fn(a, b, c)),
+                    _ => None,
+                };
+
+                let bits = a
+                    .bits
+                    .iter()
+                    .zip(b.bits.iter())
+                    .zip(c.bits.iter())
+                    .enumerate()
+                    .map(|(i, ((a, b), c))| circuit_fn(&mut cs, i, a, b, c))
+                    .collect::<Result<_, _>>()?;
+
+                Ok($name {
+                    bits,
+                    value: new_value,
+                })
+            }
+
+            /// Compute the `maj` value (a and b) xor (a and c) xor (b and c)
+            /// during SHA-2.
+            pub fn sha256_maj<E, CS>(
+                cs: CS,
+                a: &Self,
+                b: &Self,
+                c: &Self,
+            ) -> Result<Self, SynthesisError>
+            where
+                E: ScalarEngine,
+                CS: ConstraintSystem<E>,
+            {
+                Self::triop(
+                    cs,
+                    a,
+                    b,
+                    c,
+                    |a, b, c| (a & b) ^ (a & c) ^ (b & c),
+                    |cs, i, a, b, c| {
+                        Boolean::sha256_maj(cs.namespace(|| format!("maj {}", i)), a, b, c)
+                    },
+                )
+            }
+
+            /// Compute the `ch` value `(a and b) xor ((not a) and c)`
+            /// during SHA-2.
+            pub fn sha256_ch<E, CS>(
+                cs: CS,
+                a: &Self,
+                b: &Self,
+                c: &Self,
+            ) -> Result<Self, SynthesisError>
+            where
+                E: ScalarEngine,
+                CS: ConstraintSystem<E>,
+            {
+                Self::triop(
+                    cs,
+                    a,
+                    b,
+                    c,
+                    |a, b, c| (a & b) ^ ((!a) & c),
+                    |cs, i, a, b, c| {
+                        Boolean::sha256_ch(cs.namespace(|| format!("ch {}", i)), a, b, c)
+                    },
+                )
+            }
+
+            /// XOR this integer with another one.
+            pub fn xor<E, CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+            where
+                E: ScalarEngine,
+                CS: ConstraintSystem<E>,
+            {
+                let new_value = match (self.value, other.value) {
+                    (Some(a), Some(b)) => Some(a ^ b),
+                    _ => None,
+                };
+
+                let bits = self
+                    .bits
+                    .iter()
+                    .zip(other.bits.iter())
+                    .enumerate()
+                    .map(|(i, (a, b))| {
+                        Boolean::xor(cs.namespace(|| format!("xor of bit {}", i)), a, b)
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Ok($name {
+                    bits,
+                    value: new_value,
+                })
+            }
+
+            /// Perform modular addition of several integers.
+            pub fn addmany<E, CS, M>(mut cs: M, operands: &[Self]) -> Result<Self, SynthesisError>
+            where
+                E: ScalarEngine,
+                CS: ConstraintSystem<E>,
+                M: ConstraintSystem<E, Root = MultiEq<E, CS>>,
+            {
+                // Make some arbitrary bounds for ourselves to avoid overflows
+                // in the scalar field
+                assert!(E::Fr::NUM_BITS >= 2 * $bits);
+                assert!(operands.len() >= 2); // Weird trivial cases that should never happen
+                assert!(operands.len() <= 10);
+
+                // Compute the maximum value of the sum so we allocate enough bits for
+                // the result
+                let mut max_value = (operands.len() as $wide) * ($wide::from($native::max_value()));
+
+                // Keep track of the resulting value
+                let mut result_value: Option<$wide> = Some(0);
+
+                // This is a linear combination that we will enforce to equal the
+                // output
+                let mut lc = LinearCombination::zero();
+
+                let mut all_constants = true;
+
+                // Iterate over the operands
+                for op in operands {
+                    // Accumulate the value
+                    result_value = match (result_value, op.value) {
+                        (Some(v), Some(val)) => Some(v + $wide::from(val)),
+                        _ => None,
+                    };
+
+                    // Iterate over each bit of the operand and add the operand to
+                    // the linear combination
+                    let mut coeff = E::Fr::one();
+                    for bit in &op.bits {
+                        lc = lc + &bit.lc(CS::one(), coeff);
+
+                        all_constants &= bit.is_constant();
+
+                        coeff.double();
+                    }
+                }
+
+                // The value of the actual result is modulo 2^bits
+                let modular_value = result_value.map(|v| v as $native);
+
+                if all_constants && modular_value.is_some() {
+                    // We can just return a constant, rather than
+                    // unpacking the result into allocated bits.
+
+                    return Ok($name::constant(modular_value.unwrap()));
+                }
+
+                // Storage area for the resulting bits
+                let mut result_bits = vec![];
+
+                // Linear combination representing the output,
+                // for comparison with the sum of the operands
+                let mut result_lc = LinearCombination::zero();
+
+                // Allocate each bit of the result
+                let mut coeff = E::Fr::one();
+                let mut i = 0;
+                while max_value != 0 {
+                    // Allocate the bit
+                    let b = AllocatedBit::alloc(
+                        cs.namespace(|| format!("result bit {}", i)),
+                        result_value.map(|v| (v >> i) & 1 == 1),
+                    )?;
+
+                    // Add this bit to the result combination
+                    result_lc = result_lc + (coeff, b.get_variable());
+
+                    result_bits.push(b.into());
+
+                    max_value >>= 1;
+                    i += 1;
+                    coeff.double();
+                }
+
+                // Enforce equality between the sum and result
+                cs.get_root().enforce_equal(i, &lc, &result_lc);
+
+                // Discard carry bits that we don't care about
+                result_bits.truncate($bits);
+
+                Ok($name {
+                    bits: result_bits,
+                    value: modular_value,
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+macro_rules! uint_tests {
+    ($name:ident, $native:ident, $next:ident, $bits:expr) => {
+        use super::$name;
+        use crate::bls::Bls12;
+        use crate::gadgets::boolean::Boolean;
+        use crate::gadgets::multieq::MultiEq;
+        use crate::util_cs::test_cs::TestConstraintSystem;
+        use crate::util_cs::test_fixtures::test_rng;
+        use crate::ConstraintSystem;
+        use ff::Field;
+        use rand_core::RngCore;
+
+        fn check_value(a: &$name, expected: $native) {
+            assert_eq!(a.value, Some(expected));
+            for (i, b) in a.bits.iter().enumerate() {
+                assert_eq!(b.get_value(), Some(expected >> i & 1 == 1));
+            }
+        }
+
+        #[test]
+        fn test_from_bits_be() {
+            let mut rng = test_rng();
+
+            for _ in 0..1000 {
+                let v = (0..$bits)
+                    .map(|_| Boolean::constant(rng.$next() % 2 != 0))
+                    .collect::<Vec<_>>();
+
+                let b = $name::from_bits_be(&v);
+
+                for (i, bit) in b.bits.iter().enumerate() {
+                    match *bit {
+                        Boolean::Constant(bit) => {
+                            assert!(bit == ((b.value.unwrap() >> i) & 1 == 1));
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+
+                let expected_to_be_same = b.into_bits_be();
+
+                for x in v.iter().zip(expected_to_be_same.iter()) {
+                    match x {
+                        (&Boolean::Constant(true), &Boolean::Constant(true)) => {}
+                        (&Boolean::Constant(false), &Boolean::Constant(false)) => {}
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn test_from_bits() {
+            let mut rng = test_rng();
+
+            for _ in 0..1000 {
+                let v = (0..$bits)
+                    .map(|_| Boolean::constant(rng.$next() % 2 != 0))
+                    .collect::<Vec<_>>();
+
+                let b = $name::from_bits(&v);
+
+                for (i, bit) in b.bits.iter().enumerate() {
+                    match *bit {
+                        Boolean::Constant(bit) => {
+                            assert!(bit == ((b.value.unwrap() >> i) & 1 == 1));
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+
+                let expected_to_be_same = b.into_bits();
+
+                for x in v.iter().zip(expected_to_be_same.iter()) {
+                    match x {
+                        (&Boolean::Constant(true), &Boolean::Constant(true)) => {}
+                        (&Boolean::Constant(false), &Boolean::Constant(false)) => {}
+                        _ => unreachable!(),
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn test_xor() {
+            let mut rng = test_rng();
+
+            for _ in 0..1000 {
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+
+                let a = rng.$next();
+                let b = rng.$next();
+                let c = rng.$next();
+
+                let mut expected = a ^ b ^ c;
+
+                let a_bit = $name::alloc(cs.namespace(|| "a_bit"), Some(a)).unwrap();
+                let b_bit = $name::constant(b);
+                let c_bit = $name::alloc(cs.namespace(|| "c_bit"), Some(c)).unwrap();
+
+                let r = a_bit.xor(cs.namespace(|| "first xor"), &b_bit).unwrap();
+                let r = r.xor(cs.namespace(|| "second xor"), &c_bit).unwrap();
+
+                assert!(cs.is_satisfied());
+
+                assert!(r.value == Some(expected));
+
+                for b in r.bits.iter() {
+                    match *b {
+                        Boolean::Is(ref b) => {
+                            assert!(b.get_value().unwrap() == (expected & 1 == 1));
+                        }
+                        Boolean::Not(ref b) => {
+                            assert!(!b.get_value().unwrap() == (expected & 1 == 1));
+                        }
+                        Boolean::Constant(b) => {
+                            assert!(b == (expected & 1 == 1));
+                        }
+                    }
+
+                    expected >>= 1;
+                }
+            }
+        }
+
+        #[test]
+        fn test_addmany_constants() {
+            let mut rng = test_rng();
+
+            for _ in 0..1000 {
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+
+                let a = rng.$next();
+                let b = rng.$next();
+                let c = rng.$next();
+
+                let a_bit = $name::constant(a);
+                let b_bit = $name::constant(b);
+                let c_bit = $name::constant(c);
+
+                let expected = a.wrapping_add(b).wrapping_add(c);
+
+                let r = {
+                    let mut cs = MultiEq::new(&mut cs);
+                    $name::addmany(cs.namespace(|| "addition"), &[a_bit, b_bit, c_bit]).unwrap()
+                };
+
+                assert!(r.value == Some(expected));
+                assert_eq!(cs.num_constraints(), 0);
+
+                check_value(&r, expected);
+            }
+        }
+
+        #[test]
+        fn test_addmany() {
+            let mut rng = test_rng();
+
+            for _ in 0..1000 {
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+
+                let a = rng.$next();
+                let b = rng.$next();
+                let c = rng.$next();
+                let d = rng.$next();
+
+                let expected = (a ^ b).wrapping_add(c).wrapping_add(d);
+
+                let a_bit = $name::alloc(cs.namespace(|| "a_bit"), Some(a)).unwrap();
+                let b_bit = $name::constant(b);
+                let c_bit = $name::constant(c);
+                let d_bit = $name::alloc(cs.namespace(|| "d_bit"), Some(d)).unwrap();
+
+                let r = a_bit.xor(cs.namespace(|| "xor"), &b_bit).unwrap();
+                let r = {
+                    let mut cs = MultiEq::new(&mut cs);
+                    $name::addmany(cs.namespace(|| "addition"), &[r, c_bit, d_bit]).unwrap()
+                };
+
+                assert!(cs.is_satisfied());
+
+                check_value(&r, expected);
+
+                // Flip a bit and see if the addition constraint still works
+                if cs.get("addition/result bit 0/boolean").is_zero() {
+                    cs.set("addition/result bit 0/boolean", Field::one());
+                } else {
+                    cs.set("addition/result bit 0/boolean", Field::zero());
+                }
+
+                assert!(!cs.is_satisfied());
+            }
+        }
+
+        #[test]
+        fn test_rotr_rotl() {
+            let mut rng = test_rng();
+
+            let mut num = rng.$next();
+
+            let a = $name::constant(num);
+
+            for i in 0..$bits {
+                let b = a.rotr(i);
+                assert_eq!(a.bits.len(), b.bits.len());
+
+                check_value(&b, num);
+                check_value(&b.rotl(i), a.value.unwrap());
+                check_value(&a.rotl(i), a.value.unwrap().rotate_left(i as u32));
+
+                num = num.rotate_right(1);
+            }
+        }
+
+        #[test]
+        fn test_shr_shl() {
+            let mut rng = test_rng();
+
+            for _ in 0..50 {
+                for i in 0..2 * $bits {
+                    let num = rng.$next();
+                    let a = $name::constant(num);
+
+                    check_value(&a.shr(i), num >> (i % $bits));
+                    check_value(&a.shl(i), num << (i % $bits));
+                }
+            }
+        }
+
+        #[test]
+        fn test_sha256_maj() {
+            let mut rng = test_rng();
+
+            for _ in 0..1000 {
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+
+                let a = rng.$next();
+                let b = rng.$next();
+                let c = rng.$next();
+
+                let expected = (a & b) ^ (a & c) ^ (b & c);
+
+                let a_bit = $name::alloc(cs.namespace(|| "a_bit"), Some(a)).unwrap();
+                let b_bit = $name::constant(b);
+                let c_bit = $name::alloc(cs.namespace(|| "c_bit"), Some(c)).unwrap();
+
+                let r = $name::sha256_maj(&mut cs, &a_bit, &b_bit, &c_bit).unwrap();
+
+                assert!(cs.is_satisfied());
+
+                check_value(&r, expected);
+            }
+        }
+
+        #[test]
+        fn test_sha256_ch() {
+            let mut rng = test_rng();
+
+            for _ in 0..1000 {
+                let mut cs = TestConstraintSystem::<Bls12>::new();
+
+                let a = rng.$next();
+                let b = rng.$next();
+                let c = rng.$next();
+
+                let expected = (a & b) ^ ((!a) & c);
+
+                let a_bit = $name::alloc(cs.namespace(|| "a_bit"), Some(a)).unwrap();
+                let b_bit = $name::constant(b);
+                let c_bit = $name::alloc(cs.namespace(|| "c_bit"), Some(c)).unwrap();
+
+                let r = $name::sha256_ch(&mut cs, &a_bit, &b_bit, &c_bit).unwrap();
+
+                assert!(cs.is_satisfied());
+
+                check_value(&r, expected);
+            }
+        }
+    };
+}
@@ -0,0 +1,15 @@
+//! Circuit representation of a `u32`, with helpers for the SHA-256 and
+//! BLAKE2s compression functions.
+
+uint_impl!(
+    UInt32,
+    u32,
+    u64,
+    32,
+    "Represents an interpretation of 32 `Boolean` objects as an unsigned integer."
+);
+
+#[cfg(test)]
+mod tests {
+    uint_tests!(UInt32, u32, next_u32, 32);
+}
@@ -0,0 +1,15 @@
+//! Circuit representation of a `u64`, with helpers for the SHA-512 and
+//! BLAKE2b compression functions.
+
+uint_impl!(
+    UInt64,
+    u64,
+    u128,
+    64,
+    "Represents an interpretation of 64 `Boolean` objects as an unsigned integer."
+);
+
+#[cfg(test)]
+mod tests {
+    uint_tests!(UInt64, u64, next_u64, 64);
+}
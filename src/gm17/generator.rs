@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective, Wnaf};
+use rand_core::RngCore;
+
+use super::sap::SapAssembly;
+use super::{Parameters, VerifyingKey};
+use crate::bls::Engine;
+use crate::domain::{EvaluationDomain, Scalar};
+use crate::multicore::Worker;
+use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+/// Generates random parameters for a circuit.
+pub fn generate_random_parameters<E, C, R>(
+    circuit: C,
+    rng: &mut R,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+    R: RngCore,
+{
+    let alpha = E::Fr::random(rng);
+    let beta = E::Fr::random(rng);
+    let gamma = E::Fr::random(rng);
+    let x = E::Fr::random(rng);
+
+    generate_parameters(circuit, alpha, beta, gamma, x)
+}
+
+/// Generates the parameters of a circuit for the given secrets.
+pub fn generate_parameters<E, C>(
+    circuit: C,
+    alpha: E::Fr,
+    beta: E::Fr,
+    gamma: E::Fr,
+    x: E::Fr,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+{
+    let mut cs = SapAssembly::new(false);
+    cs.alloc_input(|| "", || Ok(E::Fr::one()))?;
+    circuit.synthesize(&mut cs)?;
+    let sap = cs.into_sap();
+
+    if gamma.is_zero() {
+        return Err(SynthesisError::UnexpectedIdentity);
+    }
+
+    // The Lagrange coefficients at x, from an inverse FFT of its powers.
+    let worker = Worker::new();
+    let mut domain =
+        EvaluationDomain::<E, Scalar<E>>::from_coeffs(vec![Scalar(E::Fr::zero()); sap.num_rows])?;
+    let mut power = E::Fr::one();
+    for coeff in domain.as_mut() {
+        coeff.0 = power;
+        power.mul_assign(&x);
+    }
+    let t = domain.z(&x);
+    if t.is_zero() {
+        return Err(SynthesisError::UnexpectedIdentity);
+    }
+    let powers = domain.as_ref().iter().map(|p| p.0).collect::<Vec<_>>();
+    domain.ifft(&worker, &mut None)?;
+    let lagrange = domain.into_coeffs();
+
+    let eval = |polys: &[Vec<(E::Fr, usize)>]| {
+        polys
+            .iter()
+            .map(|poly| {
+                let mut acc = E::Fr::zero();
+                for (coeff, row) in poly {
+                    let mut term = lagrange[*row].0;
+                    term.mul_assign(coeff);
+                    acc.add_assign(&term);
+                }
+                acc
+            })
+            .collect::<Vec<_>>()
+    };
+    let u = eval(&sap.u);
+    let w = eval(&sap.w);
+
+    let mut alpha_beta = alpha;
+    alpha_beta.add_assign(&beta);
+    let mut gamma2 = gamma;
+    gamma2.square();
+    let mut gamma_t = gamma;
+    gamma_t.mul_assign(&t);
+    let mut gamma2_t = gamma2;
+    gamma2_t.mul_assign(&t);
+
+    let scale = |values: &[E::Fr], by: &E::Fr| {
+        values
+            .iter()
+            .map(|value| {
+                let mut value = *value;
+                value.mul_assign(by);
+                value
+            })
+            .collect::<Vec<_>>()
+    };
+    let gamma_u = scale(&u, &gamma);
+
+    // gamma w_i + (alpha + beta) u_i, by gamma for the auxiliary variables.
+    let combined = w
+        .iter()
+        .zip(&u)
+        .map(|(w, u)| {
+            let mut acc = *w;
+            acc.mul_assign(&gamma);
+            let mut term = *u;
+            term.mul_assign(&alpha_beta);
+            acc.add_assign(&term);
+            acc
+        })
+        .collect::<Vec<_>>();
+
+    let mut two_gamma2_t = gamma2_t;
+    two_gamma2_t.double();
+    let mut alpha_beta_gamma_t = alpha_beta;
+    alpha_beta_gamma_t.mul_assign(&gamma_t);
+    let mut gamma2_t2 = gamma_t;
+    gamma2_t2.square();
+
+    let g1 = E::G1::one();
+    let g2 = E::G2::one();
+    let h_len = powers.len() - 1;
+
+    let vk = VerifyingKey {
+        h_g2: g2.into_affine(),
+        alpha_g1: g1.into_affine().mul(alpha.into_repr()).into_affine(),
+        beta_g2: g2.into_affine().mul(beta.into_repr()).into_affine(),
+        gamma_g1: g1.into_affine().mul(gamma.into_repr()).into_affine(),
+        gamma_g2: g2.into_affine().mul(gamma.into_repr()).into_affine(),
+        query: exponentiate(g1, &combined[..sap.num_inputs]),
+    };
+
+    Ok(Parameters {
+        vk,
+        a: Arc::new(exponentiate(g1, &gamma_u)),
+        b: Arc::new(exponentiate(g2, &gamma_u)),
+        c: Arc::new(exponentiate(
+            g1,
+            &scale(&combined[sap.num_inputs..], &gamma),
+        )),
+        c_r: Arc::new(exponentiate(g1, &scale(&u, &two_gamma2_t))),
+        h: Arc::new(exponentiate(g1, &scale(&powers[..h_len], &gamma2_t))),
+        gamma_t_g1: g1.into_affine().mul(gamma_t.into_repr()).into_affine(),
+        gamma_t_g2: g2.into_affine().mul(gamma_t.into_repr()).into_affine(),
+        alpha_beta_gamma_t_g1: g1
+            .into_affine()
+            .mul(alpha_beta_gamma_t.into_repr())
+            .into_affine(),
+        gamma2_t2_g1: g1.into_affine().mul(gamma2_t2.into_repr()).into_affine(),
+    })
+}
+
+fn exponentiate<G: CurveProjective>(base: G, scalars: &[G::Scalar]) -> Vec<G::Affine> {
+    let mut wnaf = Wnaf::new();
+    let mut wnaf = wnaf.base(base, scalars.len());
+    let mut points = scalars
+        .iter()
+        .map(|scalar| wnaf.scalar(scalar.into_repr()))
+        .collect::<Vec<_>>();
+    G::batch_normalization(&mut points);
+
+    points.into_iter().map(|p| p.into_affine()).collect()
+}
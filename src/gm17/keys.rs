@@ -0,0 +1,133 @@
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use groupy::{CurveAffine, EncodedPoint};
+
+use crate::bls::Engine;
+
+/// The verifying key of a circuit, for generators `G` and `H` of G1 and G2.
+pub struct VerifyingKey<E: Engine> {
+    pub h_g2: E::G2Affine,
+    pub alpha_g1: E::G1Affine,
+    pub beta_g2: E::G2Affine,
+    pub gamma_g1: E::G1Affine,
+    pub gamma_g2: E::G2Affine,
+
+    // Elements of the form gamma w_i(x) + (alpha + beta) u_i(x) for the
+    // inputs, starting with the constant one.
+    pub query: Vec<E::G1Affine>,
+}
+
+impl<E: Engine> Clone for VerifyingKey<E> {
+    fn clone(&self) -> Self {
+        VerifyingKey {
+            h_g2: self.h_g2,
+            alpha_g1: self.alpha_g1,
+            beta_g2: self.beta_g2,
+            gamma_g1: self.gamma_g1,
+            gamma_g2: self.gamma_g2,
+            query: self.query.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for VerifyingKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.h_g2 == other.h_g2
+            && self.alpha_g1 == other.alpha_g1
+            && self.beta_g2 == other.beta_g2
+            && self.gamma_g1 == other.gamma_g1
+            && self.gamma_g2 == other.gamma_g2
+            && self.query == other.query
+    }
+}
+
+impl<E: Engine> VerifyingKey<E> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.h_g2.into_compressed().as_ref())?;
+        writer.write_all(self.alpha_g1.into_compressed().as_ref())?;
+        writer.write_all(self.beta_g2.into_compressed().as_ref())?;
+        writer.write_all(self.gamma_g1.into_compressed().as_ref())?;
+        writer.write_all(self.gamma_g2.into_compressed().as_ref())?;
+        writer.write_u32::<BigEndian>(self.query.len() as u32)?;
+        for query in &self.query {
+            writer.write_all(query.into_compressed().as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let h_g2 = read_point(&mut reader)?;
+        let alpha_g1 = read_point(&mut reader)?;
+        let beta_g2 = read_point(&mut reader)?;
+        let gamma_g1 = read_point(&mut reader)?;
+        let gamma_g2 = read_point(&mut reader)?;
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let query = (0..len)
+            .map(|_| read_point(&mut reader))
+            .collect::<io::Result<_>>()?;
+
+        Ok(VerifyingKey {
+            h_g2,
+            alpha_g1,
+            beta_g2,
+            gamma_g1,
+            gamma_g2,
+            query,
+        })
+    }
+}
+
+/// The parameters of a circuit, for secrets `alpha`, `beta`, `gamma` and the
+/// point `x`, where the target polynomial evaluates to `t`.
+pub struct Parameters<E: Engine> {
+    pub vk: VerifyingKey<E>,
+
+    // gamma u_i(x) in G1 and G2 for all variables.
+    pub a: Arc<Vec<E::G1Affine>>,
+    pub b: Arc<Vec<E::G2Affine>>,
+
+    // gamma^2 w_i(x) + (alpha + beta) gamma u_i(x) for the auxiliary
+    // variables.
+    pub c: Arc<Vec<E::G1Affine>>,
+
+    // 2 gamma^2 t u_i(x) for all variables, the cross terms of the
+    // randomization of A and B.
+    pub c_r: Arc<Vec<E::G1Affine>>,
+
+    // gamma^2 t x^i for i between 0 and m-2 inclusive.
+    pub h: Arc<Vec<E::G1Affine>>,
+
+    pub gamma_t_g1: E::G1Affine,
+    pub gamma_t_g2: E::G2Affine,
+    // (alpha + beta) gamma t
+    pub alpha_beta_gamma_t_g1: E::G1Affine,
+    // gamma^2 t^2
+    pub gamma2_t2_g1: E::G1Affine,
+}
+
+impl<E: Engine> Clone for Parameters<E> {
+    fn clone(&self) -> Self {
+        Parameters {
+            vk: self.vk.clone(),
+            a: self.a.clone(),
+            b: self.b.clone(),
+            c: self.c.clone(),
+            c_r: self.c_r.clone(),
+            h: self.h.clone(),
+            gamma_t_g1: self.gamma_t_g1,
+            gamma_t_g2: self.gamma_t_g2,
+            alpha_beta_gamma_t_g1: self.alpha_beta_gamma_t_g1,
+            gamma2_t2_g1: self.gamma2_t2_g1,
+        }
+    }
+}
+
+pub(crate) fn read_point<G: CurveAffine, R: Read>(reader: &mut R) -> io::Result<G> {
+    let mut repr = G::Compressed::empty();
+    reader.read_exact(repr.as_mut())?;
+    repr.into_affine()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
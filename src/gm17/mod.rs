@@ -0,0 +1,171 @@
+//! The [GM17] proving system, with simulation-extractable proofs.
+//!
+//! Groth16 proofs are malleable: anyone can rerandomize a proof into
+//! another valid proof of the same statement. GM17 proofs cannot be
+//! changed without knowing a witness, which matters when a proof is bound
+//! to an identity or used as a nonce. In exchange the parameters are about
+//! twice as large and verification takes a second pairing check.
+//!
+//! Circuits are written against the same `ConstraintSystem` as Groth16 and
+//! reduced to a square arithmetic program, see `sap`. The parameters come
+//! from a single trusted setup per circuit, as for Groth16.
+//!
+//! [GM17]: https://eprint.iacr.org/2017/540
+
+mod generator;
+mod keys;
+mod prover;
+mod sap;
+mod verifier;
+
+pub use self::generator::{generate_parameters, generate_random_parameters};
+pub use self::keys::{Parameters, VerifyingKey};
+pub use self::prover::{create_proof, create_random_proof, Proof};
+pub use self::verifier::{prepare_verifying_key, verify_proof, PreparedVerifyingKey};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_fixtures::test_rng;
+    use crate::{Circuit, ConstraintSystem, SynthesisError};
+    use ff::{Field, PrimeField};
+    use groupy::{CurveAffine, CurveProjective};
+
+    // Knowledge of `x` with `x^3 + x + 5 = out` for the public `out`.
+    struct Cubic {
+        x: Option<Fr>,
+        out: Option<Fr>,
+    }
+
+    impl Circuit<Bls12> for Cubic {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let out = cs.alloc_input(
+                || "out",
+                || self.out.ok_or(SynthesisError::AssignmentMissing),
+            )?;
+            let x_value = self.x;
+            let x = cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?;
+            let x2_value = x_value.map(|mut x| {
+                x.square();
+                x
+            });
+            let x2 = cs.alloc(
+                || "x2",
+                || x2_value.ok_or(SynthesisError::AssignmentMissing),
+            )?;
+            let x3 = cs.alloc(
+                || "x3",
+                || {
+                    let mut x3 = x2_value.ok_or(SynthesisError::AssignmentMissing)?;
+                    x3.mul_assign(&x_value.ok_or(SynthesisError::AssignmentMissing)?);
+                    Ok(x3)
+                },
+            )?;
+            let five = Fr::from_str("5").unwrap();
+            cs.enforce(|| "x2", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+            cs.enforce(|| "x3", |lc| lc + x2, |lc| lc + x, |lc| lc + x3);
+            cs.enforce(
+                || "out",
+                |lc| lc + x3 + x + (five, CS::one()),
+                |lc| lc + CS::one(),
+                |lc| lc + out,
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_gm17() {
+        let mut rng = test_rng();
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Cubic { x: None, out: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let x = Fr::from_str("3").unwrap();
+        let out = Fr::from_str("35").unwrap();
+        let proof = create_random_proof(
+            Cubic {
+                x: Some(x),
+                out: Some(out),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_proof(&pvk, &proof, &[out]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[Fr::one()]).unwrap());
+        assert!(verify_proof(&pvk, &proof, &[]).is_err());
+
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+        assert_eq!(Proof::<Bls12>::read(&bytes[..]).unwrap(), proof);
+
+        let mut bytes = vec![];
+        params.vk.write(&mut bytes).unwrap();
+        assert!(VerifyingKey::<Bls12>::read(&bytes[..]).unwrap() == params.vk);
+
+        let wrong = create_random_proof(
+            Cubic {
+                x: Some(x),
+                out: Some(Fr::one()),
+            },
+            &params,
+            &mut rng,
+        );
+        match wrong.err() {
+            Some(SynthesisError::Unsatisfiable) => {}
+            _ => panic!("expected an unsatisfiable circuit"),
+        }
+    }
+
+    #[test]
+    fn test_non_malleability() {
+        let mut rng = test_rng();
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Cubic { x: None, out: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let out = Fr::from_str("35").unwrap();
+        let proof = create_random_proof(
+            Cubic {
+                x: Some(Fr::from_str("3").unwrap()),
+                out: Some(out),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+
+        // Scaling A by s and B by 1/s keeps their pairing, which is enough to
+        // rerandomize a Groth16 proof, but not a GM17 one.
+        let s = Fr::random(&mut rng);
+        let s_inv = s.inverse().unwrap();
+        let mauled = Proof {
+            a: proof.a.mul(s.into_repr()).into_affine(),
+            b: proof.b.mul(s_inv.into_repr()).into_affine(),
+            c: proof.c,
+        };
+        assert!(!verify_proof(&pvk, &mauled, &[out]).unwrap());
+
+        // Proofs for the same statement with different randomness differ.
+        let other = create_proof(
+            Cubic {
+                x: Some(Fr::from_str("3").unwrap()),
+                out: Some(out),
+            },
+            &params,
+            Fr::one(),
+        )
+        .unwrap();
+        assert!(verify_proof(&pvk, &other, &[out]).unwrap());
+        assert_ne!(other, proof);
+    }
+}
@@ -0,0 +1,156 @@
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective};
+use rand_core::RngCore;
+
+use super::keys::read_point;
+use super::sap::SapAssembly;
+use super::Parameters;
+use crate::bls::Engine;
+use crate::domain::{self, Scalar};
+use crate::multicore::Worker;
+use crate::multiexp::{multiexp, FullDensity};
+use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+#[derive(Clone, Debug)]
+pub struct Proof<E: Engine> {
+    pub a: E::G1Affine,
+    pub b: E::G2Affine,
+    pub c: E::G1Affine,
+}
+
+impl<E: Engine> PartialEq for Proof<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.a == other.a && self.b == other.b && self.c == other.c
+    }
+}
+
+impl<E: Engine> Proof<E> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.a.into_compressed().as_ref())?;
+        writer.write_all(self.b.into_compressed().as_ref())?;
+        writer.write_all(self.c.into_compressed().as_ref())?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        Ok(Proof {
+            a: read_point(&mut reader)?,
+            b: read_point(&mut reader)?,
+            c: read_point(&mut reader)?,
+        })
+    }
+}
+
+/// Creates a proof with a random `r`.
+pub fn create_random_proof<E, C, R>(
+    circuit: C,
+    params: &Parameters<E>,
+    rng: &mut R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+    R: RngCore,
+{
+    create_proof(circuit, params, E::Fr::random(rng))
+}
+
+/// Creates a proof whose `A` and `B` are shifted by `r` times the target
+/// polynomial.
+pub fn create_proof<E, C>(
+    circuit: C,
+    params: &Parameters<E>,
+    r: E::Fr,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+{
+    let mut cs = SapAssembly::new(true);
+    cs.alloc_input(|| "", || Ok(E::Fr::one()))?;
+    circuit.synthesize(&mut cs)?;
+    let sap = cs.into_sap();
+
+    if sap.values.len() != params.a.len() || sap.num_inputs != params.vk.query.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "the circuit has {} variables and {} inputs, the parameters {} and {}",
+            sap.values.len(),
+            sap.num_inputs,
+            params.a.len(),
+            params.vk.query.len()
+        )));
+    }
+
+    let u = sap.evaluate(&sap.u);
+    let w = sap.evaluate(&sap.w);
+    for (u, w) in u.iter().zip(&w) {
+        let mut square = *u;
+        square.square();
+        if square != *w {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+    }
+
+    let worker = Worker::new();
+    let scalars = |values: Vec<E::Fr>| values.into_iter().map(Scalar).collect::<Vec<_>>();
+    let h = domain::compute_quotient_polynomial(
+        &worker,
+        &mut None,
+        scalars(u.clone()),
+        scalars(u),
+        scalars(w),
+    )?;
+    if h.len() != params.h.len() {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+    let h = Arc::new(h.into_iter().map(|h| h.0.into_repr()).collect::<Vec<_>>());
+
+    let values = sap
+        .values
+        .iter()
+        .map(|value| value.into_repr())
+        .collect::<Vec<_>>();
+    let aux = Arc::new(values[sap.num_inputs..].to_vec());
+    let values = Arc::new(values);
+    let r_repr = r.into_repr();
+    let mut r2 = r;
+    r2.square();
+
+    let mut a = msm(params.a.clone(), values.clone())?;
+    a.add_assign(&params.gamma_t_g1.mul(r_repr));
+
+    let mut b = msm(params.b.clone(), values.clone())?;
+    b.add_assign(&params.gamma_t_g2.mul(r_repr));
+
+    let mut c = msm(params.c.clone(), aux)?;
+    c.add_assign(&msm(params.h.clone(), h)?);
+    let mut c_r = msm(params.c_r.clone(), values)?;
+    c_r.add_assign(&params.alpha_beta_gamma_t_g1.into_projective());
+    c.add_assign(&c_r.into_affine().mul(r_repr));
+    c.add_assign(&params.gamma2_t2_g1.mul(r2.into_repr()));
+
+    Ok(Proof {
+        a: a.into_affine(),
+        b: b.into_affine(),
+        c: c.into_affine(),
+    })
+}
+
+fn msm<E, G>(
+    bases: Arc<Vec<G>>,
+    scalars: Arc<Vec<<E::Fr as PrimeField>::Repr>>,
+) -> Result<G::Projective, SynthesisError>
+where
+    E: Engine,
+    G: CurveAffine<Engine = E>,
+{
+    if bases.is_empty() {
+        return Ok(G::Projective::zero());
+    }
+
+    multiexp(&Worker::new(), (bases, 0), FullDensity, scalars, &mut None).wait()
+}
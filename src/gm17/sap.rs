@@ -0,0 +1,257 @@
+//! The reduction of R1CS to a square arithmetic program (SAP).
+//!
+//! A SAP enforces `(sum z_i u_i(X))^2 = sum z_i w_i(X)` on the rows of a
+//! domain. Every R1CS constraint `a * b = c` becomes the two rows
+//! `(a + b)^2 = 4 c + e` and `(a - b)^2 = e` for a new variable `e`, and
+//! every input `x` gets a row `x^2 = s` for a new variable `s`, which makes
+//! the `u` polynomials of the inputs linearly independent.
+
+use ff::Field;
+
+use crate::bls::Engine;
+use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+/// Synthesizes a circuit and records its constraints.
+pub(crate) struct SapAssembly<E: Engine> {
+    // Whether the values are computed, otherwise they are all zero.
+    witness: bool,
+    inputs: Vec<E::Fr>,
+    aux: Vec<E::Fr>,
+    constraints: Vec<[Vec<(Variable, E::Fr)>; 3]>,
+}
+
+/// The polynomials of a SAP, as their values on the rows, and the values of
+/// its variables: the inputs, the auxiliary variables of the circuit, the
+/// `e` of the constraints and the `s` of the inputs but the first.
+pub(crate) struct Sap<E: Engine> {
+    pub num_inputs: usize,
+    pub num_rows: usize,
+    pub u: Vec<Vec<(E::Fr, usize)>>,
+    pub w: Vec<Vec<(E::Fr, usize)>>,
+    pub values: Vec<E::Fr>,
+}
+
+impl<E: Engine> SapAssembly<E> {
+    pub fn new(witness: bool) -> Self {
+        SapAssembly {
+            witness,
+            inputs: vec![],
+            aux: vec![],
+            constraints: vec![],
+        }
+    }
+
+    fn value(&self, var: &Variable) -> E::Fr {
+        match var.get_unchecked() {
+            Index::Input(i) => self.inputs[i],
+            Index::Aux(i) => self.aux[i],
+        }
+    }
+
+    fn evaluate(&self, lc: &[(Variable, E::Fr)]) -> E::Fr {
+        let mut acc = E::Fr::zero();
+        for (var, coeff) in lc {
+            let mut term = self.value(var);
+            term.mul_assign(coeff);
+            acc.add_assign(&term);
+        }
+        acc
+    }
+
+    pub fn into_sap(self) -> Sap<E> {
+        let num_inputs = self.inputs.len();
+        let num_aux = self.aux.len();
+        let num_constraints = self.constraints.len();
+        let index = |var: &Variable| match var.get_unchecked() {
+            Index::Input(i) => i,
+            Index::Aux(i) => num_inputs + i,
+        };
+
+        let num_vars = num_inputs + num_aux + num_constraints + num_inputs.saturating_sub(1);
+        let mut u = vec![vec![]; num_vars];
+        let mut w = vec![vec![]; num_vars];
+        let mut values = Vec::with_capacity(num_vars);
+        values.extend_from_slice(&self.inputs);
+        values.extend_from_slice(&self.aux);
+
+        let mut four = E::Fr::one();
+        four.double();
+        four.double();
+        for (k, [a, b, c]) in self.constraints.iter().enumerate() {
+            let (sum, diff) = (2 * k, 2 * k + 1);
+            for (var, coeff) in a {
+                u[index(var)].push((*coeff, sum));
+                u[index(var)].push((*coeff, diff));
+            }
+            for (var, coeff) in b {
+                let mut neg = *coeff;
+                neg.negate();
+                u[index(var)].push((*coeff, sum));
+                u[index(var)].push((neg, diff));
+            }
+            for (var, coeff) in c {
+                let mut coeff = *coeff;
+                coeff.mul_assign(&four);
+                w[index(var)].push((coeff, sum));
+            }
+
+            let e = values.len();
+            w[e].push((E::Fr::one(), sum));
+            w[e].push((E::Fr::one(), diff));
+            let mut value = self.evaluate(a);
+            value.sub_assign(&self.evaluate(b));
+            value.square();
+            values.push(value);
+        }
+
+        let first = 2 * num_constraints;
+        u[0].push((E::Fr::one(), first));
+        w[0].push((E::Fr::one(), first));
+        for (i, input) in self.inputs.iter().enumerate().skip(1) {
+            let row = first + i;
+            let s = values.len();
+            u[i].push((E::Fr::one(), row));
+            w[s].push((E::Fr::one(), row));
+            let mut value = *input;
+            value.square();
+            values.push(value);
+        }
+
+        Sap {
+            num_inputs,
+            num_rows: first + num_inputs,
+            u,
+            w,
+            values,
+        }
+    }
+}
+
+impl<E: Engine> Sap<E> {
+    /// The values of `sum z_i p_i` on the rows, for the polynomials `p` of
+    /// `u` or `w`.
+    pub fn evaluate(&self, polys: &[Vec<(E::Fr, usize)>]) -> Vec<E::Fr> {
+        let mut rows = vec![E::Fr::zero(); self.num_rows];
+        for (poly, value) in polys.iter().zip(&self.values) {
+            for (coeff, row) in poly {
+                let mut term = *value;
+                term.mul_assign(coeff);
+                rows[*row].add_assign(&term);
+            }
+        }
+        rows
+    }
+}
+
+impl<E: Engine> ConstraintSystem<E> for SapAssembly<E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = if self.witness { f()? } else { E::Fr::zero() };
+        self.aux.push(value);
+
+        Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = if self.witness { f()? } else { E::Fr::zero() };
+        self.inputs.push(value);
+
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+    {
+        let terms = |lc: LinearCombination<E>| {
+            lc.simplify()
+                .iter()
+                .map(|(var, coeff)| (*var, *coeff))
+                .collect::<Vec<_>>()
+        };
+        let constraint = [
+            terms(a(LinearCombination::zero())),
+            terms(b(LinearCombination::zero())),
+            terms(c(LinearCombination::zero())),
+        ];
+        self.constraints.push(constraint);
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use ff::PrimeField;
+
+    #[test]
+    fn test_sap() {
+        let fr = |n: &str| Fr::from_str(n).unwrap();
+
+        let mut cs = SapAssembly::<Bls12>::new(true);
+        let one = cs.alloc_input(|| "one", || Ok(Fr::one())).unwrap();
+        let x = cs.alloc_input(|| "x", || Ok(fr("3"))).unwrap();
+        let y = cs.alloc(|| "y", || Ok(fr("5"))).unwrap();
+        let xy = cs.alloc(|| "xy", || Ok(fr("15"))).unwrap();
+        cs.enforce(|| "xy", |lc| lc + x, |lc| lc + y, |lc| lc + xy);
+        cs.enforce(
+            || "x + 1",
+            |lc| lc + x + one,
+            |lc| lc + one,
+            |lc| lc + x + one,
+        );
+        let sap = cs.into_sap();
+
+        assert_eq!(sap.num_inputs, 2);
+        assert_eq!(sap.num_rows, 6);
+        assert_eq!(sap.values.len(), 7);
+        let u = sap.evaluate(&sap.u);
+        let w = sap.evaluate(&sap.w);
+        for (mut u, w) in u.into_iter().zip(w) {
+            u.square();
+            assert_eq!(u, w);
+        }
+
+        // An unsatisfied constraint breaks the first of its rows.
+        let mut cs = SapAssembly::<Bls12>::new(true);
+        cs.alloc_input(|| "one", || Ok(Fr::one())).unwrap();
+        let x = cs.alloc(|| "x", || Ok(fr("3"))).unwrap();
+        cs.enforce(|| "x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x);
+        let sap = cs.into_sap();
+        let mut u = sap.evaluate(&sap.u)[0];
+        u.square();
+        assert_ne!(u, sap.evaluate(&sap.w)[0]);
+    }
+}
@@ -0,0 +1,79 @@
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective};
+
+use super::{Proof, VerifyingKey};
+use crate::bls::{Engine, PairingCurveAffine};
+use crate::SynthesisError;
+
+/// A verifying key with the pairing of `alpha` and `beta` and the G2
+/// points of the first check prepared.
+pub struct PreparedVerifyingKey<E: Engine> {
+    alpha_g1_beta_g2: E::Fqk,
+    alpha_g1: E::G1Affine,
+    beta_g2: E::G2Affine,
+    neg_gamma_g1: E::G1Affine,
+    gamma_g2: <E::G2Affine as PairingCurveAffine>::Prepared,
+    neg_gamma_g2: <E::G2Affine as PairingCurveAffine>::Prepared,
+    neg_h_g2: <E::G2Affine as PairingCurveAffine>::Prepared,
+    query: Vec<E::G1Affine>,
+}
+
+pub fn prepare_verifying_key<E: Engine>(vk: &VerifyingKey<E>) -> PreparedVerifyingKey<E> {
+    let mut neg_gamma_g1 = vk.gamma_g1;
+    neg_gamma_g1.negate();
+    let mut neg_gamma_g2 = vk.gamma_g2;
+    neg_gamma_g2.negate();
+    let mut neg_h_g2 = vk.h_g2;
+    neg_h_g2.negate();
+
+    PreparedVerifyingKey {
+        alpha_g1_beta_g2: E::pairing(vk.alpha_g1, vk.beta_g2),
+        alpha_g1: vk.alpha_g1,
+        beta_g2: vk.beta_g2,
+        neg_gamma_g1,
+        gamma_g2: vk.gamma_g2.prepare(),
+        neg_gamma_g2: neg_gamma_g2.prepare(),
+        neg_h_g2: neg_h_g2.prepare(),
+        query: vk.query.clone(),
+    }
+}
+
+/// Verifies a proof for the public inputs, without the constant one. Fails
+/// if their number does not match the key.
+///
+/// Besides the SAP equation, this checks that `A` and `B` hold the same
+/// exponent, so that they cannot be rerandomized into another valid proof.
+pub fn verify_proof<E: Engine>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[E::Fr],
+) -> Result<bool, SynthesisError> {
+    if public_inputs.len() + 1 != pvk.query.len() {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    let mut psi = pvk.query[0].into_projective();
+    for (input, query) in public_inputs.iter().zip(&pvk.query[1..]) {
+        psi.add_assign(&query.mul(input.into_repr()));
+    }
+
+    // e(A + alpha, B + beta) e(psi, -gamma) e(C, -H) = e(alpha, beta)
+    let mut a = proof.a.into_projective();
+    a.add_assign_mixed(&pvk.alpha_g1);
+    let mut b = proof.b.into_projective();
+    b.add_assign_mixed(&pvk.beta_g2);
+    let sap = E::final_exponentiation(&E::miller_loop(&[
+        (&a.into_affine().prepare(), &b.into_affine().prepare()),
+        (&psi.into_affine().prepare(), &pvk.neg_gamma_g2),
+        (&proof.c.prepare(), &pvk.neg_h_g2),
+    ]));
+
+    // e(A, gamma) e(-gamma, B) = 1
+    let same = E::final_exponentiation(&E::miller_loop(&[
+        (&proof.a.prepare(), &pvk.gamma_g2),
+        (&pvk.neg_gamma_g1.prepare(), &proof.b.prepare()),
+    ]));
+
+    Ok(sap.map_or(false, |sap| sap == pvk.alpha_g1_beta_g2)
+        && same.map_or(false, |same| same == E::Fqk::one()))
+}
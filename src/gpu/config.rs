@@ -0,0 +1,84 @@
+/// GPU settings of a single proving call.
+///
+/// These used to be process wide environment variables, which races when
+/// several provers with different needs share a process.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuOptions {
+    /// Whether the GPU may be used at all. Without the `gpu` feature it
+    /// never is.
+    pub enabled: bool,
+    /// Indices into the list of OpenCL devices to use, all of them if
+    /// `None`. Multiexps are split over the devices by their core counts,
+    /// the independent FFTs of a proof are spread evenly over them.
+    pub devices: Option<Vec<usize>>,
+    /// Upper bound in bytes on the device memory a multiexp kernel sizes its
+    /// chunks for, the memory of the device if `None`.
+    pub memory_limit: Option<u64>,
+}
+
+impl GpuOptions {
+    /// Options forcing the CPU.
+    pub fn disabled() -> Self {
+        GpuOptions {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    /// Keeps the selected ones of `devices`, given in OpenCL order.
+    pub fn select_devices<T>(&self, devices: Vec<T>) -> Vec<T> {
+        match &self.devices {
+            Some(selected) => devices
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| selected.contains(i))
+                .map(|(_, d)| d)
+                .collect(),
+            None => devices,
+        }
+    }
+
+    /// Caps the memory of a device to `memory_limit`.
+    pub fn device_memory(&self, memory: u64) -> u64 {
+        self.memory_limit.map_or(memory, |limit| memory.min(limit))
+    }
+}
+
+/// Uses every device, unless `BELLMAN_NO_GPU` is set when the options are
+/// created, as the proving functions without explicit options always did.
+impl Default for GpuOptions {
+    fn default() -> Self {
+        GpuOptions {
+            enabled: std::env::var("BELLMAN_NO_GPU").is_err(),
+            devices: None,
+            memory_limit: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpu_options() {
+        let options = GpuOptions {
+            enabled: true,
+            devices: Some(vec![0, 2]),
+            memory_limit: Some(1 << 30),
+        };
+        assert_eq!(options.select_devices(vec!['a', 'b', 'c']), vec!['a', 'c']);
+        assert_eq!(options.device_memory(1 << 32), 1 << 30);
+        assert_eq!(options.device_memory(1 << 20), 1 << 20);
+
+        let all = GpuOptions {
+            devices: None,
+            memory_limit: None,
+            ..options
+        };
+        assert_eq!(all.select_devices(vec!['a', 'b']), vec!['a', 'b']);
+        assert_eq!(all.device_memory(1 << 32), 1 << 32);
+
+        assert!(!GpuOptions::disabled().enabled);
+    }
+}
@@ -1,10 +1,10 @@
 use crate::bls::Engine;
 use crate::gpu::{
     error::{GPUError, GPUResult},
-    locks, sources,
+    locks, scheduler, sources, GpuOptions,
 };
 use ff::Field;
-use log::info;
+use log::{error, info};
 use rust_gpu_tools::*;
 use std::cmp;
 
@@ -12,46 +12,124 @@ const LOG2_MAX_ELEMENTS: usize = 32; // At most 2^32 elements is supported.
 const MAX_LOG2_RADIX: u32 = 8; // Radix256
 const MAX_LOG2_LOCAL_WORK_SIZE: u32 = 7; // 128
 
-pub struct FFTKernel<E>
+// A FFT kernel on a single device
+pub struct SingleFFTKernel<E>
 where
     E: Engine,
 {
     program: opencl::Program,
     pq_buffer: opencl::Buffer<E::Fr>,
     omegas_buffer: opencl::Buffer<E::Fr>,
-    _lock: locks::GPULock, // RFC 1857: struct fields are dropped in the same order as they are declared.
     priority: bool,
 }
 
+// FFT kernels for several devices. A single FFT runs on the first one,
+// independent FFTs are spread over all of them.
+pub struct FFTKernel<E>
+where
+    E: Engine,
+{
+    kernels: Vec<SingleFFTKernel<E>>,
+    _lock: locks::GPULock, // RFC 1857: struct fields are dropped in the same order as they are declared.
+}
+
 impl<E> FFTKernel<E>
 where
     E: Engine,
 {
     pub fn create(priority: bool) -> GPUResult<FFTKernel<E>> {
+        Self::create_with_options(priority, &GpuOptions::default())
+    }
+
+    pub fn create_with_options(priority: bool, options: &GpuOptions) -> GPUResult<FFTKernel<E>> {
         let lock = locks::GPULock::lock();
 
-        let devices = opencl::Device::all()?;
-        if devices.is_empty() {
+        let devices = options.select_devices(opencl::Device::all()?);
+
+        let kernels: Vec<_> = devices
+            .into_iter()
+            .map(|d| (d.clone(), SingleFFTKernel::<E>::create(d, priority)))
+            .filter_map(|(device, res)| {
+                if let Err(ref e) = res {
+                    error!(
+                        "Cannot initialize kernel for device '{}'! Error: {}",
+                        device.name(),
+                        e
+                    );
+                }
+                res.ok()
+            })
+            .collect();
+
+        if kernels.is_empty() {
             return Err(GPUError::Simple("No working GPUs found!"));
         }
+        info!("FFT: {} working device(s) selected.", kernels.len());
+        for (i, k) in kernels.iter().enumerate() {
+            info!("FFT: Device {}: {}", i, k.program.device().name());
+        }
+
+        Ok(FFTKernel {
+            kernels,
+            _lock: lock,
+        })
+    }
+
+    /// Performs FFT on `a`, on the first device
+    pub fn radix_fft(&mut self, a: &mut [E::Fr], omega: &E::Fr, log_n: u32) -> GPUResult<()> {
+        self.kernels[0].radix_fft(a, omega, log_n)
+    }
+
+    /// Performs FFT on each of `inputs`, which all have `2^log_n` elements,
+    /// spreading them evenly over the devices
+    pub fn radix_fft_many(
+        &mut self,
+        inputs: &mut [&mut [E::Fr]],
+        omega: &E::Fr,
+        log_n: u32,
+    ) -> GPUResult<()> {
+        let ranges = scheduler::split_by_weight(inputs.len(), &vec![1; self.kernels.len()]);
+
+        let mut rest = inputs;
+        let mut jobs = Vec::with_capacity(ranges.len());
+        for (kern, range) in self.kernels.iter_mut().zip(ranges) {
+            let (inputs, tail) = std::mem::take(&mut rest).split_at_mut(range.len());
+            rest = tail;
+            jobs.push((kern, inputs));
+        }
+
+        crate::multicore::THREAD_POOL.install(|| {
+            use rayon::prelude::*;
+
+            jobs.into_par_iter()
+                .map(|(kern, inputs)| {
+                    for a in inputs.iter_mut() {
+                        kern.radix_fft(a, omega, log_n)?;
+                    }
+                    Ok(())
+                })
+                .collect::<GPUResult<Vec<_>>>()
+        })?;
 
-        // Select the first device for FFT
-        let device = devices[0].clone();
+        Ok(())
+    }
+}
 
+impl<E> SingleFFTKernel<E>
+where
+    E: Engine,
+{
+    pub fn create(device: opencl::Device, priority: bool) -> GPUResult<SingleFFTKernel<E>> {
         let src = sources::kernel::<E>(device.brand() == opencl::Brand::Nvidia);
 
         let program = opencl::Program::from_opencl(device, &src)?;
         let pq_buffer = program.create_buffer::<E::Fr>(1 << MAX_LOG2_RADIX >> 1)?;
         let omegas_buffer = program.create_buffer::<E::Fr>(LOG2_MAX_ELEMENTS)?;
 
-        info!("FFT: 1 working device(s) selected.");
-        info!("FFT: Device 0: {}", program.device().name());
-
-        Ok(FFTKernel {
+        Ok(SingleFFTKernel {
             program,
             pq_buffer,
             omegas_buffer,
-            _lock: lock,
             priority,
         })
     }
@@ -68,19 +68,24 @@ impl Drop for PriorityLock {
 use super::error::{GPUError, GPUResult};
 use super::fft::FFTKernel;
 use super::multiexp::MultiexpKernel;
+use super::queue::{Job, JobKind};
+use super::GpuOptions;
 use crate::bls::Engine;
-use crate::domain::create_fft_kernel;
-use crate::multiexp::create_multiexp_kernel;
+use crate::domain::create_fft_kernel_with_options;
+use crate::multiexp::create_multiexp_kernel_with_options;
 
 macro_rules! locked_kernel {
-    ($class:ident, $kern:ident, $func:ident, $name:expr) => {
+    ($class:ident, $kern:ident, $func:ident, $name:expr, $kind:expr) => {
         pub struct $class<E>
         where
             E: Engine,
         {
             log_d: usize,
             priority: bool,
+            owner: Option<String>,
+            options: GpuOptions,
             kernel: Option<$kern<E>>,
+            job: Option<Job>, // Dropped after the kernel, so the job is active while it's alive.
         }
 
         impl<E> $class<E>
@@ -91,19 +96,41 @@ macro_rules! locked_kernel {
                 $class::<E> {
                     log_d,
                     priority,
+                    owner: None,
+                    options: GpuOptions::default(),
                     kernel: None,
+                    job: None,
                 }
             }
 
+            /// Labels the GPU jobs of this kernel with the proof they belong to,
+            /// see [`queue_status`](super::queue_status).
+            pub fn with_owner(mut self, owner: String) -> $class<E> {
+                self.owner = Some(owner);
+                self
+            }
+
+            /// Uses `options` instead of the defaults when creating the kernel.
+            pub fn with_options(mut self, options: GpuOptions) -> $class<E> {
+                self.options = options;
+                self
+            }
+
             fn init(&mut self) {
                 if self.kernel.is_none() {
+                    let job = Job::new($kind, self.log_d, self.priority, self.owner.clone());
                     PriorityLock::wait(self.priority);
                     info!("GPU is available for {}!", $name);
-                    self.kernel = $func::<E>(self.log_d, self.priority);
+                    self.kernel = $func::<E>(self.log_d, self.priority, &self.options);
+                    if self.kernel.is_some() {
+                        job.activate();
+                        self.job = Some(job);
+                    }
                 }
             }
 
             fn free(&mut self) {
+                self.job.take();
                 if let Some(_kernel) = self.kernel.take() {
                     warn!(
                         "GPU acquired by a high priority process! Freeing up {} kernels...",
@@ -116,7 +143,7 @@ macro_rules! locked_kernel {
             where
                 F: FnMut(&mut $kern<E>) -> GPUResult<R>,
             {
-                if std::env::var("BELLMAN_NO_GPU").is_ok() {
+                if !self.options.enabled {
                     return Err(GPUError::GPUDisabled);
                 }
 
@@ -144,10 +171,17 @@ macro_rules! locked_kernel {
     };
 }
 
-locked_kernel!(LockedFFTKernel, FFTKernel, create_fft_kernel, "FFT");
+locked_kernel!(
+    LockedFFTKernel,
+    FFTKernel,
+    create_fft_kernel_with_options,
+    "FFT",
+    JobKind::FFT
+);
 locked_kernel!(
     LockedMultiexpKernel,
     MultiexpKernel,
-    create_multiexp_kernel,
-    "Multiexp"
+    create_multiexp_kernel_with_options,
+    "Multiexp",
+    JobKind::Multiexp
 );
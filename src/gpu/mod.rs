@@ -1,7 +1,23 @@
+mod config;
+
+pub use self::config::*;
+
 mod error;
 
 pub use self::error::*;
 
+mod queue;
+
+pub use self::queue::*;
+
+mod scheduler;
+
+pub use self::scheduler::*;
+
+mod tuning;
+
+pub use self::tuning::*;
+
 #[cfg(feature = "gpu")]
 mod locks;
 
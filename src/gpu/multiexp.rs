@@ -1,13 +1,16 @@
 use super::error::{GPUError, GPUResult};
 use super::locks;
+use super::scheduler;
 use super::sources;
+use super::tuning;
 use super::utils;
+use super::GpuOptions;
 use crate::bls::Engine;
 use crate::multicore::Worker;
 use crate::multiexp::{multiexp as cpu_multiexp, FullDensity};
-use ff::{PrimeField, ScalarEngine};
+use ff::{Field, PrimeField, ScalarEngine};
 use groupy::{CurveAffine, CurveProjective};
-use log::{error, info};
+use log::{error, info, warn};
 use rust_gpu_tools::*;
 use std::any::TypeId;
 use std::sync::Arc;
@@ -80,35 +83,131 @@ fn calc_best_chunk_size(max_window_size: usize, core_count: usize, exp_bits: usi
         .ceil() as usize
 }
 
-fn calc_chunk_size<E>(mem: u64, core_count: usize) -> usize
+// Fails if `mem` does not even hold the buckets.
+fn calc_chunk_size<E>(mem: u64, core_count: usize) -> GPUResult<usize>
 where
     E: Engine,
 {
     let aff_size = std::mem::size_of::<E::G1Affine>() + std::mem::size_of::<E::G2Affine>();
     let exp_size = exp_size::<E>();
     let proj_size = std::mem::size_of::<E::G1>() + std::mem::size_of::<E::G2>();
-    ((((mem as f64) * (1f64 - MEMORY_PADDING)) as usize)
-        - (2 * core_count * ((1 << MAX_WINDOW_SIZE) + 1) * proj_size))
-        / (aff_size + exp_size)
+    let usable = ((mem as f64) * (1f64 - MEMORY_PADDING)) as usize;
+    let buckets = 2 * core_count * ((1 << MAX_WINDOW_SIZE) + 1) * proj_size;
+    let free = usable.checked_sub(buckets).ok_or(GPUError::Simple(
+        "Not enough GPU memory for the multiexp buckets!",
+    ))?;
+    Ok(free / (aff_size + exp_size))
 }
 
 fn exp_size<E: Engine>() -> usize {
     std::mem::size_of::<<E::Fr as ff::PrimeField>::Repr>()
 }
 
+// The tuned core count of `d`, benchmarking it first if auto-tuning is
+// enabled, or the one from the table of known devices.
+fn device_core_count<E: Engine>(d: &opencl::Device, options: &GpuOptions) -> usize {
+    let name = d.name();
+    if let Some(core_count) = tuning::tuned_core_count(&name) {
+        return core_count;
+    }
+
+    if tuning::autotune_enabled() {
+        match tune_core_count::<E>(d, options) {
+            Ok(core_count) => {
+                info!("Multiexp: Tuned '{}' to {} cores.", name, core_count);
+                tuning::store_tuned_core_count(&name, core_count);
+                return core_count;
+            }
+            Err(e) => warn!("Cannot tune device '{}'! Error: {}", name, e),
+        }
+    }
+
+    utils::get_core_count(d)
+}
+
+/// Times a G1 multiexp on `d` for core counts around the known or default
+/// one, which sets the window size, the number of groups and the chunk
+/// length, and returns the fastest.
+pub fn tune_core_count<E: Engine>(d: &opencl::Device, options: &GpuOptions) -> GPUResult<usize> {
+    const TUNING_LEN: usize = 1 << 16;
+
+    let rng = &mut rand::thread_rng();
+    let bases = (0..TUNING_LEN)
+        .map(|_| E::G1::random(rng).into_affine())
+        .collect::<Vec<_>>();
+    let exps = (0..TUNING_LEN)
+        .map(|_| E::Fr::random(rng).into_repr())
+        .collect::<Vec<_>>();
+
+    let known = utils::get_core_count(d);
+    if known == 0 {
+        return Err(GPUError::Simple("The device has no known core count!"));
+    }
+    let mut best = (known, std::time::Duration::from_secs(u64::MAX));
+    for &(num, den) in &[(1, 2), (3, 4), (1, 1), (5, 4), (3, 2), (2, 1)] {
+        let core_count = known * num / den;
+        if core_count == 0 {
+            continue;
+        }
+        let mut kern = SingleMultiexpKernel::<E>::create_with_core_count(
+            d.clone(),
+            false,
+            options,
+            core_count,
+        )?;
+        let n = std::cmp::min(kern.n, TUNING_LEN);
+
+        let start = std::time::Instant::now();
+        kern.multiexp(&bases[..n], &exps[..n], n)?;
+        // Scale to the whole input, since smaller chunks need more launches.
+        let elapsed = start.elapsed() * ((TUNING_LEN + n - 1) / n) as u32;
+        if elapsed < best.1 {
+            best = (core_count, elapsed);
+        }
+    }
+
+    Ok(best.0)
+}
+
 impl<E> SingleMultiexpKernel<E>
 where
     E: Engine,
 {
     pub fn create(d: opencl::Device, priority: bool) -> GPUResult<SingleMultiexpKernel<E>> {
+        Self::create_with_options(d, priority, &GpuOptions::default())
+    }
+
+    pub fn create_with_options(
+        d: opencl::Device,
+        priority: bool,
+        options: &GpuOptions,
+    ) -> GPUResult<SingleMultiexpKernel<E>> {
+        let core_count = device_core_count::<E>(&d, options);
+        Self::create_with_core_count(d, priority, options, core_count)
+    }
+
+    fn create_with_core_count(
+        d: opencl::Device,
+        priority: bool,
+        options: &GpuOptions,
+        core_count: usize,
+    ) -> GPUResult<SingleMultiexpKernel<E>> {
+        if core_count == 0 {
+            return Err(GPUError::Simple("The core count must be positive!"));
+        }
+
         let src = sources::kernel::<E>(d.brand() == opencl::Brand::Nvidia);
 
         let exp_bits = exp_size::<E>() * 8;
-        let core_count = utils::get_core_count(&d);
-        let mem = d.memory();
-        let max_n = calc_chunk_size::<E>(mem, core_count);
+        let mem = options.device_memory(d.memory());
+        let max_n = calc_chunk_size::<E>(mem, core_count)?;
         let best_n = calc_best_chunk_size(MAX_WINDOW_SIZE, core_count, exp_bits);
         let n = std::cmp::min(max_n, best_n);
+        if n == 0 {
+            return Err(GPUError::Simple(
+                "Not enough GPU memory for a multiexp chunk!",
+            ));
+        }
 
         Ok(SingleMultiexpKernel {
             program: opencl::Program::from_opencl(d, &src)?,
@@ -221,13 +320,25 @@ where
     E: Engine,
 {
     pub fn create(priority: bool) -> GPUResult<MultiexpKernel<E>> {
+        Self::create_with_options(priority, &GpuOptions::default())
+    }
+
+    pub fn create_with_options(
+        priority: bool,
+        options: &GpuOptions,
+    ) -> GPUResult<MultiexpKernel<E>> {
         let lock = locks::GPULock::lock();
 
-        let devices = opencl::Device::all()?;
+        let devices = options.select_devices(opencl::Device::all()?);
 
         let kernels: Vec<_> = devices
             .into_iter()
-            .map(|d| (d.clone(), SingleMultiexpKernel::<E>::create(d, priority)))
+            .map(|d| {
+                (
+                    d.clone(),
+                    SingleMultiexpKernel::<E>::create_with_options(d, priority, options),
+                )
+            })
             .filter_map(|(device, res)| {
                 if let Err(ref e) = res {
                     error!(
@@ -274,7 +385,6 @@ where
         G: CurveAffine,
         <G as groupy::CurveAffine>::Engine: crate::bls::Engine,
     {
-        let num_devices = self.kernels.len();
         // Bases are skipped by `self.1` elements, when converted from (Arc<Vec<G>>, usize) to Source
         // https://github.com/zkcrypto/bellman/blob/10c5010fd9c2ca69442dc9775ea271e286e776d8/src/multiexp.rs#L38
         let bases = &bases[skip..(skip + n)];
@@ -285,7 +395,14 @@ where
         let (cpu_bases, bases) = bases.split_at(cpu_n);
         let (cpu_exps, exps) = exps.split_at(cpu_n);
 
-        let chunk_size = ((n as f64) / (num_devices as f64)).ceil() as usize;
+        // Faster devices get a larger share, which each of them processes in
+        // chunks that fit its memory.
+        let core_counts = self
+            .kernels
+            .iter()
+            .map(|k| k.core_count)
+            .collect::<Vec<_>>();
+        let ranges = scheduler::split_by_weight(n, &core_counts);
 
         crate::multicore::THREAD_POOL.install(|| {
             use rayon::prelude::*;
@@ -293,19 +410,22 @@ where
             let mut acc = <G as CurveAffine>::Projective::zero();
 
             let results = if n > 0 {
-                bases
-                    .par_chunks(chunk_size)
-                    .zip(exps.par_chunks(chunk_size))
-                    .zip(self.kernels.par_iter_mut())
-                    .map(|((bases, exps), kern)| -> Result<<G as CurveAffine>::Projective, GPUError> {
-                        let mut acc = <G as CurveAffine>::Projective::zero();
-                        for (bases, exps) in bases.chunks(kern.n).zip(exps.chunks(kern.n)) {
-                            let result = kern.multiexp(bases, exps, bases.len())?;
-                            acc.add_assign(&result);
-                        }
-
-                        Ok(acc)
-                    })
+                self.kernels
+                    .par_iter_mut()
+                    .zip(ranges.into_par_iter())
+                    .map(
+                        |(kern, range)| -> Result<<G as CurveAffine>::Projective, GPUError> {
+                            let bases = &bases[range.clone()];
+                            let exps = &exps[range];
+                            let mut acc = <G as CurveAffine>::Projective::zero();
+                            for (bases, exps) in bases.chunks(kern.n).zip(exps.chunks(kern.n)) {
+                                let result = kern.multiexp(bases, exps, bases.len())?;
+                                acc.add_assign(&result);
+                            }
+
+                            Ok(acc)
+                        },
+                    )
                     .collect::<Vec<_>>()
             } else {
                 Vec::new()
@@ -1,4 +1,5 @@
 use super::error::{GPUError, GPUResult};
+use super::GpuOptions;
 use crate::multicore::Worker;
 use ff::{PrimeField, ScalarEngine};
 use groupy::CurveAffine;
@@ -19,9 +20,17 @@ where
         return Err(GPUError::GPUDisabled);
     }
 
+    pub fn create_with_options(_: bool, _: &GpuOptions) -> GPUResult<FFTKernel<E>> {
+        return Err(GPUError::GPUDisabled);
+    }
+
     pub fn radix_fft(&mut self, _: &mut [E::Fr], _: &E::Fr, _: u32) -> GPUResult<()> {
         return Err(GPUError::GPUDisabled);
     }
+
+    pub fn radix_fft_many(&mut self, _: &mut [&mut [E::Fr]], _: &E::Fr, _: u32) -> GPUResult<()> {
+        return Err(GPUError::GPUDisabled);
+    }
 }
 
 pub struct MultiexpKernel<E>(PhantomData<E>)
@@ -36,6 +45,10 @@ where
         return Err(GPUError::GPUDisabled);
     }
 
+    pub fn create_with_options(_: bool, _: &GpuOptions) -> GPUResult<MultiexpKernel<E>> {
+        return Err(GPUError::GPUDisabled);
+    }
+
     pub fn multiexp<G>(
         &mut self,
         _: &Worker,
@@ -65,6 +78,14 @@ macro_rules! locked_kernel {
                 $class::<E>(PhantomData)
             }
 
+            pub fn with_owner(self, _: String) -> $class<E> {
+                self
+            }
+
+            pub fn with_options(self, _: GpuOptions) -> $class<E> {
+                self
+            }
+
             pub fn with<F, R, K>(&mut self, _: F) -> GPUResult<R>
             where
                 F: FnMut(&mut K) -> GPUResult<R>,
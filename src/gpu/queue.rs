@@ -0,0 +1,233 @@
+//! Introspection of the GPU jobs of this process.
+//!
+//! Every locked kernel registers a job when it starts waiting for the GPU and
+//! removes it once the kernel is freed. Jobs of other processes sharing the
+//! GPU lock are not visible here.
+
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    static ref QUEUE: Mutex<Queue> = Mutex::new(Queue::default());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobKind {
+    FFT,
+    Multiexp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobState {
+    /// Waiting for the GPU to become available.
+    Pending,
+    /// Holding the GPU.
+    Active,
+}
+
+#[derive(Clone, Debug)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: JobKind,
+    pub state: JobState,
+    /// Log2 of the domain size the kernel was requested for.
+    pub log_d: usize,
+    pub priority: bool,
+    /// The proof (or batch of proofs) the job belongs to, if known.
+    pub owner: Option<String>,
+    /// Time spent in the current state.
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct QueueStatus {
+    /// Active jobs first, then pending jobs in the order they were queued.
+    pub jobs: Vec<JobInfo>,
+    /// Estimated time until a job queued now would get the GPU, based on the
+    /// average duration of previous jobs. `None` if there is no history yet.
+    pub estimated_wait: Option<Duration>,
+}
+
+impl QueueStatus {
+    pub fn pending(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|job| job.state == JobState::Pending)
+            .count()
+    }
+
+    pub fn active(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|job| job.state == JobState::Active)
+            .count()
+    }
+}
+
+/// Returns a snapshot of the GPU jobs of this process.
+pub fn queue_status() -> QueueStatus {
+    QUEUE.lock().unwrap().status()
+}
+
+struct Entry {
+    kind: JobKind,
+    state: JobState,
+    log_d: usize,
+    priority: bool,
+    owner: Option<String>,
+    since: Instant,
+}
+
+#[derive(Default)]
+struct Queue {
+    next_id: u64,
+    jobs: BTreeMap<u64, Entry>,
+    // (number of jobs, total time) of finished jobs, per kind.
+    fft_history: (u32, Duration),
+    multiexp_history: (u32, Duration),
+}
+
+impl Queue {
+    fn history(&mut self, kind: JobKind) -> &mut (u32, Duration) {
+        match kind {
+            JobKind::FFT => &mut self.fft_history,
+            JobKind::Multiexp => &mut self.multiexp_history,
+        }
+    }
+
+    fn average(&mut self, kind: JobKind) -> Option<Duration> {
+        let (count, total) = *self.history(kind);
+        if count == 0 {
+            None
+        } else {
+            Some(total / count)
+        }
+    }
+
+    fn status(&mut self) -> QueueStatus {
+        let now = Instant::now();
+
+        let mut jobs = self
+            .jobs
+            .iter()
+            .map(|(&id, entry)| JobInfo {
+                id,
+                kind: entry.kind,
+                state: entry.state,
+                log_d: entry.log_d,
+                priority: entry.priority,
+                owner: entry.owner.clone(),
+                elapsed: now - entry.since,
+            })
+            .collect::<Vec<_>>();
+        // Stable, so pending jobs stay in queue order.
+        jobs.sort_by_key(|job| job.state == JobState::Pending);
+
+        let mut estimated_wait = Some(Duration::default());
+        for job in &jobs {
+            estimated_wait = match (estimated_wait, self.average(job.kind)) {
+                (Some(wait), Some(average)) => Some(match job.state {
+                    JobState::Active => wait + average.checked_sub(job.elapsed).unwrap_or_default(),
+                    JobState::Pending => wait + average,
+                }),
+                _ => None,
+            };
+        }
+
+        QueueStatus {
+            jobs,
+            estimated_wait,
+        }
+    }
+}
+
+/// A registered GPU job, removed from the queue when dropped.
+#[cfg_attr(not(feature = "gpu"), allow(dead_code))]
+pub(crate) struct Job(u64);
+
+#[cfg_attr(not(feature = "gpu"), allow(dead_code))]
+impl Job {
+    pub(crate) fn new(kind: JobKind, log_d: usize, priority: bool, owner: Option<String>) -> Job {
+        let mut queue = QUEUE.lock().unwrap();
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.jobs.insert(
+            id,
+            Entry {
+                kind,
+                state: JobState::Pending,
+                log_d,
+                priority,
+                owner,
+                since: Instant::now(),
+            },
+        );
+        Job(id)
+    }
+
+    pub(crate) fn activate(&self) {
+        if let Some(entry) = QUEUE.lock().unwrap().jobs.get_mut(&self.0) {
+            entry.state = JobState::Active;
+            entry.since = Instant::now();
+        }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        let mut queue = QUEUE.lock().unwrap();
+        if let Some(entry) = queue.jobs.remove(&self.0) {
+            if entry.state == JobState::Active {
+                let history = queue.history(entry.kind);
+                history.0 += 1;
+                history.1 += entry.since.elapsed();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(id: u64) -> Option<JobInfo> {
+        queue_status().jobs.into_iter().find(|job| job.id == id)
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let job = Job::new(JobKind::FFT, 10, false, Some("test proof".to_string()));
+
+        let info = find(job.0).unwrap();
+        assert_eq!(info.kind, JobKind::FFT);
+        assert_eq!(info.state, JobState::Pending);
+        assert_eq!(info.log_d, 10);
+        assert!(!info.priority);
+        assert_eq!(info.owner.as_deref(), Some("test proof"));
+
+        job.activate();
+        assert_eq!(find(job.0).unwrap().state, JobState::Active);
+
+        let id = job.0;
+        drop(job);
+        assert!(find(id).is_none());
+
+        // A finished active job provides history for the estimate.
+        assert!(QUEUE.lock().unwrap().average(JobKind::FFT).is_some());
+    }
+
+    #[test]
+    fn test_queue_order() {
+        let pending = Job::new(JobKind::Multiexp, 5, false, None);
+        let active = Job::new(JobKind::Multiexp, 5, true, None);
+        active.activate();
+
+        let status = queue_status();
+        let position = |id| status.jobs.iter().position(|job| job.id == id).unwrap();
+        assert!(position(active.0) < position(pending.0));
+        assert!(status.active() >= 1);
+        assert!(status.pending() >= 1);
+    }
+}
@@ -0,0 +1,50 @@
+use std::ops::Range;
+
+/// Splits `0..n` into contiguous ranges, one per device, whose lengths are
+/// proportional to the `weights` of the devices, e.g. their core counts.
+///
+/// The ranges cover `0..n` in order. If all weights are zero, the elements
+/// are split evenly.
+pub fn split_by_weight(n: usize, weights: &[usize]) -> Vec<Range<usize>> {
+    let total = weights.iter().map(|&w| w as u128).sum::<u128>();
+    if total == 0 {
+        return split_by_weight(n, &vec![1; weights.len()]);
+    }
+
+    let mut ranges = Vec::with_capacity(weights.len());
+    let mut start = 0;
+    let mut acc = 0u128;
+    for &weight in weights {
+        acc += weight as u128;
+        let end = ((n as u128) * acc / total) as usize;
+        ranges.push(start..end);
+        start = end;
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_by_weight() {
+        assert_eq!(split_by_weight(10, &[1, 1]), vec![0..5, 5..10]);
+        assert_eq!(split_by_weight(12, &[1, 2, 3]), vec![0..2, 2..6, 6..12]);
+        assert_eq!(split_by_weight(3, &[1, 1]), vec![0..1, 1..3]);
+        assert_eq!(split_by_weight(2, &[0, 5]), vec![0..0, 0..2]);
+        assert_eq!(split_by_weight(4, &[0, 0]), vec![0..2, 2..4]);
+        assert_eq!(split_by_weight(0, &[3, 1]), vec![0..0, 0..0]);
+        assert!(split_by_weight(5, &[]).is_empty());
+
+        for n in 0..50 {
+            let ranges = split_by_weight(n, &[3840, 2304, 4352]);
+            assert_eq!(ranges[0].start, 0);
+            assert_eq!(ranges[2].end, n);
+            for pair in ranges.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use log::warn;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const TUNING_FILE_NAME: &str = "bellman.gpu-tuning";
+
+/// More cores than any device has, so larger entries are rejected.
+const MAX_CORE_COUNT: usize = 1 << 20;
+
+lazy_static::lazy_static! {
+    static ref CACHE: Mutex<Option<TuningCache>> = Mutex::new(None);
+}
+
+/// Tuned core counts of GPU models, by device name.
+///
+/// The multiexp kernel derives its window size, the number of groups and its
+/// chunk length from the core count of the device, so one measured value
+/// replaces the hard-coded table in `utils` for a device. The file holds one
+/// `name:cores` line per device, like `BELLMAN_CUSTOM_GPU`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TuningCache {
+    core_counts: BTreeMap<String, usize>,
+}
+
+impl TuningCache {
+    /// `BELLMAN_GPU_TUNING_FILE`, or `bellman.gpu-tuning` in the cache
+    /// directory of the user, `None` if there is neither.
+    ///
+    /// The shared temporary directory is not used, since another user could
+    /// create the file there first and choose the kernel parameters.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("BELLMAN_GPU_TUNING_FILE") {
+            return Some(PathBuf::from(path));
+        }
+        user_cache_dir().map(|dir| dir.join(TUNING_FILE_NAME))
+    }
+
+    /// Reads the cache at `path`, which is empty if the file does not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the cache to `path`, replacing it atomically so that concurrent
+    /// readers never see a partial file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp, self.contents())?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn core_count(&self, device: &str) -> Option<usize> {
+        self.core_counts.get(device).copied()
+    }
+
+    /// Panics if `core_count` is zero.
+    pub fn set_core_count(&mut self, device: &str, core_count: usize) {
+        assert!(core_count > 0, "a device has at least one core");
+        self.core_counts.insert(device.to_string(), core_count);
+    }
+
+    fn parse(contents: &str) -> io::Result<Self> {
+        let mut cache = Self::default();
+        for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let mut parts = line.rsplitn(2, ':');
+            let cores = parts
+                .next()
+                .and_then(|c| c.trim().parse().ok())
+                .filter(|&c| c > 0 && c <= MAX_CORE_COUNT);
+            match (parts.next(), cores) {
+                (Some(name), Some(cores)) => cache.set_core_count(name.trim(), cores),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid GPU tuning entry: {}", line),
+                    ))
+                }
+            }
+        }
+        Ok(cache)
+    }
+
+    fn contents(&self) -> String {
+        self.core_counts
+            .iter()
+            .map(|(name, cores)| format!("{}:{}\n", name, cores))
+            .collect()
+    }
+}
+
+// `XDG_CACHE_HOME`, `~/.cache` or `%LOCALAPPDATA%`.
+fn user_cache_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    if cfg!(windows) {
+        var("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        var("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| Path::new(&home).join(".cache")))
+    }
+}
+
+/// Whether kernels benchmark devices missing from the tuning cache, which is
+/// enabled by setting `BELLMAN_GPU_AUTOTUNE`.
+pub fn autotune_enabled() -> bool {
+    std::env::var("BELLMAN_GPU_AUTOTUNE").is_ok()
+}
+
+/// The tuned core count of `device` from the tuning file, if any.
+pub fn tuned_core_count(device: &str) -> Option<usize> {
+    let mut cache = CACHE.lock().unwrap();
+    if cache.is_none() {
+        let loaded = match TuningCache::path() {
+            Some(path) => TuningCache::load(path).unwrap_or_else(|e| {
+                warn!("Cannot read the GPU tuning file! Error: {}", e);
+                TuningCache::default()
+            }),
+            None => TuningCache::default(),
+        };
+        *cache = Some(loaded);
+    }
+    cache.as_ref().unwrap().core_count(device)
+}
+
+/// Records the tuned core count of `device` and persists it to the tuning
+/// file, if there is one.
+pub fn store_tuned_core_count(device: &str, core_count: usize) {
+    let mut cache = CACHE.lock().unwrap();
+    let path = TuningCache::path();
+    // Merge with entries other processes may have written in the meantime.
+    let mut merged = match &path {
+        Some(path) => TuningCache::load(path).unwrap_or_default(),
+        None => cache.take().unwrap_or_default(),
+    };
+    merged.set_core_count(device, core_count);
+    if let Some(path) = path {
+        if let Err(e) = merged.save(path) {
+            warn!("Cannot write the GPU tuning file! Error: {}", e);
+        }
+    }
+    *cache = Some(merged);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tuning_cache() {
+        let mut cache = TuningCache::default();
+        cache.set_core_count("GeForce RTX 3090", 10496);
+        cache.set_core_count("gfx906", 7400);
+        assert_eq!(cache.core_count("gfx906"), Some(7400));
+        assert_eq!(cache.core_count("Tesla T4"), None);
+
+        assert_eq!(TuningCache::parse(&cache.contents()).unwrap(), cache);
+        // Device names may contain colons, the core count comes last.
+        let parsed = TuningCache::parse("\nDevice: A : 64\n").unwrap();
+        assert_eq!(parsed.core_count("Device: A"), Some(64));
+        assert!(TuningCache::parse("GeForce RTX 3090").is_err());
+        assert!(TuningCache::parse("GeForce RTX 3090:many").is_err());
+        assert!(TuningCache::parse("GeForce RTX 3090:0").is_err());
+        assert!(TuningCache::parse("GeForce RTX 3090:4294967296").is_err());
+
+        let path = std::env::temp_dir().join(format!("bellman-tuning-test-{}", std::process::id()));
+        assert_eq!(TuningCache::load(&path).unwrap(), TuningCache::default());
+        cache.save(&path).unwrap();
+        assert_eq!(TuningCache::load(&path).unwrap(), cache);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,836 @@
+//! Aggregation of Groth16 proofs with [SnarkPack].
+//!
+//! An `AggregateProof` of `n` proofs, `n` a power of two, has a size and a
+//! verification time logarithmic in `n`. The prover commits to the `A`, `B`
+//! and `C` points of the proofs and shows with the inner product arguments
+//! TIPP and MIPP that `prod e(A_i, B_i)^(r^i)` and `sum r^i C_i` are the
+//! combinations of the committed points for a random `r`. The verifier then
+//! checks these two combinations against the verifying key, like the batch
+//! verifier does for the proofs themselves.
+//!
+//! The commitment keys are powers of two secrets `alpha` and `beta` in both
+//! groups. Whoever knows the secrets can forge aggregate proofs, so in
+//! production the powers have to come from two independent powers of tau
+//! ceremonies. `setup_fake_srs` samples them locally and is only fit for
+//! tests.
+//!
+//! [SnarkPack]: https://eprint.iacr.org/2021/529
+
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective};
+use rand_core::RngCore;
+use rayon::prelude::*;
+
+use super::{fp12_to_byteblob, PreparedVerifyingKey, Proof};
+use crate::bls::{Bls12, Engine, Fq12, Fr, G1Affine, G2Affine, PairingCurveAffine};
+use crate::multicore::{THREAD_POOL, VERIFIER_POOL as POOL};
+use crate::transcript::{Blake2bTranscript, Transcript};
+use crate::SynthesisError;
+
+/// A commitment under the keys from the powers of `alpha` and of `beta`.
+pub type Commitment = (Fq12, Fq12);
+
+/// Powers `g^(alpha^i)`, `h^(alpha^i)`, `g^(beta^i)` and `h^(beta^i)` of the
+/// generators `g` of G1 and `h` of G2, starting at `i = 0`.
+#[derive(Clone, Debug)]
+pub struct GenericSrs {
+    pub g_alpha_powers: Vec<G1Affine>,
+    pub h_alpha_powers: Vec<G2Affine>,
+    pub g_beta_powers: Vec<G1Affine>,
+    pub h_beta_powers: Vec<G2Affine>,
+}
+
+/// The part of a `GenericSrs` needed to aggregate exactly `n` proofs.
+#[derive(Clone, Debug)]
+pub struct ProverSrs {
+    pub n: usize,
+    // Commitment keys, `h^(alpha^i)` and `h^(beta^i)` for `A` and `C`, and
+    // `g^(alpha^(n + i))` and `g^(beta^(n + i))` for `B`, with `i < n`.
+    vkey: (Vec<G2Affine>, Vec<G2Affine>),
+    wkey: (Vec<G1Affine>, Vec<G1Affine>),
+    // Powers to open the folded keys, `n` in G1 and `2n` in G2.
+    g_alpha_powers: Vec<G1Affine>,
+    g_beta_powers: Vec<G1Affine>,
+    h_alpha_powers: Vec<G2Affine>,
+    h_beta_powers: Vec<G2Affine>,
+}
+
+/// The part of a `GenericSrs` needed to verify aggregates of `n` proofs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifierSrs {
+    pub n: usize,
+    pub g: G1Affine,
+    pub h: G2Affine,
+    pub g_alpha: G1Affine,
+    pub g_beta: G1Affine,
+    pub h_alpha: G2Affine,
+    pub h_beta: G2Affine,
+}
+
+/// Samples an SRS with `size` powers, enough to aggregate up to `size / 2`
+/// proofs. Only for tests, see the module documentation.
+pub fn setup_fake_srs<R: RngCore>(rng: &mut R, size: usize) -> GenericSrs {
+    let alpha = powers(&Fr::random(rng), size);
+    let beta = powers(&Fr::random(rng), size);
+
+    GenericSrs {
+        g_alpha_powers: scale(&vec![G1Affine::one(); size], &alpha),
+        h_alpha_powers: scale(&vec![G2Affine::one(); size], &alpha),
+        g_beta_powers: scale(&vec![G1Affine::one(); size], &beta),
+        h_beta_powers: scale(&vec![G2Affine::one(); size], &beta),
+    }
+}
+
+impl GenericSrs {
+    /// Extracts the keys to aggregate exactly `n` proofs. `n` has to be a
+    /// power of two, at least 2 and at most half the number of powers.
+    pub fn specialize(&self, n: usize) -> Result<(ProverSrs, VerifierSrs), SynthesisError> {
+        if n < 2 || !n.is_power_of_two() {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "can only aggregate a power of two of at least 2 proofs, not {}",
+                n
+            )));
+        }
+        let size = self
+            .g_alpha_powers
+            .len()
+            .min(self.h_alpha_powers.len())
+            .min(self.g_beta_powers.len())
+            .min(self.h_beta_powers.len());
+        if 2 * n > size {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "aggregating {} proofs needs {} powers, the SRS has {}",
+                n,
+                2 * n,
+                size
+            )));
+        }
+
+        let prover = ProverSrs {
+            n,
+            vkey: (
+                self.h_alpha_powers[..n].to_vec(),
+                self.h_beta_powers[..n].to_vec(),
+            ),
+            wkey: (
+                self.g_alpha_powers[n..2 * n].to_vec(),
+                self.g_beta_powers[n..2 * n].to_vec(),
+            ),
+            g_alpha_powers: self.g_alpha_powers[..n].to_vec(),
+            g_beta_powers: self.g_beta_powers[..n].to_vec(),
+            h_alpha_powers: self.h_alpha_powers[..2 * n].to_vec(),
+            h_beta_powers: self.h_beta_powers[..2 * n].to_vec(),
+        };
+        let verifier = VerifierSrs {
+            n,
+            g: self.g_alpha_powers[0],
+            h: self.h_alpha_powers[0],
+            g_alpha: self.g_alpha_powers[1],
+            g_beta: self.g_beta_powers[1],
+            h_alpha: self.h_alpha_powers[1],
+            h_beta: self.h_beta_powers[1],
+        };
+
+        Ok((prover, verifier))
+    }
+}
+
+/// The rounds of the combined TIPP and MIPP argument. Every round halves the
+/// vectors, the left and right cross terms of which are sent as
+/// `(left, right)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GipaProof {
+    pub comms_ab: Vec<(Commitment, Commitment)>,
+    pub comms_c: Vec<(Commitment, Commitment)>,
+    pub z_ab: Vec<(Fq12, Fq12)>,
+    pub z_c: Vec<(G1Affine, G1Affine)>,
+    pub final_a: G1Affine,
+    pub final_b: G2Affine,
+    pub final_c: G1Affine,
+    pub final_vkey: (G2Affine, G2Affine),
+    pub final_wkey: (G1Affine, G1Affine),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateProof {
+    /// Commitment to the `A` and `B` points of the proofs.
+    pub com_ab: Commitment,
+    /// Commitment to the `C` points of the proofs.
+    pub com_c: Commitment,
+    /// `prod e(A_i, B_i)^(r^i)`.
+    pub ip_ab: Fq12,
+    /// `sum r^i C_i`.
+    pub agg_c: G1Affine,
+    pub gipa: GipaProof,
+    /// KZG openings of the folded keys at a random point, for the powers of
+    /// `alpha` and of `beta`.
+    pub vkey_opening: (G1Affine, G1Affine),
+    pub wkey_opening: (G2Affine, G2Affine),
+}
+
+/// Aggregates `srs.n` proofs, which may be for different inputs and
+/// circuits. The aggregate is only checked against the verifying key by
+/// `verify_aggregate_proof`, invalid proofs give an invalid aggregate.
+pub fn aggregate_proofs(
+    srs: &ProverSrs,
+    proofs: &[Proof<Bls12>],
+) -> Result<AggregateProof, SynthesisError> {
+    let n = proofs.len();
+    if n != srs.n {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} proofs for an SRS specialized to {}",
+            n, srs.n
+        )));
+    }
+
+    THREAD_POOL.install(|| {
+        let a = proofs.iter().map(|p| p.a).collect::<Vec<_>>();
+        let b = proofs.iter().map(|p| p.b).collect::<Vec<_>>();
+        let c = proofs.iter().map(|p| p.c).collect::<Vec<_>>();
+
+        let vkey = (&srs.vkey.0[..], &srs.vkey.1[..]);
+        let com_ab = commit_pair(vkey, (&srs.wkey.0[..], &srs.wkey.1[..]), &a, &b);
+        let com_c = commit_single(vkey, &c);
+
+        let mut transcript = AggregateTranscript::new(n);
+        transcript.commitment(&com_ab);
+        transcript.commitment(&com_c);
+        let r = transcript.challenge();
+        let r_inv = r.inverse().unwrap();
+        let r_powers = powers(&r, n);
+        let r_inv_powers = powers(&r_inv, n);
+
+        // The `B_i^(r^i)` have the same commitment under the `w_i^(r^-i)` as
+        // the `B_i` under the `w_i`.
+        let b_r = scale(&b, &r_powers);
+        let wkey_r_inv = (
+            scale(&srs.wkey.0, &r_inv_powers),
+            scale(&srs.wkey.1, &r_inv_powers),
+        );
+        let ip_ab = multi_pairing(&[(&a[..], &b_r[..])]);
+        let agg_c = msm(&c, &r_powers).into_affine();
+        transcript.gt(&ip_ab);
+        transcript.g1(&agg_c);
+
+        let (gipa, challenges) = prove_gipa(
+            &mut transcript,
+            a,
+            b_r,
+            c,
+            r_powers,
+            srs.vkey.clone(),
+            wkey_r_inv,
+        );
+        let z = transcript.challenge();
+
+        let challenges_inv = challenges
+            .iter()
+            .map(|x| x.inverse().unwrap())
+            .collect::<Vec<_>>();
+        let vkey_coeffs = folding_coefficients(&challenges_inv);
+        let mut wkey_coeffs = vec![Fr::zero(); n];
+        wkey_coeffs.extend(folding_coefficients(&wkey_challenges(&challenges, &r_inv)));
+
+        Ok(AggregateProof {
+            com_ab,
+            com_c,
+            ip_ab,
+            agg_c,
+            gipa,
+            vkey_opening: (
+                open(&srs.g_alpha_powers, &vkey_coeffs, &z),
+                open(&srs.g_beta_powers, &vkey_coeffs, &z),
+            ),
+            wkey_opening: (
+                open(&srs.h_alpha_powers, &wkey_coeffs, &z),
+                open(&srs.h_beta_powers, &wkey_coeffs, &z),
+            ),
+        })
+    })
+}
+
+/// Verifies an aggregate of proofs for the inputs `public_inputs`, one
+/// vector per proof in the order they were aggregated.
+pub fn verify_aggregate_proof(
+    srs: &VerifierSrs,
+    pvk: &PreparedVerifyingKey<Bls12>,
+    public_inputs: &[Vec<Fr>],
+    proof: &AggregateProof,
+) -> Result<bool, SynthesisError> {
+    let n = public_inputs.len();
+    if n != srs.n {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "inputs of {} proofs for an SRS specialized to {}",
+            n, srs.n
+        )));
+    }
+    if public_inputs
+        .iter()
+        .any(|inputs| inputs.len() + 1 != pvk.ic.len())
+    {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    let gipa = &proof.gipa;
+    let rounds = n.trailing_zeros() as usize;
+    if gipa.comms_ab.len() != rounds
+        || gipa.comms_c.len() != rounds
+        || gipa.z_ab.len() != rounds
+        || gipa.z_c.len() != rounds
+    {
+        return Ok(false);
+    }
+
+    POOL.install(|| {
+        let mut transcript = AggregateTranscript::new(n);
+        transcript.commitment(&proof.com_ab);
+        transcript.commitment(&proof.com_c);
+        let r = transcript.challenge();
+        let r_inv = r.inverse().unwrap();
+        transcript.gt(&proof.ip_ab);
+        transcript.g1(&proof.agg_c);
+
+        let mut challenges = Vec::with_capacity(rounds);
+        for i in 0..rounds {
+            transcript.round(
+                &gipa.z_ab[i],
+                &gipa.z_c[i],
+                &gipa.comms_ab[i],
+                &gipa.comms_c[i],
+            );
+            challenges.push(transcript.challenge());
+        }
+        transcript.gipa_final(gipa);
+        let z = transcript.challenge();
+
+        let challenges_inv = challenges
+            .iter()
+            .map(|x| x.inverse().unwrap())
+            .collect::<Vec<_>>();
+
+        // Fold the claimed values the way the prover folded the vectors.
+        let mut com_ab = proof.com_ab;
+        let mut com_c = proof.com_c;
+        let mut z_ab = proof.ip_ab;
+        let mut z_c = proof.agg_c.into_projective();
+        for i in 0..rounds {
+            let (x, x_inv) = (&challenges[i], &challenges_inv[i]);
+            com_ab = (
+                fold_gt(
+                    &com_ab.0,
+                    &(gipa.comms_ab[i].0).0,
+                    &(gipa.comms_ab[i].1).0,
+                    x,
+                    x_inv,
+                ),
+                fold_gt(
+                    &com_ab.1,
+                    &(gipa.comms_ab[i].0).1,
+                    &(gipa.comms_ab[i].1).1,
+                    x,
+                    x_inv,
+                ),
+            );
+            com_c = (
+                fold_gt(
+                    &com_c.0,
+                    &(gipa.comms_c[i].0).0,
+                    &(gipa.comms_c[i].1).0,
+                    x,
+                    x_inv,
+                ),
+                fold_gt(
+                    &com_c.1,
+                    &(gipa.comms_c[i].0).1,
+                    &(gipa.comms_c[i].1).1,
+                    x,
+                    x_inv,
+                ),
+            );
+            z_ab = fold_gt(&z_ab, &gipa.z_ab[i].0, &gipa.z_ab[i].1, x, x_inv);
+            z_c.add_assign(&gipa.z_c[i].0.mul(x.into_repr()));
+            z_c.add_assign(&gipa.z_c[i].1.mul(x_inv.into_repr()));
+        }
+
+        let (a, b, c) = (gipa.final_a, gipa.final_b, gipa.final_c);
+        let (v, w) = (gipa.final_vkey, gipa.final_wkey);
+
+        let tipp = com_ab.0 == pairing(&[(&a, &v.0), (&w.0, &b)])
+            && com_ab.1 == pairing(&[(&a, &v.1), (&w.1, &b)])
+            && z_ab == pairing(&[(&a, &b)]);
+
+        let r_final = fold_evaluation(&challenges_inv, &r);
+        let mipp = com_c.0 == pairing(&[(&c, &v.0)])
+            && com_c.1 == pairing(&[(&c, &v.1)])
+            && z_c == c.mul(r_final.into_repr());
+
+        // The final keys have to be the folded commitment keys, which the
+        // prover shows by opening them as polynomials in the secrets.
+        let v_eval = fold_evaluation(&challenges_inv, &z);
+        let mut w_eval = z.pow([n as u64]);
+        w_eval.mul_assign(&fold_evaluation(&wkey_challenges(&challenges, &r_inv), &z));
+        let kzg = check_opening_g2(srs, &v.0, &srs.h_alpha, &v_eval, &z, &proof.vkey_opening.0)
+            && check_opening_g2(srs, &v.1, &srs.h_beta, &v_eval, &z, &proof.vkey_opening.1)
+            && check_opening_g1(srs, &w.0, &srs.g_alpha, &w_eval, &z, &proof.wkey_opening.0)
+            && check_opening_g1(srs, &w.1, &srs.g_beta, &w_eval, &z, &proof.wkey_opening.1);
+
+        // prod e(A_i, B_i)^(r^i)
+        //     = e(alpha, beta)^(sum r^i) e(sum r^i acc_i, gamma) e(sum r^i C_i, delta)
+        let mut scalars = vec![Fr::zero(); pvk.ic.len()];
+        for (r_i, inputs) in powers(&r, n).iter().zip(public_inputs) {
+            scalars[0].add_assign(r_i);
+            for (scalar, input) in scalars[1..].iter_mut().zip(inputs) {
+                let mut term = *input;
+                term.mul_assign(r_i);
+                scalar.add_assign(&term);
+            }
+        }
+        let acc = msm(&pvk.ic, &scalars).into_affine();
+        let mut groth16 = pvk.alpha_g1_beta_g2.pow(scalars[0].into_repr());
+        groth16.mul_assign(&pairing(&[
+            (&acc, &pvk.gamma_g2),
+            (&proof.agg_c, &pvk.delta_g2),
+        ]));
+
+        Ok(tipp && mipp && kzg && groth16 == proof.ip_ab)
+    })
+}
+
+// Runs the rounds of TIPP for `prod e(a_i, b_i)` and of MIPP for
+// `sum r_i c_i` with the same challenges. Returns the proof and the
+// challenges of the rounds.
+fn prove_gipa(
+    transcript: &mut AggregateTranscript,
+    mut a: Vec<G1Affine>,
+    mut b: Vec<G2Affine>,
+    mut c: Vec<G1Affine>,
+    mut r: Vec<Fr>,
+    mut v: (Vec<G2Affine>, Vec<G2Affine>),
+    mut w: (Vec<G1Affine>, Vec<G1Affine>),
+) -> (GipaProof, Vec<Fr>) {
+    let mut comms_ab = vec![];
+    let mut comms_c = vec![];
+    let mut z_ab = vec![];
+    let mut z_c = vec![];
+    let mut challenges = vec![];
+
+    while a.len() > 1 {
+        let m = a.len() / 2;
+        let (a_l, a_r) = a.split_at(m);
+        let (b_l, b_r) = b.split_at(m);
+        let (c_l, c_r) = c.split_at(m);
+        let (r_l, r_r) = r.split_at(m);
+        let (va_l, va_r) = v.0.split_at(m);
+        let (vb_l, vb_r) = v.1.split_at(m);
+        let (wa_l, wa_r) = w.0.split_at(m);
+        let (wb_l, wb_r) = w.1.split_at(m);
+
+        // The cross terms combine the right half of `a`, `c` and `w` with the
+        // left half of `b`, `r` and `v`, and the other way around.
+        let round_z_ab = (multi_pairing(&[(a_r, b_l)]), multi_pairing(&[(a_l, b_r)]));
+        let round_z_c = (msm(c_r, r_l).into_affine(), msm(c_l, r_r).into_affine());
+        let round_comms_ab = (
+            commit_pair((va_l, vb_l), (wa_r, wb_r), a_r, b_l),
+            commit_pair((va_r, vb_r), (wa_l, wb_l), a_l, b_r),
+        );
+        let round_comms_c = (
+            commit_single((va_l, vb_l), c_r),
+            commit_single((va_r, vb_r), c_l),
+        );
+
+        transcript.round(&round_z_ab, &round_z_c, &round_comms_ab, &round_comms_c);
+        let x = transcript.challenge();
+        let x_inv = x.inverse().unwrap();
+
+        a = fold(a_l, a_r, &x);
+        b = fold(b_l, b_r, &x_inv);
+        c = fold(c_l, c_r, &x);
+        r = fold_scalars(r_l, r_r, &x_inv);
+        v = (fold(va_l, va_r, &x_inv), fold(vb_l, vb_r, &x_inv));
+        w = (fold(wa_l, wa_r, &x), fold(wb_l, wb_r, &x));
+
+        z_ab.push(round_z_ab);
+        z_c.push(round_z_c);
+        comms_ab.push(round_comms_ab);
+        comms_c.push(round_comms_c);
+        challenges.push(x);
+    }
+
+    let proof = GipaProof {
+        comms_ab,
+        comms_c,
+        z_ab,
+        z_c,
+        final_a: a[0],
+        final_b: b[0],
+        final_c: c[0],
+        final_vkey: (v.0[0], v.1[0]),
+        final_wkey: (w.0[0], w.1[0]),
+    };
+    transcript.gipa_final(&proof);
+
+    (proof, challenges)
+}
+
+// The transcript of an aggregation, with the messages of its rounds.
+struct AggregateTranscript(Blake2bTranscript);
+
+impl AggregateTranscript {
+    fn new(n: usize) -> Self {
+        let mut transcript = Blake2bTranscript::new(b"snarkpack");
+        transcript.append_message(b"n", &(n as u64).to_le_bytes());
+        AggregateTranscript(transcript)
+    }
+
+    fn g1(&mut self, p: &G1Affine) {
+        self.0.append_point(b"g1", p);
+    }
+
+    fn g2(&mut self, p: &G2Affine) {
+        self.0.append_point(b"g2", p);
+    }
+
+    fn gt(&mut self, x: &Fq12) {
+        self.0.append_message(b"gt", &fp12_to_byteblob(x));
+    }
+
+    fn commitment(&mut self, com: &Commitment) {
+        self.gt(&com.0);
+        self.gt(&com.1);
+    }
+
+    fn round(
+        &mut self,
+        z_ab: &(Fq12, Fq12),
+        z_c: &(G1Affine, G1Affine),
+        comms_ab: &(Commitment, Commitment),
+        comms_c: &(Commitment, Commitment),
+    ) {
+        self.gt(&z_ab.0);
+        self.gt(&z_ab.1);
+        self.g1(&z_c.0);
+        self.g1(&z_c.1);
+        self.commitment(&comms_ab.0);
+        self.commitment(&comms_ab.1);
+        self.commitment(&comms_c.0);
+        self.commitment(&comms_c.1);
+    }
+
+    fn gipa_final(&mut self, proof: &GipaProof) {
+        self.g1(&proof.final_a);
+        self.g2(&proof.final_b);
+        self.g1(&proof.final_c);
+        self.g2(&proof.final_vkey.0);
+        self.g2(&proof.final_vkey.1);
+        self.g1(&proof.final_wkey.0);
+        self.g1(&proof.final_wkey.1);
+    }
+
+    // A non-zero challenge, as the verifier inverts them.
+    fn challenge(&mut self) -> Fr {
+        loop {
+            let challenge = self.0.challenge_scalar::<Fr>(b"challenge");
+            if !challenge.is_zero() {
+                return challenge;
+            }
+        }
+    }
+}
+
+// Commitment to `a` under `v` and to `b` under `w`, that is
+// `prod e(a_i, v_i) e(w_i, b_i)` for both halves of the keys.
+fn commit_pair(
+    v: (&[G2Affine], &[G2Affine]),
+    w: (&[G1Affine], &[G1Affine]),
+    a: &[G1Affine],
+    b: &[G2Affine],
+) -> Commitment {
+    (
+        multi_pairing(&[(a, v.0), (w.0, b)]),
+        multi_pairing(&[(a, v.1), (w.1, b)]),
+    )
+}
+
+// Commitment to `c` under `v`, that is `prod e(c_i, v_i)` for both halves of
+// the key.
+fn commit_single(v: (&[G2Affine], &[G2Affine]), c: &[G1Affine]) -> Commitment {
+    (multi_pairing(&[(c, v.0)]), multi_pairing(&[(c, v.1)]))
+}
+
+// The product of the pairings of all the pairs of points of the slices.
+fn multi_pairing(pairs: &[(&[G1Affine], &[G2Affine])]) -> Fq12 {
+    let ml = pairs
+        .iter()
+        .map(|(a, b)| {
+            a.par_iter()
+                .zip(b.par_iter())
+                .map(|(a, b)| Bls12::miller_loop(&[(&a.prepare(), &b.prepare())]))
+                .reduce(Fq12::one, |mut acc, ml| {
+                    acc.mul_assign(&ml);
+                    acc
+                })
+        })
+        .fold(Fq12::one(), |mut acc, ml| {
+            acc.mul_assign(&ml);
+            acc
+        });
+
+    Bls12::final_exponentiation(&ml).unwrap()
+}
+
+// The product of the pairings of a few single pairs of points.
+fn pairing(pairs: &[(&G1Affine, &G2Affine)]) -> Fq12 {
+    let prepared = pairs
+        .iter()
+        .map(|(a, b)| (a.prepare(), b.prepare()))
+        .collect::<Vec<_>>();
+    let refs = prepared.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+
+    Bls12::final_exponentiation(&Bls12::miller_loop(&refs)).unwrap()
+}
+
+fn msm<G: CurveAffine<Scalar = Fr>>(bases: &[G], scalars: &[Fr]) -> G::Projective {
+    bases
+        .par_iter()
+        .zip(scalars.par_iter())
+        .map(|(base, scalar)| base.mul(scalar.into_repr()))
+        .reduce(G::Projective::zero, |mut acc, p| {
+            acc.add_assign(&p);
+            acc
+        })
+}
+
+// `points_i^(scalars_i)`
+fn scale<G: CurveAffine<Scalar = Fr>>(points: &[G], scalars: &[Fr]) -> Vec<G> {
+    points
+        .par_iter()
+        .zip(scalars.par_iter())
+        .map(|(p, s)| p.mul(s.into_repr()).into_affine())
+        .collect()
+}
+
+// `left_i right_i^x`
+fn fold<G: CurveAffine<Scalar = Fr>>(left: &[G], right: &[G], x: &Fr) -> Vec<G> {
+    let x = x.into_repr();
+    left.par_iter()
+        .zip(right.par_iter())
+        .map(|(l, r)| {
+            let mut p = r.mul(x);
+            p.add_assign_mixed(l);
+            p.into_affine()
+        })
+        .collect()
+}
+
+fn fold_scalars(left: &[Fr], right: &[Fr], x: &Fr) -> Vec<Fr> {
+    left.iter()
+        .zip(right)
+        .map(|(l, r)| {
+            let mut s = *r;
+            s.mul_assign(x);
+            s.add_assign(l);
+            s
+        })
+        .collect()
+}
+
+// `left^x value right^(x^-1)`
+fn fold_gt(value: &Fq12, left: &Fq12, right: &Fq12, x: &Fr, x_inv: &Fr) -> Fq12 {
+    let mut res = left.pow(x.into_repr());
+    res.mul_assign(value);
+    res.mul_assign(&right.pow(x_inv.into_repr()));
+    res
+}
+
+fn powers(x: &Fr, n: usize) -> Vec<Fr> {
+    let mut res = Vec::with_capacity(n);
+    let mut cur = Fr::one();
+    for _ in 0..n {
+        res.push(cur);
+        cur.mul_assign(x);
+    }
+    res
+}
+
+// A vector folded with the challenges `c_j` ends up as the combination of its
+// elements with the coefficients of `prod_j (1 + c_j X^(2^(k - 1 - j)))`,
+// where `k` is the number of rounds: the first round splits on the highest
+// bit of the index.
+fn folding_coefficients(challenges: &[Fr]) -> Vec<Fr> {
+    let mut coeffs = vec![Fr::one()];
+    for c in challenges.iter().rev() {
+        let high = coeffs
+            .iter()
+            .map(|coeff| {
+                let mut coeff = *coeff;
+                coeff.mul_assign(c);
+                coeff
+            })
+            .collect::<Vec<_>>();
+        coeffs.extend(high);
+    }
+    coeffs
+}
+
+// Evaluates the polynomial of `folding_coefficients` at `z`.
+fn fold_evaluation(challenges: &[Fr], z: &Fr) -> Fr {
+    let mut power = *z;
+    let mut res = Fr::one();
+    for c in challenges.iter().rev() {
+        let mut term = power;
+        term.mul_assign(c);
+        term.add_assign(&Fr::one());
+        res.mul_assign(&term);
+        power.square();
+    }
+    res
+}
+
+// The `w_i^(r^-i)` folded with the `x_j` are the `w_i` folded with
+// `x_j r^-(2^(k - 1 - j))`.
+fn wkey_challenges(challenges: &[Fr], r_inv: &Fr) -> Vec<Fr> {
+    let mut power = *r_inv;
+    let mut res = challenges.to_vec();
+    for c in res.iter_mut().rev() {
+        c.mul_assign(&power);
+        power.square();
+    }
+    res
+}
+
+// Commits to the quotient of `f(X) - f(z)` by `X - z`, given the coefficients
+// of `f` from the lowest degree.
+fn open<G: CurveAffine<Scalar = Fr>>(powers: &[G], coeffs: &[Fr], z: &Fr) -> G {
+    let mut quotient = vec![Fr::zero(); coeffs.len() - 1];
+    let mut acc = Fr::zero();
+    for i in (1..coeffs.len()).rev() {
+        acc.mul_assign(z);
+        acc.add_assign(&coeffs[i]);
+        quotient[i - 1] = acc;
+    }
+    msm(&powers[..quotient.len()], &quotient).into_affine()
+}
+
+// `e(g, v h^-f(z)) = e(opening, h^secret h^-z)` shows that `v = h^f(secret)`.
+fn check_opening_g2(
+    srs: &VerifierSrs,
+    commitment: &G2Affine,
+    h_secret: &G2Affine,
+    eval: &Fr,
+    z: &Fr,
+    opening: &G1Affine,
+) -> bool {
+    let mut lhs = commitment.into_projective();
+    lhs.sub_assign(&srs.h.mul(eval.into_repr()));
+    let mut rhs = h_secret.into_projective();
+    rhs.sub_assign(&srs.h.mul(z.into_repr()));
+
+    pairing(&[(&srs.g, &lhs.into_affine())]) == pairing(&[(opening, &rhs.into_affine())])
+}
+
+// `e(w g^-f(z), h) = e(g^secret g^-z, opening)` shows that `w = g^f(secret)`.
+fn check_opening_g1(
+    srs: &VerifierSrs,
+    commitment: &G1Affine,
+    g_secret: &G1Affine,
+    eval: &Fr,
+    z: &Fr,
+    opening: &G2Affine,
+) -> bool {
+    let mut lhs = commitment.into_projective();
+    lhs.sub_assign(&srs.g.mul(eval.into_repr()));
+    let mut rhs = g_secret.into_projective();
+    rhs.sub_assign(&srs.g.mul(z.into_repr()));
+
+    pairing(&[(&lhs.into_affine(), &srs.h)]) == pairing(&[(&rhs.into_affine(), opening)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::groth16::{create_random_proof, generate_random_parameters, prepare_verifying_key};
+    use crate::util_cs::test_fixtures::{test_rng, MulCircuit};
+
+    #[test]
+    fn test_folding_polynomial() {
+        let mut rng = test_rng();
+
+        let challenges = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let scalars = (0..8).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+        let mut folded = scalars.clone();
+        for x in &challenges {
+            let m = folded.len() / 2;
+            folded = fold_scalars(&folded[..m], &folded[m..], x);
+        }
+        let mut expected = Fr::zero();
+        for (s, coeff) in scalars.iter().zip(folding_coefficients(&challenges)) {
+            let mut term = *s;
+            term.mul_assign(&coeff);
+            expected.add_assign(&term);
+        }
+        assert_eq!(folded, vec![expected]);
+
+        let z = Fr::random(&mut rng);
+        let mut eval = Fr::zero();
+        for coeff in folding_coefficients(&challenges).iter().rev() {
+            eval.mul_assign(&z);
+            eval.add_assign(coeff);
+        }
+        assert_eq!(eval, fold_evaluation(&challenges, &z));
+    }
+
+    #[test]
+    fn test_aggregate_proofs() {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let srs = setup_fake_srs(&mut rng, 8);
+        let (prover_srs, verifier_srs) = srs.specialize(4).unwrap();
+        assert!(srs.specialize(3).is_err());
+        assert!(srs.specialize(8).is_err());
+
+        let mut proofs = vec![];
+        let mut inputs = vec![];
+        for _ in 0..4 {
+            let a = Fr::random(&mut rng);
+            let b = Fr::random(&mut rng);
+            let mut c = a;
+            c.mul_assign(&b);
+            let circuit = MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            proofs.push(create_random_proof(circuit, &params, &mut rng).unwrap());
+            inputs.push(vec![c]);
+        }
+
+        let aggregate = aggregate_proofs(&prover_srs, &proofs).unwrap();
+        assert_eq!(aggregate.gipa.comms_ab.len(), 2);
+        assert!(verify_aggregate_proof(&verifier_srs, &pvk, &inputs, &aggregate).unwrap());
+
+        let mut wrong_inputs = inputs.clone();
+        wrong_inputs.swap(0, 1);
+        assert!(!verify_aggregate_proof(&verifier_srs, &pvk, &wrong_inputs, &aggregate).unwrap());
+
+        let mut tampered = aggregate.clone();
+        tampered.agg_c = G1Affine::one();
+        assert!(!verify_aggregate_proof(&verifier_srs, &pvk, &inputs, &tampered).unwrap());
+
+        let mut tampered = aggregate.clone();
+        tampered.gipa.final_c = G1Affine::one();
+        assert!(!verify_aggregate_proof(&verifier_srs, &pvk, &inputs, &tampered).unwrap());
+
+        // An SRS from other secrets does not verify the openings.
+        let (_, other_srs) = setup_fake_srs(&mut rng, 8).specialize(4).unwrap();
+        assert!(!verify_aggregate_proof(&other_srs, &pvk, &inputs, &aggregate).unwrap());
+
+        assert!(aggregate_proofs(&prover_srs, &proofs[..2]).is_err());
+        assert!(verify_aggregate_proof(&verifier_srs, &pvk, &inputs[..2], &aggregate).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::check_deadline;
+use crate::gpu::GpuOptions;
+use crate::multicore::{Worker, Workers, THREAD_POOL};
+use crate::SynthesisError;
+
+/// Receives the progress of parameter generation and proving.
+///
+/// `on_phase` is called with the name of the running phase, e.g. `"synthesis"`,
+/// `"fft"` or `"multiexp"`, and how many of its `total` units of work are
+/// `done`. It may be called from several threads at once, so `done` is not
+/// guaranteed to be increasing across calls.
+pub trait ProgressSink: Send + Sync {
+    fn on_phase(&self, name: &str, done: usize, total: usize);
+}
+
+/// Cancels a running proving call, see [`Config::with_cancellation`].
+///
+/// Clones share the same flag, so the token can be handed to the proving
+/// thread and cancelled from another one. The prover checks it between its
+/// stages and while waiting for multiexps, and then fails with
+/// [`SynthesisError::Cancelled`], releasing the GPU.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Settings of a proving call, see [`create_proof_batch_with_config`].
+///
+/// [`create_proof_batch_with_config`]: super::create_proof_batch_with_config
+#[derive(Clone, Default)]
+pub struct Config {
+    pub gpu: GpuOptions,
+    /// Takes the GPU from provers without priority, see `gpu::PriorityLock`.
+    pub priority: bool,
+    /// Fails with [`SynthesisError::TimedOut`](crate::SynthesisError::TimedOut)
+    /// once this has passed.
+    pub deadline: Option<Instant>,
+    /// Runs the multiexps one after another instead of concurrently. With
+    /// `MappedParameters` only one query of the parameters is then read into
+    /// memory at a time, at the cost of a slower proof. The witnesses, the
+    /// quotient polynomials and the FFT domains are still held in memory in
+    /// full, so this does not put a ceiling on the memory of a proof.
+    pub sequential_multiexps: bool,
+    /// Proves the batch in chunks whose witness memory, see
+    /// [`estimate_witness_memory`](super::estimate_witness_memory), stays below
+    /// this many bytes. This does not bound the memory of the process, the
+    /// parameters, multiexp buckets and GPU buffers come on top.
+    pub max_witness_memory: Option<usize>,
+    /// Reports the synthesis, FFT and multiexp phases of the proof.
+    pub progress: Option<Arc<dyn ProgressSink>>,
+    pub cancellation: Option<CancellationToken>,
+    /// The pool the call runs on, instead of the global one sized by
+    /// `BELLMAN_NUM_CPUS`.
+    pub workers: Option<Workers>,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("gpu", &self.gpu)
+            .field("priority", &self.priority)
+            .field("deadline", &self.deadline)
+            .field("sequential_multiexps", &self.sequential_multiexps)
+            .field("max_witness_memory", &self.max_witness_memory)
+            .field("progress", &self.progress.is_some())
+            .field("cancellation", &self.cancellation)
+            .field("workers", &self.workers)
+            .finish()
+    }
+}
+
+impl Config {
+    pub fn with_gpu(mut self, gpu: GpuOptions) -> Self {
+        self.gpu = gpu;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: bool) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_sequential_multiexps(mut self, sequential_multiexps: bool) -> Self {
+        self.sequential_multiexps = sequential_multiexps;
+        self
+    }
+
+    /// A chunk holds at least one proof, a single proof is never split: the
+    /// FFTs and multiexps of a proof do not shrink to fit the budget. Fails
+    /// with
+    /// [`SynthesisError::MemoryBudgetExceeded`](crate::SynthesisError::MemoryBudgetExceeded)
+    /// if a single proof does not fit.
+    pub fn with_max_witness_memory(mut self, max_witness_memory: usize) -> Self {
+        self.max_witness_memory = Some(max_witness_memory);
+        self
+    }
+
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressSink>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    pub fn with_workers(mut self, workers: Workers) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Runs `f` on the pool of the call.
+    pub(crate) fn install<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match &self.workers {
+            Some(workers) => workers.install(f),
+            None => THREAD_POOL.install(f),
+        }
+    }
+
+    /// A `Worker` spawning on the pool of the call.
+    pub(crate) fn worker(&self) -> Worker {
+        match &self.workers {
+            Some(workers) => workers.worker(),
+            None => Worker::new(),
+        }
+    }
+
+    /// Fails if the call was cancelled or its deadline has passed.
+    pub(crate) fn check(&self) -> Result<(), SynthesisError> {
+        match &self.cancellation {
+            Some(cancellation) if cancellation.is_cancelled() => Err(SynthesisError::Cancelled),
+            _ => check_deadline(self.deadline),
+        }
+    }
+
+    pub(crate) fn report(&self, name: &str, done: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            progress.on_phase(name, done, total);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! A self-describing envelope holding a proof together with the verifying key
+//! and the public inputs it is checked against.
+//!
+//! Layout, all integers big-endian:
+//!
+//! ```text
+//! magic     4 bytes   "G16C"
+//! version   u16
+//! lengths   3 x u32   proof, verifying key and public inputs sections
+//! proof               as written by `Proof::write`
+//! vk                  as written by `VerifyingKey::write`
+//! inputs              as written by `fr_vector_to_byteblob`
+//! checksum  32 bytes  BLAKE2s-256 of everything above
+//! ```
+
+use std::io::{self, Read, Write};
+
+use blake2s_simd::State as Blake2s;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use super::{fr_vector_to_byteblob, groth16_primary_input_from_byteblob, Proof, VerifyingKey};
+use crate::bls::Engine;
+use crate::invalid_data;
+
+pub const MAGIC: [u8; 4] = *b"G16C";
+pub const VERSION: u16 = 1;
+
+const CHECKSUM_SIZE: usize = 32;
+
+#[derive(Clone, Debug)]
+pub struct Container<E: Engine> {
+    pub proof: Proof<E>,
+    pub vk: VerifyingKey<E>,
+    pub inputs: Vec<E::Fr>,
+}
+
+impl<E: Engine> PartialEq for Container<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.proof == other.proof && self.vk == other.vk && self.inputs == other.inputs
+    }
+}
+
+impl<E: Engine> Container<E> {
+    pub fn new(proof: Proof<E>, vk: VerifyingKey<E>, inputs: Vec<E::Fr>) -> Self {
+        Container { proof, vk, inputs }
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut proof = Vec::with_capacity(Proof::<E>::size());
+        self.proof.write(&mut proof)?;
+        let mut vk = Vec::new();
+        self.vk.write(&mut vk)?;
+        let inputs = fr_vector_to_byteblob::<E>(&self.inputs);
+
+        let mut bytes = Vec::with_capacity(18 + proof.len() + vk.len() + inputs.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.write_u16::<BigEndian>(VERSION)?;
+        for section in &[&proof, &vk, &inputs] {
+            bytes.write_u32::<BigEndian>(section.len() as u32)?;
+        }
+        for section in &[&proof, &vk, &inputs] {
+            bytes.extend_from_slice(section);
+        }
+
+        let mut h = Blake2s::new();
+        h.update(&bytes);
+
+        writer.write_all(&bytes)?;
+        writer.write_all(h.finalize().as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 18];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(invalid_data("not a groth16 container"));
+        }
+
+        let mut fields = &header[4..];
+        let version = fields.read_u16::<BigEndian>()?;
+        if version != VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported container version {}",
+                version
+            )));
+        }
+        let proof_len = fields.read_u32::<BigEndian>()? as u64;
+        let vk_len = fields.read_u32::<BigEndian>()? as u64;
+        let inputs_len = fields.read_u32::<BigEndian>()? as u64;
+
+        // Read through `take` so that bogus lengths fail with an early EOF
+        // instead of a huge allocation.
+        let body_len = proof_len + vk_len + inputs_len;
+        let mut body = Vec::new();
+        reader.by_ref().take(body_len).read_to_end(&mut body)?;
+        if body.len() as u64 != body_len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "container is truncated",
+            ));
+        }
+
+        let mut expected = [0u8; CHECKSUM_SIZE];
+        reader.read_exact(&mut expected)?;
+        let mut h = Blake2s::new();
+        h.update(&header);
+        h.update(&body);
+        if h.finalize().as_bytes() != &expected[..] {
+            return Err(invalid_data("container checksum mismatch"));
+        }
+
+        let (proof, rest) = body.split_at(proof_len as usize);
+        let (vk, inputs) = rest.split_at(vk_len as usize);
+
+        if proof.len() != Proof::<E>::size() {
+            return Err(invalid_data("proof section has a wrong length"));
+        }
+        let proof = Proof::read(proof)?;
+
+        let mut vk_reader = vk;
+        let vk = VerifyingKey::read(&mut vk_reader)?;
+        if !vk_reader.is_empty() {
+            return Err(invalid_data("verifying key section has trailing bytes"));
+        }
+
+        let inputs = groth16_primary_input_from_byteblob::<E>(inputs)
+            .map_err(|e| invalid_data(&e.to_string()))?;
+
+        Ok(Container { proof, vk, inputs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{create_random_proof, generate_random_parameters};
+    use crate::util_cs::test_fixtures::{test_rng, CubeCircuit};
+    use ff::Field;
+
+    fn container() -> Container<Bls12> {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(CubeCircuit { x: None }, &mut rng).unwrap();
+        let x = Fr::random(&mut rng);
+        let mut y = x;
+        y.square();
+        y.mul_assign(&x);
+        let proof = create_random_proof(CubeCircuit { x: Some(x) }, &params, &mut rng).unwrap();
+
+        Container::new(proof, params.vk, vec![y])
+    }
+
+    #[test]
+    fn test_container_roundtrip() {
+        let container = container();
+
+        let mut bytes = Vec::new();
+        container.write(&mut bytes).unwrap();
+        assert_eq!(&bytes[..4], b"G16C");
+
+        let read = Container::<Bls12>::read(&bytes[..]).unwrap();
+        assert!(read == container);
+
+        let pvk = crate::groth16::prepare_verifying_key(&read.vk);
+        assert!(crate::groth16::verify_proof(&pvk, &read.proof, &read.inputs).unwrap());
+    }
+
+    #[test]
+    fn test_container_rejects_corruption() {
+        let mut bytes = Vec::new();
+        container().write(&mut bytes).unwrap();
+
+        let mut flipped = bytes.clone();
+        flipped[100] ^= 1;
+        assert!(Container::<Bls12>::read(&flipped[..]).is_err());
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(Container::<Bls12>::read(&wrong_magic[..]).is_err());
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[5] = 2;
+        assert!(Container::<Bls12>::read(&wrong_version[..]).is_err());
+
+        assert!(Container::<Bls12>::read(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
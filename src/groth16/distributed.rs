@@ -0,0 +1,620 @@
+//! Proving a single circuit on several machines.
+//!
+//! Most of the memory of a Groth16 prover goes into the bases of its eight
+//! multiexps, which are linear in the bases: a multiexp over a range of the
+//! bases is an independent task whose result only has to be added to the
+//! results of the other ranges. `split` turns a synthesized circuit into such
+//! `Shard`s and a `Coordinator`. Every shard is serialized, sent to a worker
+//! that computes its `PartialResult` against its copy of the parameters, and
+//! the serialized result is handed back to the coordinator, which sums the
+//! partial results and assembles the proof.
+//!
+//! Only the multiexps are sharded. `split` computes the quotient polynomial
+//! `h` with its FFTs on the machine that synthesized the circuit, which needs
+//! the whole witness and time and memory linear in the number of
+//! constraints. The scalars take a fraction of the space of the bases, but a
+//! circuit whose FFTs do not fit on one machine cannot be proven this way.
+//!
+//! # Zero-knowledge
+//!
+//! A shard carries its range of the witness, or of the coefficients of `h`,
+//! in the clear, and its worker learns them. A proof is only zero-knowledge
+//! against parties that see no shard, so the workers have to be trusted with
+//! the witness as much as the coordinator.
+
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField, PrimeFieldRepr};
+use groupy::{CurveAffine, CurveProjective, EncodedPoint};
+use rand_core::RngCore;
+
+use super::{ParameterSource, Proof, ProvingAssignment, VerifyingKey};
+use crate::bls::Engine;
+use crate::domain::compute_quotient_polynomial;
+use crate::gpu::{LockedFFTKernel, LockedMultiexpKernel};
+use crate::multicore::Worker;
+use crate::multiexp::{multiexp, DensityTracker, FullDensity, SourceBuilder};
+use crate::{invalid_data, SynthesisError};
+
+/// One of the multiexps of a proof, named after the parameters it runs over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Query {
+    H,
+    L,
+    AInputs,
+    AAux,
+    BG1Inputs,
+    BG1Aux,
+    BG2Inputs,
+    BG2Aux,
+}
+
+const QUERIES: [Query; 8] = [
+    Query::H,
+    Query::L,
+    Query::AInputs,
+    Query::AAux,
+    Query::BG1Inputs,
+    Query::BG1Aux,
+    Query::BG2Inputs,
+    Query::BG2Aux,
+];
+
+impl Query {
+    fn index(self) -> usize {
+        QUERIES.iter().position(|q| *q == self).unwrap()
+    }
+
+    fn from_index(index: u8) -> io::Result<Self> {
+        QUERIES
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| invalid_data("invalid query"))
+    }
+
+    fn is_g2(self) -> bool {
+        self == Query::BG2Inputs || self == Query::BG2Aux
+    }
+}
+
+/// A multiexp over the bases `start..start + exps.len()` of a query.
+///
+/// The exponents are part of the witness: whoever computes the shard learns
+/// them, so shards must only be sent to trusted workers.
+#[derive(Clone, Debug)]
+pub struct Shard<E: Engine> {
+    pub query: Query,
+    pub start: usize,
+    /// The arguments the query is requested from the `ParameterSource` with.
+    args: (usize, usize),
+    exps: Vec<E::Fr>,
+}
+
+impl<E: Engine> Shard<E> {
+    pub fn len(&self) -> usize {
+        self.exps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exps.is_empty()
+    }
+
+    /// Computes the multiexp of the shard against `params`, on the GPU if
+    /// there is one.
+    pub fn compute<P: ParameterSource<E>>(
+        &self,
+        params: P,
+    ) -> Result<PartialResult<E>, SynthesisError> {
+        let (num_inputs, num_aux) = self.args;
+        let value = match self.query {
+            Query::H => PartialValue::G1(self.multiexp(params.get_h(num_inputs)?)?),
+            Query::L => PartialValue::G1(self.multiexp(params.get_l(num_inputs)?)?),
+            Query::AInputs => {
+                PartialValue::G1(self.multiexp(params.get_a(num_inputs, num_aux)?.0)?)
+            }
+            Query::AAux => PartialValue::G1(self.multiexp(params.get_a(num_inputs, num_aux)?.1)?),
+            Query::BG1Inputs => {
+                PartialValue::G1(self.multiexp(params.get_b_g1(num_inputs, num_aux)?.0)?)
+            }
+            Query::BG1Aux => {
+                PartialValue::G1(self.multiexp(params.get_b_g1(num_inputs, num_aux)?.1)?)
+            }
+            Query::BG2Inputs => {
+                PartialValue::G2(self.multiexp(params.get_b_g2(num_inputs, num_aux)?.0)?)
+            }
+            Query::BG2Aux => {
+                PartialValue::G2(self.multiexp(params.get_b_g2(num_inputs, num_aux)?.1)?)
+            }
+        };
+
+        Ok(PartialResult {
+            query: self.query,
+            start: self.start,
+            len: self.len(),
+            value,
+        })
+    }
+
+    fn multiexp<G, S>(&self, source: S) -> Result<G, SynthesisError>
+    where
+        G: CurveAffine<Engine = E>,
+        S: SourceBuilder<G>,
+    {
+        let (bases, skip) = source.get();
+        let exps = Arc::new(self.exps.iter().map(|e| e.into_repr()).collect::<Vec<_>>());
+
+        let mut log_d = 0;
+        while (1 << log_d) < exps.len() {
+            log_d += 1;
+        }
+        let mut kern = Some(LockedMultiexpKernel::<E>::new(log_d, false));
+
+        let worker = Worker::new();
+        let result = multiexp(
+            &worker,
+            (bases, skip + self.start),
+            FullDensity,
+            exps,
+            &mut kern,
+        )
+        .wait()?;
+        Ok(result.into_affine())
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u8(self.query.index() as u8)?;
+        writer.write_u32::<BigEndian>(self.start as u32)?;
+        writer.write_u32::<BigEndian>(self.args.0 as u32)?;
+        writer.write_u32::<BigEndian>(self.args.1 as u32)?;
+        writer.write_u32::<BigEndian>(self.exps.len() as u32)?;
+        for exp in &self.exps {
+            exp.into_repr().write_be(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let query = Query::from_index(reader.read_u8()?)?;
+        let start = reader.read_u32::<BigEndian>()? as usize;
+        let args = (
+            reader.read_u32::<BigEndian>()? as usize,
+            reader.read_u32::<BigEndian>()? as usize,
+        );
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let exps = (0..len)
+            .map(|_| {
+                let mut repr = <E::Fr as PrimeField>::Repr::default();
+                repr.read_be(&mut reader)?;
+                E::Fr::from_repr(repr).map_err(|e| invalid_data(&e.to_string()))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Shard {
+            query,
+            start,
+            args,
+            exps,
+        })
+    }
+}
+
+/// The multiexp of a shard.
+#[derive(Clone, Debug)]
+pub enum PartialValue<E: Engine> {
+    G1(E::G1Affine),
+    G2(E::G2Affine),
+}
+
+/// The result of `Shard::compute`, which the coordinator adds up.
+#[derive(Clone, Debug)]
+pub struct PartialResult<E: Engine> {
+    pub query: Query,
+    pub start: usize,
+    pub len: usize,
+    pub value: PartialValue<E>,
+}
+
+impl<E: Engine> PartialResult<E> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u8(self.query.index() as u8)?;
+        writer.write_u32::<BigEndian>(self.start as u32)?;
+        writer.write_u32::<BigEndian>(self.len as u32)?;
+        match self.value {
+            PartialValue::G1(ref p) => writer.write_all(p.into_uncompressed().as_ref()),
+            PartialValue::G2(ref p) => writer.write_all(p.into_uncompressed().as_ref()),
+        }
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let query = Query::from_index(reader.read_u8()?)?;
+        let start = reader.read_u32::<BigEndian>()? as usize;
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let value = if query.is_g2() {
+            let mut repr = <E::G2Affine as CurveAffine>::Uncompressed::empty();
+            reader.read_exact(repr.as_mut())?;
+            PartialValue::G2(
+                repr.into_affine()
+                    .map_err(|e| invalid_data(&e.to_string()))?,
+            )
+        } else {
+            let mut repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
+            reader.read_exact(repr.as_mut())?;
+            PartialValue::G1(
+                repr.into_affine()
+                    .map_err(|e| invalid_data(&e.to_string()))?,
+            )
+        };
+
+        Ok(PartialResult {
+            query,
+            start,
+            len,
+            value,
+        })
+    }
+}
+
+/// Collects the partial results of the shards of a proof.
+#[derive(Debug)]
+pub struct Coordinator<E: Engine> {
+    shard_len: usize,
+    lens: [usize; 8],
+    received: HashSet<(Query, usize)>,
+    g1: [E::G1; 6],
+    g2: [E::G2; 2],
+}
+
+impl<E: Engine> Coordinator<E> {
+    /// Adds the partial result of a shard. Fails if it does not belong to any
+    /// shard or if the result of that shard was already added.
+    pub fn add(&mut self, partial: &PartialResult<E>) -> Result<(), SynthesisError> {
+        let total = self.lens[partial.query.index()];
+        let expected_len = total.saturating_sub(partial.start).min(self.shard_len);
+        if partial.start % self.shard_len != 0 || partial.len != expected_len || expected_len == 0 {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "no shard of {:?} starts at {} with length {}",
+                partial.query, partial.start, partial.len
+            )));
+        }
+        if !self.received.insert((partial.query, partial.start)) {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "the shard of {:?} at {} was already added",
+                partial.query, partial.start
+            )));
+        }
+
+        match (partial.query.is_g2(), &partial.value) {
+            (false, PartialValue::G1(p)) => {
+                self.g1[partial.query.index()].add_assign_mixed(p);
+            }
+            (true, PartialValue::G2(p)) => {
+                self.g2[partial.query.index() - 6].add_assign_mixed(p);
+            }
+            _ => {
+                return Err(SynthesisError::IncompatibleLengthVector(format!(
+                    "the result of {:?} is in the wrong group",
+                    partial.query
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The number of shards whose results are still missing.
+    pub fn missing(&self) -> usize {
+        num_shards(&self.lens, self.shard_len) - self.received.len()
+    }
+
+    /// Assembles the proof from the partial results, blinded with random `r`
+    /// and `s`.
+    pub fn finish<R: RngCore>(
+        self,
+        vk: &VerifyingKey<E>,
+        rng: &mut R,
+    ) -> Result<Proof<E>, SynthesisError> {
+        let r = E::Fr::random(rng);
+        let s = E::Fr::random(rng);
+        self.finish_with(vk, r, s)
+    }
+
+    /// Like `finish`, with the given `r` and `s`.
+    pub fn finish_with(
+        self,
+        vk: &VerifyingKey<E>,
+        r: E::Fr,
+        s: E::Fr,
+    ) -> Result<Proof<E>, SynthesisError> {
+        if self.missing() != 0 {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "the results of {} shards are missing",
+                self.missing()
+            )));
+        }
+        if vk.delta_g1.is_zero() || vk.delta_g2.is_zero() {
+            // If this element is zero, someone is trying to perform a
+            // subversion-CRS attack.
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+        let [h, l, a_inputs, a_aux, b_g1_inputs, b_g1_aux] = self.g1;
+        let [b_g2_inputs, b_g2_aux] = self.g2;
+
+        let mut g_a = vk.delta_g1.mul(r);
+        g_a.add_assign_mixed(&vk.alpha_g1);
+        let mut g_b = vk.delta_g2.mul(s);
+        g_b.add_assign_mixed(&vk.beta_g2);
+        let mut g_c;
+        {
+            let mut rs = r;
+            rs.mul_assign(&s);
+
+            g_c = vk.delta_g1.mul(rs);
+            g_c.add_assign(&vk.alpha_g1.mul(s));
+            g_c.add_assign(&vk.beta_g1.mul(r));
+        }
+        let mut a_answer = a_inputs;
+        a_answer.add_assign(&a_aux);
+        g_a.add_assign(&a_answer);
+        a_answer.mul_assign(s);
+        g_c.add_assign(&a_answer);
+
+        let mut b1_answer = b_g1_inputs;
+        b1_answer.add_assign(&b_g1_aux);
+        let mut b2_answer = b_g2_inputs;
+        b2_answer.add_assign(&b_g2_aux);
+
+        g_b.add_assign(&b2_answer);
+        b1_answer.mul_assign(r);
+        g_c.add_assign(&b1_answer);
+        g_c.add_assign(&h);
+        g_c.add_assign(&l);
+
+        Ok(Proof {
+            a: g_a.into_affine(),
+            b: g_b.into_affine(),
+            c: g_c.into_affine(),
+        })
+    }
+}
+
+/// Splits the multiexps of a proof of `assignment` into shards of at most
+/// `shard_len` bases. The FFTs run here, on the GPU if there is one.
+pub fn split<E: Engine>(
+    assignment: &ProvingAssignment<E>,
+    shard_len: usize,
+) -> Result<(Coordinator<E>, Vec<Shard<E>>), SynthesisError> {
+    assert!(shard_len > 0, "shards must not be empty");
+
+    let mut log_d = 0;
+    while (1 << log_d) < assignment.a().len() {
+        log_d += 1;
+    }
+    let worker = Worker::new();
+    let mut fft_kern = Some(LockedFFTKernel::<E>::new(log_d, false));
+    let h = compute_quotient_polynomial(
+        &worker,
+        &mut fft_kern,
+        assignment.a().to_vec(),
+        assignment.b().to_vec(),
+        assignment.c().to_vec(),
+    )?
+    .into_iter()
+    .map(|s| s.0)
+    .collect::<Vec<_>>();
+    drop(fft_kern);
+
+    let inputs = assignment.input_assignment();
+    let aux = assignment.aux_assignment();
+    let a_aux_total = assignment.a_aux_density().get_total_density();
+    let b_input_total = assignment.b_input_density().get_total_density();
+    let b_aux_total = assignment.b_aux_density().get_total_density();
+
+    // The exponents of every query, filtered by its density so that they line
+    // up with the bases, and the arguments the query is requested with.
+    let queries = vec![
+        (Query::H, (h.len(), 0), h),
+        (Query::L, (aux.len(), 0), aux.to_vec()),
+        (Query::AInputs, (inputs.len(), a_aux_total), inputs.to_vec()),
+        (
+            Query::AAux,
+            (inputs.len(), a_aux_total),
+            dense(aux, assignment.a_aux_density()),
+        ),
+        (
+            Query::BG1Inputs,
+            (b_input_total, b_aux_total),
+            dense(inputs, assignment.b_input_density()),
+        ),
+        (
+            Query::BG1Aux,
+            (b_input_total, b_aux_total),
+            dense(aux, assignment.b_aux_density()),
+        ),
+        (
+            Query::BG2Inputs,
+            (b_input_total, b_aux_total),
+            dense(inputs, assignment.b_input_density()),
+        ),
+        (
+            Query::BG2Aux,
+            (b_input_total, b_aux_total),
+            dense(aux, assignment.b_aux_density()),
+        ),
+    ];
+
+    let mut lens = [0; 8];
+    let mut shards = vec![];
+    for (query, args, exps) in queries {
+        lens[query.index()] = exps.len();
+        for (i, chunk) in exps.chunks(shard_len).enumerate() {
+            shards.push(Shard {
+                query,
+                start: i * shard_len,
+                args,
+                exps: chunk.to_vec(),
+            });
+        }
+    }
+
+    let coordinator = Coordinator {
+        shard_len,
+        lens,
+        received: HashSet::new(),
+        g1: [E::G1::zero(); 6],
+        g2: [E::G2::zero(); 2],
+    };
+    debug_assert_eq!(coordinator.missing(), shards.len());
+
+    Ok((coordinator, shards))
+}
+
+fn dense<F: Copy>(assignment: &[F], density: &DensityTracker) -> Vec<F> {
+    assignment
+        .iter()
+        .zip(density.bv.iter())
+        .filter(|(_, dense)| *dense)
+        .map(|(s, _)| *s)
+        .collect()
+}
+
+fn num_shards(lens: &[usize], shard_len: usize) -> usize {
+    lens.iter()
+        .map(|len| (len + shard_len - 1) / shard_len)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{
+        create_proof_batch_from_assignments, generate_random_parameters, prepare_verifying_key,
+        synthesize_circuit, verify_proof, Config,
+    };
+    use crate::util_cs::test_fixtures::test_rng;
+    use crate::{Circuit, ConstraintSystem};
+
+    struct SumCircuit {
+        xs: Vec<Option<Fr>>,
+    }
+
+    // Proves knowledge of `xs` whose squares add up to the public input.
+    impl Circuit<Bls12> for SumCircuit {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let mut sum = Some(Fr::zero());
+            let mut lc = crate::LinearCombination::zero();
+            for (i, x) in self.xs.into_iter().enumerate() {
+                let x_var = cs.alloc(
+                    || format!("x{}", i),
+                    || x.ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                let square = x.map(|mut x| {
+                    x.square();
+                    x
+                });
+                let square_var = cs.alloc(
+                    || format!("x{}^2", i),
+                    || square.ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                cs.enforce(
+                    || format!("square {}", i),
+                    |lc| lc + x_var,
+                    |lc| lc + x_var,
+                    |lc| lc + square_var,
+                );
+                lc = lc + square_var;
+                sum = sum.and_then(|mut sum| {
+                    sum.add_assign(&square?);
+                    Some(sum)
+                });
+            }
+
+            let sum_var =
+                cs.alloc_input(|| "sum", || sum.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "sum", |_| lc, |lc| lc + CS::one(), |lc| lc + sum_var);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_distributed_proof() {
+        let rng = &mut test_rng();
+        let params =
+            generate_random_parameters::<Bls12, _, _>(SumCircuit { xs: vec![None; 10] }, rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let xs = (0..10).map(|_| Fr::random(rng)).collect::<Vec<_>>();
+        let mut sum = Fr::zero();
+        for x in &xs {
+            let mut square = *x;
+            square.square();
+            sum.add_assign(&square);
+        }
+        let circuit = SumCircuit {
+            xs: xs.iter().cloned().map(Some).collect(),
+        };
+        let assignment = synthesize_circuit(circuit).unwrap();
+
+        let (mut coordinator, shards) = split(&assignment, 3).unwrap();
+        assert!(shards.iter().all(|shard| shard.len() <= 3));
+        assert_eq!(coordinator.missing(), shards.len());
+
+        for shard in &shards {
+            // Every shard and its result go over the wire.
+            let mut bytes = vec![];
+            shard.write(&mut bytes).unwrap();
+            let received = Shard::<Bls12>::read(&bytes[..]).unwrap();
+            assert_eq!(received.query, shard.query);
+            assert_eq!(received.start, shard.start);
+            assert_eq!(received.exps, shard.exps);
+
+            let partial = received.compute(&params).unwrap();
+            let mut bytes = vec![];
+            partial.write(&mut bytes).unwrap();
+            let partial = PartialResult::read(&bytes[..]).unwrap();
+            coordinator.add(&partial).unwrap();
+            assert!(coordinator.add(&partial).is_err());
+        }
+        assert_eq!(coordinator.missing(), 0);
+
+        let r = Fr::random(rng);
+        let s = Fr::random(rng);
+        let proof = coordinator.finish_with(&params.vk, r, s).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[sum]).unwrap());
+
+        // The shards add up to the proof of the local prover.
+        let local = create_proof_batch_from_assignments(
+            vec![assignment],
+            &params,
+            vec![r],
+            vec![s],
+            &Config::default(),
+        )
+        .unwrap();
+        assert_eq!(local[0], proof);
+
+        // A coordinator does not finish with missing shards.
+        let (mut coordinator, shards) = split(
+            &synthesize_circuit(SumCircuit {
+                xs: xs.into_iter().map(Some).collect(),
+            })
+            .unwrap(),
+            4,
+        )
+        .unwrap();
+        for shard in &shards[1..] {
+            coordinator.add(&shard.compute(&params).unwrap()).unwrap();
+        }
+        assert_eq!(coordinator.missing(), 1);
+        assert!(coordinator.finish(&params.vk, rng).is_err());
+    }
+}
@@ -1,9 +1,29 @@
-use super::{create_proof_batch_priority, create_random_proof_batch_priority};
+use super::{
+    create_proof_batch_mixed_priority, create_proof_batch_priority,
+    create_random_proof_batch_mixed_priority, create_random_proof_batch_priority,
+    create_random_proof_batch_priority_with_deadline,
+};
 use super::{ParameterSource, Proof};
 use crate::bls::Engine;
+use crate::multicore::Task;
 use crate::{Circuit, SynthesisError};
+use ff::Field;
+use rand::rngs::OsRng;
 use rand_core::RngCore;
+use std::sync::Arc;
+use std::time::Instant;
 
+/// Creates a proof with the blinding factors `r` and `s`, so that the same
+/// circuit, assignment, parameters and factors always give the same proof.
+///
+/// `r` and `s` are what makes the proof zero-knowledge: they have to be
+/// uniformly random, secret, and never used for a second proof. Anyone who
+/// learns them can remove the blinding and test guesses of the witness
+/// against the proof, and two proofs with the same factors reveal the
+/// difference of their witnesses. Derive them from a secret seed with a
+/// cryptographic hash or PRF, and zeroize the seed and the factors once the
+/// proof is created: this function does not keep them, but neither does it
+/// wipe the copies it was given.
 pub fn create_proof<E, C, P: ParameterSource<E>>(
     circuit: C,
     params: P,
@@ -34,6 +54,20 @@ where
     Ok(proofs.into_iter().next().unwrap())
 }
 
+/// Creates a proof using the operating system's secure random number generator.
+pub fn create_random_proof_os<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+{
+    create_random_proof::<E, C, _, P>(circuit, params, &mut OsRng)
+}
+
+/// Like [`create_proof`] for a batch, with the blinding factors `r[i]` and
+/// `s[i]` for `circuits[i]`. The same requirements on the factors apply.
 pub fn create_proof_batch<E, C, P: ParameterSource<E>>(
     circuits: Vec<C>,
     params: P,
@@ -47,6 +81,11 @@ where
     create_proof_batch_priority::<E, C, P>(circuits, params, r, s, false)
 }
 
+/// Creates a proof for each of `circuits` with random blinding factors.
+///
+/// The circuits are synthesized in parallel on the prover's thread pool,
+/// whose size is set by `BELLMAN_NUM_CPUS`, before the FFTs and multiexps of
+/// the batch start.
 pub fn create_random_proof_batch<E, C, R, P: ParameterSource<E>>(
     circuits: Vec<C>,
     params: P,
@@ -60,6 +99,28 @@ where
     create_random_proof_batch_priority::<E, C, R, P>(circuits, params, rng, false)
 }
 
+/// Like [`create_random_proof_batch`], as a future that can be awaited on an
+/// async executor without blocking it. The blinding factors are drawn from
+/// `rng` before this returns, and the parameters are shared with the thread
+/// driving the proofs.
+pub fn create_random_proof_batch_async<E, C, R, S>(
+    circuits: Vec<C>,
+    params: Arc<S>,
+    rng: &mut R,
+) -> Task<Result<Vec<Proof<E>>, SynthesisError>>
+where
+    E: Engine,
+    C: Circuit<E> + Send + 'static,
+    R: RngCore,
+    S: Send + Sync + 'static,
+    for<'a> &'a S: ParameterSource<E>,
+{
+    let r_s = (0..circuits.len()).map(|_| E::Fr::random(rng)).collect();
+    let s_s = (0..circuits.len()).map(|_| E::Fr::random(rng)).collect();
+
+    Task::spawn(move || create_proof_batch_priority(circuits, &*params, r_s, s_s, false))
+}
+
 pub fn create_proof_in_priority<E, C, P: ParameterSource<E>>(
     circuit: C,
     params: P,
@@ -115,3 +176,88 @@ where
 {
     create_random_proof_batch_priority::<E, C, R, P>(circuits, params, rng, true)
 }
+
+pub fn create_random_proof_with_deadline<E, C, R, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    rng: &mut R,
+    deadline: Instant,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+    R: RngCore,
+{
+    let proofs = create_random_proof_batch_priority_with_deadline::<E, C, R, P>(
+        vec![circuit],
+        params,
+        rng,
+        false,
+        deadline,
+    )?;
+    Ok(proofs.into_iter().next().unwrap())
+}
+
+pub fn create_random_proof_batch_with_deadline<E, C, R, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: P,
+    rng: &mut R,
+    deadline: Instant,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+    R: RngCore,
+{
+    create_random_proof_batch_priority_with_deadline::<E, C, R, P>(
+        circuits, params, rng, false, deadline,
+    )
+}
+
+pub fn create_proof_batch_mixed<E, C, P: ParameterSource<E>>(
+    jobs: Vec<(C, P)>,
+    r: Vec<E::Fr>,
+    s: Vec<E::Fr>,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+{
+    create_proof_batch_mixed_priority::<E, C, P>(jobs, r, s, false)
+}
+
+pub fn create_random_proof_batch_mixed<E, C, R, P: ParameterSource<E>>(
+    jobs: Vec<(C, P)>,
+    rng: &mut R,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+    R: RngCore,
+{
+    create_random_proof_batch_mixed_priority::<E, C, R, P>(jobs, rng, false)
+}
+
+pub fn create_proof_batch_mixed_in_priority<E, C, P: ParameterSource<E>>(
+    jobs: Vec<(C, P)>,
+    r: Vec<E::Fr>,
+    s: Vec<E::Fr>,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+{
+    create_proof_batch_mixed_priority::<E, C, P>(jobs, r, s, true)
+}
+
+pub fn create_random_proof_batch_mixed_in_priority<E, C, R, P: ParameterSource<E>>(
+    jobs: Vec<(C, P)>,
+    rng: &mut R,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+    R: RngCore,
+{
+    create_random_proof_batch_mixed_priority::<E, C, R, P>(jobs, rng, true)
+}
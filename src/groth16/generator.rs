@@ -1,18 +1,22 @@
 use rand_core::RngCore;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use crate::bls::Engine;
 use ff::{Field, PrimeField};
 use groupy::{CurveAffine, CurveProjective, Wnaf};
+use rayon::prelude::*;
 
-use super::{Parameters, VerifyingKey};
+use super::{Parameters, ProgressSink, TauPowers, VerifyingKey};
 
 use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 
-use crate::domain::{EvaluationDomain, Scalar};
+use crate::domain::{EvaluationDomain, Point, Scalar};
+use crate::gpu::LockedFFTKernel;
 
 use crate::multicore::Worker;
+use crate::transcript::{Blake2bTranscript, Transcript, TranscriptRng};
 
 /// Generates a random common reference string for
 /// a circuit.
@@ -25,15 +29,103 @@ where
     C: Circuit<E>,
     R: RngCore,
 {
-    let g1 = E::G1::random(rng);
-    let g2 = E::G2::random(rng);
-    let alpha = E::Fr::random(rng);
-    let beta = E::Fr::random(rng);
-    let gamma = E::Fr::random(rng);
-    let delta = E::Fr::random(rng);
-    let tau = E::Fr::random(rng);
-
-    generate_parameters::<E, C>(circuit, g1, g2, alpha, beta, gamma, delta, tau)
+    let trapdoor = Trapdoor::random(rng);
+
+    generate_parameters_with_progress::<E, C>(circuit, &trapdoor, &NoProgress)
+}
+
+/// Generates a random common reference string for a circuit, reporting the
+/// synthesis, powers of tau, FFT and query evaluation phases to `progress`.
+pub fn generate_random_parameters_with_progress<E, C, R>(
+    circuit: C,
+    rng: &mut R,
+    progress: &dyn ProgressSink,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+    R: RngCore,
+{
+    let trapdoor = Trapdoor::random(rng);
+
+    generate_parameters_with_progress::<E, C>(circuit, &trapdoor, progress)
+}
+
+/// The toxic waste of a parameter generation.
+///
+/// Anyone holding the trapdoor can forge proofs for arbitrary statements, see
+/// `simulate_proof` in the `test-utils` feature. It must never be kept around
+/// for parameters that are used outside of tests.
+#[derive(Clone)]
+pub struct Trapdoor<E: Engine> {
+    pub g1: E::G1Affine,
+    pub g2: E::G2Affine,
+    pub alpha: E::Fr,
+    pub beta: E::Fr,
+    pub gamma: E::Fr,
+    pub delta: E::Fr,
+    pub tau: E::Fr,
+}
+
+impl<E: Engine> Trapdoor<E> {
+    fn random<R: RngCore>(rng: &mut R) -> Self {
+        Trapdoor {
+            g1: E::G1::random(rng).into_affine(),
+            g2: E::G2::random(rng).into_affine(),
+            alpha: E::Fr::random(rng),
+            beta: E::Fr::random(rng),
+            gamma: E::Fr::random(rng),
+            delta: E::Fr::random(rng),
+            tau: E::Fr::random(rng),
+        }
+    }
+}
+
+/// A progress sink that ignores the progress.
+struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn on_phase(&self, _: &str, _: usize, _: usize) {}
+}
+
+/// Generates a random common reference string for a circuit, and returns it
+/// together with the trapdoor it was generated from. Intended for testing
+/// only, and therefore behind the `test-utils` feature.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn generate_random_parameters_with_trapdoor<E, C, R>(
+    circuit: C,
+    rng: &mut R,
+) -> Result<(Parameters<E>, Trapdoor<E>), SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+    R: RngCore,
+{
+    let trapdoor = Trapdoor::random(rng);
+    let params = generate_parameters_with_progress::<E, C>(circuit, &trapdoor, &NoProgress)?;
+
+    Ok((params, trapdoor))
+}
+
+/// Generates parameters for a circuit from `seed`, the same ones for the
+/// same seed and circuit on every machine and run. Intended for testing only:
+/// the trapdoor is derived from the seed, so anyone who knows it can forge
+/// proofs.
+///
+/// This lets tests keep parameters as fixtures, or regenerate the exact ones
+/// a failing run used.
+pub fn generate_parameters_seeded<E, C>(
+    circuit: C,
+    seed: u64,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+{
+    let mut transcript = Blake2bTranscript::new(b"crusty3_zk seeded parameters");
+    transcript.append_message(b"seed", &seed.to_le_bytes());
+
+    generate_random_parameters(circuit, &mut TranscriptRng(&mut transcript))
 }
 
 /// This is our assembly structure that we'll use to synthesize the
@@ -129,7 +221,7 @@ impl<E: Engine> ConstraintSystem<E> for KeypairAssembly<E> {
             aux: &mut [Vec<(E::Fr, usize)>],
             this_constraint: usize,
         ) {
-            for (index, coeff) in l.0 {
+            for (index, coeff) in l.simplify().0 {
                 match index {
                     Variable(Index::Input(id)) => inputs[id].push((coeff, this_constraint)),
                     Variable(Index::Aux(id)) => aux[id].push((coeff, this_constraint)),
@@ -191,13 +283,53 @@ where
     E: Engine,
     C: Circuit<E>,
 {
+    let trapdoor = Trapdoor {
+        g1: g1.into_affine(),
+        g2: g2.into_affine(),
+        alpha,
+        beta,
+        gamma,
+        delta,
+        tau,
+    };
+
+    generate_parameters_with_progress::<E, C>(circuit, &trapdoor, &NoProgress)
+}
+
+/// Create parameters for a circuit from the toxic waste in `trapdoor`,
+/// reporting the progress to `progress`.
+pub fn generate_parameters_with_progress<E, C>(
+    circuit: C,
+    trapdoor: &Trapdoor<E>,
+    progress: &dyn ProgressSink,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+{
+    let Trapdoor {
+        g1,
+        g2,
+        alpha,
+        beta,
+        gamma,
+        delta,
+        tau,
+    } = trapdoor.clone();
+    let g1 = g1.into_projective();
+    let g2 = g2.into_projective();
+
+    let report = |name: &str, done: usize, total: usize| progress.on_phase(name, done, total);
+
     let mut assembly = KeypairAssembly::new();
 
     // Allocate the "one" input variable
     assembly.alloc_input(|| "", || Ok(E::Fr::one()))?;
 
     // Synthesize the circuit.
+    report("synthesis", 0, 1);
     circuit.synthesize(&mut assembly)?;
+    report("synthesis", 1, 1);
 
     // Input constraints to ensure full density of IC query
     // x * 0 = 0
@@ -239,6 +371,7 @@ where
         // Compute powers of tau
         {
             let powers_of_tau = powers_of_tau.as_mut();
+            report("powers of tau", 0, 1);
             worker.scope(powers_of_tau.len(), |scope, chunk| {
                 for (i, powers_of_tau) in powers_of_tau.chunks_mut(chunk).enumerate() {
                     scope.spawn(move |_scope| {
@@ -251,6 +384,7 @@ where
                     });
                 }
             });
+            report("powers of tau", 1, 1);
         }
 
         // coeff = t(x) / delta
@@ -258,12 +392,17 @@ where
         coeff.mul_assign(&delta_inverse);
 
         // Compute the H query with multiple threads
+        let h_len = h.len();
+        let h_done = AtomicUsize::new(0);
+        report("h", 0, h_len);
         worker.scope(h.len(), |scope, chunk| {
             for (h, p) in h
                 .chunks_mut(chunk)
                 .zip(powers_of_tau.as_ref().chunks(chunk))
             {
                 let mut g1_wnaf = g1_wnaf.shared();
+                let h_done = &h_done;
+                let report = &report;
 
                 scope.spawn(move |_scope| {
                     // Set values of the H query to g1^{(tau^i * t(tau)) / delta}
@@ -278,13 +417,22 @@ where
 
                     // Batch normalize
                     E::G1::batch_normalization(h);
+
+                    let done = h_done.fetch_add(h.len(), Ordering::SeqCst) + h.len();
+                    report("h", done, h_len);
                 });
             }
         });
     }
 
-    // Use inverse FFT to convert powers of tau to Lagrange coefficients
-    powers_of_tau.ifft(&worker, &mut None)?;
+    // Use inverse FFT to convert powers of tau to Lagrange coefficients, on
+    // the GPU if there is one.
+    report("fft", 0, 1);
+    let log_d = powers_of_tau.as_ref().len().trailing_zeros() as usize;
+    let mut fft_kern = Some(LockedFFTKernel::<E>::new(log_d, false));
+    powers_of_tau.ifft(&worker, &mut fft_kern)?;
+    drop(fft_kern);
+    report("fft", 1, 1);
     let powers_of_tau = powers_of_tau.into_coeffs();
 
     let mut a = vec![E::G1::zero(); assembly.num_inputs + assembly.num_aux];
@@ -321,6 +469,9 @@ where
 
         // Worker
         worker: &Worker,
+
+        // Called with the number of elements of every evaluated chunk
+        progress: &(dyn Fn(usize) + Sync),
     ) {
         // Sanity check
         assert_eq!(a.len(), at.len());
@@ -402,11 +553,21 @@ where
                     E::G1::batch_normalization(b_g1);
                     E::G2::batch_normalization(b_g2);
                     E::G1::batch_normalization(ext);
+
+                    progress(a.len());
                 });
             }
         });
     }
 
+    let num_variables = assembly.num_inputs + assembly.num_aux;
+    let evaluated = AtomicUsize::new(0);
+    let eval_progress = |len: usize| {
+        let done = evaluated.fetch_add(len, Ordering::SeqCst) + len;
+        report("queries", done, num_variables);
+    };
+    report("queries", 0, num_variables);
+
     // Evaluate for inputs.
     eval(
         &g1_wnaf,
@@ -423,6 +584,7 @@ where
         &alpha,
         &beta,
         &worker,
+        &eval_progress,
     );
 
     // Evaluate for auxiliary variables.
@@ -441,19 +603,20 @@ where
         &alpha,
         &beta,
         &worker,
+        &eval_progress,
     );
 
     // Don't allow any elements be unconstrained, so that
     // the L query is always fully dense.
-    for e in l.iter() {
-        if e.is_zero() {
-            return Err(SynthesisError::UnconstrainedVariable);
-        }
+    if l.par_iter().any(|e| e.is_zero()) {
+        return Err(SynthesisError::UnconstrainedVariable);
     }
 
     let g1 = g1.into_affine();
     let g2 = g2.into_affine();
 
+    // The queries are normalized already, so this only converts them, but
+    // there are millions of points in large circuits.
     let vk = VerifyingKey::<E> {
         alpha_g1: g1.mul(alpha).into_affine(),
         beta_g1: g1.mul(beta).into_affine(),
@@ -461,32 +624,208 @@ where
         gamma_g2: g2.mul(gamma).into_affine(),
         delta_g1: g1.mul(delta).into_affine(),
         delta_g2: g2.mul(delta).into_affine(),
-        ic: ic.into_iter().map(|e| e.into_affine()).collect(),
+        ic: ic.into_par_iter().map(|e| e.into_affine()).collect(),
     };
 
     Ok(Parameters {
         vk,
-        h: Arc::new(h.into_iter().map(|e| e.into_affine()).collect()),
-        l: Arc::new(l.into_iter().map(|e| e.into_affine()).collect()),
+        h: Arc::new(h.into_par_iter().map(|e| e.into_affine()).collect()),
+        l: Arc::new(l.into_par_iter().map(|e| e.into_affine()).collect()),
 
         // Filter points at infinity away from A/B queries
         a: Arc::new(
-            a.into_iter()
+            a.into_par_iter()
                 .filter(|e| !e.is_zero())
                 .map(|e| e.into_affine())
                 .collect(),
         ),
         b_g1: Arc::new(
-            b_g1.into_iter()
+            b_g1.into_par_iter()
                 .filter(|e| !e.is_zero())
                 .map(|e| e.into_affine())
                 .collect(),
         ),
         b_g2: Arc::new(
-            b_g2.into_iter()
+            b_g2.into_par_iter()
                 .filter(|e| !e.is_zero())
                 .map(|e| e.into_affine())
                 .collect(),
         ),
     })
 }
+
+/// Creates parameters for a circuit from the powers of tau of a phase 1
+/// ceremony, with `gamma` and `delta` set to one. The parameters are only
+/// secure after phase 2 contributions, see `phase2::MPCParameters`.
+pub fn generate_parameters_from_tau<E, C>(
+    circuit: C,
+    powers: &TauPowers<E>,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+{
+    let mut assembly = KeypairAssembly::new();
+    assembly.alloc_input(|| "", || Ok(E::Fr::one()))?;
+    circuit.synthesize(&mut assembly)?;
+    for i in 0..assembly.num_inputs {
+        assembly.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
+    }
+
+    let n = assembly.num_constraints.next_power_of_two();
+    if powers.size() < n {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+
+    // t(tau) = tau^n - 1, so the H query is tau^(i + n) - tau^i.
+    let mut h = (0..n - 1)
+        .into_par_iter()
+        .map(|i| {
+            let mut h = powers.tau_g1[i + n].into_projective();
+            h.sub_assign(&powers.tau_g1[i].into_projective());
+            h
+        })
+        .collect::<Vec<_>>();
+    E::G1::batch_normalization(&mut h);
+
+    // Use inverse FFTs in the exponent to get the Lagrange coefficients.
+    fn lagrange<E: Engine, G: CurveProjective<Engine = E>>(
+        powers: &[G::Affine],
+        worker: &Worker,
+    ) -> Result<Vec<G::Affine>, SynthesisError> {
+        let points = powers.iter().map(|p| Point(p.into_projective())).collect();
+        let mut domain = EvaluationDomain::<E, Point<G>>::from_coeffs(points)?;
+        domain.ifft(worker, &mut None)?;
+
+        let mut points = domain
+            .into_coeffs()
+            .into_iter()
+            .map(|p| p.0)
+            .collect::<Vec<_>>();
+        G::batch_normalization(&mut points);
+        Ok(points.into_iter().map(|p| p.into_affine()).collect())
+    }
+
+    let worker = Worker::new();
+    let lagrange_g1 = lagrange::<E, E::G1>(&powers.tau_g1[..n], &worker)?;
+    let lagrange_g2 = lagrange::<E, E::G2>(&powers.tau_g2[..n], &worker)?;
+    let alpha_lagrange = lagrange::<E, E::G1>(&powers.alpha_tau_g1[..n], &worker)?;
+    let beta_lagrange = lagrange::<E, E::G1>(&powers.beta_tau_g1[..n], &worker)?;
+
+    fn eval<G: CurveAffine>(bases: &[G], p: &[(G::Scalar, usize)]) -> G::Projective {
+        let mut acc = G::Projective::zero();
+        for &(coeff, index) in p {
+            acc.add_assign(&bases[index].mul(coeff.into_repr()));
+        }
+        acc
+    }
+
+    // The A, B and IC or L queries of the variables.
+    #[allow(clippy::type_complexity)]
+    let queries = |at: &[Vec<(E::Fr, usize)>],
+                   bt: &[Vec<(E::Fr, usize)>],
+                   ct: &[Vec<(E::Fr, usize)>]|
+     -> Vec<(E::G1, E::G1, E::G2, E::G1)> {
+        at.par_iter()
+            .zip(bt.par_iter())
+            .zip(ct.par_iter())
+            .map(|((at, bt), ct)| {
+                let mut ext = eval(&beta_lagrange, at);
+                ext.add_assign(&eval(&alpha_lagrange, bt));
+                ext.add_assign(&eval(&lagrange_g1, ct));
+                (
+                    eval(&lagrange_g1, at),
+                    eval(&lagrange_g1, bt),
+                    eval(&lagrange_g2, bt),
+                    ext,
+                )
+            })
+            .collect()
+    };
+    let inputs = queries(
+        &assembly.at_inputs,
+        &assembly.bt_inputs,
+        &assembly.ct_inputs,
+    );
+    let aux = queries(&assembly.at_aux, &assembly.bt_aux, &assembly.ct_aux);
+
+    let ic = inputs.iter().map(|q| q.3.into_affine()).collect();
+    let l = aux
+        .iter()
+        .map(|q| q.3.into_affine())
+        .collect::<Vec<E::G1Affine>>();
+    // Don't allow any elements be unconstrained, so that
+    // the L query is always fully dense.
+    if l.iter().any(|e| e.is_zero()) {
+        return Err(SynthesisError::UnconstrainedVariable);
+    }
+
+    let variables = inputs.iter().chain(&aux);
+    let a = variables
+        .clone()
+        .filter(|q| !q.0.is_zero())
+        .map(|q| q.0.into_affine())
+        .collect();
+    let b_g1 = variables
+        .clone()
+        .filter(|q| !q.1.is_zero())
+        .map(|q| q.1.into_affine())
+        .collect();
+    let b_g2 = variables
+        .filter(|q| !q.2.is_zero())
+        .map(|q| q.2.into_affine())
+        .collect();
+
+    let vk = VerifyingKey::<E> {
+        alpha_g1: powers.alpha_tau_g1[0],
+        beta_g1: powers.beta_tau_g1[0],
+        beta_g2: powers.beta_g2,
+        gamma_g2: E::G2Affine::one(),
+        delta_g1: E::G1Affine::one(),
+        delta_g2: E::G2Affine::one(),
+        ic,
+    };
+
+    Ok(Parameters {
+        vk,
+        h: Arc::new(h.into_iter().map(|e| e.into_affine()).collect()),
+        l: Arc::new(l),
+        a: Arc::new(a),
+        b_g1: Arc::new(b_g1),
+        b_g2: Arc::new(b_g2),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{create_random_proof, prepare_verifying_key, verify_proof};
+    use crate::util_cs::test_fixtures::{test_rng, MulCircuit};
+
+    #[test]
+    fn test_seeded_parameters() {
+        let circuit = || MulCircuit { a: None, b: None };
+        let params = generate_parameters_seeded::<Bls12, _>(circuit(), 42).unwrap();
+        assert!(params == generate_parameters_seeded(circuit(), 42).unwrap());
+        assert!(params != generate_parameters_seeded(circuit(), 43).unwrap());
+
+        let mut rng = test_rng();
+        let (a, b) = (Fr::random(&mut rng), Fr::random(&mut rng));
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let proof = create_random_proof(
+            MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
+    }
+}
@@ -0,0 +1,144 @@
+//! `0x`-prefixed hex strings of the compressed byteblob encodings.
+
+use std::io;
+
+use super::{
+    fr_vector_to_byteblob, groth16_primary_input_from_byteblob, groth16_proof_from_byteblob,
+    groth16_proof_to_byteblob, groth16_verifying_key_from_byteblob,
+    groth16_verifying_key_to_byteblob, Proof, VerifyingKey,
+};
+use crate::bls::Engine;
+use crate::invalid_data;
+
+/// Public inputs of a proof, encoded as by `fr_vector_to_byteblob`.
+#[derive(Clone, Debug)]
+pub struct PublicInputs<E: Engine>(pub Vec<E::Fr>);
+
+impl<E: Engine> PartialEq for PublicInputs<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Proof<E> {
+    pub fn to_hex(&self) -> String {
+        encode(&groth16_proof_to_byteblob(self))
+    }
+
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        let bytes = decode(hex)?;
+        if bytes.len() != Self::size() {
+            return Err(invalid_data("proof has a wrong length"));
+        }
+        groth16_proof_from_byteblob(&bytes).map_err(|e| invalid_data(&e.to_string()))
+    }
+}
+
+impl<E: Engine> VerifyingKey<E> {
+    pub fn to_hex(&self) -> String {
+        encode(&groth16_verifying_key_to_byteblob(self))
+    }
+
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        groth16_verifying_key_from_byteblob(&decode(hex)?).map_err(|e| invalid_data(&e.to_string()))
+    }
+}
+
+impl<E: Engine> PublicInputs<E> {
+    pub fn to_hex(&self) -> String {
+        encode(&fr_vector_to_byteblob::<E>(&self.0))
+    }
+
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        groth16_primary_input_from_byteblob::<E>(&decode(hex)?)
+            .map(PublicInputs)
+            .map_err(|e| invalid_data(&e.to_string()))
+    }
+}
+
+impl<E: Engine> From<Vec<E::Fr>> for PublicInputs<E> {
+    fn from(inputs: Vec<E::Fr>) -> Self {
+        PublicInputs(inputs)
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 + 2 * bytes.len());
+    hex.push_str("0x");
+    for b in bytes {
+        hex.push_str(&format!("{:02x}", b));
+    }
+    hex
+}
+
+/// Decodes a hex string, with or without the `0x` prefix.
+fn decode(hex: &str) -> io::Result<Vec<u8>> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.is_ascii() || hex.len() % 2 != 0 {
+        return Err(invalid_data("invalid hex string"));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid_data("invalid hex string"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ff::{Field, PrimeField};
+    use groupy::CurveAffine;
+
+    use crate::bls::{Bls12, Fr, G1Affine, G2Affine};
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let mut rng = test_rng();
+
+        let proof = Proof::<Bls12> {
+            a: G1Affine::one(),
+            b: G2Affine::one(),
+            c: G1Affine::one(),
+        };
+        let hex = proof.to_hex();
+        assert!(hex.starts_with("0x97f1d3a7"));
+        assert_eq!(hex.len(), 2 + 2 * Proof::<Bls12>::size());
+        assert!(Proof::<Bls12>::from_hex(&hex).unwrap() == proof);
+        assert!(Proof::<Bls12>::from_hex(&hex[2..]).unwrap() == proof);
+        assert!(Proof::<Bls12>::from_hex(&hex[..hex.len() - 2]).is_err());
+
+        let vk = VerifyingKey::<Bls12> {
+            alpha_g1: G1Affine::one(),
+            beta_g1: G1Affine::one(),
+            beta_g2: G2Affine::one(),
+            gamma_g2: G2Affine::one(),
+            delta_g1: G1Affine::one(),
+            delta_g2: G2Affine::one(),
+            ic: vec![G1Affine::one(); 2],
+        };
+        assert!(VerifyingKey::<Bls12>::from_hex(&vk.to_hex()).unwrap() == vk);
+
+        let inputs = PublicInputs::<Bls12>((0..3).map(|_| Fr::random(&mut rng)).collect());
+        assert_eq!(PublicInputs::from_hex(&inputs.to_hex()).unwrap(), inputs);
+
+        let five = PublicInputs::<Bls12>::from(vec![Fr::from_str("5").unwrap()]);
+        assert_eq!(
+            five.to_hex(),
+            "0x010000000500000000000000000000000000000000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(decode("0x0").is_err());
+        assert!(decode("0xzz").is_err());
+        assert!(decode("0xé0").is_err());
+        assert_eq!(decode("0x").unwrap(), vec![]);
+        assert_eq!(decode("00ff").unwrap(), vec![0, 0xff]);
+    }
+}
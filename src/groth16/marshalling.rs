@@ -13,41 +13,166 @@ use super::{multiscalar, PreparedVerifyingKey, Proof, VerifyingKey, GROTH16Verif
 use crate::multicore::VERIFIER_POOL as POOL;
 use crate::SynthesisError;
 
+/// Byte order within a limb, or order of the limbs within a number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// Layout of multi-limb numbers in a byteblob.
+///
+/// The plain codecs write field elements and lengths little-endian and points
+/// in their standard compressed encoding, whose coordinates are big-endian.
+/// The `_with_encoding` codecs use the given encoding for all of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encoding {
+    pub byte_order: Endianness,
+    pub limb_order: Endianness,
+}
+
+impl Encoding {
+    pub const LITTLE_ENDIAN: Encoding = Encoding {
+        byte_order: Endianness::Little,
+        limb_order: Endianness::Little,
+    };
+
+    pub const BIG_ENDIAN: Encoding = Encoding {
+        byte_order: Endianness::Big,
+        limb_order: Endianness::Big,
+    };
+
+    fn read_u32(&self, bytes: &[u8]) -> u32 {
+        match self.byte_order {
+            Endianness::Little => LittleEndian::read_u32(bytes),
+            Endianness::Big => BigEndian::read_u32(bytes),
+        }
+    }
+
+    fn write_u32(&self, bytes: &mut [u8], value: u32) {
+        match self.byte_order {
+            Endianness::Little => LittleEndian::write_u32(bytes, value),
+            Endianness::Big => BigEndian::write_u32(bytes, value),
+        }
+    }
+
+    fn limb_index(&self, i: usize, limbs: usize) -> usize {
+        match self.limb_order {
+            Endianness::Little => i,
+            Endianness::Big => limbs - 1 - i,
+        }
+    }
+
+    /// Reads `limbs.len()` limbs, least significant first.
+    fn read_limbs(&self, bytes: &[u8], limbs: &mut [u64]) {
+        let n = limbs.len();
+        for (i, chunk) in bytes.chunks(8).take(n).enumerate() {
+            limbs[self.limb_index(i, n)] = match self.byte_order {
+                Endianness::Little => LittleEndian::read_u64(chunk),
+                Endianness::Big => BigEndian::read_u64(chunk),
+            };
+        }
+    }
+
+    /// Writes `limbs.len()` limbs, least significant first.
+    fn write_limbs(&self, limbs: &[u64], bytes: &mut [u8]) {
+        let n = limbs.len();
+        for (i, chunk) in bytes.chunks_mut(8).take(n).enumerate() {
+            let limb = limbs[self.limb_index(i, n)];
+            match self.byte_order {
+                Endianness::Little => LittleEndian::write_u64(chunk, limb),
+                Endianness::Big => BigEndian::write_u64(chunk, limb),
+            }
+        }
+    }
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding::LITTLE_ENDIAN
+    }
+}
+
+/// Converts each `coordinate_size` bytes long coordinate of encoded points from one encoding to another.
+fn reencode(bytes: &[u8], from: Encoding, to: Encoding, coordinate_size: usize) -> Vec<u8> {
+    let mut reencoded = vec![0; bytes.len()];
+    let mut limbs = vec![0u64; coordinate_size / 8];
+    for (src, dst) in bytes
+        .chunks(coordinate_size)
+        .zip(reencoded.chunks_mut(coordinate_size))
+    {
+        from.read_limbs(src, &mut limbs);
+        to.write_limbs(&limbs, dst);
+    }
+    reencoded
+}
+
 pub fn std_size_t_process(proof_bytes: &[u8]) -> Result<usize, Box<dyn error::Error>>{
+    std_size_t_process_with_encoding(proof_bytes, Encoding::LITTLE_ENDIAN)
+}
 
+pub fn std_size_t_process_with_encoding(
+    proof_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<usize, Box<dyn error::Error>> {
     let std_size_byteblob_size = 4;
 
-    let fr_byteblob : Vec<u8> = proof_bytes[..std_size_byteblob_size].to_vec();
-
-    let mut dst = [0; 1];
-    LittleEndian::read_u32_into(&fr_byteblob, &mut dst);
+    if proof_bytes.len() < std_size_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "length byteblob is too short").into(),
+        );
+    }
 
-    let res = dst[0] as usize;
+    let res = encoding.read_u32(&proof_bytes[..std_size_byteblob_size]) as usize;
     Ok(res)
 }
 
-pub fn fr_process<E: Engine>(proof_bytes: &[u8]) -> Result<Fr, Box<dyn error::Error>>{
-
-    let fr_byteblob_size = 32;
+pub fn fr_process<E: Engine>(proof_bytes: &[u8]) -> Result<E::Fr, Box<dyn error::Error>> {
+    fr_process_with_encoding::<E>(proof_bytes, Encoding::LITTLE_ENDIAN)
+}
 
-    let fr_byteblob : Vec<u8> = proof_bytes[..fr_byteblob_size].to_vec();
+pub fn fr_process_with_encoding<E: Engine>(
+    proof_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<E::Fr, Box<dyn error::Error>> {
+    let mut fr_repr = <E::Fr as PrimeField>::Repr::default();
+    let fr_byteblob_size = 8 * fr_repr.as_ref().len();
+
+    if proof_bytes.len() < fr_byteblob_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "field element byteblob is too short",
+        )
+        .into());
+    }
 
-    let mut dst = [0; 4];
-    LittleEndian::read_u64_into(&fr_byteblob, &mut dst);
+    encoding.read_limbs(&proof_bytes[..fr_byteblob_size], fr_repr.as_mut());
 
-    let fr_element = Fr::from_repr(FrRepr(dst))?;
+    let fr_element = E::Fr::from_repr(fr_repr)?;
 
     Ok(fr_element)
 }
 
 pub fn fp_process<E: Engine>(proof_bytes: &[u8]) -> Result<Fq, Box<dyn error::Error>>{
+    fp_process_with_encoding::<E>(proof_bytes, Encoding::LITTLE_ENDIAN)
+}
 
+pub fn fp_process_with_encoding<E: Engine>(
+    proof_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<Fq, Box<dyn error::Error>> {
     let fp_byteblob_size = 48;
 
-    let fp_byteblob : Vec<u8> = proof_bytes[..fp_byteblob_size].to_vec();
+    if proof_bytes.len() < fp_byteblob_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "field element byteblob is too short",
+        )
+        .into());
+    }
 
     let mut dst = [0; 6];
-    LittleEndian::read_u64_into(&fp_byteblob, &mut dst);
+    encoding.read_limbs(&proof_bytes[..fp_byteblob_size], &mut dst);
 
     let fq_element = Fq::from_repr(FqRepr(dst))?;
 
@@ -58,6 +183,14 @@ pub fn fp2_process<E: Engine>(proof_bytes: &[u8]) -> Result<Fq2, Box<dyn error::
 
     let fp_byteblob_size = 48;
 
+    if proof_bytes.len() < 2 * fp_byteblob_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "fp2 element byteblob is too short",
+        )
+        .into());
+    }
+
     let mut c0 = fp_process::<E>(&proof_bytes[..fp_byteblob_size])?;
     let mut c1 = fp_process::<E>(&proof_bytes[fp_byteblob_size..])?;
 
@@ -71,6 +204,10 @@ pub fn fp6_3over2_process<E: Engine>(proof_bytes: &[u8]) -> Result<Fq6, Box<dyn
     let fp_byteblob_size = 48;
     let fp2_byteblob_size = 2*fp_byteblob_size;
 
+    if proof_bytes.len() < 3*fp2_byteblob_size {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "fp6 element byteblob is too short").into());
+    }
+
     let mut c0 = fp2_process::<E>(&proof_bytes[..fp2_byteblob_size])?;
     let mut c1 = fp2_process::<E>(&proof_bytes[fp2_byteblob_size..2*fp2_byteblob_size])?;
     let mut c2 = fp2_process::<E>(&proof_bytes[2*fp2_byteblob_size..])?;
@@ -85,6 +222,10 @@ pub fn fp12_2over3over2_process<E: Engine>(proof_bytes: &[u8]) -> Result<Fq12, B
     let fp_byteblob_size = 48;
     let fp6_3over2_bytblob_size = 3*2*fp_byteblob_size;
 
+    if proof_bytes.len() < 2*fp6_3over2_bytblob_size {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "fp12 element byteblob is too short").into());
+    }
+
     let mut c0_processed = fp6_3over2_process::<E>(&proof_bytes[..fp6_3over2_bytblob_size])?;
     let mut c1_processed = fp6_3over2_process::<E>(&proof_bytes[fp6_3over2_bytblob_size..])?;
     
@@ -93,10 +234,22 @@ pub fn fp12_2over3over2_process<E: Engine>(proof_bytes: &[u8]) -> Result<Fq12, B
     Ok(fq12_2over3over2_element)
 }
 
+/// Reads an element of the degree 12 extension, e.g. a pairing result, in the
+/// layout of `fp12_2over3over2_process`.
+pub fn fp12_process<E: Engine>(proof_bytes: &[u8]) -> Result<Fq12, Box<dyn error::Error>> {
+    fp12_2over3over2_process::<E>(proof_bytes)
+}
+
 pub fn g1_affine_process<E: Engine>(proof_bytes: &[u8]) -> Result<E::G1Affine, Box<dyn error::Error>>{
 
     let g1_byteblob_size = <E::G1Affine as CurveAffine>::Compressed::size();
 
+    if proof_bytes.len() < g1_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "G1 point byteblob is too short").into(),
+        );
+    }
+
     let mut g1_repr = <E::G1Affine as CurveAffine>::Compressed::empty();
         let start = 0;
         let end = start + g1_byteblob_size;
@@ -123,6 +276,12 @@ pub fn g2_affine_process<E: Engine>(proof_bytes: &[u8]) -> Result<E::G2Affine, B
 
     let g2_byteblob_size = <E::G2Affine as CurveAffine>::Compressed::size();
 
+    if proof_bytes.len() < g2_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "G2 point byteblob is too short").into(),
+        );
+    }
+
     let mut g2_repr = <E::G2Affine as CurveAffine>::Compressed::empty();
         let start = 0;
         let end = start + g2_byteblob_size;
@@ -145,6 +304,48 @@ pub fn g2_affine_process<E: Engine>(proof_bytes: &[u8]) -> Result<E::G2Affine, B
     Ok(g2_affine_element)
 }
 
+pub fn g1_affine_process_with_encoding<E: Engine>(
+    proof_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<E::G1Affine, Box<dyn error::Error>> {
+    let g1_byteblob_size = <E::G1Affine as CurveAffine>::Compressed::size();
+
+    if proof_bytes.len() < g1_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "G1 point byteblob is too short").into(),
+        );
+    }
+
+    g1_affine_process::<E>(&reencode(
+        &proof_bytes[..g1_byteblob_size],
+        encoding,
+        Encoding::BIG_ENDIAN,
+        g1_byteblob_size,
+    ))
+}
+
+pub fn g2_affine_process_with_encoding<E: Engine>(
+    proof_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<E::G2Affine, Box<dyn error::Error>> {
+    // A compressed G2 point holds two coordinates of the size of a compressed G1 point.
+    let coordinate_size = <E::G1Affine as CurveAffine>::Compressed::size();
+    let g2_byteblob_size = <E::G2Affine as CurveAffine>::Compressed::size();
+
+    if proof_bytes.len() < g2_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "G2 point byteblob is too short").into(),
+        );
+    }
+
+    g2_affine_process::<E>(&reencode(
+        &proof_bytes[..g2_byteblob_size],
+        encoding,
+        Encoding::BIG_ENDIAN,
+        coordinate_size,
+    ))
+}
+
 #[derive(Debug, Clone)]
 struct MarshallingError;
 
@@ -198,12 +399,22 @@ pub fn accumulation_vector_process<E: Engine>(proof_bytes: &[u8]) -> Result<Vec<
     Ok(accumulation_vector)
 }
 
-pub fn groth16_vk_from_byteblob(proof_bytes: &[u8]) -> Result<GROTH16VerificationKey::<Bls12>, Box<dyn error::Error>>{
+pub fn groth16_processed_vk_from_byteblob(
+    proof_bytes: &[u8],
+) -> Result<GROTH16VerificationKey<Bls12>, Box<dyn error::Error>> {
     let fp_byteblob_size = 48;
     let fqk_byteblob_size = 2*3*2*fp_byteblob_size;
     let g1_byteblob_size = <<Bls12 as Engine>::G1Affine as CurveAffine>::Compressed::size();
     let g2_byteblob_size = <<Bls12 as Engine>::G2Affine as CurveAffine>::Compressed::size();
 
+    if proof_bytes.len() < fqk_byteblob_size + 2 * g2_byteblob_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "prepared verifying key byteblob is too short",
+        )
+        .into());
+    }
+
     let mut alpha_g1_beta_g2_processed = fp12_2over3over2_process::<Bls12>(&proof_bytes[..fqk_byteblob_size])?;
     let mut gamma_g2_processed = g2_affine_process::<Bls12>(&proof_bytes[fqk_byteblob_size..fqk_byteblob_size+g2_byteblob_size])?;
     let mut delta_g2_processed = g2_affine_process::<Bls12>(&proof_bytes[fqk_byteblob_size+g2_byteblob_size..fqk_byteblob_size+2*g2_byteblob_size])?;
@@ -225,6 +436,189 @@ pub fn groth16_vk_from_byteblob(proof_bytes: &[u8]) -> Result<GROTH16Verificatio
     Ok(groth16_key)
 }
 
+/// Reads a prepared verifying key, see `groth16_processed_vk_from_byteblob`.
+#[deprecated(note = "renamed to `groth16_processed_vk_from_byteblob`")]
+pub fn groth16_vk_from_byteblob(
+    proof_bytes: &[u8],
+) -> Result<GROTH16VerificationKey<Bls12>, Box<dyn error::Error>> {
+    groth16_processed_vk_from_byteblob(proof_bytes)
+}
+
+/// Writes a prepared verifying key in the layout read by `groth16_processed_vk_from_byteblob`.
+pub fn groth16_processed_vk_to_byteblob(pvk: &PreparedVerifyingKey<Bls12>) -> Vec<u8> {
+    let mut byteblob = fp12_to_byteblob(&pvk.alpha_g1_beta_g2);
+    byteblob.extend_from_slice(pvk.gamma_g2.into_compressed().as_ref());
+    byteblob.extend_from_slice(pvk.delta_g2.into_compressed().as_ref());
+
+    // ic: the first element, then the remaining ones as a sparse vector: the
+    // number of elements, their indices, the elements and the domain size.
+    let (first, rest) = pvk.ic.split_first().expect("ic is never empty");
+    byteblob.extend_from_slice(first.into_compressed().as_ref());
+    byteblob.extend(std_size_t_to_byteblob(rest.len()));
+    for i in 0..rest.len() {
+        byteblob.extend(std_size_t_to_byteblob(i));
+    }
+    for ic in rest {
+        byteblob.extend_from_slice(ic.into_compressed().as_ref());
+    }
+    byteblob.extend(std_size_t_to_byteblob(rest.len()));
+    byteblob
+}
+
+/// Reads a `VerifyingKey` from a byteblob of compressed points: `alpha_g1`,
+/// `beta_g1`, `beta_g2`, `gamma_g2`, `delta_g1`, `delta_g2`, then the number
+/// of `ic` elements as a `std_size_t` followed by the `ic` elements.
+pub fn groth16_verifying_key_from_byteblob<E: Engine>(
+    vk_bytes: &[u8],
+) -> Result<VerifyingKey<E>, Box<dyn error::Error>> {
+    groth16_verifying_key_from_byteblob_inner(
+        vk_bytes,
+        PointFormat::Compressed(Encoding::BIG_ENDIAN),
+        Encoding::LITTLE_ENDIAN,
+    )
+}
+
+pub fn groth16_verifying_key_from_byteblob_with_encoding<E: Engine>(
+    vk_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<VerifyingKey<E>, Box<dyn error::Error>> {
+    groth16_verifying_key_from_byteblob_inner(vk_bytes, PointFormat::Compressed(encoding), encoding)
+}
+
+/// How the points of a verifying key byteblob are encoded.
+#[derive(Clone, Copy)]
+enum PointFormat {
+    /// Compressed points, with their coordinates in the given encoding.
+    Compressed(Encoding),
+    /// Uncompressed points, as written by `into_uncompressed`.
+    Uncompressed,
+}
+
+impl PointFormat {
+    fn g1_size<E: Engine>(self) -> usize {
+        match self {
+            PointFormat::Compressed(_) => <E::G1Affine as CurveAffine>::Compressed::size(),
+            PointFormat::Uncompressed => <E::G1Affine as CurveAffine>::Uncompressed::size(),
+        }
+    }
+
+    fn g2_size<E: Engine>(self) -> usize {
+        match self {
+            PointFormat::Compressed(_) => <E::G2Affine as CurveAffine>::Compressed::size(),
+            PointFormat::Uncompressed => <E::G2Affine as CurveAffine>::Uncompressed::size(),
+        }
+    }
+
+    fn read_g1<E: Engine>(self, bytes: &[u8]) -> Result<E::G1Affine, Box<dyn error::Error>> {
+        match self {
+            PointFormat::Compressed(encoding) => {
+                g1_affine_process_with_encoding::<E>(bytes, encoding)
+            }
+            PointFormat::Uncompressed => g1_affine_process_uncompressed::<E>(bytes),
+        }
+    }
+
+    fn read_g2<E: Engine>(self, bytes: &[u8]) -> Result<E::G2Affine, Box<dyn error::Error>> {
+        match self {
+            PointFormat::Compressed(encoding) => {
+                g2_affine_process_with_encoding::<E>(bytes, encoding)
+            }
+            PointFormat::Uncompressed => g2_affine_process_uncompressed::<E>(bytes),
+        }
+    }
+
+    fn write_g1<E: Engine>(self, point: &E::G1Affine) -> Vec<u8> {
+        match self {
+            PointFormat::Compressed(encoding) => reencode(
+                point.into_compressed().as_ref(),
+                Encoding::BIG_ENDIAN,
+                encoding,
+                self.g1_size::<E>(),
+            ),
+            PointFormat::Uncompressed => point.into_uncompressed().as_ref().to_vec(),
+        }
+    }
+
+    fn write_g2<E: Engine>(self, point: &E::G2Affine) -> Vec<u8> {
+        match self {
+            // A compressed G2 point holds two coordinates of the size of a compressed G1 point.
+            PointFormat::Compressed(encoding) => reencode(
+                point.into_compressed().as_ref(),
+                Encoding::BIG_ENDIAN,
+                encoding,
+                self.g1_size::<E>(),
+            ),
+            PointFormat::Uncompressed => point.into_uncompressed().as_ref().to_vec(),
+        }
+    }
+}
+
+fn groth16_verifying_key_from_byteblob_inner<E: Engine>(
+    vk_bytes: &[u8],
+    points: PointFormat,
+    lengths: Encoding,
+) -> Result<VerifyingKey<E>, Box<dyn error::Error>> {
+    let std_size_byteblob_size = 4;
+    let g1_byteblob_size = points.g1_size::<E>();
+    let g2_byteblob_size = points.g2_size::<E>();
+
+    let ic_begin = 3 * g1_byteblob_size + 3 * g2_byteblob_size + std_size_byteblob_size;
+    if vk_bytes.len() < ic_begin {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "verifying key byteblob is too short",
+        )
+        .into());
+    }
+
+    let g1 = |offset: &mut usize| {
+        let element = points.read_g1::<E>(&vk_bytes[*offset..*offset + g1_byteblob_size]);
+        *offset += g1_byteblob_size;
+        element
+    };
+    let g2 = |offset: &mut usize| {
+        let element = points.read_g2::<E>(&vk_bytes[*offset..*offset + g2_byteblob_size]);
+        *offset += g2_byteblob_size;
+        element
+    };
+
+    let mut offset = 0;
+    let alpha_g1 = g1(&mut offset)?;
+    let beta_g1 = g1(&mut offset)?;
+    let beta_g2 = g2(&mut offset)?;
+    let gamma_g2 = g2(&mut offset)?;
+    let delta_g1 = g1(&mut offset)?;
+    let delta_g2 = g2(&mut offset)?;
+
+    let ic_count = std_size_t_process_with_encoding(
+        &vk_bytes[offset..offset + std_size_byteblob_size],
+        lengths,
+    )?;
+    offset += std_size_byteblob_size;
+
+    if vk_bytes.len() != offset + ic_count * g1_byteblob_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "verifying key byteblob has a wrong ic length",
+        )
+        .into());
+    }
+
+    let ic = (0..ic_count)
+        .map(|_| g1(&mut offset))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(VerifyingKey {
+        alpha_g1,
+        beta_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g1,
+        delta_g2,
+        ic,
+    })
+}
+
 pub fn groth16_proof_from_byteblob<E: Engine>(proof_bytes: &[u8]) -> Result<Proof<E>, Box<dyn error::Error>>{
     
     let g1_byteblob_size = <E::G1Affine as CurveAffine>::Compressed::size();
@@ -237,15 +631,619 @@ pub fn groth16_proof_from_byteblob<E: Engine>(proof_bytes: &[u8]) -> Result<Proo
     Ok(de_prf)
 }
 
-pub fn groth16_primary_input_from_byteblob<E: Engine>(proof_bytes: &[u8]) -> Result<Vec<Fr>, Box<dyn error::Error>>{
-    
-    let fr_byteblob_size = 32;
-    let groth16_primary_input_size = proof_bytes.len()/fr_byteblob_size;
-    let mut groth16_primary_input = Vec::new();
+pub fn groth16_proof_from_byteblob_with_encoding<E: Engine>(
+    proof_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<Proof<E>, Box<dyn error::Error>> {
+    let g1_byteblob_size = <E::G1Affine as CurveAffine>::Compressed::size();
+    let g2_byteblob_size = <E::G2Affine as CurveAffine>::Compressed::size();
+
+    let proof_byteblob_size = g1_byteblob_size + g2_byteblob_size + g1_byteblob_size;
+
+    if proof_bytes.len() < proof_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "proof byteblob is too short").into(),
+        );
+    }
+
+    // Every coordinate has the size of a compressed G1 point.
+    let standard = reencode(
+        &proof_bytes[..proof_byteblob_size],
+        encoding,
+        Encoding::BIG_ENDIAN,
+        g1_byteblob_size,
+    );
+
+    groth16_proof_from_byteblob::<E>(&standard)
+}
 
-    for i in 0..groth16_primary_input_size {
-        groth16_primary_input.push(fr_process::<E>(&proof_bytes[i*fr_byteblob_size.. (i+1)*fr_byteblob_size])?);
+/// Reads a primary input from a byteblob: the number of elements as a
+/// `std_size_t`, followed by the elements in the `fr_process` encoding.
+pub fn groth16_primary_input_from_byteblob<E: Engine>(
+    proof_bytes: &[u8],
+) -> Result<Vec<E::Fr>, Box<dyn error::Error>> {
+    groth16_primary_input_from_byteblob_with_encoding::<E>(proof_bytes, Encoding::LITTLE_ENDIAN)
+}
+
+pub fn groth16_primary_input_from_byteblob_with_encoding<E: Engine>(
+    proof_bytes: &[u8],
+    encoding: Encoding,
+) -> Result<Vec<E::Fr>, Box<dyn error::Error>> {
+    let std_size_byteblob_size = 4;
+    let fr_byteblob_size = 8 * <E::Fr as PrimeField>::Repr::default().as_ref().len();
+
+    if proof_bytes.len() < std_size_byteblob_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "primary input byteblob is too short",
+        )
+        .into());
+    }
+
+    let groth16_primary_input_size =
+        std_size_t_process_with_encoding(&proof_bytes[..std_size_byteblob_size], encoding)?;
+    let elements = &proof_bytes[std_size_byteblob_size..];
+
+    if elements.len() != groth16_primary_input_size * fr_byteblob_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "primary input byteblob has a wrong length",
+        )
+        .into());
     }
 
-    Ok(groth16_primary_input)
-}
\ No newline at end of file
+    elements
+        .chunks(fr_byteblob_size)
+        .map(|fr_bytes| fr_process_with_encoding::<E>(fr_bytes, encoding))
+        .collect()
+}
+
+pub fn std_size_t_to_byteblob(size: usize) -> Vec<u8> {
+    std_size_t_to_byteblob_with_encoding(size, Encoding::LITTLE_ENDIAN)
+}
+
+pub fn std_size_t_to_byteblob_with_encoding(size: usize, encoding: Encoding) -> Vec<u8> {
+    let mut byteblob = vec![0; 4];
+    encoding.write_u32(&mut byteblob, size as u32);
+    byteblob
+}
+
+pub fn fr_to_byteblob<E: Engine>(fr: &E::Fr) -> Vec<u8> {
+    fr_to_byteblob_with_encoding::<E>(fr, Encoding::LITTLE_ENDIAN)
+}
+
+pub fn fr_to_byteblob_with_encoding<E: Engine>(fr: &E::Fr, encoding: Encoding) -> Vec<u8> {
+    let fr_repr = fr.into_repr();
+    let mut byteblob = vec![0; 8 * fr_repr.as_ref().len()];
+    encoding.write_limbs(fr_repr.as_ref(), &mut byteblob);
+    byteblob
+}
+
+/// Writes field elements in the layout read by `groth16_primary_input_from_byteblob`.
+pub fn fr_vector_to_byteblob<E: Engine>(frs: &[E::Fr]) -> Vec<u8> {
+    fr_vector_to_byteblob_with_encoding::<E>(frs, Encoding::LITTLE_ENDIAN)
+}
+
+pub fn fr_vector_to_byteblob_with_encoding<E: Engine>(
+    frs: &[E::Fr],
+    encoding: Encoding,
+) -> Vec<u8> {
+    let mut byteblob = std_size_t_to_byteblob_with_encoding(frs.len(), encoding);
+    for fr in frs {
+        byteblob.extend(fr_to_byteblob_with_encoding::<E>(fr, encoding));
+    }
+    byteblob
+}
+
+/// Writes a proof in the layout read by `groth16_proof_from_byteblob`.
+pub fn groth16_proof_to_byteblob<E: Engine>(proof: &Proof<E>) -> Vec<u8> {
+    let mut byteblob = Vec::with_capacity(Proof::<E>::size());
+    byteblob.extend_from_slice(proof.a.into_compressed().as_ref());
+    byteblob.extend_from_slice(proof.b.into_compressed().as_ref());
+    byteblob.extend_from_slice(proof.c.into_compressed().as_ref());
+    byteblob
+}
+
+pub fn groth16_proof_to_byteblob_with_encoding<E: Engine>(
+    proof: &Proof<E>,
+    encoding: Encoding,
+) -> Vec<u8> {
+    let coordinate_size = <E::G1Affine as CurveAffine>::Compressed::size();
+    reencode(
+        &groth16_proof_to_byteblob(proof),
+        Encoding::BIG_ENDIAN,
+        encoding,
+        coordinate_size,
+    )
+}
+
+/// Writes a verifying key in the layout read by `groth16_verifying_key_from_byteblob`.
+pub fn groth16_verifying_key_to_byteblob<E: Engine>(vk: &VerifyingKey<E>) -> Vec<u8> {
+    groth16_verifying_key_to_byteblob_inner(
+        vk,
+        PointFormat::Compressed(Encoding::BIG_ENDIAN),
+        Encoding::LITTLE_ENDIAN,
+    )
+}
+
+pub fn groth16_verifying_key_to_byteblob_with_encoding<E: Engine>(
+    vk: &VerifyingKey<E>,
+    encoding: Encoding,
+) -> Vec<u8> {
+    groth16_verifying_key_to_byteblob_inner(vk, PointFormat::Compressed(encoding), encoding)
+}
+
+fn groth16_verifying_key_to_byteblob_inner<E: Engine>(
+    vk: &VerifyingKey<E>,
+    points: PointFormat,
+    lengths: Encoding,
+) -> Vec<u8> {
+    let mut byteblob = Vec::new();
+    byteblob.extend(points.write_g1::<E>(&vk.alpha_g1));
+    byteblob.extend(points.write_g1::<E>(&vk.beta_g1));
+    byteblob.extend(points.write_g2::<E>(&vk.beta_g2));
+    byteblob.extend(points.write_g2::<E>(&vk.gamma_g2));
+    byteblob.extend(points.write_g1::<E>(&vk.delta_g1));
+    byteblob.extend(points.write_g2::<E>(&vk.delta_g2));
+    byteblob.extend(std_size_t_to_byteblob_with_encoding(vk.ic.len(), lengths));
+    for ic in &vk.ic {
+        byteblob.extend(points.write_g1::<E>(ic));
+    }
+    byteblob
+}
+
+pub fn fp_to_byteblob(fp: &Fq) -> Vec<u8> {
+    fp_to_byteblob_with_encoding(fp, Encoding::LITTLE_ENDIAN)
+}
+
+pub fn fp_to_byteblob_with_encoding(fp: &Fq, encoding: Encoding) -> Vec<u8> {
+    let fp_repr = fp.into_repr();
+    let mut byteblob = vec![0; 8 * fp_repr.as_ref().len()];
+    encoding.write_limbs(fp_repr.as_ref(), &mut byteblob);
+    byteblob
+}
+
+pub fn fp2_to_byteblob(fp2: &Fq2) -> Vec<u8> {
+    let mut byteblob = fp_to_byteblob(&fp2.c0);
+    byteblob.extend(fp_to_byteblob(&fp2.c1));
+    byteblob
+}
+
+pub fn fp6_3over2_to_byteblob(fp6: &Fq6) -> Vec<u8> {
+    let mut byteblob = fp2_to_byteblob(&fp6.c0);
+    byteblob.extend(fp2_to_byteblob(&fp6.c1));
+    byteblob.extend(fp2_to_byteblob(&fp6.c2));
+    byteblob
+}
+
+/// Writes an element of the degree 12 extension in the layout read by `fp12_process`.
+pub fn fp12_to_byteblob(fp12: &Fq12) -> Vec<u8> {
+    let mut byteblob = fp6_3over2_to_byteblob(&fp12.c0);
+    byteblob.extend(fp6_3over2_to_byteblob(&fp12.c1));
+    byteblob
+}
+
+pub fn g1_affine_process_uncompressed<E: Engine>(
+    proof_bytes: &[u8],
+) -> Result<E::G1Affine, Box<dyn error::Error>> {
+    let g1_byteblob_size = <E::G1Affine as CurveAffine>::Uncompressed::size();
+
+    if proof_bytes.len() < g1_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "G1 point byteblob is too short").into(),
+        );
+    }
+
+    let mut g1_repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
+    g1_repr
+        .as_mut()
+        .copy_from_slice(&proof_bytes[..g1_byteblob_size]);
+
+    let g1_affine_element = g1_repr
+        .into_affine()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|e| {
+            if e.is_zero() {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ))
+            } else {
+                Ok(e)
+            }
+        })?;
+
+    Ok(g1_affine_element)
+}
+
+pub fn g2_affine_process_uncompressed<E: Engine>(
+    proof_bytes: &[u8],
+) -> Result<E::G2Affine, Box<dyn error::Error>> {
+    let g2_byteblob_size = <E::G2Affine as CurveAffine>::Uncompressed::size();
+
+    if proof_bytes.len() < g2_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "G2 point byteblob is too short").into(),
+        );
+    }
+
+    let mut g2_repr = <E::G2Affine as CurveAffine>::Uncompressed::empty();
+    g2_repr
+        .as_mut()
+        .copy_from_slice(&proof_bytes[..g2_byteblob_size]);
+
+    let g2_affine_element = g2_repr
+        .into_affine()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|e| {
+            if e.is_zero() {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ))
+            } else {
+                Ok(e)
+            }
+        })?;
+
+    Ok(g2_affine_element)
+}
+
+/// Reads a proof written by `groth16_proof_to_byteblob_uncompressed`.
+///
+/// The points are still checked to be on the curve and in the right subgroup,
+/// but no square roots are computed, which makes this considerably cheaper
+/// than `groth16_proof_from_byteblob` for large batches.
+pub fn groth16_proof_from_byteblob_uncompressed<E: Engine>(
+    proof_bytes: &[u8],
+) -> Result<Proof<E>, Box<dyn error::Error>> {
+    let g1_byteblob_size = <E::G1Affine as CurveAffine>::Uncompressed::size();
+    let g2_byteblob_size = <E::G2Affine as CurveAffine>::Uncompressed::size();
+
+    if proof_bytes.len() < 2 * g1_byteblob_size + g2_byteblob_size {
+        return Err(
+            io::Error::new(io::ErrorKind::InvalidData, "proof byteblob is too short").into(),
+        );
+    }
+
+    let a = g1_affine_process_uncompressed::<E>(&proof_bytes[..g1_byteblob_size])?;
+    let b = g2_affine_process_uncompressed::<E>(
+        &proof_bytes[g1_byteblob_size..g1_byteblob_size + g2_byteblob_size],
+    )?;
+    let c =
+        g1_affine_process_uncompressed::<E>(&proof_bytes[g1_byteblob_size + g2_byteblob_size..])?;
+
+    Ok(Proof { a, b, c })
+}
+
+/// Writes a proof as uncompressed points `a`, `b` and `c`.
+pub fn groth16_proof_to_byteblob_uncompressed<E: Engine>(proof: &Proof<E>) -> Vec<u8> {
+    let mut byteblob = Vec::new();
+    byteblob.extend_from_slice(proof.a.into_uncompressed().as_ref());
+    byteblob.extend_from_slice(proof.b.into_uncompressed().as_ref());
+    byteblob.extend_from_slice(proof.c.into_uncompressed().as_ref());
+    byteblob
+}
+
+/// Reads a `VerifyingKey` in the layout of `groth16_verifying_key_from_byteblob`, with
+/// uncompressed points.
+pub fn groth16_verifying_key_from_byteblob_uncompressed<E: Engine>(
+    vk_bytes: &[u8],
+) -> Result<VerifyingKey<E>, Box<dyn error::Error>> {
+    groth16_verifying_key_from_byteblob_inner(
+        vk_bytes,
+        PointFormat::Uncompressed,
+        Encoding::LITTLE_ENDIAN,
+    )
+}
+
+/// Writes a verifying key in the layout read by `groth16_verifying_key_from_byteblob_uncompressed`.
+pub fn groth16_verifying_key_to_byteblob_uncompressed<E: Engine>(vk: &VerifyingKey<E>) -> Vec<u8> {
+    groth16_verifying_key_to_byteblob_inner(vk, PointFormat::Uncompressed, Encoding::LITTLE_ENDIAN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::groth16::{generate_random_parameters, Parameters};
+    use crate::util_cs::test_fixtures::test_rng;
+    use crate::{Circuit, ConstraintSystem};
+
+    // `a * b = c` and `a * c = d` for the public `c` and `d`, so that `ic` has
+    // three points. Only its parameters are used, so it has no witness.
+    struct TwoInputCircuit;
+
+    impl Circuit<Bls12> for TwoInputCircuit {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let a = cs.alloc(|| "a", || Err(SynthesisError::AssignmentMissing))?;
+            let b = cs.alloc(|| "b", || Err(SynthesisError::AssignmentMissing))?;
+            let c = cs.alloc_input(|| "c", || Err(SynthesisError::AssignmentMissing))?;
+            let d = cs.alloc_input(|| "d", || Err(SynthesisError::AssignmentMissing))?;
+
+            cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+            cs.enforce(|| "a*c=d", |lc| lc + a, |lc| lc + c, |lc| lc + d);
+
+            Ok(())
+        }
+    }
+
+    fn random_params() -> Parameters<Bls12> {
+        let mut rng = test_rng();
+
+        generate_random_parameters::<Bls12, _, _>(TwoInputCircuit, &mut rng).unwrap()
+    }
+
+    #[test]
+    fn test_groth16_primary_input_from_byteblob() {
+        let mut rng = test_rng();
+
+        let inputs = (0..5).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let blob = fr_vector_to_byteblob::<Bls12>(&inputs);
+        assert_eq!(blob.len(), 4 + 5 * 32);
+        assert_eq!(&blob[..4], &[5, 0, 0, 0]);
+
+        assert_eq!(
+            groth16_primary_input_from_byteblob::<Bls12>(&blob).unwrap(),
+            inputs
+        );
+        assert_eq!(
+            groth16_primary_input_from_byteblob::<Bls12>(&[0, 0, 0, 0]).unwrap(),
+            vec![]
+        );
+
+        assert!(groth16_primary_input_from_byteblob::<Bls12>(&blob[..blob.len() - 1]).is_err());
+        assert!(groth16_primary_input_from_byteblob::<Bls12>(&blob[..3]).is_err());
+
+        // Not a canonical field element.
+        let mut blob = vec![1, 0, 0, 0];
+        blob.extend_from_slice(&[0xff; 32]);
+        assert!(groth16_primary_input_from_byteblob::<Bls12>(&blob).is_err());
+    }
+
+    #[test]
+    fn test_groth16_verifying_key_from_byteblob() {
+        let params = random_params();
+        let blob = groth16_verifying_key_to_byteblob(&params.vk);
+        assert_eq!(blob.len(), 3 * 48 + 3 * 96 + 4 + 3 * 48);
+
+        let vk = groth16_verifying_key_from_byteblob::<Bls12>(&blob).unwrap();
+        assert!(vk == params.vk);
+        assert_eq!(vk.ic.len(), 3);
+
+        assert!(groth16_verifying_key_from_byteblob::<Bls12>(&blob[..blob.len() - 1]).is_err());
+        assert!(groth16_verifying_key_from_byteblob::<Bls12>(&blob[..100]).is_err());
+
+        let mut extended = blob.clone();
+        extended.push(0);
+        assert!(groth16_verifying_key_from_byteblob::<Bls12>(&extended).is_err());
+
+        // The previous name of groth16_processed_vk_from_byteblob.
+        let pvk = crate::groth16::prepare_verifying_key(&params.vk);
+        #[allow(deprecated)]
+        let processed = groth16_vk_from_byteblob(&groth16_processed_vk_to_byteblob(&pvk)).unwrap();
+        assert!(processed.ic == params.vk.ic);
+    }
+
+    #[test]
+    fn test_groth16_processed_vk_truncated() {
+        let pvk = crate::groth16::prepare_verifying_key(&random_params().vk);
+        let blob = groth16_processed_vk_to_byteblob(&pvk);
+        assert!(groth16_processed_vk_from_byteblob(&blob).is_ok());
+
+        // The trailing domain size is not read, so cut into the last point.
+        for len in &[0, 100, 576, 576 + 96, 576 + 2 * 96, blob.len() - 5] {
+            assert!(groth16_processed_vk_from_byteblob(&blob[..*len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_groth16_proof_byteblob_roundtrip() {
+        let mut rng = test_rng();
+
+        let params = random_params();
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+
+        // Any points will do, the codec does not care about validity.
+        let proof = Proof::<Bls12> {
+            a: params.vk.alpha_g1.mul(a).into_affine(),
+            b: params.vk.beta_g2.mul(b).into_affine(),
+            c: params.vk.delta_g1,
+        };
+
+        let blob = groth16_proof_to_byteblob(&proof);
+        assert_eq!(blob.len(), Proof::<Bls12>::size());
+        assert!(groth16_proof_from_byteblob::<Bls12>(&blob).unwrap() == proof);
+
+        // The byteblob is the compressed encoding of `Proof::write`.
+        let mut written = Vec::new();
+        proof.write(&mut written).unwrap();
+        assert_eq!(written, blob);
+    }
+
+    #[test]
+    fn test_fr_byteblob_roundtrip() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let fr = Fr::random(&mut rng);
+            let blob = fr_to_byteblob::<Bls12>(&fr);
+            assert_eq!(fr_process::<Bls12>(&blob).unwrap(), fr);
+        }
+
+        let frs = (0..10).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let blob = fr_vector_to_byteblob::<Bls12>(&frs);
+        assert_eq!(
+            groth16_primary_input_from_byteblob::<Bls12>(&blob).unwrap(),
+            frs
+        );
+    }
+
+    #[test]
+    fn test_fp_byteblob_roundtrip() {
+        let mut rng = test_rng();
+
+        for _ in 0..10 {
+            let fp = Fq::random(&mut rng);
+            let blob = fp_to_byteblob(&fp);
+            assert_eq!(blob.len(), 48);
+            assert_eq!(fp_process::<Bls12>(&blob).unwrap(), fp);
+
+            let fp2 = Fq2::random(&mut rng);
+            let blob = fp2_to_byteblob(&fp2);
+            assert_eq!(blob.len(), 2 * 48);
+            assert_eq!(fp2_process::<Bls12>(&blob).unwrap(), fp2);
+
+            let fp12 = Fq12::random(&mut rng);
+            let blob = fp12_to_byteblob(&fp12);
+            assert_eq!(blob.len(), 12 * 48);
+            assert_eq!(fp12_process::<Bls12>(&blob).unwrap(), fp12);
+        }
+
+        assert!(fp_process::<Bls12>(&[0; 47]).is_err());
+        assert!(fp_process::<Bls12>(&[0xff; 48]).is_err());
+
+        // Short input is an error rather than a panic.
+        assert!(fp2_process::<Bls12>(&[0; 47]).is_err());
+        assert!(fp2_process::<Bls12>(&[0; 95]).is_err());
+        assert!(fp6_3over2_process::<Bls12>(&[0; 287]).is_err());
+        assert!(fp12_process::<Bls12>(&[0; 100]).is_err());
+        assert!(fp12_process::<Bls12>(&vec![0; 575]).is_err());
+        assert!(g1_affine_process::<Bls12>(&[0; 47]).is_err());
+        assert!(g2_affine_process::<Bls12>(&[0; 95]).is_err());
+    }
+
+    #[test]
+    fn test_fp12_process_pairing() {
+        use crate::bls::{G1Affine, G2Affine};
+
+        let gt = Bls12::pairing(G1Affine::one(), G2Affine::one());
+        assert_eq!(fp12_process::<Bls12>(&fp12_to_byteblob(&gt)).unwrap(), gt);
+    }
+
+    #[test]
+    fn test_encoding_roundtrip() {
+        let mut rng = test_rng();
+
+        let params = random_params();
+        let proof = Proof::<Bls12> {
+            a: params.vk.alpha_g1,
+            b: params.vk.beta_g2,
+            c: params.vk.delta_g1,
+        };
+        let frs = (0..5).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let fp = Fq::random(&mut rng);
+
+        for &encoding in &[Encoding::LITTLE_ENDIAN, Encoding::BIG_ENDIAN] {
+            let blob = fr_vector_to_byteblob_with_encoding::<Bls12>(&frs, encoding);
+            assert_eq!(
+                groth16_primary_input_from_byteblob_with_encoding::<Bls12>(&blob, encoding)
+                    .unwrap(),
+                frs
+            );
+
+            let blob = fp_to_byteblob_with_encoding(&fp, encoding);
+            assert_eq!(
+                fp_process_with_encoding::<Bls12>(&blob, encoding).unwrap(),
+                fp
+            );
+
+            let blob = groth16_proof_to_byteblob_with_encoding(&proof, encoding);
+            assert!(
+                groth16_proof_from_byteblob_with_encoding::<Bls12>(&blob, encoding).unwrap()
+                    == proof
+            );
+
+            let blob = groth16_verifying_key_to_byteblob_with_encoding(&params.vk, encoding);
+            assert!(
+                groth16_verifying_key_from_byteblob_with_encoding::<Bls12>(&blob, encoding)
+                    .unwrap()
+                    == params.vk
+            );
+        }
+
+        // The default encoding is the one of the plain codecs for field elements.
+        assert_eq!(
+            fr_vector_to_byteblob_with_encoding::<Bls12>(&frs, Encoding::default()),
+            fr_vector_to_byteblob::<Bls12>(&frs)
+        );
+        // Big-endian points are the standard compressed encoding.
+        assert_eq!(
+            groth16_proof_to_byteblob_with_encoding(&proof, Encoding::BIG_ENDIAN),
+            groth16_proof_to_byteblob(&proof)
+        );
+    }
+
+    #[test]
+    fn test_encoding_layout() {
+        let five = Fr::from_str("5").unwrap();
+
+        let mut big = vec![0; 32];
+        big[31] = 5;
+        assert_eq!(
+            fr_to_byteblob_with_encoding::<Bls12>(&five, Encoding::BIG_ENDIAN),
+            big
+        );
+        assert_eq!(
+            &std_size_t_to_byteblob_with_encoding(5, Encoding::BIG_ENDIAN)[..],
+            &[0, 0, 0, 5]
+        );
+
+        // Little-endian limbs in big-endian limb order.
+        let mixed = Encoding {
+            byte_order: Endianness::Little,
+            limb_order: Endianness::Big,
+        };
+        let mut expected = vec![0; 32];
+        expected[24] = 5;
+        assert_eq!(
+            fr_to_byteblob_with_encoding::<Bls12>(&five, mixed),
+            expected
+        );
+        assert_eq!(
+            fr_process_with_encoding::<Bls12>(&expected, mixed).unwrap(),
+            five
+        );
+
+        assert_ne!(
+            fr_process_with_encoding::<Bls12>(&big, Encoding::LITTLE_ENDIAN).unwrap(),
+            five
+        );
+    }
+
+    #[test]
+    fn test_uncompressed_byteblob_roundtrip() {
+        let params = random_params();
+        let proof = Proof::<Bls12> {
+            a: params.vk.alpha_g1,
+            b: params.vk.beta_g2,
+            c: params.vk.delta_g1,
+        };
+
+        let blob = groth16_proof_to_byteblob_uncompressed(&proof);
+        assert_eq!(blob.len(), 2 * 96 + 192);
+        assert!(groth16_proof_from_byteblob_uncompressed::<Bls12>(&blob).unwrap() == proof);
+        assert!(
+            groth16_proof_from_byteblob_uncompressed::<Bls12>(&blob[..blob.len() - 1]).is_err()
+        );
+
+        let blob = groth16_verifying_key_to_byteblob_uncompressed(&params.vk);
+        assert_eq!(blob.len(), 3 * 96 + 3 * 192 + 4 + 3 * 96);
+        assert!(
+            groth16_verifying_key_from_byteblob_uncompressed::<Bls12>(&blob).unwrap() == params.vk
+        );
+        assert!(
+            groth16_verifying_key_from_byteblob_uncompressed::<Bls12>(&blob[..blob.len() - 1])
+                .is_err()
+        );
+
+        // A point off the curve is rejected.
+        let mut blob = groth16_proof_to_byteblob_uncompressed(&proof);
+        blob[95] ^= 1;
+        assert!(groth16_proof_from_byteblob_uncompressed::<Bls12>(&blob).is_err());
+    }
+}
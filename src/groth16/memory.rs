@@ -0,0 +1,47 @@
+use std::mem;
+
+use ff::PrimeField;
+
+use crate::bls::Engine;
+
+/// Estimates the memory in bytes of the witnesses of a batch of `num_proofs`
+/// proofs of a circuit with the given numbers of constraints, public inputs
+/// (including the constant one) and auxiliary variables, and of the vectors
+/// computed from them.
+///
+/// The estimate covers the witnesses, the FFT domains, the quotient
+/// polynomials and the exponents of the multiexps, which grow with the
+/// batch. It is not the peak memory of the process: it leaves out the
+/// parameters, which are either in memory already or read from a
+/// `MappedParameters` one query at a time with
+/// `Config::with_sequential_multiexps`, the buckets of the multiexps, which
+/// depend on the number of threads, and the buffers of the GPU kernels. See
+/// [`Config::with_max_witness_memory`](super::Config::with_max_witness_memory)
+/// to keep a batch under a budget.
+pub fn estimate_witness_memory<E: Engine>(
+    num_constraints: usize,
+    num_inputs: usize,
+    num_aux: usize,
+    num_proofs: usize,
+) -> usize {
+    // The prover adds a constraint per input.
+    let n = num_constraints + num_inputs;
+    num_proofs
+        * (witness_memory::<E>(n, num_inputs, num_aux)
+            + proving_memory::<E>(n, num_inputs, num_aux))
+}
+
+/// The memory of a synthesized witness with evaluations of length `n`.
+pub(crate) fn witness_memory<E: Engine>(n: usize, num_inputs: usize, num_aux: usize) -> usize {
+    let fr = mem::size_of::<E::Fr>();
+    (3 * n + num_inputs + num_aux) * fr
+}
+
+/// The memory the FFTs and multiexps of a witness allocate on top of it.
+pub(crate) fn proving_memory<E: Engine>(n: usize, num_inputs: usize, num_aux: usize) -> usize {
+    let fr = mem::size_of::<E::Fr>();
+    let repr = mem::size_of::<<E::Fr as PrimeField>::Repr>();
+    let d = n.next_power_of_two();
+    // The domains of a, b and c, the quotient and the exponents.
+    3 * d * fr + d * repr + (num_inputs + num_aux) * repr
+}
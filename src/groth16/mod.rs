@@ -2,12 +2,28 @@
 //!
 //! [Groth16]: https://eprint.iacr.org/2016/260
 
+pub mod aggregate;
+mod config;
+pub mod container;
+pub mod distributed;
 mod ext;
 mod generator;
+mod hex;
 mod mapped_params;
+mod memory;
 mod params;
+pub mod phase2;
+mod prepared_vk;
 mod proof;
 mod prover;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(any(test, feature = "test-utils"))]
+mod simulator;
+mod snarkjs;
+pub mod solidity;
+mod tau;
+mod transcript;
 mod verifier;
 mod verifying_key;
 
@@ -15,12 +31,19 @@ mod marshalling;
 
 mod multiscalar;
 
+pub use self::config::*;
 pub use self::ext::*;
 pub use self::generator::*;
+pub use self::hex::PublicInputs;
 pub use self::mapped_params::*;
+pub use self::memory::estimate_witness_memory;
 pub use self::params::*;
 pub use self::proof::*;
 pub use self::prover::*;
+#[cfg(any(test, feature = "test-utils"))]
+pub use self::simulator::*;
+pub use self::tau::TauPowers;
+pub use crate::transcript::{Blake2bTranscript, Transcript};
 pub use self::verifier::*;
 pub use self::verifying_key::*;
 
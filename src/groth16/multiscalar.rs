@@ -277,10 +277,9 @@ mod tests {
     use super::*;
 
     use crate::bls::{Bls12, Fr, FrRepr, G1Affine, G1Projective};
+    use crate::util_cs::test_fixtures::test_rng;
 
     use ff::Field;
-    use rand_core::SeedableRng;
-    use rand_xorshift::XorShiftRng;
 
     fn multiscalar_naive(points: &[G1Affine], scalars: &[FrRepr]) -> G1Projective {
         let mut acc = G1Projective::zero();
@@ -292,10 +291,7 @@ mod tests {
 
     #[test]
     fn test_multiscalar_single() {
-        let mut rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
+        let mut rng = test_rng();
 
         for _ in 0..50 {
             for (num_inputs, window_size) in &[(8, 4), (12, 1), (10, 1), (20, 2)] {
@@ -323,10 +319,7 @@ mod tests {
 
     #[test]
     fn test_multiscalar_par() {
-        let mut rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
+        let mut rng = test_rng();
 
         for _ in 0..50 {
             for (num_inputs, window_size) in &[(8, 4), (12, 1), (10, 1), (20, 2)] {
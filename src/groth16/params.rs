@@ -1,11 +1,15 @@
-use crate::bls::Engine;
+use crate::bls::{Bls12, Engine};
 use groupy::{CurveAffine, EncodedPoint};
 
-use crate::multiexp::SourceBuilder;
-use crate::SynthesisError;
+use crate::multicore::THREAD_POOL;
+use crate::multiexp::{PrecomputedBases, PrecomputedSource, SourceBuilder};
+use crate::util_cs::r1cs::circuit_shape_hash;
+use crate::{invalid_data, Circuit, SynthesisError};
 
+use blake2b_simd::State as Blake2b;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use memmap::{Mmap, MmapOptions};
+use rayon::prelude::*;
 use std::fs::File;
 use std::io::{self, Read, Write};
 use std::mem;
@@ -15,6 +19,21 @@ use std::sync::Arc;
 
 use super::{MappedParameters, VerifyingKey};
 
+/// Magic bytes of the format written by `Parameters::write_v2`.
+pub const PARAMS_MAGIC: [u8; 4] = *b"G16P";
+pub const PARAMS_VERSION: u16 = 2;
+/// Version of the v2 format with the shape hash of the circuit, written by
+/// `Parameters::write_v2_with_shape`.
+pub const PARAMS_VERSION_WITH_SHAPE: u16 = 3;
+
+/// Size of the BLAKE2b-512 digests of `Parameters::digest` and
+/// `VerifyingKey::digest`.
+pub const DIGEST_SIZE: usize = 64;
+
+/// Number of points `Parameters::read_from` and `Parameters::write_to` buffer
+/// at once.
+pub const STREAM_CHUNK_SIZE: usize = 1 << 16;
+
 #[derive(Clone)]
 pub struct Parameters<E: Engine> {
     pub vk: VerifyingKey<E>,
@@ -85,12 +104,12 @@ impl<E: Engine> Parameters<E> {
 
     // Quickly iterates through the parameter file, recording all
     // parameter offsets and caches the verifying key (vk) for quick
-    // access via reference.
+    // access via reference. Every offset is checked against the size of
+    // the mapping, so a truncated file fails here rather than in the prover.
     pub fn build_mapped_parameters(
         param_file_path: PathBuf,
         checked: bool,
     ) -> io::Result<MappedParameters<E>> {
-        let mut offset: usize = 0;
         let param_file = File::open(&param_file_path)?;
         let params = unsafe { MmapOptions::new().map(&param_file)? };
 
@@ -98,8 +117,13 @@ impl<E: Engine> Parameters<E> {
         let g1_len = mem::size_of::<<E::G1Affine as CurveAffine>::Uncompressed>();
         let g2_len = mem::size_of::<<E::G2Affine as CurveAffine>::Uncompressed>();
 
+        let truncated =
+            || io::Error::new(io::ErrorKind::UnexpectedEof, "parameters file is truncated");
+
         let read_length = |params: &Mmap, offset: &mut usize| -> Result<usize, std::io::Error> {
-            let mut raw_len = &params[*offset..*offset + u32_len];
+            let mut raw_len = params
+                .get(*offset..*offset + u32_len)
+                .ok_or_else(truncated)?;
             *offset += u32_len;
 
             match raw_len.read_u32::<BigEndian>() {
@@ -114,6 +138,9 @@ impl<E: Engine> Parameters<E> {
                            range_len: usize|
          -> Result<(), std::io::Error> {
             let len = read_length(&params, &mut *offset)?;
+            if (params.len() - *offset) / range_len < len {
+                return Err(truncated());
+            }
             for _ in 0..len {
                 (*param).push(Range {
                     start: *offset,
@@ -125,7 +152,11 @@ impl<E: Engine> Parameters<E> {
             Ok(())
         };
 
-        let vk = VerifyingKey::<E>::read_mmap(&params, &mut offset)?;
+        // The verifying key is read from a slice, which fails with an early
+        // EOF instead of indexing past the end of the mapping.
+        let mut vk_reader = &params[..];
+        let vk = VerifyingKey::<E>::read(&mut vk_reader)?;
+        let mut offset = params.len() - vk_reader.len();
 
         let mut h = vec![];
         let mut l = vec![];
@@ -377,6 +408,443 @@ impl<E: Engine> Parameters<E> {
             b_g2: Arc::new(b_g2),
         })
     }
+
+    /// BLAKE2b-512 digest of the encoding written by `write`. Parameters
+    /// loaded on different machines are byte-identical exactly when their
+    /// digests are equal. The encoding is hashed as it is produced, so no
+    /// copy of it is held in memory.
+    pub fn digest(&self) -> [u8; DIGEST_SIZE] {
+        let mut hasher = Blake2b::new();
+        self.write_to(&mut hasher)
+            .expect("writing to a hasher never fails");
+
+        let mut digest = [0u8; DIGEST_SIZE];
+        digest.copy_from_slice(hasher.finalize().as_bytes());
+        digest
+    }
+
+    /// Reads parameters like `read` and checks that the bytes read have the
+    /// digest `expected`, as returned by `digest`. Bytes after the
+    /// parameters are not read.
+    pub fn read_with_digest<R: Read>(
+        reader: R,
+        checked: bool,
+        expected: &[u8; DIGEST_SIZE],
+    ) -> io::Result<Self> {
+        let mut reader = HashReader {
+            reader,
+            hasher: Blake2b::new(),
+        };
+        let params = Self::read(&mut reader, checked)?;
+
+        check_digest(&reader.hasher, expected)?;
+        Ok(params)
+    }
+}
+
+impl<E: Engine> Parameters<E> {
+    /// Writes the parameters in the v2 format.
+    ///
+    /// Layout, all integers big-endian:
+    ///
+    /// ```text
+    /// magic     4 bytes   "G16P"
+    /// version   u16
+    /// lengths   6 x u64   vk, h, l, a, b_g1 and b_g2 sections, in bytes
+    /// shape     32 bytes  only in version 3, see `write_v2_with_shape`
+    /// vk                  as written by `VerifyingKey::write`
+    /// h, l, a, b_g1, b_g2 uncompressed points, without a count
+    /// digest    64 bytes  BLAKE2b-512 of everything above
+    /// ```
+    pub fn write_v2<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_v2_inner(writer, None)
+    }
+
+    /// Writes the parameters in the v2 format, version 3, with the
+    /// `R1cs::shape_hash` of the circuit they were generated for. Provers
+    /// load them with `read_v2_for_circuit` to find out at startup that the
+    /// circuit changed, rather than by producing proofs that do not verify.
+    pub fn write_v2_with_shape<W: Write>(&self, writer: W, shape: &[u8; 32]) -> io::Result<()> {
+        self.write_v2_inner(writer, Some(shape))
+    }
+
+    fn write_v2_inner<W: Write>(&self, writer: W, shape: Option<&[u8; 32]>) -> io::Result<()> {
+        let mut vk = vec![];
+        self.vk.write(&mut vk)?;
+
+        let g1_size = <E::G1Affine as CurveAffine>::Uncompressed::size() as u64;
+        let g2_size = <E::G2Affine as CurveAffine>::Uncompressed::size() as u64;
+        let lengths = [
+            vk.len() as u64,
+            self.h.len() as u64 * g1_size,
+            self.l.len() as u64 * g1_size,
+            self.a.len() as u64 * g1_size,
+            self.b_g1.len() as u64 * g1_size,
+            self.b_g2.len() as u64 * g2_size,
+        ];
+
+        let mut writer = HashWriter {
+            writer,
+            hasher: Blake2b::new(),
+        };
+        writer.write_all(&PARAMS_MAGIC)?;
+        match shape {
+            Some(_) => writer.write_u16::<BigEndian>(PARAMS_VERSION_WITH_SHAPE)?,
+            None => writer.write_u16::<BigEndian>(PARAMS_VERSION)?,
+        }
+        for len in &lengths {
+            writer.write_u64::<BigEndian>(*len)?;
+        }
+        if let Some(shape) = shape {
+            writer.write_all(shape)?;
+        }
+
+        writer.write_all(&vk)?;
+        for points in &[&self.h, &self.l, &self.a, &self.b_g1] {
+            for g in &points[..] {
+                writer.write_all(g.into_uncompressed().as_ref())?;
+            }
+        }
+        for g in &self.b_g2[..] {
+            writer.write_all(g.into_uncompressed().as_ref())?;
+        }
+
+        let digest = writer.hasher.finalize();
+        writer.writer.write_all(digest.as_bytes())
+    }
+
+    /// Reads parameters written by `write_v2`.
+    ///
+    /// A truncated file fails with `UnexpectedEof` naming the section it ends
+    /// in, and any corruption not caught while decoding the points fails the
+    /// digest check. Files without the v2 header have to be read with `read`.
+    /// The shape hash of version 3 files is ignored.
+    pub fn read_v2<R: Read>(reader: R, checked: bool) -> io::Result<Self> {
+        Self::read_v2_with_shape(reader, checked).map(|(params, _)| params)
+    }
+
+    /// Reads parameters written by `write_v2` or `write_v2_with_shape`, and
+    /// the shape hash of the latter.
+    pub fn read_v2_with_shape<R: Read>(
+        reader: R,
+        checked: bool,
+    ) -> io::Result<(Self, Option<[u8; 32]>)> {
+        let mut reader = HashReader {
+            reader,
+            hasher: Blake2b::new(),
+        };
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != PARAMS_MAGIC {
+            return Err(invalid_data("not a v2 parameters file"));
+        }
+        let version = reader.read_u16::<BigEndian>()?;
+        if version != PARAMS_VERSION && version != PARAMS_VERSION_WITH_SHAPE {
+            return Err(invalid_data(&format!(
+                "unsupported parameters version {}",
+                version
+            )));
+        }
+        let mut lengths = [0u64; 6];
+        for len in lengths.iter_mut() {
+            *len = reader.read_u64::<BigEndian>()?;
+        }
+        let shape = if version == PARAMS_VERSION_WITH_SHAPE {
+            let mut shape = [0u8; 32];
+            reader
+                .read_exact(&mut shape)
+                .map_err(|e| truncated(e, "shape"))?;
+            Some(shape)
+        } else {
+            None
+        };
+
+        let vk = read_section(&mut reader, "vk", lengths[0], |r| VerifyingKey::read(r))?;
+        let h = read_section(&mut reader, "h", lengths[1], |r| read_points(r, checked))?;
+        let l = read_section(&mut reader, "l", lengths[2], |r| read_points(r, checked))?;
+        let a = read_section(&mut reader, "a", lengths[3], |r| read_points(r, checked))?;
+        let b_g1 = read_section(&mut reader, "b_g1", lengths[4], |r| read_points(r, checked))?;
+        let b_g2 = read_section(&mut reader, "b_g2", lengths[5], |r| read_points(r, checked))?;
+
+        let digest = reader.hasher.finalize();
+        let mut expected = [0u8; DIGEST_SIZE];
+        reader
+            .reader
+            .read_exact(&mut expected)
+            .map_err(|e| truncated(e, "digest"))?;
+        if digest.as_bytes() != &expected[..] {
+            return Err(invalid_data("parameters digest mismatch"));
+        }
+
+        let params = Parameters {
+            vk,
+            h: Arc::new(h),
+            l: Arc::new(l),
+            a: Arc::new(a),
+            b_g1: Arc::new(b_g1),
+            b_g2: Arc::new(b_g2),
+        };
+
+        Ok((params, shape))
+    }
+
+    /// Reads parameters written by `write_v2_with_shape` and checks that
+    /// they were generated for `circuit`, which is synthesized without
+    /// assignments to compute its shape hash. Files without a shape hash
+    /// are rejected.
+    pub fn read_v2_for_circuit<R: Read, C: Circuit<E>>(
+        reader: R,
+        checked: bool,
+        circuit: C,
+    ) -> io::Result<Self> {
+        let expected =
+            circuit_shape_hash(circuit).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+        match Self::read_v2_with_shape(reader, checked)? {
+            (params, Some(shape)) if shape == expected => Ok(params),
+            (_, Some(_)) => Err(invalid_data(
+                "parameters were generated for a different circuit",
+            )),
+            (_, None) => Err(invalid_data("parameters have no circuit shape hash")),
+        }
+    }
+}
+
+impl<E: Engine> Parameters<E> {
+    /// Writes the parameters in the layout of `write`, encoding the points in
+    /// chunks of `STREAM_CHUNK_SIZE` so that `writer` sees few large writes.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.vk.write(&mut writer)?;
+
+        write_point_chunks(&mut writer, &self.h)?;
+        write_point_chunks(&mut writer, &self.l)?;
+        write_point_chunks(&mut writer, &self.a)?;
+        write_point_chunks(&mut writer, &self.b_g1)?;
+        write_point_chunks(&mut writer, &self.b_g2)?;
+
+        Ok(())
+    }
+
+    /// Reads parameters in the layout of `write`.
+    ///
+    /// Unlike `read`, the points are read in chunks of `STREAM_CHUNK_SIZE`,
+    /// so the only memory used besides the parameters themselves is one
+    /// chunk buffer. With `parallel` every chunk is decoded, and checked if
+    /// `checked` is set, on the thread pool as it comes in.
+    pub fn read_from<R: Read>(mut reader: R, checked: bool, parallel: bool) -> io::Result<Self> {
+        let vk = VerifyingKey::<E>::read(&mut reader)?;
+
+        let h = read_point_chunks(&mut reader, checked, parallel)?;
+        let l = read_point_chunks(&mut reader, checked, parallel)?;
+        let a = read_point_chunks(&mut reader, checked, parallel)?;
+        let b_g1 = read_point_chunks(&mut reader, checked, parallel)?;
+        let b_g2 = read_point_chunks(&mut reader, checked, parallel)?;
+
+        Ok(Parameters {
+            vk,
+            h: Arc::new(h),
+            l: Arc::new(l),
+            a: Arc::new(a),
+            b_g1: Arc::new(b_g1),
+            b_g2: Arc::new(b_g2),
+        })
+    }
+}
+
+// Reads a section of `len` bytes with `f`, which has to consume all of it.
+fn read_section<R: Read, T, F>(reader: &mut R, name: &str, len: u64, f: F) -> io::Result<T>
+where
+    F: FnOnce(&mut io::Take<&mut R>) -> io::Result<T>,
+{
+    let mut section = reader.take(len);
+    let value = f(&mut section).map_err(|e| truncated(e, name))?;
+    if section.limit() != 0 {
+        return Err(invalid_data(&format!(
+            "section {} has trailing bytes",
+            name
+        )));
+    }
+
+    Ok(value)
+}
+
+// Reads uncompressed points until the end of `reader`.
+fn read_points<G: CurveAffine, R: Read>(
+    reader: &mut io::Take<R>,
+    checked: bool,
+) -> io::Result<Vec<G>> {
+    let size = G::Uncompressed::size() as u64;
+    if reader.limit() % size != 0 {
+        return Err(invalid_data(
+            "section length is not a multiple of the point size",
+        ));
+    }
+
+    let mut points = vec![];
+    while reader.limit() != 0 {
+        let mut repr = G::Uncompressed::empty();
+        reader.read_exact(repr.as_mut())?;
+        points.push(decode_point(repr.as_ref(), checked)?);
+    }
+
+    Ok(points)
+}
+
+// Reads a point count and the points in chunks of `STREAM_CHUNK_SIZE`.
+fn read_point_chunks<G: CurveAffine, R: Read>(
+    reader: &mut R,
+    checked: bool,
+    parallel: bool,
+) -> io::Result<Vec<G>> {
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    let size = G::Uncompressed::size();
+
+    let mut points = Vec::new();
+    let mut buf = vec![0u8; size * len.min(STREAM_CHUNK_SIZE)];
+    let mut remaining = len;
+    while remaining != 0 {
+        let n = remaining.min(STREAM_CHUNK_SIZE);
+        let chunk = &mut buf[..n * size];
+        reader.read_exact(chunk)?;
+
+        if parallel {
+            let decoded = THREAD_POOL.install(|| {
+                chunk
+                    .par_chunks(size)
+                    .map(|bytes| decode_point(bytes, checked))
+                    .collect::<io::Result<Vec<G>>>()
+            })?;
+            points.extend(decoded);
+        } else {
+            for bytes in chunk.chunks(size) {
+                points.push(decode_point(bytes, checked)?);
+            }
+        }
+        remaining -= n;
+    }
+
+    Ok(points)
+}
+
+// Writes a point count and the points in chunks of `STREAM_CHUNK_SIZE`.
+fn write_point_chunks<G: CurveAffine, W: Write>(writer: &mut W, points: &[G]) -> io::Result<()> {
+    writer.write_u32::<BigEndian>(points.len() as u32)?;
+
+    let size = G::Uncompressed::size();
+    let mut buf = Vec::with_capacity(size * points.len().min(STREAM_CHUNK_SIZE));
+    for chunk in points.chunks(STREAM_CHUNK_SIZE) {
+        buf.clear();
+        for g in chunk {
+            buf.extend_from_slice(g.into_uncompressed().as_ref());
+        }
+        writer.write_all(&buf)?;
+    }
+
+    Ok(())
+}
+
+fn decode_point<G: CurveAffine>(bytes: &[u8], checked: bool) -> io::Result<G> {
+    let mut repr = G::Uncompressed::empty();
+    repr.as_mut().copy_from_slice(bytes);
+
+    let g = if checked {
+        repr.into_affine()
+    } else {
+        repr.into_affine_unchecked()
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if g.is_zero() {
+        return Err(invalid_data("point at infinity"));
+    }
+
+    Ok(g)
+}
+
+fn truncated(e: io::Error, section: &str) -> io::Error {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("parameters file is truncated in section {}", section),
+        )
+    } else {
+        e
+    }
+}
+
+fn check_digest(hasher: &Blake2b, expected: &[u8; DIGEST_SIZE]) -> io::Result<()> {
+    let digest = hasher.finalize();
+    if digest.as_bytes() != &expected[..] {
+        return Err(invalid_data(&format!(
+            "parameters digest mismatch: got {}",
+            digest.to_hex()
+        )));
+    }
+
+    Ok(())
+}
+
+impl Parameters<Bls12> {
+    /// Reads a Zcash Sprout or Sapling params file.
+    ///
+    /// These files use the layout of `write` and have no header of their own;
+    /// they are identified by the BLAKE2b-512 digest of their contents, which
+    /// Zcash publishes next to them. The digest is computed while reading and
+    /// compared to `expected_hash`, given in hex. As the digest covers every
+    /// byte, the points are not checked individually.
+    pub fn read_sapling_format<R: Read>(reader: R, expected_hash: &str) -> io::Result<Self> {
+        let mut reader = HashReader {
+            reader,
+            hasher: Blake2b::new(),
+        };
+        let params = Self::read(&mut reader, false)?;
+
+        // Hash trailing bytes as well, the digest covers the whole file.
+        io::copy(&mut reader, &mut io::sink())?;
+
+        let hash = reader.hasher.finalize().to_hex();
+        if !hash.eq_ignore_ascii_case(expected_hash) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "params file hash mismatch: expected {}, got {}",
+                    expected_hash, hash
+                ),
+            ));
+        }
+
+        Ok(params)
+    }
+}
+
+struct HashReader<R: Read> {
+    reader: R,
+    hasher: Blake2b,
+}
+
+impl<R: Read> Read for HashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+struct HashWriter<W: Write> {
+    writer: W,
+    hasher: Blake2b,
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
 }
 
 pub trait ParameterSource<E: Engine>: Send + Sync {
@@ -443,3 +911,406 @@ impl<'a, E: Engine> ParameterSource<E> for &'a Parameters<E> {
         Ok(((self.b_g2.clone(), 0), (self.b_g2.clone(), num_inputs)))
     }
 }
+
+/// Parameters together with precomputed window tables of all their queries,
+/// see [`Parameters::precompute`].
+pub struct PrecomputedParameters<E: Engine> {
+    pub params: Parameters<E>,
+    h: Arc<PrecomputedBases<E::G1Affine>>,
+    l: Arc<PrecomputedBases<E::G1Affine>>,
+    a: Arc<PrecomputedBases<E::G1Affine>>,
+    b_g1: Arc<PrecomputedBases<E::G1Affine>>,
+    b_g2: Arc<PrecomputedBases<E::G2Affine>>,
+}
+
+impl<E: Engine> Parameters<E> {
+    /// Precomputes window tables of `c` bits for the fixed bases of the
+    /// queries, which the CPU multiexps of every later proof use instead of
+    /// doubling between windows.
+    ///
+    /// The tables hold about `256 / c` points per base, so they take that
+    /// many times the memory of the parameters. GPU multiexps do not use them.
+    pub fn precompute(self, c: u32) -> PrecomputedParameters<E> {
+        PrecomputedParameters {
+            h: Arc::new(PrecomputedBases::new(&self.h, c)),
+            l: Arc::new(PrecomputedBases::new(&self.l, c)),
+            a: Arc::new(PrecomputedBases::new(&self.a, c)),
+            b_g1: Arc::new(PrecomputedBases::new(&self.b_g1, c)),
+            b_g2: Arc::new(PrecomputedBases::new(&self.b_g2, c)),
+            params: self,
+        }
+    }
+}
+
+impl<'a, E: Engine> ParameterSource<E> for &'a PrecomputedParameters<E> {
+    type G1Builder = PrecomputedSource<E::G1Affine>;
+    type G2Builder = PrecomputedSource<E::G2Affine>;
+
+    fn get_vk(&self, _: usize) -> Result<&VerifyingKey<E>, SynthesisError> {
+        Ok(&self.params.vk)
+    }
+
+    fn get_h(&self, _: usize) -> Result<Self::G1Builder, SynthesisError> {
+        Ok(source(&self.params.h, &self.h, 0))
+    }
+
+    fn get_l(&self, _: usize) -> Result<Self::G1Builder, SynthesisError> {
+        Ok(source(&self.params.l, &self.l, 0))
+    }
+
+    fn get_a(
+        &self,
+        num_inputs: usize,
+        _: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        Ok((
+            source(&self.params.a, &self.a, 0),
+            source(&self.params.a, &self.a, num_inputs),
+        ))
+    }
+
+    fn get_b_g1(
+        &self,
+        num_inputs: usize,
+        _: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        Ok((
+            source(&self.params.b_g1, &self.b_g1, 0),
+            source(&self.params.b_g1, &self.b_g1, num_inputs),
+        ))
+    }
+
+    fn get_b_g2(
+        &self,
+        num_inputs: usize,
+        _: usize,
+    ) -> Result<(Self::G2Builder, Self::G2Builder), SynthesisError> {
+        Ok((
+            source(&self.params.b_g2, &self.b_g2, 0),
+            source(&self.params.b_g2, &self.b_g2, num_inputs),
+        ))
+    }
+}
+
+fn source<G: CurveAffine>(
+    bases: &Arc<Vec<G>>,
+    tables: &Arc<PrecomputedBases<G>>,
+    skip: usize,
+) -> PrecomputedSource<G> {
+    PrecomputedSource {
+        bases: bases.clone(),
+        tables: tables.clone(),
+        skip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::groth16::generate_random_parameters;
+    use crate::util_cs::test_fixtures::{test_rng, MulCircuit};
+    use crate::{Circuit, ConstraintSystem};
+
+    #[test]
+    fn test_precomputed_parameters() {
+        use crate::bls::Fr;
+        use crate::groth16::{create_random_proof, prepare_verifying_key, verify_proof};
+        use ff::Field;
+
+        struct Witness(Fr, Fr);
+
+        impl Circuit<Bls12> for Witness {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let a = cs.alloc(|| "a", || Ok(self.0))?;
+                let b = cs.alloc(|| "b", || Ok(self.1))?;
+                let c = cs.alloc_input(
+                    || "c",
+                    || {
+                        let mut c = self.0;
+                        c.mul_assign(&self.1);
+                        Ok(c)
+                    },
+                )?;
+                cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+                Ok(())
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        let params = params.precompute(4);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let proof = create_random_proof(Witness(a, b), &params, &mut rng).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
+    }
+
+    #[test]
+    fn test_read_sapling_format() {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+        let hash = blake2b_simd::blake2b(&bytes).to_hex();
+
+        let read = Parameters::read_sapling_format(&bytes[..], &hash).unwrap();
+        assert!(read == params);
+        let read = Parameters::read_sapling_format(&bytes[..], &hash.to_uppercase()).unwrap();
+        assert!(read == params);
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Parameters::read_sapling_format(&trailing[..], &hash).is_err());
+        assert!(Parameters::read_sapling_format(&bytes[..bytes.len() - 1], &hash).is_err());
+    }
+
+    #[test]
+    fn test_digest() {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+        let digest = params.digest();
+        assert_eq!(&digest[..], blake2b_simd::blake2b(&bytes).as_bytes());
+
+        let read = Parameters::read_with_digest(&bytes[..], true, &digest).unwrap();
+        assert!(read == params);
+        assert_eq!(&read.digest()[..], &digest[..]);
+
+        let mut wrong = digest;
+        wrong[0] ^= 1;
+        let err = Parameters::<Bls12>::read_with_digest(&bytes[..], false, &wrong)
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("parameters digest mismatch"));
+
+        let other =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        assert_ne!(&other.digest()[..], &digest[..]);
+
+        let mut vk_bytes = vec![];
+        params.vk.write(&mut vk_bytes).unwrap();
+        let vk_digest = params.vk.digest();
+        assert_eq!(&vk_digest[..], blake2b_simd::blake2b(&vk_bytes).as_bytes());
+        assert!(VerifyingKey::read_with_digest(&vk_bytes[..], &vk_digest).unwrap() == params.vk);
+        assert!(
+            VerifyingKey::<Bls12>::read_with_digest(&vk_bytes[..], &other.vk.digest()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_v2_format() {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let mut bytes = vec![];
+        params.write_v2(&mut bytes).unwrap();
+        assert_eq!(&bytes[..4], b"G16P");
+
+        let read = Parameters::<Bls12>::read_v2(&bytes[..], true).unwrap();
+        assert!(read == params);
+        let read = Parameters::<Bls12>::read_v2(&bytes[..], false).unwrap();
+        assert!(read == params);
+
+        // The legacy format is still read by `read` only.
+        let mut legacy = vec![];
+        params.write(&mut legacy).unwrap();
+        assert!(Parameters::<Bls12>::read(&legacy[..], true).unwrap() == params);
+        assert!(Parameters::<Bls12>::read_v2(&legacy[..], true).is_err());
+        assert!(Parameters::<Bls12>::read(&bytes[..], true).is_err());
+
+        let err = Parameters::<Bls12>::read_v2(&bytes[..bytes.len() - 200], true)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(err.to_string().contains("b_g2"));
+
+        // Flipping a bit of a coordinate is only caught by the digest when
+        // the points are not checked.
+        let mut corrupted = bytes.clone();
+        let len = corrupted.len();
+        corrupted[len - DIGEST_SIZE - 1] ^= 1;
+        let err = Parameters::<Bls12>::read_v2(&corrupted[..], false)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "parameters digest mismatch");
+        assert!(Parameters::<Bls12>::read_v2(&corrupted[..], true).is_err());
+    }
+
+    #[test]
+    fn test_v2_format_with_shape() {
+        let mut rng = test_rng();
+
+        struct SquareCircuit;
+
+        impl Circuit<Bls12> for SquareCircuit {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let a = cs.alloc(|| "a", || Err(SynthesisError::AssignmentMissing))?;
+                let b = cs.alloc(|| "b", || Err(SynthesisError::AssignmentMissing))?;
+                let c = cs.alloc_input(|| "c", || Err(SynthesisError::AssignmentMissing))?;
+                cs.enforce(|| "a*a=c", |lc| lc + a, |lc| lc + a, |lc| lc + c);
+                cs.enforce(|| "b=a", |lc| lc + b, |lc| lc + CS::one(), |lc| lc + a);
+
+                Ok(())
+            }
+        }
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let shape = circuit_shape_hash(MulCircuit { a: None, b: None }).unwrap();
+        let mut bytes = vec![];
+        params.write_v2_with_shape(&mut bytes, &shape).unwrap();
+        assert_eq!(&bytes[4..6], &PARAMS_VERSION_WITH_SHAPE.to_be_bytes());
+
+        let (read, read_shape) = Parameters::<Bls12>::read_v2_with_shape(&bytes[..], true).unwrap();
+        assert!(read == params);
+        assert_eq!(read_shape, Some(shape));
+        assert!(Parameters::<Bls12>::read_v2(&bytes[..], true).unwrap() == params);
+        assert!(
+            Parameters::read_v2_for_circuit(&bytes[..], true, MulCircuit { a: None, b: None })
+                .unwrap()
+                == params
+        );
+
+        let err = Parameters::read_v2_for_circuit(&bytes[..], true, SquareCircuit)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "parameters were generated for a different circuit"
+        );
+
+        // Files without a shape hash cannot be checked.
+        let mut unbound = vec![];
+        params.write_v2(&mut unbound).unwrap();
+        assert_eq!(
+            Parameters::<Bls12>::read_v2_with_shape(&unbound[..], true)
+                .unwrap()
+                .1,
+            None
+        );
+        assert!(Parameters::read_v2_for_circuit(
+            &unbound[..],
+            true,
+            MulCircuit { a: None, b: None }
+        )
+        .is_err());
+
+        // The digest covers the shape hash.
+        let mut corrupted = bytes.clone();
+        corrupted[4 + 2 + 6 * 8] ^= 1;
+        assert!(Parameters::<Bls12>::read_v2(&corrupted[..], true).is_err());
+    }
+
+    #[test]
+    fn test_streaming_read_write() {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+
+        let mut streamed = vec![];
+        params.write_to(&mut streamed).unwrap();
+        assert_eq!(streamed, bytes);
+
+        for &parallel in &[false, true] {
+            for &checked in &[false, true] {
+                let read = Parameters::<Bls12>::read_from(&bytes[..], checked, parallel).unwrap();
+                assert!(read == params);
+            }
+
+            assert!(
+                Parameters::<Bls12>::read_from(&bytes[..bytes.len() - 1], true, parallel).is_err()
+            );
+            let mut corrupted = bytes.clone();
+            let len = corrupted.len();
+            corrupted[len - 1] ^= 1;
+            assert!(Parameters::<Bls12>::read_from(&corrupted[..], true, parallel).is_err());
+        }
+    }
+
+    #[test]
+    fn test_mapped_parameters() {
+        use crate::groth16::{create_random_proof, prepare_verifying_key, verify_proof};
+        use ff::{Field, PrimeField};
+
+        struct MulWitness(u64, u64);
+
+        impl Circuit<Bls12> for MulWitness {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let fr = |v: u64| crate::bls::Fr::from_str(&v.to_string()).unwrap();
+                let a = cs.alloc(|| "a", || Ok(fr(self.0)))?;
+                let b = cs.alloc(|| "b", || Ok(fr(self.1)))?;
+                let c = cs.alloc_input(|| "c", || Ok(fr(self.0 * self.1)))?;
+                cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+                Ok(())
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+
+        let path = std::env::temp_dir().join(format!(
+            "crusty3_zk-mapped-params-{}.params",
+            std::process::id()
+        ));
+        std::fs::write(&path, &bytes).unwrap();
+        let mapped = Parameters::<Bls12>::build_mapped_parameters(path.clone(), true).unwrap();
+        assert!(mapped.vk == params.vk);
+
+        let proof = create_random_proof(MulWitness(3, 4), &mapped, &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        let twelve = crate::bls::Fr::from_str("12").unwrap();
+        assert!(verify_proof(&pvk, &proof, &[twelve]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[crate::bls::Fr::one()]).unwrap());
+
+        // Truncated files are rejected when mapping, not when proving.
+        for &len in &[10, bytes.len() - 1] {
+            std::fs::write(&path, &bytes[..len]).unwrap();
+            let err = Parameters::<Bls12>::build_mapped_parameters(path.clone(), true)
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
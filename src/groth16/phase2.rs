@@ -0,0 +1,602 @@
+//! Phase 2 of a multi-party computation of Groth16 parameters.
+//!
+//! Phase 1, a powers of tau ceremony, fixes the secrets `tau`, `alpha` and
+//! `beta` for all circuits up to some size. Phase 2 specializes the
+//! parameters to a circuit: starting from parameters computed from phase 1,
+//! each participant multiplies `delta` by a secret of their own and divides
+//! the `h` and `l` queries by it. The result is secure as long as one
+//! participant destroyed their secret.
+//!
+//! Every contribution comes with a `PublicKey` that proves knowledge of the
+//! secret and is bound to the transcript of the ceremony so far: the hash of
+//! the parameters before the first contribution, chained with the hashes of
+//! the public keys of all earlier contributions, as in the Zcash phase 2
+//! tool. A contribution therefore cannot be reordered or replayed on top of
+//! other ones.
+//! `MPCParameters::contribute` returns the hash of the public key, which the
+//! participant publishes so that anyone can find their contribution among
+//! the ones `MPCParameters::verify` returns for the final parameters.
+
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use blake2b_simd::State as Blake2b;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective, EncodedPoint};
+use rand_core::RngCore;
+use rayon::prelude::*;
+
+use super::Parameters;
+use crate::bls::Engine;
+use crate::transcript::{Blake2bTranscript, Transcript, TranscriptRng};
+use crate::util_cs::r1cs::circuit_shape_hash;
+use crate::{Circuit, SynthesisError};
+
+const HASH_SIZE: usize = 64;
+
+/// Why a contribution was rejected.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Phase2Error {
+    #[error("the parameters are for a different circuit")]
+    ShapeMismatch,
+    #[error("the contributions do not extend the previous ones")]
+    NotExtending,
+    #[error("expected exactly one new contribution")]
+    NotOneContribution,
+    #[error("{0} changed")]
+    Changed(&'static str),
+    #[error("contribution {0} was not made on the previous contributions")]
+    WrongTranscript(usize),
+    #[error("contribution {0} does not prove knowledge of its secret")]
+    InvalidProofOfKnowledge(usize),
+    #[error("{0} is not consistent with the contributions")]
+    Inconsistent(&'static str),
+}
+
+/// The public part of a contribution.
+pub struct PublicKey<E: Engine> {
+    /// `delta` in G1 after the contribution.
+    pub delta_after: E::G1Affine,
+    /// A random point `s` and `s` times the secret.
+    pub s: E::G1Affine,
+    pub s_delta: E::G1Affine,
+    /// A point `r` derived from `transcript`, `s` and `s_delta`, times the
+    /// secret.
+    pub r_delta: E::G2Affine,
+    /// Transcript of the ceremony before the contribution: the hash of the
+    /// parameters for the first contribution, then the hash of the previous
+    /// transcript followed by the hash of the previous public key.
+    pub transcript: [u8; HASH_SIZE],
+}
+
+impl<E: Engine> Clone for PublicKey<E> {
+    fn clone(&self) -> Self {
+        PublicKey {
+            delta_after: self.delta_after,
+            s: self.s,
+            s_delta: self.s_delta,
+            r_delta: self.r_delta,
+            transcript: self.transcript,
+        }
+    }
+}
+
+impl<E: Engine> PublicKey<E> {
+    /// BLAKE2b digest of the encoding written by `write`, identifying the
+    /// contribution.
+    pub fn hash(&self) -> [u8; HASH_SIZE] {
+        let mut hasher = Blake2b::new();
+        self.write(&mut hasher)
+            .expect("writing to a hasher never fails");
+
+        let mut hash = [0u8; HASH_SIZE];
+        hash.copy_from_slice(hasher.finalize().as_bytes());
+        hash
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.delta_after.into_uncompressed().as_ref())?;
+        writer.write_all(self.s.into_uncompressed().as_ref())?;
+        writer.write_all(self.s_delta.into_uncompressed().as_ref())?;
+        writer.write_all(self.r_delta.into_uncompressed().as_ref())?;
+        writer.write_all(&self.transcript)?;
+
+        Ok(())
+    }
+
+    /// Reads a public key, checking that the points are on the curve, in the
+    /// prime-order subgroup and not at infinity.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut g1_repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
+        let mut g2_repr = <E::G2Affine as CurveAffine>::Uncompressed::empty();
+
+        let mut g1 = |reader: &mut R| -> io::Result<E::G1Affine> {
+            reader.read_exact(g1_repr.as_mut())?;
+            let point = g1_repr
+                .into_affine()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if point.is_zero() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ));
+            }
+            Ok(point)
+        };
+        let delta_after = g1(&mut reader)?;
+        let s = g1(&mut reader)?;
+        let s_delta = g1(&mut reader)?;
+
+        reader.read_exact(g2_repr.as_mut())?;
+        let r_delta = g2_repr
+            .into_affine()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if r_delta.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "point at infinity",
+            ));
+        }
+
+        let mut transcript = [0u8; HASH_SIZE];
+        reader.read_exact(&mut transcript)?;
+
+        Ok(PublicKey {
+            delta_after,
+            s,
+            s_delta,
+            r_delta,
+            transcript,
+        })
+    }
+}
+
+/// Parameters of a circuit in phase 2, with the contributions made so far.
+pub struct MPCParameters<E: Engine> {
+    params: Parameters<E>,
+    shape: [u8; 32],
+    contributions: Vec<PublicKey<E>>,
+}
+
+impl<E: Engine> Clone for MPCParameters<E> {
+    fn clone(&self) -> Self {
+        MPCParameters {
+            params: self.params.clone(),
+            shape: self.shape,
+            contributions: self.contributions.clone(),
+        }
+    }
+}
+
+impl<E: Engine> MPCParameters<E> {
+    /// Starts phase 2 for `circuit` from `params`, which have to be computed
+    /// from a phase 1 transcript. Whoever generated parameters with
+    /// `generate_random_parameters` knows all their secrets.
+    pub fn new<C: Circuit<E>>(params: Parameters<E>, circuit: C) -> Result<Self, SynthesisError> {
+        Ok(MPCParameters {
+            params,
+            shape: circuit_shape_hash(circuit)?,
+            contributions: vec![],
+        })
+    }
+
+    pub fn params(&self) -> &Parameters<E> {
+        &self.params
+    }
+
+    pub fn into_params(self) -> Parameters<E> {
+        self.params
+    }
+
+    /// The shape hash of the circuit, see `R1cs::shape_hash`.
+    pub fn shape(&self) -> &[u8; 32] {
+        &self.shape
+    }
+
+    pub fn contributions(&self) -> &[PublicKey<E>] {
+        &self.contributions
+    }
+
+    /// BLAKE2b digest of the encoding written by `write`.
+    pub fn hash(&self) -> [u8; HASH_SIZE] {
+        let mut hasher = Blake2b::new();
+        self.write(&mut hasher)
+            .expect("writing to a hasher never fails");
+
+        let mut hash = [0u8; HASH_SIZE];
+        hash.copy_from_slice(hasher.finalize().as_bytes());
+        hash
+    }
+
+    /// Contributes a secret from `rng` and returns the hash of the public
+    /// key of the contribution. The secret is dropped at the end of the call.
+    pub fn contribute<R: RngCore>(&mut self, rng: &mut R) -> [u8; HASH_SIZE] {
+        let transcript = self.next_transcript();
+        self.contribute_on(transcript, rng)
+    }
+
+    // The transcript the next contribution has to be made on.
+    fn next_transcript(&self) -> [u8; HASH_SIZE] {
+        match self.contributions.last() {
+            Some(last) => chain_transcript(&last.transcript, &last.hash()),
+            None => self.hash(),
+        }
+    }
+
+    fn contribute_on<R: RngCore>(
+        &mut self,
+        transcript: [u8; HASH_SIZE],
+        rng: &mut R,
+    ) -> [u8; HASH_SIZE] {
+        let delta = loop {
+            let delta = E::Fr::random(rng);
+            if !delta.is_zero() {
+                break delta;
+            }
+        };
+        let delta_inv = delta.inverse().expect("delta is not zero");
+
+        let s = E::G1::random(rng).into_affine();
+        let s_delta = s.mul(delta.into_repr()).into_affine();
+        let r = hash_to_g2::<E>(&transcript, &s, &s_delta);
+        let r_delta = r.mul(delta.into_repr()).into_affine();
+
+        let vk = &mut self.params.vk;
+        vk.delta_g1 = vk.delta_g1.mul(delta.into_repr()).into_affine();
+        vk.delta_g2 = vk.delta_g2.mul(delta.into_repr()).into_affine();
+        self.params.h = Arc::new(scale(&self.params.h, delta_inv));
+        self.params.l = Arc::new(scale(&self.params.l, delta_inv));
+
+        let key = PublicKey {
+            delta_after: self.params.vk.delta_g1,
+            s,
+            s_delta,
+            r_delta,
+            transcript,
+        };
+        let hash = key.hash();
+        self.contributions.push(key);
+
+        hash
+    }
+
+    /// Checks that these parameters come from `initial` through the
+    /// contributions added since, and returns their hashes.
+    pub fn verify(&self, initial: &Self) -> Result<Vec<[u8; HASH_SIZE]>, Phase2Error> {
+        if self.shape != initial.shape {
+            return Err(Phase2Error::ShapeMismatch);
+        }
+        let old = initial.contributions.len();
+        if self.contributions.len() < old
+            || initial
+                .contributions
+                .iter()
+                .zip(&self.contributions)
+                .any(|(a, b)| a.hash()[..] != b.hash()[..])
+        {
+            return Err(Phase2Error::NotExtending);
+        }
+        check_unchanged(&initial.params, &self.params)?;
+
+        let new = &self.contributions[old..];
+        let mut transcript = initial.next_transcript();
+        let mut delta = initial.params.vk.delta_g1;
+        for (i, key) in new.iter().enumerate() {
+            if key.transcript[..] != transcript[..] {
+                return Err(Phase2Error::WrongTranscript(old + i));
+            }
+            transcript = chain_transcript(&key.transcript, &key.hash());
+
+            let r = hash_to_g2::<E>(&key.transcript, &key.s, &key.s_delta);
+            if key.s.is_zero() || !same_ratio::<E>((key.s, key.s_delta), (r, key.r_delta)) {
+                return Err(Phase2Error::InvalidProofOfKnowledge(old + i));
+            }
+            if !same_ratio::<E>((delta, key.delta_after), (r, key.r_delta)) {
+                return Err(Phase2Error::Inconsistent("delta_g1"));
+            }
+            delta = key.delta_after;
+        }
+
+        let (before, after) = (&initial.params, &self.params);
+        if after.vk.delta_g1 != delta {
+            return Err(Phase2Error::Inconsistent("delta_g1"));
+        }
+        if !same_ratio::<E>(
+            (E::G1Affine::one(), after.vk.delta_g1),
+            (E::G2Affine::one(), after.vk.delta_g2),
+        ) {
+            return Err(Phase2Error::Inconsistent("delta_g2"));
+        }
+
+        let deltas = (before.vk.delta_g2, after.vk.delta_g2);
+        let (h_before, h_after) = merge_pairs(&before.h, &after.h);
+        if !same_ratio::<E>((h_after, h_before), deltas) {
+            return Err(Phase2Error::Inconsistent("h"));
+        }
+        let (l_before, l_after) = merge_pairs(&before.l, &after.l);
+        if !same_ratio::<E>((l_after, l_before), deltas) {
+            return Err(Phase2Error::Inconsistent("l"));
+        }
+
+        Ok(new.iter().map(PublicKey::hash).collect())
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.params.write(&mut writer)?;
+        self.write_transcript(writer)
+    }
+
+    pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<Self> {
+        let params = Parameters::read(&mut reader, checked)?;
+
+        let mut shape = [0u8; 32];
+        reader.read_exact(&mut shape)?;
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let contributions = (0..len)
+            .map(|_| PublicKey::read(&mut reader))
+            .collect::<io::Result<_>>()?;
+
+        Ok(MPCParameters {
+            params,
+            shape,
+            contributions,
+        })
+    }
+
+    /// Writes the shape hash and the public keys of the contributions, the
+    /// record of the ceremony to publish next to the final parameters.
+    pub fn write_transcript<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.shape)?;
+        writer.write_u32::<BigEndian>(self.contributions.len() as u32)?;
+        for key in &self.contributions {
+            key.write(&mut writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that `after` is `before` plus one contribution and returns the
+/// hash of its public key.
+pub fn verify_contribution<E: Engine>(
+    before: &MPCParameters<E>,
+    after: &MPCParameters<E>,
+) -> Result<[u8; HASH_SIZE], Phase2Error> {
+    if after.contributions.len() != before.contributions.len() + 1 {
+        return Err(Phase2Error::NotOneContribution);
+    }
+
+    Ok(after.verify(before)?[0])
+}
+
+fn check_unchanged<E: Engine>(
+    before: &Parameters<E>,
+    after: &Parameters<E>,
+) -> Result<(), Phase2Error> {
+    let (vk_before, vk_after) = (&before.vk, &after.vk);
+    let checks = [
+        ("alpha_g1", vk_before.alpha_g1 == vk_after.alpha_g1),
+        ("beta_g1", vk_before.beta_g1 == vk_after.beta_g1),
+        ("beta_g2", vk_before.beta_g2 == vk_after.beta_g2),
+        ("gamma_g2", vk_before.gamma_g2 == vk_after.gamma_g2),
+        ("ic", vk_before.ic == vk_after.ic),
+        ("a", before.a == after.a),
+        ("b_g1", before.b_g1 == after.b_g1),
+        ("b_g2", before.b_g2 == after.b_g2),
+        ("h", before.h.len() == after.h.len()),
+        ("l", before.l.len() == after.l.len()),
+    ];
+    match checks.iter().find(|(_, unchanged)| !unchanged) {
+        Some(&(name, _)) => Err(Phase2Error::Changed(name)),
+        None => Ok(()),
+    }
+}
+
+// The transcript after a contribution with public key hash `key` was made on
+// `transcript`.
+fn chain_transcript(transcript: &[u8; HASH_SIZE], key: &[u8; HASH_SIZE]) -> [u8; HASH_SIZE] {
+    let mut hasher = Blake2b::new();
+    hasher.update(transcript);
+    hasher.update(key);
+
+    let mut hash = [0u8; HASH_SIZE];
+    hash.copy_from_slice(hasher.finalize().as_bytes());
+    hash
+}
+
+// The point of G2 a contribution proves knowledge of its secret against.
+fn hash_to_g2<E: Engine>(
+    transcript: &[u8; HASH_SIZE],
+    s: &E::G1Affine,
+    s_delta: &E::G1Affine,
+) -> E::G2Affine {
+    let mut t = Blake2bTranscript::new(b"crusty3_zk phase2");
+    t.append_message(b"transcript", transcript);
+    t.append_message(b"s", s.into_uncompressed().as_ref());
+    t.append_message(b"s_delta", s_delta.into_uncompressed().as_ref());
+
+    E::G2::random(&mut TranscriptRng(&mut t)).into_affine()
+}
+
+// Whether `g1.1 = g1.0 * x` and `g2.1 = g2.0 * x` for the same `x`.
+fn same_ratio<E: Engine>(g1: (E::G1Affine, E::G1Affine), g2: (E::G2Affine, E::G2Affine)) -> bool {
+    E::pairing(g1.0, g2.1) == E::pairing(g1.1, g2.0)
+}
+
+// Combinations of `before` and `after` with the same random coefficients.
+// If all pairs have the same ratio, so do the combinations, and otherwise
+// they do not with overwhelming probability.
+fn merge_pairs<G: CurveAffine>(before: &[G], after: &[G]) -> (G, G) {
+    let (before, after) = before
+        .par_iter()
+        .zip(after.par_iter())
+        .map(|(b, a)| {
+            let rho = G::Scalar::random(&mut rand::thread_rng()).into_repr();
+            (b.mul(rho), a.mul(rho))
+        })
+        .reduce(
+            || (G::Projective::zero(), G::Projective::zero()),
+            |mut acc, (b, a)| {
+                acc.0.add_assign(&b);
+                acc.1.add_assign(&a);
+                acc
+            },
+        );
+
+    (before.into_affine(), after.into_affine())
+}
+
+fn scale<G: CurveAffine>(points: &[G], by: G::Scalar) -> Vec<G> {
+    let by = by.into_repr();
+    points.par_iter().map(|p| p.mul(by).into_affine()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use crate::util_cs::test_fixtures::{test_rng, CubeCircuit};
+    use rand_xorshift::XorShiftRng;
+
+    fn setup(rng: &mut XorShiftRng) -> MPCParameters<Bls12> {
+        let params = generate_random_parameters(CubeCircuit { x: None }, rng).unwrap();
+        MPCParameters::new(params, CubeCircuit { x: None }).unwrap()
+    }
+
+    #[test]
+    fn test_contributions() {
+        let mut rng = test_rng();
+        let initial = setup(&mut rng);
+
+        let mut mpc = initial.clone();
+        let first = mpc.contribute(&mut rng);
+        let before = mpc.clone();
+        let second = mpc.contribute(&mut rng);
+        assert_ne!(mpc.params().vk.delta_g1, initial.params().vk.delta_g1);
+
+        assert_eq!(verify_contribution(&before, &mpc).unwrap()[..], second[..]);
+        let hashes = mpc.verify(&initial).unwrap();
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[0][..], first[..]);
+        assert_eq!(hashes[1][..], second[..]);
+        assert_eq!(
+            verify_contribution(&initial, &mpc).err(),
+            Some(Phase2Error::NotOneContribution)
+        );
+
+        let x = Fr::from_str("3").unwrap();
+        let proof =
+            create_random_proof(CubeCircuit { x: Some(x) }, mpc.params(), &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&mpc.params().vk);
+        assert!(verify_proof(&pvk, &proof, &[Fr::from_str("27").unwrap()]).unwrap());
+    }
+
+    #[test]
+    fn test_reject_contributions() {
+        let mut rng = test_rng();
+        let initial = setup(&mut rng);
+        let mut mpc = initial.clone();
+        mpc.contribute(&mut rng);
+
+        // A contribution on top of a different first one.
+        let mut other = initial.clone();
+        other.contribute(&mut rng);
+        let before = other.clone();
+        other.contribute(&mut rng);
+        assert!(verify_contribution(&before, &other).is_ok());
+        assert_eq!(
+            verify_contribution(&mpc, &other).err(),
+            Some(Phase2Error::NotExtending)
+        );
+
+        let mut tampered = mpc.clone();
+        let mut l = tampered.params.l.to_vec();
+        l[0] = l[0].mul(Fr::from_str("2").unwrap()).into_affine();
+        tampered.params.l = Arc::new(l);
+        assert_eq!(
+            verify_contribution(&initial, &tampered).err(),
+            Some(Phase2Error::Inconsistent("l"))
+        );
+
+        let mut tampered = mpc.clone();
+        tampered.params.vk.ic.swap(0, 1);
+        assert_eq!(
+            verify_contribution(&initial, &tampered).err(),
+            Some(Phase2Error::Changed("ic"))
+        );
+
+        let mut tampered = mpc.clone();
+        tampered.contributions[0].transcript[0] ^= 1;
+        assert_eq!(
+            verify_contribution(&initial, &tampered).err(),
+            Some(Phase2Error::WrongTranscript(0))
+        );
+
+        let mut tampered = mpc.clone();
+        tampered.contributions[0].s_delta = tampered.contributions[0].s;
+        assert_eq!(
+            verify_contribution(&initial, &tampered).err(),
+            Some(Phase2Error::InvalidProofOfKnowledge(0))
+        );
+
+        let mut tampered = mpc;
+        tampered.shape[0] ^= 1;
+        assert_eq!(
+            verify_contribution(&initial, &tampered).err(),
+            Some(Phase2Error::ShapeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_reject_reordered_contributions() {
+        let mut rng = test_rng();
+        let initial = setup(&mut rng);
+        let mut mpc = initial.clone();
+        mpc.contribute(&mut rng);
+        let before = mpc.clone();
+        mpc.contribute(&mut rng);
+        assert!(mpc.verify(&initial).is_ok());
+
+        // The secrets commute, so swapping the keys keeps the parameters.
+        let mut reordered = mpc.clone();
+        reordered.contributions.swap(0, 1);
+        assert_eq!(
+            reordered.verify(&initial).err(),
+            Some(Phase2Error::WrongTranscript(0))
+        );
+
+        // A second contribution made as if it were the first.
+        let mut replayed = before.clone();
+        replayed.contribute_on(initial.hash(), &mut rng);
+        assert_eq!(
+            verify_contribution(&before, &replayed).err(),
+            Some(Phase2Error::WrongTranscript(1))
+        );
+        assert_eq!(
+            replayed.verify(&initial).err(),
+            Some(Phase2Error::WrongTranscript(1))
+        );
+    }
+
+    #[test]
+    fn test_write_read() {
+        let mut rng = test_rng();
+        let mut mpc = setup(&mut rng);
+        mpc.contribute(&mut rng);
+
+        let mut bytes = vec![];
+        mpc.write(&mut bytes).unwrap();
+        let read = MPCParameters::<Bls12>::read(&bytes[..], true).unwrap();
+        assert_eq!(read.hash()[..], mpc.hash()[..]);
+        assert_eq!(read.verify(&mpc).unwrap().len(), 0);
+
+        let mut transcript = vec![];
+        mpc.write_transcript(&mut transcript).unwrap();
+        assert!(bytes.ends_with(&transcript));
+    }
+}
@@ -0,0 +1,184 @@
+//! On-disk caching of prepared verifying keys.
+//!
+//! Layout, all integers big-endian:
+//!
+//! ```text
+//! magic     4 bytes   "G16V"
+//! version   u16
+//! vk hash   32 bytes  `VerifyingKey::hash` of the key it was prepared from
+//! length    u32       of the body
+//! body                as written by `groth16_processed_vk_to_byteblob`
+//! checksum  32 bytes  BLAKE2s-256 of everything above
+//! ```
+//!
+//! The checksum is unkeyed and only detects corruption: anyone can write a
+//! file with another body and a matching checksum. `read` checks the points
+//! the body shares with `vk`, but returns the pairing `alpha_g1_beta_g2` as
+//! read, since recomputing it is what the cache saves. Caches must therefore
+//! only be loaded from trusted storage: a forged pairing makes the key accept
+//! forged proofs.
+
+use std::io::{self, Read, Write};
+
+use blake2s_simd::blake2s;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use super::{
+    groth16_processed_vk_from_byteblob, groth16_processed_vk_to_byteblob, groth16vk_to_pvk,
+    PreparedVerifyingKey, VerifyingKey,
+};
+use crate::bls::Bls12;
+use crate::invalid_data;
+
+const MAGIC: [u8; 4] = *b"G16V";
+const VERSION: u16 = 1;
+
+const HASH_SIZE: usize = 32;
+const HEADER_SIZE: usize = 4 + 2 + HASH_SIZE + 4;
+
+impl PreparedVerifyingKey<Bls12> {
+    /// Writes the key prepared from `vk`, so that it can be checked by `read`.
+    pub fn write<W: Write>(&self, vk: &VerifyingKey<Bls12>, mut writer: W) -> io::Result<()> {
+        if self.ic != vk.ic || self.gamma_g2 != vk.gamma_g2 || self.delta_g2 != vk.delta_g2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "prepared verifying key was not prepared from this verifying key",
+            ));
+        }
+
+        let body = groth16_processed_vk_to_byteblob(self);
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + body.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.write_u16::<BigEndian>(VERSION)?;
+        bytes.extend_from_slice(&vk.hash());
+        bytes.write_u32::<BigEndian>(body.len() as u32)?;
+        bytes.extend_from_slice(&body);
+
+        writer.write_all(&bytes)?;
+        writer.write_all(blake2s(&bytes).as_bytes())
+    }
+
+    /// Reads a key written by `write`, without recomputing its pairing. Fails
+    /// if it was prepared from another verifying key than `vk`, or if its
+    /// points differ from those of `vk`. The file must come from trusted
+    /// storage, see the module documentation.
+    pub fn read<R: Read>(mut reader: R, vk: &VerifyingKey<Bls12>) -> io::Result<Self> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(invalid_data("not a prepared verifying key"));
+        }
+
+        let mut fields = &header[4..];
+        let version = fields.read_u16::<BigEndian>()?;
+        if version != VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported prepared verifying key version {}",
+                version
+            )));
+        }
+        if fields[..HASH_SIZE] != vk.hash() {
+            return Err(invalid_data(
+                "prepared verifying key belongs to another verifying key",
+            ));
+        }
+        let body_len = (&fields[HASH_SIZE..]).read_u32::<BigEndian>()? as u64;
+
+        // Read through `take` so that a bogus length fails with an early EOF
+        // instead of a huge allocation.
+        let mut body = Vec::new();
+        reader.by_ref().take(body_len).read_to_end(&mut body)?;
+        if body.len() as u64 != body_len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "prepared verifying key is truncated",
+            ));
+        }
+
+        let mut expected = [0u8; HASH_SIZE];
+        reader.read_exact(&mut expected)?;
+        let mut bytes = header.to_vec();
+        bytes.extend_from_slice(&body);
+        if blake2s(&bytes).as_bytes() != &expected[..] {
+            return Err(invalid_data("prepared verifying key checksum mismatch"));
+        }
+
+        let key =
+            groth16_processed_vk_from_byteblob(&body).map_err(|e| invalid_data(&e.to_string()))?;
+        if key.gamma_g2 != vk.gamma_g2 || key.delta_g2 != vk.delta_g2 || key.ic != vk.ic {
+            return Err(invalid_data(
+                "prepared verifying key does not match the verifying key",
+            ));
+        }
+
+        Ok(groth16vk_to_pvk(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::groth16::{generate_random_parameters, prepare_verifying_key};
+    use crate::util_cs::test_fixtures::{test_rng, MulCircuit};
+
+    #[test]
+    fn test_prepared_vk_roundtrip() {
+        let mut rng = test_rng();
+
+        let vk =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap()
+                .vk;
+        let other =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap()
+                .vk;
+        let pvk = prepare_verifying_key(&vk);
+
+        let mut bytes = vec![];
+        pvk.write(&vk, &mut bytes).unwrap();
+        assert_eq!(&bytes[..4], b"G16V");
+
+        let read = PreparedVerifyingKey::read(&bytes[..], &vk).unwrap();
+        assert_eq!(read.alpha_g1_beta_g2, pvk.alpha_g1_beta_g2);
+        assert_eq!(read.gamma_g2, pvk.gamma_g2);
+        assert_eq!(read.delta_g2, pvk.delta_g2);
+        assert_eq!(read.ic, pvk.ic);
+
+        assert!(PreparedVerifyingKey::read(&bytes[..], &other).is_err());
+        assert!(pvk.write(&other, &mut vec![]).is_err());
+        assert!(PreparedVerifyingKey::read(&bytes[..bytes.len() - 1], &vk).is_err());
+
+        let mut corrupted = bytes.clone();
+        corrupted[HEADER_SIZE] ^= 1;
+        let err = PreparedVerifyingKey::read(&corrupted[..], &vk)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "prepared verifying key checksum mismatch");
+
+        // The body of another key, under the hash of `vk` and a recomputed
+        // checksum, is caught by its points.
+        let mut forged = vec![];
+        prepare_verifying_key(&other)
+            .write(&other, &mut forged)
+            .unwrap();
+        forged[6..6 + HASH_SIZE].copy_from_slice(&vk.hash());
+        let end = forged.len() - HASH_SIZE;
+        let checksum = blake2s(&forged[..end]);
+        forged[end..].copy_from_slice(checksum.as_bytes());
+        let err = PreparedVerifyingKey::read(&forged[..], &vk).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "prepared verifying key does not match the verifying key"
+        );
+
+        // The pairing is not recomputed, so a cached one is returned as is.
+        let mut cached = prepare_verifying_key(&vk);
+        cached.alpha_g1_beta_g2 = prepare_verifying_key(&other).alpha_g1_beta_g2;
+        let mut bytes = vec![];
+        cached.write(&vk, &mut bytes).unwrap();
+        let read = PreparedVerifyingKey::read(&bytes[..], &vk).unwrap();
+        assert_eq!(read.alpha_g1_beta_g2, cached.alpha_g1_beta_g2);
+    }
+}
@@ -1,7 +1,10 @@
 use std::io::{self, Read, Write};
 
-use groupy::{CurveAffine, EncodedPoint};
+use ff::Field;
+use groupy::{CurveAffine, CurveProjective, EncodedPoint};
+use rand_core::RngCore;
 
+use super::VerifyingKey;
 use crate::bls::Engine;
 
 
@@ -27,6 +30,8 @@ impl<E: Engine> Proof<E> {
         Ok(())
     }
 
+    /// Reads a proof, checking that its points are in the prime-order
+    /// subgroup and not at infinity.
     pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
         let mut bytes = vec![0u8; Self::size()];
         reader.read_exact(&mut bytes)?;
@@ -35,16 +40,86 @@ impl<E: Engine> Proof<E> {
         Ok(proof)
     }
 
+    /// Reads a proof without the subgroup checks of `read`. Decompression
+    /// still ensures the points are on the curve. Only use this for proofs
+    /// from a trusted source.
+    pub fn read_unchecked<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut g1_repr = <E::G1Affine as CurveAffine>::Compressed::empty();
+        let mut g2_repr = <E::G2Affine as CurveAffine>::Compressed::empty();
+
+        reader.read_exact(g1_repr.as_mut())?;
+        let a = g1_repr
+            .into_affine_unchecked()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        reader.read_exact(g2_repr.as_mut())?;
+        let b = g2_repr
+            .into_affine_unchecked()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        reader.read_exact(g1_repr.as_mut())?;
+        let c = g1_repr
+            .into_affine_unchecked()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(Proof { a, b, c })
+    }
+
+    /// Re-randomizes the proof without the witness, as `A' = A / t`,
+    /// `B' = t B + t u delta` and `C' = C + u A` for random `t` and `u`. The
+    /// result is a fresh proof of the same statement that cannot be linked to
+    /// this one, and verifies if and only if this one does.
+    pub fn rerandomize<R: RngCore>(&self, vk: &VerifyingKey<E>, rng: &mut R) -> Self {
+        let t = loop {
+            let t = E::Fr::random(rng);
+            if !t.is_zero() {
+                break t;
+            }
+        };
+        let u = E::Fr::random(rng);
+
+        let a = self.a.mul(t.inverse().unwrap()).into_affine();
+
+        let mut tu = t;
+        tu.mul_assign(&u);
+        let mut b = self.b.mul(t);
+        b.add_assign(&vk.delta_g2.mul(tu));
+
+        let mut c = self.a.mul(u);
+        c.add_assign_mixed(&self.c);
+
+        Proof {
+            a,
+            b: b.into_affine(),
+            c: c.into_affine(),
+        }
+    }
+
     pub fn size() -> usize {
         2 * <<<E as Engine>::G1Affine as groupy::CurveAffine>::Compressed as groupy::EncodedPoint>::size()
             + <<<E as Engine>::G2Affine as groupy::CurveAffine>::Compressed as groupy::EncodedPoint>::size(
             )
     }
 
+    /// Reads `num_proofs` proofs from `proof_bytes`, decompressing and
+    /// checking all their points in parallel.
     pub fn read_many(proof_bytes: &[u8], num_proofs: usize) -> io::Result<Vec<Self>> {
         use crate::multicore::THREAD_POOL;
         use rayon::prelude::*;
-        debug_assert_eq!(proof_bytes.len(), num_proofs * Self::size());
+        let expected_len = num_proofs.checked_mul(Self::size()).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "too many proofs")
+        })?;
+        if proof_bytes.len() != expected_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "expected {} bytes for {} proofs, got {}",
+                    expected_len,
+                    num_proofs,
+                    proof_bytes.len()
+                ),
+            ));
+        }
 
         // Decompress and group check in parallel
         THREAD_POOL.install(|| {
@@ -198,6 +273,35 @@ mod test_with_bls12_381 {
         assert_eq!(Proof::<Bls12>::size(), 192);
     }
 
+    #[test]
+    fn test_read_subgroup_check() {
+        use crate::bls::{G1Affine, G1Compressed, G2Affine};
+
+        // Find a point on the curve outside of the prime-order subgroup.
+        let mut repr = G1Compressed::empty();
+        let outside = (1u8..)
+            .find_map(|x| {
+                repr.as_mut()[0] = 0x80;
+                repr.as_mut()[47] = x;
+                repr.into_affine_unchecked().ok()
+            })
+            .unwrap();
+        assert!(repr.into_affine().is_err());
+
+        let mut v = vec![];
+        Proof::<Bls12> {
+            a: outside,
+            b: G2Affine::one(),
+            c: G1Affine::one(),
+        }
+        .write(&mut v)
+        .unwrap();
+
+        assert!(Proof::<Bls12>::read(&v[..]).is_err());
+        let proof = Proof::<Bls12>::read_unchecked(&v[..]).unwrap();
+        assert_eq!(proof.a, outside);
+    }
+
     #[test]
     fn test_print() {
         println!("Test runs!");
@@ -233,7 +337,7 @@ mod test_with_bls12_381 {
         // let mut v_copy = vec![v];
 
         println!("Vector size: {}", v.len());
-        println!("{:02x?}", v);
+        println!("{}", prf.to_hex());
 
         let de_prf = Proof::<Bls12>::read(&v[..]).unwrap();
 
@@ -319,6 +423,7 @@ mod test_with_bls12_381 {
 
             let de_proof = Proof::read(&v[..]).unwrap();
             assert!(proof == de_proof);
+            assert!(Proof::read_unchecked(&v[..]).unwrap() == proof);
 
             // read two proofs
             proof.write(&mut v).unwrap();
@@ -326,9 +431,23 @@ mod test_with_bls12_381 {
             assert_eq!(de_proofs.len(), 2);
             assert_eq!(de_proofs[0], proof);
             assert_eq!(de_proofs[1], proof);
+            assert!(Proof::<Bls12>::read_many(&v[..], 3).is_err());
+            assert!(Proof::<Bls12>::read_many(&v[1..], 2).is_err());
+            assert_eq!(
+                Proof::<Bls12>::read_many(&v[..], usize::max_value())
+                    .err()
+                    .unwrap()
+                    .kind(),
+                io::ErrorKind::InvalidInput
+            );
 
             assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
             assert!(!verify_proof(&pvk, &proof, &[a]).unwrap());
+
+            let rerandomized = proof.rerandomize(&params.vk, rng);
+            assert!(rerandomized != proof);
+            assert!(verify_proof(&pvk, &rerandomized, &[c]).unwrap());
+            assert!(!verify_proof(&pvk, &rerandomized, &[a]).unwrap());
         }
     }
 }
@@ -1,25 +1,38 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::bls::Engine;
-use ff::{Field, PrimeField};
+use bit_vec::BitVec;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField, PrimeFieldRepr};
 use groupy::{CurveAffine, CurveProjective};
 use rand_core::RngCore;
 use rayon::prelude::*;
 
-use super::{ParameterSource, Proof};
-use crate::domain::{EvaluationDomain, Scalar};
+use super::memory::{proving_memory, witness_memory};
+use super::{Config, ParameterSource, Proof};
+use crate::domain::{compute_quotient_polynomial_in, FftScratch, Scalar};
 use crate::gpu::{LockedFFTKernel, LockedMultiexpKernel};
-use crate::multicore::{Worker, THREAD_POOL};
+use crate::multicore::{WaitError, Waiter, THREAD_POOL};
 use crate::multiexp::{multiexp, DensityTracker, FullDensity};
 use crate::{
-    Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable, BELLMAN_VERSION,
+    invalid_data, Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable,
+    BELLMAN_VERSION,
 };
 use log::info;
 
 #[cfg(feature = "gpu")]
 use crate::gpu::PriorityLock;
 
+// Identifies proof batches in the GPU queue, see `gpu::queue_status`.
+static BATCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// How often a cancellable call checks its token while waiting for a multiexp.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 fn eval<E: Engine>(
     lc: &LinearCombination<E>,
     mut input_density: Option<&mut DensityTracker>,
@@ -58,7 +71,13 @@ fn eval<E: Engine>(
     acc
 }
 
-struct ProvingAssignment<E: Engine> {
+/// The full witness of a synthesized circuit, as consumed by the prover.
+///
+/// Besides the values of all input and auxiliary variables, this holds the
+/// evaluations of the A, B and C polynomials for every constraint and the
+/// densities of the A and B queries, so that the multiexps can skip bases
+/// whose scalars are known to be zero.
+pub struct ProvingAssignment<E: Engine> {
     // Density of queries
     a_aux_density: DensityTracker,
     b_input_density: DensityTracker,
@@ -75,6 +94,181 @@ struct ProvingAssignment<E: Engine> {
 }
 use std::fmt;
 
+impl<E: Engine> ProvingAssignment<E> {
+    /// Density of the auxiliary variables in the A query.
+    pub fn a_aux_density(&self) -> &DensityTracker {
+        &self.a_aux_density
+    }
+
+    /// Density of the input variables in the B query.
+    pub fn b_input_density(&self) -> &DensityTracker {
+        &self.b_input_density
+    }
+
+    /// Density of the auxiliary variables in the B query.
+    pub fn b_aux_density(&self) -> &DensityTracker {
+        &self.b_aux_density
+    }
+
+    /// Evaluations of the A polynomial, one per constraint.
+    pub fn a(&self) -> &[Scalar<E>] {
+        &self.a
+    }
+
+    /// Evaluations of the B polynomial, one per constraint.
+    pub fn b(&self) -> &[Scalar<E>] {
+        &self.b
+    }
+
+    /// Evaluations of the C polynomial, one per constraint.
+    pub fn c(&self) -> &[Scalar<E>] {
+        &self.c
+    }
+
+    /// Values of the input variables, starting with the constant `one`.
+    pub fn input_assignment(&self) -> &[E::Fr] {
+        &self.input_assignment
+    }
+
+    /// Values of the auxiliary variables.
+    pub fn aux_assignment(&self) -> &[E::Fr] {
+        &self.aux_assignment
+    }
+
+    fn witness_memory(&self) -> usize {
+        witness_memory::<E>(
+            self.a.len(),
+            self.input_assignment.len(),
+            self.aux_assignment.len(),
+        )
+    }
+
+    fn proving_memory(&self) -> usize {
+        proving_memory::<E>(
+            self.a.len(),
+            self.input_assignment.len(),
+            self.aux_assignment.len(),
+        )
+    }
+}
+
+impl<E: Engine> ProvingAssignment<E> {
+    /// Serializes the witness, so that it can be proven elsewhere with
+    /// [`create_proof_batch_from_assignments`].
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_frs::<E, _, _>(&mut writer, self.a.iter().map(|s| &s.0), self.a.len())?;
+        write_frs::<E, _, _>(&mut writer, self.b.iter().map(|s| &s.0), self.b.len())?;
+        write_frs::<E, _, _>(&mut writer, self.c.iter().map(|s| &s.0), self.c.len())?;
+        write_frs::<E, _, _>(
+            &mut writer,
+            self.input_assignment.iter(),
+            self.input_assignment.len(),
+        )?;
+        write_frs::<E, _, _>(
+            &mut writer,
+            self.aux_assignment.iter(),
+            self.aux_assignment.len(),
+        )?;
+
+        write_density(&mut writer, &self.a_aux_density)?;
+        write_density(&mut writer, &self.b_input_density)?;
+        write_density(&mut writer, &self.b_aux_density)?;
+
+        Ok(())
+    }
+
+    /// Reads a witness written by [`write`](Self::write), checking that the
+    /// lengths of its parts are consistent.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let a = read_frs::<E, _>(&mut reader)?;
+        let b = read_frs::<E, _>(&mut reader)?;
+        let c = read_frs::<E, _>(&mut reader)?;
+        let input_assignment = read_frs::<E, _>(&mut reader)?;
+        let aux_assignment = read_frs::<E, _>(&mut reader)?;
+
+        if a.len() != b.len() || a.len() != c.len() {
+            return Err(invalid_data("the A, B and C evaluations differ in length"));
+        }
+        if input_assignment.is_empty() {
+            return Err(invalid_data("the input assignment lacks the one input"));
+        }
+
+        let a_aux_density = read_density(&mut reader, aux_assignment.len())?;
+        let b_input_density = read_density(&mut reader, input_assignment.len())?;
+        let b_aux_density = read_density(&mut reader, aux_assignment.len())?;
+
+        Ok(ProvingAssignment {
+            a_aux_density,
+            b_input_density,
+            b_aux_density,
+            a: a.into_iter().map(Scalar).collect(),
+            b: b.into_iter().map(Scalar).collect(),
+            c: c.into_iter().map(Scalar).collect(),
+            input_assignment,
+            aux_assignment,
+        })
+    }
+}
+
+fn write_density<W: Write>(writer: &mut W, density: &DensityTracker) -> io::Result<()> {
+    writer.write_u32::<BigEndian>(density.bv.len() as u32)?;
+    writer.write_all(&density.bv.to_bytes())
+}
+
+/// Reads a density over `expected_len` variables. The length is checked
+/// before the bits are read, so a corrupt length cannot make it allocate more
+/// than the assignments already take.
+fn read_density<R: Read>(reader: &mut R, expected_len: usize) -> io::Result<DensityTracker> {
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    if len != expected_len {
+        return Err(invalid_data(
+            "the query densities do not match the assignments",
+        ));
+    }
+    let num_bytes = (len + 7) / 8;
+    let mut bytes = Vec::with_capacity(num_bytes);
+    reader
+        .by_ref()
+        .take(num_bytes as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() != num_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the query density is truncated",
+        ));
+    }
+
+    let mut bv = BitVec::from_bytes(&bytes);
+    bv.truncate(len);
+    let total_density = bv.iter().filter(|b| *b).count();
+
+    Ok(DensityTracker { bv, total_density })
+}
+
+fn write_frs<'a, E: Engine, W: Write, I: Iterator<Item = &'a E::Fr>>(
+    writer: &mut W,
+    frs: I,
+    len: usize,
+) -> io::Result<()> {
+    writer.write_u32::<BigEndian>(len as u32)?;
+    for fr in frs {
+        fr.into_repr().write_be(&mut *writer)?;
+    }
+
+    Ok(())
+}
+
+fn read_frs<E: Engine, R: Read>(reader: &mut R) -> io::Result<Vec<E::Fr>> {
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    (0..len)
+        .map(|_| {
+            let mut repr = <E::Fr as PrimeField>::Repr::default();
+            repr.read_be(&mut *reader)?;
+            E::Fr::from_repr(repr).map_err(|e| invalid_data(&e.to_string()))
+        })
+        .collect()
+}
+
 impl<E: Engine> fmt::Debug for ProvingAssignment<E> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("ProvingAssignment")
@@ -173,9 +367,11 @@ impl<E: Engine> ConstraintSystem<E> for ProvingAssignment<E> {
         LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
         LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
     {
-        let a = a(LinearCombination::zero());
-        let b = b(LinearCombination::zero());
-        let c = c(LinearCombination::zero());
+        // Cancelled terms would count towards the densities, while the
+        // parameters have no bases for variables whose polynomials are zero.
+        let a = a(LinearCombination::zero()).simplify();
+        let b = b(LinearCombination::zero()).simplify();
+        let c = c(LinearCombination::zero()).simplify();
 
         self.a.push(Scalar(eval(
             &a,
@@ -243,6 +439,45 @@ impl<E: Engine> ConstraintSystem<E> for ProvingAssignment<E> {
     }
 }
 
+/// Synthesizes a circuit into its full witness assignment.
+///
+/// This performs exactly the synthesis the prover does: the `one` input is
+/// allocated first, and an `x * 0 = 0` constraint is added for every input
+/// after the circuit has been synthesized.
+pub fn synthesize_circuit<E, C>(circuit: C) -> Result<ProvingAssignment<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+{
+    let mut prover = ProvingAssignment::new();
+
+    prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+    circuit.synthesize(&mut prover)?;
+
+    for i in 0..prover.input_assignment.len() {
+        prover.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
+    }
+
+    Ok(prover)
+}
+
+/// Synthesizes a batch of circuits in parallel, see [`synthesize_circuit`].
+pub fn synthesize_circuits_batch<E, C>(
+    circuits: Vec<C>,
+) -> Result<Vec<ProvingAssignment<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+{
+    THREAD_POOL.install(|| {
+        circuits
+            .into_par_iter()
+            .map(synthesize_circuit)
+            .collect::<Result<Vec<_>, _>>()
+    })
+}
+
 pub fn create_random_proof_batch_priority<E, C, R, P: ParameterSource<E>>(
     circuits: Vec<C>,
     params: P,
@@ -273,55 +508,478 @@ where
 {
     info!("Bellperson {} is being used!", BELLMAN_VERSION);
 
-    THREAD_POOL.install(|| create_proof_batch_priority_inner(circuits, params, r_s, s_s, priority))
+    let params = vec![&params; circuits.len()];
+
+    let config = Config::default().with_priority(priority);
+
+    config.install(|| create_proof_batch_priority_inner(circuits, &params, r_s, s_s, &config))
 }
 
-fn create_proof_batch_priority_inner<E, C, P: ParameterSource<E>>(
+pub fn create_random_proof_batch_priority_with_deadline<E, C, R, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: P,
+    rng: &mut R,
+    priority: bool,
+    deadline: Instant,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+    R: RngCore,
+{
+    let r_s = (0..circuits.len()).map(|_| E::Fr::random(rng)).collect();
+    let s_s = (0..circuits.len()).map(|_| E::Fr::random(rng)).collect();
+
+    create_proof_batch_priority_with_deadline::<E, C, P>(
+        circuits, params, r_s, s_s, priority, deadline,
+    )
+}
+
+/// Like [`create_proof_batch_priority`], but fails with
+/// [`SynthesisError::TimedOut`] once `deadline` has passed.
+///
+/// The deadline is checked between the proving stages and while waiting for
+/// multiexp results. A kernel call that is already running on the GPU is not
+/// interrupted, the deadline is checked again as soon as it returns.
+pub fn create_proof_batch_priority_with_deadline<E, C, P: ParameterSource<E>>(
     circuits: Vec<C>,
     params: P,
     r_s: Vec<E::Fr>,
     s_s: Vec<E::Fr>,
     priority: bool,
+    deadline: Instant,
 ) -> Result<Vec<Proof<E>>, SynthesisError>
 where
     E: Engine,
     C: Circuit<E> + Send,
 {
-    let mut provers = circuits
-        .into_par_iter()
-        .map(|circuit| -> Result<_, SynthesisError> {
-            let mut prover = ProvingAssignment::new();
+    info!("Bellperson {} is being used!", BELLMAN_VERSION);
+
+    let params = vec![&params; circuits.len()];
+
+    let config = Config::default()
+        .with_priority(priority)
+        .with_deadline(deadline);
 
-            prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+    config.install(|| create_proof_batch_priority_inner(circuits, &params, r_s, s_s, &config))
+}
+
+pub fn create_random_proof_batch_with_config<E, C, R, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: P,
+    rng: &mut R,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+    R: RngCore,
+{
+    let r_s = (0..circuits.len()).map(|_| E::Fr::random(rng)).collect();
+    let s_s = (0..circuits.len()).map(|_| E::Fr::random(rng)).collect();
 
-            circuit.synthesize(&mut prover)?;
+    create_proof_batch_with_config::<E, C, P>(circuits, params, r_s, s_s, config)
+}
 
-            for i in 0..prover.input_assignment.len() {
-                prover.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
+/// Like [`create_proof_batch_priority`], with the GPU usage, priority and
+/// deadline given by `config` rather than by arguments and environment
+/// variables.
+pub fn create_proof_batch_with_config<E, C, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: P,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+{
+    info!("Bellperson {} is being used!", BELLMAN_VERSION);
+
+    let params = vec![&params; circuits.len()];
+
+    config.install(|| create_proof_batch_priority_inner(circuits, &params, r_s, s_s, config))
+}
+
+pub fn create_random_proof_batch_from_assignments<E, R, P: ParameterSource<E>>(
+    assignments: Vec<ProvingAssignment<E>>,
+    params: P,
+    rng: &mut R,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    R: RngCore,
+{
+    let r_s = (0..assignments.len()).map(|_| E::Fr::random(rng)).collect();
+    let s_s = (0..assignments.len()).map(|_| E::Fr::random(rng)).collect();
+
+    create_proof_batch_from_assignments::<E, P>(assignments, params, r_s, s_s, config)
+}
+
+/// Like [`create_proof_batch_with_config`], for circuits that were already
+/// synthesized with [`synthesize_circuit`], possibly on another machine and
+/// read back with [`ProvingAssignment::read`].
+pub fn create_proof_batch_from_assignments<E, P: ParameterSource<E>>(
+    assignments: Vec<ProvingAssignment<E>>,
+    params: P,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+{
+    info!("Bellperson {} is being used!", BELLMAN_VERSION);
+
+    let params = vec![&params; assignments.len()];
+
+    config.install(|| prove_assignments_chunked(assignments, &params, r_s, s_s, config))
+}
+
+pub fn create_random_proof_batch_mixed_priority<E, C, R, P: ParameterSource<E>>(
+    jobs: Vec<(C, P)>,
+    rng: &mut R,
+    priority: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+    R: RngCore,
+{
+    let r_s = (0..jobs.len()).map(|_| E::Fr::random(rng)).collect();
+    let s_s = (0..jobs.len()).map(|_| E::Fr::random(rng)).collect();
+
+    create_proof_batch_mixed_priority::<E, C, P>(jobs, r_s, s_s, priority)
+}
+
+/// Proves a batch of circuits, each against its own parameters.
+///
+/// Unlike [`create_proof_batch_priority`], the circuits may be of different
+/// sizes. All proofs still share the worker pool and a single FFT and
+/// multiexp kernel, so the GPU is acquired once for the whole batch rather
+/// than once per parameter set. Different circuit types can be mixed by
+/// wrapping them in an enum implementing `Circuit`, or boxed with
+/// [`create_proof_batch_dyn`].
+pub fn create_proof_batch_mixed_priority<E, C, P: ParameterSource<E>>(
+    jobs: Vec<(C, P)>,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    priority: bool,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+{
+    info!("Bellperson {} is being used!", BELLMAN_VERSION);
+
+    let (circuits, params): (Vec<_>, Vec<_>) = jobs.into_iter().unzip();
+    let params = params.iter().collect::<Vec<_>>();
+    let config = Config::default().with_priority(priority);
+
+    config.install(|| create_proof_batch_priority_inner(circuits, &params, r_s, s_s, &config))
+}
+
+/// A circuit behind a trait object, so that circuits of different types can
+/// be proven in one batch with [`create_proof_batch_dyn`].
+///
+/// `Circuit::synthesize` is generic over the constraint system, so there is
+/// no `dyn Circuit`. This trait is implemented for every circuit and only
+/// synthesizes into the prover's constraint system.
+pub trait ProverCircuit<E: Engine>: Send {
+    fn synthesize_boxed(self: Box<Self>) -> Result<ProvingAssignment<E>, SynthesisError>;
+}
+
+impl<E: Engine, C: Circuit<E> + Send> ProverCircuit<E> for C {
+    fn synthesize_boxed(self: Box<Self>) -> Result<ProvingAssignment<E>, SynthesisError> {
+        synthesize_circuit(*self)
+    }
+}
+
+pub fn create_random_proof_batch_dyn<E, R, P: ParameterSource<E>>(
+    jobs: Vec<(Box<dyn ProverCircuit<E>>, P)>,
+    rng: &mut R,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    R: RngCore,
+{
+    let r_s = (0..jobs.len()).map(|_| E::Fr::random(rng)).collect();
+    let s_s = (0..jobs.len()).map(|_| E::Fr::random(rng)).collect();
+
+    create_proof_batch_dyn::<E, P>(jobs, r_s, s_s, config)
+}
+
+/// Like [`create_proof_batch_mixed_priority`], for boxed circuits of
+/// different types, each with its own parameters. The GPU is acquired once
+/// for the whole batch.
+pub fn create_proof_batch_dyn<E, P: ParameterSource<E>>(
+    jobs: Vec<(Box<dyn ProverCircuit<E>>, P)>,
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+{
+    info!("Bellperson {} is being used!", BELLMAN_VERSION);
+
+    let (circuits, params): (Vec<_>, Vec<_>) = jobs.into_iter().unzip();
+    let params = params.iter().collect::<Vec<_>>();
+
+    config.install(|| {
+        prove_circuits_inner(
+            circuits,
+            |circuit: Box<dyn ProverCircuit<E>>| circuit.synthesize_boxed(),
+            &params,
+            r_s,
+            s_s,
+            config,
+        )
+    })
+}
+
+/// Fails with [`SynthesisError::TimedOut`] if `deadline` has passed.
+pub(crate) fn check_deadline(deadline: Option<Instant>) -> Result<(), SynthesisError> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => Err(SynthesisError::TimedOut),
+        _ => Ok(()),
+    }
+}
+
+/// With sequential multiexps, waits for a multiexp before the next one loads
+/// its part of the parameters.
+fn finish_if_sequential<T>(
+    waiter: Waiter<Result<T, SynthesisError>>,
+    config: &Config,
+) -> Result<Waiter<Result<T, SynthesisError>>, SynthesisError> {
+    if config.sequential_multiexps {
+        Ok(Waiter::done(Ok(wait_until(waiter, config)?)))
+    } else {
+        Ok(waiter)
+    }
+}
+
+/// Waits for a multiexp result, giving up once the call was cancelled or its
+/// deadline has passed.
+fn wait_until<T>(
+    waiter: Waiter<Result<T, SynthesisError>>,
+    config: &Config,
+) -> Result<T, SynthesisError> {
+    match (&config.cancellation, config.deadline) {
+        (None, Some(deadline)) => {
+            match waiter.wait_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(result) => result,
+                Err(WaitError::Timeout) => Err(SynthesisError::TimedOut),
+                Err(WaitError::Disconnected) => Err(SynthesisError::WorkerStopped),
+            }
+        }
+        (None, None) => waiter.wait(),
+        (Some(_), _) => loop {
+            config.check()?;
+            match waiter.wait_timeout(CANCELLATION_POLL_INTERVAL) {
+                Ok(result) => return result,
+                Err(WaitError::Timeout) => {}
+                Err(WaitError::Disconnected) => return Err(SynthesisError::WorkerStopped),
             }
+        },
+    }
+}
 
-            Ok(prover)
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+fn create_proof_batch_priority_inner<E, C, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    params: &[&P],
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E> + Send,
+{
+    prove_circuits_inner(
+        circuits,
+        synthesize_circuit::<E, C>,
+        params,
+        r_s,
+        s_s,
+        config,
+    )
+}
+
+fn prove_circuits_inner<E, C, S, P: ParameterSource<E>>(
+    circuits: Vec<C>,
+    synthesize_one: S,
+    params: &[&P],
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    C: Send,
+    S: Fn(C) -> Result<ProvingAssignment<E>, SynthesisError> + Sync,
+{
+    assert_eq!(circuits.len(), params.len());
+
+    let num_circuits = circuits.len();
+    config.report("synthesis", 0, num_circuits);
+    let synthesized = AtomicUsize::new(0);
+    let synthesize = |circuits: Vec<C>| {
+        circuits
+            .into_par_iter()
+            .map(|circuit| {
+                config.check()?;
+                let prover = synthesize_one(circuit)?;
+                let done = synthesized.fetch_add(1, Ordering::SeqCst) + 1;
+                config.report("synthesis", done, num_circuits);
+                Ok(prover)
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()
+    };
+
+    let budget = match config.max_witness_memory {
+        Some(budget) => budget,
+        None => {
+            let provers = synthesize(circuits)?;
+            return prove_assignments_inner(provers, params, r_s, s_s, config);
+        }
+    };
+
+    // The size of the circuits is only known once they are synthesized, so
+    // the first chunk is a single circuit and the following ones are sized
+    // after the largest circuit so far.
+    let mut circuits = circuits.into_iter();
+    let mut r_s = r_s.into_iter();
+    let mut s_s = s_s.into_iter();
+    let mut proofs = Vec::with_capacity(num_circuits);
+    let mut per_proof = 0;
+    while proofs.len() < num_circuits {
+        let chunk_len = if per_proof == 0 {
+            1
+        } else {
+            (budget / per_proof).max(1)
+        };
+        let provers = synthesize(circuits.by_ref().take(chunk_len).collect())?;
+        for prover in &provers {
+            per_proof = per_proof.max(prover.witness_memory() + prover.proving_memory());
+        }
+
+        let offset = proofs.len();
+        proofs.extend(prove_assignments_chunked(
+            provers,
+            &params[offset..offset + chunk_len.min(num_circuits - offset)],
+            r_s.by_ref().take(chunk_len).collect(),
+            s_s.by_ref().take(chunk_len).collect(),
+            config,
+        )?);
+    }
+
+    Ok(proofs)
+}
+
+/// Proves `provers` in chunks that keep the memory of the call within the
+/// budget of `config`, if it has one.
+fn prove_assignments_chunked<E, P: ParameterSource<E>>(
+    mut provers: Vec<ProvingAssignment<E>>,
+    params: &[&P],
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+{
+    let budget = match config.max_witness_memory {
+        Some(budget) => budget,
+        None => return prove_assignments_inner(provers, params, r_s, s_s, config),
+    };
+
+    // The witnesses of the chunks still to be proven stay in memory.
+    let witnesses = provers.iter().map(|p| p.witness_memory()).sum::<usize>();
+    let per_proof = provers
+        .iter()
+        .map(|p| p.proving_memory())
+        .max()
+        .unwrap_or(0);
+    if witnesses + per_proof > budget {
+        return Err(SynthesisError::MemoryBudgetExceeded(witnesses + per_proof));
+    }
+    let chunk_len = ((budget - witnesses) / per_proof.max(1)).max(1);
+
+    let mut r_s = r_s.into_iter();
+    let mut s_s = s_s.into_iter();
+    let mut proofs = Vec::with_capacity(provers.len());
+    for params in params.chunks(chunk_len) {
+        let rest = provers.split_off(params.len());
+        let chunk = std::mem::replace(&mut provers, rest);
+        proofs.extend(prove_assignments_inner(
+            chunk,
+            params,
+            r_s.by_ref().take(params.len()).collect(),
+            s_s.by_ref().take(params.len()).collect(),
+            config,
+        )?);
+    }
+
+    Ok(proofs)
+}
+
+fn prove_assignments_inner<E, P: ParameterSource<E>>(
+    mut provers: Vec<ProvingAssignment<E>>,
+    params: &[&P],
+    r_s: Vec<E::Fr>,
+    s_s: Vec<E::Fr>,
+    config: &Config,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+{
+    assert_eq!(provers.len(), params.len());
+    let priority = config.priority;
+    let num_circuits = provers.len();
+    config.check()?;
 
     // Start fft/multiexp prover timer
     let start = Instant::now();
-    info!("starting proof timer");
-
-    let worker = Worker::new();
-    let input_len = provers[0].input_assignment.len();
-    let vk = params.get_vk(input_len)?;
-    let n = provers[0].a.len();
-
-    // Make sure all circuits have the same input len.
-    for prover in &provers {
-        assert_eq!(
-            prover.a.len(),
-            n,
-            "only equaly sized circuits are supported"
-        );
+    let owner = format!(
+        "groth16 batch #{} ({} proofs)",
+        BATCH_COUNTER.fetch_add(1, Ordering::SeqCst),
+        provers.len()
+    );
+    info!("starting proof timer for {}", owner);
+
+    let worker = config.worker();
+    let vks = provers
+        .iter()
+        .zip(params.iter())
+        .map(|(prover, params)| params.get_vk(prover.input_assignment.len()))
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    // Make sure all circuits proven against the same parameters have the same size.
+    let mut sizes = HashMap::new();
+    for (prover, p) in provers.iter().zip(params.iter()) {
+        let size = *sizes
+            .entry(*p as *const P)
+            .or_insert_with(|| prover.a.len());
+        if size != prover.a.len() {
+            return Err(SynthesisError::IncompatibleLengthVector(format!(
+                "circuits with {} and {} constraints share parameters",
+                size,
+                prover.a.len()
+            )));
+        }
     }
 
+    // The kernels are shared by the whole batch, so size them for the largest circuit.
+    let n = provers
+        .iter()
+        .map(|prover| prover.a.len())
+        .max()
+        .unwrap_or(0);
+
     let mut log_d = 0;
     while (1 << log_d) < n {
         log_d += 1;
@@ -334,34 +992,30 @@ where
         None
     };
 
-    let mut fft_kern = Some(LockedFFTKernel::<E>::new(log_d, priority));
+    let mut fft_kern = Some(
+        LockedFFTKernel::<E>::new(log_d, priority)
+            .with_owner(owner.clone())
+            .with_options(config.gpu.clone()),
+    );
 
+    // The proofs of the batch share the buffers of their FFTs.
+    let mut fft_scratch = FftScratch::new();
+    config.report("fft", 0, num_circuits);
     let a_s = provers
         .iter_mut()
-        .map(|prover| {
-            let mut a =
-                EvaluationDomain::from_coeffs(std::mem::replace(&mut prover.a, Vec::new()))?;
-            let mut b =
-                EvaluationDomain::from_coeffs(std::mem::replace(&mut prover.b, Vec::new()))?;
-            let mut c =
-                EvaluationDomain::from_coeffs(std::mem::replace(&mut prover.c, Vec::new()))?;
-
-            a.ifft(&worker, &mut fft_kern)?;
-            a.coset_fft(&worker, &mut fft_kern)?;
-            b.ifft(&worker, &mut fft_kern)?;
-            b.coset_fft(&worker, &mut fft_kern)?;
-            c.ifft(&worker, &mut fft_kern)?;
-            c.coset_fft(&worker, &mut fft_kern)?;
-
-            a.mul_assign(&worker, &b);
-            drop(b);
-            a.sub_assign(&worker, &c);
-            drop(c);
-            a.divide_by_z_on_coset(&worker);
-            a.icoset_fft(&worker, &mut fft_kern)?;
-            let mut a = a.into_coeffs();
-            let a_len = a.len() - 1;
-            a.truncate(a_len);
+        .enumerate()
+        .map(|(i, prover)| {
+            config.check()?;
+
+            let a = compute_quotient_polynomial_in(
+                &worker,
+                &mut fft_kern,
+                std::mem::replace(&mut prover.a, Vec::new()),
+                std::mem::replace(&mut prover.b, Vec::new()),
+                std::mem::replace(&mut prover.c, Vec::new()),
+                &mut fft_scratch,
+            )?;
+            config.report("fft", i + 1, num_circuits);
 
             Ok(Arc::new(
                 a.into_iter().map(|s| s.0.into_repr()).collect::<Vec<_>>(),
@@ -369,19 +1023,33 @@ where
         })
         .collect::<Result<Vec<_>, SynthesisError>>()?;
 
+    drop(fft_scratch);
     drop(fft_kern);
-    let mut multiexp_kern = Some(LockedMultiexpKernel::<E>::new(log_d, priority));
-
+    let mut multiexp_kern = Some(
+        LockedMultiexpKernel::<E>::new(log_d, priority)
+            .with_owner(owner)
+            .with_options(config.gpu.clone()),
+    );
+
+    // The multiexps run in the background, a proof is only done once the
+    // results of all of them have been waited for below.
+    config.report("multiexp", 0, num_circuits);
     let h_s = a_s
         .into_iter()
-        .map(|a| {
-            let h = multiexp(
-                &worker,
-                params.get_h(a.len())?,
-                FullDensity,
-                a,
-                &mut multiexp_kern,
-            );
+        .zip(params.iter())
+        .map(|(a, params)| {
+            config.check()?;
+
+            let h = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    params.get_h(a.len())?,
+                    FullDensity,
+                    a,
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
             Ok(h)
         })
         .collect::<Result<Vec<_>, SynthesisError>>()?;
@@ -414,14 +1082,20 @@ where
 
     let l_s = aux_assignments
         .iter()
-        .map(|aux_assignment| {
-            let l = multiexp(
-                &worker,
-                params.get_l(aux_assignment.len())?,
-                FullDensity,
-                aux_assignment.clone(),
-                &mut multiexp_kern,
-            );
+        .zip(params.iter())
+        .map(|(aux_assignment, params)| {
+            config.check()?;
+
+            let l = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    params.get_l(aux_assignment.len())?,
+                    FullDensity,
+                    aux_assignment.clone(),
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
             Ok(l)
         })
         .collect::<Result<Vec<_>, SynthesisError>>()?;
@@ -430,27 +1104,36 @@ where
         .into_iter()
         .zip(input_assignments.iter())
         .zip(aux_assignments.iter())
-        .map(|((prover, input_assignment), aux_assignment)| {
+        .zip(params.iter())
+        .map(|(((prover, input_assignment), aux_assignment), params)| {
+            config.check()?;
+
             let a_aux_density_total = prover.a_aux_density.get_total_density();
 
             let (a_inputs_source, a_aux_source) =
                 params.get_a(input_assignment.len(), a_aux_density_total)?;
 
-            let a_inputs = multiexp(
-                &worker,
-                a_inputs_source,
-                FullDensity,
-                input_assignment.clone(),
-                &mut multiexp_kern,
-            );
-
-            let a_aux = multiexp(
-                &worker,
-                a_aux_source,
-                Arc::new(prover.a_aux_density),
-                aux_assignment.clone(),
-                &mut multiexp_kern,
-            );
+            let a_inputs = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    a_inputs_source,
+                    FullDensity,
+                    input_assignment.clone(),
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
+
+            let a_aux = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    a_aux_source,
+                    Arc::new(prover.a_aux_density),
+                    aux_assignment.clone(),
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
 
             let b_input_density = Arc::new(prover.b_input_density);
             let b_input_density_total = b_input_density.get_total_density();
@@ -460,39 +1143,51 @@ where
             let (b_g1_inputs_source, b_g1_aux_source) =
                 params.get_b_g1(b_input_density_total, b_aux_density_total)?;
 
-            let b_g1_inputs = multiexp(
-                &worker,
-                b_g1_inputs_source,
-                b_input_density.clone(),
-                input_assignment.clone(),
-                &mut multiexp_kern,
-            );
-
-            let b_g1_aux = multiexp(
-                &worker,
-                b_g1_aux_source,
-                b_aux_density.clone(),
-                aux_assignment.clone(),
-                &mut multiexp_kern,
-            );
+            let b_g1_inputs = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    b_g1_inputs_source,
+                    b_input_density.clone(),
+                    input_assignment.clone(),
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
+
+            let b_g1_aux = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    b_g1_aux_source,
+                    b_aux_density.clone(),
+                    aux_assignment.clone(),
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
 
             let (b_g2_inputs_source, b_g2_aux_source) =
                 params.get_b_g2(b_input_density_total, b_aux_density_total)?;
 
-            let b_g2_inputs = multiexp(
-                &worker,
-                b_g2_inputs_source,
-                b_input_density,
-                input_assignment.clone(),
-                &mut multiexp_kern,
-            );
-            let b_g2_aux = multiexp(
-                &worker,
-                b_g2_aux_source,
-                b_aux_density,
-                aux_assignment.clone(),
-                &mut multiexp_kern,
-            );
+            let b_g2_inputs = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    b_g2_inputs_source,
+                    b_input_density,
+                    input_assignment.clone(),
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
+            let b_g2_aux = finish_if_sequential(
+                multiexp(
+                    &worker,
+                    b_g2_aux_source,
+                    b_aux_density,
+                    aux_assignment.clone(),
+                    &mut multiexp_kern,
+                ),
+                config,
+            )?;
 
             Ok((
                 a_inputs,
@@ -510,16 +1205,18 @@ where
     #[cfg(feature = "gpu")]
     drop(prio_lock);
 
+    let mut proven = 0;
     let proofs = h_s
         .into_iter()
         .zip(l_s.into_iter())
         .zip(inputs.into_iter())
         .zip(r_s.into_iter())
         .zip(s_s.into_iter())
+        .zip(vks.into_iter())
         .map(
             |(
-                (((h, l), (a_inputs, a_aux, b_g1_inputs, b_g1_aux, b_g2_inputs, b_g2_aux)), r),
-                s,
+                ((((h, l), (a_inputs, a_aux, b_g1_inputs, b_g1_aux, b_g2_inputs, b_g2_aux)), r), s),
+                vk,
             )| {
                 if vk.delta_g1.is_zero() || vk.delta_g2.is_zero() {
                     // If this element is zero, someone is trying to perform a
@@ -540,22 +1237,24 @@ where
                     g_c.add_assign(&vk.alpha_g1.mul(s));
                     g_c.add_assign(&vk.beta_g1.mul(r));
                 }
-                let mut a_answer = a_inputs.wait()?;
-                a_answer.add_assign(&a_aux.wait()?);
+                let mut a_answer = wait_until(a_inputs, config)?;
+                a_answer.add_assign(&wait_until(a_aux, config)?);
                 g_a.add_assign(&a_answer);
                 a_answer.mul_assign(s);
                 g_c.add_assign(&a_answer);
 
-                let mut b1_answer = b_g1_inputs.wait()?;
-                b1_answer.add_assign(&b_g1_aux.wait()?);
-                let mut b2_answer = b_g2_inputs.wait()?;
-                b2_answer.add_assign(&b_g2_aux.wait()?);
+                let mut b1_answer = wait_until(b_g1_inputs, config)?;
+                b1_answer.add_assign(&wait_until(b_g1_aux, config)?);
+                let mut b2_answer = wait_until(b_g2_inputs, config)?;
+                b2_answer.add_assign(&wait_until(b_g2_aux, config)?);
 
                 g_b.add_assign(&b2_answer);
                 b1_answer.mul_assign(r);
                 g_c.add_assign(&b1_answer);
-                g_c.add_assign(&h.wait()?);
-                g_c.add_assign(&l.wait()?);
+                g_c.add_assign(&wait_until(h, config)?);
+                g_c.add_assign(&wait_until(l, config)?);
+                proven += 1;
+                config.report("multiexp", proven, num_circuits);
 
                 Ok(Proof {
                     a: g_a.into_affine(),
@@ -577,16 +1276,12 @@ mod tests {
     use super::*;
 
     use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_fixtures::{test_rng, CubeCircuit, MulCircuit};
     use rand::Rng;
-    use rand_core::SeedableRng;
-    use rand_xorshift::XorShiftRng;
 
     #[test]
     fn test_proving_assignment_extend() {
-        let mut rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
+        let mut rng = test_rng();
 
         for k in &[2, 4, 8] {
             for j in &[10, 20, 50] {
@@ -641,4 +1336,1125 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_synthesize_circuit() {
+        let mut rng = test_rng();
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let assignment = synthesize_circuit(MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        })
+        .unwrap();
+
+        assert_eq!(assignment.input_assignment(), &[Fr::one(), c][..]);
+        assert_eq!(assignment.aux_assignment(), &[a, b][..]);
+
+        // One circuit constraint plus one input constraint per input.
+        assert_eq!(assignment.a().len(), 3);
+        assert_eq!(assignment.b().len(), 3);
+        assert_eq!(assignment.c().len(), 3);
+        assert_eq!(assignment.a()[0].0, a);
+        assert_eq!(assignment.b()[0].0, b);
+        assert_eq!(assignment.c()[0].0, c);
+
+        assert_eq!(assignment.a_aux_density().get_total_density(), 1);
+        assert_eq!(assignment.b_aux_density().get_total_density(), 1);
+        assert_eq!(assignment.b_input_density().get_total_density(), 0);
+    }
+
+    #[test]
+    fn test_cancelled_terms() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+        };
+
+        // Proves knowledge of `x` with `x^2 = y`, where `x` also appears in
+        // the A and B linear combinations of a constraint with cancelled
+        // coefficients.
+        struct Square {
+            x: Option<Fr>,
+        }
+
+        impl Circuit<Bls12> for Square {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+                let one = cs.alloc(|| "one", || Ok(Fr::one()))?;
+                let y = cs.alloc_input(
+                    || "y",
+                    || {
+                        let mut y = self.x.ok_or(SynthesisError::AssignmentMissing)?;
+                        y.square();
+                        Ok(y)
+                    },
+                )?;
+
+                cs.enforce(|| "x^2 = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+                cs.enforce(
+                    || "one = 1",
+                    |lc| lc + one + x - x,
+                    |lc| lc + CS::one() + x - x,
+                    |lc| lc + CS::one(),
+                );
+
+                Ok(())
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let x = Fr::random(&mut rng);
+        let mut y = x;
+        y.square();
+
+        let assignment = synthesize_circuit(Square { x: Some(x) }).unwrap();
+        assert_eq!(assignment.a_aux_density().get_total_density(), 2);
+        assert_eq!(assignment.b_aux_density().get_total_density(), 1);
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Square { x: None }, &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        let proof = create_random_proof(Square { x: Some(x) }, &params, &mut rng).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[y]).unwrap());
+    }
+
+    #[test]
+    fn test_create_proof_batch_mixed() {
+        use crate::groth16::{
+            create_random_proof_batch_mixed, generate_random_parameters, prepare_verifying_key,
+            verify_proof,
+        };
+
+        // Proves knowledge of `x` such that `x^(2^n) = y`.
+        struct Squarings {
+            x: Option<Fr>,
+            n: usize,
+        }
+
+        impl Circuit<Bls12> for Squarings {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let mut value = self.x;
+                let mut var =
+                    cs.alloc(|| "x", || value.ok_or(SynthesisError::AssignmentMissing))?;
+
+                for i in 0..self.n {
+                    let prev = var;
+                    value = value.map(|mut v| {
+                        v.square();
+                        v
+                    });
+                    let get = || value.ok_or(SynthesisError::AssignmentMissing);
+                    var = if i == self.n - 1 {
+                        cs.alloc_input(|| format!("x^2^{}", i + 1), get)?
+                    } else {
+                        cs.alloc(|| format!("x^2^{}", i + 1), get)?
+                    };
+
+                    cs.enforce(
+                        || format!("square {}", i),
+                        |lc| lc + prev,
+                        |lc| lc + prev,
+                        |lc| lc + var,
+                    );
+                }
+
+                Ok(())
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let sizes = [1, 20, 5];
+        let params = sizes
+            .iter()
+            .map(|&n| generate_random_parameters::<Bls12, _, _>(Squarings { x: None, n }, &mut rng))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let xs = (0..sizes.len())
+            .map(|_| Fr::random(&mut rng))
+            .collect::<Vec<_>>();
+        let jobs = sizes
+            .iter()
+            .zip(xs.iter())
+            .zip(params.iter())
+            .map(|((&n, &x), params)| (Squarings { x: Some(x), n }, params))
+            .collect::<Vec<_>>();
+
+        let proofs = create_random_proof_batch_mixed(jobs, &mut rng).unwrap();
+        assert_eq!(proofs.len(), sizes.len());
+
+        for (((proof, params), &x), &n) in proofs
+            .iter()
+            .zip(params.iter())
+            .zip(xs.iter())
+            .zip(sizes.iter())
+        {
+            let mut y = x;
+            for _ in 0..n {
+                y.square();
+            }
+
+            let pvk = prepare_verifying_key(&params.vk);
+            assert!(verify_proof(&pvk, proof, &[y]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_create_proof_deterministic() {
+        use crate::groth16::{
+            create_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+        let circuit = || MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+
+        let r = Fr::random(&mut rng);
+        let s = Fr::random(&mut rng);
+        let proof = create_proof(circuit(), &params, r, s).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
+        assert!(proof == create_proof(circuit(), &params, r, s).unwrap());
+        assert!(proof != create_proof(circuit(), &params, s, r).unwrap());
+    }
+
+    #[test]
+    fn test_deadline_exceeded() {
+        use crate::groth16::{
+            create_random_proof, create_random_proof_with_deadline, generate_random_parameters,
+            prepare_verifying_key, verify_proofs_batch_with_deadline,
+        };
+        use std::time::Duration;
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+        let circuit = || MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+
+        let expired = Instant::now();
+        let result = create_random_proof_with_deadline(circuit(), &params, &mut rng, expired);
+        assert!(matches!(result, Err(SynthesisError::TimedOut)));
+
+        let far = Instant::now() + Duration::from_secs(3600);
+        let proof = create_random_proof_with_deadline(circuit(), &params, &mut rng, far).unwrap();
+        let other = create_random_proof(circuit(), &params, &mut rng).unwrap();
+
+        let proofs = [&proof, &other];
+        let inputs = [vec![c], vec![c]];
+        let result = verify_proofs_batch_with_deadline(&pvk, &mut rng, &proofs, &inputs, expired);
+        assert!(matches!(result, Err(SynthesisError::TimedOut)));
+        assert!(verify_proofs_batch_with_deadline(&pvk, &mut rng, &proofs, &inputs, far).unwrap());
+    }
+
+    #[test]
+    fn test_create_proof_batch_with_config() {
+        use crate::gpu::GpuOptions;
+        use crate::groth16::{
+            create_random_proof_batch_with_config, generate_random_parameters,
+            prepare_verifying_key, verify_proof, Config,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+        let circuits = || {
+            (0..2)
+                .map(|_| MulCircuit {
+                    a: Some(a),
+                    b: Some(b),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let config = Config::default()
+            .with_gpu(GpuOptions::disabled())
+            .with_priority(true);
+        let proofs =
+            create_random_proof_batch_with_config(circuits(), &params, &mut rng, &config).unwrap();
+        assert_eq!(proofs.len(), 2);
+        for proof in &proofs {
+            assert!(verify_proof(&pvk, proof, &[c]).unwrap());
+        }
+
+        let sequential = config.clone().with_sequential_multiexps(true);
+        let proofs =
+            create_random_proof_batch_with_config(circuits(), &params, &mut rng, &sequential)
+                .unwrap();
+        for proof in &proofs {
+            assert!(verify_proof(&pvk, proof, &[c]).unwrap());
+        }
+
+        let expired = config.with_deadline(Instant::now());
+        let result = create_random_proof_batch_with_config(circuits(), &params, &mut rng, &expired);
+        assert!(matches!(result, Err(SynthesisError::TimedOut)));
+    }
+
+    #[test]
+    fn test_progress_reporting() {
+        use crate::gpu::GpuOptions;
+        use crate::groth16::{
+            create_random_proof_batch_with_config, generate_random_parameters_with_progress,
+            prepare_verifying_key, verify_proof, Config, ProgressSink,
+        };
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<(String, usize, usize)>>);
+
+        impl ProgressSink for Recorder {
+            fn on_phase(&self, name: &str, done: usize, total: usize) {
+                self.0.lock().unwrap().push((name.to_string(), done, total));
+            }
+        }
+
+        impl Recorder {
+            fn finished(&self, name: &str) -> bool {
+                let events = self.0.lock().unwrap();
+                let mut events = events.iter().filter(|(n, _, _)| n == name);
+                events.clone().all(|(_, done, total)| done <= total)
+                    && events.any(|(_, done, total)| done == total)
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let recorder = Arc::new(Recorder::default());
+        let params = generate_random_parameters_with_progress::<Bls12, _, _>(
+            MulCircuit { a: None, b: None },
+            &mut rng,
+            &*recorder,
+        )
+        .unwrap();
+        for phase in &["synthesis", "powers of tau", "h", "fft", "queries"] {
+            assert!(recorder.finished(phase), "{} not reported", phase);
+        }
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+        let circuits = (0..3)
+            .map(|_| MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            })
+            .collect::<Vec<_>>();
+
+        let recorder = Arc::new(Recorder::default());
+        let config = Config::default()
+            .with_gpu(GpuOptions::disabled())
+            .with_progress(recorder.clone());
+        let proofs =
+            create_random_proof_batch_with_config(circuits, &params, &mut rng, &config).unwrap();
+        for proof in &proofs {
+            assert!(verify_proof(&pvk, proof, &[c]).unwrap());
+        }
+        for phase in &["synthesis", "fft", "multiexp"] {
+            assert!(recorder.finished(phase), "{} not reported", phase);
+        }
+        assert!(recorder
+            .0
+            .lock()
+            .unwrap()
+            .contains(&("multiexp".to_string(), 3, 3)));
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        use crate::groth16::{
+            create_random_proof_batch_from_assignments, create_random_proof_batch_with_config,
+            estimate_witness_memory, generate_random_parameters, prepare_verifying_key,
+            verify_proof,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+        let circuits = || {
+            (0..5)
+                .map(|_| MulCircuit {
+                    a: Some(a),
+                    b: Some(b),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // MulCircuit has one constraint, the inputs one and c, and the
+        // auxiliary variables a and b.
+        let one = estimate_witness_memory::<Bls12>(1, 2, 2, 1);
+        assert_eq!(estimate_witness_memory::<Bls12>(1, 2, 2, 5), 5 * one);
+        assert!(estimate_witness_memory::<Bls12>(1000, 2, 2, 1) > one);
+
+        // A budget of two proofs proves the batch in chunks.
+        let config = Config::default().with_max_witness_memory(2 * one);
+        let proofs =
+            create_random_proof_batch_with_config(circuits(), &params, &mut rng, &config).unwrap();
+        assert_eq!(proofs.len(), 5);
+        for proof in &proofs {
+            assert!(verify_proof(&pvk, proof, &[c]).unwrap());
+        }
+
+        // Synthesized witnesses all stay in memory until they are proven.
+        let assignments = circuits()
+            .into_iter()
+            .map(|circuit| synthesize_circuit(circuit).unwrap())
+            .collect::<Vec<_>>();
+        let proofs =
+            create_random_proof_batch_from_assignments(assignments, &params, &mut rng, &config);
+        assert!(matches!(
+            proofs,
+            Err(SynthesisError::MemoryBudgetExceeded(_))
+        ));
+
+        let config = Config::default().with_max_witness_memory(one - 1);
+        let proofs = create_random_proof_batch_with_config(circuits(), &params, &mut rng, &config);
+        assert!(matches!(
+            proofs,
+            Err(SynthesisError::MemoryBudgetExceeded(needed)) if needed == one
+        ));
+    }
+
+    #[test]
+    fn test_dyn_batch() {
+        use crate::groth16::{
+            create_random_proof_batch_dyn, generate_random_parameters, prepare_verifying_key,
+            verify_proof,
+        };
+
+        let mut rng = test_rng();
+
+        let mul_params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let cube_params =
+            generate_random_parameters::<Bls12, _, _>(CubeCircuit { x: None }, &mut rng).unwrap();
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+        let x = Fr::random(&mut rng);
+        let mut x3 = x;
+        x3.square();
+        x3.mul_assign(&x);
+
+        let mul = || -> Box<dyn ProverCircuit<Bls12>> {
+            Box::new(MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            })
+        };
+        let cube: Box<dyn ProverCircuit<Bls12>> = Box::new(CubeCircuit { x: Some(x) });
+        let jobs = vec![
+            (mul(), &mul_params),
+            (cube, &cube_params),
+            (mul(), &mul_params),
+        ];
+        let proofs = create_random_proof_batch_dyn(jobs, &mut rng, &Config::default()).unwrap();
+
+        let mul_pvk = prepare_verifying_key(&mul_params.vk);
+        let cube_pvk = prepare_verifying_key(&cube_params.vk);
+        assert!(verify_proof(&mul_pvk, &proofs[0], &[c]).unwrap());
+        assert!(verify_proof(&cube_pvk, &proofs[1], &[x3]).unwrap());
+        assert!(verify_proof(&mul_pvk, &proofs[2], &[c]).unwrap());
+        assert!(!verify_proof(&cube_pvk, &proofs[0], &[c]).unwrap());
+    }
+
+    #[test]
+    fn test_batch_sizes_sharing_parameters() {
+        use crate::groth16::{create_random_proof_batch, generate_random_parameters};
+
+        // Circuits of the same type but of different sizes.
+        enum Either {
+            Mul(MulCircuit),
+            Cube(CubeCircuit),
+        }
+
+        impl Circuit<Bls12> for Either {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                match self {
+                    Either::Mul(circuit) => circuit.synthesize(cs),
+                    Either::Cube(circuit) => circuit.synthesize(cs),
+                }
+            }
+        }
+
+        let mut rng = test_rng();
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+
+        let circuits = vec![
+            Either::Mul(MulCircuit {
+                a: Some(Fr::one()),
+                b: Some(Fr::one()),
+            }),
+            Either::Cube(CubeCircuit { x: Some(Fr::one()) }),
+        ];
+        assert!(matches!(
+            create_random_proof_batch(circuits, &params, &mut rng),
+            Err(SynthesisError::IncompatibleLengthVector(_))
+        ));
+    }
+
+    #[test]
+    fn test_cancellation() {
+        use crate::gpu::GpuOptions;
+        use crate::groth16::{
+            create_random_proof_batch_with_config, generate_random_parameters, CancellationToken,
+            Config, ProgressSink,
+        };
+
+        // Cancels the proof as soon as the FFT phase starts.
+        struct CancelOnFft(CancellationToken);
+
+        impl ProgressSink for CancelOnFft {
+            fn on_phase(&self, name: &str, _done: usize, _total: usize) {
+                if name == "fft" {
+                    self.0.cancel();
+                }
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let circuits = || {
+            (0..2)
+                .map(|_| MulCircuit {
+                    a: Some(a),
+                    b: Some(b),
+                })
+                .collect::<Vec<_>>()
+        };
+        let config = Config::default().with_gpu(GpuOptions::disabled());
+
+        let token = CancellationToken::new();
+        let proofs = create_random_proof_batch_with_config(
+            circuits(),
+            &params,
+            &mut rng,
+            &config.clone().with_cancellation(token.clone()),
+        )
+        .unwrap();
+        assert_eq!(proofs.len(), 2);
+        assert!(!token.is_cancelled());
+
+        token.cancel();
+        let result = create_random_proof_batch_with_config(
+            circuits(),
+            &params,
+            &mut rng,
+            &config.clone().with_cancellation(token),
+        );
+        assert!(matches!(result, Err(SynthesisError::Cancelled)));
+
+        let token = CancellationToken::new();
+        let cancelling = config
+            .with_cancellation(token.clone())
+            .with_progress(Arc::new(CancelOnFft(token)));
+        let result =
+            create_random_proof_batch_with_config(circuits(), &params, &mut rng, &cancelling);
+        assert!(matches!(result, Err(SynthesisError::Cancelled)));
+    }
+
+    #[test]
+    fn test_prove_serialized_assignments() {
+        use crate::gpu::GpuOptions;
+        use crate::groth16::{
+            create_random_proof_batch_from_assignments, generate_random_parameters,
+            prepare_verifying_key, verify_proof, Config,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let assignment = synthesize_circuit(MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        })
+        .unwrap();
+        let mut bytes = vec![];
+        assignment.write(&mut bytes).unwrap();
+        let read = ProvingAssignment::<Bls12>::read(&bytes[..]).unwrap();
+        assert_eq!(read, assignment);
+        assert!(ProvingAssignment::<Bls12>::read(&bytes[..bytes.len() - 1]).is_err());
+
+        // A density claiming far more variables than the assignments is
+        // rejected before its bits are read.
+        let num_inputs = assignment.input_assignment.len();
+        let num_aux = assignment.aux_assignment.len();
+        let densities = 3 * 4 + 2 * ((num_aux + 7) / 8) + (num_inputs + 7) / 8;
+        let mut hostile = bytes.clone();
+        let offset = hostile.len() - densities;
+        hostile[offset..offset + 4].copy_from_slice(&u32::max_value().to_be_bytes());
+        let err = ProvingAssignment::<Bls12>::read(&hostile[..])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let config = Config::default().with_gpu(GpuOptions::disabled());
+        let proofs = create_random_proof_batch_from_assignments(
+            vec![read, assignment],
+            &params,
+            &mut rng,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(proofs.len(), 2);
+        for proof in &proofs {
+            assert!(verify_proof(&pvk, proof, &[c]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_async_proving() {
+        use crate::groth16::{
+            create_random_proof_batch_async, generate_random_parameters, prepare_verifying_key,
+            verify_proofs_batch_async,
+        };
+        use crate::multicore::block_on;
+
+        let mut rng = test_rng();
+
+        let params = Arc::new(
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap(),
+        );
+        let pvk = Arc::new(prepare_verifying_key(&params.vk));
+
+        let (circuits, inputs): (Vec<_>, Vec<_>) = (0..3)
+            .map(|_| {
+                let a = Fr::random(&mut rng);
+                let b = Fr::random(&mut rng);
+                let mut c = a;
+                c.mul_assign(&b);
+                (
+                    MulCircuit {
+                        a: Some(a),
+                        b: Some(b),
+                    },
+                    vec![c],
+                )
+            })
+            .unzip();
+
+        let proofs = block_on(create_random_proof_batch_async(circuits, params, &mut rng)).unwrap();
+        assert_eq!(proofs.len(), 3);
+        assert!(block_on(verify_proofs_batch_async(
+            pvk.clone(),
+            proofs.clone(),
+            inputs
+        ))
+        .unwrap());
+
+        let wrong_inputs = vec![vec![Fr::one()]; 3];
+        assert!(!block_on(verify_proofs_batch_async(pvk, proofs, wrong_inputs)).unwrap());
+    }
+
+    #[test]
+    fn test_batch_synthesis_is_parallel() {
+        use crate::groth16::{
+            create_random_proof_batch, generate_random_parameters, prepare_verifying_key,
+            verify_proof,
+        };
+        use std::time::{Duration, Instant};
+
+        // Records how many circuits of the batch are synthesized at once.
+        struct SlowCircuit {
+            inner: MulCircuit,
+            in_flight: Arc<AtomicUsize>,
+            max_in_flight: Arc<AtomicUsize>,
+        }
+
+        impl Circuit<Bls12> for SlowCircuit {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                assert!(THREAD_POOL.current_thread_index().is_some());
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                // Waits for a second circuit to be in flight rather than
+                // sleeping, the deadline only bounds a failing run.
+                let deadline = Instant::now() + Duration::from_secs(60);
+                while THREAD_POOL.current_num_threads() > 1
+                    && self.max_in_flight.load(Ordering::SeqCst) < 2
+                    && Instant::now() < deadline
+                {
+                    std::thread::yield_now();
+                }
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                self.inner.synthesize(cs)
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let circuits = (0..4)
+            .map(|_| SlowCircuit {
+                inner: MulCircuit {
+                    a: Some(a),
+                    b: Some(b),
+                },
+                in_flight: in_flight.clone(),
+                max_in_flight: max_in_flight.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let proofs = create_random_proof_batch(circuits, &params, &mut rng).unwrap();
+        for proof in &proofs {
+            assert!(verify_proof(&pvk, proof, &[c]).unwrap());
+        }
+        if THREAD_POOL.current_num_threads() > 1 {
+            assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+        }
+    }
+
+    #[test]
+    fn test_config_workers() {
+        use crate::groth16::{
+            create_random_proof_batch_with_config, generate_random_parameters,
+            prepare_verifying_key, verify_proofs_batch_with_workers,
+        };
+        use crate::multicore::Workers;
+
+        // Checks that it is synthesized on the pool of the config.
+        struct PoolCircuit(MulCircuit);
+
+        impl Circuit<Bls12> for PoolCircuit {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let name = std::thread::current().name().map(str::to_string);
+                assert_eq!(name.as_deref(), Some("test-workers"));
+                self.0.synthesize(cs)
+            }
+        }
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .thread_name(|_| "test-workers".to_string())
+            .build()
+            .unwrap();
+        let workers = Workers::from_pool(Arc::new(pool));
+        let config = Config::default().with_workers(workers.clone());
+
+        let circuits = (0..3)
+            .map(|_| {
+                PoolCircuit(MulCircuit {
+                    a: Some(a),
+                    b: Some(b),
+                })
+            })
+            .collect::<Vec<_>>();
+        let proofs =
+            create_random_proof_batch_with_config(circuits, &params, &mut rng, &config).unwrap();
+        let proofs = proofs.iter().collect::<Vec<_>>();
+        let inputs = vec![vec![c]; 3];
+        assert!(
+            verify_proofs_batch_with_workers(&pvk, &mut rng, &proofs, &inputs, &workers).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_os_rng_entry_points() {
+        use crate::groth16::{
+            create_random_proof_os, generate_random_parameters, prepare_verifying_key,
+            verify_proofs_batch_os,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let proofs = (0..2)
+            .map(|_| {
+                create_random_proof_os(
+                    MulCircuit {
+                        a: Some(a),
+                        b: Some(b),
+                    },
+                    &params,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let proofs = proofs.iter().collect::<Vec<_>>();
+
+        assert!(verify_proofs_batch_os(&pvk, &proofs, &[vec![c], vec![c]]).unwrap());
+        assert!(!verify_proofs_batch_os(&pvk, &proofs, &[vec![c], vec![a]]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_proofs_batch_deterministic() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key,
+            verify_proofs_batch_deterministic, verify_proofs_batch_with_transcript,
+            Blake2bTranscript, Transcript,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let proofs = (0..3)
+            .map(|_| {
+                create_random_proof(
+                    MulCircuit {
+                        a: Some(a),
+                        b: Some(b),
+                    },
+                    &params,
+                    &mut rng,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let proofs = proofs.iter().collect::<Vec<_>>();
+        let inputs = vec![vec![c]; 3];
+
+        assert!(verify_proofs_batch_deterministic(&pvk, &proofs, &inputs).unwrap());
+        let mut wrong = inputs.clone();
+        wrong[2] = vec![a];
+        assert!(!verify_proofs_batch_deterministic(&pvk, &proofs, &wrong).unwrap());
+
+        // The transcript ends in the same state for the same batch.
+        let mut first = Blake2bTranscript::new(b"test");
+        let mut second = first.clone();
+        assert!(verify_proofs_batch_with_transcript(&pvk, &mut first, &proofs, &inputs).unwrap());
+        assert!(verify_proofs_batch_with_transcript(&pvk, &mut second, &proofs, &inputs).unwrap());
+        let (mut x, mut y) = ([0u8; 32], [0u8; 32]);
+        first.challenge_bytes(b"end", &mut x);
+        second.challenge_bytes(b"end", &mut y);
+        assert_eq!(x, y);
+    }
+
+    #[test]
+    fn test_verify_proof_from_bytes() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key,
+            verify_proof_from_bytes, VerificationError,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let a = Fr::random(&mut rng);
+        let b = Fr::random(&mut rng);
+        let mut c = a;
+        c.mul_assign(&b);
+
+        let proof = create_random_proof(
+            MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+
+        verify_proof_from_bytes(&pvk, &bytes, &[c]).unwrap();
+        assert!(matches!(
+            verify_proof_from_bytes(&pvk, &bytes, &[a]),
+            Err(VerificationError::InvalidProof)
+        ));
+        assert!(matches!(
+            verify_proof_from_bytes(&pvk, &bytes[1..], &[c]),
+            Err(VerificationError::MalformedProof(_))
+        ));
+        let mut corrupted = bytes.clone();
+        corrupted[0] ^= 0x40;
+        assert!(matches!(
+            verify_proof_from_bytes(&pvk, &corrupted, &[c]),
+            Err(VerificationError::MalformedProof(_))
+        ));
+        assert!(matches!(
+            verify_proof_from_bytes(&pvk, &bytes, &[c, c]),
+            Err(VerificationError::Synthesis(
+                SynthesisError::MalformedVerifyingKey
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_verify_proofs_batch_multi() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key,
+            verify_proofs_batch_multi,
+        };
+
+        let mut rng = test_rng();
+
+        // Two setups give two unrelated keys, as two circuits would.
+        let params = (0..2)
+            .map(|_| {
+                generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let pvks = params
+            .iter()
+            .map(|p| prepare_verifying_key(&p.vk))
+            .collect::<Vec<_>>();
+
+        let mut proofs = vec![];
+        let mut inputs = vec![];
+        for i in 0..3 {
+            let a = Fr::random(&mut rng);
+            let b = Fr::random(&mut rng);
+            let mut c = a;
+            c.mul_assign(&b);
+            let circuit = MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            proofs.push(create_random_proof(circuit, &params[i % 2], &mut rng).unwrap());
+            inputs.push(vec![c]);
+        }
+
+        let batch = (0..3)
+            .map(|i| (&pvks[i % 2], &proofs[i], &inputs[i][..]))
+            .collect::<Vec<_>>();
+        assert!(verify_proofs_batch_multi(&batch, &mut rng).unwrap());
+        assert!(verify_proofs_batch_multi::<Bls12, _>(&[], &mut rng).unwrap());
+
+        let mut wrong_key = batch.clone();
+        wrong_key[1].0 = &pvks[0];
+        assert!(!verify_proofs_batch_multi(&wrong_key, &mut rng).unwrap());
+
+        let mut wrong_input = batch.clone();
+        wrong_input[2].2 = &inputs[1][..];
+        assert!(!verify_proofs_batch_multi(&wrong_input, &mut rng).unwrap());
+
+        let no_input: &[Fr] = &[];
+        let mut malformed = batch;
+        malformed[0].2 = no_input;
+        assert!(matches!(
+            verify_proofs_batch_multi(&malformed, &mut rng),
+            Err(SynthesisError::MalformedVerifyingKey)
+        ));
+    }
+
+    #[test]
+    fn test_verify_proofs_batch_detailed() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key,
+            verify_proofs_batch, verify_proofs_batch_detailed,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let mut proofs = vec![];
+        let mut inputs = vec![];
+        for _ in 0..7 {
+            let a = Fr::random(&mut rng);
+            let b = Fr::random(&mut rng);
+            let mut c = a;
+            c.mul_assign(&b);
+
+            let circuit = MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            proofs.push(create_random_proof(circuit, &params, &mut rng).unwrap());
+            inputs.push(vec![c]);
+        }
+        let proofs = proofs.iter().collect::<Vec<_>>();
+
+        let invalid = verify_proofs_batch_detailed(&pvk, &mut rng, &proofs, &inputs).unwrap();
+        assert!(invalid.is_empty());
+
+        for &i in &[1, 4, 5] {
+            inputs[i][0].add_assign(&Fr::one());
+        }
+        let invalid = verify_proofs_batch_detailed(&pvk, &mut rng, &proofs, &inputs).unwrap();
+        assert_eq!(invalid, vec![1, 4, 5]);
+
+        let invalid =
+            verify_proofs_batch_detailed(&pvk, &mut rng, &proofs[..1], &inputs[..1]).unwrap();
+        assert!(invalid.is_empty());
+
+        // A proof without inputs is not reported as verified.
+        assert!(matches!(
+            verify_proofs_batch(&pvk, &mut rng, &proofs[..3], &inputs[..2]),
+            Err(SynthesisError::IncompatibleLengthVector(_))
+        ));
+        assert!(matches!(
+            verify_proofs_batch_detailed(&pvk, &mut rng, &proofs[..3], &inputs[..2]),
+            Err(SynthesisError::IncompatibleLengthVector(_))
+        ));
+    }
+
+    #[test]
+    fn test_batch_verifier() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key, BatchVerifier,
+        };
+
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let mut valid = BatchVerifier::new(&pvk);
+        let mut invalid = BatchVerifier::new(&pvk);
+        assert!(BatchVerifier::new(&pvk).finalize(&mut rng).unwrap());
+
+        for i in 0..4 {
+            let a = Fr::random(&mut rng);
+            let b = Fr::random(&mut rng);
+            let mut c = a;
+            c.mul_assign(&b);
+
+            let circuit = MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            let proof = create_random_proof(circuit, &params, &mut rng).unwrap();
+            valid.queue(proof.clone(), vec![c]).unwrap();
+            if i == 2 {
+                c.add_assign(&Fr::one());
+            }
+            invalid.queue(proof, vec![c]).unwrap();
+        }
+
+        let proof = Proof {
+            a: params.vk.alpha_g1,
+            b: params.vk.beta_g2,
+            c: params.vk.delta_g1,
+        };
+        assert!(matches!(
+            valid.queue(proof, vec![]),
+            Err(SynthesisError::MalformedVerifyingKey)
+        ));
+        assert_eq!(valid.len(), 4);
+
+        assert!(valid.finalize(&mut rng).unwrap());
+        assert_eq!(invalid.finalize_detailed(&mut rng).unwrap(), vec![2]);
+    }
 }
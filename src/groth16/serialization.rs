@@ -0,0 +1,180 @@
+//! `serde` support for the Groth16 types.
+//!
+//! Human-readable formats get the binary encoding of `write` as a hex string,
+//! other formats get the raw bytes.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use super::{
+    groth16_processed_vk_from_byteblob, groth16_processed_vk_to_byteblob, groth16vk_to_pvk,
+    Parameters, PreparedVerifyingKey, Proof, VerifyingKey,
+};
+use crate::bls::{Bls12, Engine};
+
+impl<E: Engine> Serialize for Proof<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::with_capacity(Self::size());
+        self.write(&mut bytes).map_err(serde::ser::Error::custom)?;
+        serialize_bytes(&bytes, serializer)
+    }
+}
+
+impl<'de, E: Engine> Deserialize<'de> for Proof<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_bytes(deserializer)?;
+        if bytes.len() != Self::size() {
+            return Err(de::Error::invalid_length(bytes.len(), &"a proof"));
+        }
+        Self::read(&bytes[..]).map_err(de::Error::custom)
+    }
+}
+
+impl<E: Engine> Serialize for VerifyingKey<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes).map_err(serde::ser::Error::custom)?;
+        serialize_bytes(&bytes, serializer)
+    }
+}
+
+impl<'de, E: Engine> Deserialize<'de> for VerifyingKey<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_bytes(deserializer)?;
+        Self::read(&bytes[..]).map_err(de::Error::custom)
+    }
+}
+
+impl<E: Engine> Serialize for Parameters<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes).map_err(serde::ser::Error::custom)?;
+        serialize_bytes(&bytes, serializer)
+    }
+}
+
+impl<'de, E: Engine> Deserialize<'de> for Parameters<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_bytes(deserializer)?;
+        Self::read(&bytes[..], true).map_err(de::Error::custom)
+    }
+}
+
+/// Uses the layout of `groth16_processed_vk_to_byteblob`, as the pairing
+/// result it holds can only be encoded for BLS12-381.
+impl Serialize for PreparedVerifyingKey<Bls12> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&groth16_processed_vk_to_byteblob(self), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PreparedVerifyingKey<Bls12> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_bytes(deserializer)?;
+        let vk = groth16_processed_vk_from_byteblob(&bytes).map_err(de::Error::custom)?;
+        Ok(groth16vk_to_pvk(&vk))
+    }
+}
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        let hex = bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        serializer.serialize_str(&hex)
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(BytesVisitor)
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hex string or a byte array")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        if !v.is_ascii() || v.len() % 2 != 0 {
+            return Err(E::invalid_value(de::Unexpected::Str(v), &self));
+        }
+        (0..v.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&v[i..i + 2], 16)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            })
+            .collect()
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::groth16::{generate_random_parameters, prepare_verifying_key};
+    use crate::util_cs::test_fixtures::{test_rng, MulCircuit};
+
+    #[test]
+    fn test_serde_json_roundtrip() {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let proof = Proof::<Bls12> {
+            a: params.vk.alpha_g1,
+            b: params.vk.beta_g2,
+            c: params.vk.delta_g1,
+        };
+
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(json.len(), 2 + 2 * Proof::<Bls12>::size());
+        assert!(serde_json::from_str::<Proof<Bls12>>(&json).unwrap() == proof);
+
+        let json = serde_json::to_string(&params.vk).unwrap();
+        assert!(serde_json::from_str::<VerifyingKey<Bls12>>(&json).unwrap() == params.vk);
+
+        let json = serde_json::to_string(&params).unwrap();
+        assert!(serde_json::from_str::<Parameters<Bls12>>(&json).unwrap() == params);
+
+        let pvk = prepare_verifying_key(&params.vk);
+        let json = serde_json::to_string(&pvk).unwrap();
+        let read = serde_json::from_str::<PreparedVerifyingKey<Bls12>>(&json).unwrap();
+        assert_eq!(read.alpha_g1_beta_g2, pvk.alpha_g1_beta_g2);
+        assert_eq!(read.ic, pvk.ic);
+        assert!(serde_json::from_str::<PreparedVerifyingKey<Bls12>>("\"00\"").is_err());
+
+        assert!(serde_json::from_str::<Proof<Bls12>>("\"00\"").is_err());
+        assert!(serde_json::from_str::<Proof<Bls12>>("\"0g\"").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::bls::Engine;
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective};
+use rand_core::RngCore;
+
+use super::{Proof, Trapdoor, VerifyingKey};
+use crate::SynthesisError;
+
+/// Simulates a proof for `primary_input` using the trapdoor of the setup.
+/// Only available with the `test-utils` feature.
+///
+/// No witness is needed: the resulting proof verifies against `vk` for any
+/// statement, which makes it useful for testing verifier integrations with
+/// statements that would be expensive or impossible to prove honestly. The
+/// trapdoor must be the one `vk` was generated with, see
+/// [`generate_random_parameters_with_trapdoor`](super::generate_random_parameters_with_trapdoor).
+///
+/// The simulation picks random `a` and `b`, sets `A = a * G1`, `B = b * G2`
+/// and solves the verification equation for `C`:
+/// `C = ((a * b - alpha * beta) * G1 - gamma * IC(inputs)) / delta`.
+pub fn simulate_proof<E, R>(
+    vk: &VerifyingKey<E>,
+    trapdoor: &Trapdoor<E>,
+    primary_input: &[E::Fr],
+    rng: &mut R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    R: RngCore,
+{
+    if (primary_input.len() + 1) != vk.ic.len() {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    let delta_inverse = trapdoor
+        .delta
+        .inverse()
+        .ok_or(SynthesisError::UnexpectedIdentity)?;
+
+    let a = E::Fr::random(rng);
+    let b = E::Fr::random(rng);
+
+    // Accumulate the inputs against the IC query.
+    let mut acc = vk.ic[0].into_projective();
+    for (input, ic) in primary_input.iter().zip(vk.ic.iter().skip(1)) {
+        acc.add_assign(&ic.mul(input.into_repr()));
+    }
+
+    // (a * b - alpha * beta) / delta
+    let mut c = a;
+    c.mul_assign(&b);
+    let mut alpha_beta = trapdoor.alpha;
+    alpha_beta.mul_assign(&trapdoor.beta);
+    c.sub_assign(&alpha_beta);
+    c.mul_assign(&delta_inverse);
+
+    // gamma / delta
+    let mut gamma_delta = trapdoor.gamma;
+    gamma_delta.mul_assign(&delta_inverse);
+    acc.mul_assign(gamma_delta);
+
+    let mut g_c = trapdoor.g1.mul(c);
+    g_c.sub_assign(&acc);
+
+    Ok(Proof {
+        a: trapdoor.g1.mul(a).into_affine(),
+        b: trapdoor.g2.mul(b).into_affine(),
+        c: g_c.into_affine(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{
+        generate_random_parameters_with_trapdoor, prepare_verifying_key, verify_proof,
+    };
+    use crate::util_cs::test_fixtures::MulCircuit;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn test_simulated_proof_verifies() {
+        let rng = &mut thread_rng();
+
+        let (params, trapdoor) = generate_random_parameters_with_trapdoor::<Bls12, _, _>(
+            MulCircuit { a: None, b: None },
+            rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        for _ in 0..10 {
+            // Any statement can be "proven", no witness is involved.
+            let input = Fr::random(rng);
+            let proof = simulate_proof(&params.vk, &trapdoor, &[input], rng).unwrap();
+
+            assert!(verify_proof(&pvk, &proof, &[input]).unwrap());
+            assert!(!verify_proof(&pvk, &proof, &[Fr::random(rng)]).unwrap());
+        }
+
+        assert!(simulate_proof(&params.vk, &trapdoor, &[], rng).is_err());
+    }
+}
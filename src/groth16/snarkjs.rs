@@ -0,0 +1,367 @@
+//! JSON import and export in the format of snarkjs and circom.
+//!
+//! Coordinates are decimal strings of the canonical field elements and points
+//! are given in projective coordinates, `[x, y, "1"]` for G1 and
+//! `[[x.c0, x.c1], [y.c0, y.c1], ["1", "0"]]` for G2. The point at infinity
+//! has a zero `z` coordinate. It is rejected on import, since no point of a
+//! proof or of a verifying key may be at infinity.
+
+use std::io;
+
+use groupy::{CurveAffine, EncodedPoint};
+use serde_json::{json, Value};
+
+use super::{fp_to_byteblob_with_encoding, Encoding, Proof, VerifyingKey};
+use crate::bls::{Bls12, Engine, Fq12, Fq2, Fq6, G1Affine, G2Affine};
+use crate::invalid_data;
+
+const CURVE: &str = "bls12381";
+const PROTOCOL: &str = "groth16";
+
+// Size of a big-endian base field coordinate in the uncompressed encoding.
+const COORDINATE_SIZE: usize = 48;
+
+impl Proof<Bls12> {
+    pub fn to_snarkjs_json(&self) -> String {
+        json!({
+            "pi_a": g1_to_json(&self.a),
+            "pi_b": g2_to_json(&self.b),
+            "pi_c": g1_to_json(&self.c),
+            "protocol": PROTOCOL,
+            "curve": CURVE,
+        })
+        .to_string()
+    }
+
+    pub fn from_snarkjs_json(json: &str) -> io::Result<Self> {
+        let json = parse(json)?;
+
+        Ok(Proof {
+            a: g1_from_json(field(&json, "pi_a")?)?,
+            b: g2_from_json(field(&json, "pi_b")?)?,
+            c: g1_from_json(field(&json, "pi_c")?)?,
+        })
+    }
+}
+
+impl VerifyingKey<Bls12> {
+    /// Fails if `ic` is empty, as in a malformed key.
+    pub fn to_snarkjs_json(&self) -> io::Result<String> {
+        if self.ic.is_empty() {
+            return Err(invalid_data("IC is empty"));
+        }
+        let alpha_beta = Bls12::pairing(self.alpha_g1, self.beta_g2);
+
+        Ok(json!({
+            "protocol": PROTOCOL,
+            "curve": CURVE,
+            "nPublic": self.ic.len() - 1,
+            "vk_alpha_1": g1_to_json(&self.alpha_g1),
+            "vk_beta_2": g2_to_json(&self.beta_g2),
+            "vk_gamma_2": g2_to_json(&self.gamma_g2),
+            "vk_delta_2": g2_to_json(&self.delta_g2),
+            "vk_alphabeta_12": fq12_to_json(&alpha_beta),
+            "IC": self.ic.iter().map(g1_to_json).collect::<Vec<_>>(),
+        })
+        .to_string())
+    }
+
+    /// snarkjs keys do not contain `beta_g1` and `delta_g1`, which are only
+    /// needed for proving; they are set to the point at infinity.
+    pub fn from_snarkjs_json(json: &str) -> io::Result<Self> {
+        let json = parse(json)?;
+
+        let ic = field(&json, "IC")?
+            .as_array()
+            .ok_or_else(|| invalid_data("IC is not an array"))?
+            .iter()
+            .map(g1_from_json)
+            .collect::<io::Result<Vec<_>>>()?;
+        if ic.is_empty() {
+            return Err(invalid_data("IC is empty"));
+        }
+        if let Some(n_public) = json.get("nPublic") {
+            if n_public.as_u64() != Some(ic.len() as u64 - 1) {
+                return Err(invalid_data("nPublic does not match IC"));
+            }
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1: g1_from_json(field(&json, "vk_alpha_1")?)?,
+            beta_g1: G1Affine::zero(),
+            beta_g2: g2_from_json(field(&json, "vk_beta_2")?)?,
+            gamma_g2: g2_from_json(field(&json, "vk_gamma_2")?)?,
+            delta_g1: G1Affine::zero(),
+            delta_g2: g2_from_json(field(&json, "vk_delta_2")?)?,
+            ic,
+        })
+    }
+}
+
+fn parse(json: &str) -> io::Result<Value> {
+    let json: Value = serde_json::from_str(json).map_err(|e| invalid_data(&e.to_string()))?;
+
+    if let Some(protocol) = json.get("protocol") {
+        if protocol != PROTOCOL {
+            return Err(invalid_data("unsupported protocol"));
+        }
+    }
+    if let Some(curve) = json.get("curve") {
+        if curve != CURVE {
+            return Err(invalid_data("unsupported curve"));
+        }
+    }
+
+    Ok(json)
+}
+
+fn field<'a>(json: &'a Value, name: &str) -> io::Result<&'a Value> {
+    json.get(name)
+        .ok_or_else(|| invalid_data(&format!("missing field {}", name)))
+}
+
+fn g1_to_json(point: &G1Affine) -> Value {
+    if point.is_zero() {
+        return json!(["0", "1", "0"]);
+    }
+
+    let bytes = point.into_uncompressed();
+    let (x, y) = bytes.as_ref().split_at(COORDINATE_SIZE);
+    json!([to_decimal(x), to_decimal(y), "1"])
+}
+
+fn g2_to_json(point: &G2Affine) -> Value {
+    if point.is_zero() {
+        return json!([["0", "0"], ["1", "0"], ["0", "0"]]);
+    }
+
+    // The uncompressed encoding holds x.c1, x.c0, y.c1, y.c0.
+    let bytes = point.into_uncompressed();
+    let c = bytes
+        .as_ref()
+        .chunks(COORDINATE_SIZE)
+        .map(to_decimal)
+        .collect::<Vec<_>>();
+    json!([[c[1], c[0]], [c[3], c[2]], ["1", "0"]])
+}
+
+fn g1_from_json(json: &Value) -> io::Result<G1Affine> {
+    let coordinates = strings(json, 3)?;
+    if is_zero(coordinates[2]) {
+        return Err(invalid_data("G1 point is the point at infinity"));
+    }
+    if coordinates[2] != "1" {
+        return Err(invalid_data("G1 point is not normalized"));
+    }
+
+    let mut repr = <G1Affine as CurveAffine>::Uncompressed::empty();
+    let (x, y) = repr.as_mut().split_at_mut(COORDINATE_SIZE);
+    from_decimal(coordinates[0], x)?;
+    from_decimal(coordinates[1], y)?;
+
+    repr.into_affine()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn g2_from_json(json: &Value) -> io::Result<G2Affine> {
+    let pairs = json
+        .as_array()
+        .filter(|pairs| pairs.len() == 3)
+        .ok_or_else(|| invalid_data("G2 point is not an array of three pairs"))?;
+    let x = strings(&pairs[0], 2)?;
+    let y = strings(&pairs[1], 2)?;
+    let z = strings(&pairs[2], 2)?;
+    if is_zero(z[0]) && is_zero(z[1]) {
+        return Err(invalid_data("G2 point is the point at infinity"));
+    }
+    if z[0] != "1" || !is_zero(z[1]) {
+        return Err(invalid_data("G2 point is not normalized"));
+    }
+
+    let mut repr = <G2Affine as CurveAffine>::Uncompressed::empty();
+    for (chunk, coordinate) in repr
+        .as_mut()
+        .chunks_mut(COORDINATE_SIZE)
+        .zip(&[x[1], x[0], y[1], y[0]])
+    {
+        from_decimal(coordinate, chunk)?;
+    }
+
+    repr.into_affine()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn fq12_to_json(fq12: &Fq12) -> Value {
+    let fq6 = |fq6: &Fq6| -> Value {
+        let fq2 = |fq2: &Fq2| -> Value {
+            json!([
+                to_decimal(&fp_to_byteblob_with_encoding(&fq2.c0, Encoding::BIG_ENDIAN)),
+                to_decimal(&fp_to_byteblob_with_encoding(&fq2.c1, Encoding::BIG_ENDIAN)),
+            ])
+        };
+        json!([fq2(&fq6.c0), fq2(&fq6.c1), fq2(&fq6.c2)])
+    };
+    json!([fq6(&fq12.c0), fq6(&fq12.c1)])
+}
+
+fn strings(json: &Value, len: usize) -> io::Result<Vec<&str>> {
+    json.as_array()
+        .filter(|values| values.len() == len)
+        .and_then(|values| values.iter().map(Value::as_str).collect::<Option<Vec<_>>>())
+        .ok_or_else(|| invalid_data(&format!("expected an array of {} strings", len)))
+}
+
+fn is_zero(decimal: &str) -> bool {
+    !decimal.is_empty() && decimal.bytes().all(|c| c == b'0')
+}
+
+/// Formats a big-endian unsigned integer in decimal.
+fn to_decimal(bytes: &[u8]) -> String {
+    let mut n = bytes.to_vec();
+    let mut digits = Vec::new();
+    while n.iter().any(|&b| b != 0) {
+        let mut rem = 0u32;
+        for b in n.iter_mut() {
+            let cur = (rem << 8) | *b as u32;
+            *b = (cur / 10) as u8;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.reverse();
+
+    String::from_utf8(digits).unwrap()
+}
+
+/// Parses a decimal string into a big-endian coordinate, leaving the flag
+/// bits of the uncompressed encoding clear.
+fn from_decimal(decimal: &str, out: &mut [u8]) -> io::Result<()> {
+    if decimal.is_empty() || !decimal.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(invalid_data("invalid decimal string"));
+    }
+
+    for b in out.iter_mut() {
+        *b = 0;
+    }
+    for c in decimal.bytes() {
+        let mut carry = (c - b'0') as u32;
+        for b in out.iter_mut().rev() {
+            let cur = *b as u32 * 10 + carry;
+            *b = cur as u8;
+            carry = cur >> 8;
+        }
+        if carry != 0 {
+            return Err(invalid_data("coordinate out of range"));
+        }
+    }
+    if out[0] & 0xe0 != 0 {
+        return Err(invalid_data("coordinate out of range"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ff::Field;
+    use groupy::CurveProjective;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::bls::{Fr, G1Projective, G2Projective};
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(to_decimal(&[0, 0]), "0");
+        assert_eq!(to_decimal(&[1, 0]), "256");
+        assert_eq!(to_decimal(&[0xff; 8]), "18446744073709551615");
+
+        let mut out = [0u8; 8];
+        from_decimal("18446744073709551615", &mut out).unwrap();
+        assert_eq!(out, [0xff; 8]);
+        assert!(from_decimal("18446744073709551616", &mut out).is_err());
+        assert!(from_decimal("12a", &mut out).is_err());
+        assert!(from_decimal("", &mut out).is_err());
+    }
+
+    #[test]
+    fn test_snarkjs_json_roundtrip() {
+        let mut rng = test_rng();
+
+        let g1 = |rng: &mut XorShiftRng| G1Projective::one().mul(Fr::random(rng)).into_affine();
+        let g2 = |rng: &mut XorShiftRng| G2Projective::one().mul(Fr::random(rng)).into_affine();
+
+        let proof = Proof::<Bls12> {
+            a: g1(&mut rng),
+            b: g2(&mut rng),
+            c: g1(&mut rng),
+        };
+        let json = proof.to_snarkjs_json();
+        assert!(Proof::from_snarkjs_json(&json).unwrap() == proof);
+
+        let vk = VerifyingKey::<Bls12> {
+            alpha_g1: g1(&mut rng),
+            beta_g1: G1Affine::zero(),
+            beta_g2: g2(&mut rng),
+            gamma_g2: g2(&mut rng),
+            delta_g1: G1Affine::zero(),
+            delta_g2: g2(&mut rng),
+            ic: vec![g1(&mut rng), g1(&mut rng), g1(&mut rng)],
+        };
+        let json = vk.to_snarkjs_json().unwrap();
+        assert!(VerifyingKey::from_snarkjs_json(&json).unwrap() == vk);
+
+        let mut empty = vk.clone();
+        empty.ic.clear();
+        assert!(empty.to_snarkjs_json().is_err());
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["nPublic"], 2);
+
+        // No point may be at infinity.
+        let mut infinite = value.clone();
+        infinite["IC"][2] = json!(["0", "1", "0"]);
+        assert!(VerifyingKey::from_snarkjs_json(&infinite.to_string()).is_err());
+        let mut infinite = value;
+        infinite["vk_delta_2"] = json!([["0", "0"], ["1", "0"], ["0", "0"]]);
+        assert!(VerifyingKey::from_snarkjs_json(&infinite.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_snarkjs_json_generator() {
+        // The generator of G1 as printed by snarkjs for bls12381.
+        let json = json!({
+            "pi_a": [
+                "3685416753713387016781088315183077757961620795782546409894578378688607592378376318836054947676345821548104185464507",
+                "1339506544944476473020471379941921221584933875938349620426543736416511423956333506472724655353366534992391756441569",
+                "1"
+            ],
+            "pi_b": g2_to_json(&G2Affine::one()),
+            "pi_c": g1_to_json(&G1Affine::one()),
+            "protocol": "groth16",
+            "curve": "bls12381"
+        });
+
+        let proof = Proof::<Bls12>::from_snarkjs_json(&json.to_string()).unwrap();
+        assert_eq!(proof.a, G1Affine::one());
+        assert_eq!(proof.b, G2Affine::one());
+        assert_eq!(proof.c, G1Affine::one());
+
+        let mut infinite = json.clone();
+        infinite["pi_c"] = json!(["0", "1", "0"]);
+        assert!(Proof::<Bls12>::from_snarkjs_json(&infinite.to_string()).is_err());
+
+        let mut wrong_curve = json.clone();
+        wrong_curve["curve"] = json!("bn128");
+        assert!(Proof::<Bls12>::from_snarkjs_json(&wrong_curve.to_string()).is_err());
+
+        let mut off_curve = json;
+        off_curve["pi_a"][1] = json!("1");
+        assert!(Proof::<Bls12>::from_snarkjs_json(&off_curve.to_string()).is_err());
+    }
+}
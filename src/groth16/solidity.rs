@@ -0,0 +1,353 @@
+//! Generation of a Solidity verifier contract for a verifying key.
+//!
+//! The contract uses the BLS12-381 precompiles of EIP-2537 (`BLS12_G1MSM` at
+//! `0x0c` and `BLS12_PAIRING_CHECK` at `0x0f`), so it can only be deployed on
+//! chains that have activated them. Points are passed in the encoding of
+//! EIP-2537: every base field element is 64 bytes, the big-endian value padded
+//! with 16 leading zero bytes, and the coordinates of G2 are given as
+//! `c0 || c1`. `proof_calldata` encodes a proof in this layout.
+//!
+//! `VerifyingKey::fingerprint` identifies a key by the Keccak-256 and SHA-256
+//! hashes of the concatenation, in this encoding, of
+//!
+//! ```text
+//! alpha_g1 || beta_g2 || gamma_g2 || delta_g2 || ic[0] || ... || ic[n]
+//! ```
+//!
+//! which is 896 + 128 * (n + 1) bytes. The G2 points are not negated. A
+//! contract reproduces it as `keccak256(abi.encodePacked(alpha, beta, gamma,
+//! delta, ic0, ..., icn))`, or with `sha256` for the other hash. The
+//! generated verifier holds it as `VK_FINGERPRINT`.
+
+use std::fmt::Write;
+use std::io;
+
+use groupy::{CurveAffine, EncodedPoint};
+
+use sha2::{Digest, Sha256};
+use tiny_keccak::{Hasher, Keccak};
+
+use super::{Proof, VerifyingKey};
+use crate::bls::{Bls12, G1Affine, G2Affine};
+use crate::invalid_data;
+
+// Size of a big-endian base field coordinate in the uncompressed encoding.
+const COORDINATE_SIZE: usize = 48;
+// Size of a base field element in the encoding of EIP-2537.
+const FP_SIZE: usize = 64;
+
+// The scalar field modulus, inputs must be reduced to keep proofs unique.
+const R: &str = "0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+/// Returns the source of a contract `Verifier` with a function
+/// `verifyProof(bytes calldata proof, uint256[N] calldata input)` checking
+/// proofs for the key `vk`, where `N` is its number of public inputs. Keys
+/// without public inputs get `verifyProof(bytes calldata proof)`.
+///
+/// The pairing check is `e(A, B) e(alpha, -beta) e(acc, -gamma) e(C, -delta)
+/// == 1`, the G2 points of the key are negated here so that the contract does
+/// no field arithmetic itself.
+///
+/// Fails if `ic` is empty, as in a malformed key.
+pub fn generate_verifier_contract(vk: &VerifyingKey<Bls12>) -> io::Result<String> {
+    let num_inputs = vk
+        .ic
+        .len()
+        .checked_sub(1)
+        .ok_or_else(|| invalid_data("IC is empty"))?;
+
+    let mut constants = String::new();
+    let mut g1 = |name: &str, p: &G1Affine| {
+        writeln!(
+            constants,
+            "    bytes constant {} = hex\"{}\";",
+            name,
+            to_hex(&g1_to_bytes(p))
+        )
+        .unwrap();
+    };
+    g1("ALPHA", &vk.alpha_g1);
+    for (i, ic) in vk.ic.iter().enumerate() {
+        g1(&format!("IC{}", i), ic);
+    }
+    let mut g2 = |name: &str, p: &G2Affine| {
+        let mut p = *p;
+        p.negate();
+        writeln!(
+            constants,
+            "    bytes constant {} = hex\"{}\";",
+            name,
+            to_hex(&g2_to_bytes(&p))
+        )
+        .unwrap();
+    };
+    g2("NEG_BETA", &vk.beta_g2);
+    g2("NEG_GAMMA", &vk.gamma_g2);
+    g2("NEG_DELTA", &vk.delta_g2);
+
+    // Solidity has no arrays of length zero.
+    let input = if num_inputs == 0 {
+        String::new()
+    } else {
+        format!(", uint256[{}] calldata input", num_inputs)
+    };
+
+    // The input accumulator is `IC0 + sum(input[i] * IC{i + 1})`, computed by
+    // a single multi-scalar multiplication.
+    let mut checks = String::new();
+    let mut terms = String::from("            IC0, uint256(1)");
+    for i in 0..num_inputs {
+        writeln!(
+            checks,
+            "        require(input[{}] < R, \"input not in field\");",
+            i
+        )
+        .unwrap();
+        write!(terms, ",\n            IC{}, input[{}]", i + 1, i).unwrap();
+    }
+
+    Ok(format!(
+        r#"// SPDX-License-Identifier: MIT
+// Generated by crusty3_zk, do not edit.
+pragma solidity ^0.8.0;
+
+contract Verifier {{
+    uint256 constant R = {r};
+
+    /// Keccak-256 fingerprint of the verifying key.
+    bytes32 public constant VK_FINGERPRINT = 0x{fingerprint};
+
+    address constant G1_MSM = address(0x0c);
+    address constant PAIRING_CHECK = address(0x0f);
+
+{constants}
+    /// `proof` is `A || B || C` in the encoding of EIP-2537 (512 bytes).
+    function verifyProof(bytes calldata proof{input})
+        public
+        view
+        returns (bool)
+    {{
+        require(proof.length == 512, "invalid proof length");
+{checks}
+        bytes memory msm = abi.encodePacked(
+{terms}
+        );
+        (bool ok, bytes memory acc) = G1_MSM.staticcall(msm);
+        require(ok && acc.length == 128, "input accumulation failed");
+
+        bytes memory pairing = abi.encodePacked(
+            proof[0:384], // A, B
+            ALPHA, NEG_BETA,
+            acc, NEG_GAMMA,
+            proof[384:512], NEG_DELTA // C
+        );
+        bytes memory result;
+        (ok, result) = PAIRING_CHECK.staticcall(pairing);
+        require(ok && result.length == 32, "pairing check failed");
+
+        return abi.decode(result, (uint256)) == 1;
+    }}
+}}
+"#,
+        r = R,
+        fingerprint = to_hex(&vk.fingerprint().keccak256),
+        constants = constants,
+        input = input,
+        checks = checks,
+        terms = terms,
+    ))
+}
+
+/// Encodes `proof` as the `proof` argument of the generated `verifyProof`.
+pub fn proof_calldata(proof: &Proof<Bls12>) -> Vec<u8> {
+    let mut bytes = g1_to_bytes(&proof.a);
+    bytes.extend(g2_to_bytes(&proof.b));
+    bytes.extend(g1_to_bytes(&proof.c));
+    bytes
+}
+
+/// Hashes of the encoding of a verifying key, see the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    pub keccak256: [u8; 32],
+    pub sha256: [u8; 32],
+}
+
+impl VerifyingKey<Bls12> {
+    /// The encoding of the key hashed by `fingerprint`.
+    pub fn fingerprint_encoding(&self) -> Vec<u8> {
+        let mut bytes = g1_to_bytes(&self.alpha_g1);
+        bytes.extend(g2_to_bytes(&self.beta_g2));
+        bytes.extend(g2_to_bytes(&self.gamma_g2));
+        bytes.extend(g2_to_bytes(&self.delta_g2));
+        for ic in &self.ic {
+            bytes.extend(g1_to_bytes(ic));
+        }
+        bytes
+    }
+
+    /// Keccak-256 and SHA-256 hashes identifying the key, as a contract
+    /// computes them from the points of the key.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let bytes = self.fingerprint_encoding();
+        Fingerprint {
+            keccak256: keccak256(&bytes),
+            sha256: sha256(&bytes),
+        }
+    }
+}
+
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(bytes);
+
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hash
+}
+
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(bytes));
+    hash
+}
+
+// The point at infinity is encoded as zeros.
+fn g1_to_bytes(p: &G1Affine) -> Vec<u8> {
+    if p.is_zero() {
+        return vec![0; 2 * FP_SIZE];
+    }
+    let uncompressed = p.into_uncompressed();
+    uncompressed
+        .as_ref()
+        .chunks(COORDINATE_SIZE)
+        .flat_map(pad)
+        .collect()
+}
+
+// The uncompressed encoding has the order `x.c1, x.c0, y.c1, y.c0`.
+fn g2_to_bytes(p: &G2Affine) -> Vec<u8> {
+    if p.is_zero() {
+        return vec![0; 4 * FP_SIZE];
+    }
+    let uncompressed = p.into_uncompressed();
+    let c = uncompressed
+        .as_ref()
+        .chunks(COORDINATE_SIZE)
+        .collect::<Vec<_>>();
+    [c[1], c[0], c[3], c[2]]
+        .iter()
+        .flat_map(|c| pad(c))
+        .collect()
+}
+
+fn pad(coordinate: &[u8]) -> Vec<u8> {
+    let mut padded = vec![0; FP_SIZE - coordinate.len()];
+    padded.extend_from_slice(coordinate);
+    padded
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ff::PrimeField;
+    use groupy::CurveProjective;
+
+    use crate::bls::{Fr, G1Projective};
+
+    fn vk(num_inputs: usize) -> VerifyingKey<Bls12> {
+        let mut ic = vec![];
+        for i in 0..num_inputs + 1 {
+            let mut p = G1Projective::one();
+            p.mul_assign(Fr::from_str(&(i + 2).to_string()).unwrap());
+            ic.push(p.into_affine());
+        }
+
+        VerifyingKey {
+            alpha_g1: G1Affine::one(),
+            beta_g1: G1Affine::one(),
+            beta_g2: G2Affine::one(),
+            gamma_g2: G2Affine::one(),
+            delta_g1: G1Affine::one(),
+            delta_g2: G2Affine::one(),
+            ic,
+        }
+    }
+
+    #[test]
+    fn test_generate_verifier_contract() {
+        let contract = generate_verifier_contract(&vk(2)).unwrap();
+
+        assert!(contract.contains("uint256[2] calldata input"));
+        assert!(contract.contains("IC2, input[1]"));
+        assert!(contract.contains("require(input[1] < R"));
+        assert!(!contract.contains("IC3"));
+        assert_eq!(contract.matches('{').count(), contract.matches('}').count());
+
+        // The modulus constant is the one of the scalar field.
+        assert_eq!(format!("{}", Fr::char()), R);
+
+        let fingerprint = to_hex(&vk(2).fingerprint().keccak256);
+        assert!(contract.contains(&format!("VK_FINGERPRINT = 0x{};", fingerprint)));
+
+        let contract = generate_verifier_contract(&vk(0)).unwrap();
+        assert!(contract.contains("verifyProof(bytes calldata proof)"));
+        assert!(contract.contains("IC0, uint256(1)\n        );"));
+
+        let mut empty = vk(0);
+        empty.ic.clear();
+        assert!(generate_verifier_contract(&empty).is_err());
+    }
+
+    #[test]
+    fn test_eip2537_encoding() {
+        let proof = Proof::<Bls12> {
+            a: G1Affine::one(),
+            b: G2Affine::one(),
+            c: G1Affine::zero(),
+        };
+        let bytes = proof_calldata(&proof);
+        assert_eq!(bytes.len(), 512);
+
+        // Generator x coordinate, padded to 64 bytes.
+        assert_eq!(&bytes[..16], &[0; 16]);
+        assert_eq!(&bytes[16..20], &[0x17, 0xf1, 0xd3, 0xa7]);
+        // x.c0 of the G2 generator comes first.
+        assert_eq!(&bytes[128 + 16..128 + 20], &[0x02, 0x4a, 0xa2, 0xb2]);
+        assert_eq!(
+            &bytes[128 + 64 + 16..128 + 64 + 20],
+            &[0x13, 0xe0, 0x2b, 0x60]
+        );
+        assert!(bytes[384..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(
+            to_hex(&keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            to_hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let key = vk(2);
+        let bytes = key.fingerprint_encoding();
+        assert_eq!(bytes.len(), 896 + 3 * 128);
+        assert_eq!(&bytes[..128], &g1_to_bytes(&G1Affine::one())[..]);
+        // beta is not negated, unlike in the contract constants.
+        assert_eq!(&bytes[128..384], &g2_to_bytes(&G2Affine::one())[..]);
+        assert_eq!(&bytes[896 + 2 * 128..], &g1_to_bytes(&key.ic[2])[..]);
+
+        let fingerprint = key.fingerprint();
+        assert_eq!(fingerprint.keccak256, keccak256(&bytes));
+        assert_eq!(fingerprint.sha256, sha256(&bytes));
+        assert_ne!(vk(1).fingerprint(), fingerprint);
+    }
+}
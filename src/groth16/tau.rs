@@ -0,0 +1,301 @@
+//! Powers of tau from a phase 1 ceremony.
+//!
+//! The accumulators of the powers of tau ceremony for BLS12-381 start with
+//! the 64-byte BLAKE2b hash of the previous step, followed by the points
+//! below for a ceremony of `N` powers, all compressed or all uncompressed:
+//!
+//! - `tau^i` in G1 for `i < 2N - 1`,
+//! - `tau^i` in G2 for `i < N`,
+//! - `alpha * tau^i` in G1 for `i < N`,
+//! - `beta * tau^i` in G1 for `i < N`,
+//! - `beta` in G2.
+//!
+//! A response file has the public key of the contribution after these, which
+//! is not read. The transcript has to be verified with the tools of the
+//! ceremony before its powers are used.
+
+use std::io::{self, Read};
+
+use groupy::{CurveAffine, EncodedPoint};
+use rayon::prelude::*;
+
+use crate::bls::Engine;
+
+/// The first `size` powers of tau of a ceremony, enough for circuits with
+/// up to `size` constraints and inputs.
+pub struct TauPowers<E: Engine> {
+    /// `tau^i` in G1 for `i < 2 * size - 1`.
+    pub tau_g1: Vec<E::G1Affine>,
+    /// `tau^i` in G2 for `i < size`.
+    pub tau_g2: Vec<E::G2Affine>,
+    /// `alpha * tau^i` in G1 for `i < size`.
+    pub alpha_tau_g1: Vec<E::G1Affine>,
+    /// `beta * tau^i` in G1 for `i < size`.
+    pub beta_tau_g1: Vec<E::G1Affine>,
+    pub beta_g2: E::G2Affine,
+}
+
+impl<E: Engine> Clone for TauPowers<E> {
+    fn clone(&self) -> Self {
+        TauPowers {
+            tau_g1: self.tau_g1.clone(),
+            tau_g2: self.tau_g2.clone(),
+            alpha_tau_g1: self.alpha_tau_g1.clone(),
+            beta_tau_g1: self.beta_tau_g1.clone(),
+            beta_g2: self.beta_g2,
+        }
+    }
+}
+
+impl<E: Engine> TauPowers<E> {
+    pub fn size(&self) -> usize {
+        self.tau_g2.len()
+    }
+
+    /// Reads the first `size` powers of the accumulator of a ceremony of
+    /// `ceremony_size` powers. `checked` enables the curve and subgroup
+    /// checks of the points.
+    pub fn read<R: Read>(
+        mut reader: R,
+        ceremony_size: usize,
+        size: usize,
+        compressed: bool,
+        checked: bool,
+    ) -> io::Result<Self> {
+        if size == 0 || size > ceremony_size || !size.is_power_of_two() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "cannot read {} powers from a ceremony of {}",
+                    size, ceremony_size
+                ),
+            ));
+        }
+
+        skip(&mut reader, 64)?;
+        let tau_g1 = read_points(&mut reader, 2 * size - 1, compressed, checked)?;
+        skip_points::<E::G1Affine, _>(&mut reader, 2 * (ceremony_size - size), compressed)?;
+        let tau_g2 = read_points(&mut reader, size, compressed, checked)?;
+        skip_points::<E::G2Affine, _>(&mut reader, ceremony_size - size, compressed)?;
+        let alpha_tau_g1 = read_points(&mut reader, size, compressed, checked)?;
+        skip_points::<E::G1Affine, _>(&mut reader, ceremony_size - size, compressed)?;
+        let beta_tau_g1 = read_points(&mut reader, size, compressed, checked)?;
+        skip_points::<E::G1Affine, _>(&mut reader, ceremony_size - size, compressed)?;
+        let beta_g2 = read_points(&mut reader, 1, compressed, checked)?[0];
+
+        Ok(TauPowers {
+            tau_g1,
+            tau_g2,
+            alpha_tau_g1,
+            beta_tau_g1,
+            beta_g2,
+        })
+    }
+}
+
+fn skip<R: Read>(reader: &mut R, len: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(len), &mut io::sink())? < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(())
+}
+
+fn point_size<G: CurveAffine>(compressed: bool) -> usize {
+    if compressed {
+        G::Compressed::size()
+    } else {
+        G::Uncompressed::size()
+    }
+}
+
+fn skip_points<G: CurveAffine, R: Read>(
+    reader: &mut R,
+    count: usize,
+    compressed: bool,
+) -> io::Result<()> {
+    skip(reader, (count * point_size::<G>(compressed)) as u64)
+}
+
+fn read_points<G: CurveAffine, R: Read>(
+    reader: &mut R,
+    count: usize,
+    compressed: bool,
+    checked: bool,
+) -> io::Result<Vec<G>> {
+    fn decode<P: EncodedPoint>(bytes: &[u8], checked: bool) -> io::Result<P::Affine> {
+        let mut repr = P::empty();
+        repr.as_mut().copy_from_slice(bytes);
+        let point = if checked {
+            repr.into_affine()
+        } else {
+            repr.into_affine_unchecked()
+        }
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if point.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "point at infinity",
+            ));
+        }
+        Ok(point)
+    }
+
+    let size = point_size::<G>(compressed);
+    let mut bytes = vec![0u8; count * size];
+    reader.read_exact(&mut bytes)?;
+
+    bytes
+        .par_chunks(size)
+        .map(|bytes| {
+            if compressed {
+                decode::<G::Compressed>(bytes, checked)
+            } else {
+                decode::<G::Uncompressed>(bytes, checked)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr, G1Affine, G2Affine};
+    use crate::groth16::{
+        create_random_proof, generate_parameters, generate_parameters_from_tau,
+        prepare_verifying_key, verify_proof,
+    };
+    use crate::util_cs::test_fixtures::{test_rng, CubeCircuit};
+    use crate::SynthesisError;
+    use ff::{Field, PrimeField};
+    use groupy::CurveProjective;
+    use std::io::Write;
+
+    fn powers(x: Fr, len: usize) -> Vec<Fr> {
+        let mut powers = vec![Fr::one()];
+        for i in 1..len {
+            let mut power = powers[i - 1];
+            power.mul_assign(&x);
+            powers.push(power);
+        }
+        powers
+    }
+
+    // An accumulator of a ceremony of `size` powers for the given secrets.
+    fn accumulator(tau: Fr, alpha: Fr, beta: Fr, size: usize, compressed: bool) -> Vec<u8> {
+        let mut bytes = vec![7u8; 64];
+        let g1 = |bytes: &mut Vec<u8>, scalar: Fr| {
+            let point = G1Affine::one().mul(scalar).into_affine();
+            if compressed {
+                bytes.write_all(point.into_compressed().as_ref())
+            } else {
+                bytes.write_all(point.into_uncompressed().as_ref())
+            }
+            .unwrap();
+        };
+        let tau_powers = powers(tau, 2 * size - 1);
+        for power in &tau_powers {
+            g1(&mut bytes, *power);
+        }
+        let g2 = |bytes: &mut Vec<u8>, scalar: Fr| {
+            let point = G2Affine::one().mul(scalar).into_affine();
+            if compressed {
+                bytes.write_all(point.into_compressed().as_ref())
+            } else {
+                bytes.write_all(point.into_uncompressed().as_ref())
+            }
+            .unwrap();
+        };
+        for power in &tau_powers[..size] {
+            g2(&mut bytes, *power);
+        }
+        for secret in &[alpha, beta] {
+            for power in &tau_powers[..size] {
+                let mut scalar = *power;
+                scalar.mul_assign(secret);
+                g1(&mut bytes, scalar);
+            }
+        }
+        g2(&mut bytes, beta);
+
+        bytes
+    }
+
+    #[test]
+    fn test_read_powers() {
+        let mut rng = test_rng();
+        let (tau, alpha, beta) = (
+            Fr::random(&mut rng),
+            Fr::random(&mut rng),
+            Fr::random(&mut rng),
+        );
+
+        for &compressed in &[false, true] {
+            let bytes = accumulator(tau, alpha, beta, 8, compressed);
+            let powers = TauPowers::<Bls12>::read(&bytes[..], 8, 4, compressed, true).unwrap();
+            assert_eq!(powers.size(), 4);
+            assert_eq!(powers.tau_g1.len(), 7);
+
+            assert_eq!(
+                powers.tau_g1[6],
+                G1Affine::one().mul(tau.pow(&[6])).into_affine()
+            );
+            let mut alpha_tau_3 = tau.pow(&[3]);
+            alpha_tau_3.mul_assign(&alpha);
+            assert_eq!(
+                powers.alpha_tau_g1[3],
+                G1Affine::one().mul(alpha_tau_3).into_affine()
+            );
+            assert_eq!(powers.beta_g2, G2Affine::one().mul(beta).into_affine());
+
+            assert!(TauPowers::<Bls12>::read(&bytes[..], 8, 16, compressed, true).is_err());
+            assert!(TauPowers::<Bls12>::read(&bytes[..], 8, 3, compressed, true).is_err());
+            assert!(
+                TauPowers::<Bls12>::read(&bytes[..bytes.len() - 1], 8, 4, compressed, true)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parameters_from_tau() {
+        let mut rng = test_rng();
+        let (tau, alpha, beta) = (
+            Fr::random(&mut rng),
+            Fr::random(&mut rng),
+            Fr::random(&mut rng),
+        );
+
+        let bytes = accumulator(tau, alpha, beta, 8, true);
+        let powers = TauPowers::<Bls12>::read(&bytes[..], 8, 4, true, true).unwrap();
+        let params = generate_parameters_from_tau(CubeCircuit { x: None }, &powers).unwrap();
+
+        // The same as from the secrets directly, with `gamma = delta = 1`.
+        let expected = generate_parameters::<Bls12, _>(
+            CubeCircuit { x: None },
+            G1Affine::one().into_projective(),
+            G2Affine::one().into_projective(),
+            alpha,
+            beta,
+            Fr::one(),
+            Fr::one(),
+            tau,
+        )
+        .unwrap();
+        assert!(params == expected);
+
+        let x = Fr::from_str("3").unwrap();
+        let proof = create_random_proof(CubeCircuit { x: Some(x) }, &params, &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        assert!(verify_proof(&pvk, &proof, &[Fr::from_str("27").unwrap()]).unwrap());
+
+        // Two constraints and two inputs need four powers.
+        let powers = TauPowers::<Bls12>::read(&bytes[..], 8, 2, true, true).unwrap();
+        assert!(matches!(
+            generate_parameters_from_tau(CubeCircuit { x: None }, &powers),
+            Err(SynthesisError::PolynomialDegreeTooLarge)
+        ));
+    }
+}
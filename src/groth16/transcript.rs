@@ -0,0 +1,31 @@
+use ff::{PrimeField, PrimeFieldRepr};
+
+use super::{PreparedVerifyingKey, Proof};
+use crate::bls::Engine;
+use crate::transcript::Transcript;
+
+/// Appends the key, the proofs and their inputs to `transcript`.
+pub(crate) fn append_batch<E: Engine, T: Transcript>(
+    transcript: &mut T,
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+) {
+    transcript.append_point(b"gamma", &pvk.gamma_g2);
+    transcript.append_point(b"delta", &pvk.delta_g2);
+    for ic in &pvk.ic {
+        transcript.append_point(b"ic", ic);
+    }
+
+    for (proof, inputs) in proofs.iter().zip(primary_input) {
+        transcript.append_point(b"a", &proof.a);
+        transcript.append_point(b"b", &proof.b);
+        transcript.append_point(b"c", &proof.c);
+
+        let mut bytes = vec![];
+        for input in inputs {
+            input.into_repr().write_le(&mut bytes).unwrap();
+        }
+        transcript.append_message(b"inputs", &bytes);
+    }
+}
@@ -4,10 +4,17 @@ use groupy::{CurveAffine, CurveProjective, EncodedPoint};
 use rayon::prelude::*;
 
 use super::{multiscalar, PreparedVerifyingKey, Proof, VerifyingKey, GROTH16VerificationKey, 
-            groth16_vk_from_byteblob, groth16_proof_from_byteblob, groth16_primary_input_from_byteblob, std_size_t_process};
+            groth16_processed_vk_from_byteblob, groth16_proof_from_byteblob, groth16_primary_input_from_byteblob, std_size_t_process};
 
-use crate::multicore::VERIFIER_POOL as POOL;
+use super::check_deadline;
+use super::transcript::append_batch;
+use crate::multicore::{Task, Workers, VERIFIER_POOL as POOL};
+use crate::transcript::{Blake2bTranscript, Transcript, TranscriptRng};
 use crate::SynthesisError;
+use rand::rngs::OsRng;
+use std::io;
+use std::sync::Arc;
+use std::time::Instant;
 
 /// Generate a prepared verifying key, required to verify a proofs.
 pub fn prepare_verifying_key<E: Engine>(vk: &VerifyingKey<E>) -> PreparedVerifyingKey<E> {
@@ -85,13 +92,13 @@ pub fn verify_groth16_proof_from_byteblob<E: Engine>(byteblob: &[u8]) -> Result<
         return Ok(false)
     }
 
-    let de_pi = groth16_primary_input_from_byteblob::<Bls12>(&byteblob[proof_byteblob_size + std_size_byteblob_size..proof_byteblob_size + std_size_byteblob_size + primary_input_byteblob_size]);
+    let de_pi = groth16_primary_input_from_byteblob::<Bls12>(&byteblob[proof_byteblob_size..proof_byteblob_size + std_size_byteblob_size + primary_input_byteblob_size]);
     let mut de_pi = match de_pi {
         Ok(result) => result,
         Err(e) => return Ok(false),
     };
 
-    let de_vk = groth16_vk_from_byteblob(&byteblob[proof_byteblob_size + std_size_byteblob_size + primary_input_byteblob_size..]);
+    let de_vk = groth16_processed_vk_from_byteblob(&byteblob[proof_byteblob_size + std_size_byteblob_size + primary_input_byteblob_size..]);
     let mut de_vk = match de_vk {
         Ok(result) => result,
         Err(e) => return Ok(false),
@@ -200,6 +207,40 @@ pub fn verify_proof<'a, E: Engine>(
     Ok(QAP == pvk.alpha_g1_beta_g2)
 }
 
+/// Why [`verify_proof_from_bytes`] rejected a proof.
+#[derive(thiserror::Error, Debug)]
+pub enum VerificationError {
+    /// The proof bytes are not a valid encoding: wrong length, a point not on
+    /// the curve or not in the subgroup, or a point at infinity.
+    #[error("malformed proof encoding: {0}")]
+    MalformedProof(#[source] io::Error),
+    /// The proof is well-formed but does not verify for the inputs.
+    #[error("invalid proof")]
+    InvalidProof,
+    /// The inputs do not fit the verifying key.
+    #[error(transparent)]
+    Synthesis(#[from] SynthesisError),
+}
+
+/// Decodes a proof in the compressed encoding of [`Proof::write`], checks its
+/// points and verifies it.
+pub fn verify_proof_from_bytes<'a, E: Engine>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    proof_bytes: &[u8],
+    primary_input: &[E::Fr],
+) -> Result<(), VerificationError> {
+    let proof = Proof::read_many(proof_bytes, 1)
+        .map_err(VerificationError::MalformedProof)?
+        .pop()
+        .unwrap();
+
+    if verify_proof(pvk, &proof, primary_input)? {
+        Ok(())
+    } else {
+        Err(VerificationError::InvalidProof)
+    }
+}
+
 /// Randomized batch verification - see Appendix B.2 in Zcash spec
 pub fn verify_proofs_batch<'a, E: Engine, R: rand::RngCore>(
     pvk: &'a PreparedVerifyingKey<E>,
@@ -210,7 +251,354 @@ pub fn verify_proofs_batch<'a, E: Engine, R: rand::RngCore>(
 where
     <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
 {
-    debug_assert_eq!(proofs.len(), primary_input.len());
+    verify_proofs_batch_inner(pvk, rng, proofs, primary_input, None, &POOL)
+}
+
+/// Randomized batch verification using the operating system's secure random
+/// number generator for the combination coefficients.
+pub fn verify_proofs_batch_os<'a, E: Engine>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+) -> Result<bool, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    verify_proofs_batch_inner(pvk, &mut OsRng, proofs, primary_input, None, &POOL)
+}
+
+/// Like [`verify_proofs_batch`], running on `workers` instead of the global
+/// verifier pool.
+pub fn verify_proofs_batch_with_workers<'a, E: Engine, R: rand::RngCore>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+    workers: &Workers,
+) -> Result<bool, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    verify_proofs_batch_inner(pvk, rng, proofs, primary_input, None, workers.pool())
+}
+
+/// Like [`verify_proofs_batch_os`], as a future that can be awaited on an
+/// async executor without blocking it.
+pub fn verify_proofs_batch_async<E: Engine>(
+    pvk: Arc<PreparedVerifyingKey<E>>,
+    proofs: Vec<Proof<E>>,
+    primary_input: Vec<Vec<E::Fr>>,
+) -> Task<Result<bool, SynthesisError>>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    Task::spawn(move || {
+        let proofs = proofs.iter().collect::<Vec<_>>();
+        verify_proofs_batch_inner(&pvk, &mut OsRng, &proofs, &primary_input, None, &POOL)
+    })
+}
+
+/// Randomized batch verification of proofs for different verifying keys, for
+/// example of several circuits, with a single final exponentiation. Proofs
+/// for the same key, the same reference, share the miller loops of the key.
+pub fn verify_proofs_batch_multi<'a, E: Engine, R: rand::RngCore>(
+    batch: &[(&'a PreparedVerifyingKey<E>, &Proof<E>, &[E::Fr])],
+    rng: &mut R,
+) -> Result<bool, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    for (pvk, _, primary_input) in batch {
+        if primary_input.len() + 1 != pvk.ic.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+    }
+    if batch.is_empty() {
+        return Ok(true);
+    }
+
+    // Group the proofs by key, there are only a few keys.
+    let mut keys: Vec<(&PreparedVerifyingKey<E>, Vec<usize>)> = vec![];
+    for (i, (pvk, _, _)) in batch.iter().enumerate() {
+        match keys.iter_mut().find(|(key, _)| std::ptr::eq(*key, *pvk)) {
+            Some((_, indices)) => indices.push(i),
+            None => keys.push((*pvk, vec![i])),
+        }
+    }
+
+    // Choose random coefficients for combining the proofs.
+    let rand_z: Vec<E::Fr> = (0..batch.len()).map(|_| random_coefficient::<E, R>(rng)).collect();
+
+    POOL.install(|| {
+        // MillerLoop(z_j * A_j, -B_j) for every proof
+        let ml_ab = batch
+            .par_iter()
+            .zip(rand_z.par_iter())
+            .map(|((_, proof, _), z)| {
+                let mut neg_b = proof.b;
+                neg_b.negate();
+                E::miller_loop(&[(&proof.a.mul(*z).into_affine().prepare(), &neg_b.prepare())])
+            })
+            .reduce(E::Fqk::one, |mut acc, ml| {
+                acc.mul_assign(&ml);
+                acc
+            });
+
+        // MillerLoop(\sum z_j * Accum_j, gamma) * MillerLoop(\sum z_j * C_j, delta)
+        // and alpha_beta^-(\sum z_j) for every key
+        let (ml_keys, y) = keys
+            .par_iter()
+            .map(|(pvk, indices)| {
+                let mut scalars = vec![E::Fr::zero(); pvk.ic.len()];
+                let mut acc_d = E::G1::zero();
+                for &j in indices {
+                    let z = rand_z[j];
+                    scalars[0].add_assign(&z);
+                    for (scalar, input) in scalars[1..].iter_mut().zip(batch[j].2) {
+                        let mut term = *input;
+                        term.mul_assign(&z);
+                        scalar.add_assign(&term);
+                    }
+                    acc_d.add_assign(&batch[j].1.c.mul(z));
+                }
+
+                let mut acc_g = E::G1::zero();
+                for (ic, scalar) in pvk.ic.iter().zip(&scalars) {
+                    acc_g.add_assign(&ic.mul(*scalar));
+                }
+
+                let ml = E::miller_loop(&[
+                    (&acc_g.into_affine().prepare(), &pvk.gamma_g2.prepare()),
+                    (&acc_d.into_affine().prepare(), &pvk.delta_g2.prepare()),
+                ]);
+                let mut neg_sum = scalars[0];
+                neg_sum.negate();
+
+                (ml, pvk.alpha_g1_beta_g2.pow(&neg_sum.into_repr()))
+            })
+            .reduce(
+                || (E::Fqk::one(), E::Fqk::one()),
+                |(mut ml, mut y), (other_ml, other_y)| {
+                    ml.mul_assign(&other_ml);
+                    y.mul_assign(&other_y);
+                    (ml, y)
+                },
+            );
+
+        let mut ml_all = ml_ab;
+        ml_all.mul_assign(&ml_keys);
+
+        Ok(E::final_exponentiation(&ml_all).unwrap() == y)
+    })
+}
+
+// A random 128-bit coefficient for combining the proofs of a batch.
+fn random_coefficient<E: Engine, R: rand::RngCore>(rng: &mut R) -> E::Fr {
+    use rand::Rng;
+
+    let t: u128 = rng.gen();
+    let mut el = E::Fr::zero().into_repr();
+    let el_ref: &mut [u64] = el.as_mut();
+    assert!(el_ref.len() > 1);
+
+    el_ref[0] = (t & (-1i64 as u128) >> 64) as u64;
+    el_ref[1] = (t >> 64) as u64;
+
+    E::Fr::from_repr(el).unwrap()
+}
+
+/// Like [`verify_proofs_batch`], but derives the combination coefficients from
+/// `transcript` after appending the key, the proofs and their inputs to it, so
+/// that the verification is deterministic and can be replayed.
+pub fn verify_proofs_batch_with_transcript<'a, E: Engine, T: Transcript>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    transcript: &mut T,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+) -> Result<bool, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    append_batch(transcript, pvk, proofs, primary_input);
+    verify_proofs_batch_inner(
+        pvk,
+        &mut TranscriptRng(transcript),
+        proofs,
+        primary_input,
+        None,
+        &POOL,
+    )
+}
+
+/// [`verify_proofs_batch_with_transcript`] with a fresh [`Blake2bTranscript`].
+pub fn verify_proofs_batch_deterministic<'a, E: Engine>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+) -> Result<bool, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    let mut transcript = Blake2bTranscript::new(b"groth16 batch verification");
+    verify_proofs_batch_with_transcript(pvk, &mut transcript, proofs, primary_input)
+}
+
+/// Like [`verify_proofs_batch`], but fails with [`SynthesisError::TimedOut`]
+/// once `deadline` has passed. The deadline is checked before and after the
+/// miller loops.
+pub fn verify_proofs_batch_with_deadline<'a, E: Engine, R: rand::RngCore>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+    deadline: Instant,
+) -> Result<bool, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    verify_proofs_batch_inner(pvk, rng, proofs, primary_input, Some(deadline), &POOL)
+}
+
+/// Like [`verify_proofs_batch`], but returns the indices of the invalid proofs,
+/// in increasing order, instead of a single bool. When a batch fails its halves
+/// are verified recursively, so a few bad proofs only cost a few extra batches.
+pub fn verify_proofs_batch_detailed<'a, E: Engine, R: rand::RngCore>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+) -> Result<Vec<usize>, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    if proofs.len() != primary_input.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} proofs for {} inputs",
+            proofs.len(),
+            primary_input.len()
+        )));
+    }
+
+    let mut invalid = vec![];
+    find_invalid_proofs(pvk, rng, proofs, primary_input, 0, &mut invalid)?;
+
+    Ok(invalid)
+}
+
+/// Collects proofs as they arrive, e.g. from a network stream, and verifies
+/// them all at once with [`verify_proofs_batch`].
+///
+/// The random coefficients combining the proofs are only drawn in `finalize`,
+/// so queued proofs are kept until then; only their inputs are checked early.
+pub struct BatchVerifier<'a, E: Engine> {
+    pvk: &'a PreparedVerifyingKey<E>,
+    proofs: Vec<Proof<E>>,
+    primary_inputs: Vec<Vec<E::Fr>>,
+}
+
+impl<'a, E: Engine> BatchVerifier<'a, E>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    pub fn new(pvk: &'a PreparedVerifyingKey<E>) -> Self {
+        BatchVerifier {
+            pvk,
+            proofs: vec![],
+            primary_inputs: vec![],
+        }
+    }
+
+    /// Queues a proof, failing if `primary_input` does not fit the key.
+    pub fn queue(
+        &mut self,
+        proof: Proof<E>,
+        primary_input: Vec<E::Fr>,
+    ) -> Result<(), SynthesisError> {
+        if primary_input.len() + 1 != self.pvk.ic.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        self.proofs.push(proof);
+        self.primary_inputs.push(primary_input);
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Verifies all queued proofs, an empty batch is valid.
+    pub fn finalize<R: rand::RngCore>(self, rng: &mut R) -> Result<bool, SynthesisError> {
+        if self.is_empty() {
+            return Ok(true);
+        }
+        let proofs = self.proofs.iter().collect::<Vec<_>>();
+
+        verify_proofs_batch_inner(self.pvk, rng, &proofs, &self.primary_inputs, None, &POOL)
+    }
+
+    /// Like `finalize`, but returns the indices, in queue order, of the
+    /// invalid proofs as [`verify_proofs_batch_detailed`] does.
+    pub fn finalize_detailed<R: rand::RngCore>(
+        self,
+        rng: &mut R,
+    ) -> Result<Vec<usize>, SynthesisError> {
+        let proofs = self.proofs.iter().collect::<Vec<_>>();
+
+        verify_proofs_batch_detailed(self.pvk, rng, &proofs, &self.primary_inputs)
+    }
+}
+
+fn find_invalid_proofs<'a, E: Engine, R: rand::RngCore>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+    offset: usize,
+    invalid: &mut Vec<usize>,
+) -> Result<(), SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    if proofs.is_empty()
+        || verify_proofs_batch_inner(pvk, rng, proofs, primary_input, None, &POOL)?
+    {
+        return Ok(());
+    }
+    if proofs.len() == 1 {
+        invalid.push(offset);
+        return Ok(());
+    }
+
+    let mid = proofs.len() / 2;
+    find_invalid_proofs(pvk, rng, &proofs[..mid], &primary_input[..mid], offset, invalid)?;
+    find_invalid_proofs(pvk, rng, &proofs[mid..], &primary_input[mid..], offset + mid, invalid)
+}
+
+fn verify_proofs_batch_inner<'a, E: Engine, R: rand::RngCore>(
+    pvk: &'a PreparedVerifyingKey<E>,
+    rng: &mut R,
+    proofs: &[&Proof<E>],
+    primary_input: &[Vec<E::Fr>],
+    deadline: Option<Instant>,
+    pool: &rayon::ThreadPool,
+) -> Result<bool, SynthesisError>
+where
+    <<E as ff::ScalarEngine>::Fr as ff::PrimeField>::Repr: From<<E as ff::ScalarEngine>::Fr>,
+{
+    check_deadline(deadline)?;
+
+    if proofs.len() != primary_input.len() {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "{} proofs for {} inputs",
+            proofs.len(),
+            primary_input.len()
+        )));
+    }
 
     for primary_input_elem in primary_input {
         if (primary_input_elem.len() + 1) != pvk.ic.len() {
@@ -233,22 +621,12 @@ where
     let mut accum_y = E::Fr::zero();
 
     for _ in 0..proof_num {
-        use rand::Rng;
-
-        let t: u128 = rng.gen();
-        let mut el = E::Fr::zero().into_repr();
-        let el_ref: &mut [u64] = el.as_mut();
-        assert!(el_ref.len() > 1);
-
-        el_ref[0] = (t & (-1i64 as u128) >> 64) as u64;
-        el_ref[1] = (t >> 64) as u64;
-
-        let fr = E::Fr::from_repr(el).unwrap();
+        let fr = random_coefficient::<E, R>(rng);
 
         // calculate sum
         accum_y.add_assign(&fr);
         // store FrRepr
-        rand_z_repr.push(el);
+        rand_z_repr.push(fr.into_repr());
         // store Fr
         rand_z.push(fr);
     }
@@ -262,7 +640,7 @@ where
     // Y^-Accum_Y
     let mut y = E::Fqk::zero();
 
-    POOL.install(|| {
+    pool.install(|| {
         let accum_y = &accum_y;
         let rand_z_repr = &rand_z_repr;
 
@@ -364,6 +742,8 @@ where
         });
     });
 
+    check_deadline(deadline)?;
+
     let mut ml_all = acc_ab;
     ml_all.mul_assign(&ml_d);
     ml_all.mul_assign(&ml_g);
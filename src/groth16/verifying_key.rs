@@ -1,12 +1,12 @@
 use crate::bls::{Engine, PairingCurveAffine};
-use groupy::{CurveAffine, EncodedPoint};
+use groupy::{CurveAffine, EncodedPoint, GroupDecodingError};
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use memmap::Mmap;
 use std::io::{self, Read, Write};
 use std::mem;
 
-use super::multiscalar;
+use super::{multiscalar, DIGEST_SIZE};
 
 #[derive(Clone)]
 pub struct VerifyingKey<E: Engine> {
@@ -48,7 +48,96 @@ impl<E: Engine> PartialEq for VerifyingKey<E> {
     }
 }
 
+/// Why [`VerifyingKey::validate`] rejected a key.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum VerifyingKeyError {
+    #[error("{0} is not on the curve")]
+    NotOnCurve(String),
+    #[error("{0} is not in the prime-order subgroup")]
+    NotInSubgroup(String),
+    #[error("{0} is the point at infinity")]
+    PointAtInfinity(String),
+    #[error("ic is empty")]
+    EmptyIc,
+}
+
+// Round trips `point` through the checked decoding, which rejects points off
+// the curve or outside the subgroup.
+fn check_point<G: CurveAffine>(point: &G, name: &str) -> Result<(), VerifyingKeyError> {
+    if point.is_zero() {
+        return Err(VerifyingKeyError::PointAtInfinity(name.to_string()));
+    }
+    match point.into_uncompressed().into_affine() {
+        Ok(_) => Ok(()),
+        Err(GroupDecodingError::NotInSubgroup) => {
+            Err(VerifyingKeyError::NotInSubgroup(name.to_string()))
+        }
+        Err(_) => Err(VerifyingKeyError::NotOnCurve(name.to_string())),
+    }
+}
+
 impl<E: Engine> VerifyingKey<E> {
+    /// Checks that every point is on the curve, in the prime-order subgroup
+    /// and not the point at infinity, and that `ic` is not empty. Returns the
+    /// first violation, in the order of the fields.
+    ///
+    /// Keys from `read` always pass, this is for keys that were built in
+    /// memory or read with `read_unchecked`.
+    pub fn validate(&self) -> Result<(), VerifyingKeyError> {
+        check_point(&self.alpha_g1, "alpha_g1")?;
+        check_point(&self.beta_g1, "beta_g1")?;
+        check_point(&self.beta_g2, "beta_g2")?;
+        check_point(&self.gamma_g2, "gamma_g2")?;
+        check_point(&self.delta_g1, "delta_g1")?;
+        check_point(&self.delta_g2, "delta_g2")?;
+
+        if self.ic.is_empty() {
+            return Err(VerifyingKeyError::EmptyIc);
+        }
+        for (i, ic) in self.ic.iter().enumerate() {
+            check_point(ic, &format!("ic[{}]", i))?;
+        }
+
+        Ok(())
+    }
+
+    /// BLAKE2s-256 digest of the encoding written by `write`, identifying
+    /// the key.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut bytes = vec![];
+        self.write(&mut bytes).expect("writing to a Vec never fails");
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(blake2s_simd::blake2s(&bytes).as_bytes());
+        hash
+    }
+
+    /// BLAKE2b-512 digest of the encoding written by `write`, streamed into
+    /// the hasher. Unlike `hash` it has the size of `Parameters::digest`.
+    pub fn digest(&self) -> [u8; DIGEST_SIZE] {
+        let mut hasher = blake2b_simd::State::new();
+        self.write(&mut hasher)
+            .expect("writing to a hasher never fails");
+
+        let mut digest = [0u8; DIGEST_SIZE];
+        digest.copy_from_slice(hasher.finalize().as_bytes());
+        digest
+    }
+
+    /// Reads a verifying key like `read` and checks that it has the digest
+    /// `expected`, as returned by `digest`.
+    pub fn read_with_digest<R: Read>(reader: R, expected: &[u8; DIGEST_SIZE]) -> io::Result<Self> {
+        let vk = Self::read(reader)?;
+        if vk.digest()[..] != expected[..] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "verifying key digest mismatch",
+            ));
+        }
+
+        Ok(vk)
+    }
+
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(self.alpha_g1.into_uncompressed().as_ref())?;
         writer.write_all(self.beta_g1.into_uncompressed().as_ref())?;
@@ -64,39 +153,56 @@ impl<E: Engine> VerifyingKey<E> {
         Ok(())
     }
 
-    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+    /// Reads a verifying key, checking that every point is on the curve and
+    /// in the prime-order subgroup.
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        Self::read_inner(reader, true)
+    }
+
+    /// Reads a verifying key without the curve and subgroup checks of `read`.
+    /// Only use this for keys from a trusted source.
+    pub fn read_unchecked<R: Read>(reader: R) -> io::Result<Self> {
+        Self::read_inner(reader, false)
+    }
+
+    fn read_inner<R: Read>(mut reader: R, checked: bool) -> io::Result<Self> {
         let mut g1_repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
         let mut g2_repr = <E::G2Affine as CurveAffine>::Uncompressed::empty();
 
+        let g1 = |repr: &<E::G1Affine as CurveAffine>::Uncompressed| {
+            if checked {
+                repr.into_affine()
+            } else {
+                repr.into_affine_unchecked()
+            }
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        };
+        let g2 = |repr: &<E::G2Affine as CurveAffine>::Uncompressed| {
+            if checked {
+                repr.into_affine()
+            } else {
+                repr.into_affine_unchecked()
+            }
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        };
+
         reader.read_exact(g1_repr.as_mut())?;
-        let alpha_g1 = g1_repr
-            .into_affine()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let alpha_g1 = g1(&g1_repr)?;
 
         reader.read_exact(g1_repr.as_mut())?;
-        let beta_g1 = g1_repr
-            .into_affine()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let beta_g1 = g1(&g1_repr)?;
 
         reader.read_exact(g2_repr.as_mut())?;
-        let beta_g2 = g2_repr
-            .into_affine()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let beta_g2 = g2(&g2_repr)?;
 
         reader.read_exact(g2_repr.as_mut())?;
-        let gamma_g2 = g2_repr
-            .into_affine()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let gamma_g2 = g2(&g2_repr)?;
 
         reader.read_exact(g1_repr.as_mut())?;
-        let delta_g1 = g1_repr
-            .into_affine()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let delta_g1 = g1(&g1_repr)?;
 
         reader.read_exact(g2_repr.as_mut())?;
-        let delta_g2 = g2_repr
-            .into_affine()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let delta_g2 = g2(&g2_repr)?;
 
         let ic_len = reader.read_u32::<BigEndian>()? as usize;
 
@@ -104,21 +210,18 @@ impl<E: Engine> VerifyingKey<E> {
 
         for _ in 0..ic_len {
             reader.read_exact(g1_repr.as_mut())?;
-            let g1 = g1_repr
-                .into_affine()
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-                .and_then(|e| {
-                    if e.is_zero() {
-                        Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "point at infinity",
-                        ))
-                    } else {
-                        Ok(e)
-                    }
-                })?;
+            let point = g1(&g1_repr).and_then(|e| {
+                if e.is_zero() {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "point at infinity",
+                    ))
+                } else {
+                    Ok(e)
+                }
+            })?;
 
-            ic.push(g1);
+            ic.push(point);
         }
 
         Ok(VerifyingKey {
@@ -242,3 +345,62 @@ pub struct GROTH16VerificationKey<E: Engine> {
     /// Copy of IC from `VerifiyingKey`.
     pub(crate) ic: Vec<E::G1Affine>
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, G1Affine, G1Compressed, G1Uncompressed, G2Affine};
+
+    #[test]
+    fn test_validate() {
+        let vk = VerifyingKey::<Bls12> {
+            alpha_g1: G1Affine::one(),
+            beta_g1: G1Affine::one(),
+            beta_g2: G2Affine::one(),
+            gamma_g2: G2Affine::one(),
+            delta_g1: G1Affine::one(),
+            delta_g2: G2Affine::one(),
+            ic: vec![G1Affine::one(), G1Affine::one()],
+        };
+        assert_eq!(vk.validate(), Ok(()));
+
+        let mut empty = vk.clone();
+        empty.ic.clear();
+        assert_eq!(empty.validate(), Err(VerifyingKeyError::EmptyIc));
+
+        let mut infinity = vk.clone();
+        infinity.gamma_g2 = G2Affine::zero();
+        assert_eq!(
+            infinity.validate(),
+            Err(VerifyingKeyError::PointAtInfinity("gamma_g2".into()))
+        );
+
+        // (1, 1) is not on the curve.
+        let mut repr = G1Uncompressed::empty();
+        repr.as_mut()[47] = 1;
+        repr.as_mut()[95] = 1;
+        let mut off_curve = vk.clone();
+        off_curve.ic[1] = repr.into_affine_unchecked().unwrap();
+        assert_eq!(
+            off_curve.validate(),
+            Err(VerifyingKeyError::NotOnCurve("ic[1]".into()))
+        );
+
+        // A point on the curve outside of the prime-order subgroup.
+        let mut repr = G1Compressed::empty();
+        let outside = (1u8..)
+            .find_map(|x| {
+                repr.as_mut()[0] = 0x80;
+                repr.as_mut()[47] = x;
+                repr.into_affine_unchecked().ok()
+            })
+            .unwrap();
+        let mut off_subgroup = vk;
+        off_subgroup.delta_g1 = outside;
+        assert_eq!(
+            off_subgroup.validate(),
+            Err(VerifyingKeyError::NotInSubgroup("delta_g1".into()))
+        );
+    }
+}
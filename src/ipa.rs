@@ -0,0 +1,461 @@
+//! Polynomial commitments with the inner product argument of [Bulletproofs],
+//! in the form used by [Halo].
+//!
+//! A commitment is a Pedersen vector commitment to the coefficients, and
+//! an opening at `z` proves that the inner product of the coefficients with
+//! `(1, z, z^2, ...)` is the claimed value, in `log n` rounds of halving.
+//! There is no trusted setup: the generators are hashed to the curve by
+//! try-and-increment, so no one knows their discrete logarithms. The price is a verifier linear
+//! in `n`, which Halo-style accumulation defers across proofs.
+//!
+//! Commitments are not hiding.
+//!
+//! [Bulletproofs]: https://eprint.iacr.org/2017/1066
+//! [Halo]: https://eprint.iacr.org/2019/1021
+
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective, EncodedPoint};
+use rayon::prelude::*;
+
+use crate::bls::Engine;
+use crate::multicore::Worker;
+use crate::multiexp::{multiexp, FullDensity};
+use crate::transcript::{Blake2bTranscript, Transcript};
+use crate::SynthesisError;
+
+/// The generators to commit to polynomials of up to `size` coefficients.
+pub struct Params<E: Engine> {
+    /// The generators of the coefficients.
+    pub g: Arc<Vec<E::G1Affine>>,
+    /// The generator of the inner product.
+    pub u: E::G1Affine,
+}
+
+impl<E: Engine> Clone for Params<E> {
+    fn clone(&self) -> Self {
+        Params {
+            g: self.g.clone(),
+            u: self.u,
+        }
+    }
+}
+
+/// A proof of the value of a committed polynomial at a point.
+pub struct OpeningProof<E: Engine> {
+    /// The cross terms of the rounds, `log n` of each.
+    pub l: Vec<E::G1Affine>,
+    pub r: Vec<E::G1Affine>,
+    /// The coefficient left after the last round.
+    pub a: E::Fr,
+}
+
+impl<E: Engine> Clone for OpeningProof<E> {
+    fn clone(&self) -> Self {
+        OpeningProof {
+            l: self.l.clone(),
+            r: self.r.clone(),
+            a: self.a,
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for OpeningProof<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.l == other.l && self.r == other.r && self.a == other.a
+    }
+}
+
+impl<E: Engine> OpeningProof<E> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<BigEndian>(self.l.len() as u32)?;
+        for (l, r) in self.l.iter().zip(&self.r) {
+            writer.write_all(l.into_compressed().as_ref())?;
+            writer.write_all(r.into_compressed().as_ref())?;
+        }
+        self.a.into_repr().write_be(&mut writer)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let rounds = reader.read_u32::<BigEndian>()?;
+        // No params have more than 2^64 generators.
+        if rounds > 64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "too many rounds",
+            ));
+        }
+
+        let mut l = vec![];
+        let mut r = vec![];
+        for _ in 0..rounds {
+            l.push(read_point::<E, _>(&mut reader)?);
+            r.push(read_point::<E, _>(&mut reader)?);
+        }
+        let mut repr = <E::Fr as PrimeField>::Repr::default();
+        repr.read_be(&mut reader)?;
+        let a =
+            E::Fr::from_repr(repr).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(OpeningProof { l, r, a })
+    }
+}
+
+impl<E: Engine> Params<E> {
+    /// Derives the generators for polynomials of up to `size` coefficients
+    /// from a hash of `size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a power of two.
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two(), "size must be a power of two");
+
+        let mut transcript = Blake2bTranscript::new(b"crusty3_zk ipa");
+        transcript.append_message(b"size", &(size as u64).to_le_bytes());
+
+        Params {
+            g: Arc::new(generators::<E>(&transcript, b"g", size)),
+            u: generators::<E>(&transcript, b"u", 1)[0],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.g.len()
+    }
+
+    /// Commits to the polynomial with the coefficients `coeffs`.
+    pub fn commit(&self, coeffs: &[E::Fr]) -> Result<E::G1Affine, SynthesisError> {
+        if coeffs.len() > self.size() {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        Ok(msm::<E>(&self.g[..coeffs.len()], coeffs)?.into_affine())
+    }
+
+    /// Proves the value of the polynomial with the coefficients `coeffs` at
+    /// `z`, which it returns with the proof. The commitment, `z` and the
+    /// value are appended to `transcript`, which the verifier has to replay.
+    pub fn open<T: Transcript>(
+        &self,
+        transcript: &mut T,
+        coeffs: &[E::Fr],
+        z: &E::Fr,
+    ) -> Result<(E::Fr, OpeningProof<E>), SynthesisError> {
+        let commitment = self.commit(coeffs)?;
+        let mut a = coeffs.to_vec();
+        a.resize(self.size(), E::Fr::zero());
+        let mut b = powers::<E>(z, self.size());
+        let value = inner_product::<E>(&a, &b);
+        let u = self.bind(transcript, &commitment, z, &value);
+
+        let mut g = self.g.to_vec();
+        let mut l = vec![];
+        let mut r = vec![];
+        while a.len() > 1 {
+            let half = a.len() / 2;
+            let (a_lo, a_hi) = a.split_at(half);
+            let (b_lo, b_hi) = b.split_at(half);
+            let (g_lo, g_hi) = g.split_at(half);
+
+            let mut l_j = msm::<E>(g_hi, a_lo)?;
+            l_j.add_assign(&u.mul(inner_product::<E>(a_lo, b_hi).into_repr()));
+            let mut r_j = msm::<E>(g_lo, a_hi)?;
+            r_j.add_assign(&u.mul(inner_product::<E>(a_hi, b_lo).into_repr()));
+            let l_j = l_j.into_affine();
+            let r_j = r_j.into_affine();
+
+            let (x, x_inv) = round_challenge::<E, _>(transcript, &l_j, &r_j)?;
+            a = fold::<E>(a_lo, a_hi, &x, &x_inv);
+            b = fold::<E>(b_lo, b_hi, &x_inv, &x);
+            g = fold_points::<E>(g_lo, g_hi, &x_inv, &x);
+            l.push(l_j);
+            r.push(r_j);
+        }
+
+        Ok((value, OpeningProof { l, r, a: a[0] }))
+    }
+
+    /// Verifies that the polynomial committed to in `commitment` takes
+    /// `value` at `z`, replaying the transcript of `open`.
+    pub fn verify<T: Transcript>(
+        &self,
+        transcript: &mut T,
+        commitment: &E::G1Affine,
+        z: &E::Fr,
+        value: &E::Fr,
+        proof: &OpeningProof<E>,
+    ) -> Result<bool, SynthesisError> {
+        let rounds = self.size().trailing_zeros() as usize;
+        if proof.l.len() != rounds || proof.r.len() != rounds {
+            return Ok(false);
+        }
+        let u = self.bind(transcript, commitment, z, value);
+
+        // `P = C + v U + sum x_j^2 L_j + x_j^-2 R_j`
+        let mut p = u.mul(value.into_repr());
+        p.add_assign_mixed(commitment);
+        // `s_i` is the product of `x_j` or `x_j^-1` as bit `j` from the top
+        // of `i` is set or not, so that the folded `g` is `<s, g>`.
+        let mut s = vec![E::Fr::one()];
+        for (l_j, r_j) in proof.l.iter().zip(&proof.r) {
+            let (x, x_inv) = round_challenge::<E, _>(transcript, l_j, r_j)?;
+            let mut x_squared = x;
+            x_squared.square();
+            let mut x_inv_squared = x_inv;
+            x_inv_squared.square();
+            p.add_assign(&l_j.mul(x_squared.into_repr()));
+            p.add_assign(&r_j.mul(x_inv_squared.into_repr()));
+
+            s = s
+                .iter()
+                .flat_map(|s| {
+                    let mut lo = *s;
+                    lo.mul_assign(&x_inv);
+                    let mut hi = *s;
+                    hi.mul_assign(&x);
+                    vec![lo, hi]
+                })
+                .collect();
+        }
+
+        // `P = a G + a b U` for the folded `G` and `b`.
+        let mut ab = inner_product::<E>(&s, &powers::<E>(z, self.size()));
+        ab.mul_assign(&proof.a);
+        let mut expected = msm::<E>(&self.g, &s)?;
+        expected.mul_assign(proof.a.into_repr());
+        expected.add_assign(&u.mul(ab.into_repr()));
+
+        Ok(p == expected)
+    }
+
+    // Appends the statement to the transcript and derives the generator of
+    // the inner product from it, so that the value is bound to the proof.
+    fn bind<T: Transcript>(
+        &self,
+        transcript: &mut T,
+        commitment: &E::G1Affine,
+        z: &E::Fr,
+        value: &E::Fr,
+    ) -> E::G1Affine {
+        transcript.append_point(b"commitment", commitment);
+        transcript.append_scalar(b"z", z);
+        transcript.append_scalar(b"value", value);
+        let xi = transcript.challenge_scalar::<E::Fr>(b"xi");
+
+        self.u.mul(xi.into_repr()).into_affine()
+    }
+}
+
+// The cofactor of G1 of BLS12-381.
+const G1_COFACTOR: &str = "76329603384216526031706109802092473003";
+
+// `count` points whose discrete logarithms no one knows, by try-and-increment:
+// the compressed encoding of a point is drawn from `transcript`, `label` and
+// its index until it is on the curve, then the cofactor is cleared.
+pub(crate) fn generators<E: Engine>(
+    transcript: &Blake2bTranscript,
+    label: &'static [u8],
+    count: usize,
+) -> Vec<E::G1Affine> {
+    let cofactor = E::Fr::from_str(G1_COFACTOR)
+        .expect("the cofactor is smaller than r")
+        .into_repr();
+
+    (0..count)
+        .into_par_iter()
+        .map(|i| {
+            let mut transcript = transcript.clone();
+            transcript.append_message(label, &(i as u64).to_le_bytes());
+            loop {
+                // Every challenge is appended, so each try draws new bytes.
+                let mut repr = <E::G1Affine as CurveAffine>::Compressed::empty();
+                transcript.challenge_bytes(b"x", repr.as_mut());
+                // The flags of a compressed point that is not at infinity,
+                // the sign of y stays random.
+                repr.as_mut()[0] |= 0x80;
+                repr.as_mut()[0] &= !0x40;
+                if let Ok(point) = repr.into_affine_unchecked() {
+                    let point = point.mul(cofactor);
+                    if !point.is_zero() {
+                        return point.into_affine();
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn round_challenge<E: Engine, T: Transcript>(
+    transcript: &mut T,
+    l: &E::G1Affine,
+    r: &E::G1Affine,
+) -> Result<(E::Fr, E::Fr), SynthesisError> {
+    transcript.append_point(b"l", l);
+    transcript.append_point(b"r", r);
+    let x = transcript.challenge_scalar::<E::Fr>(b"x");
+    let x_inv = x.inverse().ok_or(SynthesisError::DivisionByZero)?;
+
+    Ok((x, x_inv))
+}
+
+pub(crate) fn read_point<E: Engine, R: Read>(reader: &mut R) -> io::Result<E::G1Affine> {
+    let mut repr = <E::G1Affine as CurveAffine>::Compressed::empty();
+    reader.read_exact(repr.as_mut())?;
+    repr.into_affine()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub(crate) fn msm<E: Engine>(
+    bases: &[E::G1Affine],
+    scalars: &[E::Fr],
+) -> Result<E::G1, SynthesisError> {
+    if bases.is_empty() {
+        return Ok(E::G1::zero());
+    }
+
+    let exponents = scalars.iter().map(|s| s.into_repr()).collect();
+    multiexp(
+        &Worker::new(),
+        (Arc::new(bases.to_vec()), 0),
+        FullDensity,
+        Arc::new(exponents),
+        &mut None,
+    )
+    .wait()
+}
+
+pub(crate) fn powers<E: Engine>(x: &E::Fr, n: usize) -> Vec<E::Fr> {
+    let mut powers = Vec::with_capacity(n);
+    let mut power = E::Fr::one();
+    for _ in 0..n {
+        powers.push(power);
+        power.mul_assign(x);
+    }
+    powers
+}
+
+pub(crate) fn inner_product<E: Engine>(a: &[E::Fr], b: &[E::Fr]) -> E::Fr {
+    a.par_iter()
+        .zip(b)
+        .map(|(a, b)| {
+            let mut term = *a;
+            term.mul_assign(b);
+            term
+        })
+        .reduce(E::Fr::zero, |mut acc, term| {
+            acc.add_assign(&term);
+            acc
+        })
+}
+
+// `lo x_lo + hi x_hi`
+pub(crate) fn fold<E: Engine>(
+    lo: &[E::Fr],
+    hi: &[E::Fr],
+    x_lo: &E::Fr,
+    x_hi: &E::Fr,
+) -> Vec<E::Fr> {
+    lo.par_iter()
+        .zip(hi)
+        .map(|(lo, hi)| {
+            let mut lo = *lo;
+            lo.mul_assign(x_lo);
+            let mut hi = *hi;
+            hi.mul_assign(x_hi);
+            lo.add_assign(&hi);
+            lo
+        })
+        .collect()
+}
+
+pub(crate) fn fold_points<E: Engine>(
+    lo: &[E::G1Affine],
+    hi: &[E::G1Affine],
+    x_lo: &E::Fr,
+    x_hi: &E::Fr,
+) -> Vec<E::G1Affine> {
+    let (x_lo, x_hi) = (x_lo.into_repr(), x_hi.into_repr());
+    let mut points = lo
+        .par_iter()
+        .zip(hi)
+        .map(|(lo, hi)| {
+            let mut point = lo.mul(x_lo);
+            point.add_assign(&hi.mul(x_hi));
+            point
+        })
+        .collect::<Vec<_>>();
+    E::G1::batch_normalization(&mut points);
+
+    points.into_iter().map(|p| p.into_affine()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_generators() {
+        let transcript = Blake2bTranscript::new(b"test");
+        let points = generators::<Bls12>(&transcript, b"g", 8);
+        assert!(points == generators::<Bls12>(&transcript, b"g", 8));
+        assert!(points[0] != generators::<Bls12>(&transcript, b"h", 1)[0]);
+
+        for (i, point) in points.iter().enumerate() {
+            assert!(!point.is_zero());
+            assert!(points[..i].iter().all(|other| other != point));
+            // Decoding with the subgroup check accepts it.
+            assert!(point.into_compressed().into_affine().unwrap() == *point);
+        }
+    }
+
+    #[test]
+    fn test_open_and_verify() {
+        let mut rng = test_rng();
+        let params = Params::<Bls12>::new(16);
+        assert!(params.g == Params::<Bls12>::new(16).g);
+
+        let coeffs = (0..13).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let commitment = params.commit(&coeffs).unwrap();
+        let z = Fr::random(&mut rng);
+        let mut transcript = Blake2bTranscript::new(b"test");
+        let (value, proof) = params.open(&mut transcript, &coeffs, &z).unwrap();
+        assert_eq!(proof.l.len(), 4);
+
+        let mut expected = Fr::zero();
+        for coeff in coeffs.iter().rev() {
+            expected.mul_assign(&z);
+            expected.add_assign(coeff);
+        }
+        assert_eq!(value, expected);
+
+        let verify = |z: &Fr, value: &Fr, proof: &OpeningProof<Bls12>| {
+            let mut transcript = Blake2bTranscript::new(b"test");
+            params
+                .verify(&mut transcript, &commitment, z, value, proof)
+                .unwrap()
+        };
+        assert!(verify(&z, &value, &proof));
+        assert!(!verify(&z, &Fr::one(), &proof));
+        assert!(!verify(&Fr::one(), &value, &proof));
+
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+        let read = OpeningProof::<Bls12>::read(&bytes[..]).unwrap();
+        assert!(read == proof);
+        assert!(OpeningProof::<Bls12>::read(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut short = proof.clone();
+        short.l.pop();
+        short.r.pop();
+        assert!(!verify(&z, &value, &short));
+
+        assert!(params.commit(&vec![Fr::one(); 17]).is_err());
+    }
+}
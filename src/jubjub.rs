@@ -0,0 +1,332 @@
+//! The [Jubjub] twisted Edwards curve `-x^2 + y^2 = 1 + d x^2 y^2`, defined
+//! over the scalar field of BLS12-381 so that its arithmetic is cheap in
+//! circuits, with `d = -(10240/10241)`.
+//!
+//! Scalars are little-endian bytes or bits. Points are affine and are not
+//! checked to be in the prime order subgroup unless they come from
+//! [`group_hash`] or [`Point::mul_by_cofactor`].
+//!
+//! [Jubjub]: https://z.cash/technology/jubjub/
+
+use blake2s_simd::Params as Blake2sParams;
+use ff::{Field, PrimeField, PrimeFieldRepr, SqrtField};
+
+use crate::bls::{Fr, FrRepr};
+
+/// Order of the prime order subgroup, little-endian, `r_J =
+/// 6554484396890773809930967563523245729705921265872317281365359162392183254199`.
+pub const SUBGROUP_ORDER: [u8; 32] = [
+    0xb7, 0x2c, 0xf7, 0xd6, 0x5e, 0x0e, 0x97, 0xd0, 0x82, 0x10, 0xc8, 0xcc, 0x93, 0x20, 0x68, 0xa6,
+    0x00, 0x3b, 0x34, 0x01, 0x01, 0x3b, 0x67, 0x06, 0xa9, 0xaf, 0x33, 0x65, 0xea, 0xb4, 0x7d, 0x0e,
+];
+
+/// Number of bits of the scalars of [`pedersen_hash`] multiplying each
+/// generator, small enough that distinct segments give distinct multiples.
+pub const PEDERSEN_SEGMENT_BITS: usize = 189;
+
+/// The curve parameter `d`.
+pub fn edwards_d() -> Fr {
+    let mut d = Fr::from_str("10241")
+        .unwrap()
+        .inverse()
+        .expect("10241 is not zero");
+    d.mul_assign(&Fr::from_str("10240").unwrap());
+    d.negate();
+    d
+}
+
+/// An affine point of the curve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Point {
+    x: Fr,
+    y: Fr,
+}
+
+impl Point {
+    /// The neutral element `(0, 1)`.
+    pub fn identity() -> Self {
+        Point {
+            x: Fr::zero(),
+            y: Fr::one(),
+        }
+    }
+
+    /// The point `(x, y)`, if it is on the curve.
+    pub fn from_xy(x: Fr, y: Fr) -> Option<Self> {
+        let point = Point { x, y };
+        if point.is_on_curve() {
+            Some(point)
+        } else {
+            None
+        }
+    }
+
+    /// The point `(x, y)`, which the caller knows to be on the curve.
+    pub(crate) fn from_xy_unchecked(x: Fr, y: Fr) -> Self {
+        Point { x, y }
+    }
+
+    pub fn get_xy(&self) -> (Fr, Fr) {
+        (self.x, self.y)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    fn is_on_curve(&self) -> bool {
+        let mut x2 = self.x;
+        x2.square();
+        let mut y2 = self.y;
+        y2.square();
+
+        // y^2 - x^2
+        let mut lhs = y2;
+        lhs.sub_assign(&x2);
+
+        // 1 + d x^2 y^2
+        let mut rhs = x2;
+        rhs.mul_assign(&y2);
+        rhs.mul_assign(&edwards_d());
+        rhs.add_assign(&Fr::one());
+
+        lhs == rhs
+    }
+
+    /// Decodes the 32 byte encoding of [`Point::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        let mut repr = FrRepr::default();
+        repr.read_le(&bytes[..]).expect("32 bytes fill the repr");
+        let sign = repr.as_ref()[3] >> 63 == 1;
+        repr.as_mut()[3] &= 0x7fff_ffff_ffff_ffff;
+
+        let y = Fr::from_repr(repr).ok()?;
+
+        Self::from_y_and_sign(y, sign)
+    }
+
+    /// The point of coordinate `y` whose `x` has the parity `sign`, if any.
+    pub fn from_y_and_sign(y: Fr, sign: bool) -> Option<Self> {
+        // x^2 = (y^2 - 1) / (d y^2 + 1)
+        let mut y2 = y;
+        y2.square();
+        let mut num = y2;
+        num.sub_assign(&Fr::one());
+        let mut den = y2;
+        den.mul_assign(&edwards_d());
+        den.add_assign(&Fr::one());
+        let mut x2 = num;
+        x2.mul_assign(&den.inverse()?);
+
+        let mut x = x2.sqrt()?;
+        if x.into_repr().is_odd() != sign {
+            if x.is_zero() {
+                return None;
+            }
+            x.negate();
+        }
+
+        Some(Point { x, y })
+    }
+
+    /// Encodes `y` in little-endian, with the parity of `x` in the top bit.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut repr = self.y.into_repr();
+        if self.x.into_repr().is_odd() {
+            repr.as_mut()[3] |= 0x8000_0000_0000_0000;
+        }
+
+        let mut bytes = [0u8; 32];
+        repr.write_le(&mut bytes[..])
+            .expect("32 bytes hold the repr");
+        bytes
+    }
+
+    pub fn negate(&self) -> Self {
+        let mut x = self.x;
+        x.negate();
+        Point { x, y: self.y }
+    }
+
+    /// The sum of two points. The addition law is complete.
+    pub fn add(&self, other: &Self) -> Self {
+        // x3 = (x1 y2 + y1 x2) / (1 + d x1 x2 y1 y2)
+        // y3 = (y1 y2 + x1 x2) / (1 - d x1 x2 y1 y2)
+        let mut x1y2 = self.x;
+        x1y2.mul_assign(&other.y);
+        let mut y1x2 = self.y;
+        y1x2.mul_assign(&other.x);
+        let mut y1y2 = self.y;
+        y1y2.mul_assign(&other.y);
+        let mut x1x2 = self.x;
+        x1x2.mul_assign(&other.x);
+
+        let mut t = x1x2;
+        t.mul_assign(&y1y2);
+        t.mul_assign(&edwards_d());
+
+        let mut x = x1y2;
+        x.add_assign(&y1x2);
+        let mut den = Fr::one();
+        den.add_assign(&t);
+        x.mul_assign(&den.inverse().expect("the addition law is complete"));
+
+        let mut y = y1y2;
+        y.add_assign(&x1x2);
+        let mut den = Fr::one();
+        den.sub_assign(&t);
+        y.mul_assign(&den.inverse().expect("the addition law is complete"));
+
+        Point { x, y }
+    }
+
+    pub fn double(&self) -> Self {
+        self.add(self)
+    }
+
+    /// Multiplies by the cofactor 8, mapping into the prime order subgroup.
+    pub fn mul_by_cofactor(&self) -> Self {
+        self.double().double().double()
+    }
+
+    /// Multiplies by a scalar given as little-endian bits.
+    pub fn mul_bits<I: IntoIterator<Item = bool>>(&self, bits: I) -> Self {
+        let mut result = Self::identity();
+        let mut base = *self;
+        for bit in bits {
+            if bit {
+                result = result.add(&base);
+            }
+            base = base.double();
+        }
+
+        result
+    }
+
+    /// Multiplies by a scalar given as little-endian bytes.
+    pub fn mul(&self, scalar: &[u8]) -> Self {
+        self.mul_bits(bytes_to_bits_le(scalar))
+    }
+}
+
+/// The little-endian bits of little-endian bytes.
+pub fn bytes_to_bits_le(bytes: &[u8]) -> impl Iterator<Item = bool> + '_ {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+}
+
+/// Hashes `tag` with BLAKE2s under the 8 byte `personalization` to a point
+/// of the prime order subgroup, or `None` if the digest does not encode a
+/// point or the point has small order.
+pub fn group_hash(tag: &[u8], personalization: &[u8]) -> Option<Point> {
+    assert_eq!(personalization.len(), 8);
+
+    let hash = Blake2sParams::new()
+        .hash_length(32)
+        .personal(personalization)
+        .to_state()
+        .update(tag)
+        .finalize();
+
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(hash.as_bytes());
+
+    let point = Point::from_bytes(&bytes)?.mul_by_cofactor();
+    if point.is_identity() {
+        None
+    } else {
+        Some(point)
+    }
+}
+
+/// The `index`th generator under `personalization`, from the first
+/// [`group_hash`] of `index || counter` that gives a point.
+pub fn generator(personalization: &[u8], index: u32) -> Point {
+    (0u8..=255)
+        .find_map(|counter| {
+            let mut tag = index.to_le_bytes().to_vec();
+            tag.push(counter);
+            group_hash(&tag, personalization)
+        })
+        .expect("a group hash succeeds within 256 attempts")
+}
+
+/// The Pedersen hash of little-endian `bits` under an 8 byte
+/// `personalization`: the sum of the multiples of the generators by the
+/// successive segments of [`PEDERSEN_SEGMENT_BITS`] bits.
+pub fn pedersen_hash(personalization: &[u8], bits: &[bool]) -> Point {
+    bits.chunks(PEDERSEN_SEGMENT_BITS)
+        .enumerate()
+        .fold(Point::identity(), |acc, (i, segment)| {
+            let g = generator(personalization, i as u32);
+            acc.add(&g.mul_bits(segment.iter().cloned()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERSONALIZATION: &[u8] = b"Test_PHG";
+
+    #[test]
+    fn test_subgroup_order() {
+        let mut repr = FrRepr::default();
+        repr.read_le(&SUBGROUP_ORDER[..]).unwrap();
+        assert_eq!(
+            Fr::from_repr(repr).unwrap(),
+            Fr::from_str(
+                "6554484396890773809930967563523245729705921265872317281365359162392183254199"
+            )
+            .unwrap()
+        );
+
+        for i in 0..4 {
+            let g = generator(PERSONALIZATION, i);
+            assert!(g.is_on_curve());
+            assert!(!g.is_identity());
+            assert!(g.mul(&SUBGROUP_ORDER).is_identity());
+        }
+    }
+
+    #[test]
+    fn test_group_law() {
+        let g = generator(PERSONALIZATION, 0);
+        let h = generator(PERSONALIZATION, 1);
+
+        assert_eq!(g.add(&h), h.add(&g));
+        assert_eq!(g.add(&Point::identity()), g);
+        assert!(g.add(&g.negate()).is_identity());
+        assert_eq!(g.add(&h).add(&g), g.double().add(&h));
+        assert_eq!(g.mul(&[5]), g.double().double().add(&g));
+        assert!(g.add(&h).is_on_curve());
+    }
+
+    #[test]
+    fn test_encoding() {
+        for i in 0..8 {
+            let g = generator(PERSONALIZATION, i);
+            for p in &[g, g.negate(), Point::identity()] {
+                assert_eq!(Point::from_bytes(&p.to_bytes()), Some(*p));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pedersen_hash() {
+        let bits = (0..400).map(|i| i % 3 == 0).collect::<Vec<_>>();
+
+        let h = pedersen_hash(PERSONALIZATION, &bits);
+        assert!(h.is_on_curve());
+        assert!(h != pedersen_hash(PERSONALIZATION, &bits[1..]));
+        assert!(h != pedersen_hash(b"Other_PH", &bits));
+        assert!(pedersen_hash(PERSONALIZATION, &[]).is_identity());
+
+        let expected = generator(PERSONALIZATION, 0)
+            .mul_bits(bits[..189].iter().cloned())
+            .add(&generator(PERSONALIZATION, 1).mul_bits(bits[189..378].iter().cloned()))
+            .add(&generator(PERSONALIZATION, 2).mul_bits(bits[378..].iter().cloned()));
+        assert_eq!(h, expected);
+    }
+}
@@ -90,7 +90,7 @@
 //!         let hash = sha256d(cs.namespace(|| "SHA-256d(preimage)"), &preimage_bits)?;
 //!
 //!         // Expose the vector of 32 boolean variables as compact public inputs.
-//!         multipack::pack_into_inputs(cs.namespace(|| "pack hash"), &hash)
+//!         multipack::pack_bits_into_inputs(cs.namespace(|| "pack hash"), &hash)
 //!     }
 //! }
 //!
@@ -140,13 +140,32 @@
 extern crate hex_literal;
 
 pub mod bls;
+#[cfg(feature = "bulletproofs")]
+pub mod bulletproofs;
+#[cfg(feature = "compat")]
+pub mod compat;
+pub mod compose;
 pub mod domain;
+pub mod eddsa;
+pub mod gadgets;
+#[cfg(feature = "gm17")]
+pub mod gm17;
 
 pub mod gpu;
 #[cfg(feature = "groth16")]
 pub mod groth16;
+#[cfg(feature = "ipa")]
+pub mod ipa;
+pub mod jubjub;
+pub mod merkle;
+pub mod mimc;
 pub mod multicore;
 pub mod multiexp;
+#[cfg(feature = "plonk")]
+pub mod plonk;
+pub mod poseidon;
+pub mod secp256k1;
+pub mod transcript;
 
 pub mod util_cs;
 use ff::{Field, ScalarEngine};
@@ -212,6 +231,24 @@ impl<E: ScalarEngine> LinearCombination<E> {
         self.0.iter()
     }
 
+    /// Number of terms. Terms of the same variable are combined as they are
+    /// added, but terms whose coefficients cancelled out are kept until
+    /// `simplify`.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Removes the terms whose coefficients cancelled out to zero, such as
+    /// those of `lc + x - x`.
+    pub fn simplify(mut self) -> LinearCombination<E> {
+        self.0.retain(|_, coeff| !coeff.is_zero());
+        self
+    }
+
     pub fn add_unsimplified(mut self, (coeff, var): (E::Fr, Variable)) -> LinearCombination<E> {
         self.0
             .entry(var)
@@ -345,6 +382,22 @@ pub enum SynthesisError {
     /// During GPU multiexp/fft, some GPU related error happened
     #[error("encountered a GPU error: {0}")]
     GPUError(#[from] gpu::GPUError),
+    /// During proving or verification, the deadline passed before we were done
+    #[error("deadline exceeded")]
+    TimedOut,
+    /// During proving, the job was cancelled through its cancellation token
+    #[error("cancelled")]
+    Cancelled,
+    /// During proving, a worker stopped, e.g. by panicking, before its result
+    #[error("a worker stopped without a result")]
+    WorkerStopped,
+    /// During proving, a single proof needed more witness memory than the budget
+    #[error("witness memory budget exceeded: {0} bytes needed")]
+    MemoryBudgetExceeded(usize),
+    /// During aggregation, the number of proofs or inputs did not fit the SRS,
+    /// or during batch verification, the numbers of proofs and inputs differ
+    #[error("incompatible vector length: {0}")]
+    IncompatibleLengthVector(String),
 }
 
 /// Represents a constraint system which can have new variables
@@ -572,6 +625,11 @@ impl<'cs, E: ScalarEngine, CS: ConstraintSystem<E>> ConstraintSystem<E> for &'cs
     }
 }
 
+/// The error of a reader that found malformed data.
+pub(crate) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
 #[cfg(all(test, feature = "groth16"))]
 mod tests {
     use super::*;
@@ -607,4 +665,20 @@ mod tests {
             _ => panic!("unexpected variable type"),
         });
     }
+
+    #[test]
+    fn test_simplify() {
+        use crate::bls::Bls12;
+
+        let x = Variable::new_unchecked(Index::Aux(0));
+        let y = Variable::new_unchecked(Index::Aux(1));
+
+        let lc = LinearCombination::<Bls12>::zero() + x + y - x;
+        assert_eq!(lc.len(), 2);
+
+        let lc = lc.simplify();
+        assert_eq!(lc.len(), 1);
+        assert_eq!(lc.0[&y], <Bls12 as ScalarEngine>::Fr::one());
+        assert!((LinearCombination::<Bls12>::zero() + x - x).simplify().is_empty());
+    }
 }
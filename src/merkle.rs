@@ -0,0 +1,270 @@
+//! Binary Merkle trees over field elements, whose authentication paths
+//! `gadgets::merkle` verifies in a circuit.
+//!
+//! Levels are numbered from the leaves up. Bit `i` of the index of a leaf,
+//! counting from the least significant, tells whether its ancestor on level
+//! `i` is a right child, in which case it is hashed as `hash(sibling, node)`
+//! rather than `hash(node, sibling)`.
+
+use ff::{BitIterator, Field, PrimeField, ScalarEngine};
+
+use crate::bls::{Bls12, Fr};
+use crate::jubjub::pedersen_hash;
+use crate::poseidon::{poseidon, PoseidonConstants};
+
+/// A two-to-one hash compressing a pair of nodes into their parent.
+pub trait Hasher<E: ScalarEngine> {
+    fn hash(&self, left: &E::Fr, right: &E::Fr) -> E::Fr;
+}
+
+/// Hashes pairs with Poseidon of arity 2.
+pub struct PoseidonHasher<E: ScalarEngine> {
+    constants: PoseidonConstants<E>,
+}
+
+impl<E: ScalarEngine> PoseidonHasher<E> {
+    pub fn new() -> Self {
+        PoseidonHasher {
+            constants: PoseidonConstants::new(2),
+        }
+    }
+
+    pub fn constants(&self) -> &PoseidonConstants<E> {
+        &self.constants
+    }
+}
+
+impl<E: ScalarEngine> Default for PoseidonHasher<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: ScalarEngine> Hasher<E> for PoseidonHasher<E> {
+    fn hash(&self, left: &E::Fr, right: &E::Fr) -> E::Fr {
+        poseidon(&self.constants, &[*left, *right])
+    }
+}
+
+/// Hashes pairs to the x-coordinate of the Jubjub Pedersen hash of the
+/// canonical little-endian bits of `left` followed by those of `right`.
+pub struct PedersenHasher {
+    personalization: [u8; 8],
+}
+
+impl PedersenHasher {
+    pub fn new(personalization: [u8; 8]) -> Self {
+        PedersenHasher { personalization }
+    }
+
+    pub fn personalization(&self) -> &[u8] {
+        &self.personalization
+    }
+}
+
+impl Hasher<Bls12> for PedersenHasher {
+    fn hash(&self, left: &Fr, right: &Fr) -> Fr {
+        let mut bits = fr_to_bits_le(left);
+        bits.extend(fr_to_bits_le(right));
+
+        pedersen_hash(&self.personalization, &bits).get_xy().0
+    }
+}
+
+/// The `NUM_BITS` little-endian bits of `fr`.
+fn fr_to_bits_le(fr: &Fr) -> Vec<bool> {
+    let mut bits = BitIterator::new(fr.into_repr()).collect::<Vec<_>>();
+    bits.reverse();
+    bits.truncate(Fr::NUM_BITS as usize);
+    bits
+}
+
+/// The siblings of the nodes from a leaf up to the root, with the index of
+/// the leaf.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath<E: ScalarEngine> {
+    index: u64,
+    siblings: Vec<E::Fr>,
+}
+
+impl<E: ScalarEngine> MerklePath<E> {
+    /// Panics if `index` does not fit in `siblings.len()` bits.
+    pub fn new(index: u64, siblings: Vec<E::Fr>) -> Self {
+        assert!(
+            siblings.len() >= 64 || index >> siblings.len() == 0,
+            "index out of range for the depth"
+        );
+
+        MerklePath { index, siblings }
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    pub fn siblings(&self) -> &[E::Fr] {
+        &self.siblings
+    }
+
+    pub fn depth(&self) -> usize {
+        self.siblings.len()
+    }
+
+    /// Whether the node on each level, from the leaf up, is a right child.
+    pub fn directions(&self) -> Vec<bool> {
+        (0..self.depth())
+            .map(|i| i < 64 && (self.index >> i) & 1 == 1)
+            .collect()
+    }
+
+    /// The root of the tree holding `leaf` at this path.
+    pub fn root<H: Hasher<E>>(&self, hasher: &H, leaf: &E::Fr) -> E::Fr {
+        self.siblings
+            .iter()
+            .zip(self.directions())
+            .fold(*leaf, |node, (sibling, is_right)| {
+                if is_right {
+                    hasher.hash(sibling, &node)
+                } else {
+                    hasher.hash(&node, sibling)
+                }
+            })
+    }
+}
+
+/// A Merkle tree of fixed depth, storing every level.
+pub struct MerkleTree<E: ScalarEngine, H: Hasher<E>> {
+    hasher: H,
+    // From the leaves up to the root.
+    levels: Vec<Vec<E::Fr>>,
+}
+
+impl<E: ScalarEngine, H: Hasher<E>> MerkleTree<E, H> {
+    /// Builds a tree of `2^depth` leaves, the given `leaves` followed by
+    /// zeros.
+    ///
+    /// Panics if there are more than `2^depth` leaves.
+    pub fn new(hasher: H, depth: usize, leaves: &[E::Fr]) -> Self {
+        assert!(depth < 64, "depth must be less than 64");
+        assert!(
+            leaves.len() as u64 <= 1 << depth,
+            "too many leaves for the depth"
+        );
+
+        let mut level = leaves.to_vec();
+        level.resize(1 << depth, E::Fr::zero());
+
+        let mut levels = vec![level];
+        for _ in 0..depth {
+            let next = levels
+                .last()
+                .expect("levels is not empty")
+                .chunks(2)
+                .map(|pair| hasher.hash(&pair[0], &pair[1]))
+                .collect();
+            levels.push(next);
+        }
+
+        MerkleTree { hasher, levels }
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> E::Fr {
+        self.levels[self.depth()][0]
+    }
+
+    pub fn leaves(&self) -> &[E::Fr] {
+        &self.levels[0]
+    }
+
+    /// The path of the leaf at `index`.
+    ///
+    /// Panics if `index` is out of range.
+    pub fn path(&self, index: u64) -> MerklePath<E> {
+        assert!(index < self.leaves().len() as u64, "index out of range");
+
+        let siblings = self.levels[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(i, level)| level[((index >> i) ^ 1) as usize])
+            .collect();
+
+        MerklePath::new(index, siblings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_poseidon_tree() {
+        let mut rng = test_rng();
+
+        let leaves = (0..5).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let tree = MerkleTree::<Bls12, _>::new(PoseidonHasher::new(), 3, &leaves);
+
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.leaves().len(), 8);
+        assert_eq!(tree.leaves()[5], Fr::zero());
+
+        for index in 0..8 {
+            let path = tree.path(index);
+            let leaf = tree.leaves()[index as usize];
+            assert_eq!(path.root(tree.hasher(), &leaf), tree.root());
+
+            // The same siblings in another order do not give the root.
+            let other = MerklePath::new(index ^ 1, path.siblings().to_vec());
+            assert!(other.root(tree.hasher(), &leaf) != tree.root());
+        }
+
+        // Left and right children are hashed in order.
+        let h = tree.hasher();
+        let expected = h.hash(
+            &h.hash(
+                &h.hash(&leaves[0], &leaves[1]),
+                &h.hash(&leaves[2], &leaves[3]),
+            ),
+            &h.hash(
+                &h.hash(&leaves[4], &Fr::zero()),
+                &h.hash(&Fr::zero(), &Fr::zero()),
+            ),
+        );
+        assert_eq!(tree.root(), expected);
+        assert_eq!(tree.path(6).directions(), vec![false, true, true]);
+    }
+
+    #[test]
+    fn test_pedersen_tree() {
+        let leaves = (1..=4)
+            .map(|i| Fr::from_str(&i.to_string()).unwrap())
+            .collect::<Vec<_>>();
+        let tree = MerkleTree::new(PedersenHasher::new(*b"Test_MPH"), 2, &leaves);
+
+        for index in 0..4 {
+            let path = tree.path(index);
+            assert_eq!(
+                path.root(tree.hasher(), &leaves[index as usize]),
+                tree.root()
+            );
+        }
+
+        let h = tree.hasher();
+        assert!(h.hash(&leaves[0], &leaves[1]) != h.hash(&leaves[1], &leaves[0]));
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = MerkleTree::<Bls12, _>::new(PoseidonHasher::new(), 0, &[Fr::one()]);
+        assert_eq!(tree.root(), Fr::one());
+        assert_eq!(tree.path(0).root(tree.hasher(), &Fr::one()), Fr::one());
+    }
+}
@@ -0,0 +1,282 @@
+//! The [MiMC] block cipher and hash, and the [GMiMC] permutation, over the
+//! scalar field, computed natively.
+//!
+//! Both use the `x^5` S-box, which is a permutation of the BLS12-381 scalar
+//! field. The MiMC round constants follow the derivation of [circomlib]: the
+//! first one is zero and the others are the iterated Keccak-256 hashes of the
+//! seed `"mimc"`, reduced modulo the field order. circomlib itself works over
+//! the BN254 scalar field with the `x^7` S-box and 91 rounds, so the digests
+//! still differ from its own. GMiMC has no reference constants, they are
+//! derived from BLAKE2s like those of `crate::poseidon` and digests do not
+//! interoperate with other implementations. The same constants drive
+//! `gadgets::mimc`, so results computed here and in a circuit agree.
+//!
+//! [MiMC]: https://eprint.iacr.org/2016/492
+//! [GMiMC]: https://eprint.iacr.org/2019/397
+//! [circomlib]: https://github.com/iden3/circomlib/blob/master/src/mimc7.js
+
+use ff::{Field, PrimeField, ScalarEngine};
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::poseidon::hash_to_field;
+
+const MIMC_SEED: &[u8] = b"mimc";
+
+fn keccak256(input: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(input);
+    let mut digest = [0u8; 32];
+    keccak.finalize(&mut digest);
+    digest
+}
+
+/// Reduces a big-endian integer modulo the field order.
+fn from_be_bytes_mod_order<F: PrimeField>(bytes: &[u8]) -> F {
+    let from_u64 = |n: u64| F::from_repr(n.into()).expect("n is smaller than the field order");
+    let radix = from_u64(256);
+    bytes.iter().fold(F::zero(), |mut acc, b| {
+        acc.mul_assign(&radix);
+        acc.add_assign(&from_u64(u64::from(*b)));
+        acc
+    })
+}
+
+/// Round constants of MiMC-n/n.
+pub struct MimcConstants<E: ScalarEngine> {
+    round_constants: Vec<E::Fr>,
+}
+
+impl<E: ScalarEngine> Clone for MimcConstants<E> {
+    fn clone(&self) -> Self {
+        MimcConstants {
+            round_constants: self.round_constants.clone(),
+        }
+    }
+}
+
+impl<E: ScalarEngine> MimcConstants<E> {
+    /// Constants for `ceil(log_5 p)` rounds, the number the MiMC paper
+    /// requires for the `x^5` S-box.
+    pub fn new() -> Self {
+        let log2_5 = 5f64.log2();
+        Self::with_rounds((f64::from(E::Fr::NUM_BITS) / log2_5).ceil() as usize)
+    }
+
+    pub fn with_rounds(rounds: usize) -> Self {
+        let mut digest = keccak256(MIMC_SEED);
+        let round_constants = (0..rounds)
+            .map(|i| {
+                if i == 0 {
+                    E::Fr::zero()
+                } else {
+                    digest = keccak256(&digest);
+                    from_be_bytes_mod_order(&digest)
+                }
+            })
+            .collect();
+
+        MimcConstants { round_constants }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.round_constants.len()
+    }
+
+    pub fn round_constants(&self) -> &[E::Fr] {
+        &self.round_constants
+    }
+}
+
+impl<E: ScalarEngine> Default for MimcConstants<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pow5<F: Field>(x: &mut F) {
+    let mut x4 = *x;
+    x4.square();
+    x4.square();
+    x.mul_assign(&x4);
+}
+
+/// Encrypts `message` under `key`: each round maps `x` to
+/// `(x + key + c_i)^5`, and the key is added to the result.
+pub fn encrypt<E: ScalarEngine>(constants: &MimcConstants<E>, key: E::Fr, message: E::Fr) -> E::Fr {
+    let mut x = message;
+    for c in constants.round_constants.iter() {
+        x.add_assign(&key);
+        x.add_assign(c);
+        pow5(&mut x);
+    }
+    x.add_assign(&key);
+
+    x
+}
+
+/// Hashes `inputs` with the Miyaguchi-Preneel construction, starting from
+/// zero: each input `m` updates the digest `h` to `encrypt(h, m) + h + m`.
+pub fn mimc_hash<E: ScalarEngine>(constants: &MimcConstants<E>, inputs: &[E::Fr]) -> E::Fr {
+    inputs.iter().fold(E::Fr::zero(), |h, m| {
+        let mut next = encrypt(constants, h, *m);
+        next.add_assign(&h);
+        next.add_assign(m);
+        next
+    })
+}
+
+/// Width and round constants of a GMiMC permutation with the expanding
+/// round function.
+pub struct GmimcConstants<E: ScalarEngine> {
+    width: usize,
+    round_constants: Vec<E::Fr>,
+}
+
+impl<E: ScalarEngine> Clone for GmimcConstants<E> {
+    fn clone(&self) -> Self {
+        GmimcConstants {
+            width: self.width,
+            round_constants: self.round_constants.clone(),
+        }
+    }
+}
+
+impl<E: ScalarEngine> GmimcConstants<E> {
+    /// Constants for a permutation of `width` elements with `rounds` rounds,
+    /// which the GMiMC paper gives for each width and security level.
+    pub fn new(width: usize, rounds: usize) -> Self {
+        assert!(width >= 2, "width must be at least 2");
+
+        let tag = format!("crusty3_zk gmimc round constant {} {}", width, rounds);
+        let round_constants = (0..rounds)
+            .map(|i| hash_to_field::<E::Fr>(tag.as_bytes(), i as u64))
+            .collect();
+
+        GmimcConstants {
+            width,
+            round_constants,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of elements hashed at once by [`gmimc`].
+    pub fn arity(&self) -> usize {
+        self.width - 1
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.round_constants.len()
+    }
+
+    pub fn round_constants(&self) -> &[E::Fr] {
+        &self.round_constants
+    }
+}
+
+/// Applies the GMiMC permutation to `state` in place. Each round adds
+/// `(x_0 + c_i)^5` to every other element and rotates `x_0` to the end.
+pub fn gmimc_permute<E: ScalarEngine>(constants: &GmimcConstants<E>, state: &mut [E::Fr]) {
+    assert_eq!(state.len(), constants.width, "wrong state width");
+
+    for c in constants.round_constants.iter() {
+        let mut s = state[0];
+        s.add_assign(c);
+        pow5(&mut s);
+
+        for elt in state[1..].iter_mut() {
+            elt.add_assign(&s);
+        }
+        state.rotate_left(1);
+    }
+}
+
+/// Hashes `inputs`, of which there must be `constants.arity()`, by permuting
+/// them after a zero capacity element and taking the second element.
+pub fn gmimc<E: ScalarEngine>(constants: &GmimcConstants<E>, inputs: &[E::Fr]) -> E::Fr {
+    assert_eq!(inputs.len(), constants.arity(), "wrong number of inputs");
+
+    let mut state = Vec::with_capacity(constants.width);
+    state.push(E::Fr::zero());
+    state.extend_from_slice(inputs);
+
+    gmimc_permute(constants, &mut state);
+
+    state[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+
+    #[test]
+    fn test_mimc_constants() {
+        let constants = MimcConstants::<Bls12>::new();
+        assert_eq!(constants.rounds(), 110);
+        assert_eq!(constants.round_constants()[0], Fr::zero());
+        // keccak256(keccak256("mimc")) modulo r. Modulo the BN254 order it
+        // is the first non-zero constant of circomlib.
+        assert_eq!(
+            constants.round_constants()[1],
+            Fr::from_str(
+                "12229571979494343421523498192994790888706681595080595532007690219807782377702"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            constants.round_constants(),
+            MimcConstants::<Bls12>::new().round_constants()
+        );
+    }
+
+    #[test]
+    fn test_mimc() {
+        let constants = MimcConstants::<Bls12>::new();
+        let one = Fr::one();
+        let two = Fr::from_str("2").unwrap();
+
+        assert!(encrypt(&constants, one, two) != encrypt(&constants, two, one));
+
+        let h = mimc_hash(&constants, &[one, two]);
+        assert!(h != mimc_hash(&constants, &[two, one]));
+        assert!(h != mimc_hash(&constants, &[one]));
+
+        // One step of Miyaguchi-Preneel.
+        let mut expected = encrypt(&constants, Fr::zero(), one);
+        expected.add_assign(&one);
+        assert_eq!(mimc_hash(&constants, &[one]), expected);
+
+        // A single round is (m + k)^5 + k.
+        let single = MimcConstants::<Bls12>::with_rounds(1);
+        let mut expected = one;
+        expected.add_assign(&two);
+        pow5(&mut expected);
+        expected.add_assign(&two);
+        assert_eq!(encrypt(&single, two, one), expected);
+    }
+
+    #[test]
+    fn test_gmimc() {
+        let constants = GmimcConstants::<Bls12>::new(3, 100);
+        let one = Fr::one();
+        let two = Fr::from_str("2").unwrap();
+
+        assert_eq!(constants.arity(), 2);
+        let h = gmimc(&constants, &[one, two]);
+        assert_eq!(h, gmimc(&constants, &[one, two]));
+        assert!(h != gmimc(&constants, &[two, one]));
+
+        // The digest is the second element of the permuted state.
+        let mut state = vec![Fr::zero(), one, two];
+        gmimc_permute(&constants, &mut state);
+        assert_eq!(h, state[1]);
+
+        assert!(
+            GmimcConstants::<Bls12>::new(3, 101).round_constants()[0]
+                != constants.round_constants()[0]
+        );
+    }
+}
@@ -5,9 +5,17 @@
 //!
 //! [`CpuPool`]: futures_cpupool::CpuPool
 
-use crossbeam_channel::{bounded, Receiver};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError};
 use lazy_static::lazy_static;
 use std::env;
+use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
 
 lazy_static! {
     static ref NUM_CPUS: usize = if let Ok(num) = env::var("BELLMAN_NUM_CPUS") {
@@ -29,16 +37,81 @@ lazy_static! {
         .unwrap();
 }
 
+/// A thread pool to run the prover or the verifier on instead of the global
+/// ones, for example the pool a service already owns.
 #[derive(Clone)]
-pub struct Worker {}
+pub struct Workers {
+    pool: Arc<rayon::ThreadPool>,
+}
+
+impl Workers {
+    /// A new pool of `num_threads` threads.
+    pub fn new(num_threads: usize) -> Result<Self, rayon::ThreadPoolBuildError> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()?;
+        Ok(Self::from_pool(Arc::new(pool)))
+    }
+
+    pub fn from_pool(pool: Arc<rayon::ThreadPool>) -> Self {
+        Workers { pool }
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    pub(crate) fn pool(&self) -> &rayon::ThreadPool {
+        &self.pool
+    }
+
+    /// A `Worker` that spawns its jobs on this pool.
+    pub fn worker(&self) -> Worker {
+        Worker {
+            pool: Some(self.pool.clone()),
+        }
+    }
+
+    /// Runs `f` on the pool, so that the parallel work it starts stays in the
+    /// pool.
+    pub fn install<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.pool.install(f)
+    }
+}
+
+impl fmt::Debug for Workers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Workers")
+            .field("num_threads", &self.num_threads())
+            .finish()
+    }
+}
+
+/// Spawns jobs on `THREAD_POOL`, or on the pool of the `Workers` it was
+/// taken from.
+#[derive(Clone)]
+pub struct Worker {
+    pool: Option<Arc<rayon::ThreadPool>>,
+}
 
 impl Worker {
     pub fn new() -> Worker {
-        Worker {}
+        Worker { pool: None }
+    }
+
+    fn pool(&self) -> &rayon::ThreadPool {
+        match &self.pool {
+            Some(pool) => pool,
+            None => &THREAD_POOL,
+        }
     }
 
     pub fn log_num_cpus(&self) -> u32 {
-        log2_floor(*NUM_CPUS)
+        log2_floor(self.pool().current_num_threads())
     }
 
     pub fn compute<F, R>(&self, f: F) -> Waiter<R>
@@ -47,10 +120,18 @@ impl Worker {
         R: Send + 'static,
     {
         let (sender, receiver) = bounded(1);
-        THREAD_POOL.spawn(move || {
+        let job = move || {
             let res = f();
-            sender.send(res).unwrap();
-        });
+            // The receiver may have stopped waiting, e.g. after a timeout.
+            let _ = sender.send(res);
+        };
+        // The only thread of the pool would wait for a job queued behind it.
+        let pool = self.pool();
+        if pool.current_num_threads() == 1 && pool.current_thread_index().is_some() {
+            job();
+        } else {
+            pool.spawn(job);
+        }
 
         Waiter { receiver }
     }
@@ -60,13 +141,14 @@ impl Worker {
         F: FnOnce(&rayon::Scope<'a>, usize) -> R + Send,
         R: Send,
     {
-        let chunk_size = if elements < *NUM_CPUS {
+        let num_threads = self.pool().current_num_threads();
+        let chunk_size = if elements < num_threads {
             1
         } else {
-            elements / *NUM_CPUS
+            elements / num_threads
         };
 
-        THREAD_POOL.scope(|scope| f(scope, chunk_size))
+        self.pool().scope(|scope| f(scope, chunk_size))
     }
 }
 
@@ -80,6 +162,16 @@ impl<T> Waiter<T> {
         self.receiver.recv().unwrap()
     }
 
+    /// Wait for the result at most `timeout`.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<T, WaitError> {
+        self.receiver
+            .recv_timeout(timeout)
+            .map_err(|err| match err {
+                RecvTimeoutError::Timeout => WaitError::Timeout,
+                RecvTimeoutError::Disconnected => WaitError::Disconnected,
+            })
+    }
+
     /// One off sending.
     pub fn done(val: T) -> Self {
         let (sender, receiver) = bounded(1);
@@ -89,6 +181,98 @@ impl<T> Waiter<T> {
     }
 }
 
+/// Why `Waiter::wait_timeout` returned without a result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitError {
+    /// The result wasn't ready in time, it may still come.
+    Timeout,
+    /// The computation panicked, the result will never come.
+    Disconnected,
+}
+
+/// A blocking computation running on its own thread, as a `Future`.
+///
+/// The thread only drives the computation, whose heavy parts run on the
+/// thread pools as usual, so awaiting a task never blocks the executor of the
+/// caller. A panic of the computation is resumed in the task that polls it.
+pub struct Task<T> {
+    state: Arc<Mutex<TaskState<T>>>,
+}
+
+struct TaskState<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+impl<T: Send + 'static> Task<T> {
+    pub fn spawn<F>(f: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(TaskState {
+            result: None,
+            waker: None,
+        }));
+
+        let task_state = state.clone();
+        thread::Builder::new()
+            .name("bellman-task".to_string())
+            .spawn(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+                let mut state = task_state.lock().unwrap();
+                state.result = Some(result);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            })
+            .expect("failed to spawn a task thread");
+
+        Task { state }
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Runs `future` to completion on the current thread, for tests without an
+/// executor.
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    use std::task::{RawWaker, RawWakerVTable};
+
+    fn clone(data: *const ()) -> RawWaker {
+        RawWaker::new(data, &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    let waker = unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
 fn log2_floor(num: usize) -> u32 {
     assert!(num > 0);
 
@@ -114,4 +298,67 @@ mod tests {
         assert_eq!(log2_floor(7), 2);
         assert_eq!(log2_floor(8), 3);
     }
+
+    #[test]
+    fn test_workers() {
+        let workers = Workers::new(3).unwrap();
+        assert_eq!(workers.num_threads(), 3);
+
+        // Work started on the pool stays on it.
+        let worker = workers.worker();
+        let (index, num_threads) = worker
+            .compute(|| (rayon::current_thread_index(), rayon::current_num_threads()))
+            .wait();
+        assert!(index.unwrap() < 3);
+        assert_eq!(num_threads, 3);
+        assert_eq!(worker.log_num_cpus(), 1);
+        let num_threads = worker.scope(1, |scope, _| {
+            let (sender, receiver) = bounded(1);
+            scope.spawn(move |_| sender.send(rayon::current_num_threads()).unwrap());
+            receiver
+        });
+        assert_eq!(num_threads.recv().unwrap(), 3);
+
+        // A single thread waiting for its own job runs it instead.
+        let workers = Workers::new(1).unwrap();
+        let worker = workers.worker();
+        let inner = worker.clone();
+        let result = workers.install(|| worker.compute(move || inner.compute(|| 42).wait()).wait());
+        assert_eq!(result, 42);
+    }
+
+    #[test]
+    fn test_wait_timeout() {
+        let worker = Worker::new();
+        let waiter = worker.compute(|| {
+            thread::sleep(Duration::from_millis(50));
+            42
+        });
+        assert_eq!(
+            waiter.wait_timeout(Duration::from_millis(1)),
+            Err(WaitError::Timeout)
+        );
+        assert_eq!(waiter.wait_timeout(Duration::from_secs(10)), Ok(42));
+
+        // What a waiter sees once its job has been dropped without a result.
+        let (_, receiver) = bounded::<usize>(1);
+        let waiter = Waiter { receiver };
+        assert_eq!(
+            waiter.wait_timeout(Duration::from_secs(10)),
+            Err(WaitError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn test_task() {
+        let task = Task::spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            42
+        });
+        assert_eq!(block_on(task), 42);
+
+        let task = Task::spawn(|| -> usize { panic!("task failed") });
+        let result = panic::catch_unwind(AssertUnwindSafe(|| block_on(task)));
+        assert!(result.is_err());
+    }
 }
@@ -17,6 +17,12 @@ pub trait SourceBuilder<G: CurveAffine>: Send + Sync + 'static + Clone {
 
     fn new(self) -> Self::Source;
     fn get(self) -> (Arc<Vec<G>>, usize);
+
+    /// The precomputed window tables of the bases, if there are any, and the
+    /// index of the first base of this source in them.
+    fn precomputed(&self) -> Option<(Arc<PrecomputedBases<G>>, usize)> {
+        None
+    }
 }
 
 /// A source of bases, like an iterator.
@@ -27,6 +33,18 @@ pub trait Source<G: CurveAffine> {
         to: &mut <G as CurveAffine>::Projective,
     ) -> Result<(), SynthesisError>;
 
+    /// Like `add_assign_mixed`, but subtracts the element.
+    fn sub_assign_mixed(
+        &mut self,
+        to: &mut <G as CurveAffine>::Projective,
+    ) -> Result<(), SynthesisError> {
+        let mut base = <G as CurveAffine>::Projective::zero();
+        self.add_assign_mixed(&mut base)?;
+        to.sub_assign(&base);
+
+        Ok(())
+    }
+
     /// Skips `amt` elements from the source, avoiding deserialization.
     fn skip(&mut self, amt: usize) -> Result<(), SynthesisError>;
 }
@@ -67,6 +85,31 @@ impl<G: CurveAffine> Source<G> for (Arc<Vec<G>>, usize) {
         Ok(())
     }
 
+    fn sub_assign_mixed(
+        &mut self,
+        to: &mut <G as CurveAffine>::Projective,
+    ) -> Result<(), SynthesisError> {
+        if self.0.len() <= self.1 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "expected more bases from source",
+            )
+            .into());
+        }
+
+        if self.0[self.1].is_zero() {
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+
+        let mut base = self.0[self.1];
+        base.negate();
+        to.add_assign_mixed(&base);
+
+        self.1 += 1;
+
+        Ok(())
+    }
+
     fn skip(&mut self, amt: usize) -> Result<(), SynthesisError> {
         if self.0.len() <= self.1 {
             return Err(io::Error::new(
@@ -82,6 +125,91 @@ impl<G: CurveAffine> Source<G> for (Arc<Vec<G>>, usize) {
     }
 }
 
+/// The multiples `2^(c j) P` of fixed bases `P`, for every window `j` of `c`
+/// bits of an exponent.
+///
+/// A multiexp over these needs a single pass over the buckets instead of one
+/// per window, and no doublings to combine the windows, at the cost of
+/// storing about `256 / c` points per base.
+pub struct PrecomputedBases<G: CurveAffine> {
+    c: u32,
+    windows: usize,
+    // The multiples of base `i` are at `i * windows..(i + 1) * windows`.
+    multiples: Vec<G>,
+}
+
+impl<G: CurveAffine> PrecomputedBases<G> {
+    /// Precomputes the tables of `bases` for windows of `c` bits.
+    pub fn new(bases: &[G], c: u32) -> Self {
+        assert!(c > 0 && c < 32, "window size out of range");
+        // The signed digits may carry into one more window, see `signed_digit`.
+        let windows = ((<G::Engine as ScalarEngine>::Fr::NUM_BITS + c) / c) as usize;
+
+        let multiples = bases
+            .par_iter()
+            .map(|base| {
+                let mut multiples = Vec::with_capacity(windows);
+                let mut multiple = base.into_projective();
+                for _ in 0..windows {
+                    multiples.push(multiple);
+                    for _ in 0..c {
+                        multiple.double();
+                    }
+                }
+                <G as CurveAffine>::Projective::batch_normalization(&mut multiples);
+                multiples
+                    .into_iter()
+                    .map(|m| m.into_affine())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+            .concat();
+
+        PrecomputedBases {
+            c,
+            windows,
+            multiples,
+        }
+    }
+
+    /// The number of bases.
+    pub fn len(&self) -> usize {
+        self.multiples.len() / self.windows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.multiples.is_empty()
+    }
+
+    pub fn window_size(&self) -> u32 {
+        self.c
+    }
+}
+
+/// A source of bases with precomputed window tables.
+#[derive(Clone)]
+pub struct PrecomputedSource<G: CurveAffine> {
+    pub bases: Arc<Vec<G>>,
+    pub tables: Arc<PrecomputedBases<G>>,
+    pub skip: usize,
+}
+
+impl<G: CurveAffine> SourceBuilder<G> for PrecomputedSource<G> {
+    type Source = (Arc<Vec<G>>, usize);
+
+    fn new(self) -> (Arc<Vec<G>>, usize) {
+        (self.bases, self.skip)
+    }
+
+    fn get(self) -> (Arc<Vec<G>>, usize) {
+        (self.bases, self.skip)
+    }
+
+    fn precomputed(&self) -> Option<(Arc<PrecomputedBases<G>>, usize)> {
+        Some((self.tables.clone(), self.skip))
+    }
+}
+
 pub trait QueryDensity {
     /// Returns whether the base exists.
     type Iter: Iterator<Item = bool>;
@@ -192,6 +320,32 @@ impl DensityTracker {
     }
 }
 
+/// The digit of `exp` in the window of `c` bits starting at bit `skip`, in a
+/// recoding of `exp` with digits in `[-2^(c-1), 2^(c-1)]`.
+///
+/// A window whose top bit is set is read as negative, and its lower
+/// neighbour's digit is incremented to make up for it. The carry into the
+/// window at `skip` is therefore the bit at `skip - 1`, so every window can
+/// still be recoded on its own.
+fn signed_digit<R: PrimeFieldRepr>(mut exp: R, skip: u32, c: u32) -> i64 {
+    let carry = if skip > 0 {
+        exp.shr(skip - 1);
+        let carry = exp.as_ref()[0] & 1;
+        exp.shr(1);
+        carry
+    } else {
+        0
+    };
+
+    let raw = exp.as_ref()[0] % (1 << c);
+    let digit = (raw + carry) as i64;
+    if raw >= 1 << (c - 1) {
+        digit - (1 << c)
+    } else {
+        digit
+    }
+}
+
 fn multiexp_inner<Q, D, G, S>(
     bases: S,
     density_map: D,
@@ -216,8 +370,10 @@ where
         // Build a source for the bases
         let mut bases = bases.new();
 
-        // Create space for the buckets
-        let mut buckets = vec![<G as CurveAffine>::Projective::zero(); (1 << c) - 1];
+        // Create space for the buckets. The digits are signed, so a base
+        // whose digit is negative is subtracted from the bucket of its
+        // absolute value, which halves the number of buckets.
+        let mut buckets = vec![<G as CurveAffine>::Projective::zero(); 1 << (c - 1)];
 
         let zero = <G::Engine as ScalarEngine>::Fr::zero().into_repr();
         let one = <G::Engine as ScalarEngine>::Fr::one().into_repr();
@@ -237,14 +393,12 @@ where
                         bases.skip(1)?;
                     }
                 } else {
-                    let mut exp = exp;
-                    exp.shr(skip);
-                    let exp = exp.as_ref()[0] % (1 << c);
-
-                    if exp != 0 {
-                        bases.add_assign_mixed(&mut buckets[(exp - 1) as usize])?;
-                    } else {
-                        bases.skip(1)?;
+                    match signed_digit(exp, skip, c) {
+                        0 => bases.skip(1)?,
+                        digit if digit > 0 => {
+                            bases.add_assign_mixed(&mut buckets[(digit - 1) as usize])?
+                        }
+                        digit => bases.sub_assign_mixed(&mut buckets[(-digit - 1) as usize])?,
                     }
                 }
             }
@@ -263,7 +417,8 @@ where
         Ok(acc)
     };
 
-    let parts = (0..<G::Engine as ScalarEngine>::Fr::NUM_BITS)
+    // The top window may carry into one more window.
+    let parts = (0..=<G::Engine as ScalarEngine>::Fr::NUM_BITS)
         .into_par_iter()
         .step_by(c as usize)
         .map(|skip| this(bases.clone(), density_map.clone(), exponents.clone(), skip))
@@ -282,6 +437,80 @@ where
         })
 }
 
+fn multiexp_precomputed_inner<Q, D, G>(
+    tables: &PrecomputedBases<G>,
+    skip: usize,
+    density_map: D,
+    exponents: Arc<Vec<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>>,
+) -> Result<<G as CurveAffine>::Projective, SynthesisError>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: CurveAffine,
+{
+    let zero = <G::Engine as ScalarEngine>::Fr::zero().into_repr();
+
+    // Pair the exponents with the index of their base.
+    let mut terms = Vec::with_capacity(exponents.len());
+    let mut index = skip;
+    for (&exp, density) in exponents.iter().zip(density_map.as_ref().iter()) {
+        if density {
+            if exp != zero {
+                terms.push((index, exp));
+            }
+            index += 1;
+        }
+    }
+    if index > tables.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "expected more bases from source",
+        )
+        .into());
+    }
+
+    let c = tables.c;
+    let windows = tables.windows;
+    let chunk = (terms.len() / rayon::current_num_threads()).max(1);
+
+    Ok(terms
+        .par_chunks(chunk)
+        .map(|terms| {
+            // All windows share one set of buckets, since the base of window
+            // `j` already is multiplied by `2^(c j)`.
+            let mut buckets = vec![<G as CurveAffine>::Projective::zero(); 1 << (c - 1)];
+
+            for &(index, exp) in terms {
+                let multiples = &tables.multiples[index * windows..(index + 1) * windows];
+                for (j, multiple) in multiples.iter().enumerate() {
+                    match signed_digit(exp, j as u32 * c, c) {
+                        0 => {}
+                        digit if digit > 0 => {
+                            buckets[(digit - 1) as usize].add_assign_mixed(multiple)
+                        }
+                        digit => {
+                            let mut multiple = *multiple;
+                            multiple.negate();
+                            buckets[(-digit - 1) as usize].add_assign_mixed(&multiple)
+                        }
+                    }
+                }
+            }
+
+            let mut acc = G::Projective::zero();
+            let mut running_sum = G::Projective::zero();
+            for exp in buckets.into_iter().rev() {
+                running_sum.add_assign(&exp);
+                acc.add_assign(&running_sum);
+            }
+            acc
+        })
+        .reduce(G::Projective::zero, |mut acc, part| {
+            acc.add_assign(&part);
+            acc
+        }))
+}
+
 /// Perform multi-exponentiation. The caller is responsible for ensuring the
 /// query size is the same as the number of exponents.
 pub fn multiexp<Q, D, G, S>(
@@ -328,7 +557,12 @@ where
         assert!(query_size == exponents.len());
     }
 
-    let result = pool.compute(move || multiexp_inner(bases, density_map, exponents, c));
+    let result = match bases.precomputed() {
+        Some((tables, skip)) => {
+            pool.compute(move || multiexp_precomputed_inner(&tables, skip, density_map, exponents))
+        }
+        None => pool.compute(move || multiexp_inner(bases, density_map, exponents, c)),
+    };
 
     #[cfg(feature = "gpu")]
     {
@@ -393,11 +627,110 @@ fn test_with_bls12() {
     assert_eq!(naive, fast);
 }
 
-pub fn create_multiexp_kernel<E>(_log_d: usize, priority: bool) -> Option<gpu::MultiexpKernel<E>>
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn test_signed_digits() {
+    use crate::bls::Fr;
+
+    let rng = &mut rand::thread_rng();
+    let two = Fr::from_str("2").unwrap();
+
+    for c in 1..20 {
+        for x in (0..20)
+            .map(|_| Fr::random(rng))
+            .chain(vec![Fr::zero(), Fr::one()])
+        {
+            // Recompose x from its digits, most significant window first.
+            let mut acc = Fr::zero();
+            let skips = (0..=Fr::NUM_BITS).step_by(c as usize).collect::<Vec<_>>();
+            for &skip in skips.iter().rev() {
+                for _ in 0..c {
+                    acc.mul_assign(&two);
+                }
+
+                let digit = signed_digit(x.into_repr(), skip, c);
+                assert!(digit.abs() <= 1 << (c - 1));
+                let abs = Fr::from_str(&digit.abs().to_string()).unwrap();
+                if digit < 0 {
+                    acc.sub_assign(&abs);
+                } else {
+                    acc.add_assign(&abs);
+                }
+            }
+            assert_eq!(acc, x);
+        }
+    }
+}
+
+#[cfg(any(feature = "pairing", feature = "blst"))]
+#[test]
+fn test_precomputed_multiexp() {
+    use crate::bls::{Bls12, Engine};
+
+    const SAMPLES: usize = 1 << 8;
+
+    let rng = &mut rand::thread_rng();
+    let g = Arc::new(
+        (0..SAMPLES)
+            .map(|_| <Bls12 as Engine>::G1::random(rng).into_affine())
+            .collect::<Vec<_>>(),
+    );
+    let pool = Worker::new();
+
+    for &c in &[1, 4, 11] {
+        let tables = Arc::new(PrecomputedBases::new(&g, c));
+        assert_eq!(tables.len(), SAMPLES);
+
+        // Every other base, starting after the first one.
+        let mut density = DensityTracker::new();
+        for i in 0..SAMPLES - 1 {
+            density.add_element();
+            if i % 2 == 0 {
+                density.inc(i);
+            }
+        }
+        let v = Arc::new(
+            (0..SAMPLES - 1)
+                .map(|i| match i {
+                    0 => <Bls12 as ScalarEngine>::Fr::one().into_repr(),
+                    2 => <Bls12 as ScalarEngine>::Fr::zero().into_repr(),
+                    _ => <Bls12 as ScalarEngine>::Fr::random(rng).into_repr(),
+                })
+                .collect::<Vec<_>>(),
+        );
+        let density = Arc::new(density);
+
+        let expected = multiexp(&pool, (g.clone(), 1), density.clone(), v.clone(), &mut None)
+            .wait()
+            .unwrap();
+        let source = PrecomputedSource {
+            bases: g.clone(),
+            tables,
+            skip: 1,
+        };
+        let precomputed = multiexp(&pool, source, density, v, &mut None)
+            .wait()
+            .unwrap();
+        assert_eq!(expected, precomputed);
+    }
+}
+
+pub fn create_multiexp_kernel<E>(log_d: usize, priority: bool) -> Option<gpu::MultiexpKernel<E>>
+where
+    E: crate::bls::Engine,
+{
+    create_multiexp_kernel_with_options(log_d, priority, &gpu::GpuOptions::default())
+}
+
+pub fn create_multiexp_kernel_with_options<E>(
+    _log_d: usize,
+    priority: bool,
+    options: &gpu::GpuOptions,
+) -> Option<gpu::MultiexpKernel<E>>
 where
     E: crate::bls::Engine,
 {
-    match gpu::MultiexpKernel::<E>::create(priority) {
+    match gpu::MultiexpKernel::<E>::create_with_options(priority, options) {
         Ok(k) => {
             info!("GPU Multiexp kernel instantiated!");
             Some(k)
@@ -472,16 +805,13 @@ pub fn gpu_multiexp_consistency() {
 mod tests {
     use super::*;
 
+    use crate::util_cs::test_fixtures::test_rng;
     use rand::Rng;
-    use rand_core::SeedableRng;
     use rand_xorshift::XorShiftRng;
 
     #[test]
     fn test_extend_density_regular() {
-        let mut rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
+        let mut rng = test_rng();
 
         for k in &[2, 4, 8] {
             for j in &[10, 20, 50] {
@@ -523,10 +853,7 @@ mod tests {
 
     #[test]
     fn test_extend_density_input() {
-        let mut rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
+        let mut rng = test_rng();
         let trials = 10;
         let max_bits = 10;
         let max_density = max_bits;
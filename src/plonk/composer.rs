@@ -0,0 +1,310 @@
+use ff::Field;
+
+use crate::bls::Engine;
+use crate::SynthesisError;
+
+/// A variable of a PLONK circuit. Every wire a variable is placed on carries
+/// its value, which the permutation argument enforces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Var(pub(crate) usize);
+
+/// A gate `q_l a + q_r b + q_o c + q_m a b + q_c = 0` on the wires `a`, `b`
+/// and `c`.
+pub struct Gate<E: Engine> {
+    pub a: Var,
+    pub b: Var,
+    pub c: Var,
+    pub q_l: E::Fr,
+    pub q_r: E::Fr,
+    pub q_o: E::Fr,
+    pub q_m: E::Fr,
+    pub q_c: E::Fr,
+}
+
+impl<E: Engine> Clone for Gate<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: Engine> Copy for Gate<E> {}
+
+impl<E: Engine> Gate<E> {
+    /// A gate on the given wires with all selectors zero.
+    pub fn new(a: Var, b: Var, c: Var) -> Self {
+        Gate {
+            a,
+            b,
+            c,
+            q_l: E::Fr::zero(),
+            q_r: E::Fr::zero(),
+            q_o: E::Fr::zero(),
+            q_m: E::Fr::zero(),
+            q_c: E::Fr::zero(),
+        }
+    }
+
+    // The left-hand side of the gate for the wire values `a`, `b` and `c`.
+    pub(crate) fn evaluate(&self, a: &E::Fr, b: &E::Fr, c: &E::Fr) -> E::Fr {
+        let mut acc = self.q_c;
+        for (q, w) in &[(self.q_l, *a), (self.q_r, *b), (self.q_o, *c)] {
+            let mut term = *q;
+            term.mul_assign(w);
+            acc.add_assign(&term);
+        }
+        let mut term = self.q_m;
+        term.mul_assign(a);
+        term.mul_assign(b);
+        acc.add_assign(&term);
+
+        acc
+    }
+}
+
+/// A circuit in the arithmetization of PLONK, the counterpart of
+/// `crate::Circuit` for the `plonk` module.
+pub trait Circuit<E: Engine> {
+    /// Allocates the variables of the circuit and adds its gates.
+    fn synthesize(self, composer: &mut Composer<E>) -> Result<(), SynthesisError>;
+}
+
+/// Collects the variables and gates of a circuit.
+///
+/// The public inputs take the first gates, in the order they are allocated,
+/// followed by a gate fixing `zero` and then the gates added by the circuit.
+/// Unused wires are set to `zero`.
+pub struct Composer<E: Engine> {
+    // Whether the values of the variables are computed, otherwise they are
+    // all zero.
+    witness: bool,
+    values: Vec<E::Fr>,
+    inputs: Vec<Var>,
+    gates: Vec<Gate<E>>,
+}
+
+impl<E: Engine> Composer<E> {
+    pub(crate) fn new(witness: bool) -> Self {
+        let mut composer = Composer {
+            witness,
+            values: vec![E::Fr::zero()],
+            inputs: vec![],
+            gates: vec![],
+        };
+
+        let zero = composer.zero();
+        let mut gate = Gate::new(zero, zero, zero);
+        gate.q_l = E::Fr::one();
+        composer.gate(gate);
+
+        composer
+    }
+
+    /// A variable that is always zero.
+    pub fn zero(&self) -> Var {
+        Var(0)
+    }
+
+    /// The value of `var`, `None` when creating keys.
+    pub fn value(&self, var: Var) -> Option<E::Fr> {
+        if self.witness {
+            Some(self.values[var.0])
+        } else {
+            None
+        }
+    }
+
+    /// Allocates a private variable. `value` is only called when proving.
+    pub fn alloc<F>(&mut self, value: F) -> Result<Var, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+    {
+        let value = if self.witness {
+            value()?
+        } else {
+            E::Fr::zero()
+        };
+        self.values.push(value);
+
+        Ok(Var(self.values.len() - 1))
+    }
+
+    /// Allocates a public input. `value` is only called when proving.
+    pub fn alloc_input<F>(&mut self, value: F) -> Result<Var, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+    {
+        let var = self.alloc(value)?;
+        self.inputs.push(var);
+
+        Ok(var)
+    }
+
+    /// Adds a gate. The values of its wires are not checked here, an
+    /// unsatisfied gate makes `create_proof` fail.
+    pub fn gate(&mut self, gate: Gate<E>) {
+        self.gates.push(gate);
+    }
+
+    /// Returns a new variable `a + b`.
+    pub fn add(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a).and_then(|mut a| {
+            self.value(b).map(|b| {
+                a.add_assign(&b);
+                a
+            })
+        });
+        let c = self.derived(value);
+
+        let mut gate = Gate::new(a, b, c);
+        gate.q_l = E::Fr::one();
+        gate.q_r = E::Fr::one();
+        gate.q_o = minus_one::<E>();
+        self.gate(gate);
+
+        c
+    }
+
+    /// Returns a new variable `a * b`.
+    pub fn mul(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a).and_then(|mut a| {
+            self.value(b).map(|b| {
+                a.mul_assign(&b);
+                a
+            })
+        });
+        let c = self.derived(value);
+
+        let mut gate = Gate::new(a, b, c);
+        gate.q_m = E::Fr::one();
+        gate.q_o = minus_one::<E>();
+        self.gate(gate);
+
+        c
+    }
+
+    /// Returns a new variable `a + constant`.
+    pub fn add_constant(&mut self, a: Var, constant: E::Fr) -> Var {
+        let value = self.value(a).map(|mut a| {
+            a.add_assign(&constant);
+            a
+        });
+        let c = self.derived(value);
+
+        let zero = self.zero();
+        let mut gate = Gate::new(a, zero, c);
+        gate.q_l = E::Fr::one();
+        gate.q_o = minus_one::<E>();
+        gate.q_c = constant;
+        self.gate(gate);
+
+        c
+    }
+
+    /// Enforces `a = b`.
+    pub fn assert_equal(&mut self, a: Var, b: Var) {
+        let zero = self.zero();
+        let mut gate = Gate::new(a, b, zero);
+        gate.q_l = E::Fr::one();
+        gate.q_r = minus_one::<E>();
+        self.gate(gate);
+    }
+
+    /// Enforces `a = constant`.
+    pub fn assert_constant(&mut self, a: Var, constant: E::Fr) {
+        let zero = self.zero();
+        let mut gate = Gate::new(a, zero, zero);
+        gate.q_l = E::Fr::one();
+        gate.q_c = constant;
+        gate.q_c.negate();
+        self.gate(gate);
+    }
+
+    /// The number of gates, including those of the public inputs.
+    pub fn num_gates(&self) -> usize {
+        self.inputs.len() + self.gates.len()
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    // A variable holding `value`, which is `None` exactly when creating keys.
+    pub(crate) fn derived(&mut self, value: Option<E::Fr>) -> Var {
+        self.values.push(value.unwrap_or_else(E::Fr::zero));
+        Var(self.values.len() - 1)
+    }
+
+    // The gates in the order of the rows of the circuit.
+    pub(crate) fn rows(&self) -> Vec<Gate<E>> {
+        let zero = self.zero();
+        let inputs = self.inputs.iter().map(|&input| {
+            let mut gate = Gate::new(input, zero, zero);
+            gate.q_l = E::Fr::one();
+            gate
+        });
+
+        inputs.chain(self.gates.iter().copied()).collect()
+    }
+
+    // The values of the public inputs, see `value`.
+    pub(crate) fn input_values(&self) -> Vec<E::Fr> {
+        self.inputs
+            .iter()
+            .map(|input| self.values[input.0])
+            .collect()
+    }
+
+    pub(crate) fn values(&self) -> &[E::Fr] {
+        &self.values
+    }
+}
+
+fn minus_one<E: Engine>() -> E::Fr {
+    let mut minus_one = E::Fr::one();
+    minus_one.negate();
+    minus_one
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use ff::PrimeField;
+
+    fn fr(n: &str) -> Fr {
+        Fr::from_str(n).unwrap()
+    }
+
+    #[test]
+    fn test_composer() {
+        let mut composer = Composer::<Bls12>::new(true);
+        let x = composer.alloc_input(|| Ok(fr("3"))).unwrap();
+        let y = composer.alloc(|| Ok(fr("4"))).unwrap();
+        let xy = composer.mul(x, y);
+        let sum = composer.add(xy, y);
+        let out = composer.add_constant(sum, fr("5"));
+        composer.assert_constant(out, fr("21"));
+
+        assert_eq!(composer.value(out), Some(fr("21")));
+        assert_eq!(composer.num_inputs(), 1);
+        assert_eq!(composer.num_gates(), 6);
+
+        let rows = composer.rows();
+        assert_eq!(rows[0].a, x);
+        assert_eq!(rows[1].a, composer.zero());
+        for row in &rows {
+            let values = composer.values();
+            let (a, b, c) = (values[row.a.0], values[row.b.0], values[row.c.0]);
+            assert!(row.evaluate(&a, &b, &c).is_zero());
+        }
+
+        // Keys are created without calling the value closures.
+        let mut composer = Composer::<Bls12>::new(false);
+        let x = composer
+            .alloc(|| Err(SynthesisError::AssignmentMissing))
+            .unwrap();
+        let y = composer.add(x, x);
+        assert_eq!(composer.value(y), None);
+    }
+}
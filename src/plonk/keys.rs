@@ -0,0 +1,195 @@
+use ff::{Field, PrimeField, PrimeFieldRepr};
+
+use super::composer::{Circuit, Composer};
+use super::kzg::Srs;
+use super::poly;
+use crate::bls::Engine;
+use crate::multicore::Worker;
+use crate::transcript::{Blake2bTranscript, Transcript};
+use crate::SynthesisError;
+
+/// The commitments to the selectors and permutation of a circuit, with the
+/// part of the SRS the verifier needs.
+pub struct VerifyingKey<E: Engine> {
+    /// The number of rows of the circuit, a power of two.
+    pub n: usize,
+    pub num_inputs: usize,
+    pub q_l: E::G1Affine,
+    pub q_r: E::G1Affine,
+    pub q_o: E::G1Affine,
+    pub q_m: E::G1Affine,
+    pub q_c: E::G1Affine,
+    /// The permutation polynomials of the `a`, `b` and `c` wires.
+    pub sigma: [E::G1Affine; 3],
+    pub g2: E::G2Affine,
+    pub tau_g2: E::G2Affine,
+}
+
+impl<E: Engine> Clone for VerifyingKey<E> {
+    fn clone(&self) -> Self {
+        VerifyingKey {
+            n: self.n,
+            num_inputs: self.num_inputs,
+            q_l: self.q_l,
+            q_r: self.q_r,
+            q_o: self.q_o,
+            q_m: self.q_m,
+            q_c: self.q_c,
+            sigma: self.sigma,
+            g2: self.g2,
+            tau_g2: self.tau_g2,
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for VerifyingKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n
+            && self.num_inputs == other.num_inputs
+            && self.q_l == other.q_l
+            && self.q_r == other.q_r
+            && self.q_o == other.q_o
+            && self.q_m == other.q_m
+            && self.q_c == other.q_c
+            && self.sigma == other.sigma
+            && self.g2 == other.g2
+            && self.tau_g2 == other.tau_g2
+    }
+}
+
+impl<E: Engine> VerifyingKey<E> {
+    // The commitments in the order of `ProvingKey::selectors`.
+    pub(crate) fn selectors(&self) -> [E::G1Affine; 5] {
+        [self.q_l, self.q_r, self.q_o, self.q_m, self.q_c]
+    }
+
+    // A transcript bound to the key and the public inputs.
+    pub(crate) fn transcript(&self, inputs: &[E::Fr]) -> Blake2bTranscript {
+        let mut transcript = Blake2bTranscript::new(b"crusty3_zk plonk");
+        transcript.append_message(b"n", &(self.n as u64).to_le_bytes());
+        for commitment in self.selectors().iter().chain(&self.sigma) {
+            transcript.append_point(b"key", commitment);
+        }
+
+        let mut bytes = vec![];
+        for input in inputs {
+            input
+                .into_repr()
+                .write_le(&mut bytes)
+                .expect("writing to a vector succeeds");
+        }
+        transcript.append_message(b"inputs", &bytes);
+
+        transcript
+    }
+}
+
+/// The selector and permutation polynomials of a circuit, and the SRS to
+/// commit with.
+pub struct ProvingKey<E: Engine> {
+    pub vk: VerifyingKey<E>,
+    pub(crate) srs: Srs<E>,
+    // `q_l`, `q_r`, `q_o`, `q_m` and `q_c`.
+    pub(crate) selectors: [Vec<E::Fr>; 5],
+    pub(crate) sigma: [Vec<E::Fr>; 3],
+    // The values of `sigma` on the domain.
+    pub(crate) sigma_values: [Vec<E::Fr>; 3],
+}
+
+/// Creates the keys of a circuit from a universal SRS. The circuit is
+/// synthesized without calling the value closures.
+///
+/// The SRS needs `3 n + 6` powers for a circuit of `n` rows, the number of
+/// gates rounded up to a power of two and at least 8.
+pub fn setup<E, C>(srs: &Srs<E>, circuit: C) -> Result<ProvingKey<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+{
+    let mut composer = Composer::new(false);
+    circuit.synthesize(&mut composer)?;
+    let rows = composer.rows();
+
+    let n = rows.len().max(8).next_power_of_two();
+    if srs.size() < 3 * n + 6 {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+    let worker = Worker::new();
+
+    let mut selectors = [vec![], vec![], vec![], vec![], vec![]];
+    for (i, selector) in selectors.iter_mut().enumerate() {
+        let mut values = rows
+            .iter()
+            .map(|row| [row.q_l, row.q_r, row.q_o, row.q_m, row.q_c][i])
+            .collect::<Vec<_>>();
+        values.resize(n, E::Fr::zero());
+        *selector = poly::interpolate::<E>(&values, &worker)?;
+    }
+
+    // Wire `j` of row `i` is labeled `k_j omega^i`, and the permutation maps
+    // every wire to the next one of the same variable.
+    let mut wires = vec![vec![]; composer.values().len()];
+    for j in 0..3 {
+        for i in 0..n {
+            let var = rows
+                .get(i)
+                .map_or(composer.zero(), |row| [row.a, row.b, row.c][j]);
+            wires[var.0].push((j, i));
+        }
+    }
+    let omegas = poly::powers::<E>(&poly::omega::<E>(n.trailing_zeros()), n);
+    let shifts = shifts::<E>();
+    let mut sigma_values = [
+        vec![E::Fr::zero(); n],
+        vec![E::Fr::zero(); n],
+        vec![E::Fr::zero(); n],
+    ];
+    for cycle in &wires {
+        for (k, &(j, i)) in cycle.iter().enumerate() {
+            let (next_j, next_i) = cycle[(k + 1) % cycle.len()];
+            let mut label = omegas[next_i];
+            label.mul_assign(&shifts[next_j]);
+            sigma_values[j][i] = label;
+        }
+    }
+
+    let mut sigma = [vec![], vec![], vec![]];
+    for (sigma, values) in sigma.iter_mut().zip(&sigma_values) {
+        *sigma = poly::interpolate::<E>(values, &worker)?;
+    }
+
+    let vk = VerifyingKey {
+        n,
+        num_inputs: composer.num_inputs(),
+        q_l: srs.commit(&selectors[0])?,
+        q_r: srs.commit(&selectors[1])?,
+        q_o: srs.commit(&selectors[2])?,
+        q_m: srs.commit(&selectors[3])?,
+        q_c: srs.commit(&selectors[4])?,
+        sigma: [
+            srs.commit(&sigma[0])?,
+            srs.commit(&sigma[1])?,
+            srs.commit(&sigma[2])?,
+        ],
+        g2: srs.g2,
+        tau_g2: srs.tau_g2,
+    };
+
+    Ok(ProvingKey {
+        vk,
+        srs: srs.clone(),
+        selectors,
+        sigma,
+        sigma_values,
+    })
+}
+
+/// `1`, `k_1` and `k_2`, the shifts of the labels of the `a`, `b` and `c`
+/// wires. They are the multiplicative generator and its square, whose
+/// cosets of the domains are distinct.
+pub(crate) fn shifts<E: Engine>() -> [E::Fr; 3] {
+    let k1 = E::Fr::multiplicative_generator();
+    let mut k2 = k1;
+    k2.square();
+    [E::Fr::one(), k1, k2]
+}
@@ -0,0 +1,201 @@
+//! KZG commitments to polynomials over a universal SRS.
+
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective, EncodedPoint};
+use rand_core::RngCore;
+use rayon::prelude::*;
+
+use super::poly;
+use crate::bls::Engine;
+use crate::groth16::TauPowers;
+use crate::multicore::Worker;
+use crate::multiexp::{multiexp, FullDensity};
+use crate::SynthesisError;
+
+/// A structured reference string, the powers of a secret `tau`. It does not
+/// depend on the circuit: an SRS of `n` powers serves every circuit whose
+/// polynomials have fewer than `n` coefficients.
+pub struct Srs<E: Engine> {
+    /// `tau^i` in G1.
+    pub g1: Arc<Vec<E::G1Affine>>,
+    /// The generator of G2.
+    pub g2: E::G2Affine,
+    /// `tau` in G2.
+    pub tau_g2: E::G2Affine,
+}
+
+impl<E: Engine> Clone for Srs<E> {
+    fn clone(&self) -> Self {
+        Srs {
+            g1: self.g1.clone(),
+            g2: self.g2,
+            tau_g2: self.tau_g2,
+        }
+    }
+}
+
+impl<E: Engine> Srs<E> {
+    /// Samples an SRS of `size` powers of a secret drawn from `rng`. Whoever
+    /// knows the secret can forge proofs, so this is only fit for tests.
+    pub fn setup_fake<R: RngCore>(size: usize, rng: &mut R) -> Self {
+        let tau = E::Fr::random(rng);
+        let g1 = E::G1Affine::one();
+        let g1 = poly::powers::<E>(&tau, size)
+            .into_par_iter()
+            .map(|power| g1.mul(power.into_repr()).into_affine())
+            .collect();
+
+        Srs {
+            g1: Arc::new(g1),
+            g2: E::G2Affine::one(),
+            tau_g2: E::G2Affine::one().mul(tau.into_repr()).into_affine(),
+        }
+    }
+
+    /// Takes the SRS from the powers of tau of a phase 1 ceremony, which
+    /// has `2 * powers.size() - 1` powers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `powers` has fewer than two powers in G2.
+    pub fn from_tau(powers: &TauPowers<E>) -> Self {
+        assert!(powers.tau_g2.len() >= 2, "too few powers of tau");
+        Srs {
+            g1: Arc::new(powers.tau_g1.clone()),
+            g2: powers.tau_g2[0],
+            tau_g2: powers.tau_g2[1],
+        }
+    }
+
+    /// The number of powers, one more than the largest degree of the
+    /// polynomials it commits to.
+    pub fn size(&self) -> usize {
+        self.g1.len()
+    }
+
+    /// Commits to the polynomial with the coefficients `coeffs`.
+    pub fn commit(&self, coeffs: &[E::Fr]) -> Result<E::G1Affine, SynthesisError> {
+        if coeffs.len() > self.g1.len() {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+        if coeffs.is_empty() {
+            return Ok(E::G1Affine::zero());
+        }
+
+        let exponents = coeffs.iter().map(|c| c.into_repr()).collect();
+        let commitment = multiexp(
+            &Worker::new(),
+            (self.g1.clone(), 0),
+            FullDensity,
+            Arc::new(exponents),
+            &mut None,
+        )
+        .wait()?;
+
+        Ok(commitment.into_affine())
+    }
+
+    /// Commits to the quotient of `p(X) - p(z)` by `X - z`, which proves
+    /// the value of `p` at `z`.
+    pub fn open(&self, coeffs: &[E::Fr], z: &E::Fr) -> Result<E::G1Affine, SynthesisError> {
+        self.commit(&poly::divide_by_linear::<E>(coeffs, z))
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<BigEndian>(self.g1.len() as u32)?;
+        for g in &self.g1[..] {
+            writer.write_all(g.into_uncompressed().as_ref())?;
+        }
+        writer.write_all(self.g2.into_uncompressed().as_ref())?;
+        writer.write_all(self.tau_g2.into_uncompressed().as_ref())?;
+
+        Ok(())
+    }
+
+    /// Reads an SRS written by `write`. `checked` enables the curve and
+    /// subgroup checks of the points.
+    pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<Self> {
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let mut g1 = Vec::with_capacity(len);
+        for _ in 0..len {
+            g1.push(read_point::<E::G1Affine, _>(&mut reader, checked)?);
+        }
+        let g2 = read_point(&mut reader, checked)?;
+        let tau_g2 = read_point(&mut reader, checked)?;
+
+        Ok(Srs {
+            g1: Arc::new(g1),
+            g2,
+            tau_g2,
+        })
+    }
+}
+
+fn read_point<G: CurveAffine, R: Read>(reader: &mut R, checked: bool) -> io::Result<G> {
+    let mut repr = G::Uncompressed::empty();
+    reader.read_exact(repr.as_mut())?;
+
+    let point = if checked {
+        repr.into_affine()
+    } else {
+        repr.into_affine_unchecked()
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    if point.is_zero() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "point at infinity",
+        ));
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr, G1Affine};
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_commit_and_open() {
+        let mut rng = test_rng();
+        let srs = Srs::<Bls12>::setup_fake(16, &mut rng);
+
+        let coeffs = (0..10).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let commitment = srs.commit(&coeffs).unwrap();
+        let mut expected = <G1Affine as CurveAffine>::Projective::zero();
+        for (coeff, base) in coeffs.iter().zip(srs.g1.iter()) {
+            expected.add_assign(&base.mul(coeff.into_repr()));
+        }
+        assert_eq!(commitment, expected.into_affine());
+
+        // e(W, tau - z) = e(C - y, 1)
+        let z = Fr::random(&mut rng);
+        let y = poly::evaluate::<Bls12>(&coeffs, &z);
+        let proof = srs.open(&coeffs, &z).unwrap();
+        let mut tau_minus_z = srs.tau_g2.into_projective();
+        tau_minus_z.sub_assign(&srs.g2.mul(z.into_repr()));
+        let mut c_minus_y = commitment.into_projective();
+        c_minus_y.sub_assign(&G1Affine::one().mul(y.into_repr()));
+        assert_eq!(
+            Bls12::pairing(proof, tau_minus_z.into_affine()),
+            Bls12::pairing(c_minus_y.into_affine(), srs.g2)
+        );
+
+        let too_long = vec![Fr::one(); 17];
+        assert!(srs.commit(&too_long).is_err());
+
+        let mut bytes = vec![];
+        srs.write(&mut bytes).unwrap();
+        let read = Srs::<Bls12>::read(&bytes[..], true).unwrap();
+        assert_eq!(read.g1, srs.g1);
+        assert_eq!(read.tau_g2, srs.tau_g2);
+        assert!(Srs::<Bls12>::read(&bytes[..bytes.len() - 1], true).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! The [PLONK] proving system, with KZG commitments over a universal SRS.
+//!
+//! Unlike Groth16, the structured reference string does not depend on the
+//! circuit: `Srs::from_tau` takes it from the output of a powers of tau
+//! ceremony, and `setup` derives the keys of any circuit small enough for
+//! it. `Srs::setup_fake` samples an insecure SRS for tests.
+//!
+//! Circuits are written against a `Composer` of standard gates
+//! `q_l a + q_r b + q_o c + q_m a b + q_c = 0`, with copy constraints
+//! between wires holding the same `Var`. The FFTs run on `EvaluationDomain`
+//! and the commitments on the multiexps of the crate.
+//!
+//! `R1cs` adapts circuits of the R1CS front-end, so that the circuits and
+//! gadgets written for Groth16 are proven with a universal setup too.
+//!
+//! The prover opens every polynomial at the challenge `zeta`, instead of
+//! the linearization of the paper, which makes proofs larger by a few
+//! field elements in exchange for a simpler verifier.
+//!
+//! [PLONK]: https://eprint.iacr.org/2019/953
+
+mod composer;
+mod keys;
+pub mod kzg;
+mod poly;
+mod prover;
+mod r1cs;
+mod verifier;
+
+pub use self::composer::{Circuit, Composer, Gate, Var};
+pub use self::keys::{setup, ProvingKey, VerifyingKey};
+pub use self::kzg::Srs;
+pub use self::prover::{create_proof, Evaluations, Proof};
+pub use self::r1cs::R1cs;
+pub use self::verifier::verify_proof;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_fixtures::test_rng;
+    use crate::SynthesisError;
+    use ff::{Field, PrimeField};
+
+    // Knowledge of `x` with `x^3 + x + 5 = out` for the public `out`.
+    struct Cubic {
+        x: Option<Fr>,
+        out: Option<Fr>,
+    }
+
+    impl Circuit<Bls12> for Cubic {
+        fn synthesize(self, composer: &mut Composer<Bls12>) -> Result<(), SynthesisError> {
+            let out = composer.alloc_input(|| self.out.ok_or(SynthesisError::AssignmentMissing))?;
+            let x = composer.alloc(|| self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            let x2 = composer.mul(x, x);
+            let x3 = composer.mul(x2, x);
+            let sum = composer.add(x3, x);
+            let sum = composer.add_constant(sum, Fr::from_str("5").unwrap());
+            composer.assert_equal(sum, out);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plonk() {
+        let mut rng = test_rng();
+        let srs = Srs::<Bls12>::setup_fake(64, &mut rng);
+        let pk = setup(&srs, Cubic { x: None, out: None }).unwrap();
+        assert_eq!(pk.vk.n, 8);
+        assert_eq!(pk.vk.num_inputs, 1);
+
+        let x = Fr::from_str("3").unwrap();
+        let out = Fr::from_str("35").unwrap();
+        let proof = create_proof(
+            &pk,
+            Cubic {
+                x: Some(x),
+                out: Some(out),
+            },
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_proof(&pk.vk, &proof, &[out]).unwrap());
+        assert!(!verify_proof(&pk.vk, &proof, &[Fr::one()]).unwrap());
+        assert!(verify_proof(&pk.vk, &proof, &[]).is_err());
+
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+        let read = Proof::<Bls12>::read(&bytes[..]).unwrap();
+        assert!(verify_proof(&pk.vk, &read, &[out]).unwrap());
+
+        let wrong = create_proof(
+            &pk,
+            Cubic {
+                x: Some(x),
+                out: Some(Fr::one()),
+            },
+            &mut rng,
+        );
+        match wrong.err() {
+            Some(SynthesisError::Unsatisfiable) => {}
+            _ => panic!("expected an unsatisfiable circuit"),
+        }
+
+        let small = Srs::<Bls12>::setup_fake(16, &mut rng);
+        assert!(setup(&small, Cubic { x: None, out: None }).is_err());
+    }
+}
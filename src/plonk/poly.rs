@@ -0,0 +1,159 @@
+//! Polynomials in coefficient form, on top of `EvaluationDomain`.
+
+use ff::{Field, PrimeField};
+use rayon::prelude::*;
+
+use crate::bls::Engine;
+use crate::domain::{EvaluationDomain, Scalar};
+use crate::multicore::Worker;
+use crate::SynthesisError;
+
+/// The primitive `2^log_n`-th root of unity of the domains.
+pub fn omega<E: Engine>(log_n: u32) -> E::Fr {
+    let mut omega = E::Fr::root_of_unity();
+    for _ in log_n..E::Fr::S {
+        omega.square();
+    }
+    omega
+}
+
+/// `x^0, ..., x^(n - 1)`.
+pub fn powers<E: Engine>(x: &E::Fr, n: usize) -> Vec<E::Fr> {
+    let mut powers = Vec::with_capacity(n);
+    let mut power = E::Fr::one();
+    for _ in 0..n {
+        powers.push(power);
+        power.mul_assign(x);
+    }
+    powers
+}
+
+pub fn evaluate<E: Engine>(coeffs: &[E::Fr], x: &E::Fr) -> E::Fr {
+    let mut acc = E::Fr::zero();
+    for coeff in coeffs.iter().rev() {
+        acc.mul_assign(x);
+        acc.add_assign(coeff);
+    }
+    acc
+}
+
+/// The quotient of `p(X) - p(z)` by `X - z`.
+pub fn divide_by_linear<E: Engine>(coeffs: &[E::Fr], z: &E::Fr) -> Vec<E::Fr> {
+    let mut quotient = vec![E::Fr::zero(); coeffs.len().saturating_sub(1)];
+    let mut acc = E::Fr::zero();
+    for i in (1..coeffs.len()).rev() {
+        acc.mul_assign(z);
+        acc.add_assign(&coeffs[i]);
+        quotient[i - 1] = acc;
+    }
+    quotient
+}
+
+/// The coefficients of the polynomial taking `values` on the domain of
+/// their size, a power of two.
+pub fn interpolate<E: Engine>(
+    values: &[E::Fr],
+    worker: &Worker,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    let mut domain = EvaluationDomain::<E, _>::from_coeffs(to_scalars::<E>(values))?;
+    domain.ifft(worker, &mut None)?;
+    Ok(from_scalars(domain))
+}
+
+/// The values of a polynomial on the coset of the domain of size `size`
+/// shifted by the multiplicative generator.
+pub fn coset_evaluations<E: Engine>(
+    coeffs: &[E::Fr],
+    size: usize,
+    worker: &Worker,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    assert!(coeffs.len() <= size);
+    let mut scalars = to_scalars::<E>(coeffs);
+    scalars.resize(size, Scalar(E::Fr::zero()));
+
+    let mut domain = EvaluationDomain::<E, _>::from_coeffs(scalars)?;
+    domain.coset_fft(worker, &mut None)?;
+    Ok(from_scalars(domain))
+}
+
+/// The coefficients of the polynomial taking `values` on the coset of
+/// `coset_evaluations`.
+pub fn coset_interpolate<E: Engine>(
+    values: &[E::Fr],
+    worker: &Worker,
+) -> Result<Vec<E::Fr>, SynthesisError> {
+    let mut domain = EvaluationDomain::<E, _>::from_coeffs(to_scalars::<E>(values))?;
+    domain.icoset_fft(worker, &mut None)?;
+    Ok(from_scalars(domain))
+}
+
+/// `sum_i scalars_i polys_i`.
+pub fn linear_combination<E: Engine>(polys: &[&[E::Fr]], scalars: &[E::Fr]) -> Vec<E::Fr> {
+    let len = polys.iter().map(|p| p.len()).max().unwrap_or(0);
+    (0..len)
+        .into_par_iter()
+        .map(|i| {
+            let mut acc = E::Fr::zero();
+            for (poly, scalar) in polys.iter().zip(scalars) {
+                if let Some(coeff) = poly.get(i) {
+                    let mut term = *coeff;
+                    term.mul_assign(scalar);
+                    acc.add_assign(&term);
+                }
+            }
+            acc
+        })
+        .collect()
+}
+
+fn to_scalars<E: Engine>(values: &[E::Fr]) -> Vec<Scalar<E>> {
+    values.iter().map(|v| Scalar(*v)).collect()
+}
+
+fn from_scalars<E: Engine>(domain: EvaluationDomain<E, Scalar<E>>) -> Vec<E::Fr> {
+    domain.into_coeffs().into_iter().map(|s| s.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_fixtures::test_rng;
+
+    #[test]
+    fn test_polynomials() {
+        let mut rng = test_rng();
+        let worker = Worker::new();
+
+        let values = (0..8).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+        let coeffs = interpolate::<Bls12>(&values, &worker).unwrap();
+        let omega = omega::<Bls12>(3);
+        for (i, value) in values.iter().enumerate() {
+            let x = omega.pow(&[i as u64]);
+            assert_eq!(evaluate::<Bls12>(&coeffs, &x), *value);
+        }
+
+        let coset = coset_evaluations::<Bls12>(&coeffs, 32, &worker).unwrap();
+        let mut x = Fr::multiplicative_generator();
+        x.mul_assign(&omega::<Bls12>(5).pow(&[3]));
+        assert_eq!(coset[3], evaluate::<Bls12>(&coeffs, &x));
+
+        let mut back = coset_interpolate::<Bls12>(&coset, &worker).unwrap();
+        assert!(back[8..].iter().all(|c| c.is_zero()));
+        back.truncate(8);
+        assert_eq!(back, coeffs);
+
+        let z = Fr::random(&mut rng);
+        let quotient = divide_by_linear::<Bls12>(&coeffs, &z);
+        let y = Fr::random(&mut rng);
+        // q(y) (y - z) = p(y) - p(z)
+        let mut lhs = evaluate::<Bls12>(&quotient, &y);
+        let mut diff = y;
+        diff.sub_assign(&z);
+        lhs.mul_assign(&diff);
+        let mut rhs = evaluate::<Bls12>(&coeffs, &y);
+        rhs.sub_assign(&evaluate::<Bls12>(&coeffs, &z));
+        assert_eq!(lhs, rhs);
+    }
+}
@@ -0,0 +1,442 @@
+use std::io::{self, Read, Write};
+
+use ff::{Field, PrimeField, PrimeFieldRepr};
+use groupy::{CurveAffine, EncodedPoint};
+use rand_core::RngCore;
+use rayon::prelude::*;
+
+use super::composer::{Circuit, Composer};
+use super::keys::{shifts, ProvingKey, VerifyingKey};
+use super::poly;
+use crate::bls::Engine;
+use crate::multicore::Worker;
+use crate::transcript::Transcript;
+use crate::SynthesisError;
+
+/// The values of the polynomials of a proof at the challenge `zeta`, and of
+/// the permutation polynomial at `zeta * omega`.
+pub struct Evaluations<E: Engine> {
+    pub a: E::Fr,
+    pub b: E::Fr,
+    pub c: E::Fr,
+    /// `q_l`, `q_r`, `q_o`, `q_m` and `q_c`.
+    pub selectors: [E::Fr; 5],
+    pub sigma: [E::Fr; 3],
+    pub z: E::Fr,
+    pub t: E::Fr,
+    pub z_omega: E::Fr,
+}
+
+impl<E: Engine> Clone for Evaluations<E> {
+    fn clone(&self) -> Self {
+        Evaluations {
+            a: self.a,
+            b: self.b,
+            c: self.c,
+            selectors: self.selectors,
+            sigma: self.sigma,
+            z: self.z,
+            t: self.t,
+            z_omega: self.z_omega,
+        }
+    }
+}
+
+impl<E: Engine> Evaluations<E> {
+    // The values at `zeta`, in the order of `Proof::commitments`.
+    pub(crate) fn at_zeta(&self) -> Vec<E::Fr> {
+        let mut values = vec![self.a, self.b, self.c];
+        values.extend_from_slice(&self.selectors);
+        values.extend_from_slice(&self.sigma);
+        values.push(self.z);
+        values.push(self.t);
+        values
+    }
+}
+
+/// A PLONK proof.
+///
+/// All polynomials are opened at `zeta` and the identities are checked on
+/// their values, rather than on the linearization polynomial of the paper.
+/// This makes proofs 13 scalars larger but keeps the verifier simple.
+pub struct Proof<E: Engine> {
+    /// Commitments to the wire polynomials.
+    pub a: E::G1Affine,
+    pub b: E::G1Affine,
+    pub c: E::G1Affine,
+    /// Commitment to the permutation polynomial.
+    pub z: E::G1Affine,
+    /// Commitment to the quotient polynomial.
+    pub t: E::G1Affine,
+    pub evaluations: Evaluations<E>,
+    /// Opening of all polynomials at `zeta`.
+    pub w_zeta: E::G1Affine,
+    /// Opening of `z` at `zeta * omega`.
+    pub w_zeta_omega: E::G1Affine,
+}
+
+impl<E: Engine> Clone for Proof<E> {
+    fn clone(&self) -> Self {
+        Proof {
+            a: self.a,
+            b: self.b,
+            c: self.c,
+            z: self.z,
+            t: self.t,
+            evaluations: self.evaluations.clone(),
+            w_zeta: self.w_zeta,
+            w_zeta_omega: self.w_zeta_omega,
+        }
+    }
+}
+
+impl<E: Engine> Proof<E> {
+    // The commitments opened at `zeta`, in the order of
+    // `Evaluations::at_zeta`.
+    pub(crate) fn commitments(&self, vk: &VerifyingKey<E>) -> Vec<E::G1Affine> {
+        let mut commitments = vec![self.a, self.b, self.c];
+        commitments.extend_from_slice(&vk.selectors());
+        commitments.extend_from_slice(&vk.sigma);
+        commitments.push(self.z);
+        commitments.push(self.t);
+        commitments
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for point in &[self.a, self.b, self.c, self.z, self.t] {
+            writer.write_all(point.into_compressed().as_ref())?;
+        }
+        for value in self.evaluations.at_zeta() {
+            value.into_repr().write_be(&mut writer)?;
+        }
+        self.evaluations.z_omega.into_repr().write_be(&mut writer)?;
+        writer.write_all(self.w_zeta.into_compressed().as_ref())?;
+        writer.write_all(self.w_zeta_omega.into_compressed().as_ref())?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut points = [E::G1Affine::zero(); 5];
+        for point in points.iter_mut() {
+            *point = read_point::<E, _>(&mut reader)?;
+        }
+        let mut values = [E::Fr::zero(); 14];
+        for value in values.iter_mut() {
+            *value = read_scalar::<E, _>(&mut reader)?;
+        }
+        let w_zeta = read_point::<E, _>(&mut reader)?;
+        let w_zeta_omega = read_point::<E, _>(&mut reader)?;
+
+        Ok(Proof {
+            a: points[0],
+            b: points[1],
+            c: points[2],
+            z: points[3],
+            t: points[4],
+            evaluations: Evaluations {
+                a: values[0],
+                b: values[1],
+                c: values[2],
+                selectors: [values[3], values[4], values[5], values[6], values[7]],
+                sigma: [values[8], values[9], values[10]],
+                z: values[11],
+                t: values[12],
+                z_omega: values[13],
+            },
+            w_zeta,
+            w_zeta_omega,
+        })
+    }
+}
+
+fn read_point<E: Engine, R: Read>(reader: &mut R) -> io::Result<E::G1Affine> {
+    let mut repr = <E::G1Affine as CurveAffine>::Compressed::empty();
+    reader.read_exact(repr.as_mut())?;
+    repr.into_affine()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn read_scalar<E: Engine, R: Read>(reader: &mut R) -> io::Result<E::Fr> {
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    repr.read_be(reader)?;
+    E::Fr::from_repr(repr).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Creates a proof for `circuit` with the proving key `pk`, blinded with
+/// randomness from `rng`. Fails with `Unsatisfiable` if a gate does not
+/// hold for the values of the circuit.
+pub fn create_proof<E, C, R>(
+    pk: &ProvingKey<E>,
+    circuit: C,
+    rng: &mut R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    C: Circuit<E>,
+    R: RngCore,
+{
+    let vk = &pk.vk;
+    let n = vk.n;
+
+    let mut composer = Composer::new(true);
+    circuit.synthesize(&mut composer)?;
+    let rows = composer.rows();
+    if rows.len() > n || composer.num_inputs() != vk.num_inputs {
+        return Err(SynthesisError::IncompatibleLengthVector(format!(
+            "circuit has {} rows and {} inputs, the key {} and {}",
+            rows.len(),
+            composer.num_inputs(),
+            n,
+            vk.num_inputs
+        )));
+    }
+
+    let values = composer.values();
+    let inputs = composer.input_values();
+    for (i, row) in rows.iter().enumerate() {
+        let mut lhs = row.evaluate(&values[row.a.0], &values[row.b.0], &values[row.c.0]);
+        if let Some(input) = inputs.get(i) {
+            lhs.sub_assign(input);
+        }
+        if !lhs.is_zero() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+    }
+
+    let worker = Worker::new();
+    let omega = poly::omega::<E>(n.trailing_zeros());
+    let mut transcript = vk.transcript(&inputs);
+
+    // Round 1: the wire polynomials, blinded with two random coefficients.
+    let wire_values = (0..3)
+        .map(|j| {
+            (0..n)
+                .map(|i| {
+                    rows.get(i)
+                        .map_or(E::Fr::zero(), |row| values[[row.a, row.b, row.c][j].0])
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut wires = vec![];
+    for values in &wire_values {
+        let mut wire = poly::interpolate::<E>(values, &worker)?;
+        blind::<E, _>(&mut wire, 2, rng);
+        wires.push(wire);
+    }
+    let a = pk.srs.commit(&wires[0])?;
+    let b = pk.srs.commit(&wires[1])?;
+    let c = pk.srs.commit(&wires[2])?;
+    for wire in &[a, b, c] {
+        transcript.append_point(b"wire", wire);
+    }
+    let beta = transcript.challenge_scalar::<E::Fr>(b"beta");
+    let gamma = transcript.challenge_scalar::<E::Fr>(b"gamma");
+
+    // Round 2: the permutation polynomial, with
+    // `z(omega^(i + 1)) = z(omega^i) prod_j (w_j + beta k_j omega^i + gamma)
+    // / (w_j + beta sigma_j(omega^i) + gamma)`.
+    let shifts = shifts::<E>();
+    let omegas = poly::powers::<E>(&omega, n);
+    let ratios = (0..n)
+        .into_par_iter()
+        .map(|i| {
+            let mut num = E::Fr::one();
+            let mut den = E::Fr::one();
+            for ((w, shift), sigma) in wire_values.iter().zip(&shifts).zip(&pk.sigma_values) {
+                let mut id = omegas[i];
+                id.mul_assign(shift);
+                num.mul_assign(&permuted::<E>(&w[i], &id, &beta, &gamma));
+                den.mul_assign(&permuted::<E>(&w[i], &sigma[i], &beta, &gamma));
+            }
+            den.inverse().map(|den| {
+                num.mul_assign(&den);
+                num
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(SynthesisError::DivisionByZero)?;
+    let mut z_values = Vec::with_capacity(n);
+    let mut acc = E::Fr::one();
+    for ratio in &ratios {
+        z_values.push(acc);
+        acc.mul_assign(ratio);
+    }
+    let mut z_poly = poly::interpolate::<E>(&z_values, &worker)?;
+    blind::<E, _>(&mut z_poly, 3, rng);
+    let z = pk.srs.commit(&z_poly)?;
+    transcript.append_point(b"z", &z);
+    let alpha = transcript.challenge_scalar::<E::Fr>(b"alpha");
+
+    // Round 3: the quotient polynomial, computed on a coset of the domain of
+    // size 4n, as its degree is at most 3n + 5.
+    let size = 4 * n;
+    let coset = |coeffs: &[E::Fr]| poly::coset_evaluations::<E>(coeffs, size, &worker);
+    let wire_evals = [coset(&wires[0])?, coset(&wires[1])?, coset(&wires[2])?];
+    let z_evals = coset(&z_poly)?;
+    let mut selector_evals = vec![];
+    for selector in &pk.selectors {
+        selector_evals.push(coset(selector)?);
+    }
+    let mut sigma_evals = vec![];
+    for sigma in &pk.sigma {
+        sigma_evals.push(coset(sigma)?);
+    }
+
+    let mut pi_values = vec![E::Fr::zero(); n];
+    for (value, input) in pi_values.iter_mut().zip(&inputs) {
+        *value = *input;
+        value.negate();
+    }
+    let pi_evals = coset(&poly::interpolate::<E>(&pi_values, &worker)?)?;
+    let mut l1_values = vec![E::Fr::zero(); n];
+    l1_values[0] = E::Fr::one();
+    let l1_evals = coset(&poly::interpolate::<E>(&l1_values, &worker)?)?;
+
+    // The coset is `g omega_4n^i`, on which `Z_H = X^n - 1` takes the four
+    // values `g^n omega_4n^(n i) - 1`.
+    let generator = E::Fr::multiplicative_generator();
+    let omega_4n = poly::omega::<E>(size.trailing_zeros());
+    let xs = poly::powers::<E>(&omega_4n, size)
+        .into_iter()
+        .map(|mut x| {
+            x.mul_assign(&generator);
+            x
+        })
+        .collect::<Vec<_>>();
+    let zh_inv = (0..4)
+        .map(|k| {
+            let mut zh = omega_4n.pow(&[(n * k) as u64]);
+            zh.mul_assign(&generator.pow(&[n as u64]));
+            zh.sub_assign(&E::Fr::one());
+            zh.inverse().expect("the coset is disjoint from the domain")
+        })
+        .collect::<Vec<_>>();
+
+    let mut alpha_squared = alpha;
+    alpha_squared.square();
+    let t_values = (0..size)
+        .into_par_iter()
+        .map(|i| {
+            let w = [wire_evals[0][i], wire_evals[1][i], wire_evals[2][i]];
+            let q = [
+                selector_evals[0][i],
+                selector_evals[1][i],
+                selector_evals[2][i],
+                selector_evals[3][i],
+                selector_evals[4][i],
+            ];
+            let mut acc = gate::<E>(&w, &q, &pi_evals[i]);
+
+            let mut lhs = z_evals[i];
+            let mut rhs = z_evals[(i + 4) % size];
+            for ((w, shift), sigma) in w.iter().zip(&shifts).zip(&sigma_evals) {
+                let mut id = xs[i];
+                id.mul_assign(shift);
+                lhs.mul_assign(&permuted::<E>(w, &id, &beta, &gamma));
+                rhs.mul_assign(&permuted::<E>(w, &sigma[i], &beta, &gamma));
+            }
+            lhs.sub_assign(&rhs);
+            lhs.mul_assign(&alpha);
+            acc.add_assign(&lhs);
+
+            let mut first = z_evals[i];
+            first.sub_assign(&E::Fr::one());
+            first.mul_assign(&l1_evals[i]);
+            first.mul_assign(&alpha_squared);
+            acc.add_assign(&first);
+
+            acc.mul_assign(&zh_inv[i % 4]);
+            acc
+        })
+        .collect::<Vec<_>>();
+    let mut t_poly = poly::coset_interpolate::<E>(&t_values, &worker)?;
+    t_poly.truncate(3 * n + 6);
+    let t = pk.srs.commit(&t_poly)?;
+    transcript.append_point(b"t", &t);
+    let zeta = transcript.challenge_scalar::<E::Fr>(b"zeta");
+
+    // Round 4: the evaluations.
+    let mut zeta_omega = zeta;
+    zeta_omega.mul_assign(&omega);
+    let mut polys = vec![&wires[0][..], &wires[1][..], &wires[2][..]];
+    polys.extend(pk.selectors.iter().map(|p| &p[..]));
+    polys.extend(pk.sigma.iter().map(|p| &p[..]));
+    polys.push(&z_poly);
+    polys.push(&t_poly);
+    let at_zeta = polys
+        .par_iter()
+        .map(|p| poly::evaluate::<E>(p, &zeta))
+        .collect::<Vec<_>>();
+    let evaluations = Evaluations {
+        a: at_zeta[0],
+        b: at_zeta[1],
+        c: at_zeta[2],
+        selectors: [at_zeta[3], at_zeta[4], at_zeta[5], at_zeta[6], at_zeta[7]],
+        sigma: [at_zeta[8], at_zeta[9], at_zeta[10]],
+        z: at_zeta[11],
+        t: at_zeta[12],
+        z_omega: poly::evaluate::<E>(&z_poly, &zeta_omega),
+    };
+    for value in at_zeta.iter().chain(Some(&evaluations.z_omega)) {
+        transcript.append_scalar(b"evaluation", value);
+    }
+    let v = transcript.challenge_scalar::<E::Fr>(b"v");
+
+    // Round 5: the openings, of a random combination of all polynomials at
+    // `zeta` and of `z` at `zeta * omega`.
+    let combined = poly::linear_combination::<E>(&polys, &poly::powers::<E>(&v, polys.len()));
+    let w_zeta = pk.srs.open(&combined, &zeta)?;
+    let w_zeta_omega = pk.srs.open(&z_poly, &zeta_omega)?;
+
+    Ok(Proof {
+        a,
+        b,
+        c,
+        z,
+        t,
+        evaluations,
+        w_zeta,
+        w_zeta_omega,
+    })
+}
+
+// Adds `(sum_k b_k X^k) Z_H(X)` for `count` random `b_k` to a polynomial of
+// `n` coefficients, which keeps its values on the domain.
+fn blind<E: Engine, R: RngCore>(coeffs: &mut Vec<E::Fr>, count: usize, rng: &mut R) {
+    let n = coeffs.len();
+    coeffs.resize(n + count, E::Fr::zero());
+    for k in 0..count {
+        let b = E::Fr::random(rng);
+        coeffs[n + k].add_assign(&b);
+        coeffs[k].sub_assign(&b);
+    }
+}
+
+// `w + beta label + gamma`
+pub(crate) fn permuted<E: Engine>(w: &E::Fr, label: &E::Fr, beta: &E::Fr, gamma: &E::Fr) -> E::Fr {
+    let mut acc = *label;
+    acc.mul_assign(beta);
+    acc.add_assign(w);
+    acc.add_assign(gamma);
+    acc
+}
+
+// `q_l a + q_r b + q_o c + q_m a b + q_c + pi` for the wires `w` and the
+// selectors `q`.
+pub(crate) fn gate<E: Engine>(w: &[E::Fr; 3], q: &[E::Fr; 5], pi: &E::Fr) -> E::Fr {
+    let mut acc = q[4];
+    acc.add_assign(pi);
+    for (q, w) in q.iter().zip(w) {
+        let mut term = *q;
+        term.mul_assign(w);
+        acc.add_assign(&term);
+    }
+    let mut term = q[3];
+    term.mul_assign(&w[0]);
+    term.mul_assign(&w[1]);
+    acc.add_assign(&term);
+
+    acc
+}
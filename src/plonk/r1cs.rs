@@ -0,0 +1,210 @@
+//! Circuits of the R1CS front-end of the crate, proven with PLONK.
+
+use ff::Field;
+
+use super::composer::{Circuit, Composer, Gate, Var};
+use crate::bls::Engine;
+use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+/// Adapts a `crate::Circuit` to a PLONK circuit, so that circuits and gadgets
+/// written against `ConstraintSystem` get the universal setup of `plonk`
+/// without being rewritten.
+///
+/// The public inputs are those of the circuit without the constant one, as
+/// for Groth16. Every linear combination costs a gate per term, except one
+/// of a single variable with coefficient one, which is free, and every
+/// constraint one more gate for the product.
+pub struct R1cs<C>(pub C);
+
+impl<E, C> Circuit<E> for R1cs<C>
+where
+    E: Engine,
+    C: crate::Circuit<E>,
+{
+    fn synthesize(self, composer: &mut Composer<E>) -> Result<(), SynthesisError> {
+        let one = composer.alloc(|| Ok(E::Fr::one()))?;
+        composer.assert_constant(one, E::Fr::one());
+
+        let mut cs = R1csComposer {
+            composer,
+            inputs: vec![one],
+            aux: vec![],
+        };
+        self.0.synthesize(&mut cs)
+    }
+}
+
+struct R1csComposer<'a, E: Engine> {
+    composer: &'a mut Composer<E>,
+    // The variables of the inputs, starting with the constant one.
+    inputs: Vec<Var>,
+    aux: Vec<Var>,
+}
+
+impl<'a, E: Engine> R1csComposer<'a, E> {
+    fn var(&self, var: &Variable) -> Var {
+        match var.get_unchecked() {
+            Index::Input(i) => self.inputs[i],
+            Index::Aux(i) => self.aux[i],
+        }
+    }
+
+    // A variable holding the value of `lc`. The terms are sorted by variable,
+    // as the order of a `LinearCombination` differs between syntheses and the
+    // gates must not.
+    fn lc(&mut self, lc: LinearCombination<E>) -> Var {
+        let mut terms = lc
+            .simplify()
+            .iter()
+            .map(|(var, coeff)| (self.var(var), *coeff))
+            .collect::<Vec<_>>();
+        terms.sort_by_key(|(var, _)| var.0);
+
+        if let [(var, coeff)] = terms[..] {
+            if coeff == E::Fr::one() {
+                return var;
+            }
+        }
+
+        let mut minus_one = E::Fr::one();
+        minus_one.negate();
+        let mut acc = self.composer.zero();
+        for (var, coeff) in terms {
+            let value = self.composer.value(acc).and_then(|mut acc| {
+                self.composer.value(var).map(|mut var| {
+                    var.mul_assign(&coeff);
+                    acc.add_assign(&var);
+                    acc
+                })
+            });
+            let sum = self.composer.derived(value);
+
+            let mut gate = Gate::new(acc, var, sum);
+            gate.q_l = E::Fr::one();
+            gate.q_r = coeff;
+            gate.q_o = minus_one;
+            self.composer.gate(gate);
+            acc = sum;
+        }
+
+        acc
+    }
+}
+
+impl<'a, E: Engine> ConstraintSystem<E> for R1csComposer<'a, E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux.push(self.composer.alloc(f)?);
+
+        Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.inputs.push(self.composer.alloc_input(f)?);
+
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+    {
+        let a = self.lc(a(LinearCombination::zero()));
+        let b = self.lc(b(LinearCombination::zero()));
+        let c = self.lc(c(LinearCombination::zero()));
+
+        let mut gate = Gate::new(a, b, c);
+        gate.q_m = E::Fr::one();
+        gate.q_o = E::Fr::one();
+        gate.q_o.negate();
+        self.composer.gate(gate);
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Namespaces are only used for annotations.
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::plonk::{create_proof, setup, verify_proof, Srs};
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::PrimeField;
+
+    // `(x + 1) x = y - 1` for the public `y`.
+    struct Quadratic {
+        x: Option<Fr>,
+        y: Option<Fr>,
+    }
+
+    impl crate::Circuit<Bls12> for Quadratic {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let y = cs.alloc_input(|| "y", || self.y.ok_or(SynthesisError::AssignmentMissing))?;
+            let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(
+                || "quadratic",
+                |lc| lc + x + CS::one(),
+                |lc| lc + x,
+                |lc| lc + y - CS::one(),
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_r1cs() {
+        let mut rng = test_rng();
+        let srs = Srs::<Bls12>::setup_fake(64, &mut rng);
+        let pk = setup(&srs, R1cs(Quadratic { x: None, y: None })).unwrap();
+
+        let y = Fr::from_str("13").unwrap();
+        let circuit = Quadratic {
+            x: Some(Fr::from_str("3").unwrap()),
+            y: Some(y),
+        };
+        let proof = create_proof(&pk, R1cs(circuit), &mut rng).unwrap();
+        assert!(verify_proof(&pk.vk, &proof, &[y]).unwrap());
+        assert!(!verify_proof(&pk.vk, &proof, &[Fr::one()]).unwrap());
+
+        let circuit = Quadratic {
+            x: Some(Fr::from_str("4").unwrap()),
+            y: Some(y),
+        };
+        match create_proof(&pk, R1cs(circuit), &mut rng).err() {
+            Some(SynthesisError::Unsatisfiable) => {}
+            _ => panic!("expected an unsatisfiable circuit"),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use ff::{Field, PrimeField};
+use groupy::{CurveAffine, CurveProjective};
+
+use super::keys::{shifts, VerifyingKey};
+use super::poly;
+use super::prover::{gate, permuted, Proof};
+use crate::bls::{Engine, PairingCurveAffine};
+use crate::transcript::Transcript;
+use crate::SynthesisError;
+
+/// Verifies `proof` for the public inputs `inputs`.
+///
+/// Checks the gate, permutation and first-row identities on the opened
+/// values at `zeta`, then both openings with a single pairing check.
+pub fn verify_proof<E: Engine>(
+    vk: &VerifyingKey<E>,
+    proof: &Proof<E>,
+    inputs: &[E::Fr],
+) -> Result<bool, SynthesisError> {
+    if inputs.len() != vk.num_inputs {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    // Replay the transcript of the prover.
+    let mut transcript = vk.transcript(inputs);
+    for wire in &[proof.a, proof.b, proof.c] {
+        transcript.append_point(b"wire", wire);
+    }
+    let beta = transcript.challenge_scalar::<E::Fr>(b"beta");
+    let gamma = transcript.challenge_scalar::<E::Fr>(b"gamma");
+    transcript.append_point(b"z", &proof.z);
+    let alpha = transcript.challenge_scalar::<E::Fr>(b"alpha");
+    transcript.append_point(b"t", &proof.t);
+    let zeta = transcript.challenge_scalar::<E::Fr>(b"zeta");
+    let e = &proof.evaluations;
+    let at_zeta = e.at_zeta();
+    for value in at_zeta.iter().chain(Some(&e.z_omega)) {
+        transcript.append_scalar(b"evaluation", value);
+    }
+    let v = transcript.challenge_scalar::<E::Fr>(b"v");
+    transcript.append_point(b"opening", &proof.w_zeta);
+    transcript.append_point(b"opening", &proof.w_zeta_omega);
+    let u = transcript.challenge_scalar::<E::Fr>(b"u");
+
+    // `L_i(zeta) = omega^i Z_H(zeta) / (n (zeta - omega^i))`
+    let n = vk.n;
+    let omega = poly::omega::<E>(n.trailing_zeros());
+    let mut zh = zeta.pow(&[n as u64]);
+    zh.sub_assign(&E::Fr::one());
+    let n_inv = E::Fr::from_repr((n as u64).into())
+        .ok()
+        .and_then(|n| n.inverse())
+        .ok_or(SynthesisError::MalformedVerifyingKey)?;
+    let lagrange = |omega_i: E::Fr| {
+        let mut den = zeta;
+        den.sub_assign(&omega_i);
+        den.inverse().map(|den| {
+            let mut l = omega_i;
+            l.mul_assign(&zh);
+            l.mul_assign(&n_inv);
+            l.mul_assign(&den);
+            l
+        })
+    };
+
+    // `zeta` is in the domain with negligible probability.
+    let l1 = match lagrange(E::Fr::one()) {
+        Some(l1) => l1,
+        None => return Ok(false),
+    };
+    let mut pi = E::Fr::zero();
+    for (input, omega_i) in inputs.iter().zip(poly::powers::<E>(&omega, inputs.len())) {
+        let mut term = match lagrange(omega_i) {
+            Some(l) => l,
+            None => return Ok(false),
+        };
+        term.mul_assign(input);
+        pi.sub_assign(&term);
+    }
+
+    let mut lhs = gate::<E>(&[e.a, e.b, e.c], &e.selectors, &pi);
+
+    let mut perm = e.z;
+    let mut perm_sigma = e.z_omega;
+    for ((w, shift), sigma) in [e.a, e.b, e.c].iter().zip(&shifts::<E>()).zip(&e.sigma) {
+        let mut id = zeta;
+        id.mul_assign(shift);
+        perm.mul_assign(&permuted::<E>(w, &id, &beta, &gamma));
+        perm_sigma.mul_assign(&permuted::<E>(w, sigma, &beta, &gamma));
+    }
+    perm.sub_assign(&perm_sigma);
+    perm.mul_assign(&alpha);
+    lhs.add_assign(&perm);
+
+    let mut first = e.z;
+    first.sub_assign(&E::Fr::one());
+    first.mul_assign(&l1);
+    first.mul_assign(&alpha);
+    first.mul_assign(&alpha);
+    lhs.add_assign(&first);
+
+    let mut rhs = e.t;
+    rhs.mul_assign(&zh);
+    if lhs != rhs {
+        return Ok(false);
+    }
+
+    // The openings `W` of `p` at `x` satisfy `e(W, tau) = e(x W + C - y, 1)`
+    // for the commitment `C` to `p` and `y = p(x)`. Both are combined with
+    // `u`, and the polynomials opened at `zeta` with the powers of `v`.
+    let mut zeta_omega = zeta;
+    zeta_omega.mul_assign(&omega);
+    let commitments = proof.commitments(vk);
+    let powers = poly::powers::<E>(&v, commitments.len());
+
+    let mut f = proof.z.mul(u.into_repr());
+    let mut y = e.z_omega;
+    y.mul_assign(&u);
+    for ((commitment, value), power) in commitments.iter().zip(&at_zeta).zip(&powers) {
+        f.add_assign(&commitment.mul(power.into_repr()));
+        let mut term = *value;
+        term.mul_assign(power);
+        y.add_assign(&term);
+    }
+    f.sub_assign(&E::G1Affine::one().mul(y.into_repr()));
+
+    let mut left = proof.w_zeta_omega.mul(u.into_repr());
+    left.add_assign_mixed(&proof.w_zeta);
+
+    let mut right = f;
+    right.add_assign(&proof.w_zeta.mul(zeta.into_repr()));
+    let mut u_zeta_omega = u;
+    u_zeta_omega.mul_assign(&zeta_omega);
+    right.add_assign(&proof.w_zeta_omega.mul(u_zeta_omega.into_repr()));
+    right.negate();
+
+    let result = E::final_exponentiation(&E::miller_loop(&[
+        (&left.into_affine().prepare(), &vk.tau_g2.prepare()),
+        (&right.into_affine().prepare(), &vk.g2.prepare()),
+    ]));
+
+    Ok(result.map_or(false, |result| result == E::Fqk::one()))
+}
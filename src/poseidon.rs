@@ -0,0 +1,497 @@
+//! The [Poseidon] hash function over the scalar field, computed natively.
+//!
+//! The permutation uses the `x^5` S-box, `full_rounds / 2` full rounds on
+//! each side of the partial rounds and a Cauchy MDS matrix. The same
+//! [`PoseidonConstants`] drive `gadgets::poseidon`, so digests computed here
+//! and in a circuit agree.
+//!
+//! The constants are not those of the reference implementation, so digests
+//! do not match other Poseidon implementations. The round constants are
+//! derived deterministically from BLAKE2s rather than from the Grain LFSR.
+//! The MDS matrix is a fixed Cauchy matrix rather than one sampled from the
+//! LFSR. Like the reference matrices, it is checked against infinitely long
+//! subspace trails with the sufficient condition of [Grassi, Rechberger and
+//! Schofnegger]: the characteristic polynomials of its first `2 * width`
+//! powers are irreducible.
+//!
+//! [Poseidon]: https://eprint.iacr.org/2019/458
+//! [Grassi, Rechberger and Schofnegger]: https://eprint.iacr.org/2020/500
+
+use blake2s_simd::Params as Blake2sParams;
+use ff::{BitIterator, Field, PrimeField, PrimeFieldRepr, ScalarEngine};
+
+/// Width, number of rounds, round constants and MDS matrix of a Poseidon
+/// permutation.
+pub struct PoseidonConstants<E: ScalarEngine> {
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    round_constants: Vec<E::Fr>,
+    mds: Vec<Vec<E::Fr>>,
+    domain_tag: E::Fr,
+}
+
+impl<E: ScalarEngine> Clone for PoseidonConstants<E> {
+    fn clone(&self) -> Self {
+        PoseidonConstants {
+            width: self.width,
+            full_rounds: self.full_rounds,
+            partial_rounds: self.partial_rounds,
+            round_constants: self.round_constants.clone(),
+            mds: self.mds.clone(),
+            domain_tag: self.domain_tag,
+        }
+    }
+}
+
+impl<E: ScalarEngine> PoseidonConstants<E> {
+    /// Constants for hashing `arity` elements, with the number of rounds the
+    /// Poseidon paper recommends for 128-bit security over 255-bit fields.
+    ///
+    /// Panics if `arity` is not between 1 and 8.
+    pub fn new(arity: usize) -> Self {
+        let partial_rounds = match arity {
+            1 | 2 => 57,
+            3 | 4 => 60,
+            5..=8 => 63,
+            _ => panic!("no recommended number of rounds for arity {}", arity),
+        };
+
+        Self::with_rounds(arity + 1, 8, partial_rounds)
+    }
+
+    /// Constants for a permutation of `width` elements, hashing `width - 1`
+    /// elements. `full_rounds` must be even.
+    pub fn with_rounds(width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        assert!(width >= 2, "width must be at least 2");
+        assert_eq!(full_rounds % 2, 0, "full_rounds must be even");
+
+        let tag = format!(
+            "crusty3_zk poseidon round constant {} {} {}",
+            width, full_rounds, partial_rounds
+        );
+
+        let round_constants = (0..width * (full_rounds + partial_rounds))
+            .map(|i| hash_to_field::<E::Fr>(tag.as_bytes(), i as u64))
+            .collect();
+
+        // The first Cauchy matrix without subspace trails.
+        let mds = (0..)
+            .map(|shift| cauchy_matrix::<E::Fr>(width, shift))
+            .find(|mds| has_no_subspace_trails(mds))
+            .expect("the shifts are unbounded");
+
+        // Capacity element of a full Merkle tree node of arity `width - 1`,
+        // 2^arity - 1.
+        let mut domain_tag = E::Fr::one();
+        for _ in 0..width - 1 {
+            domain_tag.double();
+        }
+        domain_tag.sub_assign(&E::Fr::one());
+
+        PoseidonConstants {
+            width,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+            domain_tag,
+        }
+    }
+
+    /// Number of elements of the permutation, one more than the arity.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of elements hashed at once.
+    pub fn arity(&self) -> usize {
+        self.width - 1
+    }
+
+    pub fn full_rounds(&self) -> usize {
+        self.full_rounds
+    }
+
+    pub fn partial_rounds(&self) -> usize {
+        self.partial_rounds
+    }
+
+    /// Round constants, `width` per round.
+    pub fn round_constants(&self) -> &[E::Fr] {
+        &self.round_constants
+    }
+
+    /// Rows of the MDS matrix.
+    pub fn mds(&self) -> &[Vec<E::Fr>] {
+        &self.mds
+    }
+
+    /// The initial value of the capacity element.
+    pub fn domain_tag(&self) -> E::Fr {
+        self.domain_tag
+    }
+
+    /// Whether `round` applies the S-box to all elements, rather than only
+    /// the first one.
+    pub fn is_full_round(&self, round: usize) -> bool {
+        let half = self.full_rounds / 2;
+        round < half || round >= half + self.partial_rounds
+    }
+}
+
+/// Computes the Poseidon digest of `inputs`, of which there must be
+/// `constants.arity()`.
+pub fn poseidon<E: ScalarEngine>(constants: &PoseidonConstants<E>, inputs: &[E::Fr]) -> E::Fr {
+    assert_eq!(inputs.len(), constants.arity(), "wrong number of inputs");
+
+    let mut state = Vec::with_capacity(constants.width);
+    state.push(constants.domain_tag);
+    state.extend_from_slice(inputs);
+
+    permute(constants, &mut state);
+
+    state[1]
+}
+
+/// Applies the Poseidon permutation to `state` in place.
+pub fn permute<E: ScalarEngine>(constants: &PoseidonConstants<E>, state: &mut [E::Fr]) {
+    assert_eq!(state.len(), constants.width, "wrong state width");
+
+    let rounds = constants.full_rounds + constants.partial_rounds;
+    for (round, round_constants) in constants
+        .round_constants
+        .chunks(constants.width)
+        .enumerate()
+        .take(rounds)
+    {
+        for (elt, constant) in state.iter_mut().zip(round_constants) {
+            elt.add_assign(constant);
+        }
+
+        if constants.is_full_round(round) {
+            state.iter_mut().for_each(sbox);
+        } else {
+            sbox(&mut state[0]);
+        }
+
+        let mixed = constants
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(E::Fr::zero(), |mut acc, (m, s)| {
+                        let mut tmp = *m;
+                        tmp.mul_assign(s);
+                        acc.add_assign(&tmp);
+                        acc
+                    })
+            })
+            .collect::<Vec<_>>();
+        state.copy_from_slice(&mixed);
+    }
+}
+
+fn sbox<F: Field>(x: &mut F) {
+    let mut x4 = *x;
+    x4.square();
+    x4.square();
+    x.mul_assign(&x4);
+}
+
+/// The Cauchy matrix `1 / (x_i + y_j)` with distinct `x_i = i` and
+/// `y_j = width + shift + j`, which is MDS.
+fn cauchy_matrix<F: PrimeField>(width: usize, shift: usize) -> Vec<Vec<F>> {
+    (0..width)
+        .map(|i| {
+            (0..width)
+                .map(|j| {
+                    from_u64::<F>((i + width + shift + j) as u64)
+                        .inverse()
+                        .expect("x_i + y_j is not zero")
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether the characteristic polynomials of `m`, `m^2`, ..., `m^(2 width)`
+/// are irreducible. Then no power of `m` has an invariant subspace, which
+/// rules out subspace trails of any length through the partial rounds.
+fn has_no_subspace_trails<F: PrimeField>(m: &[Vec<F>]) -> bool {
+    let mut power = m.to_vec();
+    for _ in 0..2 * m.len() {
+        if !is_irreducible(&char_poly(&power)) {
+            return false;
+        }
+        power = mat_mul(&power, m);
+    }
+    true
+}
+
+fn mat_mul<F: Field>(a: &[Vec<F>], b: &[Vec<F>]) -> Vec<Vec<F>> {
+    a.iter()
+        .map(|row| {
+            (0..b.len())
+                .map(|j| {
+                    row.iter().zip(b).fold(F::zero(), |mut acc, (x, b_row)| {
+                        let mut tmp = *x;
+                        tmp.mul_assign(&b_row[j]);
+                        acc.add_assign(&tmp);
+                        acc
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The characteristic polynomial of `m`, by the Faddeev-LeVerrier
+/// algorithm. Polynomials are coefficients from the constant term up.
+fn char_poly<F: PrimeField>(m: &[Vec<F>]) -> Vec<F> {
+    let n = m.len();
+    let mut coeffs = vec![F::zero(); n + 1];
+    coeffs[n] = F::one();
+
+    // m_k = m (m_(k-1) + c_(n-k+1) I) and c_(n-k) = -tr(m_k) / k
+    let mut m_k = vec![vec![F::zero(); n]; n];
+    for k in 1..=n {
+        for (i, row) in m_k.iter_mut().enumerate() {
+            row[i].add_assign(&coeffs[n - k + 1]);
+        }
+        m_k = mat_mul(m, &m_k);
+
+        let mut trace = (0..n).fold(F::zero(), |mut acc, i| {
+            acc.add_assign(&m_k[i][i]);
+            acc
+        });
+        trace.negate();
+        trace.mul_assign(&from_u64::<F>(k as u64).inverse().expect("k is not zero"));
+        coeffs[n - k] = trace;
+    }
+
+    coeffs
+}
+
+/// Whether the monic polynomial `f` is irreducible, by Rabin's test: `f` of
+/// degree `n` divides `x^(p^n) - x` and is coprime to `x^(p^(n/q)) - x` for
+/// the prime factors `q` of `n`.
+fn is_irreducible<F: PrimeField>(f: &[F]) -> bool {
+    let n = f.len() - 1;
+    let x = vec![F::zero(), F::one()];
+
+    // frobenius[k] = x^(p^k) mod f, with g(x)^p = g(x^p) over the field.
+    let mut x_p = vec![F::one()];
+    for bit in BitIterator::new(F::char()) {
+        x_p = poly_mul_mod(&x_p, &x_p, f);
+        if bit {
+            x_p = poly_mul_mod(&x_p, &x, f);
+        }
+    }
+    let mut frobenius = vec![poly_mod(x, f), x_p.clone()];
+    for _ in 2..=n {
+        let next = frobenius
+            .last()
+            .expect("frobenius is not empty")
+            .iter()
+            .rev()
+            .fold(vec![], |acc, coeff| {
+                let mut acc = poly_mul_mod(&acc, &x_p, f);
+                poly_add_constant(&mut acc, coeff);
+                acc
+            });
+        frobenius.push(next);
+    }
+
+    let minus_x = |mut g: Vec<F>| {
+        g.resize(g.len().max(2), F::zero());
+        g[1].sub_assign(&F::one());
+        trim(g)
+    };
+    if !minus_x(frobenius[n].clone()).is_empty() {
+        return false;
+    }
+    (2..=n)
+        .filter(|q| n % q == 0 && (2..*q).all(|d| q % d != 0))
+        .all(|q| poly_gcd(f.to_vec(), minus_x(frobenius[n / q].clone())).len() == 1)
+}
+
+fn trim<F: Field>(mut a: Vec<F>) -> Vec<F> {
+    while a.last().map_or(false, |c| c.is_zero()) {
+        a.pop();
+    }
+    a
+}
+
+fn poly_add_constant<F: Field>(a: &mut Vec<F>, c: &F) {
+    if a.is_empty() {
+        a.push(F::zero());
+    }
+    a[0].add_assign(c);
+}
+
+/// The remainder of `a` by the non-zero `f`.
+fn poly_mod<F: Field>(a: Vec<F>, f: &[F]) -> Vec<F> {
+    let f = trim(f.to_vec());
+    let lead_inv = f[f.len() - 1].inverse().expect("f is not zero");
+    let mut a = trim(a);
+    while a.len() >= f.len() {
+        let mut coeff = a[a.len() - 1];
+        coeff.mul_assign(&lead_inv);
+        let shift = a.len() - f.len();
+        for (i, f_i) in f.iter().enumerate() {
+            let mut tmp = *f_i;
+            tmp.mul_assign(&coeff);
+            a[shift + i].sub_assign(&tmp);
+        }
+        a = trim(a);
+    }
+    a
+}
+
+fn poly_mul_mod<F: Field>(a: &[F], b: &[F], f: &[F]) -> Vec<F> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut product = vec![F::zero(); a.len() + b.len() - 1];
+    for (i, a_i) in a.iter().enumerate() {
+        for (j, b_j) in b.iter().enumerate() {
+            let mut tmp = *a_i;
+            tmp.mul_assign(b_j);
+            product[i + j].add_assign(&tmp);
+        }
+    }
+    poly_mod(product, f)
+}
+
+fn poly_gcd<F: Field>(mut a: Vec<F>, mut b: Vec<F>) -> Vec<F> {
+    a = trim(a);
+    b = trim(b);
+    while !b.is_empty() {
+        let r = poly_mod(a, &b);
+        a = b;
+        b = r;
+    }
+    a
+}
+
+fn from_u64<F: PrimeField>(n: u64) -> F {
+    F::from_repr(F::Repr::from(n)).expect("small integers are in the field")
+}
+
+/// Maps `tag` and `index` to a field element by rejection sampling BLAKE2s
+/// digests truncated to the bit length of the field.
+pub(crate) fn hash_to_field<F: PrimeField>(tag: &[u8], index: u64) -> F {
+    for nonce in 0u64.. {
+        let hash = Blake2sParams::new()
+            .hash_length(32)
+            .to_state()
+            .update(tag)
+            .update(&index.to_le_bytes())
+            .update(&nonce.to_le_bytes())
+            .finalize();
+
+        let mut repr = F::Repr::default();
+        repr.read_le(hash.as_bytes())
+            .expect("the digest fills the representation");
+
+        let limbs = repr.as_mut();
+        let excess = limbs.len() as u32 * 64 - F::NUM_BITS;
+        let top = limbs.len() - 1;
+        limbs[top] &= u64::max_value() >> excess;
+
+        if let Ok(elt) = F::from_repr(repr) {
+            return elt;
+        }
+    }
+
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+
+    #[test]
+    fn test_constants_are_deterministic() {
+        let a = PoseidonConstants::<Bls12>::new(2);
+        let b = PoseidonConstants::<Bls12>::new(2);
+
+        assert_eq!(a.width(), 3);
+        assert_eq!(a.round_constants().len(), 3 * (8 + 57));
+        assert_eq!(a.round_constants(), b.round_constants());
+        assert_eq!(a.mds(), b.mds());
+        assert_eq!(a.domain_tag(), Fr::from_str("3").unwrap());
+
+        // Different rounds give unrelated constants.
+        let c = PoseidonConstants::<Bls12>::with_rounds(3, 8, 56);
+        assert!(a.round_constants()[0] != c.round_constants()[0]);
+    }
+
+    #[test]
+    fn test_mds_is_invertible() {
+        let constants = PoseidonConstants::<Bls12>::new(2);
+        let m = constants.mds();
+
+        // The 2x2 minors of a Cauchy matrix do not vanish.
+        for (i, upper) in m.iter().enumerate() {
+            for lower in &m[i + 1..] {
+                let mut ad = upper[0];
+                ad.mul_assign(&lower[1]);
+                let mut bc = upper[1];
+                bc.mul_assign(&lower[0]);
+                assert!(ad != bc);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mds_has_no_subspace_trails() {
+        // x^2 - 4 = (x - 2)(x + 2), while 5 is not a square modulo r.
+        let fr = |n: &str| Fr::from_str(n).unwrap();
+        let minus = |n: &str| {
+            let mut c = fr(n);
+            c.negate();
+            vec![c, Fr::zero(), Fr::one()]
+        };
+        assert!(!is_irreducible(&minus("4")));
+        assert!(is_irreducible(&minus("5")));
+
+        // The unshifted matrices of width 3 have invariant subspaces.
+        assert!(!has_no_subspace_trails(&cauchy_matrix::<Fr>(3, 0)));
+        assert!(!has_no_subspace_trails(&cauchy_matrix::<Fr>(3, 1)));
+        let constants = PoseidonConstants::<Bls12>::new(2);
+        assert_eq!(constants.mds(), &cauchy_matrix::<Fr>(3, 2)[..]);
+
+        // The characteristic polynomial of a diagonal matrix.
+        let diagonal = vec![vec![fr("2"), Fr::zero()], vec![Fr::zero(), fr("3")]];
+        assert_eq!(
+            char_poly(&diagonal),
+            vec![fr("6"), minus("5")[0], Fr::one()]
+        );
+    }
+
+    #[test]
+    fn test_poseidon() {
+        let constants = PoseidonConstants::<Bls12>::new(2);
+        let one = Fr::one();
+        let two = Fr::from_str("2").unwrap();
+
+        let h = poseidon(&constants, &[one, two]);
+        assert_eq!(h, poseidon(&constants, &[one, two]));
+        assert!(h != poseidon(&constants, &[two, one]));
+
+        // The digest is the second element of the permuted state.
+        let mut state = vec![constants.domain_tag(), one, two];
+        permute(&constants, &mut state);
+        assert_eq!(h, state[1]);
+
+        let wide = PoseidonConstants::<Bls12>::new(8);
+        let inputs = (1..=8)
+            .map(|i| Fr::from_str(&i.to_string()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(poseidon(&wide, &inputs) != poseidon(&constants, &[one, two]));
+    }
+}
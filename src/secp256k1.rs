@@ -0,0 +1,288 @@
+//! The secp256k1 curve `y^2 = x^3 + 7` and its ECDSA signatures, computed
+//! natively for `gadgets::ecdsa`.
+//!
+//! Numbers are [`Nat`]s. This is neither fast nor constant time, and is only
+//! meant for computing and checking witnesses.
+
+use crate::gadgets::nonnative::Nat;
+
+/// The modulus `p` of the base field.
+pub fn base_modulus() -> Nat {
+    Nat::from_bytes_be(&[
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff, 0xff,
+        0xfc, 0x2f,
+    ])
+}
+
+/// The order `n` of the group of points.
+pub fn scalar_modulus() -> Nat {
+    Nat::from_bytes_be(&[
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36,
+        0x41, 0x41,
+    ])
+}
+
+fn mul_mod(a: &Nat, b: &Nat, modulus: &Nat) -> Nat {
+    (a * b).div_rem(modulus).1
+}
+
+fn add_mod(a: &Nat, b: &Nat, modulus: &Nat) -> Nat {
+    (a + b).div_rem(modulus).1
+}
+
+fn sub_mod(a: &Nat, b: &Nat, modulus: &Nat) -> Nat {
+    let b = b.div_rem(modulus).1;
+    (&(a + modulus) - &b).div_rem(modulus).1
+}
+
+/// An affine point of the curve other than the point at infinity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Point {
+    x: Nat,
+    y: Nat,
+}
+
+impl Point {
+    /// The point `(x, y)`, if it is on the curve.
+    pub fn from_xy(x: Nat, y: Nat) -> Option<Self> {
+        let p = base_modulus();
+        if x >= p || y >= p {
+            return None;
+        }
+
+        let x3 = mul_mod(&mul_mod(&x, &x, &p), &x, &p);
+        if mul_mod(&y, &y, &p) == add_mod(&x3, &Nat::from_u64(7), &p) {
+            Some(Point { x, y })
+        } else {
+            None
+        }
+    }
+
+    /// The standard generator `G`.
+    pub fn generator() -> Self {
+        Point {
+            x: Nat::from_bytes_be(&[
+                0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87,
+                0x0b, 0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b,
+                0x16, 0xf8, 0x17, 0x98,
+            ]),
+            y: Nat::from_bytes_be(&[
+                0x48, 0x3a, 0xda, 0x77, 0x26, 0xa3, 0xc4, 0x65, 0x5d, 0xa4, 0xfb, 0xfc, 0x0e, 0x11,
+                0x08, 0xa8, 0xfd, 0x17, 0xb4, 0x48, 0xa6, 0x85, 0x54, 0x19, 0x9c, 0x47, 0xd0, 0x8f,
+                0xfb, 0x10, 0xd4, 0xb8,
+            ]),
+        }
+    }
+
+    pub fn x(&self) -> &Nat {
+        &self.x
+    }
+
+    pub fn y(&self) -> &Nat {
+        &self.y
+    }
+
+    pub fn negate(&self) -> Self {
+        let p = base_modulus();
+        Point {
+            x: self.x.clone(),
+            y: sub_mod(&Nat::zero(), &self.y, &p),
+        }
+    }
+
+    /// The sum of two points, or `None` for the point at infinity.
+    pub fn add(&self, other: &Self) -> Option<Self> {
+        let p = base_modulus();
+        if self.x == other.x {
+            return if self.y == other.y {
+                self.double()
+            } else {
+                None
+            };
+        }
+
+        // lambda = (y2 - y1) / (x2 - x1)
+        let dx = sub_mod(&other.x, &self.x, &p);
+        let dy = sub_mod(&other.y, &self.y, &p);
+        let lambda = mul_mod(&dy, &dx.inv_mod(&p).expect("dx is not zero"), &p);
+
+        Some(self.with_slope(&lambda, &other.x))
+    }
+
+    /// Twice this point, or `None` for the point at infinity.
+    pub fn double(&self) -> Option<Self> {
+        let p = base_modulus();
+        if self.y.is_zero() {
+            return None;
+        }
+
+        // lambda = 3 x^2 / 2 y
+        let x2 = mul_mod(&self.x, &self.x, &p);
+        let three_x2 = mul_mod(&x2, &Nat::from_u64(3), &p);
+        let two_y = add_mod(&self.y, &self.y, &p);
+        let lambda = mul_mod(&three_x2, &two_y.inv_mod(&p).expect("y is not zero"), &p);
+
+        Some(self.with_slope(&lambda, &self.x))
+    }
+
+    /// The sum of this point and the point of abscissa `other_x` on the line
+    /// through it of slope `lambda`.
+    fn with_slope(&self, lambda: &Nat, other_x: &Nat) -> Self {
+        let p = base_modulus();
+
+        // x3 = lambda^2 - x1 - x2, y3 = lambda (x1 - x3) - y1
+        let x3 = sub_mod(
+            &sub_mod(&mul_mod(lambda, lambda, &p), &self.x, &p),
+            other_x,
+            &p,
+        );
+        let y3 = sub_mod(
+            &mul_mod(lambda, &sub_mod(&self.x, &x3, &p), &p),
+            &self.y,
+            &p,
+        );
+
+        Point { x: x3, y: y3 }
+    }
+
+    /// The multiple by `scalar`, or `None` for the point at infinity.
+    pub fn mul(&self, scalar: &Nat) -> Option<Self> {
+        let mut result: Option<Self> = None;
+        for i in (0..scalar.bits()).rev() {
+            result = result.and_then(|r| r.double());
+            if scalar.bit(i) {
+                result = match result {
+                    Some(r) => r.add(self),
+                    None => Some(self.clone()),
+                };
+            }
+        }
+
+        result
+    }
+}
+
+/// An ECDSA signature `(r, s)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub r: Nat,
+    pub s: Nat,
+}
+
+/// Signs the message hash `z` with the private key and `nonce`, which must
+/// be secret and never reused. Returns `None` for the rare nonces that give
+/// no signature.
+pub fn sign_with_nonce(private_key: &Nat, z: &Nat, nonce: &Nat) -> Option<Signature> {
+    let n = scalar_modulus();
+
+    let k = nonce.div_rem(&n).1;
+    let r = Point::generator().mul(&k)?.x.div_rem(&n).1;
+    if r.is_zero() {
+        return None;
+    }
+
+    // s = (z + r d) / k
+    let s = mul_mod(
+        &add_mod(z, &mul_mod(&r, private_key, &n), &n),
+        &k.inv_mod(&n)?,
+        &n,
+    );
+    if s.is_zero() {
+        return None;
+    }
+
+    Some(Signature { r, s })
+}
+
+/// Whether `signature` is a valid signature of the message hash `z` under
+/// `public_key`.
+pub fn verify(public_key: &Point, z: &Nat, signature: &Signature) -> bool {
+    let n = scalar_modulus();
+    let Signature { r, s } = signature;
+    if r.is_zero() || *r >= n || s.is_zero() || *s >= n {
+        return false;
+    }
+
+    // u1 = z / s, u2 = r / s
+    let w = s.inv_mod(&n).expect("s is not zero");
+    let u1 = mul_mod(z, &w, &n);
+    let u2 = mul_mod(r, &w, &n);
+
+    let sum = match (Point::generator().mul(&u1), public_key.mul(&u2)) {
+        (Some(a), Some(b)) => a.add(&b),
+        (a, None) => a,
+        (None, b) => b,
+    };
+
+    match sum {
+        Some(point) => point.x.div_rem(&n).1 == *r,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_group_law() {
+        let g = Point::generator();
+        assert!(Point::from_xy(g.x.clone(), g.y.clone()).is_some());
+        assert!(Point::from_xy(g.x.clone(), Nat::from_u64(1)).is_none());
+
+        let g2 = g.double().unwrap();
+        let g3 = g2.add(&g).unwrap();
+        assert_eq!(g.add(&g2), Some(g3.clone()));
+        assert_eq!(g.mul(&Nat::from_u64(3)), Some(g3.clone()));
+        assert!(Point::from_xy(g3.x.clone(), g3.y.clone()).is_some());
+        assert_eq!(g.add(&g.negate()), None);
+        assert_eq!(g.mul(&scalar_modulus()), None);
+    }
+
+    #[test]
+    fn test_ecdsa() {
+        let d = Nat::from_bytes_be(&hex!(
+            "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721"
+        ));
+        let z = Nat::from_bytes_be(&hex!(
+            "af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf"
+        ));
+        let k = Nat::from_bytes_be(&hex!(
+            "a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60"
+        ));
+
+        let public_key = Point::generator().mul(&d).unwrap();
+        assert_eq!(
+            public_key,
+            Point::from_xy(
+                Nat::from_bytes_be(&hex!(
+                    "2c8c31fc9f990c6b55e3865a184a4ce50e09481f2eaeb3e60ec1cea13a6ae645"
+                )),
+                Nat::from_bytes_be(&hex!(
+                    "64b95e4fdb6948c0386e189b006a29f686769b011704275e4459822dc3328085"
+                )),
+            )
+            .unwrap()
+        );
+
+        let signature = sign_with_nonce(&d, &z, &k).unwrap();
+        assert_eq!(
+            signature,
+            Signature {
+                r: Nat::from_bytes_be(&hex!(
+                    "432310e32cb80eb6503a26ce83cc165c783b870845fb8aad6d970889fcd7a6c8"
+                )),
+                s: Nat::from_bytes_be(&hex!(
+                    "530128b6b81c548874a6305d93ed071ca6e05074d85863d4056ce89b02bfab69"
+                )),
+            }
+        );
+
+        assert!(verify(&public_key, &z, &signature));
+        assert!(!verify(&public_key, &(&z + &Nat::from_u64(1)), &signature));
+        assert!(!verify(&Point::generator(), &z, &signature));
+    }
+}
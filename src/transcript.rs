@@ -0,0 +1,150 @@
+//! Fiat-Shamir transcripts.
+//!
+//! Every non-interactive argument of the crate derives its challenges from
+//! a `Transcript`: batch verification, aggregation, PLONK, inner product
+//! arguments and range proofs. Messages are appended under a label, and a
+//! challenge depends on all messages and challenges before it, so that two
+//! protocols or two steps of one protocol never share a challenge by
+//! accident.
+//!
+//! `Blake2bTranscript` is the implementation used by the crate. Other hashes
+//! plug in by implementing `append_message` and `challenge_bytes`, the
+//! points, scalars and challenges are encoded by the provided methods.
+
+use ff::{PrimeField, PrimeFieldRepr};
+use groupy::CurveAffine;
+use rand_core::{Error, RngCore};
+
+/// A Fiat-Shamir transcript.
+///
+/// Every challenge has to depend on all messages appended before it and on
+/// the previous challenges.
+pub trait Transcript {
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]);
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]);
+
+    /// Appends a point in compressed form.
+    fn append_point<G: CurveAffine>(&mut self, label: &'static [u8], point: &G) {
+        self.append_message(label, point.into_compressed().as_ref());
+    }
+
+    /// Appends a scalar in little-endian form.
+    fn append_scalar<F: PrimeField>(&mut self, label: &'static [u8], scalar: &F) {
+        let mut bytes = vec![];
+        scalar
+            .into_repr()
+            .write_le(&mut bytes)
+            .expect("writing to a vector succeeds");
+        self.append_message(label, &bytes);
+    }
+
+    /// Draws a uniform scalar.
+    fn challenge_scalar<F: PrimeField>(&mut self, label: &'static [u8]) -> F {
+        self.append_message(b"challenge", label);
+        F::random(&mut TranscriptRng(self))
+    }
+}
+
+/// A `Transcript` hashing with BLAKE2b.
+#[derive(Clone)]
+pub struct Blake2bTranscript {
+    state: blake2b_simd::State,
+}
+
+impl Blake2bTranscript {
+    /// Starts a transcript for the protocol named `label`.
+    pub fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Blake2bTranscript {
+            state: blake2b_simd::State::new(),
+        };
+        transcript.append_message(b"protocol", label);
+        transcript
+    }
+}
+
+impl Transcript for Blake2bTranscript {
+    // Lengths are prepended so that the concatenation is unambiguous.
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.state.update(&(label.len() as u64).to_le_bytes());
+        self.state.update(label);
+        self.state.update(&(message.len() as u64).to_le_bytes());
+        self.state.update(message);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.append_message(label, &(dest.len() as u64).to_le_bytes());
+        for (i, chunk) in dest.chunks_mut(blake2b_simd::OUTBYTES).enumerate() {
+            let hash = self
+                .state
+                .clone()
+                .update(&(i as u64).to_le_bytes())
+                .finalize();
+            chunk.copy_from_slice(&hash.as_bytes()[..chunk.len()]);
+        }
+        self.append_message(b"challenge", dest);
+    }
+}
+
+/// An `RngCore` drawing its bytes from a transcript, for sampling anything
+/// with a `random` constructor, such as curve points, from a challenge.
+pub struct TranscriptRng<'a, T: Transcript + ?Sized>(pub &'a mut T);
+
+impl<'a, T: Transcript + ?Sized> RngCore for TranscriptRng<'a, T> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.challenge_bytes(b"coefficient", dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::Fr;
+
+    #[test]
+    fn test_blake2b_transcript() {
+        let challenge = |message: &[u8]| {
+            let mut transcript = Blake2bTranscript::new(b"test");
+            transcript.append_message(b"message", message);
+            let mut first = [0u8; 100];
+            transcript.challenge_bytes(b"first", &mut first);
+            let mut second = [0u8; 100];
+            transcript.challenge_bytes(b"first", &mut second);
+            (first.to_vec(), second.to_vec())
+        };
+
+        let (first, second) = challenge(b"hello");
+        assert_eq!((first.clone(), second.clone()), challenge(b"hello"));
+        assert_ne!(first, second);
+        assert_ne!(first, challenge(b"hellp").0);
+        // The second block of a challenge is not a copy of the first one.
+        assert_ne!(first[..36], first[64..]);
+
+        // Scalar challenges are separated by their labels.
+        let scalar = |label: &'static [u8]| {
+            let mut transcript = Blake2bTranscript::new(b"test");
+            transcript.append_scalar(b"scalar", &Fr::from_str("7").unwrap());
+            transcript.challenge_scalar::<Fr>(label)
+        };
+        assert_eq!(scalar(b"x"), scalar(b"x"));
+        assert_ne!(scalar(b"x"), scalar(b"y"));
+    }
+}
@@ -0,0 +1,324 @@
+//! Loading of circuits compiled by circom: the `.r1cs` constraint system and
+//! the `.wtns` witness produced by its witness generator.
+//!
+//! Both are little-endian section based files. Wire 0 is the constant one,
+//! followed by the public outputs, the public inputs and the private wires,
+//! so the public wires map to the inputs of this crate in the same order.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use ff::{Field, PrimeField, PrimeFieldRepr};
+
+use super::r1cs::{Constraint, R1cs, R1csCircuit, SparseLc, Witness};
+use crate::bls::Engine;
+use crate::{invalid_data, Index};
+
+const R1CS_HEADER: u32 = 1;
+const R1CS_CONSTRAINTS: u32 = 2;
+const WTNS_HEADER: u32 = 1;
+const WTNS_VALUES: u32 = 2;
+
+/// Reads a circom `.r1cs` file.
+pub fn read_r1cs<E: Engine, R: Read>(reader: R) -> io::Result<R1cs<E>> {
+    let mut sections = read_sections(reader, b"r1cs")?;
+
+    let header = section(&mut sections, R1CS_HEADER)?;
+    let mut header = &header[..];
+    let n8 = read_prime::<E>(&mut header)?;
+    let num_wires = header.read_u32::<LittleEndian>()? as usize;
+    let num_pub_out = header.read_u32::<LittleEndian>()? as usize;
+    let num_pub_in = header.read_u32::<LittleEndian>()? as usize;
+    let _num_prv_in = header.read_u32::<LittleEndian>()?;
+    let _num_labels = header.read_u64::<LittleEndian>()?;
+    let num_constraints = header.read_u32::<LittleEndian>()? as usize;
+
+    let num_inputs = 1 + num_pub_out + num_pub_in;
+    if num_inputs > num_wires {
+        return Err(invalid_data("more public wires than wires"));
+    }
+
+    let constraints = section(&mut sections, R1CS_CONSTRAINTS)?;
+    let mut constraints = &constraints[..];
+    let mut read_lc = || -> io::Result<SparseLc<E>> {
+        let num_terms = constraints.read_u32::<LittleEndian>()? as usize;
+        let mut lc = Vec::with_capacity(num_terms.min(num_wires));
+        for _ in 0..num_terms {
+            let wire = constraints.read_u32::<LittleEndian>()? as usize;
+            let index = if wire < num_inputs {
+                Index::Input(wire)
+            } else if wire < num_wires {
+                Index::Aux(wire - num_inputs)
+            } else {
+                return Err(invalid_data("wire out of range"));
+            };
+            lc.push((index, read_fr::<E>(&mut constraints, n8)?));
+        }
+        Ok(lc)
+    };
+
+    let mut r1cs_constraints = Vec::with_capacity(num_constraints.min(1 << 20));
+    for _ in 0..num_constraints {
+        let a = read_lc()?;
+        let b = read_lc()?;
+        let c = read_lc()?;
+        r1cs_constraints.push(Constraint { a, b, c });
+    }
+
+    Ok(R1cs {
+        num_inputs,
+        num_aux: num_wires - num_inputs,
+        constraints: r1cs_constraints,
+    })
+}
+
+/// Reads a circom `.wtns` file into the witness of `r1cs`.
+pub fn read_witness<E: Engine, R: Read>(reader: R, r1cs: &R1cs<E>) -> io::Result<Witness<E>> {
+    let mut sections = read_sections(reader, b"wtns")?;
+
+    let header = section(&mut sections, WTNS_HEADER)?;
+    let mut header = &header[..];
+    let n8 = read_prime::<E>(&mut header)?;
+    let num_wires = header.read_u32::<LittleEndian>()? as usize;
+    if num_wires != r1cs.num_inputs + r1cs.num_aux {
+        return Err(invalid_data("witness does not match the constraint system"));
+    }
+
+    let values = section(&mut sections, WTNS_VALUES)?;
+    let mut values = &values[..];
+    let mut wires = Vec::with_capacity(num_wires);
+    for _ in 0..num_wires {
+        wires.push(read_fr::<E>(&mut values, n8)?);
+    }
+
+    if wires[0] != E::Fr::one() {
+        return Err(invalid_data("wire 0 is not one"));
+    }
+    let aux = wires.split_off(r1cs.num_inputs);
+    wires.remove(0);
+
+    Ok(Witness { inputs: wires, aux })
+}
+
+/// Loads a circuit from a `.r1cs` file and, for proving, its `.wtns` witness.
+pub fn load_circuit<E: Engine, P: AsRef<Path>>(
+    r1cs_path: P,
+    wtns_path: Option<P>,
+) -> io::Result<R1csCircuit<E>> {
+    let r1cs = read_r1cs(BufReader::new(File::open(r1cs_path)?))?;
+    let witness = match wtns_path {
+        Some(path) => Some(read_witness(BufReader::new(File::open(path)?), &r1cs)?),
+        None => None,
+    };
+
+    Ok(R1csCircuit { r1cs, witness })
+}
+
+fn read_sections<R: Read>(mut reader: R, magic: &[u8; 4]) -> io::Result<HashMap<u32, Vec<u8>>> {
+    let mut file_magic = [0u8; 4];
+    reader.read_exact(&mut file_magic)?;
+    if &file_magic != magic {
+        return Err(invalid_data("wrong magic"));
+    }
+    let _version = reader.read_u32::<LittleEndian>()?;
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let section_type = reader.read_u32::<LittleEndian>()?;
+        let size = reader.read_u64::<LittleEndian>()?;
+
+        // Read through `take` so that bogus sizes fail with an early EOF
+        // instead of a huge allocation.
+        let mut content = Vec::new();
+        reader.by_ref().take(size).read_to_end(&mut content)?;
+        if content.len() as u64 != size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "section is truncated",
+            ));
+        }
+        sections.insert(section_type, content);
+    }
+
+    Ok(sections)
+}
+
+fn section(sections: &mut HashMap<u32, Vec<u8>>, section_type: u32) -> io::Result<Vec<u8>> {
+    sections
+        .remove(&section_type)
+        .ok_or_else(|| invalid_data(&format!("missing section {}", section_type)))
+}
+
+/// Reads the field size and prime of a header and checks that they are the
+/// ones of the scalar field of `E`.
+fn read_prime<E: Engine>(header: &mut &[u8]) -> io::Result<usize> {
+    let n8 = header.read_u32::<LittleEndian>()? as usize;
+
+    let mut modulus = vec![];
+    E::Fr::char().write_le(&mut modulus)?;
+    if n8 != modulus.len() {
+        return Err(invalid_data("field size does not match the scalar field"));
+    }
+
+    let mut prime = vec![0u8; n8];
+    header.read_exact(&mut prime)?;
+    if prime != modulus {
+        return Err(invalid_data("prime does not match the scalar field"));
+    }
+
+    Ok(n8)
+}
+
+fn read_fr<E: Engine>(reader: &mut &[u8], n8: usize) -> io::Result<E::Fr> {
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    repr.read_le(reader.take(n8 as u64))?;
+
+    E::Fr::from_repr(repr).map_err(|e| invalid_data(&e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use byteorder::WriteBytesExt;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use crate::util_cs::test_fixtures::test_rng;
+
+    fn write_section(out: &mut Vec<u8>, section_type: u32, content: &[u8]) {
+        out.write_u32::<LittleEndian>(section_type).unwrap();
+        out.write_u64::<LittleEndian>(content.len() as u64).unwrap();
+        out.extend_from_slice(content);
+    }
+
+    fn write_prime(out: &mut Vec<u8>) {
+        out.write_u32::<LittleEndian>(32).unwrap();
+        Fr::char().write_le(&mut *out).unwrap();
+    }
+
+    fn write_fr(out: &mut Vec<u8>, fr: &Fr) {
+        fr.into_repr().write_le(&mut *out).unwrap();
+    }
+
+    // The circom encoding of `out = x * x * x` with `out` public:
+    // wires [1, out, x, x2].
+    fn cube_r1cs() -> Vec<u8> {
+        let mut header = vec![];
+        write_prime(&mut header);
+        for &n in &[4u32, 1, 0, 1] {
+            header.write_u32::<LittleEndian>(n).unwrap();
+        }
+        header.write_u64::<LittleEndian>(4).unwrap();
+        header.write_u32::<LittleEndian>(2).unwrap();
+
+        let mut constraints = vec![];
+        // x * x = x2, x2 * x = out
+        for &(a, b, c) in &[(2u32, 2u32, 3u32), (3, 2, 1)] {
+            for &wire in &[a, b, c] {
+                constraints.write_u32::<LittleEndian>(1).unwrap();
+                constraints.write_u32::<LittleEndian>(wire).unwrap();
+                write_fr(&mut constraints, &Fr::one());
+            }
+        }
+
+        let mut file = b"r1cs".to_vec();
+        file.write_u32::<LittleEndian>(1).unwrap();
+        file.write_u32::<LittleEndian>(2).unwrap();
+        // Sections may come in any order.
+        write_section(&mut file, R1CS_CONSTRAINTS, &constraints);
+        write_section(&mut file, R1CS_HEADER, &header);
+        file
+    }
+
+    fn cube_wtns(x: u64) -> Vec<u8> {
+        let mut header = vec![];
+        write_prime(&mut header);
+        header.write_u32::<LittleEndian>(4).unwrap();
+
+        let mut values = vec![];
+        for &v in &[1, x * x * x, x, x * x] {
+            write_fr(&mut values, &Fr::from_str(&v.to_string()).unwrap());
+        }
+
+        let mut file = b"wtns".to_vec();
+        file.write_u32::<LittleEndian>(2).unwrap();
+        file.write_u32::<LittleEndian>(2).unwrap();
+        write_section(&mut file, WTNS_HEADER, &header);
+        write_section(&mut file, WTNS_VALUES, &values);
+        file
+    }
+
+    #[test]
+    fn test_read_r1cs_and_witness() {
+        let r1cs = read_r1cs::<Bls12, _>(&cube_r1cs()[..]).unwrap();
+        assert_eq!(r1cs.num_inputs, 2);
+        assert_eq!(r1cs.num_aux, 2);
+        assert_eq!(r1cs.constraints.len(), 2);
+        assert_eq!(r1cs.constraints[1].c[0].0, Index::Input(1));
+        assert_eq!(r1cs.constraints[1].a[0].0, Index::Aux(1));
+
+        let witness = read_witness(&cube_wtns(3)[..], &r1cs).unwrap();
+        assert_eq!(witness.inputs, vec![Fr::from_str("27").unwrap()]);
+        assert!(r1cs.is_satisfied(&witness));
+
+        let mut wrong = cube_wtns(3);
+        let len = wrong.len();
+        wrong[len - 32] ^= 1;
+        let wrong = read_witness(&wrong[..], &r1cs).unwrap();
+        assert!(!r1cs.is_satisfied(&wrong));
+    }
+
+    #[test]
+    fn test_read_r1cs_rejects_invalid() {
+        let file = cube_r1cs();
+        assert!(read_r1cs::<Bls12, _>(&file[..file.len() - 1]).is_err());
+
+        let mut wrong_magic = file.clone();
+        wrong_magic[0] = b'x';
+        assert!(read_r1cs::<Bls12, _>(&wrong_magic[..]).is_err());
+
+        // The prime is the first field of the header, after the field size.
+        let mut wrong_prime = file;
+        let header = wrong_prime.len() - (4 + 32 + 4 * 4 + 8 + 4);
+        wrong_prime[header + 4] ^= 1;
+        assert!(read_r1cs::<Bls12, _>(&wrong_prime[..]).is_err());
+    }
+
+    #[test]
+    fn test_prove_circom_circuit() {
+        let mut rng = test_rng();
+
+        let r1cs = read_r1cs::<Bls12, _>(&cube_r1cs()[..]).unwrap();
+        let witness = read_witness(&cube_wtns(5)[..], &r1cs).unwrap();
+
+        let params = generate_random_parameters(
+            R1csCircuit {
+                r1cs: r1cs.clone(),
+                witness: None,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let inputs = witness.inputs.clone();
+        let proof = create_random_proof(
+            R1csCircuit {
+                r1cs,
+                witness: Some(witness),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+
+        let pvk = prepare_verifying_key(&params.vk);
+        assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[Fr::from_str("124").unwrap()]).unwrap());
+    }
+}
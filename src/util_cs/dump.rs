@@ -0,0 +1,387 @@
+//! Witness dumps: every assigned variable of a circuit with its namespace
+//! path, in a text file that can be diffed between runs.
+//!
+//! `WitnessDump::record` synthesizes a circuit with its witness and records
+//! the values, and `WitnessDump::replay` checks the constraints of a circuit
+//! against a dump, so that a proof that fails to verify can be traced back
+//! to the constraints and values involved.
+//!
+//! The file starts with a `crusty3_zk witness v1` line, followed by a line
+//! per variable in allocation order, inputs first:
+//!
+//! ```text
+//! input<TAB><value in big-endian hex><TAB><path>
+//! aux<TAB><value in big-endian hex><TAB><path>
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use ff::{Field, PrimeField, PrimeFieldRepr};
+
+use super::test_cs::{compute_path, TestConstraintSystem, UnsatisfiedConstraint};
+use crate::bls::Engine;
+use crate::{Circuit, ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+const HEADER: &str = "crusty3_zk witness v1";
+
+/// The paths and values of the variables of a circuit, without the constant
+/// `ONE` input.
+pub struct WitnessDump<E: Engine> {
+    pub inputs: Vec<(String, E::Fr)>,
+    pub aux: Vec<(String, E::Fr)>,
+}
+
+impl<E: Engine> Clone for WitnessDump<E> {
+    fn clone(&self) -> Self {
+        WitnessDump {
+            inputs: self.inputs.clone(),
+            aux: self.aux.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for WitnessDump<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inputs == other.inputs && self.aux == other.aux
+    }
+}
+
+impl<E: Engine> fmt::Debug for WitnessDump<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WitnessDump")
+            .field("inputs", &self.inputs)
+            .field("aux", &self.aux)
+            .finish()
+    }
+}
+
+/// The outcome of replaying a circuit against a `WitnessDump`.
+pub struct Replay<E: Engine> {
+    /// Paths of variables of the circuit that are not in the dump. They are
+    /// assigned zero.
+    pub missing: Vec<String>,
+    /// Constraints that do not hold, in the order they were enforced.
+    pub unsatisfied: Vec<UnsatisfiedConstraint<E>>,
+}
+
+impl<E: Engine> Replay<E> {
+    /// Whether the dump assigns every variable and satisfies every
+    /// constraint.
+    pub fn is_satisfied(&self) -> bool {
+        self.missing.is_empty() && self.unsatisfied.is_empty()
+    }
+}
+
+impl<E: Engine> WitnessDump<E> {
+    /// Synthesizes `circuit`, which must have a witness, and records the
+    /// value of every variable.
+    pub fn record<C: Circuit<E>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs)?;
+
+        Ok(Self::from_test_cs(&cs))
+    }
+
+    /// The values assigned in `cs`.
+    pub fn from_test_cs(cs: &TestConstraintSystem<E>) -> Self {
+        let entries = |vars: &[(E::Fr, String)]| {
+            vars.iter()
+                .map(|(value, path)| (path.clone(), *value))
+                .collect()
+        };
+
+        WitnessDump {
+            inputs: entries(&cs.get_inputs()[1..]),
+            aux: entries(cs.get_aux()),
+        }
+    }
+
+    /// Synthesizes `circuit` with the values of the dump in place of its own
+    /// assignments, which it does not need to have, and checks every
+    /// constraint. Variables are matched by path.
+    pub fn replay<C: Circuit<E>>(&self, circuit: C) -> Result<Replay<E>, SynthesisError> {
+        let mut cs = ReplayCS {
+            cs: TestConstraintSystem::new(),
+            namespace: vec![],
+            values: self
+                .inputs
+                .iter()
+                .chain(&self.aux)
+                .map(|(path, value)| (path.as_str(), *value))
+                .collect(),
+            missing: vec![],
+        };
+        circuit.synthesize(&mut cs)?;
+
+        Ok(Replay {
+            missing: cs.missing,
+            unsatisfied: cs.cs.unsatisfied_constraints(),
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+
+        let lines = self
+            .inputs
+            .iter()
+            .map(|entry| ("input", entry))
+            .chain(self.aux.iter().map(|entry| ("aux", entry)));
+        for (kind, (path, value)) in lines {
+            if path.contains('\n') {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("path {:?} contains a newline", path),
+                ));
+            }
+
+            let mut repr = vec![];
+            value.into_repr().write_be(&mut repr)?;
+            let hex = repr
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+            writeln!(writer, "{}\t{}\t{}", kind, hex, path)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut lines = reader.lines();
+        match lines.next() {
+            Some(header) if header? == HEADER => {}
+            _ => return Err(invalid("not a witness dump".into())),
+        }
+
+        let mut dump = WitnessDump {
+            inputs: vec![],
+            aux: vec![],
+        };
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let number = i + 2;
+
+            let mut fields = line.splitn(3, '\t');
+            let (kind, hex, path) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(kind), Some(hex), Some(path)) => (kind, hex, path),
+                _ => return Err(invalid(format!("line {} is malformed", number))),
+            };
+            let value = decode_value::<E>(hex)
+                .ok_or_else(|| invalid(format!("line {} has an invalid value", number)))?;
+
+            match kind {
+                "input" if dump.aux.is_empty() => dump.inputs.push((path.into(), value)),
+                "aux" => dump.aux.push((path.into(), value)),
+                _ => return Err(invalid(format!("line {} is malformed", number))),
+            }
+        }
+
+        Ok(dump)
+    }
+}
+
+fn decode_value<E: Engine>(hex: &str) -> Option<E::Fr> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    if bytes.len() != repr.as_ref().len() * 8 {
+        return None;
+    }
+    repr.read_be(&bytes[..]).ok()?;
+
+    E::Fr::from_repr(repr).ok()
+}
+
+// Synthesizes a circuit into a `TestConstraintSystem`, taking the values of
+// the variables from a dump instead of the circuit.
+struct ReplayCS<'a, E: Engine> {
+    cs: TestConstraintSystem<E>,
+    namespace: Vec<String>,
+    values: HashMap<&'a str, E::Fr>,
+    missing: Vec<String>,
+}
+
+impl<'a, E: Engine> ReplayCS<'a, E> {
+    fn value(&mut self, name: &str) -> E::Fr {
+        let path = compute_path(&self.namespace, name);
+        match self.values.get(path.as_str()) {
+            Some(value) => *value,
+            None => {
+                self.missing.push(path);
+                E::Fr::zero()
+            }
+        }
+    }
+}
+
+impl<'a, E: Engine> ConstraintSystem<E> for ReplayCS<'a, E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let name = annotation().into();
+        let value = self.value(&name);
+        self.cs.alloc(|| name, || Ok(value))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let name = annotation().into();
+        let value = self.value(&name);
+        self.cs.alloc_input(|| name, || Ok(value))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+    {
+        self.cs.enforce(annotation, a, b, c);
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name = name_fn().into();
+        self.namespace.push(name.clone());
+        self.cs.push_namespace(|| name);
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespace.pop();
+        self.cs.pop_namespace();
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+
+    // Proves knowledge of `x` with `x^3 + x + 5 = out`.
+    struct CubicCircuit {
+        x: Option<Fr>,
+    }
+
+    impl Circuit<Bls12> for CubicCircuit {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let x_val = self.x;
+            let x2_val = x_val.map(|mut x| {
+                x.square();
+                x
+            });
+            let out_val = x2_val.map(|mut out| {
+                out.mul_assign(&x_val.unwrap());
+                out.add_assign(&x_val.unwrap());
+                out.add_assign(&Fr::from_str("5").unwrap());
+                out
+            });
+
+            let x = cs.alloc(|| "x", || x_val.ok_or(SynthesisError::AssignmentMissing))?;
+            let out = cs.alloc_input(
+                || "out",
+                || out_val.ok_or(SynthesisError::AssignmentMissing),
+            )?;
+
+            let mut cs = cs.namespace(|| "cube");
+            let x2 = cs.alloc(|| "x2", || x2_val.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "square", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+            cs.enforce(
+                || "sum",
+                |lc| lc + x2 + CS::one(),
+                |lc| lc + x,
+                |lc| lc + out - (Fr::from_str("5").unwrap(), CS::one()),
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_dump_roundtrip() {
+        let dump = WitnessDump::<Bls12>::record(CubicCircuit {
+            x: Some(Fr::from_str("3").unwrap()),
+        })
+        .unwrap();
+        assert_eq!(
+            dump.inputs,
+            vec![("out".to_string(), Fr::from_str("35").unwrap())]
+        );
+        assert_eq!(dump.aux.len(), 2);
+        assert_eq!(dump.aux[1].0, "cube/x2");
+
+        let mut bytes = vec![];
+        dump.write(&mut bytes).unwrap();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.starts_with("crusty3_zk witness v1\ninput\t"));
+        assert!(text.ends_with("0000000000000009\tcube/x2\n"));
+        assert_eq!(WitnessDump::read(&bytes[..]).unwrap(), dump);
+
+        assert!(WitnessDump::<Bls12>::read(&bytes[22..]).is_err());
+        let truncated = text.replace("\tcube/x2", "");
+        assert!(WitnessDump::<Bls12>::read(truncated.as_bytes()).is_err());
+        let non_canonical = format!("{}\naux\t{}\tx\n", HEADER, "ff".repeat(32));
+        assert!(WitnessDump::<Bls12>::read(non_canonical.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_replay() {
+        let dump = WitnessDump::<Bls12>::record(CubicCircuit {
+            x: Some(Fr::from_str("3").unwrap()),
+        })
+        .unwrap();
+
+        // The circuit being replayed needs no witness.
+        let replay = dump.replay(CubicCircuit { x: None }).unwrap();
+        assert!(replay.is_satisfied());
+
+        let mut wrong = dump.clone();
+        wrong.aux[1].1 = Fr::from_str("10").unwrap();
+        let replay = wrong.replay(CubicCircuit { x: None }).unwrap();
+        assert!(replay.missing.is_empty());
+        assert_eq!(replay.unsatisfied.len(), 2);
+        assert_eq!(replay.unsatisfied[0].path, "cube/square");
+        assert!(replay.unsatisfied[0]
+            .variables
+            .contains(&("cube/x2".to_string(), Fr::from_str("10").unwrap())));
+
+        let mut renamed = dump;
+        renamed.aux[0].0 = "y".into();
+        let replay = renamed.replay(CubicCircuit { x: None }).unwrap();
+        assert_eq!(replay.missing, vec!["x".to_string()]);
+        assert!(!replay.is_satisfied());
+    }
+}
@@ -0,0 +1,418 @@
+//! Public inputs by name.
+//!
+//! A circuit names its public inputs through the annotations of
+//! `alloc_input`, so an input is identified by its namespace path.
+//! `InputLayout::from_circuit` records the paths in allocation order, which
+//! is the order the verifier expects the inputs in. A verifier fills an
+//! `InputMap` by name and `InputLayout::assign` puts the values in that
+//! order, failing on missing and unknown names instead of silently
+//! verifying against shuffled inputs.
+//!
+//! The layout can be written out, so that verifiers do not need the circuit.
+//! The file starts with a `crusty3_zk inputs v1` line, followed by the path
+//! of each input on a line of its own.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufRead, Write};
+
+use super::test_cs::compute_path;
+use crate::bls::Engine;
+use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+const HEADER: &str = "crusty3_zk inputs v1";
+
+/// Why a layout or input map could not be built or applied.
+#[derive(thiserror::Error, Debug)]
+pub enum InputError {
+    #[error("public input {0:?} is not supplied")]
+    Missing(String),
+    #[error("public input {0:?} is not an input of the circuit")]
+    Unknown(String),
+    #[error("public input {0:?} is allocated twice")]
+    Duplicate(String),
+    #[error(transparent)]
+    Synthesis(#[from] SynthesisError),
+}
+
+/// The paths of the public inputs of a circuit in allocation order, without
+/// the constant `ONE` input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputLayout {
+    names: Vec<String>,
+}
+
+impl InputLayout {
+    /// Synthesizes `circuit` without its witness to find its inputs.
+    pub fn from_circuit<E: Engine, C: Circuit<E>>(circuit: C) -> Result<Self, InputError> {
+        let inputs = record(circuit, false)?;
+
+        Ok(InputLayout {
+            names: inputs.into_iter().map(|(name, _)| name).collect(),
+        })
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The position of the input `name` among the verifier's inputs.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// The values of `inputs` in the order of the layout, as passed to the
+    /// verifier.
+    pub fn assign<E: Engine>(&self, inputs: &InputMap<E>) -> Result<Vec<E::Fr>, InputError> {
+        let values = self
+            .names
+            .iter()
+            .map(|name| {
+                inputs
+                    .get(name)
+                    .ok_or_else(|| InputError::Missing(name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let names = self.names.iter().collect::<HashSet<_>>();
+        if let Some(name) = inputs.values.keys().find(|name| !names.contains(name)) {
+            return Err(InputError::Unknown(name.clone()));
+        }
+
+        Ok(values)
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        for name in &self.names {
+            if name.contains('\n') {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("path {:?} contains a newline", name),
+                ));
+            }
+            writeln!(writer, "{}", name)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut lines = reader.lines();
+        match lines.next() {
+            Some(header) if header? == HEADER => {}
+            _ => return Err(invalid("not an input layout".into())),
+        }
+
+        let names = lines.collect::<io::Result<Vec<_>>>()?;
+        let mut seen = HashSet::new();
+        if let Some(name) = names.iter().find(|name| !seen.insert(name.as_str())) {
+            return Err(invalid(format!("input {:?} is listed twice", name)));
+        }
+
+        Ok(InputLayout { names })
+    }
+}
+
+/// Values of public inputs by path.
+pub struct InputMap<E: Engine> {
+    values: BTreeMap<String, E::Fr>,
+}
+
+impl<E: Engine> Clone for InputMap<E> {
+    fn clone(&self) -> Self {
+        InputMap {
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<E: Engine> Default for InputMap<E> {
+    fn default() -> Self {
+        InputMap {
+            values: BTreeMap::new(),
+        }
+    }
+}
+
+impl<E: Engine> InputMap<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synthesizes `circuit` with its witness and collects the values of its
+    /// inputs.
+    pub fn from_circuit<C: Circuit<E>>(circuit: C) -> Result<Self, InputError> {
+        let mut map = InputMap::new();
+        for (name, value) in record(circuit, true)? {
+            map.insert(name, value.ok_or(SynthesisError::AssignmentMissing)?);
+        }
+
+        Ok(map)
+    }
+
+    /// Sets the input `name`, returning its previous value.
+    pub fn insert<N: Into<String>>(&mut self, name: N, value: E::Fr) -> Option<E::Fr> {
+        self.values.insert(name.into(), value)
+    }
+
+    pub fn get(&self, name: &str) -> Option<E::Fr> {
+        self.values.get(name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+// The paths of the inputs of `circuit` and, if `values` is set, their values.
+fn record<E: Engine, C: Circuit<E>>(
+    circuit: C,
+    values: bool,
+) -> Result<Vec<(String, Option<E::Fr>)>, InputError> {
+    let mut cs = InputRecorder {
+        namespace: vec![],
+        values,
+        num_aux: 0,
+        inputs: vec![],
+    };
+    circuit.synthesize(&mut cs)?;
+
+    let mut seen = HashSet::new();
+    if let Some((name, _)) = cs
+        .inputs
+        .iter()
+        .find(|(name, _)| !seen.insert(name.as_str()))
+    {
+        return Err(InputError::Duplicate(name.clone()));
+    }
+
+    Ok(cs.inputs)
+}
+
+struct InputRecorder<E: Engine> {
+    namespace: Vec<String>,
+    values: bool,
+    num_aux: usize,
+    inputs: Vec<(String, Option<E::Fr>)>,
+}
+
+impl<E: Engine> ConstraintSystem<E> for InputRecorder<E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.num_aux += 1;
+
+        Ok(Variable::new_unchecked(Index::Aux(self.num_aux - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = if self.values { Some(f()?) } else { None };
+        let path = compute_path(&self.namespace, &annotation().into());
+        self.inputs.push((path, value));
+
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len())))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _: LA, _: LB, _: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+    {
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.namespace.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        self.namespace.pop();
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::{Field, PrimeField};
+
+    fn fr(n: &str) -> Fr {
+        Fr::from_str(n).unwrap()
+    }
+
+    // Public `b`, `sum/a` and `sum/c` with `a + b = c`.
+    struct Sum {
+        a: Option<Fr>,
+        b: Option<Fr>,
+    }
+
+    impl Circuit<Bls12> for Sum {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let b = cs.alloc_input(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+
+            let mut cs = cs.namespace(|| "sum");
+            let a = cs.alloc_input(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+            let c = cs.alloc_input(
+                || "c",
+                || {
+                    let mut c = self.a.ok_or(SynthesisError::AssignmentMissing)?;
+                    c.add_assign(&self.b.ok_or(SynthesisError::AssignmentMissing)?);
+                    Ok(c)
+                },
+            )?;
+            cs.enforce(
+                || "a + b = c",
+                |lc| lc + a + b,
+                |lc| lc + CS::one(),
+                |lc| lc + c,
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = InputLayout::from_circuit(Sum { a: None, b: None }).unwrap();
+        assert_eq!(layout.names(), &["b", "sum/a", "sum/c"]);
+        assert_eq!(layout.position("sum/c"), Some(2));
+
+        let mut inputs = InputMap::<Bls12>::new();
+        inputs.insert("sum/c", fr("5"));
+        inputs.insert("b", fr("3"));
+        assert!(matches!(
+            layout.assign(&inputs),
+            Err(InputError::Missing(name)) if name == "sum/a"
+        ));
+
+        inputs.insert("sum/a", fr("2"));
+        let values = layout.assign(&inputs).unwrap();
+        assert_eq!(values, vec![fr("3"), fr("2"), fr("5")]);
+
+        let mut cs = TestConstraintSystem::new();
+        Sum {
+            a: Some(fr("2")),
+            b: Some(fr("3")),
+        }
+        .synthesize(&mut cs)
+        .unwrap();
+        assert!(cs.is_satisfied());
+        assert!(cs.verify(&values));
+
+        inputs.insert("a", fr("2"));
+        assert!(matches!(
+            layout.assign(&inputs),
+            Err(InputError::Unknown(name)) if name == "a"
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_input() {
+        struct Twice;
+
+        impl Circuit<Bls12> for Twice {
+            fn synthesize<CS: ConstraintSystem<Bls12>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                for _ in 0..2 {
+                    cs.namespace(|| "x").alloc_input(|| "y", || Ok(Fr::one()))?;
+                }
+                Ok(())
+            }
+        }
+
+        assert!(matches!(
+            InputLayout::from_circuit(Twice),
+            Err(InputError::Duplicate(name)) if name == "x/y"
+        ));
+    }
+
+    #[test]
+    fn test_write_read() {
+        let layout = InputLayout::from_circuit(Sum { a: None, b: None }).unwrap();
+
+        let mut bytes = vec![];
+        layout.write(&mut bytes).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "crusty3_zk inputs v1\nb\nsum/a\nsum/c\n"
+        );
+        assert_eq!(InputLayout::read(&bytes[..]).unwrap(), layout);
+
+        assert!(InputLayout::read(&b"crusty3_zk inputs v1\nb\nb\n"[..]).is_err());
+        assert!(InputLayout::read(&b"b\nsum/a\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_verify_by_name() {
+        let mut rng = test_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(Sum { a: None, b: None }, &mut rng).unwrap();
+        let circuit = || Sum {
+            a: Some(fr("2")),
+            b: Some(fr("3")),
+        };
+        let proof = create_random_proof(circuit(), &params, &mut rng).unwrap();
+
+        // The prover sends the inputs by name, in whatever order.
+        let sent = InputMap::from_circuit(circuit()).unwrap();
+        let mut received = InputMap::<Bls12>::new();
+        for name in &["sum/c", "b", "sum/a"] {
+            received.insert(*name, sent.get(name).unwrap());
+        }
+
+        let layout = InputLayout::from_circuit(Sum { a: None, b: None }).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        let inputs = layout.assign(&received).unwrap();
+        assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+
+        received.insert("sum/a", fr("3"));
+        let inputs = layout.assign(&received).unwrap();
+        assert!(!verify_proof(&pvk, &proof, &inputs).unwrap());
+    }
+}
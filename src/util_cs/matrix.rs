@@ -0,0 +1,392 @@
+//! The A, B and C matrices of a rank-1 constraint system in compressed
+//! sparse row (CSR) form, for analysis and other backends.
+//!
+//! Row `i` of each matrix is constraint `i`. Column `j` is input `j` for `j`
+//! below `num_inputs`, column 0 being the constant one, and aux variable
+//! `j - num_inputs` above. A witness vector `z` in the same order satisfies
+//! the system if `(A z) * (B z) = C z` entry by entry. Terms of the same
+//! variable are summed, zero terms dropped and columns sorted within rows.
+//!
+//! `R1csMatrices::write` uses the following layout, all integers
+//! little-endian as most array libraries expect:
+//!
+//! ```text
+//! magic        4 bytes  "R1MX"
+//! version      u32      1
+//! field size   u32      bytes per value
+//! num_inputs   u64      including the constant one
+//! num_aux      u64
+//! rows         u64      number of constraints
+//! then for each of A, B and C:
+//!   nnz        u64      number of non-zero entries
+//!   offsets    (rows + 1) x u64, row i spans entries offsets[i]..offsets[i + 1]
+//!   columns    nnz x u64
+//!   values     nnz x field size, little-endian canonical representation
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField, PrimeFieldRepr};
+
+use super::r1cs::{R1cs, R1csRecorder, SparseLc};
+use crate::bls::Engine;
+use crate::{invalid_data, Circuit, Index, SynthesisError};
+
+const MAGIC: [u8; 4] = *b"R1MX";
+const VERSION: u32 = 1;
+
+/// A sparse matrix over the scalar field in compressed sparse row form.
+pub struct CsrMatrix<E: Engine> {
+    pub num_rows: usize,
+    pub num_cols: usize,
+    /// Row `i` spans `row_offsets[i]..row_offsets[i + 1]` of `columns` and
+    /// `values`.
+    pub row_offsets: Vec<usize>,
+    pub columns: Vec<usize>,
+    pub values: Vec<E::Fr>,
+}
+
+impl<E: Engine> Clone for CsrMatrix<E> {
+    fn clone(&self) -> Self {
+        CsrMatrix {
+            num_rows: self.num_rows,
+            num_cols: self.num_cols,
+            row_offsets: self.row_offsets.clone(),
+            columns: self.columns.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for CsrMatrix<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_rows == other.num_rows
+            && self.num_cols == other.num_cols
+            && self.row_offsets == other.row_offsets
+            && self.columns == other.columns
+            && self.values == other.values
+    }
+}
+
+impl<E: Engine> CsrMatrix<E> {
+    /// Number of non-zero entries.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The non-zero entries of row `i` as column and value.
+    pub fn row(&self, i: usize) -> impl Iterator<Item = (usize, &E::Fr)> + '_ {
+        let range = self.row_offsets[i]..self.row_offsets[i + 1];
+        self.columns[range.clone()]
+            .iter()
+            .cloned()
+            .zip(&self.values[range])
+    }
+
+    /// The product of the matrix with `z`, which has an entry per column.
+    pub fn mul_vec(&self, z: &[E::Fr]) -> Vec<E::Fr> {
+        assert_eq!(z.len(), self.num_cols, "wrong vector length");
+
+        (0..self.num_rows)
+            .map(|i| {
+                let mut acc = E::Fr::zero();
+                for (j, value) in self.row(i) {
+                    let mut term = z[j];
+                    term.mul_assign(value);
+                    acc.add_assign(&term);
+                }
+                acc
+            })
+            .collect()
+    }
+
+    fn from_rows<'a, I>(rows: I, num_rows: usize, num_inputs: usize, num_cols: usize) -> Self
+    where
+        I: Iterator<Item = &'a SparseLc<E>>,
+    {
+        let mut matrix = CsrMatrix {
+            num_rows,
+            num_cols,
+            row_offsets: Vec::with_capacity(num_rows + 1),
+            columns: vec![],
+            values: vec![],
+        };
+        matrix.row_offsets.push(0);
+
+        for lc in rows {
+            let mut terms = BTreeMap::new();
+            for (index, coeff) in lc {
+                let column = match *index {
+                    Index::Input(i) => i,
+                    Index::Aux(i) => num_inputs + i,
+                };
+                terms
+                    .entry(column)
+                    .or_insert_with(E::Fr::zero)
+                    .add_assign(coeff);
+            }
+            for (column, value) in terms {
+                if !value.is_zero() {
+                    matrix.columns.push(column);
+                    matrix.values.push(value);
+                }
+            }
+            matrix.row_offsets.push(matrix.columns.len());
+        }
+
+        matrix
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u64::<LittleEndian>(self.nnz() as u64)?;
+        for offset in &self.row_offsets {
+            writer.write_u64::<LittleEndian>(*offset as u64)?;
+        }
+        for column in &self.columns {
+            writer.write_u64::<LittleEndian>(*column as u64)?;
+        }
+        for value in &self.values {
+            value.into_repr().write_le(&mut *writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn read<R: Read>(reader: &mut R, num_rows: usize, num_cols: usize) -> io::Result<Self> {
+        let nnz = reader.read_u64::<LittleEndian>()? as usize;
+
+        let row_offsets = (0..=num_rows)
+            .map(|_| reader.read_u64::<LittleEndian>().map(|o| o as usize))
+            .collect::<io::Result<Vec<_>>>()?;
+        if row_offsets[0] != 0
+            || row_offsets[num_rows] != nnz
+            || row_offsets.windows(2).any(|w| w[0] > w[1])
+        {
+            return Err(invalid_data("invalid row offsets"));
+        }
+
+        let mut columns = Vec::with_capacity(nnz.min(1 << 20));
+        for _ in 0..nnz {
+            let column = reader.read_u64::<LittleEndian>()? as usize;
+            if column >= num_cols {
+                return Err(invalid_data("column out of range"));
+            }
+            columns.push(column);
+        }
+
+        let mut values = Vec::with_capacity(nnz.min(1 << 20));
+        for _ in 0..nnz {
+            let mut repr = <E::Fr as PrimeField>::Repr::default();
+            repr.read_le(&mut *reader)?;
+            values.push(E::Fr::from_repr(repr).map_err(|_| invalid_data("invalid value"))?);
+        }
+
+        Ok(CsrMatrix {
+            num_rows,
+            num_cols,
+            row_offsets,
+            columns,
+            values,
+        })
+    }
+}
+
+/// The constraint matrices of a rank-1 constraint system.
+pub struct R1csMatrices<E: Engine> {
+    /// Number of public inputs, including the constant one.
+    pub num_inputs: usize,
+    pub num_aux: usize,
+    pub a: CsrMatrix<E>,
+    pub b: CsrMatrix<E>,
+    pub c: CsrMatrix<E>,
+}
+
+impl<E: Engine> PartialEq for R1csMatrices<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_inputs == other.num_inputs
+            && self.num_aux == other.num_aux
+            && self.a == other.a
+            && self.b == other.b
+            && self.c == other.c
+    }
+}
+
+impl<E: Engine> R1csMatrices<E> {
+    pub fn from_r1cs(r1cs: &R1cs<E>) -> Self {
+        let rows = r1cs.constraints.len();
+        let cols = r1cs.num_inputs + r1cs.num_aux;
+        let constraints = &r1cs.constraints;
+        let inputs = r1cs.num_inputs;
+
+        R1csMatrices {
+            num_inputs: r1cs.num_inputs,
+            num_aux: r1cs.num_aux,
+            a: CsrMatrix::from_rows(constraints.iter().map(|c| &c.a), rows, inputs, cols),
+            b: CsrMatrix::from_rows(constraints.iter().map(|c| &c.b), rows, inputs, cols),
+            c: CsrMatrix::from_rows(constraints.iter().map(|c| &c.c), rows, inputs, cols),
+        }
+    }
+
+    /// Synthesizes `circuit`, which needs no witness, and returns its
+    /// matrices.
+    pub fn from_circuit<C: Circuit<E>>(circuit: C) -> Result<Self, SynthesisError> {
+        let (r1cs, _) = R1csRecorder::record(circuit)?;
+        Ok(Self::from_r1cs(&r1cs))
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.a.num_rows
+    }
+
+    /// Whether `z`, the constant one followed by the inputs and the aux
+    /// variables, satisfies every constraint.
+    pub fn is_satisfied(&self, z: &[E::Fr]) -> bool {
+        if z.len() != self.num_inputs + self.num_aux {
+            return false;
+        }
+
+        let a = self.a.mul_vec(z);
+        let b = self.b.mul_vec(z);
+        let c = self.c.mul_vec(z);
+        a.into_iter().zip(b).zip(c).all(|((mut a, b), c)| {
+            a.mul_assign(&b);
+            a == c
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let field_size = <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+
+        writer.write_all(&MAGIC)?;
+        writer.write_u32::<LittleEndian>(VERSION)?;
+        writer.write_u32::<LittleEndian>(field_size as u32)?;
+        writer.write_u64::<LittleEndian>(self.num_inputs as u64)?;
+        writer.write_u64::<LittleEndian>(self.num_aux as u64)?;
+        writer.write_u64::<LittleEndian>(self.num_constraints() as u64)?;
+
+        self.a.write(&mut writer)?;
+        self.b.write(&mut writer)?;
+        self.c.write(&mut writer)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("not an R1CS matrices file"));
+        }
+        if reader.read_u32::<LittleEndian>()? != VERSION {
+            return Err(invalid_data("unsupported R1CS matrices version"));
+        }
+        let field_size = <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+        if reader.read_u32::<LittleEndian>()? as usize != field_size {
+            return Err(invalid_data("field size mismatch"));
+        }
+
+        let num_inputs = reader.read_u64::<LittleEndian>()? as usize;
+        let num_aux = reader.read_u64::<LittleEndian>()? as usize;
+        let rows = reader.read_u64::<LittleEndian>()? as usize;
+        let cols = num_inputs
+            .checked_add(num_aux)
+            .ok_or_else(|| invalid_data("too many variables"))?;
+
+        Ok(R1csMatrices {
+            num_inputs,
+            num_aux,
+            a: CsrMatrix::read(&mut reader, rows, cols)?,
+            b: CsrMatrix::read(&mut reader, rows, cols)?,
+            c: CsrMatrix::read(&mut reader, rows, cols)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::r1cs::Constraint;
+
+    fn fr(n: &str) -> Fr {
+        Fr::from_str(n).unwrap()
+    }
+
+    // x^3 + x + 5 = out, with x, x^2 as aux and a duplicated term.
+    fn cube() -> R1cs<Bls12> {
+        let x = Index::Aux(0);
+        let x2 = Index::Aux(1);
+        let out = Index::Input(1);
+        let one = Index::Input(0);
+        let mut minus_five = fr("5");
+        minus_five.negate();
+        let mut minus_one = fr("1");
+        minus_one.negate();
+
+        R1cs {
+            num_inputs: 2,
+            num_aux: 2,
+            constraints: vec![
+                Constraint {
+                    a: vec![(x, fr("1"))],
+                    b: vec![(x, fr("1"))],
+                    c: vec![(x2, fr("1"))],
+                },
+                Constraint {
+                    a: vec![(x2, fr("1")), (one, fr("1"))],
+                    b: vec![(x, fr("2")), (x, minus_one), (x2, fr("0"))],
+                    c: vec![(out, fr("1")), (one, minus_five)],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_matrices() {
+        let matrices = R1csMatrices::from_r1cs(&cube());
+        assert_eq!(matrices.num_constraints(), 2);
+        assert_eq!(matrices.a.num_cols, 4);
+
+        assert_eq!(matrices.a.row_offsets, vec![0, 1, 3]);
+        assert_eq!(matrices.a.columns, vec![2, 0, 3]);
+        // Duplicate and zero terms are merged away.
+        assert_eq!(matrices.b.row_offsets, vec![0, 1, 2]);
+        assert_eq!(matrices.b.row(1).collect::<Vec<_>>(), vec![(2, &Fr::one())]);
+        assert_eq!(matrices.c.columns, vec![3, 0, 1]);
+        assert_eq!(matrices.a.nnz() + matrices.b.nnz() + matrices.c.nnz(), 8);
+
+        let z = vec![fr("1"), fr("35"), fr("3"), fr("9")];
+        assert!(matrices.is_satisfied(&z));
+        assert!(!matrices.is_satisfied(&[fr("1"), fr("36"), fr("3"), fr("9")]));
+        assert!(!matrices.is_satisfied(&z[..3]));
+    }
+
+    #[test]
+    fn test_matrices_roundtrip() {
+        let matrices = R1csMatrices::from_r1cs(&cube());
+
+        let mut bytes = vec![];
+        matrices.write(&mut bytes).unwrap();
+        assert_eq!(&bytes[..4], b"R1MX");
+        assert_eq!(
+            bytes.len(),
+            4 + 4 + 4 + 3 * 8 + 3 * (8 + 3 * 8) + 8 * 8 + 8 * 32
+        );
+
+        let read = R1csMatrices::<Bls12>::read(&bytes[..]).unwrap();
+        assert!(read == matrices);
+
+        assert!(R1csMatrices::<Bls12>::read(&bytes[..bytes.len() - 1]).is_err());
+        let mut corrupted = bytes.clone();
+        corrupted[4 + 4 + 4 + 3 * 8 + 8 + 3 * 8] = 0xff;
+        assert!(R1csMatrices::<Bls12>::read(&corrupted[..]).is_err());
+
+        // Variable counts whose sum overflows.
+        let mut overflowing = bytes;
+        for byte in &mut overflowing[12..28] {
+            *byte = 0xff;
+        }
+        assert!(R1csMatrices::<Bls12>::read(&overflowing[..]).is_err());
+    }
+}
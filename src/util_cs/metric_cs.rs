@@ -2,7 +2,8 @@ use crate::bls::Engine;
 use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 use ff::{Field, PrimeField, ScalarEngine};
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 
 #[derive(Clone, Copy)]
 struct OrderedVariable(Variable);
@@ -40,8 +41,186 @@ impl Ord for OrderedVariable {
     }
 }
 
+/// Sizes of a namespace and all the namespaces nested in it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceStats {
+    pub constraints: usize,
+    pub inputs: usize,
+    pub aux: usize,
+    /// Number of non-zero terms in the A, B and C linear combinations.
+    pub a: usize,
+    pub b: usize,
+    pub c: usize,
+    /// Number of terms whose coefficients cancelled out, which
+    /// `LinearCombination::simplify` removes and which are not counted in
+    /// `a`, `b` and `c`.
+    pub cancelled: usize,
+}
+
+impl NamespaceStats {
+    fn density(terms: usize, constraints: usize) -> f64 {
+        if constraints == 0 {
+            0.0
+        } else {
+            terms as f64 / constraints as f64
+        }
+    }
+
+    /// Average number of terms in the A linear combinations.
+    pub fn a_density(&self) -> f64 {
+        Self::density(self.a, self.constraints)
+    }
+
+    /// Average number of terms in the B linear combinations.
+    pub fn b_density(&self) -> f64 {
+        Self::density(self.b, self.constraints)
+    }
+
+    /// Average number of terms in the C linear combinations.
+    pub fn c_density(&self) -> f64 {
+        Self::density(self.c, self.constraints)
+    }
+}
+
+/// Constraint statistics of a circuit, as collected by a `MetricCS`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CircuitStats {
+    /// Totals for the whole circuit, including the `ONE` input.
+    pub total: NamespaceStats,
+    /// Statistics per namespace path, each including its sub-namespaces.
+    pub namespaces: BTreeMap<String, NamespaceStats>,
+}
+
+impl CircuitStats {
+    /// Size of the evaluation domain a proof of this circuit needs.
+    pub fn domain_size(&self) -> usize {
+        (self.total.constraints + self.total.inputs).next_power_of_two()
+    }
+
+    /// The namespaces ordered by decreasing number of constraints, then by
+    /// decreasing number of aux variables and by path.
+    pub fn by_cost(&self) -> Vec<(&str, &NamespaceStats)> {
+        let mut namespaces = self
+            .namespaces
+            .iter()
+            .map(|(path, stats)| (path.as_str(), stats))
+            .collect::<Vec<_>>();
+        namespaces.sort_by(|(path1, stats1), (path2, stats2)| {
+            stats2
+                .constraints
+                .cmp(&stats1.constraints)
+                .then(stats2.aux.cmp(&stats1.aux))
+                .then(path1.cmp(path2))
+        });
+
+        namespaces
+    }
+
+    /// A table of the namespaces at most `max_depth` levels deep, most
+    /// costly first, with their share of all constraints and the number of
+    /// constraints enforced directly in them rather than in a sub-namespace.
+    pub fn cost_report(&self, max_depth: usize) -> String {
+        // Constraints in the direct sub-namespaces of each namespace, with
+        // the root as "".
+        let mut nested = BTreeMap::<&str, usize>::new();
+        for (path, stats) in &self.namespaces {
+            let parent = path.rfind('/').map_or("", |i| &path[..i]);
+            *nested.entry(parent).or_default() += stats.constraints;
+        }
+        let own = |path: &str, stats: &NamespaceStats| {
+            stats.constraints - nested.get(path).copied().unwrap_or_default()
+        };
+        let share = |stats: &NamespaceStats| {
+            if self.total.constraints == 0 {
+                0.0
+            } else {
+                100.0 * stats.constraints as f64 / self.total.constraints as f64
+            }
+        };
+
+        let mut report = format!(
+            "{:<40} {:>11} {:>7} {:>11} {:>10} {:>8}\n",
+            "namespace", "constraints", "share", "own", "aux", "inputs"
+        );
+        let rows = Some(("<total>", &self.total)).into_iter().chain(
+            self.by_cost()
+                .into_iter()
+                .filter(|(path, _)| path.split('/').count() <= max_depth),
+        );
+        for (path, stats) in rows {
+            let key = if path == "<total>" { "" } else { path };
+            report.push_str(&format!(
+                "{:<40} {:>11} {:>6.1}% {:>11} {:>10} {:>8}\n",
+                path,
+                stats.constraints,
+                share(stats),
+                own(key, stats),
+                stats.aux,
+                stats.inputs
+            ));
+        }
+
+        report
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<40} {:>10} {:>8} {:>10} {:>8} {:>8} {:>8} {:>10}",
+            "namespace", "constraints", "inputs", "aux", "A", "B", "C", "cancelled"
+        )?;
+        let rows = Some(("<total>", &self.total)).into_iter().chain(
+            self.namespaces
+                .iter()
+                .map(|(path, stats)| (path.as_str(), stats)),
+        );
+        for (path, stats) in rows {
+            writeln!(
+                f,
+                "{:<40} {:>10} {:>8} {:>10} {:>8.2} {:>8.2} {:>8.2} {:>10}",
+                path,
+                stats.constraints,
+                stats.inputs,
+                stats.aux,
+                stats.a_density(),
+                stats.b_density(),
+                stats.c_density(),
+                stats.cancelled
+            )?;
+        }
+        writeln!(f, "domain size: {}", self.domain_size())
+    }
+}
+
+/// The kind of object a path of the circuit structure names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StructureItem {
+    Namespace,
+    Input,
+    Aux,
+    Constraint,
+}
+
+impl StructureItem {
+    fn name(self) -> &'static str {
+        match self {
+            StructureItem::Namespace => "namespace",
+            StructureItem::Input => "input",
+            StructureItem::Aux => "aux",
+            StructureItem::Constraint => "constraint",
+        }
+    }
+}
+
+fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 pub struct MetricCS<E: Engine> {
     named_objects: HashMap<String, NamedObject>,
+    structure: Vec<(String, StructureItem)>,
     current_namespace: Vec<String>,
     #[allow(clippy::type_complexity)]
     constraints: Vec<(
@@ -90,6 +269,51 @@ impl<E: Engine> MetricCS<E> {
         self.inputs.len()
     }
 
+    pub fn num_aux(&self) -> usize {
+        self.aux.len()
+    }
+
+    /// Counts of constraints, variables and linear combination terms, in
+    /// total and per namespace.
+    pub fn stats(&self) -> CircuitStats {
+        let mut stats = CircuitStats::default();
+
+        fn update<F: Fn(&mut NamespaceStats)>(stats: &mut CircuitStats, path: &str, f: F) {
+            f(&mut stats.total);
+            let mut components = path.split('/').collect::<Vec<_>>();
+            components.pop();
+            for depth in 1..=components.len() {
+                let namespace = components[..depth].join("/");
+                f(stats.namespaces.entry(namespace).or_default());
+            }
+        }
+
+        for path in &self.inputs {
+            update(&mut stats, path, |s| s.inputs += 1);
+        }
+        for path in &self.aux {
+            update(&mut stats, path, |s| s.aux += 1);
+        }
+        for (a, b, c, path) in &self.constraints {
+            let cancelled = a.len() + b.len() + c.len();
+            let (a, b, c) = (
+                proc_lc::<E>(a).len(),
+                proc_lc::<E>(b).len(),
+                proc_lc::<E>(c).len(),
+            );
+            let cancelled = cancelled - a - b - c;
+            update(&mut stats, path, |s| {
+                s.constraints += 1;
+                s.a += a;
+                s.b += b;
+                s.c += c;
+                s.cancelled += cancelled;
+            });
+        }
+
+        stats
+    }
+
     pub fn pretty_print_list(&self) -> Vec<String> {
         let mut result = Vec::new();
 
@@ -178,6 +402,112 @@ impl<E: Engine> MetricCS<E> {
         s
     }
 
+    /// Full paths of the namespaces, variables and constraints, in the order
+    /// they were created.
+    pub fn structure(&self) -> &[(String, StructureItem)] {
+        &self.structure
+    }
+
+    /// The namespace tree of the circuit, one line per namespace, variable
+    /// and constraint, indented by depth. Constraints show their number of
+    /// terms in A, B and C. The output is deterministic, so that it can be
+    /// diffed between versions of a circuit.
+    pub fn print_structure(&self) -> String {
+        let mut s = String::new();
+        let mut constraints = self.constraints.iter();
+
+        for (path, item) in &self.structure {
+            let depth = path.matches('/').count();
+            let name = path.rsplit('/').next().unwrap_or_default();
+            s.push_str(&"  ".repeat(depth));
+            s.push_str(item.name());
+            s.push(' ');
+            s.push_str(name);
+
+            if *item == StructureItem::Constraint {
+                let (a, b, c, _) = constraints.next().expect("a constraint per path");
+                s.push_str(&format!(
+                    " ({}, {}, {})",
+                    proc_lc::<E>(a).len(),
+                    proc_lc::<E>(b).len(),
+                    proc_lc::<E>(c).len()
+                ));
+            }
+            s.push('\n');
+        }
+
+        s
+    }
+
+    /// The circuit as a Graphviz graph: namespaces are nested clusters, and
+    /// each variable has an edge to every constraint it appears in.
+    pub fn to_dot(&self) -> String {
+        let mut s = String::from("digraph circuit {\n");
+        let mut open: Vec<&str> = vec![];
+        let (mut inputs, mut aux, mut constraints, mut clusters) = (0, 0, 0, 0);
+
+        for (path, item) in &self.structure {
+            let parent = path.rfind('/').map_or("", |i| &path[..i]);
+            while open.last().map_or(false, |&ns| ns != parent) {
+                open.pop();
+                s.push_str(&"  ".repeat(open.len() + 1));
+                s.push_str("}\n");
+            }
+
+            let indent = "  ".repeat(open.len() + 1);
+            let label = escape_dot(path.rsplit('/').next().unwrap_or_default());
+            match item {
+                StructureItem::Namespace => {
+                    s.push_str(&format!(
+                        "{}subgraph cluster_{} {{\n{}  label=\"{}\";\n",
+                        indent, clusters, indent, label
+                    ));
+                    clusters += 1;
+                    open.push(path);
+                }
+                StructureItem::Input => {
+                    s.push_str(&format!(
+                        "{}I{} [label=\"{}\", shape=box];\n",
+                        indent, inputs, label
+                    ));
+                    inputs += 1;
+                }
+                StructureItem::Aux => {
+                    s.push_str(&format!("{}A{} [label=\"{}\"];\n", indent, aux, label));
+                    aux += 1;
+                }
+                StructureItem::Constraint => {
+                    s.push_str(&format!(
+                        "{}C{} [label=\"{}\", shape=diamond];\n",
+                        indent, constraints, label
+                    ));
+                    constraints += 1;
+                }
+            }
+        }
+        while open.pop().is_some() {
+            s.push_str(&"  ".repeat(open.len() + 1));
+            s.push_str("}\n");
+        }
+
+        for (i, (a, b, c, _)) in self.constraints.iter().enumerate() {
+            let variables = [a, b, c]
+                .iter()
+                .flat_map(|lc| proc_lc::<E>(lc).into_iter().map(|(var, _)| var))
+                .collect::<BTreeSet<_>>();
+            for var in variables {
+                let node = match var.0.get_unchecked() {
+                    Index::Input(j) => format!("I{}", j),
+                    Index::Aux(j) => format!("A{}", j),
+                };
+                s.push_str(&format!("  {} -> C{};\n", node, i));
+            }
+        }
+
+        s.push_str("}\n");
+        s
+    }
+
     fn set_named_obj(&mut self, path: String, to: NamedObject) {
         if self.named_objects.contains_key(&path) {
             panic!("tried to create object at existing path: {}", path);
@@ -193,6 +523,7 @@ impl<E: Engine> Default for MetricCS<E> {
         map.insert("ONE".into(), NamedObject::Var(MetricCS::<E>::one()));
         MetricCS {
             named_objects: map,
+            structure: vec![("ONE".into(), StructureItem::Input)],
             current_namespace: vec![],
             constraints: vec![],
             inputs: vec![String::from("ONE")],
@@ -211,6 +542,7 @@ impl<E: Engine> ConstraintSystem<E> for MetricCS<E> {
         AR: Into<String>,
     {
         let path = compute_path(&self.current_namespace, &annotation().into());
+        self.structure.push((path.clone(), StructureItem::Aux));
         self.aux.push(path);
 
         Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
@@ -223,6 +555,7 @@ impl<E: Engine> ConstraintSystem<E> for MetricCS<E> {
         AR: Into<String>,
     {
         let path = compute_path(&self.current_namespace, &annotation().into());
+        self.structure.push((path.clone(), StructureItem::Input));
         self.inputs.push(path);
 
         Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
@@ -239,6 +572,8 @@ impl<E: Engine> ConstraintSystem<E> for MetricCS<E> {
         let path = compute_path(&self.current_namespace, &annotation().into());
         let index = self.constraints.len();
         self.set_named_obj(path.clone(), NamedObject::Constraint(index));
+        self.structure
+            .push((path.clone(), StructureItem::Constraint));
 
         let a = a(LinearCombination::zero());
         let b = b(LinearCombination::zero());
@@ -254,7 +589,8 @@ impl<E: Engine> ConstraintSystem<E> for MetricCS<E> {
     {
         let name = name_fn().into();
         let path = compute_path(&self.current_namespace, &name);
-        self.set_named_obj(path, NamedObject::Namespace);
+        self.set_named_obj(path.clone(), NamedObject::Namespace);
+        self.structure.push((path, StructureItem::Namespace));
         self.current_namespace.push(name);
     }
 
@@ -286,3 +622,181 @@ fn compute_path(ns: &[String], this: &str) -> String {
 
     name
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::{Bls12, Fr};
+
+    #[test]
+    fn test_stats() {
+        let mut cs = MetricCS::<Bls12>::new();
+
+        let x = cs.alloc_input(|| "x", || Ok(Fr::one())).unwrap();
+        {
+            let mut cs = cs.namespace(|| "square");
+            let y = cs.alloc(|| "y", || Ok(Fr::one())).unwrap();
+            cs.enforce(|| "y = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+
+            let mut cs = cs.namespace(|| "sum");
+            let z = cs.alloc(|| "z", || Ok(Fr::one())).unwrap();
+            cs.enforce(
+                || "z = x + y",
+                |lc| lc + x + y,
+                |lc| lc + MetricCS::<Bls12>::one(),
+                |lc| lc + z,
+            );
+        }
+
+        let stats = cs.stats();
+        assert_eq!(cs.num_aux(), 2);
+        assert_eq!(
+            stats.total,
+            NamespaceStats {
+                constraints: 2,
+                inputs: 2,
+                aux: 2,
+                a: 3,
+                b: 2,
+                c: 2,
+                cancelled: 0,
+            }
+        );
+        assert_eq!(stats.namespaces.len(), 2);
+        assert_eq!(
+            stats.namespaces["square"],
+            NamespaceStats {
+                inputs: 0,
+                ..stats.total
+            }
+        );
+        assert_eq!(
+            stats.namespaces["square/sum"],
+            NamespaceStats {
+                constraints: 1,
+                inputs: 0,
+                aux: 1,
+                a: 2,
+                b: 1,
+                c: 1,
+                cancelled: 0,
+            }
+        );
+        assert_eq!(stats.namespaces["square/sum"].a_density(), 2.0);
+        assert_eq!(stats.domain_size(), 4);
+        assert!(stats.to_string().contains("square/sum"));
+    }
+
+    #[test]
+    fn test_stats_cancelled_terms() {
+        let mut cs = MetricCS::<Bls12>::new();
+
+        let x = cs.alloc(|| "x", || Ok(Fr::one())).unwrap();
+        let y = cs.alloc(|| "y", || Ok(Fr::one())).unwrap();
+        cs.enforce(
+            || "x = y",
+            |lc| lc + x + y - y,
+            |lc| lc + MetricCS::<Bls12>::one(),
+            |lc| lc + y - x + x,
+        );
+
+        let stats = cs.stats();
+        assert_eq!(stats.total.a, 1);
+        assert_eq!(stats.total.c, 1);
+        assert_eq!(stats.total.cancelled, 2);
+    }
+
+    #[test]
+    fn test_cost_report() {
+        let mut cs = MetricCS::<Bls12>::new();
+
+        let x = cs.alloc(|| "x", || Ok(Fr::one())).unwrap();
+        {
+            let mut cs = cs.namespace(|| "range");
+            cs.enforce(
+                || "boolean",
+                |lc| lc + x,
+                |lc| lc + MetricCS::<Bls12>::one() - x,
+                |lc| lc,
+            );
+        }
+        {
+            let mut cs = cs.namespace(|| "hash");
+            cs.enforce(|| "init", |lc| lc + x, |lc| lc + x, |lc| lc + x);
+            for i in 0..2 {
+                let mut cs = cs.namespace(|| format!("round{}", i));
+                let y = cs.alloc(|| "y", || Ok(Fr::one())).unwrap();
+                cs.enforce(|| "square", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+            }
+        }
+
+        let stats = cs.stats();
+        let order = stats
+            .by_cost()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["hash", "hash/round0", "hash/round1", "range"]);
+
+        let report = stats.cost_report(1);
+        let rows = report
+            .lines()
+            .skip(1)
+            .map(|row| row.split_whitespace().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["<total>", "4", "100.0%", "0", "3", "1"],
+                vec!["hash", "3", "75.0%", "1", "2", "0"],
+                vec!["range", "1", "25.0%", "1", "0", "0"],
+            ]
+        );
+        assert_eq!(stats.cost_report(2).lines().count(), 6);
+    }
+
+    #[test]
+    fn test_structure() {
+        let mut cs = MetricCS::<Bls12>::new();
+
+        let x = cs.alloc_input(|| "x", || Ok(Fr::one())).unwrap();
+        {
+            let mut cs = cs.namespace(|| "square");
+            let y = cs.alloc(|| "y", || Ok(Fr::one())).unwrap();
+            cs.enforce(|| "y = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+        }
+        let z = cs.alloc(|| "z \"quoted\"", || Ok(Fr::one())).unwrap();
+        cs.enforce(
+            || "z = x + 1",
+            |lc| lc + x + MetricCS::<Bls12>::one(),
+            |lc| lc + MetricCS::<Bls12>::one(),
+            |lc| lc + z,
+        );
+
+        assert_eq!(
+            cs.structure()[2],
+            ("square".into(), StructureItem::Namespace)
+        );
+        assert_eq!(
+            cs.print_structure(),
+            "input ONE\n\
+             input x\n\
+             namespace square\n  \
+               aux y\n  \
+               constraint y = x^2 (1, 1, 1)\n\
+             aux z \"quoted\"\n\
+             constraint z = x + 1 (2, 1, 1)\n"
+        );
+
+        let dot = cs.to_dot();
+        assert!(dot.starts_with("digraph circuit {\n"));
+        assert!(
+            dot.contains("  subgraph cluster_0 {\n    label=\"square\";\n    A0 [label=\"y\"];\n")
+        );
+        assert!(dot.contains("A1 [label=\"z \\\"quoted\\\"\"];"));
+        assert!(dot.contains("  I1 -> C0;\n"));
+        assert!(dot.contains("  A0 -> C0;\n"));
+        assert!(dot.contains("  I0 -> C1;\n  I1 -> C1;\n  A1 -> C1;\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -1,3 +1,11 @@
 pub mod bench_cs;
+pub mod circom;
+pub mod dump;
+pub mod input_map;
+pub mod matrix;
 pub mod metric_cs;
+pub mod optimize;
+pub mod r1cs;
 pub mod test_cs;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
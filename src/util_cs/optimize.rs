@@ -0,0 +1,302 @@
+//! An optimization pass over a recorded `R1cs`, run before parameter
+//! generation.
+//!
+//! `optimize` removes constraints that hold for every assignment, which are
+//! those with a zero C and a zero A or B, and constraints that repeat an
+//! earlier one, also with A and B swapped. It then removes the aux variables
+//! that no remaining constraint refers to, and renumbers the others.
+//! Inputs are kept, as the verifier supplies them.
+//!
+//! The returned `Optimization` lists what was removed and maps witnesses of
+//! the original system to the optimized one, which can then be proven with
+//! `R1csCircuit`.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use ff::{PrimeField, PrimeFieldRepr};
+
+use super::r1cs::{index_key, normalize, Constraint, R1cs, SparseLc, Witness};
+use crate::bls::Engine;
+use crate::Index;
+
+/// What `optimize` removed from a constraint system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Optimization {
+    /// Indices of the removed constraints in the original system.
+    pub removed_constraints: Vec<usize>,
+    /// Indices of the removed aux variables in the original system.
+    pub removed_aux: Vec<usize>,
+    num_constraints: usize,
+    aux_map: Vec<Option<usize>>,
+}
+
+impl Optimization {
+    /// The new index of aux variable `i` of the original system, `None` if
+    /// it was removed.
+    pub fn map_aux(&self, i: usize) -> Option<usize> {
+        self.aux_map[i]
+    }
+
+    /// The witness of the optimized system corresponding to `witness`.
+    pub fn map_witness<E: Engine>(&self, witness: &Witness<E>) -> Witness<E> {
+        assert_eq!(
+            witness.aux.len(),
+            self.aux_map.len(),
+            "wrong witness length"
+        );
+
+        Witness {
+            inputs: witness.inputs.clone(),
+            aux: witness
+                .aux
+                .iter()
+                .zip(&self.aux_map)
+                .filter(|(_, new)| new.is_some())
+                .map(|(value, _)| *value)
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Optimization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "removed {} of {} constraints and {} of {} aux variables",
+            self.removed_constraints.len(),
+            self.num_constraints,
+            self.removed_aux.len(),
+            self.aux_map.len()
+        )
+    }
+}
+
+/// Removes redundant constraints and unused aux variables from `r1cs`. The
+/// linear combinations of the result are normalized.
+pub fn optimize<E: Engine>(r1cs: &R1cs<E>) -> (R1cs<E>, Optimization) {
+    let mut seen = HashSet::new();
+    let mut removed_constraints = vec![];
+    let mut constraints = vec![];
+    for (i, constraint) in r1cs.constraints.iter().enumerate() {
+        let a = normalize::<E>(&constraint.a);
+        let b = normalize::<E>(&constraint.b);
+        let c = normalize::<E>(&constraint.c);
+
+        let trivial = c.is_empty() && (a.is_empty() || b.is_empty());
+        let (a_key, b_key) = (encode::<E>(&a), encode::<E>(&b));
+        let key = if a_key <= b_key {
+            (a_key, b_key, encode::<E>(&c))
+        } else {
+            (b_key, a_key, encode::<E>(&c))
+        };
+
+        if trivial || !seen.insert(key) {
+            removed_constraints.push(i);
+        } else {
+            constraints.push(Constraint { a, b, c });
+        }
+    }
+
+    let mut used = vec![false; r1cs.num_aux];
+    for constraint in &constraints {
+        for lc in &[&constraint.a, &constraint.b, &constraint.c] {
+            for (index, _) in lc.iter() {
+                if let Index::Aux(i) = *index {
+                    used[i] = true;
+                }
+            }
+        }
+    }
+
+    let mut removed_aux = vec![];
+    let mut aux_map = Vec::with_capacity(r1cs.num_aux);
+    let mut num_aux = 0;
+    for (i, &used) in used.iter().enumerate() {
+        if used {
+            aux_map.push(Some(num_aux));
+            num_aux += 1;
+        } else {
+            aux_map.push(None);
+            removed_aux.push(i);
+        }
+    }
+
+    let renumber = |lc: &mut SparseLc<E>| {
+        for (index, _) in lc.iter_mut() {
+            if let Index::Aux(i) = *index {
+                *index = Index::Aux(aux_map[i].expect("used variables are kept"));
+            }
+        }
+    };
+    for constraint in constraints.iter_mut() {
+        renumber(&mut constraint.a);
+        renumber(&mut constraint.b);
+        renumber(&mut constraint.c);
+    }
+
+    let optimized = R1cs {
+        num_inputs: r1cs.num_inputs,
+        num_aux,
+        constraints,
+    };
+    let optimization = Optimization {
+        removed_constraints,
+        removed_aux,
+        num_constraints: r1cs.constraints.len(),
+        aux_map,
+    };
+
+    (optimized, optimization)
+}
+
+// A byte encoding of a normalized linear combination, equal for equal ones.
+fn encode<E: Engine>(lc: &SparseLc<E>) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (index, coeff) in lc {
+        let (kind, i) = index_key(*index);
+        bytes.push(kind);
+        bytes.extend_from_slice(&(i as u64).to_be_bytes());
+        coeff
+            .into_repr()
+            .write_be(&mut bytes)
+            .expect("writing to a vector succeeds");
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use crate::util_cs::r1cs::R1csCircuit;
+    use crate::util_cs::test_fixtures::test_rng;
+    use ff::Field;
+
+    fn fr(n: &str) -> Fr {
+        Fr::from_str(n).unwrap()
+    }
+
+    // x * y = out and y != 0, with a trivial constraint, a duplicate, a
+    // swapped duplicate, a scaled copy with split terms, and two dead
+    // variables, aux 1 and 3.
+    fn bloated() -> (R1cs<Bls12>, Witness<Bls12>) {
+        let one = Index::Input(0);
+        let out = Index::Input(1);
+        let x = Index::Aux(0);
+        let dead = Index::Aux(1);
+        let y = Index::Aux(2);
+        let y_inv = Index::Aux(4);
+        let mul = Constraint {
+            a: vec![(x, fr("1"))],
+            b: vec![(y, fr("1"))],
+            c: vec![(out, fr("1"))],
+        };
+
+        let r1cs = R1cs {
+            num_inputs: 2,
+            num_aux: 5,
+            constraints: vec![
+                mul.clone(),
+                Constraint {
+                    a: vec![(dead, fr("1"))],
+                    b: vec![],
+                    c: vec![(one, fr("0"))],
+                },
+                Constraint {
+                    a: vec![(y, fr("1"))],
+                    b: vec![(x, fr("2")), (x, fr("1")), (x, fr("2"))],
+                    c: vec![(out, fr("5"))],
+                },
+                Constraint {
+                    a: vec![(y, fr("1"))],
+                    b: vec![(x, fr("1"))],
+                    c: vec![(out, fr("1"))],
+                },
+                mul,
+                Constraint {
+                    a: vec![(y, fr("1"))],
+                    b: vec![(y_inv, fr("1"))],
+                    c: vec![(one, fr("1"))],
+                },
+            ],
+        };
+        let witness = Witness {
+            inputs: vec![fr("6")],
+            aux: vec![
+                fr("2"),
+                fr("7"),
+                fr("3"),
+                fr("8"),
+                fr("3").inverse().unwrap(),
+            ],
+        };
+
+        (r1cs, witness)
+    }
+
+    #[test]
+    fn test_optimize() {
+        let (r1cs, witness) = bloated();
+        assert!(r1cs.is_satisfied(&witness));
+
+        let (optimized, optimization) = optimize(&r1cs);
+        assert_eq!(optimization.removed_constraints, vec![1, 3, 4]);
+        assert_eq!(optimization.removed_aux, vec![1, 3]);
+        assert_eq!(optimization.map_aux(2), Some(1));
+        assert_eq!(optimization.map_aux(3), None);
+        assert_eq!(
+            optimization.to_string(),
+            "removed 3 of 6 constraints and 2 of 5 aux variables"
+        );
+
+        assert_eq!(optimized.num_inputs, 2);
+        assert_eq!(optimized.num_aux, 3);
+        assert_eq!(optimized.constraints.len(), 3);
+        // The split terms are merged.
+        assert_eq!(optimized.constraints[1].b, vec![(Index::Aux(0), fr("5"))]);
+
+        let mapped = optimization.map_witness(&witness);
+        assert_eq!(mapped.aux, vec![fr("2"), fr("3"), witness.aux[4]]);
+        assert!(optimized.is_satisfied(&mapped));
+
+        let mut wrong = mapped;
+        wrong.aux[1] = fr("4");
+        assert!(!optimized.is_satisfied(&wrong));
+    }
+
+    #[test]
+    fn test_prove_optimized() {
+        let mut rng = test_rng();
+
+        let (r1cs, witness) = bloated();
+        let (optimized, optimization) = optimize(&r1cs);
+        let witness = optimization.map_witness(&witness);
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            R1csCircuit {
+                r1cs: optimized.clone(),
+                witness: None,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let proof = create_random_proof(
+            R1csCircuit {
+                r1cs: optimized,
+                witness: Some(witness),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+
+        let pvk = prepare_verifying_key(&params.vk);
+        assert!(verify_proof(&pvk, &proof, &[fr("6")]).unwrap());
+    }
+}
@@ -0,0 +1,423 @@
+//! An owned representation of a rank-1 constraint system.
+//!
+//! `R1csRecorder` records any `Circuit` into an `R1cs` and, if the circuit
+//! has assignments, its `Witness`; `R1csCircuit` turns them back into a
+//! `Circuit` that can be used for parameter generation and proving. The
+//! circom loader, the CSR matrices and the optimization pass work on these
+//! types.
+
+use blake2s_simd::Params as Blake2sParams;
+use std::collections::BTreeMap;
+
+use ff::{Field, PrimeField, PrimeFieldRepr};
+
+use crate::bls::Engine;
+use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+/// A sparse linear combination. `Index::Input(0)` is the constant one.
+pub type SparseLc<E> = Vec<(Index, <E as ff::ScalarEngine>::Fr)>;
+
+#[derive(Clone, Debug)]
+pub struct Constraint<E: Engine> {
+    pub a: SparseLc<E>,
+    pub b: SparseLc<E>,
+    pub c: SparseLc<E>,
+}
+
+#[derive(Clone, Debug)]
+pub struct R1cs<E: Engine> {
+    /// Number of public inputs, including the constant one.
+    pub num_inputs: usize,
+    pub num_aux: usize,
+    pub constraints: Vec<Constraint<E>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Witness<E: Engine> {
+    /// Public inputs, without the constant one.
+    pub inputs: Vec<E::Fr>,
+    pub aux: Vec<E::Fr>,
+}
+
+impl<E: Engine> R1cs<E> {
+    /// Checks that the witness has the right shape and satisfies every constraint.
+    pub fn is_satisfied(&self, witness: &Witness<E>) -> bool {
+        if witness.inputs.len() + 1 != self.num_inputs || witness.aux.len() != self.num_aux {
+            return false;
+        }
+
+        let eval = |lc: &SparseLc<E>| -> Option<E::Fr> {
+            let mut acc = E::Fr::zero();
+            for (index, coeff) in lc {
+                let mut value = match *index {
+                    Index::Input(0) => E::Fr::one(),
+                    Index::Input(i) => *witness.inputs.get(i - 1)?,
+                    Index::Aux(i) => *witness.aux.get(i)?,
+                };
+                value.mul_assign(coeff);
+                acc.add_assign(&value);
+            }
+            Some(acc)
+        };
+
+        self.constraints.iter().all(|constraint| {
+            match (
+                eval(&constraint.a),
+                eval(&constraint.b),
+                eval(&constraint.c),
+            ) {
+                (Some(mut a), Some(b), Some(c)) => {
+                    a.mul_assign(&b);
+                    a == c
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// A BLAKE2s hash of the numbers of variables and the constraint
+    /// matrices. Terms of a linear combination are summed per variable and
+    /// sorted, and zero terms dropped, so only the matrices matter, not how
+    /// the circuit wrote them. Namespaces and annotations are not included.
+    pub fn shape_hash(&self) -> [u8; 32] {
+        let mut state = Blake2sParams::new().personal(b"R1CSHAPE").to_state();
+        state.update(&(self.num_inputs as u64).to_be_bytes());
+        state.update(&(self.num_aux as u64).to_be_bytes());
+        state.update(&(self.constraints.len() as u64).to_be_bytes());
+
+        for constraint in &self.constraints {
+            for lc in &[&constraint.a, &constraint.b, &constraint.c] {
+                let terms = normalize::<E>(lc);
+                state.update(&(terms.len() as u64).to_be_bytes());
+                for (index, coeff) in terms {
+                    let (kind, i) = index_key(index);
+                    state.update(&[kind]);
+                    state.update(&(i as u64).to_be_bytes());
+                    let mut repr = vec![];
+                    coeff
+                        .into_repr()
+                        .write_be(&mut repr)
+                        .expect("writing to a vector succeeds");
+                    state.update(&repr);
+                }
+            }
+        }
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(state.finalize().as_bytes());
+        hash
+    }
+}
+
+// Orders inputs before aux variables.
+pub(crate) fn index_key(index: Index) -> (u8, usize) {
+    match index {
+        Index::Input(i) => (0, i),
+        Index::Aux(i) => (1, i),
+    }
+}
+
+/// `lc` with the terms of each variable summed, zero terms dropped and the
+/// terms sorted, inputs first.
+pub fn normalize<E: Engine>(lc: &SparseLc<E>) -> SparseLc<E> {
+    let mut terms = BTreeMap::new();
+    for (index, coeff) in lc {
+        terms
+            .entry(index_key(*index))
+            .or_insert_with(|| (*index, E::Fr::zero()))
+            .1
+            .add_assign(coeff);
+    }
+
+    terms
+        .into_iter()
+        .map(|(_, term)| term)
+        .filter(|(_, coeff)| !coeff.is_zero())
+        .collect()
+}
+
+/// The `R1cs::shape_hash` of `circuit`, which is synthesized without
+/// assignments being required.
+pub fn circuit_shape_hash<E: Engine, C: Circuit<E>>(
+    circuit: C,
+) -> Result<[u8; 32], SynthesisError> {
+    let (r1cs, _) = R1csRecorder::record(circuit)?;
+    Ok(r1cs.shape_hash())
+}
+
+/// A constraint system recording the constraints and assignments of a circuit.
+pub struct R1csRecorder<E: Engine> {
+    inputs: Vec<Option<E::Fr>>,
+    aux: Vec<Option<E::Fr>>,
+    constraints: Vec<Constraint<E>>,
+}
+
+impl<E: Engine> R1csRecorder<E> {
+    pub fn new() -> Self {
+        R1csRecorder::default()
+    }
+
+    /// Synthesizes `circuit` and returns its constraint system, and its
+    /// witness if every variable was assigned.
+    pub fn record<C: Circuit<E>>(
+        circuit: C,
+    ) -> Result<(R1cs<E>, Option<Witness<E>>), SynthesisError> {
+        let mut recorder = R1csRecorder::new();
+        circuit.synthesize(&mut recorder)?;
+        Ok(recorder.finish())
+    }
+
+    pub fn finish(self) -> (R1cs<E>, Option<Witness<E>>) {
+        let r1cs = R1cs {
+            num_inputs: self.inputs.len(),
+            num_aux: self.aux.len(),
+            constraints: self.constraints,
+        };
+
+        let inputs = self.inputs[1..].iter().cloned().collect::<Option<Vec<_>>>();
+        let aux = self.aux.into_iter().collect::<Option<Vec<_>>>();
+        let witness = match (inputs, aux) {
+            (Some(inputs), Some(aux)) => Some(Witness { inputs, aux }),
+            _ => None,
+        };
+
+        (r1cs, witness)
+    }
+}
+
+impl<E: Engine> Default for R1csRecorder<E> {
+    fn default() -> Self {
+        R1csRecorder {
+            inputs: vec![Some(E::Fr::one())],
+            aux: vec![],
+            constraints: vec![],
+        }
+    }
+}
+
+fn sparse<E: Engine>(lc: LinearCombination<E>) -> SparseLc<E> {
+    let mut terms = lc
+        .iter()
+        .map(|(var, coeff)| (var.get_unchecked(), *coeff))
+        .collect::<Vec<_>>();
+    // Linear combinations are unordered, sort for a deterministic output.
+    terms.sort_by_key(|(index, _)| match *index {
+        Index::Input(i) => (0, i),
+        Index::Aux(i) => (1, i),
+    });
+    terms
+}
+
+// An unassigned variable is recorded as `None` instead of failing synthesis,
+// so that circuits without a witness can be recorded as well.
+fn assignment<F, T>(f: F) -> Result<Option<T>, SynthesisError>
+where
+    F: FnOnce() -> Result<T, SynthesisError>,
+{
+    match f() {
+        Ok(value) => Ok(Some(value)),
+        Err(SynthesisError::AssignmentMissing) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl<E: Engine> ConstraintSystem<E> for R1csRecorder<E> {
+    type Root = Self;
+
+    fn new() -> Self {
+        R1csRecorder::default()
+    }
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux.push(assignment(f)?);
+
+        Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<E::Fr, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.inputs.push(assignment(f)?);
+
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+        LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+    {
+        self.constraints.push(Constraint {
+            a: sparse(a(LinearCombination::zero())),
+            b: sparse(b(LinearCombination::zero())),
+            c: sparse(c(LinearCombination::zero())),
+        });
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// A circuit given by an `R1cs`, with an optional witness for proving.
+#[derive(Clone, Debug)]
+pub struct R1csCircuit<E: Engine> {
+    pub r1cs: R1cs<E>,
+    pub witness: Option<Witness<E>>,
+}
+
+impl<E: Engine> Circuit<E> for R1csCircuit<E> {
+    fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let R1csCircuit { r1cs, witness } = self;
+
+        if let Some(witness) = &witness {
+            if witness.inputs.len() + 1 != r1cs.num_inputs || witness.aux.len() != r1cs.num_aux {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+        }
+
+        let mut inputs = vec![CS::one()];
+        for i in 1..r1cs.num_inputs {
+            inputs.push(cs.alloc_input(
+                || format!("input {}", i),
+                || {
+                    witness
+                        .as_ref()
+                        .map(|w| w.inputs[i - 1])
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?);
+        }
+
+        let mut aux = Vec::with_capacity(r1cs.num_aux);
+        for i in 0..r1cs.num_aux {
+            aux.push(cs.alloc(
+                || format!("aux {}", i),
+                || {
+                    witness
+                        .as_ref()
+                        .map(|w| w.aux[i])
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?);
+        }
+
+        let var = |index: Index| -> Result<Variable, SynthesisError> {
+            match index {
+                Index::Input(i) => inputs.get(i).cloned(),
+                Index::Aux(i) => aux.get(i).cloned(),
+            }
+            .ok_or(SynthesisError::Unsatisfiable)
+        };
+        let lc = |terms: &SparseLc<E>| -> Result<LinearCombination<E>, SynthesisError> {
+            let mut lc = LinearCombination::zero();
+            for (index, coeff) in terms {
+                lc = lc + (*coeff, var(*index)?);
+            }
+            Ok(lc)
+        };
+
+        for (i, constraint) in r1cs.constraints.iter().enumerate() {
+            let a = lc(&constraint.a)?;
+            let b = lc(&constraint.b)?;
+            let c = lc(&constraint.c)?;
+            cs.enforce(|| format!("constraint {}", i), |_| a, |_| b, |_| c);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bls::{Bls12, Fr};
+    use crate::util_cs::test_cs::TestConstraintSystem;
+    use crate::util_cs::test_fixtures::CubeCircuit;
+    use ff::PrimeField;
+
+    #[test]
+    fn test_record_and_replay() {
+        let x = Fr::from_str("3").unwrap();
+        let (r1cs, witness) = R1csRecorder::record(CubeCircuit { x: Some(x) }).unwrap();
+        let witness = witness.unwrap();
+
+        assert_eq!(r1cs.num_inputs, 2);
+        assert_eq!(r1cs.num_aux, 2);
+        assert_eq!(r1cs.constraints.len(), 2);
+        assert_eq!(witness.inputs, vec![Fr::from_str("27").unwrap()]);
+        assert!(r1cs.is_satisfied(&witness));
+
+        let mut wrong = witness.clone();
+        wrong.inputs[0] = Fr::from_str("28").unwrap();
+        assert!(!r1cs.is_satisfied(&wrong));
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        R1csCircuit {
+            r1cs: r1cs.clone(),
+            witness: Some(witness),
+        }
+        .synthesize(&mut cs)
+        .unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 2);
+        assert!(cs.verify(&[Fr::from_str("27").unwrap()]));
+
+        // Without assignments only the shape is recorded.
+        let (shape, witness) = R1csRecorder::record(CubeCircuit { x: None }).unwrap();
+        assert!(witness.is_none());
+        assert_eq!(shape.num_aux, r1cs.num_aux);
+        assert_eq!(shape.constraints.len(), r1cs.constraints.len());
+    }
+
+    #[test]
+    fn test_shape_hash() {
+        let hash = circuit_shape_hash(CubeCircuit { x: None }).unwrap();
+        assert_eq!(
+            hash,
+            circuit_shape_hash(CubeCircuit {
+                x: Some(Fr::from_str("3").unwrap())
+            })
+            .unwrap()
+        );
+
+        // Splitting a term in two, or adding a zero one, keeps the shape.
+        let (mut r1cs, _) = R1csRecorder::record(CubeCircuit { x: None }).unwrap();
+        let (index, coeff) = r1cs.constraints[1].a[0];
+        let mut half = coeff;
+        half.mul_assign(&Fr::from_str("2").unwrap().inverse().unwrap());
+        r1cs.constraints[1].a[0] = (index, half);
+        r1cs.constraints[1].a.push((index, half));
+        r1cs.constraints[1].b.push((Index::Aux(1), Fr::zero()));
+        assert_eq!(r1cs.shape_hash(), hash);
+
+        // Changing a coefficient or the number of inputs does not.
+        let mut changed = r1cs.clone();
+        changed.constraints[0].c[0].1 = Fr::from_str("2").unwrap();
+        assert!(changed.shape_hash() != hash);
+        let mut changed = r1cs;
+        changed.num_inputs += 1;
+        assert!(changed.shape_hash() != hash);
+    }
+}
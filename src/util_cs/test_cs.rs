@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::fmt;
 
 use crate::bls::Engine;
 use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
@@ -30,6 +31,43 @@ pub struct TestConstraintSystem<E: Engine> {
     aux: Vec<(E::Fr, String)>,
 }
 
+/// A constraint `a * b = c` that does not hold, with the evaluations of its
+/// linear combinations and the variables they refer to.
+pub struct UnsatisfiedConstraint<E: Engine> {
+    pub path: String,
+    pub a: E::Fr,
+    pub b: E::Fr,
+    pub c: E::Fr,
+    /// The paths and values of the variables of the constraint, inputs first.
+    pub variables: Vec<(String, E::Fr)>,
+}
+
+impl<E: Engine> fmt::Debug for UnsatisfiedConstraint<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnsatisfiedConstraint")
+            .field("path", &self.path)
+            .field("a", &self.a)
+            .field("b", &self.b)
+            .field("c", &self.c)
+            .field("variables", &self.variables)
+            .finish()
+    }
+}
+
+impl<E: Engine> fmt::Display for UnsatisfiedConstraint<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "unsatisfied constraint `{}`:", self.path)?;
+        writeln!(f, "  a = {}", self.a)?;
+        writeln!(f, "  b = {}", self.b)?;
+        writeln!(f, "  c = {}", self.c)?;
+        for (path, value) in &self.variables {
+            writeln!(f, "  {} = {}", path, value)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Copy)]
 struct OrderedVariable(Variable);
 
@@ -218,30 +256,68 @@ impl<E: Engine> TestConstraintSystem<E> {
     }
 
     pub fn which_is_unsatisfied(&self) -> Option<&str> {
-        for &(ref a, ref b, ref c, ref path) in &self.constraints {
-            let mut a = eval_lc::<E>(a, &self.inputs, &self.aux);
-            let b = eval_lc::<E>(b, &self.inputs, &self.aux);
-            let c = eval_lc::<E>(c, &self.inputs, &self.aux);
+        self.constraints
+            .iter()
+            .find(|constraint| !self.holds(constraint))
+            .map(|constraint| &*constraint.3)
+    }
+
+    /// All constraints that do not hold, in the order they were enforced.
+    pub fn unsatisfied_constraints(&self) -> Vec<UnsatisfiedConstraint<E>> {
+        self.constraints
+            .iter()
+            .filter(|constraint| !self.holds(constraint))
+            .map(|(a, b, c, path)| {
+                let mut variables = BTreeMap::new();
+                for lc in &[a, b, c] {
+                    for (&var, _) in lc.iter() {
+                        let (value, path) = match var.get_unchecked() {
+                            Index::Input(index) => &self.inputs[index],
+                            Index::Aux(index) => &self.aux[index],
+                        };
+                        variables.insert(OrderedVariable(var), (path.clone(), *value));
+                    }
+                }
+
+                UnsatisfiedConstraint {
+                    path: path.clone(),
+                    a: eval_lc::<E>(a, &self.inputs, &self.aux),
+                    b: eval_lc::<E>(b, &self.inputs, &self.aux),
+                    c: eval_lc::<E>(c, &self.inputs, &self.aux),
+                    variables: variables.into_iter().map(|(_, v)| v).collect(),
+                }
+            })
+            .collect()
+    }
 
-            a.mul_assign(&b);
+    #[allow(clippy::type_complexity)]
+    fn holds(
+        &self,
+        (a, b, c, _): &(
+            LinearCombination<E>,
+            LinearCombination<E>,
+            LinearCombination<E>,
+            String,
+        ),
+    ) -> bool {
+        let mut a = eval_lc::<E>(a, &self.inputs, &self.aux);
+        let b = eval_lc::<E>(b, &self.inputs, &self.aux);
+        let c = eval_lc::<E>(c, &self.inputs, &self.aux);
 
-            if a != c {
-                return Some(&*path);
-            }
-        }
+        a.mul_assign(&b);
 
-        None
+        a == c
     }
 
+    /// Whether all constraints hold. Prints the first one that does not.
     pub fn is_satisfied(&self) -> bool {
-        match self.which_is_unsatisfied() {
-            Some(b) => {
-                println!("fail: {:?}", b);
+        match self.unsatisfied_constraints().first() {
+            Some(unsatisfied) => {
+                println!("fail: {}", unsatisfied);
                 false
             }
             None => true,
         }
-        // self.which_is_unsatisfied().is_none()
     }
 
     pub fn num_constraints(&self) -> usize {
@@ -277,7 +353,7 @@ impl<E: Engine> TestConstraintSystem<E> {
         self.inputs.len()
     }
 
-    pub fn get_input(&mut self, index: usize, path: &str) -> E::Fr {
+    pub fn get_input(&self, index: usize, path: &str) -> E::Fr {
         let (assignment, name) = self.inputs[index].clone();
 
         assert_eq!(path, name);
@@ -289,7 +365,11 @@ impl<E: Engine> TestConstraintSystem<E> {
         &self.inputs[..]
     }
 
-    pub fn get(&mut self, path: &str) -> E::Fr {
+    pub fn get_aux(&self) -> &[(E::Fr, String)] {
+        &self.aux[..]
+    }
+
+    pub fn get(&self, path: &str) -> E::Fr {
         match self.named_objects.get(path) {
             Some(&NamedObject::Var(ref v)) => match v.get_unchecked() {
                 Index::Input(index) => self.inputs[index].0,
@@ -312,7 +392,7 @@ impl<E: Engine> TestConstraintSystem<E> {
     }
 }
 
-fn compute_path(ns: &[String], this: &str) -> String {
+pub(crate) fn compute_path(ns: &[String], this: &str) -> String {
     assert!(
         !this.chars().any(|a| a == '/'),
         "'/' is not allowed in names"
@@ -449,6 +529,21 @@ mod tests {
         assert!(!cs.is_satisfied());
         assert!(cs.which_is_unsatisfied() == Some("mult"));
 
+        let unsatisfied = cs.unsatisfied_constraints();
+        assert_eq!(unsatisfied.len(), 1);
+        let mult = &unsatisfied[0];
+        assert_eq!(mult.path, "mult");
+        assert_eq!(mult.a, Fr::from_str("4").unwrap());
+        assert_eq!(mult.b, Fr::from_str("4").unwrap());
+        assert_eq!(mult.c, Fr::from_str("40").unwrap());
+        let paths = mult
+            .variables
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["a/var", "b/var", "product"]);
+        assert!(mult.to_string().contains("unsatisfied constraint `mult`"));
+
         assert!(cs.get("product") == Fr::from_str("40").unwrap());
 
         cs.set("product", Fr::from_str("16").unwrap());
@@ -0,0 +1,68 @@
+//! Circuits and a seeded RNG shared by the tests of the crate.
+
+use ff::Field;
+use rand_core::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use crate::bls::{Bls12, Fr};
+use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+/// The RNG the tests draw from, seeded the same way in every run.
+pub fn test_rng() -> XorShiftRng {
+    XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ])
+}
+
+/// Proves knowledge of `a` and `b` with `a * b = c` for the public `c`.
+pub struct MulCircuit {
+    pub a: Option<Fr>,
+    pub b: Option<Fr>,
+}
+
+impl Circuit<Bls12> for MulCircuit {
+    fn synthesize<CS: ConstraintSystem<Bls12>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let c = self.a.and_then(|mut a| {
+            self.b.map(|b| {
+                a.mul_assign(&b);
+                a
+            })
+        });
+
+        let a = cs.alloc(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+        let b = cs.alloc(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+        let c = cs.alloc_input(|| "c", || c.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+        Ok(())
+    }
+}
+
+/// Proves knowledge of `x` with `x^3 = out` for the public `out`.
+pub struct CubeCircuit {
+    pub x: Option<Fr>,
+}
+
+impl Circuit<Bls12> for CubeCircuit {
+    fn synthesize<CS: ConstraintSystem<Bls12>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let square = self.x.map(|mut x| {
+            x.square();
+            x
+        });
+        let cube = self.x.and_then(|x| {
+            square.map(|mut cube| {
+                cube.mul_assign(&x);
+                cube
+            })
+        });
+
+        let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+        let x2 = cs.alloc(|| "x2", || square.ok_or(SynthesisError::AssignmentMissing))?;
+        let out = cs.alloc_input(|| "out", || cube.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| "square", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+        cs.enforce(|| "cube", |lc| lc + x2, |lc| lc + x, |lc| lc + out);
+
+        Ok(())
+    }
+}